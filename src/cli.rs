@@ -1,3 +1,4 @@
 pub mod build;
 pub mod commands;
+pub mod common;
 pub mod process;
@@ -1,39 +1,66 @@
-use clap::{arg, Command};
+use clap::{arg, ArgGroup, Command};
 
 use strum::IntoEnumIterator;
 
-use super::commands::get_nodes_artifacts;
+use super::commands::{
+    apply_hsm_based_on_component_quantity::{self, exact_solver, scoring_strategy},
+    export_assets, get_nodes_artifacts,
+};
+
+use crate::common::{color_ops, history_ops, log_ops, slurm_ops, xname};
 
 pub fn subcommand_get_artifacts_node(hsm_group: Option<&String>) -> Command {
     let mut artifact_subcommand = Command::new("artifacts")
                 .aliases(["a", "art"])
                 .about("Get node's artifacts")
                 .arg_required_else_help(true)
-                .arg(arg!(<XNAME> "xname").required(true))
-                .arg(arg!(-t --type <TYPE> "Filters output to specific type").value_parser(get_nodes_artifacts::ArtifactType::iter().map(|e| e.into()).collect::<Vec<&str>>()))
-                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+                .arg(arg!(<XNAME> "xname, NID or hostname").required(true))
+                .arg(arg!(-t --type <TYPE> "Filters output to specific types. Accepts a comma separated list eg Processor,NodeAccel").value_delimiter(',').value_parser(get_nodes_artifacts::ArtifactType::iter().map(|e| e.into()).collect::<Vec<&str>>()))
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format. 'csv' prints one row per hw component").value_parser(["json", "csv"]))
+                .arg(arg!(--"with-firmware" "Also print the firmware versions (BIOS, BMC, GPUs, etc) reported by FAS"))
+                .arg(arg!(--wide "Also print the serial and part number of each component, eg for FRU tracking and warranty lookups. JSON output always includes them"))
+                .arg(arg!(--fields <FIELDS> "Comma separated list of fields of each hw component to print eg xname,type,model,serial. Fields are node, xname, type, model, serial_number (or serial) and part_number (or part)").value_delimiter(',').value_parser(export_assets::parse_asset_field).conflicts_with("wide"));
 
-    match hsm_group {
-        None => {
-            artifact_subcommand = artifact_subcommand.arg(arg!(<HSM_GROUP_NAME> "hsm group name"))
-        }
-        Some(_) => {}
+    if hsm_group.is_none() {
+        artifact_subcommand = artifact_subcommand.arg(arg!(<HSM_GROUP_NAME> "hsm group name"))
     }
 
+    let firmware_subcommand = Command::new("firmware")
+        .aliases(["f", "fw"])
+        .about("Get node's firmware versions (BIOS, BMC, GPUs, etc) reported by FAS")
+        .arg_required_else_help(true)
+        .arg(arg!(<XNAME> "xname").required(true).value_parser(xname::parse_node_xname))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
+    let membership_subcommand = Command::new("membership")
+        .aliases(["m", "member"])
+        .about("Get the HSM groups and partition each node belongs to")
+        .arg_required_else_help(true)
+        .arg(arg!(<XNAMES> ... "xnames, NIDs or hostnames, accepts hostlist expressions").required(true))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
     Command::new("nodes")
         .aliases(["n", "node", "nd"])
         .about("Get node's artifacts")
         .subcommand(artifact_subcommand)
+        .subcommand(firmware_subcommand)
+        .subcommand(membership_subcommand)
 }
 
 pub fn subcommand_get_hsm_group(hsm_group: Option<&String>) -> Command {
-    let mut artifact_subcommand = Command::new("artifacts").aliases(["a", "art"]).about("Get HSM group's artifacts").arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+    let mut artifact_subcommand = Command::new("artifacts").aliases(["a", "art"]).about("Get HSM group's artifacts").arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format. 'markdown' and 'html' print a report with a summary and a table of nodes, to paste in change tickets and wikis. 'csv' prints one row per hw component").value_parser(["json", "csv", "markdown", "html"])).arg(arg!(--fields <FIELDS> "Print one row per hw component with these fields, comma separated eg xname,type,model,serial. Fields are node, xname, type, model, serial_number (or serial) and part_number (or part). Not applied to markdown and html reports").value_delimiter(',').value_parser(export_assets::parse_asset_field)).arg(arg!(--"with-firmware" "Also print the firmware versions (BIOS, BMC, GPUs, etc) reported by FAS")).arg(arg!(--"no-stream" "Wait for all nodes and print a single table with a column per hw component instead of printing each node as soon as its hw inventory arrives")).arg(arg!(--count "Only print the number of hw components (processors, memory DIMMs, accelerators and HSN NICs) of the nodes").conflicts_with_all(["output", "fields", "with-firmware"])).arg(arg!(--"as-of" <TIMESTAMP> "Get the artifacts of the members the HSM group had at this time (RFC 3339 eg 2024-05-14T08:00:00Z or a date eg 2024-05-14) according to the history (see 'history list'). Hw inventory is current").value_parser(history_ops::parse_as_of).conflicts_with("partition"));
 
     match hsm_group {
         None => {
             artifact_subcommand = artifact_subcommand
                 .arg_required_else_help(true)
-                .arg(arg!(<HSM_GROUP_NAME> "hsm group name"))
+                .arg(arg!([HSM_GROUP_NAME] "hsm group name"))
+                .arg(arg!(--partition <PARTITION_NAME> "Get the artifacts of the nodes in this HSM partition instead of a HSM group"))
+                .group(
+                    ArgGroup::new("hsm_artifacts_scope")
+                        .args(["HSM_GROUP_NAME", "partition"])
+                        .required(true),
+                )
         }
         Some(_) => {
             artifact_subcommand = artifact_subcommand.arg_required_else_help(false);
@@ -42,24 +69,186 @@ pub fn subcommand_get_hsm_group(hsm_group: Option<&String>) -> Command {
 
     let mut pattern_subcommand = Command::new("pattern")
         .aliases(["p", "pat", "ptrn", "pttrn"])
-        .about("Get HSM group's hw configuration pattern");
+        .about("Get HSM group's hw configuration pattern")
+        .arg(arg!(--"as-of" <TIMESTAMP> "Get the pattern of the members HSM groups had at this time (RFC 3339 eg 2024-05-14T08:00:00Z or a date eg 2024-05-14) according to the history (see 'history list'). Hw inventory is current").value_parser(history_ops::parse_as_of));
 
     match hsm_group {
         None => {
             pattern_subcommand = pattern_subcommand
+                .arg(arg!([HSM_GROUP_NAME] "hsm group name. If missing the pattern of each HSM group is printed, one per line"))
+                .arg(arg!(--all "Print the pattern of each HSM group, one per line").conflicts_with("HSM_GROUP_NAME"))
+        }
+        Some(_) => {
+            pattern_subcommand = pattern_subcommand.arg_required_else_help(false);
+        }
+    }
+
+    let mut scores_subcommand = Command::new("scores")
+        .aliases(["s", "score"])
+        .about("Print the density and score of each node for the hw components requested, as 'apply hsm-group' calculates them to select the nodes to migrate. Nothing is planned nor modified")
+        .arg(arg!(-p --pattern <PATTERN> "Hw components requested like `<hw component>:<quantity>` (eg a100:4:epyc:2), without HSM group name").required(true))
+        .arg(arg!(--parent <HSM_GROUP_NAME> "Parent HSM group, its nodes are also scored as candidates"))
+        .arg(arg!(-s --strategy <STRATEGY> "Heuristic used to score the nodes (see 'apply hsm-group --strategy')").value_parser(scoring_strategy::SCORING_STRATEGY_NAME_VEC).default_value("density"))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
+    match hsm_group {
+        None => {
+            scores_subcommand = scores_subcommand
                 .arg_required_else_help(true)
                 .arg(arg!(<HSM_GROUP_NAME> "hsm group name"))
         }
         Some(_) => {
-            pattern_subcommand = pattern_subcommand.arg_required_else_help(false);
+            scores_subcommand = scores_subcommand.arg_required_else_help(false);
         }
     }
 
+    let list_subcommand = Command::new("list")
+        .aliases(["l", "ls"])
+        .about("List HSM groups with a health badge combining node health, locality and pattern compliance")
+        .arg(arg!(-p --pattern <PATTERN> ... "Pattern declared for a HSM group like `<hsm_group_name>:<hw component>:<quantity>`, used to check the HSM group complies with it. Can be repeated for multiple HSM groups"))
+        .arg(arg!(--"as-of" <TIMESTAMP> "List HSM groups with the members they had at this time (RFC 3339 eg 2024-05-14T08:00:00Z or a date eg 2024-05-14) according to the history (see 'history list'). Node health and hw inventory are current").value_parser(history_ops::parse_as_of))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
+    let overlap_subcommand = Command::new("overlap")
+        .aliases(["o", "orphans"])
+        .about("List nodes belonging to more than one HSM group and nodes in no HSM group at all. Overlapping membership breaks the target/parent accounting in 'apply hsm-group'")
+        .arg(arg!(-i --ignore <HSM_GROUP_NAME> ... "HSM group to ignore (eg groups containing all nodes in the system). Can be repeated"))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
     Command::new("hsm-groups")
         .aliases(["h", "hg", "hsm", "hsmgrops"])
         .about("Get HSM group's artifacts")
         .subcommand(artifact_subcommand)
         .subcommand(pattern_subcommand)
+        .subcommand(scores_subcommand)
+        .subcommand(list_subcommand)
+        .subcommand(overlap_subcommand)
+}
+
+pub fn subcommand_get_layout(hsm_group: Option<&String>) -> Command {
+    Command::new("layout")
+        .aliases(["lay", "map"])
+        .about("Print a map of the cabinets, chassis, slots and blades with nodes of a HSM group, showing the hw component of each node and whether it belongs to the HSM group, to another HSM group or to none")
+        .arg(arg!(--hsm <HSM_GROUP_NAME> "HSM group whose physical footprint is printed").required(hsm_group.is_none()))
+}
+
+pub fn subcommand_get_partitions() -> Command {
+    Command::new("partitions")
+        .aliases(["p", "part"])
+        .about("List HSM partitions with their members. Unlike HSM groups, a node belongs to at most one partition")
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]))
+}
+
+pub fn subcommand_get_hw_components() -> Command {
+    Command::new("hw-components")
+        .aliases(["hw", "hwc"])
+        .about("List the hw components (eg epyc, a100, instinct) found in the processor and accelerator models of the nodes, with their counts. These are the hw components which can be used in patterns")
+        .arg(arg!(--hsm <HSM_GROUP_NAME> "Only nodes in this HSM group. If missing all nodes in the system are scanned"))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]))
+}
+
+pub fn subcommand_apply_cluster() -> Command {
+    Command::new("cluster")
+        .aliases(["c", "clstr"])
+        .arg_required_else_help(true)
+        .about("Rearange nodes in several HSM groups based on a cluster definition YAML file declaring the hw components of each HSM group")
+        .arg(arg!(-f --file <PATH> "Cluster definition YAML file").required(true))
+        .arg(arg!(--"exact-max-nodes" <NUM_NODES> "Maximum number of candidate nodes to use the exact solver in HSM groups with 'solver: exact', above this the greedy solver is used").value_parser(clap::value_parser!(usize)).default_value("30"))
+        .arg(arg!(-v --verbose "Print details and hw component tables of each solver iteration instead of a progress bar"))
+        .arg(arg!(--"exclude-xnames" <XNAMES> "Nodes never moved between HSM groups regardless of their score (eg login nodes or nodes under maintenance), accepts hostlist expressions. Added to 'excluded_xnames' of the site in configuration file"))
+        .arg(arg!(--partition <PARTITION_NAME> "Only nodes in this HSM partition are moved, nodes in other partitions are excluded"))
+        .arg(arg!(--"dry-run" "Print the node moves without applying them"))
+        .arg(arg!(-y --"assume-yes" "Apply the node moves without asking for confirmation"))
+        .arg(arg!(--"check-slurm" "Query Slurm and refuse to move nodes with jobs allocated, nodes with jobs allocated in the parent HSM group are not candidates (also enabled by 'slurm.enabled' in configuration file)"))
+        .arg(arg!(--"slurm-sync" <FORMAT> "Once the node moves are applied, print the scontrol commands ('scontrol') or slurm.conf lines ('conf') updating the Slurm partitions of the HSM groups changed, or run the scontrol commands ('apply'). HSM groups are mapped to partitions in 'slurm.partitions' in configuration file, by default partitions have the same name as HSM groups").value_parser(slurm_ops::SLURM_SYNC_NAME_VEC))
+}
+
+pub fn subcommand_apply_defrag() -> Command {
+    Command::new("defrag")
+        .about("Swap free nodes with tenant nodes with the same hw components so free nodes end up together in the same chassis (largest block of free nodes). Tenant HSM groups keep the same hw components")
+        .arg(arg!(-p --pool <HSM_GROUP_NAME> "Parent HSM group acting as pool of free resources").default_value("nodes_free"))
+        .arg(arg!(-i --ignore <HSM_GROUP_NAME> ... "HSM group which is not a tenant (eg groups containing all nodes in the system). Can be repeated"))
+        .arg(arg!(--"exclude-xnames" <XNAMES> "Nodes never moved between HSM groups (eg login nodes or nodes under maintenance), accepts hostlist expressions. Added to 'excluded_xnames' of the site in configuration file"))
+        .arg(arg!(--partition <PARTITION_NAME> "Only nodes in this HSM partition are moved, nodes in other partitions are excluded"))
+        .arg(arg!(--"dry-run" "Print the node moves without applying them"))
+        .arg(arg!(-y --"assume-yes" "Apply the node moves without asking for confirmation"))
+        .arg(arg!(--"check-slurm" "Query Slurm and never move nodes with jobs allocated (also enabled by 'slurm.enabled' in configuration file)"))
+}
+
+pub fn subcommand_apply_rebalance() -> Command {
+    Command::new("rebalance")
+        .arg_required_else_help(true)
+        .about("Redistribute the nodes of several HSM groups in one plan according to weights or explicit counts. Nodes move directly between the HSM groups")
+        .arg(arg!(-g --groups <HSM_GROUP_NAMES> "Comma separated list of HSM groups to rebalance eg zinal,eiger").value_delimiter(',').required(true))
+        .arg(arg!(-w --weights <WEIGHTS> "Share of nodes of each HSM group, in the same order than --groups eg 2:1").value_delimiter(':').value_parser(clap::value_parser!(usize)))
+        .arg(arg!(-c --counts <COUNTS> "Number of nodes of each HSM group, in the same order than --groups eg 10:5. Must add up to the nodes rebalanced").value_delimiter(':').value_parser(clap::value_parser!(usize)))
+        .group(ArgGroup::new("rebalance_target").args(["weights", "counts"]).required(true))
+        .arg(arg!(--"hw-components" <HW_COMPONENTS> "Comma separated list of hw components (eg a100,instinct), only nodes with any of them are rebalanced. If missing all nodes are rebalanced").value_delimiter(','))
+        .arg(arg!(--"exclude-xnames" <XNAMES> "Nodes never moved between HSM groups (eg login nodes or nodes under maintenance), accepts hostlist expressions. Added to 'excluded_xnames' of the site in configuration file"))
+        .arg(arg!(--partition <PARTITION_NAME> "Only nodes in this HSM partition are moved, nodes in other partitions are excluded"))
+        .arg(arg!(--"dry-run" "Print the node moves without applying them"))
+        .arg(arg!(-y --"assume-yes" "Apply the node moves without asking for confirmation"))
+        .arg(arg!(--"check-slurm" "Query Slurm and never move nodes with jobs allocated (also enabled by 'slurm.enabled' in configuration file)"))
+}
+
+pub fn subcommand_history() -> Command {
+    Command::new("history")
+        .arg_required_else_help(true)
+        .about("Membership changes applied by clstr")
+        .subcommand(
+            Command::new("list")
+                .alias("ls")
+                .about("List membership changes applied")
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+        .subcommand(
+            Command::new("show")
+                .arg_required_else_help(true)
+                .about("Show node moves and HSM group members before and after a membership change")
+                .arg(arg!(<ID> "history entry id").value_parser(clap::value_parser!(u64)))
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("List who applied node moves (Keycloak user or service account) and the CSM API requests sent, also for node moves which failed. Add 'audit_url' to configuration file to also send each entry to an audit endpoint on the CSM host")
+                .arg(arg!(-u --user <USER> "Only entries of this Keycloak user or service account"))
+                .arg(arg!(--"api-calls" "List each CSM API request sent (method, url and status) instead of one row per apply"))
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+}
+
+pub fn subcommand_undo() -> Command {
+    Command::new("undo")
+        .arg_required_else_help(true)
+        .about("Revert the node moves of a membership change in history")
+        .arg(arg!(<ID> "history entry id").value_parser(clap::value_parser!(u64)))
+        .arg(arg!(--"dry-run" "Print the node moves without applying them"))
+        .arg(arg!(-y --"assume-yes" "Apply the node moves without asking for confirmation"))
+}
+
+pub fn subcommand_reconcile() -> Command {
+    Command::new("reconcile")
+        .arg_required_else_help(true)
+        .about("Long running process which periodically compares HSM groups against a cluster definition YAML file and reports or applies the drift")
+        .arg(arg!(-f --file <PATH> "Cluster definition YAML file (see `apply cluster`), read on each pass").required(true))
+        .arg(arg!(-i --interval <INTERVAL> "Time between passes eg 90s, 15m, 2h").default_value("15m"))
+        .arg(arg!(--apply "Apply the node moves needed to converge to the cluster definition, otherwise drift is only reported"))
+        .arg(arg!(--"lease-file" <PATH> "Lease file shared by reconcilers, only the reconciler holding the lease acts. Required to run reconcilers on several hosts for redundancy, it must be on a file system shared by them (with working flock, eg NFSv4). Defaults to a file per site in the temporary directory, which only excludes reconcilers on the same host"))
+        .arg(arg!(--"exact-max-nodes" <NUM_NODES> "Maximum number of candidate nodes to use the exact solver in HSM groups with 'solver: exact', above this the greedy solver is used").value_parser(clap::value_parser!(usize)).default_value("30"))
+}
+
+pub fn subcommand_serve() -> Command {
+    Command::new("serve")
+        .about("Long running process serving HSM groups, node artifacts, plan computation and plan apply as a REST API authenticated with the CSM API token of the caller (Authorization: Bearer <token>)")
+        .arg(arg!(-l --listen <ADDRESS> "Address to listen on, [<host>]:<port> eg 127.0.0.1:8080 or :8443 (all interfaces). Addresses other than loopback require --tls-cert and --tls-key since callers send their CSM API token").default_value("127.0.0.1:8080"))
+        .arg(arg!(--"tls-cert" <PATH> "PEM file with the certificate chain to serve HTTPS").requires("tls-key"))
+        .arg(arg!(--"tls-key" <PATH> "PEM file with the private key of --tls-cert").requires("tls-cert"))
+}
+
+pub fn subcommand_tui() -> Command {
+    Command::new("tui")
+        .about("Interactive dashboard with HSM groups, their hw components, free pool capacity and recent changes. Select nodes and move them to another HSM group without writing a pattern, node moves are applied after confirming them")
+        .arg(arg!(--"dry-run" "Print the node moves confirmed in the dashboard without applying them"))
 }
 
 pub fn subcommand_apply_hsm() -> Command {
@@ -67,26 +256,406 @@ pub fn subcommand_apply_hsm() -> Command {
         .aliases(["hsm"])
         .arg_required_else_help(true)
         .about("Rearange nodes in a HSM group based on pattern")
-        .arg(arg!(-p --pattern <VALUE> ... "Pattern to express the new HSM layout like `<hsm_group_name>[:<property>]*:<num_nodes>`. Where hsm_group_name (mandatory) is the target HSM group, property (optional) is the property (eg NVIDIA, A100, AMD, EPYC, etc) to filter nodes' components (Nodes[].Processors[].PopulatedFRU.ProcessorFRUInfo.Model or Nodes[].NodeAccels[].PopulatedFRU.NodeAccelFRUInfo.Model) and num_nodes (mandatory) is the number of nodes with those properties we need for the new HSM layout. Eg test:nvidia:a100:2 means `test` HSM group should have 2 nodes with NVIDIA A100, test:nvidia:2:amd:rome:3 means `test` HSM group will have 2 nvidia nodes and 3 AMD ROME nodes. NOTE: a single pattern may match multiple nodes therefore the total combination of num_nodes for a single HSM group does not accumulate.").required(true))
+        .arg(arg!(-p --pattern <VALUE> ... "Pattern to express the new HSM layout like `<hsm_group_name>[:<property>]*:<num_nodes>`. Where hsm_group_name (mandatory) is the target HSM group, property (optional) is the property (eg NVIDIA, A100, AMD, EPYC, etc) to filter nodes' components (Nodes[].Processors[].PopulatedFRU.ProcessorFRUInfo.Model or Nodes[].NodeAccels[].PopulatedFRU.NodeAccelFRUInfo.Model) and num_nodes (mandatory) is the number of nodes with those properties we need for the new HSM layout. Eg test:nvidia:a100:2 means `test` HSM group should have 2 nodes with NVIDIA A100, test:nvidia:2:amd:rome:3 means `test` HSM group will have 2 nvidia nodes and 3 AMD ROME nodes. Counters accept qualifiers, eg zinal:a100>=4:epyc=30:instinct<=2 means at least 4 a100, exactly 30 epyc and at most 2 instinct (counters without qualifier are a floor). Hw components prefixed with '!' are excluded, eg zinal:epyc:20:!a100 means no node with a100 in zinal. Hw profiles in 'profiles' in configuration file are referenced with '@', eg zinal:@gpu_node:4 with `gpu_node = 'a100:epyc'` means zinal:a100:4:epyc:4. Memory is the total capacity of the nodes in GB or TB, eg zinal:memory:512gb:a100:4 or zinal:memory:1tb (a unit is required), gpumem is the total memory of the accelerators eg zinal:gpumem>=320gb. HSN NICs and drives are counted with hsn, nvme, ssd, hdd or drive (any kind), eg zinal:hsn:2:nvme:4. NOTE: a single pattern may match multiple nodes therefore the total combination of num_nodes for a single HSM group does not accumulate. Repeat -p to rearrange several HSM groups (one pattern each) as a single change, eg -p zinal:a100:4 -p eiger:epyc:10, node moves are applied all or nothing. Long patterns are read from a file with @<file> or from stdin with -, one pattern per line eg -p @patterns.txt").value_parser(apply_hsm_based_on_component_quantity::parse_pattern_arg).required(true))
+        .arg(arg!(--"from-hsm" <HSM_GROUP_NAME> "Clone the hw components of this HSM group (see 'get hsm-groups pattern'). Pattern must only have the target HSM group name, eg -p eiger --from-hsm zinal"))
+        .arg(arg!(-s --strategy <STRATEGY> "Heuristic used to select the nodes to migrate. 'density' prefers nodes with the hw components requested, 'scarcity' also avoids wasting scarce hw components, 'locality' keeps the HSM group nodes close to each other. Use '--objective min-moves' to keep current HSM group members").value_parser(scoring_strategy::SCORING_STRATEGY_NAME_VEC).default_value("density"))
+        .arg(arg!(--objective <OBJECTIVE> "Solver objective. 'density' picks the best scored nodes from target and parent HSM groups, 'min-moves' keeps current HSM group members and only moves the nodes needed to fulfill the pattern").value_parser(apply_hsm_based_on_component_quantity::SOLVER_OBJECTIVE_NAME_VEC).default_value("density"))
+        .arg(arg!(--solver <SOLVER> "Solver used to find the nodes to migrate. 'greedy' picks the best scored node on each iteration, 'exact' explores all node combinations and finds the optimal solution for small problems (see --exact-max-nodes)").value_parser(exact_solver::SOLVER_NAME_VEC).default_value("greedy"))
+        .arg(arg!(--"exact-max-nodes" <NUM_NODES> "Maximum number of candidate nodes (nodes with hw components requested) to use the exact solver, above this the greedy solver is used").value_parser(clap::value_parser!(usize)).default_value("30"))
+        .arg(arg!(-v --verbose "Print details and hw component tables of each solver iteration instead of a progress bar"))
+        .arg(arg!(--explain "Print why each node was selected: its scores, the hw components requested it contributes and the alternatives rejected"))
+        .arg(arg!(--"exclude-xnames" <XNAMES> "Nodes never moved between HSM groups regardless of their score (eg login nodes or nodes under maintenance), accepts hostlist expressions. Added to 'excluded_xnames' of the site in configuration file"))
+        .arg(arg!(--"pin-xnames" <XNAMES> "Nodes which always end up in the target HSM group regardless of their score, they are taken from the parent HSM group if needed. Accepts hostlist expressions"))
+        .arg(arg!(--"whole-blade" "Move the nodes sharing a blade (same xXcCsSbB prefix) together, the solver picks blades instead of nodes"))
+        .arg(arg!(--partition <PARTITION_NAME> "Only nodes in this HSM partition are moved, nodes in other partitions are excluded"))
+        .arg(arg!(--"as-of" <TIMESTAMP> "Calculate the plan with the members HSM groups had at this time (RFC 3339 eg 2024-05-14T08:00:00Z or a date eg 2024-05-14) according to the history (see 'history list'), eg could this request have been satisfied last week? Hw inventory is current, node moves are not applied").value_parser(history_ops::parse_as_of).conflicts_with("assume-yes"))
+        .arg(arg!(--"dry-run" "Print the node moves without applying them"))
+        .arg(arg!(--check "Print the node moves needed without applying them and exit 0 if HSM groups already satisfy the patterns or 2 if node moves are needed, eg to detect drift in CI").conflicts_with_all(["dry-run", "assume-yes"]))
+        .arg(arg!(-o --output <FORMAT> "Output format. 'json' prints {\"changed\": <bool>, \"applied\": <bool>, \"node_moves\": [...]} to stdout once done, plans and progress are printed to stderr").value_parser(["json"]))
+        .arg(arg!(-y --"assume-yes" "Apply the node moves without asking for confirmation"))
+        .arg(arg!(--"check-slurm" "Query Slurm and refuse to move nodes with jobs allocated, nodes with jobs allocated in the parent HSM group are not candidates (also enabled by 'slurm.enabled' in configuration file)"))
+        .arg(arg!(--"slurm-sync" <FORMAT> "Once the node moves are applied, print the scontrol commands ('scontrol') or slurm.conf lines ('conf') updating the Slurm partitions of the HSM groups changed, or run the scontrol commands ('apply'). HSM groups are mapped to partitions in 'slurm.partitions' in configuration file, by default partitions have the same name as HSM groups").value_parser(slurm_ops::SLURM_SYNC_NAME_VEC))
+        .arg(arg!(--"require-off" "Refuse to move nodes which are not powered off (PCS)"))
+        .arg(arg!(--"power-off-before-move" "Power off (PCS soft-off) the nodes moved after confirmation and wait until they are off before changing HSM groups").conflicts_with("require-off"))
+        .arg(arg!(--"bos-template" <BOS_SESSIONTEMPLATE> "Once nodes are moved, reboot the nodes added to the HSM group with this BOS session template"))
+        .arg(arg!(--"boot-image" <IMAGE_ID> "Once nodes are moved, reboot the nodes added to the HSM group with this IMS image. A BOS session template 'clstr-<HSM group>-<image id>' is created").conflicts_with("bos-template"))
+        .arg(arg!(--"kernel-parameters" <KERNEL_PARAMETERS> "Kernel parameters of the BOS session template created by --boot-image").requires("boot-image"))
+        .arg(arg!(--"desired-configuration" <CFS_CONFIGURATION> "Once nodes are moved, set the CFS desired configuration of the nodes added to the HSM group"))
+        .arg(arg!(--wait "Wait until CFS configured the nodes added to the HSM group").requires("desired-configuration"))
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("create")
+                .arg_required_else_help(true)
+                .about("Create a new HSM group, empty or with the nodes provided")
+                .arg(arg!(<HSM_GROUP_NAME> "hsm group name"))
+                .arg(arg!(-m --members <XNAMES> "Comma separated list of xnames to add to the new HSM group").value_delimiter(','))
+                .arg(arg!(-d --description <DESCRIPTION> "HSM group description")),
+        )
+}
+
+pub fn subcommand_update() -> Command {
+    Command::new("update")
+        .alias("u")
+        .arg_required_else_help(true)
+        .about("Update cluster resources")
+        .subcommand(
+            Command::new("hsm-group")
+                .aliases(["hsm"])
+                .arg_required_else_help(true)
+                .about("Update HSM group membership")
+                .subcommand(
+                    Command::new("move")
+                        .alias("mv")
+                        .arg_required_else_help(true)
+                        .about("Move nodes from one HSM group to another")
+                        .arg(arg!(--from <HSM_GROUP_NAME> "HSM group the nodes are moved from").required(true))
+                        .arg(arg!(--to <HSM_GROUP_NAME> "HSM group the nodes are moved to").required(true))
+                        .arg(arg!(--xnames <XNAMES> "Comma separated list of xnames (or NIDs or hostnames), accepts hostlist expressions eg x1001c1s5b0n[0-1],x1001c1s[6-7]b0n0").required(true))
+                        .arg(arg!(--"dry-run" "Print the node moves without applying them"))
+                        .arg(arg!(-y --"assume-yes" "Apply the node moves without asking for confirmation"))
+                        .arg(arg!(--"check-slurm" "Query Slurm and refuse to move nodes with jobs allocated (also enabled by 'slurm.enabled' in configuration file)"))
+                        .arg(arg!(--"slurm-sync" <FORMAT> "Once the node moves are applied, print the scontrol commands ('scontrol') or slurm.conf lines ('conf') updating the Slurm partitions of the HSM groups changed, or run the scontrol commands ('apply'). HSM groups are mapped to partitions in 'slurm.partitions' in configuration file, by default partitions have the same name as HSM groups").value_parser(slurm_ops::SLURM_SYNC_NAME_VEC)),
+                ),
+        )
+}
+
+pub fn subcommand_delete() -> Command {
+    Command::new("delete")
+        .alias("d")
+        .arg_required_else_help(true)
+        .about("Delete cluster resources")
+        .subcommand(
+            Command::new("hsm-group")
+                .aliases(["hsm"])
+                .arg_required_else_help(true)
+                .about("Delete a HSM group. Nodes are not deleted")
+                .arg(arg!(<HSM_GROUP_NAME> "hsm group name"))
+                .arg(arg!(--"if-empty" "Only delete the HSM group if it has no members, fails otherwise. This is the default unless --force"))
+                .arg(arg!(-f --force "Delete the HSM group even if it has members, after asking for confirmation").conflicts_with("if-empty"))
+                .arg(arg!(-y --"assume-yes" "Do not ask for confirmation when deleting a HSM group with members with --force").requires("force")),
+        )
+}
+
+pub fn subcommand_permissions(hsm_group: Option<&String>) -> Command {
+    let mut permissions_subcommand = Command::new("permissions")
+        .aliases(["perm", "perms"])
+        .about("Probe CSM APIs and report which clstr capabilities the current token can exercise")
+        .arg(arg!(--"write-probe" "Also probe write permissions by creating and deleting an empty scratch HSM group. Existing HSM groups are never modified"))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
+    if hsm_group.is_none() {
+        permissions_subcommand = permissions_subcommand.arg(arg!([HSM_GROUP_NAME] "hsm group name used to probe group, inventory and update permissions. Defaults to the first HSM group visible"))
+    }
+
+    permissions_subcommand
+}
+
+pub fn subcommand_pool() -> Command {
+    Command::new("pool")
+        .arg_required_else_help(true)
+        .about("Manage the pool of free resources (parent HSM group)")
+        .subcommand(
+            Command::new("audit")
+                .about("Report nodes in the pool which need cleanup before being allocated to tenants (bad state, referenced by BOS/CFS, missing inventory or located in racks marked for decommission)")
+                .arg(arg!(-p --pool <HSM_GROUP_NAME> "Parent HSM group acting as pool of free resources").default_value("nodes_free"))
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+}
+
+pub fn subcommand_find(hsm_group: Option<&String>) -> Command {
+    let mut nodes_subcommand = Command::new("nodes")
+        .aliases(["n", "node"])
+        .arg_required_else_help(true)
+        .about("Print the nodes matching an expression as a hostlist expression, which can be passed to 'update hsm-group move --xnames'")
+        .arg(arg!(-w --where <EXPRESSION> "Expression like `gpu == 'a100' && memory >= 512GB && hsm != 'zinal'`. Comparisons (==, !=, >=, <=, >, <) are combined with &&, || and ! and grouped with parenthesis. Fields are xname, hsm, state, usable, cpu and gpu (keywords of the models eg amd, epyc, a100), cpu_count, gpu_count, dimm_count, hsn_count, cores, memory and gpumem (accept units eg 512GB)").required(true))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print a hostlist expression").value_parser(["json"]))
+        .arg(arg!(--count "Only print the number of nodes matching the expression").conflicts_with("output"));
+
+    if hsm_group.is_none() {
+        nodes_subcommand =
+            nodes_subcommand.arg(arg!(--hsm <HSM_GROUP_NAME> "Only look for nodes in this HSM group, otherwise nodes of all HSM groups are considered"))
+    }
+
+    Command::new("find")
+        .alias("f")
+        .arg_required_else_help(true)
+        .about("Find cluster resources")
+        .subcommand(nodes_subcommand)
+}
+
+pub fn subcommand_resolve() -> Command {
+    Command::new("resolve")
+        .arg_required_else_help(true)
+        .about("Map nodes between xnames, NIDs, hostnames and IP/MAC addresses using SLS and HSM. NIDs and hostnames are also accepted anywhere an xname is")
+        .arg(arg!(<NODES> "Comma separated list of xnames, NIDs, hostnames, IP or MAC addresses, accepts hostlist expressions eg nid[001000-001003]"))
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]))
+}
+
+pub fn subcommand_report() -> Command {
+    Command::new("report")
+        .arg_required_else_help(true)
+        .about("Reports for planning, nothing is modified")
+        .subcommand(
+            Command::new("capacity")
+                .arg_required_else_help(true)
+                .about("How many instances of a hw profile the pool and each HSM group could satisfy, and the hw component limiting it. Useful for procurement and scheduling decisions")
+                .arg(arg!(-p --pattern <PATTERN> "Hw profile of one instance like `<hw component>:<quantity>:...`, without HSM group name, eg a100:4:epyc:30 or a100:4:memory:512gb").required(true))
+                .arg(arg!(--pool <HSM_GROUP_NAME> "Parent HSM group acting as pool of free resources").default_value("nodes_free"))
+                .arg(arg!(--"as-of" <TIMESTAMP> "Report the capacity with the members HSM groups had at this time (RFC 3339 eg 2024-05-14T08:00:00Z or a date eg 2024-05-14) according to the history (see 'history list'). Hw inventory is current").value_parser(history_ops::parse_as_of))
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+}
+
+pub fn subcommand_export(hsm_group: Option<&String>) -> Command {
+    let mut inventory_subcommand = Command::new("inventory")
+        .aliases(["i", "inv"])
+        .about("Export hw inventory (node summaries) of HSM group members to a versioned snapshot file, used as backup, for offline planning or to diff inventories over time")
+        .arg(arg!(-f --file <PATH> "File to write the snapshot to. If missing it will print the snapshot to stdout"))
+        .arg(arg!(--format <FORMAT> "Snapshot format. 'ndjson' writes a header line followed by one line per node").value_parser(["json", "ndjson"]).default_value("json"));
+
+    if hsm_group.is_none() {
+        inventory_subcommand = inventory_subcommand
+            .arg(arg!(--hsm <HSM_GROUP_NAME> "HSM group to export"))
+            .arg(arg!(--all "Export all HSM groups"))
+            .group(
+                ArgGroup::new("hsm_group_selection")
+                    .args(["hsm", "all"])
+                    .required(true),
+            )
+    }
+
+    let mut ansible_subcommand = Command::new("ansible")
+        .aliases(["a", "ans"])
+        .about("Export an Ansible inventory where each HSM group is a host group and the hw of each node (accelerator model and count, memory and rack) are host variables prefixed with 'clstr_'")
+        .arg(arg!(-f --file <PATH> "File to write the inventory to. If missing it will print the inventory to stdout"))
+        .arg(arg!(--format <FORMAT> "Inventory format").value_parser(["yaml", "ini"]).default_value("yaml"));
+
+    if hsm_group.is_none() {
+        ansible_subcommand = ansible_subcommand
+            .arg(arg!(--hsm <HSM_GROUP_NAME> "HSM group to export"))
+            .arg(arg!(--all "Export all HSM groups"))
+            .group(
+                ArgGroup::new("hsm_group_selection")
+                    .args(["hsm", "all"])
+                    .required(true),
+            )
+    }
+
+    let mut assets_subcommand = Command::new("assets")
+        .about("Export processors, GPUs, DIMMs and HSN NICs of each node with their serial and part numbers, one record per hw component, to import in NetBox or a CMDB")
+        .arg(arg!(-f --file <PATH> "File to write the assets to. If missing it will print the assets to stdout"))
+        .arg(arg!(--format <FORMAT> "Assets format").value_parser(["csv", "json"]).default_value("csv"))
+        .arg(arg!(--fields <FIELDS> "Comma separated list of fields of each hw component to print eg xname,type,model,serial. Fields are node, xname, type, model, serial_number (or serial) and part_number (or part)").value_delimiter(',').value_parser(export_assets::parse_asset_field));
+
+    if hsm_group.is_none() {
+        assets_subcommand = assets_subcommand
+            .arg(arg!(--hsm <HSM_GROUP_NAME> "HSM group to export"))
+            .arg(arg!(--all "Export all HSM groups"))
+            .group(
+                ArgGroup::new("hsm_group_selection")
+                    .args(["hsm", "all"])
+                    .required(true),
+            )
+    }
+
+    Command::new("export")
+        .arg_required_else_help(true)
+        .about("Export cluster data")
+        .subcommand(inventory_subcommand)
+        .subcommand(ansible_subcommand)
+        .subcommand(assets_subcommand)
+        .subcommand(
+            Command::new("prometheus")
+                .aliases(["p", "prom"])
+                .about("Export per HSM group gauges (nodes, accelerators by model, total memory) and the size of the pool of free resources as Prometheus metrics. Prints the metrics to stdout if neither --listen nor --textfile are provided")
+                .arg(arg!(--listen <ADDRESS> "Address to serve the metrics on (path /metrics), eg :9101"))
+                .arg(arg!(--textfile <PATH> "File to write the metrics to, eg for node_exporter textfile collector"))
+                .arg(arg!(--interval <SECONDS> "Seconds between hw inventory refreshes when serving the metrics").value_parser(clap::value_parser!(u64)).default_value("300"))
+                .arg(arg!(--pool <HSM_GROUP_NAME> "Parent HSM group acting as pool of free resources").default_value("nodes_free"))
+                .group(ArgGroup::new("prometheus_output").args(["listen", "textfile"])),
+        )
+}
+
+pub fn subcommand_diff() -> Command {
+    Command::new("diff")
+        .arg_required_else_help(true)
+        .about("Compare cluster data")
+        .subcommand(
+            Command::new("inventory")
+                .aliases(["i", "inv"])
+                .arg_required_else_help(true)
+                .about("Report nodes added/removed, components replaced and memory capacity changes between 2 inventory snapshots (see 'export inventory') or between a snapshot and the live system")
+                .arg(arg!(<OLD> "Old inventory snapshot file"))
+                .arg(arg!([NEW] "New inventory snapshot file"))
+                .arg(arg!(--live "Compare old inventory snapshot against the current hw inventory of its HSM groups"))
+                .group(
+                    ArgGroup::new("new_inventory")
+                        .args(["NEW", "live"])
+                        .required(true),
+                )
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+        .subcommand(
+            Command::new("fru")
+                .arg_required_else_help(true)
+                .about("Report replaced components (processors, GPUs, DIMMs and HSN NICs which serial number changed in the same location) between 2 inventory snapshots (see 'export inventory') or between a snapshot and the live system")
+                .arg(arg!(<OLD> "Old inventory snapshot file"))
+                .arg(arg!([NEW] "New inventory snapshot file"))
+                .arg(arg!(--live "Compare old inventory snapshot against the current hw inventory of its HSM groups"))
+                .group(
+                    ArgGroup::new("new_inventory")
+                        .args(["NEW", "live"])
+                        .required(true),
+                )
+                .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"])),
+        )
+}
+
+pub fn subcommand_validate(hsm_group: Option<&String>) -> Command {
+    let mut nodes_subcommand = Command::new("nodes")
+        .aliases(["n", "node"])
+        .about("Flag degraded or asymmetric nodes, this is, nodes which DIMM count, memory capacity or GPU count differs from the majority of nodes in their blade or HSM group. Exits with error if any is found")
+        .arg(arg!(-o --output <FORMAT> "Output format. If missing it will print output data in human redeable (tabular) format").value_parser(["json"]));
+
+    if hsm_group.is_none() {
+        nodes_subcommand = nodes_subcommand
+            .arg_required_else_help(true)
+            .arg(arg!(--hsm <HSM_GROUP_NAME> "HSM group to validate").required(true))
+    }
+
+    let pattern_subcommand = Command::new("pattern")
+        .aliases(["p", "pat"])
+        .arg_required_else_help(true)
+        .about("Check the syntax of a pattern (HSM group name, hw component counters, hw components repeated and hw profiles) without fetching the hw inventory. Exits with error if the pattern is not valid")
+        .arg(arg!(<PATTERN> "Pattern like `<hsm_group_name>:<hw component>:<quantity>`, see 'apply hsm-group --pattern'"));
+
+    Command::new("validate")
+        .arg_required_else_help(true)
+        .about("Validate cluster")
+        .subcommand(nodes_subcommand)
+        .subcommand(pattern_subcommand)
+}
+
+pub fn subcommand_config() -> Command {
+    Command::new("config")
+        .alias("c")
+        .arg_required_else_help(true)
+        .about("Manage clstr configuration (config.toml)")
+        .subcommand(
+            Command::new("init")
+                .about("Create or extend configuration file asking for site name, CSM and Keycloak urls, root certificate and default HSM group. Connectivity is checked before writing"),
+        )
+        .subcommand(
+            Command::new("migrate")
+                .about("Copy manta configuration file, root certificates and clstr history into clstr directories (~/.config/clstr on Linux). manta files are kept"),
+        )
+        .subcommand(
+            Command::new("show")
+                .alias("s")
+                .about("Show configuration file location, active site and its details. Secrets are redacted"),
+        )
+        .subcommand(
+            Command::new("set")
+                .arg_required_else_help(true)
+                .about("Set a configuration key")
+                .arg(arg!(<KEY> "Dot separated configuration key eg 'site' or 'sites.alps.shasta_base_url'"))
+                .arg(arg!(<VALUE> "Value. Parsed as TOML value (eg true, 5, [\"x1000\"]), otherwise stored as string")),
+        )
+        .subcommand(
+            Command::new("unset")
+                .arg_required_else_help(true)
+                .about("Remove a configuration key")
+                .arg(arg!(<KEY> "Dot separated configuration key eg 'hsm_group' or 'sites.alps.decommissioned_racks'")),
+        )
+        .subcommand(
+            Command::new("validate")
+                .aliases(["v", "check"])
+                .about("Validate active site is defined and each site has shasta_base_url, keycloak_base_url and a valid root certificate"),
+        )
+}
+
+pub fn subcommand_health() -> Command {
+    Command::new("health")
+        .about("Check configuration, SOCKS5 proxy, CSM root certificate, Keycloak token and HSM, BOS and CFS endpoints of the active site. Exits with error code if any check fails, useful before long apply operations")
+        .arg(arg!(--"api-versions" "Also probe CSM APIs (BOS v2, PCS, CFS v3) and print the CSM version detected. Set 'csm_version' of the site in configuration file if detection is wrong"))
 }
 
 pub fn build_cli(hsm_group: Option<&String>) -> Command {
     Command::new("clstr")
         .arg_required_else_help(true)
+        .arg(
+            arg!(--site <SITE_NAME> "Site to target (overrides 'site' in configuration file)")
+                .env("CLSTR_SITE")
+                .global(true),
+        )
+        .arg(
+            arg!(--config <PATH> "Configuration file. If missing ~/.config/clstr/config.toml on Linux, or manta configuration file if clstr has none (see 'config migrate')")
+                .env("CLSTR_CONFIG")
+                .global(true),
+        )
+        .arg(
+            arg!(--token <TOKEN> "CSM API bearer token, Keycloak authentication is skipped. Useful in automation where a service token is injected by the environment")
+                .env("CLSTR_TOKEN")
+                .hide_env_values(true)
+                .global(true),
+        )
+        .arg(
+            arg!(--"base-url" <URL> "CSM API base url (overrides 'shasta_base_url' of the site in configuration file). With --token the site is optional, without it the CSM API certificate is verified against --cacert or the public CAs")
+                .env("CLSTR_BASE_URL")
+                .global(true),
+        )
+        .arg(arg!(--cacert <PATH> "CA certificate (PEM) used instead of the CSM root certificate of the site in configuration directory").global(true))
+        .arg(arg!(--insecure "Do not verify TLS certificates of CSM (eg development systems with self-signed certificates). Keycloak user authentication still needs a root certificate, use --token or 'auth.backend = \"keyring\"' otherwise").global(true))
+        .arg(arg!(--precision <DIGITS> "Number of decimals used to print scores in tables (overrides 'precision' in configuration file). JSON output keeps raw values").value_parser(clap::value_parser!(usize)).global(true))
+        .arg(arg!(--"sort-by" <COLUMN> "Column used to sort the rows of node tables eg 'Score' or 'Component Type', case insensitive. Numbers and xnames are sorted naturally (x1001c1s10 after x1001c1s2). JSON output is not sorted").global(true))
+        .arg(arg!(--color <WHEN> "Color table cells and layouts. 'auto' colors if stdout is a terminal and NO_COLOR is not set (overrides 'color' in configuration file)").value_parser(color_ops::COLOR_MODE_NAME_VEC).global(true))
+        .arg(arg!(--"no-pager" "Do not pipe tables longer than the terminal through $CLSTR_PAGER or $PAGER (less by default)").global(true))
+        .arg(arg!(--timeout <SECONDS> "Maximum time a CSM API request may take before failing, 0 waits forever (overrides 'timeout' in configuration file) [default: 120]").value_parser(clap::value_parser!(u64)).global(true))
+        .arg(arg!(-q --quiet "Only log errors, overrides the log level in configuration file. Useful in shell pipelines and health checks").global(true))
+        .arg(arg!(--"trace-http" "Print each CSM API request (method, url, status code and latency) to stderr to troubleshoot API incompatibilities").global(true))
+        .arg(arg!(--"trace-http-body" "Like --trace-http and also print request and response bodies, tokens and passwords are redacted").global(true))
+        .arg(arg!(--record <PATH> "Record the CSM API responses of this run into a session file to attach to bug reports, tokens and passwords are redacted").conflicts_with("replay").global(true))
+        .arg(arg!(--replay <PATH> "Run the command against the CSM API responses recorded with --record instead of CSM, no Keycloak authentication needed").global(true))
+        .arg(arg!(--"log-file" <PATH> "Also write logs to this file, rotated by size (overrides 'log_file' in configuration file). Records of level info and above are always written").global(true))
+        .arg(arg!(--"log-format" <FORMAT> "Format of the log file, 'json' writes one JSON object per line (overrides 'log_format' in configuration file)").value_parser(log_ops::LOG_FORMAT_NAME_VEC).global(true))
+        .arg(arg!(--"thousands-separator" <SEPARATOR> "Thousands separator used to print counters and scores in tables eg ',', '.', \"'\", 'space' or 'none' (overrides 'thousands_separator' in configuration file). JSON output keeps raw values").global(true))
         .subcommand(
             Command::new("get")
                 .alias("g")
                 .arg_required_else_help(true)
                 .about("Get cluster details")
                 .subcommand(subcommand_get_artifacts_node(hsm_group))
-                .subcommand(subcommand_get_hsm_group(hsm_group)),
+                .subcommand(subcommand_get_hsm_group(hsm_group))
+                .subcommand(subcommand_get_hw_components())
+                .subcommand(subcommand_get_layout(hsm_group))
+                .subcommand(subcommand_get_partitions()),
         )
         .subcommand(
             Command::new("apply")
                 .alias("a")
                 .arg_required_else_help(true)
                 .about("Create new cluster")
-                // .subcommand(subcommand_apply_cluster(/* hsm_group */))
+                .subcommand(subcommand_apply_cluster())
+                .subcommand(subcommand_apply_defrag())
+                .subcommand(subcommand_apply_rebalance())
                 .subcommand(subcommand_apply_hsm(/* hsm_group */)),
         )
+        .subcommand(subcommand_reconcile())
+        .subcommand(subcommand_serve())
+        .subcommand(subcommand_tui())
+        .subcommand(subcommand_history())
+        .subcommand(subcommand_undo())
+        .subcommand(subcommand_update())
+        .subcommand(subcommand_delete())
+        .subcommand(subcommand_export(hsm_group))
+        .subcommand(subcommand_diff())
+        .subcommand(subcommand_validate(hsm_group))
+        .subcommand(subcommand_pool())
+        .subcommand(subcommand_report())
+        .subcommand(subcommand_find(hsm_group))
+        .subcommand(subcommand_resolve())
+        .subcommand(subcommand_permissions(hsm_group))
+        .subcommand(subcommand_config())
+        .subcommand(subcommand_health())
 }
@@ -1,5 +1,45 @@
+pub mod apply_cluster;
+pub mod apply_defrag;
 pub mod apply_hsm_based_on_component_quantity;
+// Node quantity patterns, not wired to any subcommand
+#[allow(dead_code)]
 pub mod apply_hsm_based_on_node_quantity;
+pub mod apply_rebalance;
+pub mod config_init;
+pub mod config_migrate;
+pub mod config_set;
+pub mod config_show;
+pub mod config_unset;
+pub mod config_validate;
+pub mod create_hsm_group;
+pub mod delete_hsm_group;
+pub mod diff_fru;
+pub mod diff_inventory;
+pub mod export_ansible;
+pub mod export_assets;
+pub mod export_inventory;
+pub mod export_prometheus;
+pub mod find_nodes;
 pub mod get_hsm_artifacts;
+pub mod get_hsm_list;
+pub mod get_hsm_overlap;
 pub mod get_hsm_pattern;
+pub mod get_hsm_scores;
+pub mod get_hw_components;
+pub mod get_layout;
 pub mod get_nodes_artifacts;
+pub mod get_nodes_firmware;
+pub mod get_nodes_membership;
+pub mod get_partitions;
+pub mod health;
+pub mod history;
+pub mod permissions;
+pub mod pool_audit;
+pub mod reconcile;
+pub mod report_capacity;
+pub mod resolve;
+pub mod serve;
+pub mod tui;
+pub mod update_hsm_group_move;
+pub mod validate_nodes;
+pub mod validate_pattern;
@@ -0,0 +1,430 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::{
+        self, exact_solver, scoring_strategy::SCORING_STRATEGY_NAME_VEC,
+        utils::parse_pattern_with_qualifiers, SOLVER_OBJECTIVE_NAME_VEC,
+    },
+    cli::common::{
+        lock_ops,
+        node_migration_ops::{self, NodeMove},
+        slurm_ops::{self, SlurmSettings},
+        tenant_ops,
+    },
+    common::format_ops::format_count,
+    shasta::hsm::{HsmGroup, Member},
+};
+
+/// Desired layout of a cluster, eg:
+///
+/// ```yaml
+/// parent_hsm_group: nodes_free
+/// hsm_groups:
+///   - name: zinal
+///     hw_components:
+///       a100: 4
+///       epyc: 30
+///     strategy: locality
+///   - name: eiger
+///     hw_components:
+///       epyc: 128
+///     objective: min-moves
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClusterDefinition {
+    /// HSM group nodes are taken from and returned to
+    #[serde(default = "default_parent_hsm_group")]
+    pub parent_hsm_group: String,
+    pub hsm_groups: Vec<HsmGroupDefinition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HsmGroupDefinition {
+    pub name: String,
+    /// hw component -> quantity, same as the counters in `apply hsm-group --pattern`
+    pub hw_components: BTreeMap<String, usize>,
+    /// Scoring strategy, 'locality' keeps the nodes of the HSM group close to each other
+    #[serde(default = "default_strategy")]
+    pub strategy: String,
+    #[serde(default = "default_objective")]
+    pub objective: String,
+    #[serde(default = "default_solver")]
+    pub solver: String,
+}
+
+fn default_parent_hsm_group() -> String {
+    "nodes_free".to_string()
+}
+
+fn default_strategy() -> String {
+    "density".to_string()
+}
+
+fn default_objective() -> String {
+    "density".to_string()
+}
+
+fn default_solver() -> String {
+    "greedy".to_string()
+}
+
+impl ClusterDefinition {
+    /// HSM groups declared plus the parent HSM group
+    pub fn get_hsm_group_name_vec(&self) -> Vec<String> {
+        self.hsm_groups
+            .iter()
+            .map(|hsm_group_definition| hsm_group_definition.name.clone())
+            .chain([self.parent_hsm_group.clone()])
+            .collect()
+    }
+}
+
+impl HsmGroupDefinition {
+    /// Pattern accepted by `apply hsm-group`, eg zinal:a100:4:epyc:30
+    pub fn to_pattern(&self) -> String {
+        [self.name.clone()]
+            .into_iter()
+            .chain(
+                self.hw_components
+                    .iter()
+                    .map(|(hw_component, qty)| format!("{}:{}", hw_component, qty)),
+            )
+            .collect::<Vec<String>>()
+            .join(":")
+    }
+}
+
+/// Computes the node moves for all HSM groups declared in a cluster definition file and applies
+/// them after confirmation. Nodes in `excluded_xname_vec` are never moved
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    file_path: &str,
+    exact_solver_max_nodes: usize,
+    verbose: bool,
+    excluded_xname_vec: &[String],
+    dry_run: bool,
+    assume_yes: bool,
+    slurm_settings: &SlurmSettings,
+    check_slurm: bool,
+    slurm_sync_opt: Option<&String>,
+) {
+    let cluster_definition = read_cluster_definition_or_exit(file_path);
+
+    // HSM groups declared and parent HSM group are locked while planning and applying
+    let _lock_file_vec =
+        lock_ops::lock_hsm_group_vec_or_exit(&cluster_definition.get_hsm_group_name_vec());
+
+    let busy_xname_vec = slurm_ops::get_busy_xname_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        Some(slurm_settings).filter(|_| check_slurm),
+    )
+    .await;
+
+    let node_move_vec = calculate_cluster_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &cluster_definition,
+        exact_solver_max_nodes,
+        verbose,
+        &busy_xname_vec,
+        excluded_xname_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not calculate node moves: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    slurm_ops::exit_if_busy_node_moved(&node_move_vec, &busy_xname_vec);
+
+    if !node_migration_ops::confirm_node_move_vec(&node_move_vec, dry_run, assume_yes) {
+        return;
+    }
+
+    match apply_cluster_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        &format!("apply cluster -f {}", file_path),
+    )
+    .await
+    {
+        Ok(history_id_opt) => {
+            node_migration_ops::print_history_id(history_id_opt);
+            println!(
+                "{} nodes moved across {} HSM groups",
+                format_count(node_move_vec.len()),
+                format_count(cluster_definition.hsm_groups.len())
+            )
+        }
+        Err(error) => {
+            eprintln!("Failed applying node moves: {}. Exit", error);
+            std::process::exit(1);
+        }
+    }
+
+    slurm_ops::sync_slurm_partition_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        slurm_sync_opt,
+        slurm_settings,
+    )
+    .await;
+}
+
+/// Computes the node moves for all HSM groups in a cluster definition. HSM groups are planned in
+/// file order and nodes taken from the parent HSM group by one HSM group are not available to the
+/// next ones. Nodes in `busy_xname_vec` (eg with Slurm jobs allocated) are never candidates and
+/// nodes in `excluded_xname_vec` are never moved. Fails if the node moves of any HSM group can't
+/// be calculated (see `calculate_node_move_vec`)
+pub async fn calculate_cluster_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    cluster_definition: &ClusterDefinition,
+    exact_solver_max_nodes: usize,
+    verbose: bool,
+    busy_xname_vec: &[String],
+    excluded_xname_vec: &[String],
+) -> Result<Vec<NodeMove>, String> {
+    let mut node_move_vec: Vec<NodeMove> = Vec::new();
+
+    for hsm_group_definition in &cluster_definition.hsm_groups {
+        println!(
+            "\n===== HSM group '{}' ({}) =====",
+            hsm_group_definition.name,
+            hsm_group_definition.to_pattern()
+        );
+
+        let reserved_xname_vec: Vec<String> = node_move_vec
+            .iter()
+            .filter(|node_move| node_move.from_hsm_group == cluster_definition.parent_hsm_group)
+            .map(|node_move| node_move.xname.clone())
+            .chain(busy_xname_vec.iter().cloned())
+            .collect();
+
+        node_move_vec.extend(
+            apply_hsm_based_on_component_quantity::calculate_node_move_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                &hsm_group_definition.to_pattern(),
+                &reserved_xname_vec,
+                &apply_hsm_based_on_component_quantity::PlanOptions {
+                    parent_hsm_group_name: &cluster_definition.parent_hsm_group,
+                    scoring_strategy_name: &hsm_group_definition.strategy,
+                    solver_objective_name: &hsm_group_definition.objective,
+                    solver_name: &hsm_group_definition.solver,
+                    exact_solver_max_nodes,
+                    verbose,
+                    explain: false,
+                    excluded_xname_vec,
+                    pinned_xname_vec: &[],
+                    whole_blade: false,
+                    as_of_opt: None,
+                },
+            )
+            .await
+            .map_err(|error| format!("HSM group '{}': {}", hsm_group_definition.name, error))?,
+        );
+    }
+
+    Ok(node_move_vec)
+}
+
+/// Creates the destination HSM groups which don't exist yet and applies the node moves. Returns
+/// the history entry recorded, if any
+pub async fn apply_cluster_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    command: &str,
+) -> Result<Option<u64>, Box<dyn std::error::Error>> {
+    let to_hsm_group_set: HashSet<&String> = node_move_vec
+        .iter()
+        .map(|node_move| &node_move.to_hsm_group)
+        .collect();
+
+    for hsm_group_name in to_hsm_group_set {
+        let hsm_group_value_vec =
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                Some(hsm_group_name),
+            ))
+            .await?;
+
+        if hsm_group_value_vec.is_empty() {
+            log::info!("Creating HSM group '{}'", hsm_group_name);
+
+            crate::shasta::hsm::http_client::post_hsm_group(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                &HsmGroup::new(hsm_group_name.clone(), None, None, Some(Member::new(None))),
+            )
+            .await
+            .map_err(|error| {
+                format!("could not create HSM group '{}': {}", hsm_group_name, error)
+            })?;
+        }
+    }
+
+    node_migration_ops::apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+        command,
+    )
+    .await
+    .map_err(Into::into)
+}
+
+/// Reads a cluster definition file, exits if the file can't be read, parsed or is not valid
+pub fn read_cluster_definition_or_exit(file_path: &str) -> ClusterDefinition {
+    let cluster_definition = read_cluster_definition(file_path).unwrap_or_else(|error| {
+        eprintln!(
+            "Could not read cluster definition '{}': {}. Exit",
+            file_path, error
+        );
+        std::process::exit(1);
+    });
+
+    tenant_ops::exit_if_hsm_group_not_available(
+        &cluster_definition
+            .hsm_groups
+            .iter()
+            .map(|hsm_group_definition| hsm_group_definition.name.as_str())
+            .chain([cluster_definition.parent_hsm_group.as_str()])
+            .collect::<Vec<&str>>(),
+    );
+
+    cluster_definition
+}
+
+pub fn read_cluster_definition(
+    file_path: &str,
+) -> Result<ClusterDefinition, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(file_path)?;
+
+    let cluster_definition: ClusterDefinition = serde_yaml::from_str(&content)?;
+
+    validate_cluster_definition(&cluster_definition)?;
+
+    Ok(cluster_definition)
+}
+
+pub fn validate_cluster_definition(
+    cluster_definition: &ClusterDefinition,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut hsm_group_name_set = HashSet::new();
+
+    for hsm_group_definition in &cluster_definition.hsm_groups {
+        let hsm_group_name = &hsm_group_definition.name;
+
+        if !hsm_group_name_set.insert(hsm_group_name) {
+            return Err(format!("HSM group '{}' declared more than once", hsm_group_name).into());
+        }
+
+        if hsm_group_name.eq(&cluster_definition.parent_hsm_group) {
+            return Err(format!(
+                "HSM group '{}' can't be the parent HSM group",
+                hsm_group_name
+            )
+            .into());
+        }
+
+        if hsm_group_definition.hw_components.is_empty() {
+            return Err(format!("HSM group '{}' has no hw components", hsm_group_name).into());
+        }
+
+        if let Err(pattern_error_vec) =
+            parse_pattern_with_qualifiers(&hsm_group_definition.to_pattern())
+        {
+            return Err(format!(
+                "HSM group '{}' hw components not valid: {}",
+                hsm_group_name,
+                pattern_error_vec
+                    .iter()
+                    .map(|pattern_error| format!(
+                        "'{}' {}",
+                        pattern_error.element, pattern_error.message
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+            .into());
+        }
+
+        if !SCORING_STRATEGY_NAME_VEC.contains(&hsm_group_definition.strategy.as_str()) {
+            return Err(format!(
+                "HSM group '{}' strategy '{}' not valid, valid values are {:?}",
+                hsm_group_name, hsm_group_definition.strategy, SCORING_STRATEGY_NAME_VEC
+            )
+            .into());
+        }
+
+        if !SOLVER_OBJECTIVE_NAME_VEC.contains(&hsm_group_definition.objective.as_str()) {
+            return Err(format!(
+                "HSM group '{}' objective '{}' not valid, valid values are {:?}",
+                hsm_group_name, hsm_group_definition.objective, SOLVER_OBJECTIVE_NAME_VEC
+            )
+            .into());
+        }
+
+        if !exact_solver::SOLVER_NAME_VEC.contains(&hsm_group_definition.solver.as_str()) {
+            return Err(format!(
+                "HSM group '{}' solver '{}' not valid, valid values are {:?}",
+                hsm_group_name,
+                hsm_group_definition.solver,
+                exact_solver::SOLVER_NAME_VEC
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_definition_from_yaml() {
+        let cluster_definition: ClusterDefinition = serde_yaml::from_str(
+            "hsm_groups:
+  - name: zinal
+    hw_components:
+      epyc: 30
+      a100: 4
+    strategy: locality
+  - name: zinal
+    hw_components:
+      epyc: 2
+",
+        )
+        .unwrap();
+
+        assert_eq!(cluster_definition.parent_hsm_group, "nodes_free");
+        assert_eq!(
+            cluster_definition.hsm_groups[0].to_pattern(),
+            "zinal:a100:4:epyc:30"
+        );
+        assert_eq!(cluster_definition.hsm_groups[1].objective, "density");
+        assert!(validate_cluster_definition(&cluster_definition).is_err());
+    }
+}
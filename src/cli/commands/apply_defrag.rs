@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::utils::{
+        calculate_node_hw_component_count, calculate_xname_distance,
+        get_node_hw_properties_from_value,
+    },
+    cli::common::{
+        lock_ops,
+        node_migration_ops::{self, NodeMove},
+        slurm_ops::{self, SlurmSettings},
+    },
+    common::{
+        format_ops::format_count,
+        hw_inventory_ops::{self, get_chassis_xname},
+    },
+};
+
+/// Free node and tenant node with the same hw components which exchange HSM groups
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NodeSwap {
+    /// Node in the pool, moved to the tenant HSM group
+    pub free_xname: String,
+    /// Node in the tenant HSM group, moved to the pool
+    pub tenant_xname: String,
+    pub hsm_group: String,
+}
+
+/// Proposes swaps between free nodes and tenant nodes with the same hw components so free nodes
+/// end up together in the same chassis, this is, the largest block of free nodes within distance 2
+/// (see `calculate_xname_distance`) grows. Tenant HSM groups keep the same hw components. Nodes
+/// in `excluded_xname_vec` and, if `check_slurm`, nodes with jobs allocated are never moved. HSM
+/// groups in `ignore_hsm_group_vec` (eg groups containing all nodes in the system) are not tenants
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    pool_hsm_group_name: &str,
+    ignore_hsm_group_vec: &[&String],
+    excluded_xname_vec: &[String],
+    dry_run: bool,
+    assume_yes: bool,
+    slurm_settings: &SlurmSettings,
+    check_slurm: bool,
+) {
+    let busy_xname_vec = slurm_ops::get_busy_xname_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        Some(slurm_settings).filter(|_| check_slurm),
+    )
+    .await;
+
+    let hsm_group_value_vec = crate::common::tenant_ops::filter_available_hsm_group_value_vec(
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        }),
+    );
+
+    // Any tenant HSM group may swap nodes with the pool, all of them are locked while planning
+    // and applying
+    let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(
+        &hsm_group_value_vec
+            .iter()
+            .filter_map(|hsm_group_value| hsm_group_value["label"].as_str())
+            .filter(|hsm_group_name| {
+                !ignore_hsm_group_vec
+                    .iter()
+                    .any(|ignore_hsm_group_name| ignore_hsm_group_name.as_str() == *hsm_group_name)
+            })
+            .map(str::to_string)
+            .collect::<Vec<String>>(),
+    );
+
+    // xname -> HSM groups
+    let mut node_hsm_group_hashmap: HashMap<String, Vec<String>> = HashMap::new();
+
+    for hsm_group_value in &hsm_group_value_vec {
+        let hsm_group_name = hsm_group_value["label"].as_str().unwrap().to_string();
+
+        if ignore_hsm_group_vec.contains(&&hsm_group_name) {
+            continue;
+        }
+
+        for xname in
+            mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(hsm_group_value)
+        {
+            node_hsm_group_hashmap
+                .entry(xname)
+                .or_default()
+                .push(hsm_group_name.clone());
+        }
+    }
+
+    // Nodes in more than one HSM group are never moved, membership accounting would break
+    let is_movable = |xname: &String| {
+        node_hsm_group_hashmap[xname].len() == 1
+            && !excluded_xname_vec.contains(xname)
+            && !busy_xname_vec.contains(xname)
+    };
+
+    let mut pool_member_vec: Vec<String> = node_hsm_group_hashmap
+        .iter()
+        .filter(|(_, hsm_group_vec)| hsm_group_vec.contains(&pool_hsm_group_name.to_string()))
+        .map(|(xname, _)| xname.clone())
+        .collect();
+    pool_member_vec.sort();
+
+    if pool_member_vec.is_empty() {
+        eprintln!(
+            "HSM group '{}' not found or without members. Exit",
+            pool_hsm_group_name
+        );
+        std::process::exit(1);
+    }
+
+    let pool_chassis_vec: Vec<String> = pool_member_vec
+        .iter()
+        .filter_map(|xname| get_chassis_xname(xname))
+        .collect();
+
+    // Tenant nodes which may be swapped, the ones sharing chassis with free nodes
+    let mut tenant_node_vec: Vec<(String, String)> = node_hsm_group_hashmap
+        .iter()
+        .filter(|(xname, hsm_group_vec)| {
+            !hsm_group_vec.contains(&pool_hsm_group_name.to_string())
+                && is_movable(xname)
+                && get_chassis_xname(xname)
+                    .is_some_and(|chassis_xname| pool_chassis_vec.contains(&chassis_xname))
+        })
+        .map(|(xname, hsm_group_vec)| (xname.clone(), hsm_group_vec[0].clone()))
+        .collect();
+    tenant_node_vec.sort();
+
+    let free_node_vec: Vec<String> = pool_member_vec
+        .iter()
+        .filter(|xname| is_movable(xname))
+        .cloned()
+        .collect();
+
+    let xname_vec: Vec<String> = free_node_vec
+        .iter()
+        .chain(tenant_node_vec.iter().map(|(xname, _)| xname))
+        .cloned()
+        .collect();
+
+    // xname -> hw component counters, nodes are only swapped if they are the same
+    let node_hw_component_count_hashmap: HashMap<String, HashMap<String, usize>> =
+        hw_inventory_ops::get_node_hw_inventory_hashmap(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &xname_vec,
+        )
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not fetch hw inventory: {}. Exit", error);
+            std::process::exit(1);
+        })
+        .into_iter()
+        .map(|(xname, node_hw_inventory_value)| {
+            let (node_hw_component_vec, node_memory_capacity_vec, node_accelerator_memory_vec) =
+                get_node_hw_properties_from_value(&node_hw_inventory_value, Vec::new());
+
+            (
+                xname,
+                calculate_node_hw_component_count(
+                    node_hw_component_vec,
+                    &node_memory_capacity_vec,
+                    &node_accelerator_memory_vec,
+                ),
+            )
+        })
+        .collect();
+
+    let (largest_free_block_before, _) = get_largest_free_block(&pool_member_vec);
+
+    let node_swap_vec = calculate_node_swap_vec(
+        &pool_member_vec,
+        &free_node_vec,
+        &tenant_node_vec,
+        &node_hw_component_count_hashmap,
+    );
+
+    let pool_member_after_vec: Vec<String> = pool_member_vec
+        .iter()
+        .filter(|xname| {
+            !node_swap_vec
+                .iter()
+                .any(|node_swap| node_swap.free_xname.eq(*xname))
+        })
+        .cloned()
+        .chain(
+            node_swap_vec
+                .iter()
+                .map(|node_swap| node_swap.tenant_xname.clone()),
+        )
+        .collect();
+
+    let (largest_free_block_after, chassis_xname_opt) =
+        get_largest_free_block(&pool_member_after_vec);
+
+    println!(
+        "Largest block of free nodes: {} nodes{} (currently {} nodes), {} swaps",
+        format_count(largest_free_block_after),
+        chassis_xname_opt
+            .map(|chassis_xname| format!(" in chassis '{}'", chassis_xname))
+            .unwrap_or_default(),
+        format_count(largest_free_block_before),
+        format_count(node_swap_vec.len())
+    );
+
+    let node_move_vec: Vec<NodeMove> = node_swap_vec
+        .iter()
+        .flat_map(|node_swap| {
+            [
+                NodeMove::new(
+                    &node_swap.free_xname,
+                    pool_hsm_group_name,
+                    &node_swap.hsm_group,
+                ),
+                NodeMove::new(
+                    &node_swap.tenant_xname,
+                    &node_swap.hsm_group,
+                    pool_hsm_group_name,
+                ),
+            ]
+        })
+        .collect();
+
+    node_migration_ops::confirm_and_apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        &format!("apply defrag --pool {}", pool_hsm_group_name),
+        dry_run,
+        assume_yes,
+    )
+    .await;
+}
+
+/// Number of free nodes in the chassis with most free nodes, and that chassis
+pub fn get_largest_free_block(pool_member_vec: &[String]) -> (usize, Option<String>) {
+    let chassis_free_count_hashmap = get_chassis_free_count_hashmap(pool_member_vec);
+
+    chassis_free_count_hashmap
+        .into_iter()
+        .max_by(
+            |(chassis_xname_1, free_count_1), (chassis_xname_2, free_count_2)| {
+                free_count_1
+                    .cmp(free_count_2)
+                    .then(chassis_xname_2.cmp(chassis_xname_1))
+            },
+        )
+        .map(|(chassis_xname, free_count)| (free_count, Some(chassis_xname)))
+        .unwrap_or((0, None))
+}
+
+fn get_chassis_free_count_hashmap(pool_member_vec: &[String]) -> BTreeMap<String, usize> {
+    let mut chassis_free_count_hashmap: BTreeMap<String, usize> = BTreeMap::new();
+
+    for chassis_xname in pool_member_vec
+        .iter()
+        .filter_map(|xname| get_chassis_xname(xname))
+    {
+        *chassis_free_count_hashmap.entry(chassis_xname).or_default() += 1;
+    }
+
+    chassis_free_count_hashmap
+}
+
+/// Swaps which make the largest block of free nodes. Each chassis with free nodes is tried as
+/// destination: its tenant nodes are swapped with free nodes outside it with the same hw
+/// components, taking first the free nodes in the chassis with fewest free nodes and furthest away.
+/// The chassis ending up with most free nodes wins
+pub fn calculate_node_swap_vec(
+    pool_member_vec: &[String],
+    free_node_vec: &[String],
+    tenant_node_vec: &[(String, String)],
+    node_hw_component_count_hashmap: &HashMap<String, HashMap<String, usize>>,
+) -> Vec<NodeSwap> {
+    let chassis_free_count_hashmap = get_chassis_free_count_hashmap(pool_member_vec);
+
+    let mut best_node_swap_vec: Vec<NodeSwap> = Vec::new();
+    let mut best_free_count = 0;
+
+    for (chassis_xname, free_count) in &chassis_free_count_hashmap {
+        let mut free_candidate_vec: Vec<&String> = free_node_vec
+            .iter()
+            .filter(|xname| get_chassis_xname(xname).as_ref() != Some(chassis_xname))
+            .filter(|xname| node_hw_component_count_hashmap.contains_key(*xname))
+            .collect();
+
+        // Free nodes in most fragmented chassis first, then furthest away from the destination
+        free_candidate_vec.sort_by_key(|xname| {
+            (
+                get_chassis_xname(xname)
+                    .and_then(|chassis_xname| chassis_free_count_hashmap.get(&chassis_xname))
+                    .copied()
+                    .unwrap_or(0),
+                4 - calculate_xname_distance(xname, chassis_xname),
+            )
+        });
+
+        let mut node_swap_vec = Vec::new();
+
+        for (tenant_xname, hsm_group_name) in tenant_node_vec
+            .iter()
+            .filter(|(xname, _)| get_chassis_xname(xname).as_ref() == Some(chassis_xname))
+        {
+            let Some(tenant_hw_component_count_hashmap) =
+                node_hw_component_count_hashmap.get(tenant_xname)
+            else {
+                continue;
+            };
+
+            if let Some(position) = free_candidate_vec.iter().position(|free_xname| {
+                node_hw_component_count_hashmap
+                    .get(*free_xname)
+                    .is_some_and(|free_hw_component_count_hashmap| {
+                        free_hw_component_count_hashmap.eq(tenant_hw_component_count_hashmap)
+                    })
+            }) {
+                let free_xname = free_candidate_vec.remove(position);
+
+                node_swap_vec.push(NodeSwap {
+                    free_xname: free_xname.clone(),
+                    tenant_xname: tenant_xname.clone(),
+                    hsm_group: hsm_group_name.clone(),
+                });
+            }
+        }
+
+        // Swaps taking free nodes from the chassis don't change its free count
+        if free_count + node_swap_vec.len() > best_free_count {
+            best_free_count = free_count + node_swap_vec.len();
+            best_node_swap_vec = node_swap_vec;
+        }
+    }
+
+    best_node_swap_vec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_node_swap_vec() {
+        let epyc = HashMap::from([("epyc".to_string(), 2)]);
+        let a100 = HashMap::from([("a100".to_string(), 4)]);
+
+        // 2 free nodes in chassis c0 and 1 in chassis c1
+        let pool_member_vec = vec![
+            "x1000c0s0b0n0".to_string(),
+            "x1000c0s0b0n1".to_string(),
+            "x1000c1s0b0n0".to_string(),
+        ];
+        let tenant_node_vec = vec![
+            ("x1000c0s1b0n0".to_string(), "zinal".to_string()),
+            ("x1000c0s1b0n1".to_string(), "zinal".to_string()),
+            ("x1000c1s1b0n0".to_string(), "zinal".to_string()),
+        ];
+        let node_hw_component_count_hashmap = HashMap::from([
+            ("x1000c0s0b0n0".to_string(), epyc.clone()),
+            ("x1000c0s0b0n1".to_string(), epyc.clone()),
+            ("x1000c1s0b0n0".to_string(), epyc.clone()),
+            ("x1000c0s1b0n0".to_string(), epyc.clone()),
+            ("x1000c0s1b0n1".to_string(), a100.clone()),
+            ("x1000c1s1b0n0".to_string(), epyc.clone()),
+        ]);
+
+        let node_swap_vec = calculate_node_swap_vec(
+            &pool_member_vec,
+            &pool_member_vec,
+            &tenant_node_vec,
+            &node_hw_component_count_hashmap,
+        );
+
+        // Only the tenant node in c0 with the same hw components as the free node in c1 is swapped
+        assert_eq!(
+            node_swap_vec,
+            vec![NodeSwap {
+                free_xname: "x1000c1s0b0n0".to_string(),
+                tenant_xname: "x1000c0s1b0n0".to_string(),
+                hsm_group: "zinal".to_string(),
+            }]
+        );
+    }
+}
@@ -2,7 +2,10 @@ use std::{collections::HashMap, sync::Arc, time::Instant};
 
 use tokio::sync::Semaphore;
 
-use crate::cli::commands::apply_hsm_based_on_node_quantity::utils::hsm_node_hw_profile;
+use crate::{
+    cli::commands::apply_hsm_based_on_node_quantity::utils::hsm_node_hw_profile,
+    shasta::ShastaClient,
+};
 
 // TEST --> a hsm -p zinal:a100:epyc:a100:2:epyc:instinct:8:epyc:5
 //
@@ -288,17 +291,21 @@ pub async fn exec(
     .sort_by(|a, b| b.len().cmp(&a.len())); */
 
     // Target HSM group
-    let target_hsm_group_value = mesa::hsm::group::shasta::http_client::get(
-        shasta_token,
-        shasta_base_url,
-        shasta_root_cert,
-        Some(&target_hsm_group_name.to_string()),
-    )
-    .await
-    .unwrap()
-    .first()
-    .unwrap()
-    .clone();
+    let target_hsm_group_value =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(&target_hsm_group_name.to_string()),
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        })
+        .first()
+        .unwrap()
+        .clone();
 
     let hsm_group_parent_members =
         mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
@@ -332,13 +339,18 @@ pub async fn exec(
 
     let mut tasks = tokio::task::JoinSet::new();
 
-    let sem = Arc::new(Semaphore::new(5)); // CSM 1.3.1 higher number of concurrent tasks won't
-                                           // make it faster
+    let sem = Arc::new(Semaphore::new(
+        crate::common::csm_version_ops::get_max_concurrent_requests(),
+    ));
+
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)
+        .unwrap_or_else(|error| {
+            eprintln!("Could not build http client for CSM API: {}. Exit", error);
+            std::process::exit(1);
+        });
 
     for hsm_member in hsm_group_parent_members {
-        let shasta_token_string = shasta_token.to_string();
-        let shasta_base_url_string = shasta_base_url.to_string();
-        let shasta_root_cert_vec = shasta_root_cert.to_vec();
+        let shasta_client = Arc::clone(&shasta_client);
         let user_defined_hw_profile_vec_aux =
             user_defined_hw_properties_grouped_by_hw_profile_vec_sorted.clone();
 
@@ -346,14 +358,7 @@ pub async fn exec(
 
         tasks.spawn(async move {
             let _permit = permit; // Wait semaphore to allow new tasks https://github.com/tokio-rs/tokio/discussions/2648#discussioncomment-34885
-            hsm_node_hw_profile(
-                shasta_token_string,
-                shasta_base_url_string,
-                &shasta_root_cert_vec,
-                &hsm_member,
-                user_defined_hw_profile_vec_aux,
-            )
-            .await
+            hsm_node_hw_profile(&shasta_client, &hsm_member, user_defined_hw_profile_vec_aux).await
         });
     }
 
@@ -463,17 +468,21 @@ pub async fn exec(
     } */
 
     // Free node HSM group
-    let hsm_group_parent_value = mesa::hsm::group::shasta::http_client::get(
-        shasta_token,
-        shasta_base_url,
-        shasta_root_cert,
-        Some(&hsm_group_parent.to_string()),
-    )
-    .await
-    .unwrap()
-    .first()
-    .unwrap()
-    .clone();
+    let hsm_group_parent_value =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(&hsm_group_parent.to_string()),
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        })
+        .first()
+        .unwrap()
+        .clone();
 
     let hsm_group_parent_members =
         mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
@@ -493,21 +502,18 @@ pub async fn exec(
         node_counter_vec: Vec::new(),
     };
 
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)
+        .unwrap_or_else(|error| {
+            eprintln!("Could not build http client for CSM API: {}. Exit", error);
+            std::process::exit(1);
+        });
+
     for hsm_member in hsm_group_parent_members.clone() {
-        let shasta_token_string = shasta_token.to_string();
-        let shasta_base_url_string = shasta_base_url.to_string();
-        let shasta_root_cert_vec = shasta_root_cert.to_vec();
+        let shasta_client = Arc::clone(&shasta_client);
         let user_defined_hw_profile_vec_aux =
             user_defined_hw_properties_grouped_by_hw_profile_vec_sorted.clone();
         tasks.spawn(async move {
-            hsm_node_hw_profile(
-                shasta_token_string,
-                shasta_base_url_string,
-                &shasta_root_cert_vec,
-                &hsm_member,
-                user_defined_hw_profile_vec_aux,
-            )
-            .await
+            hsm_node_hw_profile(&shasta_client, &hsm_member, user_defined_hw_profile_vec_aux).await
         });
     }
 
@@ -659,21 +665,19 @@ pub mod utils {
 
     use serde_json::Value;
 
+    use crate::{
+        common::table_ops,
+        shasta::{hsm, ShastaClient},
+    };
+
     pub async fn hsm_node_hw_profile(
-        shasta_token: String,
-        shasta_base_url: String,
-        shasta_root_cert: &[u8],
+        shasta_client: &ShastaClient,
         hsm_member: &str,
         user_defined_hw_profile_vec: Vec<Vec<String>>,
     ) -> (String, Option<Vec<String>>) {
-        let profile = mesa::hsm::hw_inventory::shasta::http_client::get_hw_inventory(
-            &shasta_token,
-            &shasta_base_url,
-            shasta_root_cert,
-            hsm_member,
-        )
-        .await
-        .unwrap();
+        let profile = hsm::http_client::get_hw_inventory_query(shasta_client, hsm_member, "Node")
+            .await
+            .unwrap();
         let actual_xname_hw_profile_hashset =
             get_node_hw_properties(&profile, user_defined_hw_profile_vec.clone());
 
@@ -735,7 +739,7 @@ pub mod utils {
         headers[0].push("Node".to_string());
         headers = headers.into_iter().chain(user_patterns.clone()).collect();
 
-        let mut table = comfy_table::Table::new();
+        let mut table = table_ops::new_table();
 
         table.set_header(headers.iter().map(|header| header.join(":")));
 
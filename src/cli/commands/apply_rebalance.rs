@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+
+use comfy_table::Cell;
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::utils::{
+        calculate_xname_distance, get_node_hw_properties_from_value,
+    },
+    cli::common::{
+        node_migration_ops::{self, NodeMove},
+        slurm_ops::{self, SlurmSettings},
+    },
+    common::{format_ops::format_count, hw_inventory_ops, table_ops},
+};
+
+/// How the nodes are split between the HSM groups rebalanced
+pub enum RebalanceTarget {
+    /// Share of nodes of each HSM group eg 2:1
+    Weights(Vec<usize>),
+    /// Number of nodes of each HSM group eg 10:5, must add up to the nodes rebalanced
+    Counts(Vec<usize>),
+}
+
+/// Redistributes the nodes of several HSM groups in one plan so each HSM group ends up with the
+/// share of nodes given by `rebalance_target`. If `hw_component_vec` is not empty, only nodes
+/// with any of those hw components (eg a100) are rebalanced. Nodes move directly between the HSM
+/// groups, each HSM group receives the nodes closest to its members (see
+/// `calculate_xname_distance`). Nodes in `excluded_xname_vec`, nodes with jobs allocated if
+/// `check_slurm`, and nodes in more than one of the HSM groups are never moved
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_vec: &[String],
+    rebalance_target: RebalanceTarget,
+    hw_component_vec: &[String],
+    excluded_xname_vec: &[String],
+    dry_run: bool,
+    assume_yes: bool,
+    slurm_settings: &SlurmSettings,
+    check_slurm: bool,
+) {
+    if hsm_group_name_vec.len() < 2 {
+        eprintln!("At least 2 HSM groups are needed to rebalance nodes. Exit");
+        std::process::exit(1);
+    }
+
+    let (RebalanceTarget::Weights(value_vec) | RebalanceTarget::Counts(value_vec)) =
+        &rebalance_target;
+
+    if value_vec.len() != hsm_group_name_vec.len() {
+        eprintln!(
+            "{} HSM groups but {} weights or counts provided, one per HSM group is needed. Exit",
+            hsm_group_name_vec.len(),
+            value_vec.len()
+        );
+        std::process::exit(1);
+    }
+
+    let busy_xname_vec = slurm_ops::get_busy_xname_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        Some(slurm_settings).filter(|_| check_slurm),
+    )
+    .await;
+
+    // HSM group name -> members
+    let mut hsm_group_member_vec: Vec<(String, Vec<String>)> = Vec::new();
+
+    for hsm_group_name in hsm_group_name_vec {
+        let hsm_group_value =
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                Some(hsm_group_name),
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            })
+            .first()
+            .cloned()
+            .unwrap_or_else(|| {
+                eprintln!("HSM group '{}' not found. Exit", hsm_group_name);
+                std::process::exit(1);
+            });
+
+        hsm_group_member_vec.push((
+            hsm_group_name.clone(),
+            mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(&hsm_group_value),
+        ));
+    }
+
+    // Nodes in more than one of the HSM groups are left out
+    let mut xname_count_hashmap: HashMap<String, usize> = HashMap::new();
+
+    for (_, member_vec) in &hsm_group_member_vec {
+        for xname in member_vec {
+            *xname_count_hashmap.entry(xname.clone()).or_default() += 1;
+        }
+    }
+
+    for (xname, _) in xname_count_hashmap.iter().filter(|(_, count)| **count > 1) {
+        log::warn!(
+            "Node '{}' belongs to more than one HSM group to rebalance, left out",
+            xname
+        );
+    }
+
+    for (_, member_vec) in hsm_group_member_vec.iter_mut() {
+        member_vec.retain(|xname| xname_count_hashmap[xname] == 1);
+    }
+
+    // Only nodes with the hw components requested are rebalanced
+    if !hw_component_vec.is_empty() {
+        let xname_vec: Vec<String> = xname_count_hashmap.keys().cloned().collect();
+
+        let node_hw_inventory_hashmap = hw_inventory_ops::get_node_hw_inventory_hashmap(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &xname_vec,
+        )
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not fetch hw inventory: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+        let hw_component_lowercase_vec: Vec<String> = hw_component_vec
+            .iter()
+            .map(|hw_component| hw_component.to_lowercase())
+            .collect();
+
+        for (_, member_vec) in hsm_group_member_vec.iter_mut() {
+            member_vec.retain(|xname| {
+                node_hw_inventory_hashmap
+                    .get(xname)
+                    .is_some_and(|node_hw_inventory_value| {
+                        get_node_hw_properties_from_value(
+                            node_hw_inventory_value,
+                            hw_component_lowercase_vec.clone(),
+                        )
+                        .0
+                        .iter()
+                        .any(|hw_component| hw_component_lowercase_vec.contains(hw_component))
+                    })
+            });
+        }
+    }
+
+    let node_count = hsm_group_member_vec
+        .iter()
+        .map(|(_, member_vec)| member_vec.len())
+        .sum();
+
+    let target_count_vec = match rebalance_target {
+        RebalanceTarget::Weights(weight_vec) => {
+            if weight_vec.iter().sum::<usize>() == 0 {
+                eprintln!("Weights add up to 0. Exit");
+                std::process::exit(1);
+            }
+
+            calculate_target_count_vec(&weight_vec, node_count)
+        }
+        RebalanceTarget::Counts(count_vec) => {
+            if count_vec.iter().sum::<usize>() != node_count {
+                eprintln!(
+                    "Counts add up to {} but there are {} nodes to rebalance. Exit",
+                    count_vec.iter().sum::<usize>(),
+                    node_count
+                );
+                std::process::exit(1);
+            }
+
+            count_vec
+        }
+    };
+
+    let movable_xname_vec: Vec<String> = hsm_group_member_vec
+        .iter()
+        .flat_map(|(_, member_vec)| member_vec)
+        .filter(|xname| !excluded_xname_vec.contains(xname) && !busy_xname_vec.contains(xname))
+        .cloned()
+        .collect();
+
+    let node_move_vec = calculate_rebalance_node_move_vec(
+        &hsm_group_member_vec,
+        &movable_xname_vec,
+        &target_count_vec,
+    );
+
+    print_table(&hsm_group_member_vec, &target_count_vec, &node_move_vec);
+
+    node_migration_ops::confirm_and_apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        &format!("apply rebalance --groups {}", hsm_group_name_vec.join(",")),
+        dry_run,
+        assume_yes,
+    )
+    .await;
+}
+
+/// Splits `node_count` nodes by weight, remaining nodes go to the HSM groups with largest
+/// remainder (largest remainder method) so counts always add up to `node_count`
+pub fn calculate_target_count_vec(weight_vec: &[usize], node_count: usize) -> Vec<usize> {
+    let weight_total: usize = weight_vec.iter().sum();
+
+    let mut target_count_vec: Vec<usize> = weight_vec
+        .iter()
+        .map(|weight| node_count * weight / weight_total)
+        .collect();
+
+    let mut remainder_vec: Vec<(usize, usize)> = weight_vec
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| (i, node_count * weight % weight_total))
+        .collect();
+    remainder_vec.sort_by(|(i_1, remainder_1), (i_2, remainder_2)| {
+        remainder_2.cmp(remainder_1).then(i_1.cmp(i_2))
+    });
+
+    let node_left_count = node_count - target_count_vec.iter().sum::<usize>();
+
+    for (i, _) in remainder_vec.into_iter().take(node_left_count) {
+        target_count_vec[i] += 1;
+    }
+
+    target_count_vec
+}
+
+/// Node moves so each HSM group gets its target number of nodes. HSM groups with fewer nodes than
+/// their target take, one at a time, the movable node of an HSM group above its target with the
+/// lowest average distance to their members
+pub fn calculate_rebalance_node_move_vec(
+    hsm_group_member_vec: &[(String, Vec<String>)],
+    movable_xname_vec: &[String],
+    target_count_vec: &[usize],
+) -> Vec<NodeMove> {
+    let mut member_vec_vec: Vec<Vec<String>> = hsm_group_member_vec
+        .iter()
+        .map(|(_, member_vec)| member_vec.clone())
+        .collect();
+
+    let mut node_move_vec = Vec::new();
+
+    // HSM group furthest below its target
+    while let Some(receiver) = (0..member_vec_vec.len())
+        .filter(|i| member_vec_vec[*i].len() < target_count_vec[*i])
+        .max_by_key(|i| {
+            (
+                target_count_vec[*i] - member_vec_vec[*i].len(),
+                usize::MAX - i,
+            )
+        })
+    {
+        let candidate_opt = (0..member_vec_vec.len())
+            .filter(|i| member_vec_vec[*i].len() > target_count_vec[*i])
+            .flat_map(|donor| {
+                member_vec_vec[donor]
+                    .iter()
+                    .filter(|xname| movable_xname_vec.contains(xname))
+                    .map(move |xname| (donor, xname.clone()))
+            })
+            .min_by_key(|(_, xname)| {
+                let distance_total: usize = member_vec_vec[receiver]
+                    .iter()
+                    .map(|member| calculate_xname_distance(xname, member))
+                    .sum();
+
+                // Average distance in hundredths to compare integers
+                (
+                    distance_total * 100 / member_vec_vec[receiver].len().max(1),
+                    xname.clone(),
+                )
+            });
+
+        let Some((donor, xname)) = candidate_opt else {
+            log::warn!(
+                "HSM group '{}' can't reach {} nodes, no movable node left in HSM groups above their target",
+                hsm_group_member_vec[receiver].0,
+                target_count_vec[receiver]
+            );
+            break;
+        };
+
+        member_vec_vec[donor].retain(|member| member.ne(&xname));
+        member_vec_vec[receiver].push(xname.clone());
+
+        node_move_vec.push(NodeMove::new(
+            &xname,
+            &hsm_group_member_vec[donor].0,
+            &hsm_group_member_vec[receiver].0,
+        ));
+    }
+
+    node_move_vec
+}
+
+pub fn print_table(
+    hsm_group_member_vec: &[(String, Vec<String>)],
+    target_count_vec: &[usize],
+    node_move_vec: &[NodeMove],
+) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["HSM group", "Nodes", "Target", "Nodes after"]);
+
+    for ((hsm_group_name, member_vec), target_count) in
+        hsm_group_member_vec.iter().zip(target_count_vec)
+    {
+        let node_count_after = member_vec.len()
+            + node_move_vec
+                .iter()
+                .filter(|node_move| node_move.to_hsm_group.eq(hsm_group_name))
+                .count()
+            - node_move_vec
+                .iter()
+                .filter(|node_move| node_move.from_hsm_group.eq(hsm_group_name))
+                .count();
+
+        table.add_row(vec![
+            Cell::new(hsm_group_name),
+            Cell::new(format_count(member_vec.len())),
+            Cell::new(format_count(*target_count)),
+            Cell::new(format_count(node_count_after)),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_rebalance_node_move_vec() {
+        assert_eq!(calculate_target_count_vec(&[2, 1], 10), vec![7, 3]);
+        assert_eq!(calculate_target_count_vec(&[1, 1, 1], 4), vec![2, 1, 1]);
+
+        let hsm_group_member_vec = vec![
+            (
+                "zinal".to_string(),
+                vec!["x1000c0s0b0n0".to_string(), "x1000c0s0b0n1".to_string()],
+            ),
+            (
+                "eiger".to_string(),
+                vec![
+                    "x1000c0s1b0n0".to_string(),
+                    "x1000c1s0b0n0".to_string(),
+                    "x1000c1s0b0n1".to_string(),
+                    "x1000c1s1b0n0".to_string(),
+                ],
+            ),
+        ];
+        let movable_xname_vec: Vec<String> = hsm_group_member_vec
+            .iter()
+            .flat_map(|(_, member_vec)| member_vec.clone())
+            .collect();
+
+        // zinal takes the eiger node closest to its members
+        assert_eq!(
+            calculate_rebalance_node_move_vec(&hsm_group_member_vec, &movable_xname_vec, &[3, 3]),
+            vec![NodeMove::new("x1000c0s1b0n0", "eiger", "zinal")]
+        );
+    }
+}
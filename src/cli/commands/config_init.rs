@@ -0,0 +1,185 @@
+use std::path::PathBuf;
+
+use config::Config;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
+
+use crate::{cli::commands::health, common::config_ops, shasta::ProxySettings};
+
+/// Asks for site name, CSM and Keycloak urls, CSM root certificate and default HSM group, checks
+/// the root certificate is trusted by both urls and writes them into the configuration file. The
+/// root certificate is copied into the configuration directory. Other sites and keys in an
+/// existing configuration file are kept. manta configuration file is never written, it has to be
+/// migrated first
+pub async fn exec(settings: &Config) {
+    if config_ops::is_legacy_configuration() {
+        eprintln!("{}. Exit", config_ops::get_legacy_configuration_error());
+        std::process::exit(1);
+    }
+
+    let theme = ColorfulTheme::default();
+
+    let site_name: String = Input::with_theme(&theme)
+        .with_prompt("Site name")
+        .with_initial_text(settings.get_string("site").unwrap_or_default())
+        .interact_text()
+        .unwrap_or_else(exit_on_prompt_error);
+
+    if settings
+        .get_table("sites")
+        .unwrap_or_default()
+        .contains_key(&site_name)
+        && !Confirm::with_theme(&theme)
+            .with_prompt(format!(
+                "Site '{}' already in configuration file. Do you want to overwrite it?",
+                site_name
+            ))
+            .default(false)
+            .interact()
+            .unwrap_or_else(exit_on_prompt_error)
+    {
+        println!("Cancelled by user. Nothing changed");
+        std::process::exit(0);
+    }
+
+    let shasta_base_url = prompt_url(&theme, "CSM API base url (shasta_base_url)");
+    let keycloak_base_url = prompt_url(&theme, "Keycloak base url (keycloak_base_url)");
+
+    let root_cert_file_path: String = Input::with_theme(&theme)
+        .with_prompt("CSM root certificate file (PEM)")
+        .validate_with(|root_cert_file_path: &String| {
+            std::fs::read(root_cert_file_path)
+                .map_err(|error| error.to_string())
+                .and_then(|root_cert| {
+                    reqwest::Certificate::from_pem(&root_cert)
+                        .map(|_| ())
+                        .map_err(|error| format!("not a valid PEM certificate: {}", error))
+                })
+        })
+        .interact_text()
+        .unwrap_or_else(exit_on_prompt_error);
+
+    let hsm_group_name: String = Input::with_theme(&theme)
+        .with_prompt("Default HSM group (empty for none)")
+        .with_initial_text(settings.get_string("hsm_group").unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()
+        .unwrap_or_else(exit_on_prompt_error);
+
+    // Connectivity
+    if let Some(proxy_settings) = ProxySettings::from_config(settings, Some(&site_name)) {
+        println!(
+            "Checking connectivity through SOCKS5 proxy {}",
+            proxy_settings.socks5_proxy
+        );
+    }
+
+    let root_cert = std::fs::read(&root_cert_file_path).unwrap();
+
+    let mut connectivity_ok = true;
+
+    for url in [&shasta_base_url, &keycloak_base_url] {
+        let (trusted, detail) = health::check_root_cert(&root_cert, url).await;
+
+        if trusted {
+            println!("✅ {}", detail);
+        } else {
+            println!("❌ {}", detail);
+            connectivity_ok = false;
+        }
+    }
+
+    if !connectivity_ok
+        && !Confirm::with_theme(&theme)
+            .with_prompt(
+                "Connectivity check failed. Do you want to write the configuration anyway?",
+            )
+            .default(false)
+            .interact()
+            .unwrap_or_else(exit_on_prompt_error)
+    {
+        println!("Cancelled by user. Nothing changed");
+        std::process::exit(0);
+    }
+
+    // Configuration file
+    let mut document = if config_ops::get_configuration_toml_file_path().exists() {
+        config_ops::read_configuration_document().unwrap_or_else(|error| {
+            eprintln!("{}. Exit", error);
+            std::process::exit(1);
+        })
+    } else {
+        toml_edit::Document::new()
+    };
+
+    let mut key_value_vec = vec![
+        ("site".to_string(), site_name.clone()),
+        (
+            format!("sites.{}.shasta_base_url", site_name),
+            shasta_base_url,
+        ),
+        (
+            format!("sites.{}.keycloak_base_url", site_name),
+            keycloak_base_url,
+        ),
+    ];
+
+    if !hsm_group_name.is_empty() {
+        key_value_vec.push(("hsm_group".to_string(), hsm_group_name));
+    }
+
+    for (key, value) in key_value_vec {
+        if let Err(error) =
+            config_ops::set_configuration_toml_value(&mut document, &key, value.into())
+        {
+            eprintln!("{}. Exit", error);
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(error) = config_ops::write_configuration_document(&document) {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    }
+
+    // Root certificate
+    let root_cert_dest_file_path = config_ops::get_csm_root_cert_file_path(&site_name);
+
+    if PathBuf::from(&root_cert_file_path)
+        .canonicalize()
+        .ok()
+        .ne(&root_cert_dest_file_path.canonicalize().ok())
+    {
+        if let Err(error) = std::fs::write(&root_cert_dest_file_path, &root_cert) {
+            eprintln!(
+                "Could not write {}: {}. Exit",
+                root_cert_dest_file_path.to_string_lossy(),
+                error
+            );
+            std::process::exit(1);
+        }
+    }
+
+    println!(
+        "Configuration written to {}, run 'clstr health' to check authentication and CSM endpoints",
+        config_ops::get_configuration_toml_file_path().to_string_lossy()
+    );
+}
+
+fn prompt_url(theme: &ColorfulTheme, prompt: &str) -> String {
+    let url: String = Input::with_theme(theme)
+        .with_prompt(prompt)
+        .validate_with(|url: &String| {
+            reqwest::Url::parse(url)
+                .map(|_| ())
+                .map_err(|error| format!("not a valid url: {}", error))
+        })
+        .interact_text()
+        .unwrap_or_else(exit_on_prompt_error);
+
+    url.trim_end_matches('/').to_string()
+}
+
+fn exit_on_prompt_error<T>(error: std::io::Error) -> T {
+    eprintln!("{}. Exit", error);
+    std::process::exit(1);
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use crate::common::{audit_ops, config_ops, history_ops};
+
+/// Copies manta configuration file, CSM root certificates, clstr history and audit trail into
+/// clstr own directories, manta files are kept since manta still uses them. Exits if clstr
+/// already has a configuration file
+pub fn exec() {
+    let legacy_project_dirs = config_ops::get_legacy_project_dirs();
+    let project_dirs = config_ops::get_project_dirs();
+
+    let legacy_config_dir_path = legacy_project_dirs.config_dir();
+    let config_dir_path = project_dirs.config_dir();
+
+    if config_dir_path.join("config.toml").exists() {
+        eprintln!(
+            "Configuration file {} already exists, nothing migrated. Exit",
+            config_dir_path.join("config.toml").to_string_lossy()
+        );
+        std::process::exit(1);
+    }
+
+    if !legacy_config_dir_path.join("config.toml").exists() {
+        println!(
+            "No manta configuration file found in {}, nothing to migrate",
+            legacy_config_dir_path.to_string_lossy()
+        );
+        return;
+    }
+
+    // Configuration file and CSM root certificates
+    let mut file_name_vec = vec!["config.toml".to_string()];
+
+    file_name_vec.extend(
+        std::fs::read_dir(legacy_config_dir_path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|dir_entry| dir_entry.file_name().to_string_lossy().to_string())
+            .filter(|file_name| file_name.ends_with("_root_cert.pem")),
+    );
+
+    for file_name in file_name_vec {
+        copy_file_or_exit(
+            &legacy_config_dir_path.join(&file_name),
+            &config_dir_path.join(&file_name),
+        );
+    }
+
+    // History of membership changes and audit trail
+    for file_name in [history_ops::HISTORY_FILE_NAME, audit_ops::AUDIT_FILE_NAME] {
+        let legacy_data_file_path = legacy_project_dirs.data_dir().join(file_name);
+        let data_file_path = project_dirs.data_dir().join(file_name);
+
+        if legacy_data_file_path.exists() && !data_file_path.exists() {
+            copy_file_or_exit(&legacy_data_file_path, &data_file_path);
+        }
+    }
+
+    println!("Migration completed, manta files are kept");
+}
+
+fn copy_file_or_exit(source_path: &Path, dest_path: &Path) {
+    let copy_rslt = dest_path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::copy(source_path, dest_path));
+
+    match copy_rslt {
+        Ok(_) => println!(
+            "{} -> {}",
+            source_path.to_string_lossy(),
+            dest_path.to_string_lossy()
+        ),
+        Err(error) => {
+            eprintln!(
+                "Could not copy {} to {}: {}. Exit",
+                source_path.to_string_lossy(),
+                dest_path.to_string_lossy(),
+                error
+            );
+            std::process::exit(1);
+        }
+    }
+}
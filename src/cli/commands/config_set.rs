@@ -0,0 +1,40 @@
+use config::Config;
+
+use crate::common::config_ops;
+
+/// Sets a key in the configuration file. Key is a dot separated path eg `site` or
+/// `sites.alps.shasta_base_url`
+pub fn exec(settings: &Config, key: &str, value: &str) {
+    let mut document = config_ops::read_configuration_document().unwrap_or_else(|error| {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    });
+
+    if key.eq("site")
+        && !settings
+            .get_table("sites")
+            .unwrap_or_default()
+            .contains_key(value)
+    {
+        eprintln!(
+            "WARNING: site '{}' not found in configuration, please add 'sites.{}' details",
+            value, value
+        );
+    }
+
+    if let Err(error) = config_ops::set_configuration_value(&mut document, key, value) {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    }
+
+    if let Err(error) = config_ops::write_configuration_document(&document) {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    }
+
+    println!(
+        "Configuration key '{}' set to '{}'",
+        key,
+        config_ops::redact_value(key, value)
+    );
+}
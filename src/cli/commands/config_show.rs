@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use comfy_table::Cell;
+use config::Config;
+
+use crate::common::{config_ops, table_ops};
+
+/// Prints configuration file location, global settings and details of the active site. Values
+/// overriden by `CLSTR_*` (or legacy `MANTA_*`) env vars are shown and secrets are redacted. If the configuration can't
+/// be loaded (`config_error_opt`), settings are shown as written in the configuration file
+pub fn exec(settings: &Config, site_name_opt: Option<&String>, config_error_opt: Option<&str>) {
+    println!(
+        "Configuration file: {}",
+        config_ops::get_configuration_toml_file_path().to_string_lossy()
+    );
+
+    println!(
+        "Active site: {}",
+        site_name_opt
+            .map(|site_name| site_name.as_str())
+            .unwrap_or("Not defined")
+    );
+
+    let (site_available_vec, setting_vec) = match config_error_opt {
+        None => get_setting_vec(settings, site_name_opt),
+        Some(config_error) => {
+            eprintln!(
+                "Configuration not valid, settings shown as written in the file: {}",
+                config_error
+            );
+
+            let document = config_ops::read_configuration_document().unwrap_or_else(|error| {
+                eprintln!("{}. Exit", error);
+                std::process::exit(1);
+            });
+
+            get_document_setting_vec(&document, site_name_opt)
+        }
+    };
+
+    println!("Sites available: {}", site_available_vec.join(", "));
+
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Key", "Value"]);
+
+    for (key, value) in setting_vec {
+        // Secrets are keyed by the last segment, eg sites.alps.client_secret
+        let value = config_ops::redact_value(key.rsplit('.').next().unwrap_or_default(), &value);
+
+        table.add_row(vec![Cell::new(key), Cell::new(value)]);
+    }
+
+    // Active site root certificate
+    if let Some(site_name) = site_name_opt {
+        let root_cert_file_path = config_ops::get_csm_root_cert_file_path(site_name);
+
+        table.add_row(vec![
+            Cell::new("root cert"),
+            Cell::new(format!(
+                "{} ({})",
+                root_cert_file_path.to_string_lossy(),
+                if root_cert_file_path.is_file() {
+                    "found"
+                } else {
+                    "missing"
+                }
+            )),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// Sites available plus global settings and settings of the active site, sorted by key
+fn get_setting_vec(
+    settings: &Config,
+    site_name_opt: Option<&String>,
+) -> (Vec<String>, Vec<(String, String)>) {
+    let site_detail_hashmap = settings.get_table("sites").unwrap_or_default();
+
+    let mut site_available_vec: Vec<String> = site_detail_hashmap.keys().cloned().collect();
+    site_available_vec.sort();
+
+    // Global settings
+    let mut setting_vec: Vec<(String, String)> = settings
+        .clone()
+        .try_deserialize::<HashMap<String, config::Value>>()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(key, _)| key.ne("sites"))
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+    setting_vec.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Active site settings
+    if let Some(site_name) = site_name_opt {
+        let mut site_setting_vec: Vec<(String, String)> = site_detail_hashmap
+            .get(site_name)
+            .and_then(|site_detail| site_detail.clone().into_table().ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| (format!("sites.{}.{}", site_name, key), value.to_string()))
+            .collect();
+        site_setting_vec.sort_by(|a, b| a.0.cmp(&b.0));
+
+        setting_vec.append(&mut site_setting_vec);
+    }
+
+    (site_available_vec, setting_vec)
+}
+
+/// Like `get_setting_vec` over the configuration file as written, without `CLSTR_*` env vars.
+/// Values are shown as TOML
+fn get_document_setting_vec(
+    document: &toml_edit::Document,
+    site_name_opt: Option<&String>,
+) -> (Vec<String>, Vec<(String, String)>) {
+    let site_table_like_opt = document
+        .get("sites")
+        .and_then(|sites_item| sites_item.as_table_like());
+
+    let mut site_available_vec: Vec<String> = site_table_like_opt
+        .map(|site_table_like| {
+            site_table_like
+                .iter()
+                .map(|(site_name, _)| site_name.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    site_available_vec.sort();
+
+    // Global settings
+    let mut setting_vec: Vec<(String, String)> =
+        get_table_like_setting_vec("", document.as_table())
+            .into_iter()
+            .filter(|(key, _)| key.ne("sites"))
+            .collect();
+
+    // Active site settings
+    if let Some((site_name, site_detail_table_like)) = site_name_opt.and_then(|site_name| {
+        site_table_like_opt?
+            .get(site_name)?
+            .as_table_like()
+            .map(|site_detail_table_like| (site_name, site_detail_table_like))
+    }) {
+        setting_vec.append(&mut get_table_like_setting_vec(
+            &format!("sites.{}.", site_name),
+            site_detail_table_like,
+        ));
+    }
+
+    (site_available_vec, setting_vec)
+}
+
+/// Keys (with `prefix`) and TOML values of a table, sorted by key
+fn get_table_like_setting_vec(
+    prefix: &str,
+    table_like: &dyn toml_edit::TableLike,
+) -> Vec<(String, String)> {
+    let mut setting_vec: Vec<(String, String)> = table_like
+        .iter()
+        .map(|(key, item)| {
+            (
+                format!("{}{}", prefix, key),
+                item.to_string().trim().to_string(),
+            )
+        })
+        .collect();
+    setting_vec.sort_by(|a, b| a.0.cmp(&b.0));
+
+    setting_vec
+}
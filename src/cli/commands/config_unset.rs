@@ -0,0 +1,22 @@
+use crate::common::config_ops;
+
+/// Removes a key from the configuration file. Key is a dot separated path eg `hsm_group` or
+/// `sites.alps.decommissioned_racks`
+pub fn exec(key: &str) {
+    let mut document = config_ops::read_configuration_document().unwrap_or_else(|error| {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    });
+
+    if let Err(error) = config_ops::unset_configuration_value(&mut document, key) {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    }
+
+    if let Err(error) = config_ops::write_configuration_document(&document) {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    }
+
+    println!("Configuration key '{}' removed", key);
+}
@@ -0,0 +1,174 @@
+use comfy_table::{Cell, Color};
+use config::Config;
+
+use crate::common::{config_ops, table_ops};
+
+pub struct ConfigCheck {
+    pub site: String,
+    pub check: String,
+    pub valid: bool,
+    pub detail: String,
+}
+
+impl ConfigCheck {
+    fn new(site: &str, check: &str, valid: bool, detail: String) -> Self {
+        Self {
+            site: site.to_string(),
+            check: check.to_string(),
+            valid,
+            detail,
+        }
+    }
+}
+
+/// Validates active site is defined and each site has the required keys (shasta_base_url,
+/// keycloak_base_url) and a valid CSM root certificate. If the configuration can't be loaded
+/// (`config_error_opt`), only the error is reported. Exits with error code if any check fails
+pub fn exec(settings: &Config, site_name_opt: Option<&String>, config_error_opt: Option<&str>) {
+    let config_check_vec = match config_error_opt {
+        Some(config_error) => vec![get_config_file_check(config_error)],
+        None => get_config_check_vec(settings, site_name_opt),
+    };
+
+    print_table(&config_check_vec);
+
+    if config_check_vec
+        .iter()
+        .any(|config_check| !config_check.valid)
+    {
+        eprintln!("Configuration is not valid");
+        std::process::exit(1);
+    }
+
+    println!("Configuration is valid");
+}
+
+/// Check of a configuration file which can't be loaded. TOML parser of the editable document
+/// points to the line and column of syntax errors
+fn get_config_file_check(config_error: &str) -> ConfigCheck {
+    let detail = match config_ops::read_configuration_document() {
+        Ok(_) => config_error.to_string(),
+        Err(error) => error.to_string().trim().to_string(),
+    };
+
+    ConfigCheck::new("-", "file", false, detail)
+}
+
+/// Checks of the active site and of each site in configuration file, site is '-' for checks not
+/// related to a site
+pub fn get_config_check_vec(settings: &Config, site_name_opt: Option<&String>) -> Vec<ConfigCheck> {
+    let mut config_check_vec = Vec::new();
+
+    let site_detail_hashmap = settings.get_table("sites").unwrap_or_default();
+
+    // Active site
+    match site_name_opt {
+        Some(site_name) if site_detail_hashmap.contains_key(site_name) => config_check_vec.push(
+            ConfigCheck::new("-", "site", true, format!("active site '{}'", site_name)),
+        ),
+        Some(site_name) => config_check_vec.push(ConfigCheck::new(
+            "-",
+            "site",
+            false,
+            format!("active site '{}' not found in 'sites'", site_name),
+        )),
+        None => config_check_vec.push(ConfigCheck::new(
+            "-",
+            "site",
+            false,
+            "key 'site' missing and no --site provided".to_string(),
+        )),
+    }
+
+    if site_detail_hashmap.is_empty() {
+        config_check_vec.push(ConfigCheck::new(
+            "-",
+            "sites",
+            false,
+            "no sites defined".to_string(),
+        ));
+    }
+
+    let mut site_name_vec: Vec<&String> = site_detail_hashmap.keys().collect();
+    site_name_vec.sort();
+
+    for site_name in site_name_vec {
+        let site_detail_value = site_detail_hashmap
+            .get(site_name)
+            .and_then(|site_detail| site_detail.clone().into_table().ok())
+            .unwrap_or_default();
+
+        // Required keys
+        for required_key in config_ops::SITE_REQUIRED_KEY_VEC {
+            match site_detail_value
+                .get(required_key)
+                .map(|value| value.to_string())
+            {
+                Some(value) if !value.is_empty() => {
+                    config_check_vec.push(ConfigCheck::new(site_name, required_key, true, value))
+                }
+                _ => config_check_vec.push(ConfigCheck::new(
+                    site_name,
+                    required_key,
+                    false,
+                    format!("key 'sites.{}.{}' missing", site_name, required_key),
+                )),
+            }
+        }
+
+        // Root certificate
+        let root_cert_file_path = config_ops::get_csm_root_cert_file_path(site_name);
+
+        match std::fs::read(&root_cert_file_path) {
+            Ok(root_cert) => match reqwest::Certificate::from_pem(&root_cert) {
+                Ok(_) => config_check_vec.push(ConfigCheck::new(
+                    site_name,
+                    "root cert",
+                    true,
+                    root_cert_file_path.to_string_lossy().to_string(),
+                )),
+                Err(error) => config_check_vec.push(ConfigCheck::new(
+                    site_name,
+                    "root cert",
+                    false,
+                    format!(
+                        "{} is not a valid PEM certificate: {}",
+                        root_cert_file_path.to_string_lossy(),
+                        error
+                    ),
+                )),
+            },
+            Err(error) => config_check_vec.push(ConfigCheck::new(
+                site_name,
+                "root cert",
+                false,
+                format!("{}: {}", root_cert_file_path.to_string_lossy(), error),
+            )),
+        }
+    }
+
+    config_check_vec
+}
+
+pub fn print_table(config_check_vec: &Vec<ConfigCheck>) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Site", "Check", "Result", "Detail"]);
+
+    for config_check in config_check_vec {
+        let result_cell = if config_check.valid {
+            Cell::new("✅").fg(Color::Green)
+        } else {
+            Cell::new("❌").fg(Color::Red)
+        };
+
+        table.add_row(vec![
+            Cell::new(config_check.site.clone()),
+            Cell::new(config_check.check.clone()),
+            result_cell.set_alignment(comfy_table::CellAlignment::Center),
+            Cell::new(config_check.detail.clone()),
+        ]);
+    }
+
+    println!("{table}");
+}
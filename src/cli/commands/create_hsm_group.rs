@@ -0,0 +1,74 @@
+use crate::shasta::hsm::{HsmGroup, Member};
+
+/// Creates an empty HSM group or a HSM group with the xnames in `member_vec`. Warns about members
+/// already in other HSM groups since overlapping membership breaks the target/parent HSM group
+/// accounting in `apply hsm-group`
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &str,
+    description_opt: Option<&String>,
+    member_vec: Vec<String>,
+) {
+    if !member_vec.is_empty() {
+        let hsm_group_vec =
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            });
+
+        for hsm_group_value in &hsm_group_vec {
+            let hsm_group_member_vec =
+                mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                    hsm_group_value,
+                );
+
+            for member in member_vec
+                .iter()
+                .filter(|member| hsm_group_member_vec.contains(member))
+            {
+                log::warn!(
+                    "Node '{}' already belongs to HSM group '{}'",
+                    member,
+                    hsm_group_value["label"].as_str().unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    let hsm_group = HsmGroup::new(
+        hsm_group_name.to_string(),
+        description_opt.cloned(),
+        None,
+        Some(Member::new(Some(member_vec.clone()))),
+    );
+
+    match crate::shasta::hsm::http_client::post_hsm_group(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &hsm_group,
+    )
+    .await
+    {
+        Ok(_) => println!(
+            "HSM group '{}' created with {} members",
+            hsm_group_name,
+            member_vec.len()
+        ),
+        Err(error) => {
+            eprintln!(
+                "Could not create HSM group '{}': {}. Exit",
+                hsm_group_name, error
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+/// Deletes a HSM group. Nodes are not deleted, only the HSM group. The HSM group is only deleted if
+/// it has no members (eg after `apply hsm-group` moved all of them back to the parent HSM group)
+/// unless `force`, then the user is asked for confirmation unless `assume_yes`. Deletions are not
+/// recorded in the history, so the members of a HSM group deleted with `force` are printed to
+/// recreate it if needed
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &String,
+    force: bool,
+    assume_yes: bool,
+) {
+    let hsm_group_value_vec =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(hsm_group_name),
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+    let Some(hsm_group_value) = hsm_group_value_vec.first() else {
+        eprintln!("HSM group '{}' not found. Exit", hsm_group_name);
+        std::process::exit(1);
+    };
+
+    let member_vec =
+        mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(hsm_group_value);
+
+    if !force && !member_vec.is_empty() {
+        eprintln!(
+            "HSM group '{}' has {} members, not deleting it. Move them to another HSM group with 'apply hsm-group' or use --force. Exit",
+            hsm_group_name,
+            member_vec.len()
+        );
+        std::process::exit(1);
+    }
+
+    if !member_vec.is_empty()
+        && !assume_yes
+        && !Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "HSM group '{}' has {} members. Do you want to delete it?",
+                hsm_group_name,
+                member_vec.len()
+            ))
+            .default(false)
+            .interact()
+            .unwrap_or_else(|error| {
+                eprintln!("Could not read confirmation: {}. Exit", error);
+                std::process::exit(1);
+            })
+    {
+        println!("Operation cancelled by user");
+        std::process::exit(0);
+    }
+
+    match crate::shasta::hsm::http_client::delete_hsm_group(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name,
+    )
+    .await
+    {
+        Ok(_) if member_vec.is_empty() => println!("HSM group '{}' deleted", hsm_group_name),
+        Ok(_) => println!(
+            "HSM group '{}' deleted, its members were: {}",
+            hsm_group_name,
+            member_vec.join(",")
+        ),
+        Err(error) => {
+            eprintln!(
+                "Could not delete HSM group '{}': {}. Exit",
+                hsm_group_name, error
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use comfy_table::Cell;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::{
+        export_inventory::InventorySnapshot,
+        get_nodes_artifacts::{ArtifactSummary, NodeSummary},
+    },
+    common::{format_ops::format_count, table_ops},
+};
+
+/// Component replaced in the same location (xname), this is, its serial number changed between
+/// 2 inventory snapshots. It was replaced after `replaced_after` and before `replaced_before`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FruReplacement {
+    pub node: String,
+    pub component: String,
+    pub r#type: String,
+    pub old_serial_number: String,
+    pub new_serial_number: String,
+    pub old_part_number: Option<String>,
+    pub new_part_number: Option<String>,
+    pub replaced_after: String,
+    pub replaced_before: String,
+}
+
+/// Reports the processors, GPUs, DIMMs and HSN NICs which serial number changed for the same
+/// xname between 2 inventory snapshots (see `export inventory`), this is, replaced FRUs. The
+/// replacement dates are bounded by the timestamps of the snapshots
+pub fn exec(
+    old_inventory_snapshot: &InventorySnapshot,
+    new_inventory_snapshot: &InventorySnapshot,
+    output_opt: Option<&String>,
+) {
+    let fru_replacement_vec =
+        calculate_fru_replacement_vec(old_inventory_snapshot, new_inventory_snapshot);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&fru_replacement_vec).unwrap()
+        );
+    } else {
+        print_table(&fru_replacement_vec);
+
+        println!(
+            "{} components replaced in {} nodes between {} and {}",
+            format_count(fru_replacement_vec.len()),
+            format_count(
+                fru_replacement_vec
+                    .iter()
+                    .map(|fru_replacement| &fru_replacement.node)
+                    .collect::<BTreeSet<&String>>()
+                    .len()
+            ),
+            format_timestamp(old_inventory_snapshot.timestamp),
+            format_timestamp(new_inventory_snapshot.timestamp)
+        );
+    }
+}
+
+/// Components in both snapshots, in the same node, which serial number changed. Components
+/// without serial number in any snapshot are ignored since their replacement can't be told
+pub fn calculate_fru_replacement_vec(
+    old_inventory_snapshot: &InventorySnapshot,
+    new_inventory_snapshot: &InventorySnapshot,
+) -> Vec<FruReplacement> {
+    let old_component_hashmap = get_component_hashmap(&old_inventory_snapshot.nodes);
+    let new_component_hashmap = get_component_hashmap(&new_inventory_snapshot.nodes);
+
+    let replaced_after = format_timestamp(old_inventory_snapshot.timestamp);
+    let replaced_before = format_timestamp(new_inventory_snapshot.timestamp);
+
+    let mut fru_replacement_vec = Vec::new();
+
+    for ((node_xname, component_xname), old_artifact_summary) in &old_component_hashmap {
+        let Some(new_artifact_summary) =
+            new_component_hashmap.get(&(*node_xname, *component_xname))
+        else {
+            continue;
+        };
+
+        let (Some(old_serial_number), Some(new_serial_number)) = (
+            &old_artifact_summary.serial_number,
+            &new_artifact_summary.serial_number,
+        ) else {
+            continue;
+        };
+
+        if old_serial_number != new_serial_number {
+            fru_replacement_vec.push(FruReplacement {
+                node: node_xname.to_string(),
+                component: component_xname.to_string(),
+                r#type: new_artifact_summary.r#type.to_string(),
+                old_serial_number: old_serial_number.clone(),
+                new_serial_number: new_serial_number.clone(),
+                old_part_number: old_artifact_summary.part_number.clone(),
+                new_part_number: new_artifact_summary.part_number.clone(),
+                replaced_after: replaced_after.clone(),
+                replaced_before: replaced_before.clone(),
+            });
+        }
+    }
+
+    fru_replacement_vec
+}
+
+/// Returns (node xname, component xname) -> component for all components in all nodes
+fn get_component_hashmap(
+    node_summary_vec: &[NodeSummary],
+) -> BTreeMap<(&str, &str), &ArtifactSummary> {
+    node_summary_vec
+        .iter()
+        .flat_map(|node_summary| {
+            node_summary
+                .processors
+                .iter()
+                .chain(&node_summary.node_accels)
+                .chain(&node_summary.memory)
+                .chain(&node_summary.node_hsn_nics)
+                .map(|artifact_summary| {
+                    (
+                        (node_summary.xname.as_str(), artifact_summary.xname.as_str()),
+                        artifact_summary,
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Snapshot timestamp (seconds since epoch) as UTC date and time
+fn format_timestamp(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|date_time| date_time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or(timestamp.to_string())
+}
+
+pub fn print_table(fru_replacement_vec: &[FruReplacement]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec![
+        "Node",
+        "Component",
+        "Type",
+        "Old serial number",
+        "New serial number",
+        "Old part number",
+        "New part number",
+    ]);
+
+    for fru_replacement in fru_replacement_vec {
+        table.add_row(vec![
+            Cell::new(&fru_replacement.node),
+            Cell::new(&fru_replacement.component),
+            Cell::new(&fru_replacement.r#type),
+            Cell::new(&fru_replacement.old_serial_number),
+            Cell::new(&fru_replacement.new_serial_number),
+            Cell::new(fru_replacement.old_part_number.clone().unwrap_or_default()),
+            Cell::new(fru_replacement.new_part_number.clone().unwrap_or_default()),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::get_nodes_artifacts::ArtifactType;
+
+    fn inventory_snapshot(timestamp: u64, dimm_serial_number_vec: &[&str]) -> InventorySnapshot {
+        InventorySnapshot {
+            version: 1,
+            timestamp,
+            hsm_groups: Vec::new(),
+            nodes: vec![NodeSummary {
+                xname: "x1000c0s0b0n0".to_string(),
+                r#type: "Node".to_string(),
+                processors: Vec::new(),
+                memory: dimm_serial_number_vec
+                    .iter()
+                    .enumerate()
+                    .map(|(index, serial_number)| ArtifactSummary {
+                        xname: format!("x1000c0s0b0n0d{}", index),
+                        r#type: ArtifactType::Memory,
+                        info: Some("16384 MiB".to_string()),
+                        serial_number: Some(serial_number.to_string())
+                            .filter(|serial_number| !serial_number.is_empty()),
+                        part_number: None,
+                        core_count: None,
+                        max_speed_mhz: None,
+                        gpu_memory: None,
+                    })
+                    .collect(),
+                node_accels: Vec::new(),
+                node_hsn_nics: Vec::new(),
+                state: None,
+                flag: None,
+                enabled: None,
+            }],
+            failed_nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_calculate_fru_replacement_vec() {
+        let old_inventory_snapshot = inventory_snapshot(1700000000, &["A1", "A2", ""]);
+        let new_inventory_snapshot = inventory_snapshot(1710000000, &["A1", "B2", "B3"]);
+
+        let fru_replacement_vec =
+            calculate_fru_replacement_vec(&old_inventory_snapshot, &new_inventory_snapshot);
+
+        assert_eq!(fru_replacement_vec.len(), 1);
+        assert_eq!(fru_replacement_vec[0].component, "x1000c0s0b0n0d1");
+        assert_eq!(fru_replacement_vec[0].old_serial_number, "A2");
+        assert_eq!(fru_replacement_vec[0].new_serial_number, "B2");
+        assert_eq!(
+            fru_replacement_vec[0].replaced_after,
+            "2023-11-14 22:13:20 UTC"
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::{
+        export_inventory::{get_inventory_snapshot, read_inventory_snapshot, InventorySnapshot},
+        get_nodes_artifacts::NodeSummary,
+    },
+    common::{format_ops::format_count, table_ops},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InventoryChange {
+    pub node: String,
+    pub change: String,
+    pub component: Option<String>,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl InventoryChange {
+    fn new(
+        node: &str,
+        change: &str,
+        component: Option<&String>,
+        old: Option<String>,
+        new: Option<String>,
+    ) -> Self {
+        Self {
+            node: node.to_string(),
+            change: change.to_string(),
+            component: component.cloned(),
+            old,
+            new,
+        }
+    }
+}
+
+/// Reports the differences between 2 inventory snapshots (see `export inventory`): nodes added or
+/// removed, components (processors, GPUs, DIMMs and HSN NICs) added, removed or replaced (FRU info
+/// changed) and changes in the total memory capacity of each node. Nodes which hw inventory could
+/// not be fetched in any of the snapshots are not compared
+pub fn exec(
+    old_inventory_snapshot: &InventorySnapshot,
+    new_inventory_snapshot: &InventorySnapshot,
+    output_opt: Option<&String>,
+) {
+    let failed_xname_set: BTreeSet<&String> = old_inventory_snapshot
+        .failed_nodes
+        .iter()
+        .chain(&new_inventory_snapshot.failed_nodes)
+        .collect();
+
+    if !failed_xname_set.is_empty() {
+        eprintln!(
+            "WARNING - Hw inventory of {} nodes could not be fetched, they are not compared: {}",
+            format_count(failed_xname_set.len()),
+            failed_xname_set
+                .iter()
+                .map(|xname| xname.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+    }
+
+    let inventory_change_vec =
+        calculate_inventory_snapshot_change_vec(old_inventory_snapshot, new_inventory_snapshot);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&inventory_change_vec).unwrap()
+        );
+    } else {
+        print_table(&inventory_change_vec);
+
+        println!(
+            "{} changes found in {} nodes",
+            format_count(inventory_change_vec.len()),
+            format_count(
+                inventory_change_vec
+                    .iter()
+                    .map(|inventory_change| &inventory_change.node)
+                    .collect::<BTreeSet<&String>>()
+                    .len()
+            )
+        );
+    }
+}
+
+/// Reads an inventory snapshot file, exits if the file can't be read or parsed
+pub fn read_inventory_snapshot_or_exit(file_path: &str) -> InventorySnapshot {
+    read_inventory_snapshot(file_path).unwrap_or_else(|error| {
+        eprintln!(
+            "Could not read inventory snapshot '{}': {}. Exit",
+            file_path, error
+        );
+        std::process::exit(1);
+    })
+}
+
+/// Current hw inventory of the HSM groups in `old_inventory_snapshot`, restricted to
+/// `hsm_group_name_opt` if set, so it can be compared with the old snapshot
+pub async fn get_live_inventory_snapshot(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    old_inventory_snapshot: &InventorySnapshot,
+) -> InventorySnapshot {
+    let old_hsm_group_name_vec: Vec<String> = old_inventory_snapshot
+        .hsm_groups
+        .iter()
+        .map(|hsm_group_snapshot| hsm_group_snapshot.label.clone())
+        .collect();
+
+    // Fetch the same HSM groups as in the old snapshot
+    let hsm_group_name_opt = match hsm_group_name_opt {
+        Some(_) => hsm_group_name_opt,
+        None if old_hsm_group_name_vec.len() == 1 => old_hsm_group_name_vec.first(),
+        None => None,
+    };
+
+    let mut new_inventory_snapshot = get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name_opt,
+    )
+    .await;
+
+    new_inventory_snapshot
+        .hsm_groups
+        .retain(|hsm_group_snapshot| old_hsm_group_name_vec.contains(&hsm_group_snapshot.label));
+
+    let new_member_vec: Vec<&String> = new_inventory_snapshot
+        .hsm_groups
+        .iter()
+        .flat_map(|hsm_group_snapshot| &hsm_group_snapshot.members)
+        .collect();
+
+    new_inventory_snapshot
+        .nodes
+        .retain(|node_summary| new_member_vec.contains(&&node_summary.xname));
+
+    new_inventory_snapshot
+}
+
+/// Changes between 2 inventory snapshots, but for the nodes which hw inventory could not be
+/// fetched in any of them (eg a node would be reported as removed otherwise)
+pub fn calculate_inventory_snapshot_change_vec(
+    old_inventory_snapshot: &InventorySnapshot,
+    new_inventory_snapshot: &InventorySnapshot,
+) -> Vec<InventoryChange> {
+    calculate_inventory_change_vec(&old_inventory_snapshot.nodes, &new_inventory_snapshot.nodes)
+        .into_iter()
+        .filter(|inventory_change| {
+            !old_inventory_snapshot
+                .failed_nodes
+                .contains(&inventory_change.node)
+                && !new_inventory_snapshot
+                    .failed_nodes
+                    .contains(&inventory_change.node)
+        })
+        .collect()
+}
+
+pub fn calculate_inventory_change_vec(
+    old_node_summary_vec: &[NodeSummary],
+    new_node_summary_vec: &[NodeSummary],
+) -> Vec<InventoryChange> {
+    let old_node_summary_hashmap: HashMap<&String, &NodeSummary> = old_node_summary_vec
+        .iter()
+        .map(|node_summary| (&node_summary.xname, node_summary))
+        .collect();
+
+    let new_node_summary_hashmap: HashMap<&String, &NodeSummary> = new_node_summary_vec
+        .iter()
+        .map(|node_summary| (&node_summary.xname, node_summary))
+        .collect();
+
+    let xname_set: BTreeSet<&String> = old_node_summary_hashmap
+        .keys()
+        .chain(new_node_summary_hashmap.keys())
+        .copied()
+        .collect();
+
+    let mut inventory_change_vec = Vec::new();
+
+    for xname in xname_set {
+        match (
+            old_node_summary_hashmap.get(xname),
+            new_node_summary_hashmap.get(xname),
+        ) {
+            (Some(_), None) => {
+                inventory_change_vec.push(InventoryChange::new(
+                    xname,
+                    "node removed",
+                    None,
+                    None,
+                    None,
+                ));
+            }
+            (None, Some(_)) => {
+                inventory_change_vec.push(InventoryChange::new(
+                    xname,
+                    "node added",
+                    None,
+                    None,
+                    None,
+                ));
+            }
+            (Some(old_node_summary), Some(new_node_summary)) => {
+                inventory_change_vec.extend(calculate_node_change_vec(
+                    old_node_summary,
+                    new_node_summary,
+                ));
+            }
+            (None, None) => {}
+        }
+    }
+
+    inventory_change_vec
+}
+
+fn calculate_node_change_vec(
+    old_node_summary: &NodeSummary,
+    new_node_summary: &NodeSummary,
+) -> Vec<InventoryChange> {
+    let xname = &old_node_summary.xname;
+
+    let old_component_hashmap = get_component_hashmap(old_node_summary);
+    let new_component_hashmap = get_component_hashmap(new_node_summary);
+
+    let component_xname_set: BTreeSet<&String> = old_component_hashmap
+        .keys()
+        .chain(new_component_hashmap.keys())
+        .copied()
+        .collect();
+
+    let mut inventory_change_vec = Vec::new();
+
+    for component_xname in component_xname_set {
+        match (
+            old_component_hashmap.get(component_xname),
+            new_component_hashmap.get(component_xname),
+        ) {
+            (Some(old_info), None) => inventory_change_vec.push(InventoryChange::new(
+                xname,
+                "component removed",
+                Some(component_xname),
+                (*old_info).clone(),
+                None,
+            )),
+            (None, Some(new_info)) => inventory_change_vec.push(InventoryChange::new(
+                xname,
+                "component added",
+                Some(component_xname),
+                None,
+                (*new_info).clone(),
+            )),
+            (Some(old_info), Some(new_info)) if old_info != new_info => {
+                inventory_change_vec.push(InventoryChange::new(
+                    xname,
+                    "component replaced",
+                    Some(component_xname),
+                    (*old_info).clone(),
+                    (*new_info).clone(),
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    let old_memory_capacity_mib = calculate_memory_capacity_mib(old_node_summary);
+    let new_memory_capacity_mib = calculate_memory_capacity_mib(new_node_summary);
+
+    if old_memory_capacity_mib != new_memory_capacity_mib {
+        inventory_change_vec.push(InventoryChange::new(
+            xname,
+            "memory capacity changed",
+            None,
+            Some(format!("{} MiB", old_memory_capacity_mib)),
+            Some(format!("{} MiB", new_memory_capacity_mib)),
+        ));
+    }
+
+    inventory_change_vec
+}
+
+/// Returns component xname -> FRU info for all components in a node
+fn get_component_hashmap(node_summary: &NodeSummary) -> BTreeMap<&String, &Option<String>> {
+    node_summary
+        .processors
+        .iter()
+        .chain(&node_summary.node_accels)
+        .chain(&node_summary.memory)
+        .chain(&node_summary.node_hsn_nics)
+        .map(|artifact_summary| (&artifact_summary.xname, &artifact_summary.info))
+        .collect()
+}
+
+/// Total memory of a node in MiB, DIMMs info looks like '16384 MiB'
+pub fn calculate_memory_capacity_mib(node_summary: &NodeSummary) -> u64 {
+    node_summary
+        .memory
+        .iter()
+        .filter_map(|memory| memory.info.as_ref())
+        .filter_map(|info| info.trim_end_matches(" MiB").parse::<u64>().ok())
+        .sum()
+}
+
+pub fn print_table(inventory_change_vec: &[InventoryChange]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Node", "Change", "Component", "Old", "New"]);
+
+    for inventory_change in inventory_change_vec {
+        let change_color = if inventory_change.change.ends_with("removed") {
+            Color::Red
+        } else if inventory_change.change.ends_with("added") {
+            Color::Green
+        } else {
+            Color::Yellow
+        };
+
+        table.add_row(vec![
+            Cell::new(&inventory_change.node),
+            Cell::new(&inventory_change.change).fg(change_color),
+            Cell::new(inventory_change.component.clone().unwrap_or_default()),
+            Cell::new(inventory_change.old.clone().unwrap_or_default()),
+            Cell::new(inventory_change.new.clone().unwrap_or_default()),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::get_nodes_artifacts::{ArtifactSummary, ArtifactType};
+
+    fn dimm(xname: &str, capacity_mib: usize) -> ArtifactSummary {
+        ArtifactSummary {
+            xname: xname.to_string(),
+            r#type: ArtifactType::Memory,
+            info: Some(format!("{} MiB", capacity_mib)),
+            serial_number: None,
+            part_number: None,
+            core_count: None,
+            max_speed_mhz: None,
+            gpu_memory: None,
+        }
+    }
+
+    fn node_summary(xname: &str, memory: Vec<ArtifactSummary>) -> NodeSummary {
+        NodeSummary {
+            xname: xname.to_string(),
+            r#type: "Node".to_string(),
+            processors: Vec::new(),
+            memory,
+            node_accels: Vec::new(),
+            node_hsn_nics: Vec::new(),
+            state: None,
+            flag: None,
+            enabled: None,
+        }
+    }
+
+    #[test]
+    fn test_calculate_inventory_change_vec() {
+        let old_node_summary_vec = vec![
+            node_summary(
+                "x1000c0s0b0n0",
+                vec![
+                    dimm("x1000c0s0b0n0d0", 16384),
+                    dimm("x1000c0s0b0n0d1", 16384),
+                ],
+            ),
+            node_summary("x1000c0s0b0n1", Vec::new()),
+        ];
+        let new_node_summary_vec = vec![
+            node_summary("x1000c0s0b0n0", vec![dimm("x1000c0s0b0n0d0", 16384)]),
+            node_summary("x1000c0s0b1n0", Vec::new()),
+        ];
+
+        let inventory_change_vec =
+            calculate_inventory_change_vec(&old_node_summary_vec, &new_node_summary_vec);
+
+        let change_vec: Vec<(&str, &str)> = inventory_change_vec
+            .iter()
+            .map(|inventory_change| {
+                (
+                    inventory_change.node.as_str(),
+                    inventory_change.change.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            change_vec,
+            vec![
+                ("x1000c0s0b0n0", "component removed"),
+                ("x1000c0s0b0n0", "memory capacity changed"),
+                ("x1000c0s0b0n1", "node removed"),
+                ("x1000c0s0b1n0", "node added"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calculate_inventory_snapshot_change_vec() {
+        let old_inventory_snapshot = InventorySnapshot {
+            version: 1,
+            timestamp: 0,
+            hsm_groups: Vec::new(),
+            nodes: vec![
+                node_summary("x1000c0s0b0n0", Vec::new()),
+                node_summary("x1000c0s0b0n1", Vec::new()),
+            ],
+            failed_nodes: Vec::new(),
+        };
+        let new_inventory_snapshot = InventorySnapshot {
+            version: 1,
+            timestamp: 1,
+            hsm_groups: Vec::new(),
+            nodes: Vec::new(),
+            failed_nodes: vec!["x1000c0s0b0n1".to_string()],
+        };
+
+        // Node which hw inventory could not be fetched is not reported as removed
+        assert_eq!(
+            calculate_inventory_snapshot_change_vec(
+                &old_inventory_snapshot,
+                &new_inventory_snapshot
+            ),
+            vec![InventoryChange::new(
+                "x1000c0s0b0n0",
+                "node removed",
+                None,
+                None,
+                None
+            )]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    cli::commands::{
+        export_inventory::{self, InventorySnapshot},
+        get_nodes_artifacts::NodeSummary,
+    },
+    common::{format_ops::format_count, hw_inventory_ops},
+};
+
+/// Writes an Ansible inventory of the HSM group (or all HSM groups if None) to a file (or
+/// stdout). Each HSM group is a host group and the hw of each node (accelerators, memory and
+/// rack) are host variables prefixed with `clstr_`
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    file_path_opt: Option<&String>,
+    format: &str,
+) {
+    let inventory_snapshot = export_inventory::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name_opt,
+    )
+    .await;
+
+    let ansible_inventory = if format.eq("ini") {
+        get_ansible_inventory_ini(&inventory_snapshot)
+    } else {
+        get_ansible_inventory_yaml(&inventory_snapshot)
+    };
+
+    match file_path_opt {
+        Some(file_path) => {
+            std::fs::write(file_path, ansible_inventory).unwrap_or_else(|error| {
+                eprintln!("Could not write file '{}': {}. Exit", file_path, error);
+                std::process::exit(1);
+            });
+
+            println!(
+                "Ansible inventory of {} HSM groups and {} nodes written to '{}'",
+                format_count(inventory_snapshot.hsm_groups.len()),
+                format_count(inventory_snapshot.nodes.len()),
+                file_path
+            );
+        }
+        None => print!("{}", ansible_inventory),
+    }
+}
+
+/// Host variables of a node: accelerator model(s) and count, total memory in MiB and rack
+/// (cabinet xname). Accelerator variables are missing if the node has none
+pub fn get_host_var_map(node_summary: &NodeSummary) -> Map<String, Value> {
+    let mut host_var_map = Map::new();
+
+    if !node_summary.node_accels.is_empty() {
+        let mut gpu_model_vec: Vec<String> = node_summary
+            .node_accels
+            .iter()
+            .filter_map(|node_accel| node_accel.info.clone())
+            .collect();
+        gpu_model_vec.sort();
+        gpu_model_vec.dedup();
+
+        host_var_map.insert(
+            "clstr_gpu_model".to_string(),
+            json!(gpu_model_vec.join(", ")),
+        );
+        host_var_map.insert(
+            "clstr_gpu_count".to_string(),
+            json!(node_summary.node_accels.len()),
+        );
+    }
+
+    let memory_mib: u64 = node_summary
+        .memory
+        .iter()
+        .filter_map(|memory_dimm| {
+            memory_dimm
+                .info
+                .as_ref()
+                .and_then(|info| info.split(' ').next())
+                .and_then(|memory_capacity| memory_capacity.parse::<u64>().ok())
+        })
+        .sum();
+
+    host_var_map.insert("clstr_memory_mib".to_string(), json!(memory_mib));
+
+    if let Some(cabinet_xname) = hw_inventory_ops::get_cabinet_xname(&node_summary.xname) {
+        host_var_map.insert("clstr_rack".to_string(), json!(cabinet_xname));
+    }
+
+    host_var_map
+}
+
+/// Ansible only accepts letters, numbers and underscores in group names, eg
+/// `zinal-a100` -> `zinal_a100`
+pub fn get_ansible_group_name(hsm_group_name: &str) -> String {
+    hsm_group_name
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || character == '_' {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Ansible YAML inventory, host variables under `all.hosts` and one child group per HSM group
+pub fn get_ansible_inventory_yaml(inventory_snapshot: &InventorySnapshot) -> String {
+    let host_map: Map<String, Value> = get_node_summary_sorted_vec(inventory_snapshot)
+        .into_iter()
+        .map(|node_summary| {
+            (
+                node_summary.xname.clone(),
+                Value::Object(get_host_var_map(node_summary)),
+            )
+        })
+        .collect();
+
+    let children_map: Map<String, Value> = inventory_snapshot
+        .hsm_groups
+        .iter()
+        .map(|hsm_group_snapshot| {
+            let member_map: Map<String, Value> = hsm_group_snapshot
+                .members
+                .iter()
+                .map(|xname| (xname.clone(), json!({})))
+                .collect();
+
+            (
+                get_ansible_group_name(&hsm_group_snapshot.label),
+                json!({ "hosts": member_map }),
+            )
+        })
+        .collect();
+
+    serde_yaml::to_string(&json!({
+        "all": {
+            "hosts": host_map,
+            "children": children_map,
+        }
+    }))
+    .unwrap()
+}
+
+/// Ansible INI inventory, host variables in the `all` group and one section per HSM group
+pub fn get_ansible_inventory_ini(inventory_snapshot: &InventorySnapshot) -> String {
+    let mut ini = "[all]\n".to_string();
+
+    for node_summary in get_node_summary_sorted_vec(inventory_snapshot) {
+        let host_var_vec: Vec<String> = get_host_var_map(node_summary)
+            .into_iter()
+            .map(|(host_var, value)| match value {
+                Value::String(value) => format!("{}=\"{}\"", host_var, value.replace('"', "\\\"")),
+                value => format!("{}={}", host_var, value),
+            })
+            .collect();
+
+        ini.push_str(&format!(
+            "{} {}\n",
+            node_summary.xname,
+            host_var_vec.join(" ")
+        ));
+    }
+
+    for hsm_group_snapshot in &inventory_snapshot.hsm_groups {
+        ini.push_str(&format!(
+            "\n[{}]\n",
+            get_ansible_group_name(&hsm_group_snapshot.label)
+        ));
+
+        for xname in &hsm_group_snapshot.members {
+            ini.push_str(&format!("{}\n", xname));
+        }
+    }
+
+    ini
+}
+
+fn get_node_summary_sorted_vec(inventory_snapshot: &InventorySnapshot) -> Vec<&NodeSummary> {
+    let node_summary_hashmap: BTreeMap<&String, &NodeSummary> = inventory_snapshot
+        .nodes
+        .iter()
+        .map(|node_summary| (&node_summary.xname, node_summary))
+        .collect();
+
+    node_summary_hashmap.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::{
+        export_inventory::HsmGroupSnapshot,
+        get_nodes_artifacts::{ArtifactSummary, ArtifactType},
+    };
+
+    fn get_inventory_snapshot() -> InventorySnapshot {
+        InventorySnapshot {
+            version: 1,
+            timestamp: 0,
+            hsm_groups: vec![HsmGroupSnapshot {
+                label: "zinal-a100".to_string(),
+                members: vec!["x1000c0s0b0n0".to_string()],
+            }],
+            nodes: vec![NodeSummary {
+                xname: "x1000c0s0b0n0".to_string(),
+                r#type: "Node".to_string(),
+                processors: Vec::new(),
+                memory: vec![
+                    ArtifactSummary {
+                        xname: "x1000c0s0b0n0d0".to_string(),
+                        r#type: ArtifactType::Memory,
+                        info: Some("16384 MiB".to_string()),
+                        serial_number: None,
+                        part_number: None,
+                        core_count: None,
+                        max_speed_mhz: None,
+                        gpu_memory: None,
+                    };
+                    2
+                ],
+                node_accels: vec![
+                    ArtifactSummary {
+                        xname: "x1000c0s0b0n0a0".to_string(),
+                        r#type: ArtifactType::NodeAccel,
+                        info: Some("NVIDIA A100".to_string()),
+                        serial_number: None,
+                        part_number: None,
+                        core_count: None,
+                        max_speed_mhz: None,
+                        gpu_memory: None,
+                    };
+                    4
+                ],
+                node_hsn_nics: Vec::new(),
+                state: None,
+                flag: None,
+                enabled: None,
+            }],
+            failed_nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_get_ansible_inventory_ini() {
+        assert_eq!(
+            get_ansible_inventory_ini(&get_inventory_snapshot()),
+            "[all]\nx1000c0s0b0n0 clstr_gpu_count=4 clstr_gpu_model=\"NVIDIA A100\" clstr_memory_mib=32768 clstr_rack=\"x1000\"\n\n[zinal_a100]\nx1000c0s0b0n0\n"
+        );
+    }
+
+    #[test]
+    fn test_get_ansible_inventory_yaml() {
+        let inventory_value: Value =
+            serde_yaml::from_str(&get_ansible_inventory_yaml(&get_inventory_snapshot())).unwrap();
+
+        assert_eq!(
+            inventory_value["all"]["hosts"]["x1000c0s0b0n0"]["clstr_gpu_model"],
+            "NVIDIA A100"
+        );
+        assert_eq!(
+            inventory_value["all"]["hosts"]["x1000c0s0b0n0"]["clstr_memory_mib"],
+            32768
+        );
+        assert!(inventory_value["all"]["children"]["zinal_a100"]["hosts"]
+            .get("x1000c0s0b0n0")
+            .is_some());
+    }
+}
@@ -0,0 +1,292 @@
+use comfy_table::Cell;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    cli::commands::{
+        export_inventory,
+        get_nodes_artifacts::{ArtifactSummary, NodeSummary},
+    },
+    common::{format_ops::format_count, table_ops, xname},
+};
+
+/// Fields of an asset which can be selected with `--fields`, same order as the fields of
+/// AssetRecord. Also the columns of the CSV asset export
+pub const ASSET_FIELD_NAME_VEC: [&str; 6] = [
+    "node",
+    "xname",
+    "type",
+    "model",
+    "serial_number",
+    "part_number",
+];
+
+/// Hw component installed in a node as an asset management system (eg NetBox or a CMDB) expects
+/// it. `model` is the processor/GPU model, the DIMM capacity or the HSN NIC description
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AssetRecord {
+    pub node: String,
+    pub xname: String,
+    pub r#type: String,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub part_number: Option<String>,
+}
+
+impl AssetRecord {
+    /// Value of a field in ASSET_FIELD_NAME_VEC
+    pub fn get_field(&self, field: &str) -> Option<&String> {
+        match field {
+            "node" => Some(&self.node),
+            "xname" => Some(&self.xname),
+            "type" => Some(&self.r#type),
+            "model" => self.model.as_ref(),
+            "serial_number" => self.serial_number.as_ref(),
+            "part_number" => self.part_number.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Validates a `--fields` value, `serial` and `part` are accepted for `serial_number` and
+/// `part_number`. To be used as clap value parser
+pub fn parse_asset_field(field: &str) -> Result<String, String> {
+    let field = match field.trim().to_lowercase().as_str() {
+        "serial" => "serial_number".to_string(),
+        "part" => "part_number".to_string(),
+        field => field.to_string(),
+    };
+
+    if ASSET_FIELD_NAME_VEC.contains(&field.as_str()) {
+        Ok(field)
+    } else {
+        Err(format!(
+            "unknown field, valid fields are {}",
+            ASSET_FIELD_NAME_VEC.join(", ")
+        ))
+    }
+}
+
+/// Writes the processors, GPUs, DIMMs and HSN NICs of the HSM group (or all HSM groups if None)
+/// with their serial and part numbers to a file (or stdout) in CSV or JSON format, one record
+/// per hw component
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    file_path_opt: Option<&String>,
+    format: &str,
+    field_vec_opt: Option<&Vec<String>>,
+) {
+    let inventory_snapshot = export_inventory::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name_opt,
+    )
+    .await;
+
+    let asset_record_vec = get_asset_record_vec(&inventory_snapshot.nodes);
+
+    let field_vec = get_field_vec(field_vec_opt);
+
+    let assets = if format.eq("json") {
+        get_asset_json(&asset_record_vec, &field_vec) + "\n"
+    } else {
+        get_asset_csv(&asset_record_vec, &field_vec)
+    };
+
+    match file_path_opt {
+        Some(file_path) => {
+            std::fs::write(file_path, assets).unwrap_or_else(|error| {
+                eprintln!("Could not write file '{}': {}. Exit", file_path, error);
+                std::process::exit(1);
+            });
+
+            println!(
+                "{} assets of {} nodes written to '{}'",
+                format_count(asset_record_vec.len()),
+                format_count(inventory_snapshot.nodes.len()),
+                file_path
+            );
+        }
+        None => print!("{}", assets),
+    }
+}
+
+/// Prints one record per hw component of the nodes with the fields selected (all if None) as
+/// JSON, CSV or table depending on `output_opt`
+pub fn print_asset_record_vec(
+    node_summary_vec: &[NodeSummary],
+    field_vec_opt: Option<&Vec<String>>,
+    output_opt: Option<&String>,
+) {
+    let asset_record_vec = get_asset_record_vec(node_summary_vec);
+
+    let field_vec = get_field_vec(field_vec_opt);
+
+    match output_opt.map(String::as_str) {
+        Some("json") => println!("{}", get_asset_json(&asset_record_vec, &field_vec)),
+        Some("csv") => print!("{}", get_asset_csv(&asset_record_vec, &field_vec)),
+        _ => print_table(&asset_record_vec, &field_vec),
+    }
+}
+
+/// Fields selected, all fields if None
+fn get_field_vec(field_vec_opt: Option<&Vec<String>>) -> Vec<String> {
+    field_vec_opt.cloned().unwrap_or(
+        ASSET_FIELD_NAME_VEC
+            .iter()
+            .map(|field| field.to_string())
+            .collect(),
+    )
+}
+
+/// One record per hw component of each node, sorted by node and hw component xname
+pub fn get_asset_record_vec(node_summary_vec: &[NodeSummary]) -> Vec<AssetRecord> {
+    let mut asset_record_vec: Vec<AssetRecord> = node_summary_vec
+        .iter()
+        .flat_map(|node_summary| {
+            node_summary
+                .processors
+                .iter()
+                .chain(node_summary.node_accels.iter())
+                .chain(node_summary.memory.iter())
+                .chain(node_summary.node_hsn_nics.iter())
+                .map(|artifact_summary| get_asset_record(&node_summary.xname, artifact_summary))
+        })
+        .collect();
+
+    asset_record_vec.sort_by(|a, b| {
+        xname::compare_xnames(&a.node, &b.node).then(xname::compare_xnames(&a.xname, &b.xname))
+    });
+
+    asset_record_vec
+}
+
+fn get_asset_record(node_xname: &str, artifact_summary: &ArtifactSummary) -> AssetRecord {
+    AssetRecord {
+        node: node_xname.to_string(),
+        xname: artifact_summary.xname.clone(),
+        r#type: artifact_summary.r#type.to_string(),
+        model: artifact_summary.info.clone(),
+        serial_number: artifact_summary.serial_number.clone(),
+        part_number: artifact_summary.part_number.clone(),
+    }
+}
+
+/// CSV (RFC 4180) with the fields selected and a header line, missing values are empty fields
+pub fn get_asset_csv(asset_record_vec: &[AssetRecord], field_vec: &[String]) -> String {
+    let mut csv = field_vec.join(",") + "\n";
+
+    for asset_record in asset_record_vec {
+        csv.push_str(
+            &field_vec
+                .iter()
+                .map(|field| {
+                    escape_csv_field(asset_record.get_field(field).map_or("", String::as_str))
+                })
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// JSON array with an object per asset with the fields selected, missing values are null
+pub fn get_asset_json(asset_record_vec: &[AssetRecord], field_vec: &[String]) -> String {
+    let asset_value_vec: Vec<Map<String, Value>> = asset_record_vec
+        .iter()
+        .map(|asset_record| {
+            field_vec
+                .iter()
+                .map(|field| {
+                    (
+                        field.clone(),
+                        serde_json::json!(asset_record.get_field(field)),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&asset_value_vec).unwrap()
+}
+
+pub fn print_table(asset_record_vec: &[AssetRecord], field_vec: &[String]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(field_vec);
+
+    let mut row_vec: Vec<Vec<Cell>> = asset_record_vec
+        .iter()
+        .map(|asset_record| {
+            field_vec
+                .iter()
+                .map(|field| Cell::new(asset_record.get_field(field).map_or("n/a", String::as_str)))
+                .collect()
+        })
+        .collect();
+
+    table_ops::sort_row_vec(field_vec, &mut row_vec);
+    table.add_rows(row_vec);
+
+    println!("{table}");
+}
+
+/// Quotes fields with commas, quotes or line breaks, quotes inside are doubled
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_asset_csv() {
+        let asset_record_vec = vec![
+            AssetRecord {
+                node: "x1000c0s0b0n0".to_string(),
+                xname: "x1000c0s0b0n0a0".to_string(),
+                r#type: "NodeAccel".to_string(),
+                model: Some("NVIDIA A100, SXM4".to_string()),
+                serial_number: Some("1652321000123".to_string()),
+                part_number: Some("699-2G506-0210-300".to_string()),
+            },
+            AssetRecord {
+                node: "x1000c0s0b0n0".to_string(),
+                xname: "x1000c0s0b0n0d0".to_string(),
+                r#type: "Memory".to_string(),
+                model: Some("16384 MiB".to_string()),
+                serial_number: None,
+                part_number: None,
+            },
+        ];
+
+        assert_eq!(
+            get_asset_csv(
+                &asset_record_vec,
+                &ASSET_FIELD_NAME_VEC.map(|field| field.to_string())
+            ),
+            "node,xname,type,model,serial_number,part_number\n\
+             x1000c0s0b0n0,x1000c0s0b0n0a0,NodeAccel,\"NVIDIA A100, SXM4\",1652321000123,699-2G506-0210-300\n\
+             x1000c0s0b0n0,x1000c0s0b0n0d0,Memory,16384 MiB,,\n"
+        );
+
+        assert_eq!(
+            get_asset_csv(
+                &asset_record_vec[..1],
+                &["xname".to_string(), parse_asset_field("serial").unwrap()]
+            ),
+            "xname,serial_number\nx1000c0s0b0n0a0,1652321000123\n"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::common::format_ops::format_count;
+
+pub use crate::common::snapshot_ops::{
+    read_inventory_snapshot, write_ndjson, HsmGroupSnapshot, InventorySnapshot,
+};
+
+/// Dumps the hw inventory (NodeSummary) of all members of a HSM group (or all HSM groups) to a
+/// file (or stdout) in JSON or NDJSON format. Nodes belonging to multiple HSM groups are only
+/// dumped once
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    file_path_opt: Option<&String>,
+    format: &str,
+) {
+    let inventory_snapshot = get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name_opt,
+    )
+    .await;
+
+    let writer: Box<dyn Write> = match file_path_opt {
+        Some(file_path) => Box::new(File::create(file_path).unwrap_or_else(|error| {
+            eprintln!("Could not create file '{}': {}. Exit", file_path, error);
+            std::process::exit(1);
+        })),
+        None => Box::new(std::io::stdout()),
+    };
+
+    let mut writer = BufWriter::new(writer);
+
+    if format.eq("ndjson") {
+        write_ndjson(&mut writer, &inventory_snapshot).unwrap();
+    } else {
+        serde_json::to_writer_pretty(&mut writer, &inventory_snapshot).unwrap();
+        writeln!(writer).unwrap();
+    }
+
+    writer.flush().unwrap();
+
+    if let Some(file_path) = file_path_opt {
+        println!(
+            "Inventory snapshot of {} HSM groups and {} nodes written to '{}'",
+            format_count(inventory_snapshot.hsm_groups.len()),
+            format_count(inventory_snapshot.nodes.len()),
+            file_path
+        );
+    }
+}
+
+/// Like `snapshot_ops::get_inventory_snapshot`, exits if the inventory can't be fetched
+pub async fn get_inventory_snapshot(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+) -> InventorySnapshot {
+    crate::common::snapshot_ops::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name_opt,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get inventory snapshot: {}. Exit", error);
+        std::process::exit(1);
+    })
+}
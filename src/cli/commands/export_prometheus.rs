@@ -0,0 +1,367 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as FmtWrite,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+use crate::cli::commands::{
+    export_inventory::{self, InventorySnapshot},
+    get_nodes_artifacts::NodeSummary,
+};
+
+/// Exposes hw metrics of each HSM group in Prometheus text format: nodes, accelerators by model,
+/// total memory and nodes in the pool of free resources. Metrics are either served on
+/// `listen_addr_opt` (refreshed every `interval_secs`), written to `textfile_path_opt` for
+/// node_exporter textfile collector or printed to stdout
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    pool_hsm_group_name: &str,
+    listen_addr_opt: Option<&String>,
+    textfile_path_opt: Option<&String>,
+    interval_secs: u64,
+) {
+    let inventory_snapshot = export_inventory::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        None,
+    )
+    .await;
+
+    let metrics = get_prometheus_metrics(&inventory_snapshot, pool_hsm_group_name);
+
+    if let Some(textfile_path) = textfile_path_opt {
+        // Written to a temporary file and renamed so the collector never reads a partial file
+        let textfile_tmp_path = format!("{}.tmp", textfile_path);
+
+        if let Err(error) = std::fs::write(&textfile_tmp_path, &metrics)
+            .and_then(|_| std::fs::rename(&textfile_tmp_path, textfile_path))
+        {
+            eprintln!("Could not write file '{}': {}. Exit", textfile_path, error);
+            std::process::exit(1);
+        }
+
+        println!("Metrics written to '{}'", textfile_path);
+    } else if let Some(listen_addr) = listen_addr_opt {
+        // ':9101' listens on all interfaces
+        let listen_addr = if listen_addr.starts_with(':') {
+            format!("0.0.0.0{}", listen_addr)
+        } else {
+            listen_addr.clone()
+        };
+
+        let listener = TcpListener::bind(&listen_addr)
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not listen on '{}': {}. Exit", listen_addr, error);
+                std::process::exit(1);
+            });
+
+        println!("Serving metrics on http://{}/metrics", listen_addr);
+
+        let metrics_lock = Arc::new(RwLock::new(metrics));
+
+        // Inventory is refreshed in the background so scrapes don't wait for CSM
+        let metrics_lock_aux = Arc::clone(&metrics_lock);
+        let shasta_token_string = shasta_token.to_string();
+        let shasta_base_url_string = shasta_base_url.to_string();
+        let shasta_root_cert_vec = shasta_root_cert.to_vec();
+        let pool_hsm_group_name_string = pool_hsm_group_name.to_string();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+
+                let inventory_snapshot = export_inventory::get_inventory_snapshot(
+                    &shasta_token_string,
+                    &shasta_base_url_string,
+                    &shasta_root_cert_vec,
+                    None,
+                )
+                .await;
+
+                *metrics_lock_aux.write().unwrap() =
+                    get_prometheus_metrics(&inventory_snapshot, &pool_hsm_group_name_string);
+
+                log::info!("Metrics refreshed");
+            }
+        });
+
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    log::warn!("Could not accept connection: {}", error);
+                    continue;
+                }
+            };
+
+            let metrics_lock_aux = Arc::clone(&metrics_lock);
+
+            tokio::spawn(async move {
+                let (reader, mut writer) = stream.split();
+
+                let mut request_line = String::new();
+                let _ = BufReader::new(reader).read_line(&mut request_line).await;
+
+                let response = if request_line.starts_with("GET /metrics") {
+                    let metrics = metrics_lock_aux.read().unwrap().clone();
+
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        metrics.len(),
+                        metrics
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+
+                let _ = writer.write_all(response.as_bytes()).await;
+            });
+        }
+    } else {
+        print!("{}", metrics);
+    }
+}
+
+/// Metrics in Prometheus text exposition format, one sample per HSM group (and accelerator
+/// model). Memory is the sum of the memory DIMMs capacity (MiB in CSM) in bytes
+pub fn get_prometheus_metrics(
+    inventory_snapshot: &InventorySnapshot,
+    pool_hsm_group_name: &str,
+) -> String {
+    let node_summary_hashmap: BTreeMap<&String, &NodeSummary> = inventory_snapshot
+        .nodes
+        .iter()
+        .map(|node_summary| (&node_summary.xname, node_summary))
+        .collect();
+
+    let mut node_count_vec = Vec::new();
+    let mut accelerator_count_vec = Vec::new();
+    let mut memory_bytes_vec = Vec::new();
+
+    for hsm_group_snapshot in &inventory_snapshot.hsm_groups {
+        let hsm_group_node_summary_vec: Vec<&NodeSummary> = hsm_group_snapshot
+            .members
+            .iter()
+            .filter_map(|xname| node_summary_hashmap.get(xname).copied())
+            .collect();
+
+        node_count_vec.push((
+            hsm_group_snapshot.label.clone(),
+            hsm_group_snapshot.members.len(),
+        ));
+
+        let mut accelerator_count_hashmap: BTreeMap<String, usize> = BTreeMap::new();
+
+        for node_accel in hsm_group_node_summary_vec
+            .iter()
+            .flat_map(|node_summary| &node_summary.node_accels)
+        {
+            *accelerator_count_hashmap
+                .entry(node_accel.info.clone().unwrap_or("unknown".to_string()))
+                .or_default() += 1;
+        }
+
+        for (model, count) in accelerator_count_hashmap {
+            accelerator_count_vec.push((hsm_group_snapshot.label.clone(), model, count));
+        }
+
+        let memory_mib: u64 = hsm_group_node_summary_vec
+            .iter()
+            .flat_map(|node_summary| &node_summary.memory)
+            .filter_map(|memory_dimm| {
+                memory_dimm
+                    .info
+                    .as_ref()
+                    .and_then(|info| info.split(' ').next())
+                    .and_then(|memory_capacity| memory_capacity.parse::<u64>().ok())
+            })
+            .sum();
+
+        memory_bytes_vec.push((hsm_group_snapshot.label.clone(), memory_mib * 1024 * 1024));
+    }
+
+    let pool_node_count = node_count_vec
+        .iter()
+        .find(|(hsm_group_name, _)| hsm_group_name.eq(pool_hsm_group_name))
+        .map(|(_, node_count)| *node_count)
+        .unwrap_or(0);
+
+    let mut metrics = String::new();
+
+    writeln!(
+        metrics,
+        "# HELP clstr_hsm_group_nodes Nodes in the HSM group"
+    )
+    .unwrap();
+    writeln!(metrics, "# TYPE clstr_hsm_group_nodes gauge").unwrap();
+    for (hsm_group_name, node_count) in node_count_vec {
+        writeln!(
+            metrics,
+            "clstr_hsm_group_nodes{{hsm_group=\"{}\"}} {}",
+            escape_label_value(&hsm_group_name),
+            node_count
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        metrics,
+        "# HELP clstr_hsm_group_accelerators Accelerators (eg GPUs) in the HSM group by model"
+    )
+    .unwrap();
+    writeln!(metrics, "# TYPE clstr_hsm_group_accelerators gauge").unwrap();
+    for (hsm_group_name, model, count) in accelerator_count_vec {
+        writeln!(
+            metrics,
+            "clstr_hsm_group_accelerators{{hsm_group=\"{}\",model=\"{}\"}} {}",
+            escape_label_value(&hsm_group_name),
+            escape_label_value(&model),
+            count
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        metrics,
+        "# HELP clstr_hsm_group_memory_bytes Total memory of the nodes in the HSM group"
+    )
+    .unwrap();
+    writeln!(metrics, "# TYPE clstr_hsm_group_memory_bytes gauge").unwrap();
+    for (hsm_group_name, memory_bytes) in memory_bytes_vec {
+        writeln!(
+            metrics,
+            "clstr_hsm_group_memory_bytes{{hsm_group=\"{}\"}} {}",
+            escape_label_value(&hsm_group_name),
+            memory_bytes
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        metrics,
+        "# HELP clstr_pool_nodes Nodes in the pool of free resources"
+    )
+    .unwrap();
+    writeln!(metrics, "# TYPE clstr_pool_nodes gauge").unwrap();
+    writeln!(
+        metrics,
+        "clstr_pool_nodes{{pool=\"{}\"}} {}",
+        escape_label_value(pool_hsm_group_name),
+        pool_node_count
+    )
+    .unwrap();
+
+    metrics
+}
+
+fn escape_label_value(label_value: &str) -> String {
+    label_value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::{
+        export_inventory::HsmGroupSnapshot,
+        get_nodes_artifacts::{ArtifactSummary, ArtifactType},
+    };
+
+    fn get_node_summary(
+        xname: &str,
+        accel_model_vec: &[&str],
+        memory_mib_vec: &[u64],
+    ) -> NodeSummary {
+        NodeSummary {
+            xname: xname.to_string(),
+            r#type: "Node".to_string(),
+            processors: Vec::new(),
+            memory: memory_mib_vec
+                .iter()
+                .map(|memory_mib| ArtifactSummary {
+                    xname: format!("{}d0", xname),
+                    r#type: ArtifactType::Memory,
+                    info: Some(format!("{} MiB", memory_mib)),
+                    serial_number: None,
+                    part_number: None,
+                    core_count: None,
+                    max_speed_mhz: None,
+                    gpu_memory: None,
+                })
+                .collect(),
+            node_accels: accel_model_vec
+                .iter()
+                .map(|accel_model| ArtifactSummary {
+                    xname: format!("{}a0", xname),
+                    r#type: ArtifactType::NodeAccel,
+                    info: Some(accel_model.to_string()),
+                    serial_number: None,
+                    part_number: None,
+                    core_count: None,
+                    max_speed_mhz: None,
+                    gpu_memory: None,
+                })
+                .collect(),
+            node_hsn_nics: Vec::new(),
+            state: None,
+            flag: None,
+            enabled: None,
+        }
+    }
+
+    #[test]
+    fn test_get_prometheus_metrics() {
+        let inventory_snapshot = InventorySnapshot {
+            version: 1,
+            timestamp: 0,
+            hsm_groups: vec![
+                HsmGroupSnapshot {
+                    label: "zinal".to_string(),
+                    members: vec!["x1000c0s0b0n0".to_string(), "x1000c0s0b0n1".to_string()],
+                },
+                HsmGroupSnapshot {
+                    label: "nodes_free".to_string(),
+                    members: vec!["x1000c0s1b0n0".to_string()],
+                },
+            ],
+            nodes: vec![
+                get_node_summary(
+                    "x1000c0s0b0n0",
+                    &["NVIDIA A100", "NVIDIA A100"],
+                    &[16384, 16384],
+                ),
+                get_node_summary("x1000c0s0b0n1", &["NVIDIA A100", "AMD MI250X"], &[16384]),
+                get_node_summary("x1000c0s1b0n0", &[], &[1024]),
+            ],
+            failed_nodes: Vec::new(),
+        };
+
+        let metrics = get_prometheus_metrics(&inventory_snapshot, "nodes_free");
+
+        assert!(metrics.contains("clstr_hsm_group_nodes{hsm_group=\"zinal\"} 2\n"));
+        assert!(metrics.contains(
+            "clstr_hsm_group_accelerators{hsm_group=\"zinal\",model=\"NVIDIA A100\"} 3\n"
+        ));
+        assert!(metrics.contains(
+            "clstr_hsm_group_accelerators{hsm_group=\"zinal\",model=\"AMD MI250X\"} 1\n"
+        ));
+        assert!(metrics.contains("clstr_hsm_group_memory_bytes{hsm_group=\"zinal\"} 51539607552\n"));
+        assert!(
+            metrics.contains("clstr_hsm_group_memory_bytes{hsm_group=\"nodes_free\"} 1073741824\n")
+        );
+        assert!(metrics.contains("clstr_pool_nodes{pool=\"nodes_free\"} 1\n"));
+    }
+}
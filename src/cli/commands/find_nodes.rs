@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use crate::{
+    cli::commands::{
+        diff_inventory::calculate_memory_capacity_mib,
+        export_inventory,
+        get_nodes_artifacts::{get_node_status_hashmap, ArtifactSummary, NodeSummary},
+    },
+    common::{
+        filter_ops::{self, FilterValue},
+        hostlist_ops, hw_component_ops, memory_ops, xname,
+    },
+};
+
+/// Fields of a node which can be used in `--where` expressions
+pub const FIELD_NAME_VEC: [&str; 13] = [
+    "xname",
+    "hsm",
+    "state",
+    "cpu",
+    "gpu",
+    "cpu_count",
+    "gpu_count",
+    "dimm_count",
+    "hsn_count",
+    "cores",
+    "memory",
+    "gpumem",
+    "usable",
+];
+
+/// Prints the xnames of the nodes (of a HSM group or all HSM groups) matching a filter expression
+/// like `gpu == 'a100' && memory >= 512GB && hsm != 'zinal'`, as a hostlist expression which can
+/// be passed to `update hsm-group move --xnames` or as JSON. `count` only prints the number of
+/// nodes matching
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    where_expression: &str,
+    output_opt: Option<&String>,
+    count: bool,
+) {
+    let filter_expression =
+        filter_ops::parse_filter_expression(where_expression).unwrap_or_else(|error| {
+            eprintln!(
+                "Expression '{}' not valid: {}. Exit",
+                where_expression, error
+            );
+            std::process::exit(1);
+        });
+
+    let field_vec = filter_expression.get_field_vec();
+
+    if let Some(field) = field_vec
+        .iter()
+        .find(|field| !FIELD_NAME_VEC.contains(field))
+    {
+        eprintln!(
+            "Field '{}' not valid, fields are {}. Exit",
+            field,
+            FIELD_NAME_VEC.join(", ")
+        );
+        std::process::exit(1);
+    }
+
+    let mut inventory_snapshot = export_inventory::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        hsm_group_name_opt,
+    )
+    .await;
+
+    // Node state is only fetched if the expression needs it
+    if field_vec
+        .iter()
+        .any(|field| ["state", "usable"].contains(field))
+    {
+        match get_node_status_hashmap(shasta_token, shasta_base_url, shasta_root_cert).await {
+            Ok(node_status_hashmap) => {
+                for node_summary in inventory_snapshot.nodes.iter_mut() {
+                    if let Some(node_status_value) = node_status_hashmap.get(&node_summary.xname) {
+                        node_summary.set_component_status(node_status_value);
+                    }
+                }
+            }
+            Err(error) => {
+                eprintln!("Could not get node state from HSM: {}. Exit", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // xname -> HSM groups
+    let mut node_hsm_group_hashmap: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for hsm_group_snapshot in &inventory_snapshot.hsm_groups {
+        for member in &hsm_group_snapshot.members {
+            node_hsm_group_hashmap
+                .entry(member)
+                .or_default()
+                .push(&hsm_group_snapshot.label);
+        }
+    }
+
+    let mut xname_vec: Vec<String> = inventory_snapshot
+        .nodes
+        .iter()
+        .filter(|node_summary| {
+            filter_expression.matches(&get_node_field_value_hashmap(
+                node_summary,
+                node_hsm_group_hashmap
+                    .get(node_summary.xname.as_str())
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            ))
+        })
+        .map(|node_summary| node_summary.xname.clone())
+        .collect();
+
+    xname_vec.sort_by(|a, b| xname::compare_xnames(a, b));
+
+    if count {
+        println!("{}", xname_vec.len());
+    } else if output_opt.is_some_and(|output| output.eq("json")) {
+        println!("{}", serde_json::to_string_pretty(&xname_vec).unwrap());
+    } else if xname_vec.is_empty() {
+        eprintln!("No node matches '{}'", where_expression);
+    } else {
+        println!("{}", hostlist_ops::compress_hostlist(&xname_vec));
+    }
+}
+
+/// Values of each field in FIELD_NAME_VEC for a node. Processors and accelerators are the
+/// keywords of their models (eg amd, epyc, nvidia, a100) and memory capacities are in bytes
+pub fn get_node_field_value_hashmap<'a>(
+    node_summary: &NodeSummary,
+    hsm_group_name_vec: &[&str],
+) -> HashMap<&'a str, Vec<FilterValue>> {
+    let number = |value: u64| vec![FilterValue::Number(value)];
+
+    HashMap::from([
+        ("xname", vec![FilterValue::Text(node_summary.xname.clone())]),
+        (
+            "hsm",
+            hsm_group_name_vec
+                .iter()
+                .map(|hsm_group_name| FilterValue::Text(hsm_group_name.to_string()))
+                .collect(),
+        ),
+        (
+            "state",
+            node_summary
+                .state
+                .iter()
+                .map(|state| FilterValue::Text(state.clone()))
+                .collect(),
+        ),
+        ("cpu", get_hw_component_value_vec(&node_summary.processors)),
+        ("gpu", get_hw_component_value_vec(&node_summary.node_accels)),
+        ("cpu_count", number(node_summary.processors.len() as u64)),
+        ("gpu_count", number(node_summary.node_accels.len() as u64)),
+        ("dimm_count", number(node_summary.memory.len() as u64)),
+        ("hsn_count", number(node_summary.node_hsn_nics.len() as u64)),
+        (
+            "cores",
+            number(
+                node_summary
+                    .processors
+                    .iter()
+                    .filter_map(|processor| processor.core_count)
+                    .sum(),
+            ),
+        ),
+        (
+            "memory",
+            number(calculate_memory_capacity_mib(node_summary) * memory_ops::MIB),
+        ),
+        (
+            "gpumem",
+            number(
+                node_summary
+                    .node_accels
+                    .iter()
+                    .filter_map(|node_accel| node_accel.gpu_memory)
+                    .sum(),
+            ),
+        ),
+        (
+            "usable",
+            vec![FilterValue::Text(node_summary.is_usable().to_string())],
+        ),
+    ])
+}
+
+/// Keywords of the models of processors or accelerators (eg nvidia, a100), without duplicates
+fn get_hw_component_value_vec(artifact_summary_vec: &[ArtifactSummary]) -> Vec<FilterValue> {
+    let mut hw_component_vec: Vec<String> = artifact_summary_vec
+        .iter()
+        .filter_map(|artifact_summary| artifact_summary.info.as_deref())
+        .flat_map(hw_component_ops::get_model_keyword_vec)
+        .collect();
+
+    hw_component_vec.sort();
+    hw_component_vec.dedup();
+
+    hw_component_vec
+        .into_iter()
+        .map(FilterValue::Text)
+        .collect()
+}
@@ -1,30 +1,54 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
     time::Instant,
 };
 
-use comfy_table::Color;
+use chrono::{DateTime, Utc};
+use comfy_table::{Cell, Color};
+use indicatif::{ProgressBar, ProgressStyle};
 use tokio::sync::Semaphore;
 
-use crate::cli::commands::get_nodes_artifacts::NodeSummary;
+use crate::{
+    cli::commands::{
+        diff_inventory::calculate_memory_capacity_mib,
+        export_assets,
+        get_nodes_artifacts::{self, ArtifactSummary, NodeSummary},
+    },
+    cli::common::{firmware_ops, history_ops, power_ops},
+    common::{format_ops::format_count, memory_ops, report_ops, table_ops, xname},
+    shasta::{hsm, ShastaClient},
+};
 
+/// Prints the hw artifacts of the members of a HSM group or, if `as_of_opt` is set, of the members
+/// it had then according to the history
 pub async fn exec(
     shasta_token: &str,
     shasta_base_url: &str,
     shasta_root_cert: &[u8],
     hsm_group_name: &str,
+    as_of_opt: Option<&DateTime<Utc>>,
     output_opt: Option<&String>,
+    field_vec_opt: Option<&Vec<String>>,
+    with_firmware: bool,
+    no_stream: bool,
+    count: bool,
 ) {
     // Target HSM group
-    let hsm_group_value = mesa::hsm::group::shasta::http_client::get(
-        shasta_token,
-        shasta_base_url,
-        shasta_root_cert,
-        Some(&hsm_group_name.to_string()),
+    let hsm_group_value = history_ops::rewind_hsm_group_value_vec_or_exit(
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(&hsm_group_name.to_string()),
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        }),
+        as_of_opt,
     )
-    .await
-    .unwrap()
     .first()
     .unwrap()
     .clone();
@@ -39,6 +63,35 @@ pub async fn exec(
     let hsm_group_target_members =
         mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(&hsm_group_value);
 
+    exec_member_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &format!("HSM group '{}'", hsm_group_name),
+        hsm_group_target_members,
+        output_opt,
+        field_vec_opt,
+        with_firmware,
+        no_stream,
+        count,
+    )
+    .await;
+}
+
+/// Same as `exec` for any set of nodes (eg the members of an HSM partition), `scope` describes
+/// them in the output eg "partition 'p1'". `count` only prints the number of hw components
+pub async fn exec_member_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    scope: &str,
+    hsm_group_target_members: Vec<String>,
+    output_opt: Option<&String>,
+    field_vec_opt: Option<&Vec<String>>,
+    with_firmware: bool,
+    no_stream: bool,
+    count: bool,
+) {
     let mut hsm_summary = Vec::new();
 
     let start_total = Instant::now();
@@ -57,62 +110,238 @@ pub async fn exec(
         node_summary_vec.push(node_summary);
     } */
 
+    let json_output = output_opt.is_some() && output_opt.unwrap().eq("json");
+
+    // Markdown and HTML reports are printed once all nodes are fetched
+    let report_format_opt =
+        output_opt.filter(|output| report_ops::REPORT_FORMAT_NAME_VEC.contains(&output.as_str()));
+
+    // Hw components with the fields selected, one row each, are printed once all nodes are fetched
+    let asset_output = report_format_opt.is_none()
+        && (field_vec_opt.is_some() || output_opt.is_some_and(|output| output.eq("csv")));
+
+    // Table output prints each node as soon as its hw inventory arrives unless --no-stream
+    let stream = output_opt.is_none() && !no_stream && !asset_output && !count;
+
+    // State, flag and enabled of all nodes in a single call
+    let node_status_hashmap = get_nodes_artifacts::get_node_status_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        log::warn!("Could not get node state from HSM: {}", error);
+        HashMap::new()
+    });
+
+    let power_state_hashmap = if json_output || count {
+        HashMap::new()
+    } else {
+        power_ops::get_power_state_hashmap_or_empty(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &hsm_group_target_members,
+        )
+        .await
+    };
+
+    let progress_bar = if stream {
+        let progress_bar = ProgressBar::new(hsm_group_target_members.len() as u64);
+
+        progress_bar.set_style(
+            ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} nodes")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        progress_bar.set_message("Fetching hw inventory");
+
+        progress_bar
+    } else {
+        ProgressBar::hidden()
+    };
+
     let mut tasks = tokio::task::JoinSet::new();
 
-    let sem = Arc::new(Semaphore::new(5)); // CSM 1.3.1 higher number of concurrent tasks won't
-                                           // make it faster
+    let sem = Arc::new(Semaphore::new(
+        crate::common::csm_version_ops::get_max_concurrent_requests(),
+    ));
 
     // Get HW inventory details for target HSM group
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)
+        .unwrap_or_else(|error| {
+            eprintln!("Could not build http client for CSM API: {}. Exit", error);
+            std::process::exit(1);
+        });
+
     for hsm_member in hsm_group_target_members.clone() {
-        let shasta_token_string = shasta_token.to_string(); // TODO: make it static
-        let shasta_base_url_string = shasta_base_url.to_string(); // TODO: make it static
-        let shasta_root_cert_vec = shasta_root_cert.to_vec();
-        let hsm_member_string = hsm_member.to_string(); // TODO: make it static
-                                                        //
+        let shasta_client = Arc::clone(&shasta_client);
+        let hsm_member_string = hsm_member.to_string();
         let permit = Arc::clone(&sem).acquire_owned().await;
 
         log::info!("Getting HW inventory details for node '{}'", hsm_member);
         tasks.spawn(async move {
             let _permit = permit; // Wait semaphore to allow new tasks https://github.com/tokio-rs/tokio/discussions/2648#discussioncomment-34885
-            mesa::hsm::hw_inventory::shasta::http_client::get_hw_inventory(
-                &shasta_token_string,
-                &shasta_base_url_string,
-                &shasta_root_cert_vec,
-                &hsm_member_string,
-            )
-            .await
-            .unwrap()
+            hsm::http_client::get_hw_inventory_query(&shasta_client, &hsm_member_string, "Node")
+                .await
+                .unwrap()
         });
     }
 
     while let Some(message) = tasks.join_next().await {
         if let Ok(mut node_hw_inventory) = message {
             node_hw_inventory = node_hw_inventory.pointer("/Nodes/0").unwrap().clone();
-            let node_summary = NodeSummary::from_csm_value(node_hw_inventory.clone());
+            let mut node_summary = NodeSummary::from_csm_value(node_hw_inventory.clone());
+
+            if let Some(node_status_value) = node_status_hashmap.get(&node_summary.xname) {
+                node_summary.set_component_status(node_status_value);
+            }
+
+            if stream {
+                progress_bar.println(get_node_stream_line(
+                    &node_summary,
+                    power_state_hashmap.get(&node_summary.xname),
+                ));
+            }
+
             hsm_summary.push(node_summary);
         } else {
             log::error!("Failed procesing/fetching node hw information");
         }
+
+        progress_bar.inc(1);
     }
 
+    progress_bar.finish_and_clear();
+
     let duration = start_total.elapsed();
 
     log::info!(
-        "Time elapsed in http calls to get hw inventory for HSM '{}' is: {:?}",
-        hsm_group_name,
+        "Time elapsed in http calls to get hw inventory for {} is: {:?}",
+        scope,
         duration
     );
 
-    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+    if count {
+        println!(
+            "{}",
+            hsm_summary
+                .iter()
+                .map(|node_summary| {
+                    node_summary.processors.len()
+                        + node_summary.memory.len()
+                        + node_summary.node_accels.len()
+                        + node_summary.node_hsn_nics.len()
+                })
+                .sum::<usize>()
+        );
+
+        return;
+    }
+
+    if let Some(report_format) = report_format_opt {
+        let firmware_version_vec = if with_firmware {
+            firmware_ops::get_firmware_version_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                &hsm_group_target_members,
+            )
+            .await
+            .unwrap_or_else(|error| {
+                log::error!("Could not get firmware versions from FAS: {}", error);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
+        print!(
+            "{}",
+            report_ops::get_hsm_artifacts_report(
+                scope,
+                &hsm_summary,
+                &power_state_hashmap,
+                &firmware_version_vec
+            )
+            .render(report_format)
+        );
+
+        return;
+    }
+
+    if asset_output {
+        export_assets::print_asset_record_vec(&hsm_summary, field_vec_opt, output_opt);
+    } else if json_output {
         for node_summary in &hsm_summary {
             println!("{}", serde_json::to_string_pretty(&node_summary).unwrap());
         }
+    } else if stream {
+        println!("{} nodes in {}", format_count(hsm_summary.len()), scope);
+        print_summary_table(&hsm_summary);
     } else {
-        print_table(&hsm_summary);
+        print_table(&hsm_summary, &power_state_hashmap);
+        print_summary_table(&hsm_summary);
+    }
+
+    if with_firmware {
+        firmware_ops::print_firmware_version_vec_after_artifacts(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &hsm_group_target_members,
+            output_opt,
+        )
+        .await;
     }
 }
 
-pub fn print_table(node_summary_vec: &Vec<NodeSummary>) {
+/// Line printed for a node in streaming mode, eg
+/// `x1000c0s0b0n0  on   Ready/OK  AMD EPYC 7742 (2), 32768 MiB (16)`
+pub fn get_node_stream_line(
+    node_summary: &NodeSummary,
+    power_state_opt: Option<&String>,
+) -> String {
+    let mut hw_component_count_hashmap: BTreeMap<String, usize> = BTreeMap::new();
+
+    for artifact_summary in node_summary
+        .processors
+        .iter()
+        .chain(node_summary.node_accels.iter())
+        .chain(node_summary.memory.iter())
+        .chain(node_summary.node_hsn_nics.iter())
+    {
+        *hw_component_count_hashmap
+            .entry(artifact_summary.info.clone().unwrap_or("ERROR".to_string()))
+            .or_insert(0) += 1;
+    }
+
+    format!(
+        "{:<16} {:<4} {:<18} {}",
+        node_summary.xname,
+        power_state_opt.map_or("n/a", |power_state| power_state),
+        node_summary.get_component_status_cell().content(),
+        hw_component_count_hashmap
+            .iter()
+            .map(|(hw_component, qty)| {
+                format!(
+                    "{} ({})",
+                    hw_component,
+                    memory_ops::format_hw_component_qty(hw_component, *qty)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// `power_state_hashmap` is xname -> power state, a 'Power' column is added if not empty. A
+/// 'State' column is added if nodes have HSM state
+pub fn print_table(
+    node_summary_vec: &Vec<NodeSummary>,
+    power_state_hashmap: &HashMap<String, String>,
+) {
     let mut hsm_node_hw_component_count_hashmap_vec: Vec<(String, HashMap<String, usize>)> = vec![];
 
     let mut processor_set: HashSet<String> = HashSet::new();
@@ -209,23 +438,151 @@ pub fn print_table(node_summary_vec: &Vec<NodeSummary>) {
         .concat(),
     );
 
-    hsm_node_hw_component_count_hashmap_vec.sort_by(|a, b| a.0.cmp(&b.0));
+    hsm_node_hw_component_count_hashmap_vec.sort_by(|a, b| xname::compare_xnames(&a.0, &b.0));
+
+    let mut node_column_header_vec: Vec<String> = Vec::new();
+    let mut node_column_cell_hashmap: HashMap<String, Vec<Cell>> = HashMap::new();
 
-    print_table_f32_score(&headers, &hsm_node_hw_component_count_hashmap_vec);
+    if !power_state_hashmap.is_empty() {
+        node_column_header_vec.push("Power".to_string());
+
+        for node_summary in node_summary_vec {
+            node_column_cell_hashmap
+                .entry(node_summary.xname.clone())
+                .or_default()
+                .push(power_ops::get_power_state_cell(
+                    power_state_hashmap.get(&node_summary.xname),
+                ));
+        }
+    }
+
+    if node_summary_vec
+        .iter()
+        .any(|node_summary| node_summary.state.is_some())
+    {
+        node_column_header_vec.push("State".to_string());
+
+        for node_summary in node_summary_vec {
+            node_column_cell_hashmap
+                .entry(node_summary.xname.clone())
+                .or_default()
+                .push(node_summary.get_component_status_cell());
+        }
+    }
+
+    print_table_f32_score_with_node_columns(
+        &headers,
+        &hsm_node_hw_component_count_hashmap_vec,
+        &node_column_header_vec,
+        &node_column_cell_hashmap,
+    );
 }
 
-pub fn calculate_hsm_total_number_hw_components(
-    target_hsm_hw_component_count_vec: &[(String, HashMap<String, usize>)],
-) -> usize {
-    target_hsm_hw_component_count_vec
+/// Footer of the nodes table: number of nodes, CPUs and GPUs by model and memory, with their
+/// total and min/max per node (nodes without a model count as 0)
+pub fn print_summary_table(node_summary_vec: &[NodeSummary]) {
+    if node_summary_vec.is_empty() {
+        return;
+    }
+
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec![
+        "Type",
+        "Hw component",
+        "Total",
+        "Min per node",
+        "Max per node",
+    ]);
+
+    table.add_row(vec![
+        Cell::new("Nodes"),
+        Cell::new(""),
+        Cell::new(format_count(node_summary_vec.len())),
+        Cell::new(""),
+        Cell::new(""),
+    ]);
+
+    for (hw_component_type, model_count_vec_hashmap) in [
+        (
+            "CPU",
+            get_model_count_vec_hashmap(node_summary_vec, |node_summary| &node_summary.processors),
+        ),
+        (
+            "GPU",
+            get_model_count_vec_hashmap(node_summary_vec, |node_summary| &node_summary.node_accels),
+        ),
+    ] {
+        for (model, count_vec) in model_count_vec_hashmap {
+            table.add_row(vec![
+                Cell::new(hw_component_type),
+                Cell::new(model),
+                Cell::new(format_count(count_vec.iter().sum::<usize>())),
+                Cell::new(format_count(*count_vec.iter().min().unwrap())),
+                Cell::new(format_count(*count_vec.iter().max().unwrap())),
+            ]);
+        }
+    }
+
+    let memory_capacity_vec: Vec<u64> = node_summary_vec
         .iter()
-        .flat_map(|(_node, hw_component_hashmap)| hw_component_hashmap.values())
-        .sum()
+        .map(|node_summary| calculate_memory_capacity_mib(node_summary) * 1024 * 1024)
+        .collect();
+
+    table.add_row(vec![
+        Cell::new("Memory"),
+        Cell::new(""),
+        Cell::new(memory_ops::format_memory_size(
+            memory_capacity_vec.iter().sum(),
+        )),
+        Cell::new(memory_ops::format_memory_size(
+            *memory_capacity_vec.iter().min().unwrap(),
+        )),
+        Cell::new(memory_ops::format_memory_size(
+            *memory_capacity_vec.iter().max().unwrap(),
+        )),
+    ]);
+
+    println!("{table}");
+}
+
+/// Model -> number of hw components of that model in each node
+fn get_model_count_vec_hashmap(
+    node_summary_vec: &[NodeSummary],
+    get_artifact_summary_vec: fn(&NodeSummary) -> &Vec<ArtifactSummary>,
+) -> BTreeMap<String, Vec<usize>> {
+    let mut model_count_vec_hashmap: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+
+    for (node_index, node_summary) in node_summary_vec.iter().enumerate() {
+        for artifact_summary in get_artifact_summary_vec(node_summary) {
+            model_count_vec_hashmap
+                .entry(artifact_summary.info.clone().unwrap_or("ERROR".to_string()))
+                .or_insert_with(|| vec![0; node_summary_vec.len()])[node_index] += 1;
+        }
+    }
+
+    model_count_vec_hashmap
 }
 
 pub fn print_table_f32_score(
     user_defined_hw_componet_vec: &[String],
     hsm_node_hw_pattern_vec: &[(String, HashMap<String, usize>)],
+) {
+    print_table_f32_score_with_node_columns(
+        user_defined_hw_componet_vec,
+        hsm_node_hw_pattern_vec,
+        &[],
+        &HashMap::new(),
+    )
+}
+
+/// Same as `print_table_f32_score` with extra columns after the node (eg power state).
+/// `node_column_cell_hashmap` is xname -> one cell per header in `node_column_header_vec`
+pub fn print_table_f32_score_with_node_columns(
+    user_defined_hw_componet_vec: &[String],
+    hsm_node_hw_pattern_vec: &[(String, HashMap<String, usize>)],
+    node_column_header_vec: &[String],
+    node_column_cell_hashmap: &HashMap<String, Vec<Cell>>,
 ) {
     /* println!("DEBUG - hsm_hw_pattern_vec:\n{:?}", hsm_hw_pattern_vec);
     println!(
@@ -246,9 +603,18 @@ pub fn print_table_f32_score(
 
     // println!("DEBUG - all_hw_component_vec : {:?}", all_hw_component_vec);
 
-    let mut table = comfy_table::Table::new();
+    let mut table = table_ops::new_table();
+
+    let header_vec = [
+        vec!["Node".to_string()],
+        node_column_header_vec.to_vec(),
+        all_hw_component_vec.clone(),
+    ]
+    .concat();
 
-    table.set_header([vec!["Node".to_string()], all_hw_component_vec.clone()].concat());
+    table.set_header(header_vec.clone());
+
+    let mut row_vec: Vec<Vec<Cell>> = Vec::new();
 
     for (xname, node_pattern_hashmap) in hsm_node_hw_pattern_vec {
         // println!("node_pattern_hashmap: {:?}", node_pattern_hashmap);
@@ -258,6 +624,14 @@ pub fn print_table_f32_score(
         row.push(
             comfy_table::Cell::new(xname.clone()).set_alignment(comfy_table::CellAlignment::Center),
         );
+        // Node extra table cells
+        if !node_column_header_vec.is_empty() {
+            row.extend(node_column_cell_hashmap.get(xname).cloned().unwrap_or(vec![
+                    Cell::new("n/a");
+                    node_column_header_vec
+                        .len()
+                ]));
+        }
         // User hw components table cell
         for hw_component in &all_hw_component_vec {
             if hw_component.to_uppercase().contains("ERROR")
@@ -267,18 +641,24 @@ pub fn print_table_f32_score(
             {
                 let counter = node_pattern_hashmap.get(hw_component).unwrap();
                 row.push(
-                    comfy_table::Cell::new(format!("⚠️  ({})", counter))
-                        .fg(Color::Yellow)
-                        .set_alignment(comfy_table::CellAlignment::Center),
+                    comfy_table::Cell::new(format!(
+                        "⚠️  ({})",
+                        memory_ops::format_hw_component_qty(hw_component, *counter)
+                    ))
+                    .fg(Color::Yellow)
+                    .set_alignment(comfy_table::CellAlignment::Center),
                 );
             } else if user_defined_hw_componet_vec.contains(hw_component)
                 && node_pattern_hashmap.contains_key(hw_component)
             {
                 let counter = node_pattern_hashmap.get(hw_component).unwrap();
                 row.push(
-                    comfy_table::Cell::new(format!("✅ ({})", counter,))
-                        .fg(Color::Green)
-                        .set_alignment(comfy_table::CellAlignment::Center),
+                    comfy_table::Cell::new(format!(
+                        "✅ ({})",
+                        memory_ops::format_hw_component_qty(hw_component, *counter)
+                    ))
+                    .fg(Color::Green)
+                    .set_alignment(comfy_table::CellAlignment::Center),
                 );
             } else {
                 // node does not contain hardware but it was requested by the user
@@ -292,7 +672,7 @@ pub fn print_table_f32_score(
             if node_pattern_hashmap.contains_key(user_defined_hw_component) {
                 let counter = node_pattern_hashmap.get(user_defined_hw_component).unwrap();
                 row.push(
-                    comfy_table::Cell::new(format!("✅ ({})", counter,))
+                    comfy_table::Cell::new(format!("✅ ({})", format_count(counter)))
                         .fg(Color::Green)
                         .set_alignment(comfy_table::CellAlignment::Center),
                 );
@@ -303,8 +683,11 @@ pub fn print_table_f32_score(
                 );
             }
         } */
-        table.add_row(row);
+        row_vec.push(row);
     }
 
+    table_ops::sort_row_vec(&header_vec, &mut row_vec);
+    table.add_rows(row_vec);
+
     println!("{table}\n");
 }
@@ -0,0 +1,355 @@
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use chrono::{DateTime, Utc};
+use comfy_table::{Cell, CellAlignment, Color};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::utils::{
+        calculate_node_hw_component_count, calculate_xname_distance,
+        get_hsm_hw_component_count_filtered_by_user_request, get_node_hw_component_count,
+        parse_pattern_with_qualifiers_or_exit, QuantityQualifier,
+    },
+    cli::common::history_ops,
+    common::{
+        format_ops::{format_count, format_score},
+        table_ops,
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HsmGroupHealth {
+    pub hsm_group_name: String,
+    pub member_count: usize,
+    pub ready_count: usize,
+    pub off_count: usize,
+    pub unhealthy_count: usize,
+    pub locality_score: f32,
+    pub pattern_compliant: Option<bool>, // None means no pattern declared for this HSM group
+    pub badge: String,
+}
+
+impl HsmGroupHealth {
+    /// Combines health counters, locality score and pattern compliance in a single badge:
+    /// red if pattern is not fulfilled or less than 90% of nodes are ready, yellow if some nodes
+    /// are not ready or nodes are spread across racks (locality score below 50), green otherwise
+    pub fn calculate_badge(&self) -> String {
+        let ready_ratio = if self.member_count == 0 {
+            1f32
+        } else {
+            self.ready_count as f32 / self.member_count as f32
+        };
+
+        if self.pattern_compliant == Some(false) || ready_ratio < 0.9 {
+            "🔴".to_string()
+        } else if self.ready_count < self.member_count || self.locality_score < 50f32 {
+            "🟡".to_string()
+        } else {
+            "🟢".to_string()
+        }
+    }
+}
+
+/// Lists HSM groups with a composite health badge combining node health (HSM state components),
+/// locality score (average distance between members, 100 means all nodes in the same blade) and
+/// compliance against the patterns declared by the user. If `as_of_opt` is set, HSM groups have the
+/// members they had then according to the history
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    pattern_vec_opt: Option<Vec<&String>>,
+    as_of_opt: Option<&DateTime<Utc>>,
+    output_opt: Option<&String>,
+) {
+    let hsm_group_vec = if hsm_group_name_opt.is_some() {
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            hsm_group_name_opt,
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        })
+    } else {
+        crate::common::tenant_ops::filter_available_hsm_group_value_vec(
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            }),
+        )
+    };
+
+    let hsm_group_vec = history_ops::rewind_hsm_group_value_vec_or_exit(hsm_group_vec, as_of_opt);
+
+    // Get state of all nodes in a single call
+    let node_status_value = crate::shasta::hsm::http_client::get_all_components_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "Node",
+    )
+    .await
+    .unwrap();
+
+    let node_status_hashmap: HashMap<String, Value> = node_status_value["Components"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .map(|node_status| {
+            (
+                node_status["ID"].as_str().unwrap().to_string(),
+                node_status.clone(),
+            )
+        })
+        .collect();
+
+    // Patterns declared by the user
+    let pattern_hashmap: HashMap<String, HashMap<String, (usize, QuantityQualifier)>> =
+        pattern_vec_opt
+            .unwrap_or_default()
+            .into_iter()
+            .map(|pattern| parse_pattern_with_qualifiers_or_exit(pattern))
+            .collect();
+
+    let mut hsm_group_health_vec = Vec::new();
+
+    for hsm_group_value in &hsm_group_vec {
+        let hsm_group_name = hsm_group_value["label"].as_str().unwrap().to_string();
+
+        let hsm_group_member_vec =
+            mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(hsm_group_value);
+
+        let mut ready_count = 0;
+        let mut off_count = 0;
+        let mut unhealthy_count = 0;
+
+        for member in &hsm_group_member_vec {
+            let node_status_opt = node_status_hashmap.get(member);
+
+            let state = node_status_opt
+                .and_then(|node_status| node_status["State"].as_str())
+                .unwrap_or("Unknown");
+            let flag = node_status_opt
+                .and_then(|node_status| node_status["Flag"].as_str())
+                .unwrap_or("Unknown");
+            let enabled = node_status_opt
+                .and_then(|node_status| node_status["Enabled"].as_bool())
+                .unwrap_or(true);
+
+            if state.eq("Ready") && flag.eq("OK") && enabled {
+                ready_count += 1;
+            } else if state.eq("Off") {
+                off_count += 1;
+            } else {
+                unhealthy_count += 1;
+            }
+        }
+
+        let pattern_compliant = match pattern_hashmap.get(&hsm_group_name.to_lowercase()) {
+            Some(hw_component_qualified_count_hashmap) => Some(
+                is_hsm_group_compliant_with_pattern(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    &hsm_group_member_vec,
+                    hw_component_qualified_count_hashmap,
+                )
+                .await,
+            ),
+            None => None,
+        };
+
+        let mut hsm_group_health = HsmGroupHealth {
+            hsm_group_name,
+            member_count: hsm_group_member_vec.len(),
+            ready_count,
+            off_count,
+            unhealthy_count,
+            locality_score: calculate_locality_score(&hsm_group_member_vec),
+            pattern_compliant,
+            badge: String::new(),
+        };
+
+        hsm_group_health.badge = hsm_group_health.calculate_badge();
+
+        hsm_group_health_vec.push(hsm_group_health);
+    }
+
+    hsm_group_health_vec.sort_by(|a, b| a.hsm_group_name.cmp(&b.hsm_group_name));
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hsm_group_health_vec).unwrap()
+        );
+    } else {
+        print_table(&hsm_group_health_vec);
+    }
+}
+
+/// Locality score of a list of nodes. 100 means all nodes are in the same blade while 0 means all
+/// nodes are in different racks
+pub fn calculate_locality_score(xname_vec: &[String]) -> f32 {
+    if xname_vec.len() < 2 {
+        return 100f32;
+    }
+
+    let mut distance_total = 0;
+    let mut pair_count = 0;
+
+    for (index, xname_1) in xname_vec.iter().enumerate() {
+        for xname_2 in &xname_vec[index + 1..] {
+            distance_total += calculate_xname_distance(xname_1, xname_2);
+            pair_count += 1;
+        }
+    }
+
+    100f32 * (1f32 - (distance_total as f32 / pair_count as f32) / 4f32)
+}
+
+/// Checks if the hw components in a list of nodes honor the hw component counters and qualifiers
+/// in a pattern
+pub async fn is_hsm_group_compliant_with_pattern(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_member_vec: &[String],
+    hw_component_qualified_count_hashmap: &HashMap<String, (usize, QuantityQualifier)>,
+) -> bool {
+    let user_defined_hw_component_vec: Vec<String> = hw_component_qualified_count_hashmap
+        .keys()
+        .cloned()
+        .collect();
+
+    let start = Instant::now();
+
+    let mut tasks = tokio::task::JoinSet::new();
+
+    let sem = Arc::new(Semaphore::new(
+        crate::common::csm_version_ops::get_max_concurrent_requests(),
+    ));
+
+    let shasta_client =
+        crate::shasta::ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)
+            .unwrap_or_else(|error| {
+                eprintln!("Could not build http client for CSM API: {}. Exit", error);
+                std::process::exit(1);
+            });
+
+    for hsm_member in hsm_group_member_vec {
+        let shasta_client = Arc::clone(&shasta_client);
+        let hsm_member_string = hsm_member.to_string();
+        let user_defined_hw_component_vec = user_defined_hw_component_vec.clone();
+
+        let permit = Arc::clone(&sem).acquire_owned().await;
+
+        tasks.spawn(async move {
+            let _permit = permit; // Wait semaphore to allow new tasks https://github.com/tokio-rs/tokio/discussions/2648#discussioncomment-34885
+            get_node_hw_component_count(
+                &shasta_client,
+                &hsm_member_string,
+                user_defined_hw_component_vec,
+            )
+            .await
+        });
+    }
+
+    let mut hsm_node_hw_component_count_vec = Vec::new();
+
+    while let Some(message) = tasks.join_next().await {
+        if let Ok(node_hw_component_vec_tuple) = message {
+            hsm_node_hw_component_count_vec.push((
+                node_hw_component_vec_tuple.0,
+                calculate_node_hw_component_count(
+                    node_hw_component_vec_tuple.1,
+                    &node_hw_component_vec_tuple.2,
+                    &node_hw_component_vec_tuple.3,
+                ),
+            ));
+        } else {
+            log::error!("Failed procesing/fetching node hw information");
+        }
+    }
+
+    log::info!(
+        "Time elapsed to calculate hw components for pattern compliance is: {:?}",
+        start.elapsed()
+    );
+
+    let hsm_hw_component_count_filtered_by_user_request_hashmap =
+        get_hsm_hw_component_count_filtered_by_user_request(
+            &user_defined_hw_component_vec,
+            &hsm_node_hw_component_count_vec,
+        );
+
+    hw_component_qualified_count_hashmap
+        .iter()
+        .all(|(hw_component, (qty_requested, qualifier))| {
+            qualifier.is_satisfied(
+                *qty_requested,
+                *hsm_hw_component_count_filtered_by_user_request_hashmap
+                    .get(hw_component)
+                    .unwrap_or(&0),
+            )
+        })
+}
+
+pub fn print_table(hsm_group_health_vec: &Vec<HsmGroupHealth>) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec![
+        "HSM Group",
+        "Members",
+        "Ready",
+        "Off",
+        "Unhealthy",
+        "Locality",
+        "Pattern",
+        "Badge",
+    ]);
+
+    for hsm_group_health in hsm_group_health_vec {
+        let pattern_cell = match hsm_group_health.pattern_compliant {
+            Some(true) => Cell::new("✅").fg(Color::Green),
+            Some(false) => Cell::new("❌").fg(Color::Red),
+            None => Cell::new("-"),
+        };
+
+        table.add_row(vec![
+            Cell::new(hsm_group_health.hsm_group_name.clone()),
+            Cell::new(format_count(hsm_group_health.member_count))
+                .set_alignment(CellAlignment::Center),
+            Cell::new(format_count(hsm_group_health.ready_count))
+                .fg(Color::Green)
+                .set_alignment(CellAlignment::Center),
+            Cell::new(format_count(hsm_group_health.off_count))
+                .set_alignment(CellAlignment::Center),
+            Cell::new(format_count(hsm_group_health.unhealthy_count))
+                .fg(if hsm_group_health.unhealthy_count > 0 {
+                    Color::Red
+                } else {
+                    Color::Reset
+                })
+                .set_alignment(CellAlignment::Center),
+            Cell::new(format_score(hsm_group_health.locality_score))
+                .set_alignment(CellAlignment::Center),
+            pattern_cell.set_alignment(CellAlignment::Center),
+            Cell::new(hsm_group_health.badge.clone()).set_alignment(CellAlignment::Center),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::common::{format_ops::format_count, table_ops, xname};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HsmOverlapReport {
+    /// Nodes belonging to more than one HSM group
+    pub overlapping: Vec<OverlappingNode>,
+    /// Nodes in HSM components which do not belong to any HSM group
+    pub orphans: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OverlappingNode {
+    pub xname: String,
+    pub hsm_groups: Vec<String>,
+}
+
+/// Lists nodes belonging to more than one HSM group and nodes in HSM components which don't
+/// belong to any HSM group. Overlapping membership breaks the target/parent HSM group accounting
+/// in `apply hsm-group`. HSM groups in `ignore_hsm_group_vec` (eg groups containing all nodes in
+/// the system) are not taken into account
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    ignore_hsm_group_vec: &[&String],
+    output_opt: Option<&String>,
+) {
+    let hsm_group_vec = crate::common::tenant_ops::filter_available_hsm_group_value_vec(
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        }),
+    );
+
+    let hsm_group_member_vec: Vec<(String, Vec<String>)> = hsm_group_vec
+        .iter()
+        .map(|hsm_group_value| {
+            (
+                hsm_group_value["label"].as_str().unwrap().to_string(),
+                mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                    hsm_group_value,
+                ),
+            )
+        })
+        .filter(|(hsm_group_name, _)| !ignore_hsm_group_vec.contains(&hsm_group_name))
+        .collect();
+
+    let node_status_value = crate::shasta::hsm::http_client::get_all_components_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "Node",
+    )
+    .await
+    .unwrap();
+
+    let node_vec: Vec<String> = node_status_value["Components"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter_map(|node_status| node_status["ID"].as_str())
+        .map(|xname| xname.to_string())
+        .collect();
+
+    let hsm_overlap_report = calculate_hsm_overlap_report(&hsm_group_member_vec, &node_vec);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hsm_overlap_report).unwrap()
+        );
+    } else {
+        print_table(&hsm_overlap_report);
+
+        println!(
+            "{} nodes in more than one HSM group, {} nodes in no HSM group",
+            format_count(hsm_overlap_report.overlapping.len()),
+            format_count(hsm_overlap_report.orphans.len())
+        );
+    }
+}
+
+pub fn calculate_hsm_overlap_report(
+    hsm_group_member_vec: &[(String, Vec<String>)],
+    node_vec: &[String],
+) -> HsmOverlapReport {
+    // xname -> HSM groups
+    let mut node_hsm_group_hashmap: BTreeMap<&String, Vec<String>> = BTreeMap::new();
+
+    for (hsm_group_name, member_vec) in hsm_group_member_vec {
+        for member in member_vec {
+            node_hsm_group_hashmap
+                .entry(member)
+                .or_default()
+                .push(hsm_group_name.clone());
+        }
+    }
+
+    let mut overlapping: Vec<OverlappingNode> = node_hsm_group_hashmap
+        .iter()
+        .filter(|(_, hsm_group_vec)| hsm_group_vec.len() > 1)
+        .map(|(xname, hsm_group_vec)| {
+            let mut hsm_group_vec = hsm_group_vec.clone();
+            hsm_group_vec.sort();
+
+            OverlappingNode {
+                xname: xname.to_string(),
+                hsm_groups: hsm_group_vec,
+            }
+        })
+        .collect();
+
+    let mut orphans: Vec<String> = node_vec
+        .iter()
+        .filter(|xname| !node_hsm_group_hashmap.contains_key(xname))
+        .cloned()
+        .collect();
+    orphans.sort_by(|xname_1, xname_2| xname::compare_xnames(xname_1, xname_2));
+
+    overlapping.sort_by(|a, b| xname::compare_xnames(&a.xname, &b.xname));
+
+    HsmOverlapReport {
+        overlapping,
+        orphans,
+    }
+}
+
+pub fn print_table(hsm_overlap_report: &HsmOverlapReport) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Node", "Issue", "HSM groups"]);
+
+    for overlapping_node in &hsm_overlap_report.overlapping {
+        table.add_row(vec![
+            Cell::new(&overlapping_node.xname),
+            Cell::new("overlap").fg(Color::Yellow),
+            Cell::new(overlapping_node.hsm_groups.join(", ")),
+        ]);
+    }
+
+    for xname in &hsm_overlap_report.orphans {
+        table.add_row(vec![
+            Cell::new(xname),
+            Cell::new("orphan").fg(Color::Red),
+            Cell::new(""),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_hsm_overlap_report() {
+        let hsm_group_member_vec = vec![
+            (
+                "zinal".to_string(),
+                vec!["x1000c0s0b0n0".to_string(), "x1000c0s0b0n1".to_string()],
+            ),
+            ("nodes_free".to_string(), vec!["x1000c0s0b0n1".to_string()]),
+        ];
+        let node_vec = vec![
+            "x1000c0s0b0n0".to_string(),
+            "x1000c0s0b0n1".to_string(),
+            "x1000c0s0b1n0".to_string(),
+        ];
+
+        let hsm_overlap_report = calculate_hsm_overlap_report(&hsm_group_member_vec, &node_vec);
+
+        assert_eq!(
+            hsm_overlap_report.overlapping,
+            vec![OverlappingNode {
+                xname: "x1000c0s0b0n1".to_string(),
+                hsm_groups: vec!["nodes_free".to_string(), "zinal".to_string()],
+            }]
+        );
+        assert_eq!(hsm_overlap_report.orphans, vec!["x1000c0s0b1n0"]);
+    }
+}
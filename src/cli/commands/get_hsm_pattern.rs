@@ -1,117 +1,244 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::collections::BTreeMap;
 
-use tokio::sync::Semaphore;
+use chrono::{DateTime, Utc};
 
-use crate::cli::commands::get_nodes_artifacts::NodeSummary;
+use crate::{
+    cli::{commands::get_nodes_artifacts::NodeSummary, common::history_ops},
+    common::{hw_component_ops, hw_inventory_ops},
+};
 
+/// Prints the hw configuration pattern of a HSM group or, if `hsm_group_name_opt` is missing, of
+/// all HSM groups, one line per HSM group. Patterns can be used as is in `apply hsm-group`. If
+/// `as_of_opt` is set, HSM groups have the members they had then according to the history
 pub async fn exec(
     shasta_token: &str,
     shasta_base_url: &str,
     shasta_root_cert: &[u8],
-    hsm_group_name: &str,
+    hsm_group_name_opt: Option<&String>,
+    as_of_opt: Option<&DateTime<Utc>>,
 ) {
-    // Target HSM group
-    let hsm_group_value = mesa::hsm::group::shasta::http_client::get(
+    let hsm_pattern_hashmap = get_hsm_pattern_hashmap(
         shasta_token,
         shasta_base_url,
         shasta_root_cert,
-        Some(&hsm_group_name.to_string()),
+        hsm_group_name_opt,
+        as_of_opt,
     )
-    .await
-    .unwrap()
-    .first()
-    .unwrap()
-    .clone();
+    .await;
 
-    log::info!(
-        "Get HW artifacts for nodes in HSM group '{:?}' and members {:?}",
-        hsm_group_value["label"],
-        hsm_group_value["members"]
-    );
+    if hsm_pattern_hashmap.is_empty() {
+        eprintln!("No HSM group found. Exit");
+        std::process::exit(1);
+    }
 
-    // Get target HSM group members
-    let hsm_group_target_members =
-        mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(&hsm_group_value);
+    for hsm_pattern in hsm_pattern_hashmap.values() {
+        println!("{}", hsm_pattern);
+    }
+}
 
-    let mut hsm_summary = Vec::new();
+/// Pattern for `apply hsm-group` which gives the target HSM group the same hw components than
+/// the source HSM group (clone layout). `pattern` must only have the target HSM group name
+pub async fn get_pattern_from_hsm_group_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    pattern: &str,
+    source_hsm_group_name: &String,
+) -> String {
+    if pattern.contains(':') {
+        eprintln!(
+            "Pattern '{}' has hw components, only the target HSM group name is accepted with --from-hsm. Exit",
+            pattern
+        );
+        std::process::exit(1);
+    }
 
-    let start_total = Instant::now();
+    let hsm_pattern_hashmap = get_hsm_pattern_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        Some(source_hsm_group_name),
+        None,
+    )
+    .await;
 
-    let mut tasks = tokio::task::JoinSet::new();
+    let source_hsm_pattern = hsm_pattern_hashmap
+        .get(source_hsm_group_name)
+        .unwrap_or_else(|| {
+            eprintln!("HSM group '{}' not found. Exit", source_hsm_group_name);
+            std::process::exit(1);
+        });
 
-    let sem = Arc::new(Semaphore::new(5)); // CSM 1.3.1 higher number of concurrent tasks won't
-                                           // make it faster
+    let pattern = format!(
+        "{}{}",
+        pattern,
+        &source_hsm_pattern[source_hsm_group_name.len()..]
+    );
 
-    // Get HW inventory details for target HSM group
-    for hsm_member in hsm_group_target_members.clone() {
-        let shasta_token_string = shasta_token.to_string(); // TODO: make it static
-        let shasta_base_url_string = shasta_base_url.to_string(); // TODO: make it static
-        let shasta_root_cert_vec = shasta_root_cert.to_vec();
-        let hsm_member_string = hsm_member.to_string(); // TODO: make it static
-                                                        //
-        let permit = Arc::clone(&sem).acquire_owned().await;
+    println!(
+        "Pattern from HSM group '{}': {}",
+        source_hsm_group_name, pattern
+    );
 
-        log::info!("Getting HW inventory details for node '{}'", hsm_member);
-        tasks.spawn(async move {
-            let _permit = permit; // Wait semaphore to allow new tasks https://github.com/tokio-rs/tokio/discussions/2648#discussioncomment-34885
-            mesa::hsm::hw_inventory::shasta::http_client::get_hw_inventory(
-                &shasta_token_string,
-                &shasta_base_url_string,
-                &shasta_root_cert_vec,
-                &hsm_member_string,
-            )
-            .await
-            .unwrap()
-        });
-    }
+    pattern
+}
 
-    while let Some(message) = tasks.join_next().await {
-        if let Ok(mut node_hw_inventory) = message {
-            node_hw_inventory = node_hw_inventory.pointer("/Nodes/0").unwrap().clone();
-            let node_summary = NodeSummary::from_csm_value(node_hw_inventory.clone());
-            hsm_summary.push(node_summary);
-        } else {
-            log::error!("Failed procesing/fetching node hw information");
+/// HSM group name -> pattern of the HSM group, for a HSM group or, if `hsm_group_name_opt` is
+/// missing, for all HSM groups. Hw inventory of each node is fetched once even if it belongs to
+/// several HSM groups. If `as_of_opt` is set, HSM groups have the members they had then according
+/// to the history
+pub async fn get_hsm_pattern_hashmap(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    as_of_opt: Option<&DateTime<Utc>>,
+) -> BTreeMap<String, String> {
+    let hsm_group_value_vec = match hsm_group_name_opt {
+        Some(hsm_group_name) => {
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                Some(hsm_group_name),
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            })
         }
-    }
+        None => crate::common::tenant_ops::filter_available_hsm_group_value_vec(
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            }),
+        ),
+    };
+
+    let hsm_group_value_vec =
+        history_ops::rewind_hsm_group_value_vec_or_exit(hsm_group_value_vec, as_of_opt);
 
-    let duration = start_total.elapsed();
+    // HSM group name -> members
+    let hsm_group_member_hashmap: BTreeMap<String, Vec<String>> = hsm_group_value_vec
+        .iter()
+        .map(|hsm_group_value| {
+            (
+                hsm_group_value["label"].as_str().unwrap().to_string(),
+                mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                    hsm_group_value,
+                ),
+            )
+        })
+        .collect();
+
+    let mut xname_vec: Vec<String> = hsm_group_member_hashmap
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    xname_vec.sort();
+    xname_vec.dedup();
 
     log::info!(
-        "Time elapsed in http calls to get hw inventory for HSM '{}' is: {:?}",
-        hsm_group_name,
-        duration
+        "Get HW artifacts for {} nodes in {} HSM groups",
+        xname_vec.len(),
+        hsm_group_member_hashmap.len()
     );
 
-    // println!("DEBUG - hsm_summary: {:#?}", hsm_summary);
+    let node_hw_inventory_hashmap = hw_inventory_ops::get_node_hw_inventory_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &xname_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not fetch hw inventory: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    hsm_group_member_hashmap
+        .into_iter()
+        .map(|(hsm_group_name, hsm_group_member_vec)| {
+            let hsm_summary: Vec<NodeSummary> = hsm_group_member_vec
+                .iter()
+                .filter_map(|xname| node_hw_inventory_hashmap.get(xname))
+                .filter_map(|node_hw_inventory_value| {
+                    node_hw_inventory_value.pointer("/Nodes/0").cloned()
+                })
+                .map(NodeSummary::from_csm_value)
+                .collect();
+
+            if hsm_summary.len() < hsm_group_member_vec.len() {
+                log::warn!(
+                    "Hw inventory missing for {} nodes in HSM group '{}'",
+                    hsm_group_member_vec.len() - hsm_summary.len(),
+                    hsm_group_name
+                );
+            }
+
+            let hsm_pattern = get_hsm_pattern(&hsm_group_name, hsm_summary);
+
+            (hsm_group_name, hsm_pattern)
+        })
+        .collect()
+}
+
+/// Pattern `<hsm group name>:<hw component>:<counter>:...` with the hw components of the nodes.
+/// Processors and accelerators are named by their canonical hw component (see
+/// common::hw_component_ops) and memory is the total capacity with units eg memory:512gb
+pub fn get_hsm_pattern(hsm_group_name: &str, hsm_summary: Vec<NodeSummary>) -> String {
+    let hsm_node_hw_component_count_hashmap = get_hsm_hw_component_count_hashmap(hsm_summary);
+
+    [hsm_group_name.to_string()]
+        .into_iter()
+        .chain(
+            hsm_node_hw_component_count_hashmap
+                .iter()
+                .map(|(hw_component, qty)| {
+                    if hw_component.eq("memory") {
+                        format!(
+                            "{}:{}",
+                            hw_component,
+                            crate::common::memory_ops::format_memory_size(
+                                *qty as u64 * crate::common::memory_ops::MIB
+                            )
+                        )
+                    } else {
+                        format!("{}:{}", hw_component, qty)
+                    }
+                }),
+        )
+        .collect::<Vec<String>>()
+        .join(":")
+}
 
-    let mut hsm_node_hw_component_count_hashmap: HashMap<String, usize> = HashMap::new();
+/// hw component -> quantity of the nodes, 'memory' is the total memory capacity in MiB
+fn get_hsm_hw_component_count_hashmap(hsm_summary: Vec<NodeSummary>) -> BTreeMap<String, usize> {
+    let mut hsm_node_hw_component_count_hashmap: BTreeMap<String, usize> = BTreeMap::new();
 
     for node_summary in hsm_summary {
         for processor in node_summary.processors {
             hsm_node_hw_component_count_hashmap
-                .entry(
-                    processor
-                        .info
-                        .unwrap()
-                        .chars()
-                        .filter(|c| !c.is_whitespace())
-                        .collect(),
-                )
+                .entry(hw_component_ops::get_canonical_hw_component(
+                    &processor.info.unwrap(),
+                ))
                 .and_modify(|qty| *qty += 1)
                 .or_insert(1);
         }
 
         for node_accel in node_summary.node_accels {
             hsm_node_hw_component_count_hashmap
-                .entry(
-                    node_accel
-                        .info
-                        .unwrap()
-                        .chars()
-                        .filter(|c| !c.is_whitespace())
-                        .collect(),
-                )
+                .entry(hw_component_ops::get_canonical_hw_component(
+                    &node_accel.info.unwrap(),
+                ))
                 .and_modify(|qty| *qty += 1)
                 .or_insert(1);
         }
@@ -136,13 +263,5 @@ pub async fn exec(
         }
     }
 
-    println!(
-        "{}:{}",
-        hsm_group_name,
-        hsm_node_hw_component_count_hashmap
-            .iter()
-            .map(|(hw_component, qty)| format!("{}:{}", hw_component, qty))
-            .collect::<Vec<String>>()
-            .join(":")
-    );
+    hsm_node_hw_component_count_hashmap
 }
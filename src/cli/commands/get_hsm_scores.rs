@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::{
+        scoring_strategy::{get_scoring_strategy, ScoringContext},
+        utils::{
+            build_table_f32_score, calculate_all_deltas, calculate_hsm_hw_component_count,
+            calculate_hsm_hw_component_normalized_density_score_from_hsm_node_hw_component_count_vec,
+            calculate_hsm_total_number_hw_components, calculate_node_density_score,
+            calculate_node_hw_component_count, get_node_hw_properties_from_value,
+            parse_pattern_with_qualifiers_or_exit, QuantityQualifier,
+        },
+    },
+    common::{hw_inventory_ops, xname},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NodeScore {
+    pub xname: String,
+    pub hsm_group: String,
+    pub density_score: usize,
+    pub score: f32,
+    pub hw_components: HashMap<String, usize>,
+}
+
+/// Prints the density and strategy score of each node in a HSM group (and in the parent HSM group
+/// if provided) for the hw components requested, as `apply hsm-group` computes them before
+/// migrating the first node. Nothing is planned nor modified
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &str,
+    hw_component_pattern: &str,
+    parent_hsm_group_name_opt: Option<&String>,
+    scoring_strategy_name: &str,
+    output_opt: Option<&String>,
+) {
+    // Pattern has no HSM group name, the HSM group is used so the pattern can be validated as usual
+    let (_, hw_component_qualified_count_hashmap) = parse_pattern_with_qualifiers_or_exit(
+        &format!("{}:{}", hsm_group_name, hw_component_pattern),
+    );
+
+    let user_defined_hw_component_vec: Vec<String> = hw_component_qualified_count_hashmap
+        .keys()
+        .cloned()
+        .collect();
+
+    // As if no node was migrated yet, all hw components requested are missing
+    let hw_components_to_migrate: HashMap<String, isize> = hw_component_qualified_count_hashmap
+        .iter()
+        .map(|(hw_component, (qty, qualifier))| {
+            (
+                hw_component.clone(),
+                -(qualifier.get_min_qty(*qty) as isize),
+            )
+        })
+        .collect();
+
+    // xname -> HSM group
+    let mut node_hsm_group_vec: Vec<(String, String)> = Vec::new();
+
+    for hsm_group_name in [Some(&hsm_group_name.to_string()), parent_hsm_group_name_opt]
+        .into_iter()
+        .flatten()
+    {
+        let hsm_group_value =
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                Some(hsm_group_name),
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            })
+            .first()
+            .cloned()
+            .unwrap_or_else(|| {
+                eprintln!("HSM group '{}' not found. Exit", hsm_group_name);
+                std::process::exit(1);
+            });
+
+        for xname in
+            mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(&hsm_group_value)
+        {
+            node_hsm_group_vec.push((xname, hsm_group_name.clone()));
+        }
+    }
+
+    let hsm_member_vec: Vec<String> = node_hsm_group_vec
+        .iter()
+        .filter(|(_, node_hsm_group_name)| node_hsm_group_name.eq(hsm_group_name))
+        .map(|(xname, _)| xname.clone())
+        .collect();
+
+    let xname_vec: Vec<String> = node_hsm_group_vec
+        .iter()
+        .map(|(xname, _)| xname.clone())
+        .collect();
+
+    let mut node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)> =
+        hw_inventory_ops::get_node_hw_inventory_hashmap(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &xname_vec,
+        )
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not fetch hw inventory: {}. Exit", error);
+            std::process::exit(1);
+        })
+        .into_iter()
+        .map(|(xname, node_hw_inventory_value)| {
+            let (node_hw_component_vec, node_memory_capacity_vec, node_accelerator_memory_vec) =
+                get_node_hw_properties_from_value(
+                    &node_hw_inventory_value,
+                    user_defined_hw_component_vec.clone(),
+                );
+
+            (
+                xname,
+                calculate_node_hw_component_count(
+                    node_hw_component_vec,
+                    &node_memory_capacity_vec,
+                    &node_accelerator_memory_vec,
+                ),
+            )
+        })
+        .collect();
+
+    node_hw_component_count_vec
+        .sort_by(|(xname_1, _), (xname_2, _)| xname::compare_xnames(xname_1, xname_2));
+
+    // Collective data, same as `apply hsm-group`
+    let hw_component_count_hashmap = calculate_hsm_hw_component_count(&node_hw_component_count_vec);
+
+    let hw_component_normalized_scores_hashmap =
+        calculate_hsm_hw_component_normalized_density_score_from_hsm_node_hw_component_count_vec(
+            &node_hw_component_count_vec,
+            calculate_hsm_total_number_hw_components(&node_hw_component_count_vec),
+        );
+
+    let density_score_hashmap = calculate_node_density_score(&node_hw_component_count_vec);
+
+    let hw_component_count_requested_hashmap: HashMap<String, usize> = hw_components_to_migrate
+        .iter()
+        .map(|(hw_component, qty)| (hw_component.clone(), qty.unsigned_abs()))
+        .collect();
+
+    let (hw_components_to_migrate_from_target_hsm_to_parent_hsm, _) = calculate_all_deltas(
+        &hw_component_count_requested_hashmap
+            .iter()
+            .map(|(hw_component, qty)| (hw_component.clone(), (*qty, QuantityQualifier::Exact)))
+            .collect(),
+        &hw_component_count_hashmap,
+    );
+
+    let scoring_context = ScoringContext {
+        hw_component_normalized_scores_hashmap: &hw_component_normalized_scores_hashmap,
+        hw_component_count_hashmap: &hw_component_count_hashmap,
+        hw_component_count_requested_hashmap: &hw_component_count_requested_hashmap,
+        hw_components_to_migrate_from_target_hsm_to_parent_hsm:
+            &hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+        target_hsm_member_vec: &hsm_member_vec,
+    };
+
+    let mut node_score_vec = get_scoring_strategy(scoring_strategy_name).calculate_node_score_vec(
+        &node_hw_component_count_vec,
+        &hw_components_to_migrate,
+        &[],
+        &scoring_context,
+    );
+
+    // Best candidates first
+    node_score_vec.sort_by(|(xname_1, score_1), (xname_2, score_2)| {
+        score_2
+            .total_cmp(score_1)
+            .then(xname::compare_xnames(xname_1, xname_2))
+    });
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        let node_hw_component_count_hashmap: HashMap<String, HashMap<String, usize>> =
+            node_hw_component_count_vec.into_iter().collect();
+
+        let node_score_summary_vec: Vec<NodeScore> = node_score_vec
+            .iter()
+            .map(|(xname, score)| NodeScore {
+                xname: xname.clone(),
+                hsm_group: node_hsm_group_vec
+                    .iter()
+                    .find(|(node_xname, _)| node_xname.eq(xname))
+                    .map(|(_, node_hsm_group_name)| node_hsm_group_name.clone())
+                    .unwrap_or_default(),
+                density_score: *density_score_hashmap.get(xname).unwrap_or(&0),
+                score: *score,
+                hw_components: node_hw_component_count_hashmap
+                    .get(xname)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&node_score_summary_vec).unwrap()
+        );
+    } else {
+        println!(
+            "Scores of nodes in HSM group '{}'{} for hw components {:?} ('{}' strategy), best candidates first",
+            hsm_group_name,
+            parent_hsm_group_name_opt
+                .map(|parent_hsm_group_name| format!(" and '{}'", parent_hsm_group_name))
+                .unwrap_or_default(),
+            user_defined_hw_component_vec,
+            scoring_strategy_name
+        );
+
+        let node_hw_component_count_sorted_vec: Vec<(String, HashMap<String, usize>)> =
+            node_score_vec
+                .iter()
+                .filter_map(|(xname, _)| {
+                    node_hw_component_count_vec
+                        .iter()
+                        .find(|(node_xname, _)| node_xname.eq(xname))
+                        .cloned()
+                })
+                .collect();
+
+        println!(
+            "{}\n",
+            build_table_f32_score(
+                &user_defined_hw_component_vec,
+                &node_hw_component_count_sorted_vec,
+                &density_score_hashmap,
+                &node_score_vec,
+            )
+        );
+    }
+}
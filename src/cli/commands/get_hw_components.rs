@@ -0,0 +1,135 @@
+use comfy_table::Cell;
+
+use crate::{
+    cli::commands::get_nodes_artifacts,
+    common::{
+        format_ops::format_count,
+        hw_component_ops::{self, HwComponentKeyword},
+        hw_inventory_ops, table_ops,
+    },
+};
+
+/// Lists the keywords found in the processor and accelerator models of the nodes in a HSM group,
+/// or of all nodes in the system if no HSM group is provided. These are the hw components which
+/// can be used in patterns
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    output_opt: Option<&String>,
+) {
+    let xname_vec: Vec<String> = match hsm_group_name_opt {
+        Some(hsm_group_name) => {
+            let hsm_group_value_vec =
+                crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    Some(hsm_group_name),
+                ))
+                .await
+                .unwrap_or_else(|error| {
+                    eprintln!("Could not get HSM groups: {}. Exit", error);
+                    std::process::exit(1);
+                });
+
+            match hsm_group_value_vec.first() {
+                Some(hsm_group_value) => {
+                    mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                        hsm_group_value,
+                    )
+                }
+                None => {
+                    eprintln!("HSM group '{}' not found. Exit", hsm_group_name);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => match get_nodes_artifacts::get_node_status_hashmap(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+        )
+        .await
+        {
+            Ok(node_status_hashmap) => node_status_hashmap.into_keys().collect(),
+            Err(error) => {
+                eprintln!("Could not get nodes in the system: {}. Exit", error);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    log::info!(
+        "Get processor and accelerator models of {} nodes",
+        xname_vec.len()
+    );
+
+    let node_hw_inventory_hashmap = hw_inventory_ops::get_node_hw_inventory_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &xname_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not fetch hw inventory: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    let node_model_vec: Vec<(String, Vec<String>)> = node_hw_inventory_hashmap
+        .iter()
+        .map(|(xname, node_hw_inventory_value)| {
+            let node_hw_inventory_value = node_hw_inventory_value
+                .pointer("/Nodes/0")
+                .unwrap_or(node_hw_inventory_value);
+
+            let processor_vec = mesa::hsm::hw_inventory::shasta::utils::get_list_processor_model_from_hw_inventory_value(
+                node_hw_inventory_value,
+            )
+            .unwrap_or_default();
+
+            let accelerator_vec = mesa::hsm::hw_inventory::shasta::utils::get_list_accelerator_model_from_hw_inventory_value(
+                node_hw_inventory_value,
+            )
+            .unwrap_or_default();
+
+            (xname.clone(), [processor_vec, accelerator_vec].concat())
+        })
+        .collect();
+
+    let hw_component_keyword_vec = hw_component_ops::get_hw_component_keyword_vec(&node_model_vec);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hw_component_keyword_vec).unwrap()
+        );
+    } else {
+        print_table(&hw_component_keyword_vec);
+
+        println!(
+            "{} hw components found in {} nodes",
+            format_count(hw_component_keyword_vec.len()),
+            format_count(node_model_vec.len())
+        );
+    }
+}
+
+pub fn print_table(hw_component_keyword_vec: &[HwComponentKeyword]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Hw component", "Components", "Nodes", "Models"]);
+
+    for hw_component_keyword in hw_component_keyword_vec {
+        table.add_row(vec![
+            Cell::new(&hw_component_keyword.keyword),
+            Cell::new(format_count(hw_component_keyword.components)),
+            Cell::new(format_count(hw_component_keyword.nodes)),
+            Cell::new(hw_component_keyword.models.join("\n")),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use termion::color;
+
+use crate::common::{
+    color_ops, format_ops::format_count, hw_component_ops, hw_inventory_ops, membership_ops,
+    xname::Xname,
+};
+
+/// Width of the hw component printed for each node in the layout
+const HW_COMPONENT_WIDTH: usize = 8;
+
+/// Node in the physical layout: its HSM groups and the hw component (accelerator or processor,
+/// see `get_canonical_hw_component`) telling it apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeLayout {
+    pub xname: String,
+    pub hsm_groups: Vec<String>,
+    pub hw_component: Option<String>,
+}
+
+/// Prints a map of the cabinets, chassis, slots and blades with nodes of the HSM group so
+/// operators can see the physical footprint of a tenant. Every node in those chassis is printed
+/// with its hw component and marked depending on whether it belongs to the HSM group, to another
+/// HSM group or to none
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &str,
+) {
+    let node_membership_hashmap = membership_ops::get_node_membership_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &[],
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get node memberships from HSM: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    let chassis_xname_set: BTreeSet<String> = node_membership_hashmap
+        .values()
+        .filter(|node_membership| {
+            node_membership
+                .hsm_groups
+                .contains(&hsm_group_name.to_string())
+        })
+        .filter_map(|node_membership| hw_inventory_ops::get_chassis_xname(&node_membership.xname))
+        .collect();
+
+    if chassis_xname_set.is_empty() {
+        eprintln!("HSM group '{}' not found or empty. Exit", hsm_group_name);
+        std::process::exit(1);
+    }
+
+    // Neighbour nodes in the same chassis are printed too so the footprint has context
+    let xname_vec: Vec<String> = node_membership_hashmap
+        .keys()
+        .filter(|xname| {
+            hw_inventory_ops::get_chassis_xname(xname)
+                .is_some_and(|chassis_xname| chassis_xname_set.contains(&chassis_xname))
+        })
+        .cloned()
+        .collect();
+
+    log::info!(
+        "Get hw inventory of {} nodes in {} chassis",
+        xname_vec.len(),
+        chassis_xname_set.len()
+    );
+
+    let node_hw_inventory_hashmap = hw_inventory_ops::get_node_hw_inventory_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &xname_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not fetch hw inventory: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    let node_layout_vec: Vec<NodeLayout> = xname_vec
+        .iter()
+        .map(|xname| NodeLayout {
+            xname: xname.clone(),
+            hsm_groups: node_membership_hashmap[xname].hsm_groups.clone(),
+            hw_component: node_hw_inventory_hashmap
+                .get(xname)
+                .and_then(get_hw_component),
+        })
+        .collect();
+
+    print!(
+        "{}",
+        render_layout(
+            hsm_group_name,
+            &node_layout_vec,
+            color_ops::is_color_enabled()
+        )
+    );
+}
+
+/// Hw component of the first accelerator of the node, or of its first processor if the node has
+/// no accelerators
+fn get_hw_component(node_hw_inventory_value: &serde_json::Value) -> Option<String> {
+    let node_hw_inventory_value = node_hw_inventory_value
+        .pointer("/Nodes/0")
+        .unwrap_or(node_hw_inventory_value);
+
+    mesa::hsm::hw_inventory::shasta::utils::get_list_accelerator_model_from_hw_inventory_value(
+        node_hw_inventory_value,
+    )
+    .unwrap_or_default()
+    .first()
+    .or(
+        mesa::hsm::hw_inventory::shasta::utils::get_list_processor_model_from_hw_inventory_value(
+            node_hw_inventory_value,
+        )
+        .unwrap_or_default()
+        .first(),
+    )
+    .map(|model| hw_component_ops::get_canonical_hw_component(model))
+}
+
+/// Renders the layout, one line per slot with its blades and nodes:
+///
+/// ```text
+/// x1000
+///   c0
+///     s0  b0 [■ a100     ■ a100    ] b1 [· epyc     □ epyc    ]
+/// ```
+///
+/// '■' nodes in the HSM group, '□' nodes in other HSM groups and '·' nodes in no HSM group.
+/// If `colored`, nodes in the HSM group are green and nodes in no HSM group are grey
+pub fn render_layout(
+    hsm_group_name: &str,
+    node_layout_vec: &[NodeLayout],
+    colored: bool,
+) -> String {
+    // blade -> (node, node cell), blades are ordered by cabinet, chassis, slot and BMC
+    let mut blade_hashmap: BTreeMap<Xname, Vec<(Xname, String)>> = BTreeMap::new();
+
+    let mut hsm_group_node_count = 0;
+
+    for node_layout in node_layout_vec {
+        let xname = match node_layout.xname.parse::<Xname>() {
+            Ok(xname) if xname.is_node() => xname,
+            _ => {
+                log::warn!("Node '{}' skipped, not a node xname", node_layout.xname);
+                continue;
+            }
+        };
+
+        let (marker, color_start) = if node_layout
+            .hsm_groups
+            .iter()
+            .any(|hsm_group| hsm_group == hsm_group_name)
+        {
+            hsm_group_node_count += 1;
+            ("■", color::Fg(color::Green).to_string())
+        } else if node_layout.hsm_groups.is_empty() {
+            ("·", color::Fg(color::LightBlack).to_string())
+        } else {
+            ("□", String::new())
+        };
+
+        let mut hw_component = node_layout.hw_component.clone().unwrap_or("?".to_string());
+        hw_component.truncate(HW_COMPONENT_WIDTH);
+
+        let node_cell = format!(
+            "{} {:<width$}",
+            marker,
+            hw_component,
+            width = HW_COMPONENT_WIDTH
+        );
+
+        let node_cell = if colored && !color_start.is_empty() {
+            format!("{}{}{}", color_start, node_cell, color::Fg(color::Reset))
+        } else {
+            node_cell
+        };
+
+        blade_hashmap
+            .entry(xname.blade())
+            .or_default()
+            .push((xname, node_cell));
+    }
+
+    let mut layout = String::new();
+    let mut previous_blade_opt: Option<Xname> = None;
+
+    for (blade, mut node_cell_vec) in blade_hashmap {
+        let previous_blade = previous_blade_opt.unwrap_or(Xname {
+            cabinet: u32::MAX,
+            chassis: None,
+            slot: None,
+            bmc: None,
+            node: None,
+        });
+
+        if blade.cabinet != previous_blade.cabinet {
+            layout.push_str(&format!("x{}\n", blade.cabinet));
+        }
+
+        if blade.chassis() != previous_blade.chassis() {
+            layout.push_str(&format!("  c{}\n", blade.chassis.unwrap_or_default()));
+        }
+
+        if blade.distance(&previous_blade) > 1 {
+            layout = layout.trim_end_matches(' ').to_string();
+            if !layout.ends_with('\n') {
+                layout.push('\n');
+            }
+            layout.push_str(&format!(
+                "    {:<4}",
+                format!("s{}", blade.slot.unwrap_or_default())
+            ));
+        } else {
+            layout.push(' ');
+        }
+
+        node_cell_vec.sort();
+
+        layout.push_str(&format!(
+            "b{} [{}]",
+            blade.bmc.unwrap_or_default(),
+            node_cell_vec
+                .into_iter()
+                .map(|(_, node_cell)| node_cell)
+                .collect::<Vec<String>>()
+                .join(" ")
+        ));
+
+        previous_blade_opt = Some(blade);
+    }
+
+    if !layout.is_empty() {
+        layout.push('\n');
+    }
+
+    layout.push_str(&format!(
+        "\n■ {} ({} nodes)  □ other HSM groups  · no HSM group\n",
+        hsm_group_name,
+        format_count(hsm_group_node_count)
+    ));
+
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_layout(xname: &str, hsm_group: &str, hw_component: &str) -> NodeLayout {
+        NodeLayout {
+            xname: xname.to_string(),
+            hsm_groups: if hsm_group.is_empty() {
+                Vec::new()
+            } else {
+                vec![hsm_group.to_string()]
+            },
+            hw_component: Some(hw_component.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_render_layout() {
+        let node_layout_vec = vec![
+            node_layout("x1000c0s10b0n0", "zinal", "a100"),
+            node_layout("x1000c0s2b0n1", "", "epyc"),
+            node_layout("x1000c0s2b0n0", "eiger", "epyc"),
+        ];
+
+        assert_eq!(
+            render_layout("zinal", &node_layout_vec, false),
+            "x1000\n  c0\n    s2  b0 [□ epyc     · epyc    ]\n    s10 b0 [■ a100    ]\n\n■ zinal (1 nodes)  □ other HSM groups  · no HSM group\n"
+        );
+    }
+}
@@ -1,131 +1,11 @@
-use comfy_table::{Cell, Table};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use comfy_table::Cell;
 use std::str::FromStr;
 use std::string::ToString;
-use strum_macros::{AsRefStr, Display, EnumIter, EnumString, IntoStaticStr};
 use termion::color;
 
-#[derive(
-    Debug, EnumIter, EnumString, IntoStaticStr, AsRefStr, Display, Serialize, Deserialize, Clone,
-)]
-pub enum ArtifactType {
-    Memory,
-    Processor,
-    NodeAccel,
-    NodeHsnNic,
-    Drive,
-    CabinetPDU,
-    CabinetPDUPowerConnector,
-    CMMRectifier,
-    NodeAccelRiser,
-    NodeEnclosurePowerSupplie,
-    NodeBMC,
-    RouterBMC,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NodeSummary {
-    pub xname: String,
-    pub r#type: String,
-    pub processors: Vec<ArtifactSummary>,
-    pub memory: Vec<ArtifactSummary>,
-    pub node_accels: Vec<ArtifactSummary>,
-    pub node_hsn_nics: Vec<ArtifactSummary>,
-}
-
-impl NodeSummary {
-    pub fn from_csm_value(hw_artifact_value: Value) -> Self {
-        let processors = hw_artifact_value["Processors"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .map(|processor_value| ArtifactSummary::from_processor_value(processor_value.clone()))
-            .collect();
-
-        let memory = hw_artifact_value["Memory"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .map(|memory_value| ArtifactSummary::from_memory_value(memory_value.clone()))
-            .collect();
-
-        let node_accels = hw_artifact_value["NodeAccels"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .map(|nodeaccel_value| ArtifactSummary::from_nodeaccel_value(nodeaccel_value.clone()))
-            .collect();
-
-        let node_hsn_nics = hw_artifact_value["NodeHsnNics"]
-            .as_array()
-            .unwrap_or(&Vec::new())
-            .iter()
-            .map(|nodehsnnic_value| {
-                ArtifactSummary::from_nodehsnnics_value(nodehsnnic_value.clone())
-            })
-            .collect();
-
-        Self {
-            xname: hw_artifact_value["ID"].as_str().unwrap().to_string(),
-            r#type: hw_artifact_value["Type"].as_str().unwrap().to_string(),
-            processors,
-            memory,
-            node_accels,
-            node_hsn_nics,
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ArtifactSummary {
-    pub xname: String,
-    pub r#type: ArtifactType,
-    pub info: Option<String>,
-}
-
-impl ArtifactSummary {
-    fn from_processor_value(processor_value: Value) -> Self {
-        Self {
-            xname: processor_value["ID"].as_str().unwrap().to_string(),
-            r#type: ArtifactType::from_str(processor_value["Type"].as_str().unwrap()).unwrap(),
-            info: processor_value
-                .pointer("/PopulatedFRU/ProcessorFRUInfo/Model")
-                .map(|model| model.as_str().unwrap().to_string()),
-        }
-    }
-
-    fn from_memory_value(memory_value: Value) -> Self {
-        // println!("DEBUG - memory raw data: {:#?}", memory_value);
-        Self {
-            xname: memory_value["ID"].as_str().unwrap().to_string(),
-            r#type: ArtifactType::from_str(memory_value["Type"].as_str().unwrap()).unwrap(),
-            info: memory_value
-                .pointer("/PopulatedFRU/MemoryFRUInfo/CapacityMiB")
-                .map(|capacity_mib| capacity_mib.as_number().unwrap().to_string() + " MiB"),
-        }
-    }
-
-    fn from_nodehsnnics_value(nodehsnnic_value: Value) -> Self {
-        Self {
-            xname: nodehsnnic_value["ID"].as_str().unwrap().to_string(),
-            r#type: ArtifactType::from_str(nodehsnnic_value["Type"].as_str().unwrap()).unwrap(),
-            info: nodehsnnic_value
-                .pointer("/NodeHsnNicLocationInfo/Description")
-                .map(|description| description.as_str().unwrap().to_string()),
-        }
-    }
-
-    fn from_nodeaccel_value(nodeaccel_value: Value) -> Self {
-        Self {
-            xname: nodeaccel_value["ID"].as_str().unwrap().to_string(),
-            r#type: ArtifactType::from_str(nodeaccel_value["Type"].as_str().unwrap()).unwrap(),
-            info: nodeaccel_value
-                .pointer("/PopulatedFRU/NodeAccelFRUInfo/Model")
-                .map(|model| model.as_str().unwrap().to_string()),
-        }
-    }
-}
+pub use crate::common::artifact_ops::{
+    get_node_status_hashmap, ArtifactSummary, ArtifactType, NodeSummary,
+};
 
 /// Get nodes status/configuration for some nodes filtered by a HSM group.
 pub async fn exec(
@@ -134,16 +14,20 @@ pub async fn exec(
     shasta_root_cert: &[u8],
     hsm_group_name: Option<&String>,
     xname: &str,
-    type_artifact_opt: Option<&String>,
+    type_artifact_vec_opt: Option<Vec<&String>>,
     output_opt: Option<&String>,
+    with_firmware: bool,
+    wide: bool,
+    field_vec_opt: Option<&Vec<String>>,
 ) {
-    let hsm_groups_resp = mesa::hsm::group::shasta::http_client::get_hsm_group_vec(
-        shasta_token,
-        shasta_base_url,
-        shasta_root_cert,
-        hsm_group_name,
-    )
-    .await;
+    let hsm_groups_resp =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            hsm_group_name,
+        ))
+        .await;
 
     let hsm_group_list = if let Ok(hsm_groups) = hsm_groups_resp {
         hsm_groups
@@ -170,102 +54,142 @@ pub async fn exec(
 
     hsm_groups_node_list.sort();
 
-    let mut node_hw_inventory = &mesa::hsm::hw_inventory::shasta::http_client::get_hw_inventory(
-        shasta_token,
-        shasta_base_url,
-        shasta_root_cert,
-        xname,
-    )
-    .await
-    .unwrap();
+    let node_hw_inventory =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hw_inventory(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            xname,
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get hw inventory: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+    let node_hw_inventory = node_hw_inventory.pointer("/Nodes/0").unwrap();
+
+    let mut node_summary = if let Some(type_artifact_vec) = type_artifact_vec_opt {
+        let artifact_type_vec: Vec<ArtifactType> = type_artifact_vec
+            .iter()
+            .map(|type_artifact| ArtifactType::from_str(type_artifact).unwrap())
+            .collect();
 
-    node_hw_inventory = node_hw_inventory.pointer("/Nodes/0").unwrap();
+        NodeSummary::from_csm_value_filtered_by_artifact_type(
+            node_hw_inventory.clone(),
+            &artifact_type_vec,
+        )
+    } else {
+        NodeSummary::from_csm_value(node_hw_inventory.clone())
+    };
 
-    if let Some(type_artifact) = type_artifact_opt {
-        node_hw_inventory = &node_hw_inventory
-            .as_array()
-            .unwrap()
-            .iter()
-            .find(|&node| node["ID"].as_str().unwrap().eq(xname))
-            .unwrap()[type_artifact];
+    match get_node_status_hashmap(shasta_token, shasta_base_url, shasta_root_cert).await {
+        Ok(node_status_hashmap) => {
+            if let Some(node_status_value) = node_status_hashmap.get(xname) {
+                node_summary.set_component_status(node_status_value);
+            }
+        }
+        Err(error) => log::warn!("Could not get node state from HSM: {}", error),
     }
 
-    let node_summary = NodeSummary::from_csm_value(node_hw_inventory.clone());
-
-    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+    if field_vec_opt.is_some() || output_opt.is_some_and(|output| output.eq("csv")) {
+        crate::cli::commands::export_assets::print_asset_record_vec(
+            &[node_summary],
+            field_vec_opt,
+            output_opt,
+        );
+    } else if output_opt.is_some() && output_opt.unwrap().eq("json") {
         println!("{}", serde_json::to_string_pretty(&node_summary).unwrap());
     } else {
-        print_table(&[node_summary].to_vec());
+        let power_state_hashmap = crate::common::power_ops::get_power_state_hashmap_or_empty(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &[xname.to_string()],
+        )
+        .await;
+
+        if let Some(power_state) = power_state_hashmap.get(xname) {
+            println!("Power state: {}", power_state);
+        }
+
+        if node_summary.state.is_some() {
+            println!(
+                "State: {}",
+                node_summary.get_component_status_cell().content()
+            );
+        }
+
+        print_table(&[node_summary].to_vec(), wide);
+    }
+
+    if with_firmware {
+        crate::cli::common::firmware_ops::print_firmware_version_vec_after_artifacts(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &[xname.to_string()],
+            output_opt,
+        )
+        .await;
     }
 }
 
-pub fn print_table(node_summary_vec: &Vec<NodeSummary>) {
-    let mut table = Table::new();
+/// `wide` adds the serial and part number of each component
+pub fn print_table(node_summary_vec: &Vec<NodeSummary>, wide: bool) {
+    let mut table = crate::common::table_ops::new_table();
 
-    table.set_header(vec![
+    let mut header_vec = vec![
         "Node XName",
         "Component XName",
         "Component Type",
         "Component Info",
-    ]);
+    ];
 
-    for node_summary in node_summary_vec {
-        for processor in &node_summary.processors {
-            table.add_row(vec![
-                Cell::new(node_summary.xname.clone()),
-                Cell::new(processor.xname.clone()),
-                Cell::new(processor.r#type.clone()),
-                Cell::new(
-                    processor
-                        .info
-                        .clone()
-                        .unwrap_or("*** Missing info".to_string()),
-                ),
-            ]);
-        }
+    if wide {
+        header_vec.extend(["Serial Number", "Part Number"]);
+    }
 
-        for memory in &node_summary.memory {
-            table.add_row(vec![
-                Cell::new(node_summary.xname.clone()),
-                Cell::new(memory.xname.clone()),
-                Cell::new(memory.r#type.clone()),
-                Cell::new(
-                    memory
-                        .info
-                        .clone()
-                        .unwrap_or("*** Missing info".to_string()),
-                ),
-            ]);
-        }
+    table.set_header(header_vec.clone());
 
-        for node_accel in &node_summary.node_accels {
-            table.add_row(vec![
-                Cell::new(node_summary.xname.clone()),
-                Cell::new(node_accel.xname.clone()),
-                Cell::new(node_accel.r#type.clone()),
-                Cell::new(
-                    node_accel
-                        .clone()
-                        .info
-                        .unwrap_or("*** Missing info".to_string()),
-                ),
-            ]);
-        }
+    let mut row_vec: Vec<Vec<Cell>> = Vec::new();
 
-        for node_hsn_nic in &node_summary.node_hsn_nics {
-            table.add_row(vec![
+    for node_summary in node_summary_vec {
+        for artifact_summary in node_summary
+            .processors
+            .iter()
+            .chain(node_summary.memory.iter())
+            .chain(node_summary.node_accels.iter())
+            .chain(node_summary.node_hsn_nics.iter())
+        {
+            let mut row = vec![
                 Cell::new(node_summary.xname.clone()),
-                Cell::new(node_hsn_nic.xname.clone()),
-                Cell::new(node_hsn_nic.r#type.clone()),
-                Cell::new(
-                    node_hsn_nic
-                        .clone()
-                        .info
-                        .unwrap_or("*** Missing info".to_string()),
-                ),
-            ]);
+                Cell::new(artifact_summary.xname.clone()),
+                Cell::new(artifact_summary.r#type.clone()),
+                Cell::new(artifact_summary.get_info_with_attributes()),
+            ];
+
+            if wide {
+                row.push(Cell::new(
+                    artifact_summary.serial_number.as_deref().unwrap_or("n/a"),
+                ));
+                row.push(Cell::new(
+                    artifact_summary.part_number.as_deref().unwrap_or("n/a"),
+                ));
+            }
+
+            row_vec.push(row);
         }
     }
 
+    crate::common::table_ops::sort_row_vec(
+        &header_vec
+            .iter()
+            .map(|header| header.to_string())
+            .collect::<Vec<String>>(),
+        &mut row_vec,
+    );
+    table.add_rows(row_vec);
+
     println!("{table}");
 }
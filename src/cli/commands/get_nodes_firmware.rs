@@ -0,0 +1,36 @@
+use crate::cli::common::firmware_ops;
+
+/// Prints the firmware versions (BIOS, BMC, GPUs, etc) of a node reported by FAS
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname: &str,
+    output_opt: Option<&String>,
+) {
+    let firmware_version_vec = firmware_ops::get_firmware_version_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &[xname.to_string()],
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get firmware versions from FAS: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    if firmware_version_vec.is_empty() {
+        println!("No firmware versions found for node '{}'", xname);
+        return;
+    }
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&firmware_version_vec).unwrap()
+        );
+    } else {
+        firmware_ops::print_firmware_version_table(&firmware_version_vec);
+    }
+}
@@ -0,0 +1,54 @@
+use comfy_table::Cell;
+
+use crate::common::{
+    membership_ops::{self, NodeMembership},
+    table_ops,
+};
+
+/// Prints the HSM groups and partition each node belongs to
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+    output_opt: Option<&String>,
+) {
+    let node_membership_hashmap = membership_ops::get_node_membership_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        xname_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get node memberships from HSM: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    let node_membership_vec: Vec<&NodeMembership> = node_membership_hashmap.values().collect();
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&node_membership_vec).unwrap()
+        );
+    } else {
+        print_table(&node_membership_vec);
+    }
+}
+
+pub fn print_table(node_membership_vec: &[&NodeMembership]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Node", "HSM groups", "Partition"]);
+
+    for node_membership in node_membership_vec {
+        table.add_row(vec![
+            Cell::new(&node_membership.xname),
+            Cell::new(node_membership.hsm_groups.join(", ")),
+            Cell::new(node_membership.partition.clone().unwrap_or_default()),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,75 @@
+use comfy_table::Cell;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{format_ops::format_count, hostlist_ops, partition_ops, table_ops},
+    shasta::hsm,
+};
+
+/// HSM partition, partitions are a hard split of the system: unlike HSM groups a node belongs to
+/// at most one partition
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartitionSummary {
+    pub name: String,
+    pub description: Option<String>,
+    pub members: Vec<String>,
+}
+
+/// Lists HSM partitions with their members
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    output_opt: Option<&String>,
+) {
+    let partition_value_vec =
+        hsm::http_client::get_partitions(shasta_token, shasta_base_url, shasta_root_cert)
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM partitions: {}. Exit", error);
+                std::process::exit(1);
+            });
+
+    let mut partition_summary_vec: Vec<PartitionSummary> = partition_value_vec
+        .iter()
+        .map(|partition_value| PartitionSummary {
+            name: partition_value["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            description: partition_value["description"]
+                .as_str()
+                .filter(|description| !description.is_empty())
+                .map(str::to_string),
+            members: partition_ops::get_partition_member_vec(partition_value),
+        })
+        .collect();
+
+    partition_summary_vec.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&partition_summary_vec).unwrap()
+        );
+    } else {
+        print_table(&partition_summary_vec);
+    }
+}
+
+pub fn print_table(partition_summary_vec: &[PartitionSummary]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Name", "Description", "Nodes", "Members"]);
+
+    for partition_summary in partition_summary_vec {
+        table.add_row(vec![
+            Cell::new(&partition_summary.name),
+            Cell::new(partition_summary.description.clone().unwrap_or_default()),
+            Cell::new(format_count(partition_summary.members.len())),
+            Cell::new(hostlist_ops::compress_hostlist(&partition_summary.members)),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,383 @@
+use std::time::{Duration, Instant};
+
+use comfy_table::{Cell, CellAlignment, Color};
+use config::Config;
+
+use crate::{
+    cli::commands::config_validate,
+    common::{
+        auth_ops, config_ops,
+        csm_version_ops::{self, CsmApiProbe},
+        table_ops,
+    },
+    shasta::{ProxySettings, SendTraced},
+};
+
+/// Maximum time to open a TCP connection to the SOCKS5 proxy
+const SOCKS5_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// CSM services used by clstr and their health endpoints
+const SERVICE_HEALTH_ENDPOINT_VEC: [(&str, &str); 3] = [
+    ("hsm", "/smd/hsm/v2/service/ready"),
+    ("bos", "/bos/v2/healthz"),
+    ("cfs", "/cfs/healthz"),
+];
+
+pub struct HealthCheck {
+    pub check: String,
+    pub healthy: bool,
+    pub detail: String,
+}
+
+impl HealthCheck {
+    fn new(check: &str, healthy: bool, detail: String) -> Self {
+        Self {
+            check: check.to_string(),
+            healthy,
+            detail,
+        }
+    }
+}
+
+/// Checks configuration of the active site, SOCKS5 proxy, CSM root certificate, Keycloak token
+/// and HSM, BOS and CFS endpoints. Checks depending on a failed one are reported as failed
+/// without running them. Exits with error code if any check fails
+pub async fn exec(settings: &Config, site_name_opt: Option<&String>, api_versions: bool) {
+    let mut health_check_vec = Vec::new();
+
+    let site_name_opt = check_config(settings, site_name_opt, &mut health_check_vec);
+
+    let csm_api_probe_vec_opt = match site_name_opt {
+        Some(site_name) => {
+            check_site(settings, site_name, api_versions, &mut health_check_vec).await
+        }
+        None => None,
+    };
+
+    print_table(&health_check_vec);
+
+    if let Some(csm_api_probe_vec) = csm_api_probe_vec_opt {
+        print_api_version_table(&csm_api_probe_vec);
+
+        match csm_version_ops::get_csm_version_from_api_probe_vec(&csm_api_probe_vec) {
+            Some(csm_version) => println!("CSM {} or newer detected", csm_version),
+            None => println!("CSM version not detected"),
+        }
+    }
+
+    if health_check_vec
+        .iter()
+        .any(|health_check| !health_check.healthy)
+    {
+        eprintln!("Health check failed");
+        std::process::exit(1);
+    }
+
+    println!("All health checks passed");
+}
+
+/// Configuration of the active site (see `config validate`). Returns the active site if its
+/// configuration is valid
+fn check_config<'a>(
+    settings: &Config,
+    site_name_opt: Option<&'a String>,
+    health_check_vec: &mut Vec<HealthCheck>,
+) -> Option<&'a String> {
+    let config_error_vec: Vec<String> =
+        config_validate::get_config_check_vec(settings, site_name_opt)
+            .into_iter()
+            .filter(|config_check| {
+                config_check.site.eq("-")
+                    || site_name_opt.is_some_and(|site_name| config_check.site.eq(site_name))
+            })
+            .filter(|config_check| !config_check.valid)
+            .map(|config_check| config_check.detail)
+            .collect();
+
+    if config_error_vec.is_empty() {
+        health_check_vec.push(HealthCheck::new(
+            "config",
+            true,
+            config_ops::get_configuration_toml_file_path()
+                .to_string_lossy()
+                .to_string(),
+        ));
+        site_name_opt
+    } else {
+        health_check_vec.push(HealthCheck::new(
+            "config",
+            false,
+            config_error_vec.join("; "),
+        ));
+        None
+    }
+}
+
+/// Returns the CSM APIs probed if `api_versions` and a token could be obtained
+async fn check_site(
+    settings: &Config,
+    site_name: &str,
+    api_versions: bool,
+    health_check_vec: &mut Vec<HealthCheck>,
+) -> Option<Vec<CsmApiProbe>> {
+    let site_detail_value = settings
+        .get_table("sites")
+        .unwrap_or_default()
+        .get(site_name)
+        .and_then(|site_detail| site_detail.clone().into_table().ok())
+        .unwrap_or_default();
+
+    let shasta_base_url = site_detail_value
+        .get("shasta_base_url")
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let keycloak_base_url = site_detail_value
+        .get("keycloak_base_url")
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+
+    // SOCKS5 proxy, all other checks go through it
+    let socks5_healthy = match ProxySettings::from_config(settings, Some(site_name)) {
+        Some(proxy_settings) => {
+            let (healthy, detail) = check_socks5_proxy(&proxy_settings.socks5_proxy).await;
+            health_check_vec.push(HealthCheck::new("socks5 proxy", healthy, detail));
+            healthy
+        }
+        None => {
+            health_check_vec.push(HealthCheck::new(
+                "socks5 proxy",
+                true,
+                "not configured".to_string(),
+            ));
+            true
+        }
+    };
+
+    if !socks5_healthy {
+        skip_checks(
+            &["root cert", "keycloak token"],
+            "socks5 proxy",
+            health_check_vec,
+        );
+        skip_service_checks("socks5 proxy", health_check_vec);
+        return None;
+    }
+
+    // Root certificate, it must be valid PEM and trusted by Keycloak TLS endpoint
+    let shasta_root_cert_rslt = config_ops::get_csm_root_cert_content(site_name);
+
+    let (root_cert_healthy, root_cert_detail) = match &shasta_root_cert_rslt {
+        Ok(shasta_root_cert) => check_root_cert(shasta_root_cert, &keycloak_base_url).await,
+        Err(error) => (false, error.to_string()),
+    };
+    health_check_vec.push(HealthCheck::new(
+        "root cert",
+        root_cert_healthy,
+        root_cert_detail,
+    ));
+
+    if !root_cert_healthy {
+        skip_checks(&["keycloak token"], "root cert", health_check_vec);
+        skip_service_checks("root cert", health_check_vec);
+        return None;
+    }
+
+    let shasta_root_cert = shasta_root_cert_rslt.unwrap_or_default();
+
+    // Keycloak token
+    let start = Instant::now();
+
+    let shasta_token = match auth_ops::get_api_token(
+        settings,
+        site_name,
+        &shasta_base_url,
+        &shasta_root_cert,
+        &keycloak_base_url,
+    )
+    .await
+    {
+        Ok(shasta_token) => {
+            health_check_vec.push(HealthCheck::new(
+                "keycloak token",
+                true,
+                format!("{} ({} ms)", keycloak_base_url, start.elapsed().as_millis()),
+            ));
+            shasta_token
+        }
+        Err(error) => {
+            health_check_vec.push(HealthCheck::new("keycloak token", false, error.to_string()));
+            skip_service_checks("keycloak token", health_check_vec);
+            return None;
+        }
+    };
+
+    // CSM services
+    for (service_name, health_endpoint) in SERVICE_HEALTH_ENDPOINT_VEC {
+        let (healthy, detail) = check_service(
+            &shasta_token,
+            &shasta_base_url,
+            &shasta_root_cert,
+            health_endpoint,
+        )
+        .await;
+        health_check_vec.push(HealthCheck::new(service_name, healthy, detail));
+    }
+
+    if api_versions {
+        Some(
+            csm_version_ops::probe_api_vec(&shasta_token, &shasta_base_url, &shasta_root_cert)
+                .await,
+        )
+    } else {
+        None
+    }
+}
+
+/// Opens a TCP connection to the proxy, eg socks5h://localhost:1080
+async fn check_socks5_proxy(socks5_proxy: &str) -> (bool, String) {
+    let socks5_proxy_url = match reqwest::Url::parse(socks5_proxy) {
+        Ok(socks5_proxy_url) => socks5_proxy_url,
+        Err(error) => return (false, format!("'{}' not valid: {}", socks5_proxy, error)),
+    };
+
+    let Some(host) = socks5_proxy_url.host_str() else {
+        return (false, format!("'{}' has no host", socks5_proxy));
+    };
+
+    // SOCKS5 default port
+    let port = socks5_proxy_url.port().unwrap_or(1080);
+
+    let start = Instant::now();
+
+    match tokio::time::timeout(
+        SOCKS5_CONNECT_TIMEOUT,
+        tokio::net::TcpStream::connect((host, port)),
+    )
+    .await
+    {
+        Ok(Ok(_)) => (
+            true,
+            format!("{} ({} ms)", socks5_proxy, start.elapsed().as_millis()),
+        ),
+        Ok(Err(error)) => (false, format!("{}: {}", socks5_proxy, error)),
+        Err(_) => (
+            false,
+            format!(
+                "{}: timed out after {}s",
+                socks5_proxy,
+                SOCKS5_CONNECT_TIMEOUT.as_secs()
+            ),
+        ),
+    }
+}
+
+/// Any http response from `url` (eg Keycloak) means the TLS handshake succeeded with the root
+/// certificate
+pub async fn check_root_cert(shasta_root_cert: &[u8], url: &str) -> (bool, String) {
+    let client = match crate::shasta::build_client(shasta_root_cert) {
+        Ok(client) => client,
+        Err(error) => return (false, format!("not a valid PEM certificate: {}", error)),
+    };
+
+    match client.get(url).send_traced().await {
+        Ok(_) => (true, format!("trusted by {}", url)),
+        Err(error) => (
+            false,
+            format!("TLS connection to {} failed: {}", url, error),
+        ),
+    }
+}
+
+async fn check_service(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    health_endpoint: &str,
+) -> (bool, String) {
+    let client = match crate::shasta::build_client(shasta_root_cert) {
+        Ok(client) => client,
+        Err(error) => return (false, error.to_string()),
+    };
+
+    let api_url = shasta_base_url.to_owned() + health_endpoint;
+
+    let start = Instant::now();
+
+    match client
+        .get(&api_url)
+        .header("Authorization", format!("Bearer {}", shasta_token))
+        .send_traced()
+        .await
+    {
+        Ok(response) if response.status().is_success() => (
+            true,
+            format!("{} ({} ms)", api_url, start.elapsed().as_millis()),
+        ),
+        Ok(response) => (false, format!("{}: {}", api_url, response.status())),
+        Err(error) => (false, format!("{}: {}", api_url, error)),
+    }
+}
+
+fn skip_checks(check_vec: &[&str], failed_check: &str, health_check_vec: &mut Vec<HealthCheck>) {
+    for check in check_vec {
+        health_check_vec.push(HealthCheck::new(
+            check,
+            false,
+            format!("skipped, '{}' check failed", failed_check),
+        ));
+    }
+}
+
+fn skip_service_checks(failed_check: &str, health_check_vec: &mut Vec<HealthCheck>) {
+    let service_name_vec: Vec<&str> = SERVICE_HEALTH_ENDPOINT_VEC
+        .iter()
+        .map(|(service_name, _)| *service_name)
+        .collect();
+
+    skip_checks(&service_name_vec, failed_check, health_check_vec);
+}
+
+pub fn print_table(health_check_vec: &Vec<HealthCheck>) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Check", "Result", "Detail"]);
+
+    for health_check in health_check_vec {
+        let result_cell = if health_check.healthy {
+            Cell::new("✅").fg(Color::Green)
+        } else {
+            Cell::new("❌").fg(Color::Red)
+        };
+
+        table.add_row(vec![
+            Cell::new(health_check.check.clone()),
+            result_cell.set_alignment(CellAlignment::Center),
+            Cell::new(health_check.detail.clone()),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+pub fn print_api_version_table(csm_api_probe_vec: &[CsmApiProbe]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["API", "Endpoint", "Since CSM", "Available"]);
+
+    for csm_api_probe in csm_api_probe_vec {
+        let available_cell = if csm_api_probe.available {
+            Cell::new("✅").fg(Color::Green)
+        } else {
+            Cell::new("❌").fg(Color::Red)
+        };
+
+        table.add_row(vec![
+            Cell::new(csm_api_probe.api.clone()),
+            Cell::new(csm_api_probe.endpoint.clone()),
+            Cell::new(csm_api_probe.csm_version.to_string()),
+            available_cell.set_alignment(CellAlignment::Center),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,287 @@
+use comfy_table::{Cell, Color};
+
+use crate::{
+    cli::common::{
+        history_ops::{self, HistoryEntry},
+        lock_ops,
+        node_migration_ops::{self, NodeMove},
+        tenant_ops,
+    },
+    common::{
+        audit_ops::{self, AuditEntry},
+        format_ops::format_count,
+        table_ops,
+    },
+};
+
+/// Lists the membership changes applied by clstr, oldest first
+pub fn exec_list(output_opt: Option<&String>) {
+    let history_entry_vec = read_history_entry_vec_or_exit();
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&history_entry_vec).unwrap()
+        );
+        return;
+    }
+
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec![
+        "ID",
+        "Timestamp",
+        "User",
+        "Command",
+        "Nodes moved",
+        "Status",
+    ]);
+
+    for history_entry in &history_entry_vec {
+        let status_cell = match &history_entry.error {
+            Some(_) if history_entry.rolled_back => Cell::new("rolled back").fg(Color::Yellow),
+            Some(_) => Cell::new("failed").fg(Color::Red),
+            None => Cell::new("applied").fg(Color::Green),
+        };
+
+        table.add_row(vec![
+            Cell::new(history_entry.id),
+            Cell::new(&history_entry.timestamp),
+            Cell::new(&history_entry.user),
+            Cell::new(&history_entry.command),
+            Cell::new(format_count(history_entry.node_moves.len())),
+            status_cell,
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// Shows the node moves and the members of the HSM groups involved before and after a change
+pub fn exec_show(id: u64, output_opt: Option<&String>) {
+    let history_entry = get_history_entry_or_exit(id);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!("{}", serde_json::to_string_pretty(&history_entry).unwrap());
+        return;
+    }
+
+    println!("ID: {}", history_entry.id);
+    println!("Timestamp: {}", history_entry.timestamp);
+    println!("User: {}", history_entry.user);
+    println!("Command: {}", history_entry.command);
+
+    if let Some(error) = &history_entry.error {
+        println!("Error: {}", error);
+
+        if history_entry.rolled_back {
+            println!("Node moves rolled back, HSM groups restored to members before");
+        } else {
+            println!("HSM groups left partially changed, members after is the state to recover");
+        }
+    }
+
+    node_migration_ops::print_node_move_table(&history_entry.node_moves);
+
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["HSM group", "Members before", "Members after"]);
+
+    for hsm_group_snapshot in &history_entry.before {
+        let member_after_vec = history_entry
+            .after
+            .iter()
+            .find(|hsm_group_snapshot_after| {
+                hsm_group_snapshot_after.label == hsm_group_snapshot.label
+            })
+            .map(|hsm_group_snapshot_after| hsm_group_snapshot_after.members.clone())
+            .unwrap_or_default();
+
+        table.add_row(vec![
+            Cell::new(&hsm_group_snapshot.label),
+            Cell::new(hsm_group_snapshot.members.join("\n")),
+            Cell::new(member_after_vec.join("\n")),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// Lists the audit trail of node moves applied, oldest first: who applied them and, with
+/// `api_calls`, every CSM API request sent. Entries are filtered by Keycloak user if `user_opt`
+pub fn exec_audit(user_opt: Option<&String>, api_calls: bool, output_opt: Option<&String>) {
+    let audit_entry_vec: Vec<AuditEntry> = audit_ops::read_audit_entry_vec()
+        .unwrap_or_else(|error| {
+            eprintln!(
+                "Could not read audit file '{}': {}. Exit",
+                audit_ops::get_audit_file_path().display(),
+                error
+            );
+            std::process::exit(1);
+        })
+        .into_iter()
+        .filter(|audit_entry| match user_opt {
+            Some(user) => audit_entry.user.eq(user),
+            None => true,
+        })
+        .collect();
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&audit_entry_vec).unwrap()
+        );
+        return;
+    }
+
+    let mut table = table_ops::new_table();
+
+    if api_calls {
+        table.set_header(vec!["Timestamp", "User", "Method", "Url", "Status"]);
+
+        for audit_entry in &audit_entry_vec {
+            for api_call in &audit_entry.api_calls {
+                let status_cell = match api_call.status {
+                    Some(status) if status < 400 => Cell::new(status).fg(Color::Green),
+                    Some(status) => Cell::new(status).fg(Color::Red),
+                    None => Cell::new(api_call.error.as_deref().unwrap_or("-")).fg(Color::Red),
+                };
+
+                table.add_row(vec![
+                    Cell::new(&audit_entry.timestamp),
+                    Cell::new(&audit_entry.user),
+                    Cell::new(&api_call.method),
+                    Cell::new(&api_call.url),
+                    status_cell,
+                ]);
+            }
+        }
+    } else {
+        table.set_header(vec![
+            "Timestamp",
+            "Site",
+            "User",
+            "Command",
+            "History ID",
+            "API calls",
+            "Status",
+        ]);
+
+        for audit_entry in &audit_entry_vec {
+            let status_cell = match &audit_entry.error {
+                Some(error) => Cell::new(error).fg(Color::Red),
+                None => Cell::new("applied").fg(Color::Green),
+            };
+
+            table.add_row(vec![
+                Cell::new(&audit_entry.timestamp),
+                Cell::new(&audit_entry.site),
+                Cell::new(&audit_entry.user),
+                Cell::new(&audit_entry.command),
+                Cell::new(
+                    audit_entry
+                        .history_id
+                        .map_or("-".to_string(), |history_id| history_id.to_string()),
+                ),
+                Cell::new(format_count(audit_entry.api_calls.len())),
+                status_cell,
+            ]);
+        }
+    }
+
+    println!("{table}");
+}
+
+/// Reverts the node moves of a history entry. Nodes must still be in the HSM group they were
+/// moved to, otherwise HSM groups changed since and the undo is refused
+pub async fn exec_undo(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    id: u64,
+    dry_run: bool,
+    assume_yes: bool,
+) {
+    let history_entry = get_history_entry_or_exit(id);
+
+    if history_entry.rolled_back {
+        eprintln!(
+            "Node moves of history entry {} were already rolled back. Exit",
+            id
+        );
+        std::process::exit(1);
+    }
+
+    let node_move_vec = history_ops::get_reverse_node_move_vec(&history_entry.node_moves);
+
+    let hsm_group_name_vec = history_ops::get_hsm_group_name_vec(&node_move_vec);
+
+    // History entry may touch HSM groups outside 'hsm_available' of the operator
+    tenant_ops::exit_if_hsm_group_not_available(&hsm_group_name_vec);
+
+    // HSM groups are locked while checking and reverting the node moves
+    let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(&hsm_group_name_vec);
+
+    let hsm_group_snapshot_vec = node_migration_ops::get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &hsm_group_name_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get HSM groups: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    let node_move_conflict_vec: Vec<&NodeMove> = node_move_vec
+        .iter()
+        .filter(|node_move| {
+            !hsm_group_snapshot_vec.iter().any(|hsm_group_snapshot| {
+                hsm_group_snapshot.label == node_move.from_hsm_group
+                    && hsm_group_snapshot.members.contains(&node_move.xname)
+            })
+        })
+        .collect();
+
+    if !node_move_conflict_vec.is_empty() {
+        eprintln!(
+            "Can't undo history entry {}, nodes {:?} are not in the HSM group they were moved to anymore. Exit",
+            id,
+            node_move_conflict_vec
+                .iter()
+                .map(|node_move| &node_move.xname)
+                .collect::<Vec<&String>>()
+        );
+        std::process::exit(1);
+    }
+
+    node_migration_ops::confirm_and_apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        &format!("undo {}", id),
+        dry_run,
+        assume_yes,
+    )
+    .await;
+}
+
+fn read_history_entry_vec_or_exit() -> Vec<HistoryEntry> {
+    history_ops::read_history_entry_vec().unwrap_or_else(|error| {
+        eprintln!(
+            "Could not read history file '{}': {}. Exit",
+            history_ops::get_history_file_path().display(),
+            error
+        );
+        std::process::exit(1);
+    })
+}
+
+fn get_history_entry_or_exit(id: u64) -> HistoryEntry {
+    history_ops::get_history_entry(id).unwrap_or_else(|error| {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,252 @@
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::common::table_ops;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionProbe {
+    pub capability: String,
+    pub probe: String,
+    pub allowed: Option<bool>, // None means the probe was skipped
+    pub detail: String,
+}
+
+impl PermissionProbe {
+    fn new(capability: &str, probe: &str, allowed: Option<bool>, detail: String) -> Self {
+        Self {
+            capability: capability.to_string(),
+            probe: probe.to_string(),
+            allowed,
+            detail,
+        }
+    }
+}
+
+/// Performs harmless probe calls against CSM and reports which clstr capabilities the current
+/// token can exercise on this site.
+/// NOTE: HSM API does not support dry-run, the write probe creates and deletes an empty scratch HSM
+/// group so existing groups are never modified. It only runs if `write_probe` is set
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+    write_probe: bool,
+    output_opt: Option<&String>,
+) {
+    let mut permission_probe_vec = Vec::new();
+
+    // List HSM groups
+    let hsm_group_vec_rslt =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+        ))
+        .await;
+
+    let hsm_group_name_to_probe_opt = match &hsm_group_vec_rslt {
+        Ok(hsm_group_vec) => {
+            permission_probe_vec.push(PermissionProbe::new(
+                "get hsm-groups",
+                "List HSM groups",
+                Some(true),
+                format!("{} HSM groups visible", hsm_group_vec.len()),
+            ));
+
+            hsm_group_name_opt.cloned().or(hsm_group_vec
+                .first()
+                .and_then(|hsm_group| hsm_group["label"].as_str())
+                .map(|label| label.to_string()))
+        }
+        Err(error) => {
+            permission_probe_vec.push(PermissionProbe::new(
+                "get hsm-groups",
+                "List HSM groups",
+                Some(false),
+                error.to_string(),
+            ));
+
+            hsm_group_name_opt.cloned()
+        }
+    };
+
+    // Read HSM group
+    let hsm_group_value_opt = if let Some(hsm_group_name) = &hsm_group_name_to_probe_opt {
+        match crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(hsm_group_name),
+        ))
+        .await
+        {
+            Ok(hsm_group_vec) if !hsm_group_vec.is_empty() => {
+                permission_probe_vec.push(PermissionProbe::new(
+                    "get hsm-groups artifacts|pattern",
+                    "Read HSM group",
+                    Some(true),
+                    format!("HSM group '{}'", hsm_group_name),
+                ));
+                hsm_group_vec.first().cloned()
+            }
+            Ok(_) => {
+                permission_probe_vec.push(PermissionProbe::new(
+                    "get hsm-groups artifacts|pattern",
+                    "Read HSM group",
+                    Some(false),
+                    format!("HSM group '{}' not found", hsm_group_name),
+                ));
+                None
+            }
+            Err(error) => {
+                permission_probe_vec.push(PermissionProbe::new(
+                    "get hsm-groups artifacts|pattern",
+                    "Read HSM group",
+                    Some(false),
+                    error.to_string(),
+                ));
+                None
+            }
+        }
+    } else {
+        permission_probe_vec.push(PermissionProbe::new(
+            "get hsm-groups artifacts|pattern",
+            "Read HSM group",
+            None,
+            "No HSM group available to probe".to_string(),
+        ));
+        None
+    };
+
+    // Read node hw inventory
+    let xname_opt = hsm_group_value_opt.as_ref().and_then(|hsm_group_value| {
+        mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(hsm_group_value)
+            .first()
+            .cloned()
+    });
+
+    if let Some(xname) = xname_opt {
+        match crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hw_inventory(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &xname,
+        ))
+        .await
+        {
+            Ok(_) => permission_probe_vec.push(PermissionProbe::new(
+                "get nodes artifacts",
+                "Read node hw inventory",
+                Some(true),
+                format!("Node '{}'", xname),
+            )),
+            Err(error) => permission_probe_vec.push(PermissionProbe::new(
+                "get nodes artifacts",
+                "Read node hw inventory",
+                Some(false),
+                error.to_string(),
+            )),
+        }
+    } else {
+        permission_probe_vec.push(PermissionProbe::new(
+            "get nodes artifacts",
+            "Read node hw inventory",
+            None,
+            "No node available to probe".to_string(),
+        ));
+    }
+
+    // Create and delete a scratch HSM group
+    if write_probe {
+        let scratch_hsm_group_name = format!(
+            "clstr-permissions-probe-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp()
+        );
+
+        let scratch_hsm_group = crate::shasta::hsm::HsmGroup::new(
+            scratch_hsm_group_name.clone(),
+            Some("Scratch HSM group created by 'clstr permissions', safe to delete".to_string()),
+            None,
+            None,
+        );
+
+        match crate::shasta::hsm::http_client::post_hsm_group(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &scratch_hsm_group,
+        )
+        .await
+        {
+            Ok(_) => {
+                let delete_rslt = crate::shasta::hsm::http_client::delete_hsm_group(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    &scratch_hsm_group_name,
+                )
+                .await;
+
+                permission_probe_vec.push(PermissionProbe::new(
+                    "apply hsm-group",
+                    "Create and delete scratch HSM group",
+                    Some(delete_rslt.is_ok()),
+                    match delete_rslt {
+                        Ok(_) => format!("HSM group '{}'", scratch_hsm_group_name),
+                        Err(error) => format!(
+                            "HSM group '{}' created but could not be deleted, please delete it manually: {}",
+                            scratch_hsm_group_name, error
+                        ),
+                    },
+                ))
+            }
+            Err(error) => permission_probe_vec.push(PermissionProbe::new(
+                "apply hsm-group",
+                "Create and delete scratch HSM group",
+                Some(false),
+                error.to_string(),
+            )),
+        }
+    } else {
+        permission_probe_vec.push(PermissionProbe::new(
+            "apply hsm-group",
+            "Create and delete scratch HSM group",
+            None,
+            "Write probe not requested, use --write-probe".to_string(),
+        ));
+    }
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&permission_probe_vec).unwrap()
+        );
+    } else {
+        print_table(&permission_probe_vec);
+    }
+}
+
+pub fn print_table(permission_probe_vec: &Vec<PermissionProbe>) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Capability", "Probe", "Result", "Detail"]);
+
+    for permission_probe in permission_probe_vec {
+        let result_cell = match permission_probe.allowed {
+            Some(true) => Cell::new("✅").fg(Color::Green),
+            Some(false) => Cell::new("❌").fg(Color::Red),
+            None => Cell::new("⚠️  skipped").fg(Color::Yellow),
+        };
+
+        table.add_row(vec![
+            Cell::new(permission_probe.capability.clone()),
+            Cell::new(permission_probe.probe.clone()),
+            result_cell.set_alignment(comfy_table::CellAlignment::Center),
+            Cell::new(permission_probe.detail.clone()),
+        ]);
+    }
+
+    println!("{table}");
+}
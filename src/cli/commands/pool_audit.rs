@@ -0,0 +1,311 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
+
+use comfy_table::Cell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::utils::get_xname_location_vec,
+    common::{format_ops::format_count, table_ops, xname},
+    shasta::{hsm, ShastaClient},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PoolAuditIssue {
+    pub xname: String,
+    pub issue: String,
+    pub detail: String,
+}
+
+impl PoolAuditIssue {
+    fn new(xname: &str, issue: &str, detail: String) -> Self {
+        Self {
+            xname: xname.to_string(),
+            issue: issue.to_string(),
+            detail,
+        }
+    }
+}
+
+/// Inspects the nodes in a pool (parent HSM group) and reports the ones which should be cleaned up
+/// before being allocated to a tenant:
+///  - nodes in bad state (HSM state components not Ready/Off, flag not OK or disabled)
+///  - nodes still referenced by CFS (desired configuration set) or BOS (session template node list)
+///  - nodes with missing hw inventory
+///  - nodes physically located in racks marked for decommission (site `decommissioned_racks`
+///    config key)
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    pool_hsm_group_name: &str,
+    decommissioned_rack_vec: &[String],
+    output_opt: Option<&String>,
+) {
+    let pool_hsm_group_value =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(&pool_hsm_group_name.to_string()),
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        })
+        .first()
+        .cloned()
+        .unwrap_or_else(|| {
+            eprintln!("HSM group '{}' not found. Exit", pool_hsm_group_name);
+            std::process::exit(1);
+        });
+
+    let mut pool_member_vec =
+        mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(&pool_hsm_group_value);
+
+    pool_member_vec.sort();
+
+    let mut pool_audit_issue_vec: Vec<PoolAuditIssue> = Vec::new();
+
+    // Nodes in bad state
+    let node_status_value = crate::shasta::hsm::http_client::get_all_components_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "Node",
+    )
+    .await
+    .unwrap();
+
+    let node_status_hashmap: HashMap<String, Value> = node_status_value["Components"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .map(|node_status| {
+            (
+                node_status["ID"].as_str().unwrap().to_string(),
+                node_status.clone(),
+            )
+        })
+        .collect();
+
+    for xname in &pool_member_vec {
+        match node_status_hashmap.get(xname) {
+            Some(node_status) => {
+                let state = node_status["State"].as_str().unwrap_or("Unknown");
+                let flag = node_status["Flag"].as_str().unwrap_or("Unknown");
+                let enabled = node_status["Enabled"].as_bool().unwrap_or(true);
+
+                if !["Ready", "Off"].contains(&state) || !flag.eq("OK") || !enabled {
+                    pool_audit_issue_vec.push(PoolAuditIssue::new(
+                        xname,
+                        "Bad state",
+                        format!("state '{}' flag '{}' enabled {}", state, flag, enabled),
+                    ));
+                }
+            }
+            None => pool_audit_issue_vec.push(PoolAuditIssue::new(
+                xname,
+                "Bad state",
+                "Node not found in HSM state components".to_string(),
+            )),
+        }
+    }
+
+    // Nodes referenced by CFS
+    for xname_chunk in pool_member_vec.chunks(50) {
+        let cfs_component_vec =
+            crate::shasta::cfs::component::http_client::get_multiple_components(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                xname_chunk,
+            )
+            .await
+            .unwrap_or_else(|error| {
+                log::error!("Failed fetching CFS components: {}", error);
+                Vec::new()
+            });
+
+        for cfs_component in cfs_component_vec {
+            let desired_config = cfs_component["desiredConfig"].as_str().unwrap_or_default();
+
+            if !desired_config.is_empty() {
+                pool_audit_issue_vec.push(PoolAuditIssue::new(
+                    cfs_component["id"].as_str().unwrap_or_default(),
+                    "Referenced by CFS",
+                    format!("desired configuration '{}'", desired_config),
+                ));
+            }
+        }
+    }
+
+    // Nodes referenced by BOS
+    let bos_sessiontemplate_vec = crate::shasta::bos::template::http_client::get_all(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        log::error!("Failed fetching BOS session templates: {}", error);
+        Vec::new()
+    });
+
+    let pool_member_set: HashSet<&String> = pool_member_vec.iter().collect();
+
+    for bos_sessiontemplate in &bos_sessiontemplate_vec {
+        for xname in
+            crate::shasta::bos::template::utils::get_node_list_from_bos_sessiontemplate_value(
+                bos_sessiontemplate,
+            )
+        {
+            if pool_member_set.contains(&xname) {
+                pool_audit_issue_vec.push(PoolAuditIssue::new(
+                    &xname,
+                    "Referenced by BOS",
+                    format!(
+                        "session template '{}'",
+                        bos_sessiontemplate["name"].as_str().unwrap_or_default()
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Nodes with missing hw inventory
+    let start = Instant::now();
+
+    let mut tasks = tokio::task::JoinSet::new();
+
+    let sem = Arc::new(Semaphore::new(
+        crate::common::csm_version_ops::get_max_concurrent_requests(),
+    ));
+
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)
+        .unwrap_or_else(|error| {
+            eprintln!("Could not build http client for CSM API: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+    for xname in pool_member_vec.clone() {
+        let shasta_client = Arc::clone(&shasta_client);
+
+        let permit = Arc::clone(&sem).acquire_owned().await;
+
+        tasks.spawn(async move {
+            let _permit = permit; // Wait semaphore to allow new tasks https://github.com/tokio-rs/tokio/discussions/2648#discussioncomment-34885
+            let node_hw_inventory_rslt =
+                hsm::http_client::get_hw_inventory_query(&shasta_client, &xname, "Node")
+                    .await
+                    .map_err(|error| error.to_string());
+
+            (xname, node_hw_inventory_rslt)
+        });
+    }
+
+    while let Some(message) = tasks.join_next().await {
+        if let Ok((xname, node_hw_inventory_rslt)) = message {
+            match node_hw_inventory_rslt {
+                Ok(node_hw_inventory) => {
+                    let node_hw_inventory = node_hw_inventory.pointer("/Nodes/0");
+
+                    let processor_count = node_hw_inventory
+                        .and_then(|node| node["Processors"].as_array())
+                        .map(|processor_vec| processor_vec.len())
+                        .unwrap_or(0);
+
+                    let memory_count = node_hw_inventory
+                        .and_then(|node| node["Memory"].as_array())
+                        .map(|memory_vec| memory_vec.len())
+                        .unwrap_or(0);
+
+                    if processor_count == 0 || memory_count == 0 {
+                        pool_audit_issue_vec.push(PoolAuditIssue::new(
+                            &xname,
+                            "Missing inventory",
+                            format!(
+                                "{} processors and {} memory dimms found",
+                                processor_count, memory_count
+                            ),
+                        ));
+                    }
+                }
+                Err(error) => pool_audit_issue_vec.push(PoolAuditIssue::new(
+                    &xname,
+                    "Missing inventory",
+                    error,
+                )),
+            }
+        } else {
+            log::error!("Failed procesing/fetching node hw information");
+        }
+    }
+
+    log::info!(
+        "Time elapsed in http calls to get hw inventory for HSM '{}' is: {:?}",
+        pool_hsm_group_name,
+        start.elapsed()
+    );
+
+    // Nodes in racks marked for decommission
+    for xname in &pool_member_vec {
+        if let Some(rack) = get_xname_location_vec(xname).first() {
+            if decommissioned_rack_vec.contains(rack) {
+                pool_audit_issue_vec.push(PoolAuditIssue::new(
+                    xname,
+                    "Rack decommission",
+                    format!("rack '{}' marked for decommission", rack),
+                ));
+            }
+        }
+    }
+
+    pool_audit_issue_vec
+        .sort_by(|a, b| xname::compare_xnames(&a.xname, &b.xname).then(a.issue.cmp(&b.issue)));
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&pool_audit_issue_vec).unwrap()
+        );
+    } else {
+        print_table(&pool_audit_issue_vec);
+
+        let node_with_issues_count = pool_audit_issue_vec
+            .iter()
+            .map(|pool_audit_issue| &pool_audit_issue.xname)
+            .collect::<HashSet<&String>>()
+            .len();
+
+        println!(
+            "{} out of {} nodes in pool '{}' need cleanup before being allocated",
+            format_count(node_with_issues_count),
+            format_count(pool_member_vec.len()),
+            pool_hsm_group_name
+        );
+    }
+}
+
+pub fn print_table(pool_audit_issue_vec: &Vec<PoolAuditIssue>) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Done", "Node", "Issue", "Detail"]);
+
+    for pool_audit_issue in pool_audit_issue_vec {
+        table.add_row(vec![
+            Cell::new("[ ]"),
+            Cell::new(pool_audit_issue.xname.clone()),
+            Cell::new(pool_audit_issue.issue.clone()),
+            Cell::new(pool_audit_issue.detail.clone()),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,388 @@
+use std::{
+    fs::{File, OpenOptions},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::apply_cluster,
+    cli::common::{
+        lock_ops, node_migration_ops,
+        slurm_ops::{self, SlurmSettings},
+    },
+    common::{
+        event_ops::{self, ChangeEvent},
+        format_ops::format_count,
+    },
+};
+
+/// Lease held by the active reconciler. Other reconcilers using the same lease file stay in
+/// standby until the lease expires (eg the active reconciler died) or is released
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReconcileLease {
+    /// <hostname>:<pid> of the reconciler holding the lease
+    pub holder: String,
+    /// Seconds since UNIX epoch
+    pub renewed_at: u64,
+    pub ttl_secs: u64,
+}
+
+impl ReconcileLease {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.renewed_at + self.ttl_secs < now
+    }
+}
+
+/// Runs forever re-evaluating the HSM groups in a cluster definition file every `interval`. Drift
+/// (node moves needed to converge to the cluster definition) is applied if `apply`, otherwise it
+/// is only reported. Only the reconciler holding the lease in `lease_file_path` acts, so several
+/// reconcilers can run for redundancy as long as they share the lease file (eg on a shared file
+/// system, the default lease file only excludes reconcilers on the same host). A new API
+/// token is requested on each pass since the process outlives Keycloak tokens. If
+/// `slurm_settings_opt` is set, moves of nodes with jobs allocated are postponed. Nodes in
+/// `excluded_xname_vec` are never moved
+pub async fn exec(
+    settings: &config::Config,
+    site_name: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    keycloak_base_url: &str,
+    file_path: &str,
+    interval: Duration,
+    lease_file_path: &Path,
+    exact_solver_max_nodes: usize,
+    apply: bool,
+    slurm_settings_opt: Option<&SlurmSettings>,
+    excluded_xname_vec: &[String],
+) {
+    let holder = format!(
+        "{}:{}",
+        std::env::var("HOSTNAME").unwrap_or("localhost".to_string()),
+        std::process::id()
+    );
+
+    // Lease expires if not renewed during 2 passes
+    let ttl_secs = interval.as_secs() * 2;
+
+    log::info!(
+        "Reconciler '{}' started, interval {:?}, lease file '{}'",
+        holder,
+        interval,
+        lease_file_path.display()
+    );
+
+    loop {
+        // Cluster definition is read on each pass to pick up changes (eg GitOps)
+        let cluster_definition = apply_cluster::read_cluster_definition(file_path);
+
+        if !acquire_lease(lease_file_path, &holder, ttl_secs) {
+            log::info!("Lease held by another reconciler, standing by");
+        } else {
+            match cluster_definition {
+                Ok(cluster_definition) => {
+                    match crate::common::auth_ops::get_api_token(
+                        settings,
+                        site_name,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        keycloak_base_url,
+                    )
+                    .await
+                    {
+                        // Plan of a pass only reporting the drift is published once the pass
+                        // finishes, applied node moves are already published
+                        Ok(shasta_token) => {
+                            event_ops::scope(reconcile(
+                                &shasta_token,
+                                shasta_base_url,
+                                shasta_root_cert,
+                                file_path,
+                                &cluster_definition,
+                                exact_solver_max_nodes,
+                                apply,
+                                slurm_settings_opt,
+                                excluded_xname_vec,
+                            ))
+                            .await
+                        }
+                        Err(error) => log::error!("Could not get API token: {}", error),
+                    }
+                }
+                Err(error) => log::error!(
+                    "Could not read cluster definition '{}': {}",
+                    file_path,
+                    error
+                ),
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => {
+                release_lease(lease_file_path, &holder);
+                println!("Reconciler stopped");
+                return;
+            }
+        }
+    }
+}
+
+async fn reconcile(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    file_path: &str,
+    cluster_definition: &apply_cluster::ClusterDefinition,
+    exact_solver_max_nodes: usize,
+    apply: bool,
+    slurm_settings_opt: Option<&SlurmSettings>,
+    excluded_xname_vec: &[String],
+) {
+    // HSM groups changed by an operator at the same time are reconciled in a later pass
+    let _lock_file_vec =
+        match lock_ops::lock_hsm_group_vec(&cluster_definition.get_hsm_group_name_vec()) {
+            Ok(lock_file_vec) => lock_file_vec,
+            Err(error) => {
+                log::warn!("Could not lock HSM groups, skipping pass: {}", error);
+                return;
+            }
+        };
+
+    let busy_xname_vec = match slurm_settings_opt {
+        Some(slurm_settings) => match slurm_ops::get_busy_xname_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            slurm_settings,
+        )
+        .await
+        {
+            Ok(busy_xname_vec) => busy_xname_vec,
+            Err(error) => {
+                log::error!(
+                    "Could not get nodes with jobs allocated from Slurm, skipping pass: {}",
+                    error
+                );
+                return;
+            }
+        },
+        None => Vec::new(),
+    };
+
+    // Transient CSM errors (eg timeouts) only fail this pass, the next pass retries
+    let mut node_move_vec = match apply_cluster::calculate_cluster_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        cluster_definition,
+        exact_solver_max_nodes,
+        false,
+        &busy_xname_vec,
+        excluded_xname_vec,
+    )
+    .await
+    {
+        Ok(node_move_vec) => node_move_vec,
+        Err(error) => {
+            log::error!("Could not calculate node moves, skipping pass: {}", error);
+            return;
+        }
+    };
+
+    // Nodes with jobs allocated are moved in a later pass, once they are idle
+    for busy_node_move in slurm_ops::get_busy_node_move_vec(&node_move_vec, &busy_xname_vec) {
+        log::warn!(
+            "Node '{}' has jobs allocated in Slurm, postponing its move from '{}' to '{}'",
+            busy_node_move.xname,
+            busy_node_move.from_hsm_group,
+            busy_node_move.to_hsm_group
+        );
+    }
+
+    node_move_vec.retain(|node_move| !busy_xname_vec.contains(&node_move.xname));
+
+    if node_move_vec.is_empty() {
+        log::info!("No drift, HSM groups match the cluster definition");
+        return;
+    }
+
+    log::warn!(
+        "Drift detected, {} node moves needed to match the cluster definition",
+        format_count(node_move_vec.len())
+    );
+
+    node_migration_ops::print_node_move_table(&node_move_vec);
+
+    event_ops::emit(ChangeEvent::PlanCreated {
+        node_moves: node_move_vec.clone(),
+    });
+
+    if apply {
+        match apply_cluster::apply_cluster_node_move_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &node_move_vec,
+            &format!("reconcile -f {}", file_path),
+        )
+        .await
+        {
+            Ok(_) => log::info!("{} nodes moved", format_count(node_move_vec.len())),
+            Err(error) => log::error!("Failed applying node moves: {}", error),
+        }
+    }
+}
+
+/// Default lease file, one per site. It lives in the temporary directory of the host, so it only
+/// excludes reconcilers running on the same host
+pub fn get_default_lease_file_path(site_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("clstr-reconcile-{}.lease", site_name))
+}
+
+fn get_now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Takes an exclusive lock (flock) on `<lease file>.lock` while the lease is read and written,
+/// so 2 reconcilers can't both find the lease expired and take it. Released when the file
+/// returned is dropped
+fn lock_lease_file(lease_file_path: &Path) -> std::io::Result<File> {
+    let mut lease_lock_file_path = lease_file_path.as_os_str().to_owned();
+    lease_lock_file_path.push(".lock");
+
+    let lease_lock_file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lease_lock_file_path)?;
+
+    // SAFETY: flock only reads the file descriptor, which is valid while lease_lock_file lives
+    if unsafe { libc::flock(lease_lock_file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(lease_lock_file)
+}
+
+/// Takes or renews the lease. Returns false if the lease is held by another reconciler and has
+/// not expired
+fn acquire_lease(lease_file_path: &Path, holder: &str, ttl_secs: u64) -> bool {
+    let _lease_lock_file = match lock_lease_file(lease_file_path) {
+        Ok(lease_lock_file) => lease_lock_file,
+        Err(error) => {
+            log::error!(
+                "Could not lock lease file '{}': {}",
+                lease_file_path.display(),
+                error
+            );
+            return false;
+        }
+    };
+
+    let now = get_now_secs();
+
+    let lease_opt = std::fs::read_to_string(lease_file_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<ReconcileLease>(&content).ok());
+
+    if !can_acquire_lease(lease_opt.as_ref(), holder, now) {
+        return false;
+    }
+
+    let lease = ReconcileLease {
+        holder: holder.to_string(),
+        renewed_at: now,
+        ttl_secs,
+    };
+
+    // Write and rename so other reconcilers never read a partial lease
+    let lease_file_tmp_path = lease_file_path.with_extension(format!("{}.tmp", std::process::id()));
+
+    let write_rslt = std::fs::write(&lease_file_tmp_path, serde_json::to_string(&lease).unwrap())
+        .and_then(|_| std::fs::rename(&lease_file_tmp_path, lease_file_path));
+
+    if let Err(error) = write_rslt {
+        log::error!(
+            "Could not write lease file '{}': {}",
+            lease_file_path.display(),
+            error
+        );
+        return false;
+    }
+
+    true
+}
+
+fn release_lease(lease_file_path: &Path, holder: &str) {
+    let _lease_lock_file = lock_lease_file(lease_file_path);
+
+    let lease_opt = std::fs::read_to_string(lease_file_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<ReconcileLease>(&content).ok());
+
+    if lease_opt.is_some_and(|lease| lease.holder.eq(holder)) {
+        let _ = std::fs::remove_file(lease_file_path);
+    }
+}
+
+pub fn can_acquire_lease(lease_opt: Option<&ReconcileLease>, holder: &str, now: u64) -> bool {
+    match lease_opt {
+        None => true,
+        Some(lease) => lease.holder.eq(holder) || lease.is_expired(now),
+    }
+}
+
+/// Parses intervals like '90s', '15m', '2h' or '1d'. Numbers without unit are seconds
+pub fn parse_interval(interval: &str) -> Result<Duration, String> {
+    let interval = interval.trim();
+
+    let (value, unit_secs) = match interval.chars().last() {
+        Some('s') => (&interval[..interval.len() - 1], 1),
+        Some('m') => (&interval[..interval.len() - 1], 60),
+        Some('h') => (&interval[..interval.len() - 1], 60 * 60),
+        Some('d') => (&interval[..interval.len() - 1], 60 * 60 * 24),
+        _ => (interval, 1),
+    };
+
+    match value.parse::<u64>() {
+        Ok(value) if value > 0 => Ok(Duration::from_secs(value * unit_secs)),
+        _ => Err(format!(
+            "interval '{}' not valid, use a number followed by s, m, h or d (eg 15m)",
+            interval
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("15m"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_interval("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("15x").is_err());
+    }
+
+    #[test]
+    fn test_can_acquire_lease() {
+        let lease = ReconcileLease {
+            holder: "host-a:1".to_string(),
+            renewed_at: 1000,
+            ttl_secs: 60,
+        };
+
+        assert!(can_acquire_lease(None, "host-b:2", 1030));
+        assert!(can_acquire_lease(Some(&lease), "host-a:1", 1030));
+        assert!(!can_acquire_lease(Some(&lease), "host-b:2", 1030));
+        assert!(can_acquire_lease(Some(&lease), "host-b:2", 1061));
+    }
+}
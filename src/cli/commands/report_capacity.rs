@@ -0,0 +1,309 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::utils::{
+        calculate_node_hw_component_count, get_node_hw_properties_from_value,
+        parse_pattern_with_qualifiers_or_exit,
+    },
+    cli::common::history_ops,
+    common::{format_ops::format_count, hw_inventory_ops, table_ops},
+};
+
+/// Instances of a hw profile a HSM group could satisfy
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HsmGroupCapacity {
+    pub hsm_group: String,
+    pub nodes: usize,
+    pub instances: usize,
+    /// Hw component which runs out first, None if the hw profile requests no hw component
+    pub limiting_hw_component: Option<String>,
+    /// hw component requested -> quantity in the HSM group
+    pub hw_components: BTreeMap<String, usize>,
+}
+
+/// Reports how many instances of a hw profile (eg a100:4:epyc:30) the pool and each HSM group
+/// could satisfy, and the hw component limiting it. Instances are calculated from the hw component
+/// counters of the HSM group as a whole, like `apply hsm-group` does, therefore it is an upper
+/// bound if nodes can't be split between instances. If `as_of_opt` is set, HSM groups have the
+/// members they had then according to the history. Nothing is modified
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hw_component_pattern: &str,
+    pool_hsm_group_name: &str,
+    as_of_opt: Option<&DateTime<Utc>>,
+    output_opt: Option<&String>,
+) {
+    // Pattern has no HSM group name, the pool is used so the pattern can be validated as usual
+    let (_, hw_component_qualified_count_hashmap) = parse_pattern_with_qualifiers_or_exit(
+        &format!("{}:{}", pool_hsm_group_name, hw_component_pattern),
+    );
+
+    // Only floors count for capacity
+    let hw_component_count_requested_hashmap: HashMap<String, usize> =
+        hw_component_qualified_count_hashmap
+            .iter()
+            .map(|(hw_component, (qty, qualifier))| {
+                (hw_component.clone(), qualifier.get_min_qty(*qty))
+            })
+            .filter(|(_, qty)| *qty > 0)
+            .collect();
+
+    let hsm_group_value_vec = history_ops::rewind_hsm_group_value_vec_or_exit(
+        crate::common::tenant_ops::filter_available_hsm_group_value_vec(
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+            ))
+            .await
+            .unwrap_or_else(|error| {
+                eprintln!("Could not get HSM groups: {}. Exit", error);
+                std::process::exit(1);
+            }),
+        ),
+        as_of_opt,
+    );
+
+    // HSM group name -> members
+    let hsm_group_member_hashmap: BTreeMap<String, Vec<String>> = hsm_group_value_vec
+        .iter()
+        .map(|hsm_group_value| {
+            (
+                hsm_group_value["label"].as_str().unwrap().to_string(),
+                mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                    hsm_group_value,
+                ),
+            )
+        })
+        .collect();
+
+    if !hsm_group_member_hashmap.contains_key(pool_hsm_group_name) {
+        eprintln!("HSM group '{}' not found. Exit", pool_hsm_group_name);
+        std::process::exit(1);
+    }
+
+    let mut xname_vec: Vec<String> = hsm_group_member_hashmap
+        .values()
+        .flatten()
+        .cloned()
+        .collect();
+    xname_vec.sort();
+    xname_vec.dedup();
+
+    let user_defined_hw_component_vec: Vec<String> = hw_component_count_requested_hashmap
+        .keys()
+        .cloned()
+        .collect();
+
+    // xname -> hw component counters
+    let node_hw_component_count_hashmap: HashMap<String, HashMap<String, usize>> =
+        hw_inventory_ops::get_node_hw_inventory_hashmap(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &xname_vec,
+        )
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not fetch hw inventory: {}. Exit", error);
+            std::process::exit(1);
+        })
+        .into_iter()
+        .map(|(xname, node_hw_inventory_value)| {
+            let (node_hw_component_vec, node_memory_capacity_vec, node_accelerator_memory_vec) =
+                get_node_hw_properties_from_value(
+                    &node_hw_inventory_value,
+                    user_defined_hw_component_vec.clone(),
+                );
+
+            (
+                xname,
+                calculate_node_hw_component_count(
+                    node_hw_component_vec,
+                    &node_memory_capacity_vec,
+                    &node_accelerator_memory_vec,
+                ),
+            )
+        })
+        .collect();
+
+    // Pool first, then the rest of HSM groups
+    let mut hsm_group_capacity_vec: Vec<HsmGroupCapacity> = hsm_group_member_hashmap
+        .iter()
+        .map(|(hsm_group_name, hsm_group_member_vec)| {
+            let hsm_group_node_hw_component_count_vec: Vec<&HashMap<String, usize>> =
+                hsm_group_member_vec
+                    .iter()
+                    .filter_map(|xname| node_hw_component_count_hashmap.get(xname))
+                    .collect();
+
+            calculate_hsm_group_capacity(
+                hsm_group_name,
+                &hsm_group_node_hw_component_count_vec,
+                &hw_component_count_requested_hashmap,
+            )
+        })
+        .collect();
+
+    hsm_group_capacity_vec
+        .sort_by_key(|hsm_group_capacity| hsm_group_capacity.hsm_group != pool_hsm_group_name);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hsm_group_capacity_vec).unwrap()
+        );
+    } else {
+        print_table(
+            &hsm_group_capacity_vec,
+            &hw_component_count_requested_hashmap,
+        );
+
+        let pool_capacity = &hsm_group_capacity_vec[0];
+
+        println!(
+            "Pool '{}' could satisfy {} instances of '{}'{}",
+            pool_hsm_group_name,
+            format_count(pool_capacity.instances),
+            hw_component_pattern,
+            pool_capacity
+                .limiting_hw_component
+                .as_ref()
+                .map(|hw_component| format!(", limited by '{}'", hw_component))
+                .unwrap_or_default()
+        );
+    }
+}
+
+/// Instances of the hw profile requested the nodes of a HSM group could satisfy. The limiting hw
+/// component is the one with fewest instances, ties are broken alphabetically
+pub fn calculate_hsm_group_capacity(
+    hsm_group_name: &str,
+    node_hw_component_count_vec: &[&HashMap<String, usize>],
+    hw_component_count_requested_hashmap: &HashMap<String, usize>,
+) -> HsmGroupCapacity {
+    let hw_components: BTreeMap<String, usize> = hw_component_count_requested_hashmap
+        .keys()
+        .map(|hw_component| {
+            (
+                hw_component.clone(),
+                node_hw_component_count_vec
+                    .iter()
+                    .map(|node_hw_component_count_hashmap| {
+                        node_hw_component_count_hashmap
+                            .get(hw_component)
+                            .unwrap_or(&0)
+                    })
+                    .sum(),
+            )
+        })
+        .collect();
+
+    let limiting_hw_component_opt = hw_components
+        .iter()
+        .map(|(hw_component, qty)| {
+            (
+                hw_component,
+                qty / hw_component_count_requested_hashmap[hw_component],
+            )
+        })
+        .min_by_key(|(_, instances)| *instances);
+
+    HsmGroupCapacity {
+        hsm_group: hsm_group_name.to_string(),
+        nodes: node_hw_component_count_vec.len(),
+        instances: limiting_hw_component_opt
+            .map(|(_, instances)| instances)
+            .unwrap_or(0),
+        limiting_hw_component: limiting_hw_component_opt
+            .map(|(hw_component, _)| hw_component.clone()),
+        hw_components,
+    }
+}
+
+pub fn print_table(
+    hsm_group_capacity_vec: &[HsmGroupCapacity],
+    hw_component_count_requested_hashmap: &HashMap<String, usize>,
+) {
+    let mut hw_component_vec: Vec<&String> = hw_component_count_requested_hashmap.keys().collect();
+    hw_component_vec.sort();
+
+    let mut table = table_ops::new_table();
+
+    table.set_header(
+        ["HSM group", "Nodes", "Instances", "Limiting hw component"]
+            .into_iter()
+            .map(|header| header.to_string())
+            .chain(hw_component_vec.iter().map(|hw_component| {
+                format!(
+                    "{} ({} per instance)",
+                    hw_component, hw_component_count_requested_hashmap[*hw_component]
+                )
+            })),
+    );
+
+    for hsm_group_capacity in hsm_group_capacity_vec {
+        let instances_cell = Cell::new(format_count(hsm_group_capacity.instances));
+
+        table.add_row(
+            [
+                Cell::new(&hsm_group_capacity.hsm_group),
+                Cell::new(format_count(hsm_group_capacity.nodes)),
+                if hsm_group_capacity.instances == 0 {
+                    instances_cell.fg(Color::Red)
+                } else {
+                    instances_cell.fg(Color::Green)
+                },
+                Cell::new(
+                    hsm_group_capacity
+                        .limiting_hw_component
+                        .clone()
+                        .unwrap_or_default(),
+                ),
+            ]
+            .into_iter()
+            .chain(hw_component_vec.iter().map(|hw_component| {
+                Cell::new(format_count(
+                    *hsm_group_capacity
+                        .hw_components
+                        .get(*hw_component)
+                        .unwrap_or(&0),
+                ))
+            })),
+        );
+    }
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_hsm_group_capacity() {
+        let node_1 = HashMap::from([("a100".to_string(), 4), ("epyc".to_string(), 2)]);
+        let node_2 = HashMap::from([("a100".to_string(), 4), ("epyc".to_string(), 2)]);
+        let node_3 = HashMap::from([("epyc".to_string(), 2)]);
+
+        let hsm_group_capacity = calculate_hsm_group_capacity(
+            "nodes_free",
+            &[&node_1, &node_2, &node_3],
+            &HashMap::from([("a100".to_string(), 4), ("epyc".to_string(), 4)]),
+        );
+
+        assert_eq!(hsm_group_capacity.nodes, 3);
+        assert_eq!(hsm_group_capacity.instances, 1);
+        assert_eq!(
+            hsm_group_capacity.limiting_hw_component,
+            Some("epyc".to_string())
+        );
+        assert_eq!(hsm_group_capacity.hw_components["a100"], 8);
+    }
+}
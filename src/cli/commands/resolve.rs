@@ -0,0 +1,82 @@
+use comfy_table::Cell;
+
+use crate::common::{
+    hostlist_ops,
+    resolver_ops::{NodeIdentity, NodeResolver},
+    table_ops,
+};
+
+/// Prints the xname, NID, hostnames and IP/MAC addresses of each node in `names` (comma
+/// separated xnames, NIDs, hostnames or IP/MAC addresses, accepts hostlist expressions). Exits
+/// with error if any node is not found
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    names: &str,
+    output_opt: Option<&String>,
+) {
+    let name_vec = hostlist_ops::expand_hostlist(names).unwrap_or_else(|error| {
+        eprintln!("Could not expand nodes '{}': {}. Exit", names, error);
+        std::process::exit(1);
+    });
+
+    let node_resolver = NodeResolver::fetch(shasta_token, shasta_base_url, shasta_root_cert)
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get nodes from HSM: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+    let mut node_identity_vec: Vec<&NodeIdentity> = Vec::new();
+    let mut unknown_name_vec: Vec<&String> = Vec::new();
+
+    for name in &name_vec {
+        match node_resolver.resolve(name) {
+            Some(node_identity) => node_identity_vec.push(node_identity),
+            None => unknown_name_vec.push(name),
+        }
+    }
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&node_identity_vec).unwrap()
+        );
+    } else {
+        print_table(&node_identity_vec);
+    }
+
+    if !unknown_name_vec.is_empty() {
+        eprintln!("Nodes not found {:?}. Exit", unknown_name_vec);
+        std::process::exit(1);
+    }
+}
+
+pub fn print_table(node_identity_vec: &[&NodeIdentity]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec![
+        "XName",
+        "NID",
+        "Hostnames",
+        "IP addresses",
+        "MAC addresses",
+    ]);
+
+    for node_identity in node_identity_vec {
+        table.add_row(vec![
+            Cell::new(&node_identity.xname),
+            Cell::new(
+                node_identity
+                    .nid
+                    .map_or("n/a".to_string(), |nid| nid.to_string()),
+            ),
+            Cell::new(node_identity.hostnames.join(", ")),
+            Cell::new(node_identity.ip_addresses.join(", ")),
+            Cell::new(node_identity.mac_addresses.join(", ")),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,798 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
+};
+
+use hyper::{
+    header,
+    server::{
+        accept::{self, Accept},
+        conn::AddrIncoming,
+    },
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
+use tokio_rustls::{rustls, server::TlsStream, TlsAcceptor};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    cli::commands::{
+        apply_hsm_based_on_component_quantity::{
+            scoring_strategy::{get_scoring_strategy, SCORING_STRATEGY_NAME_VEC},
+            utils::{
+                calculate_node_moves, calculate_plan, get_node_hw_component_count_vec,
+                parse_pattern_with_qualifiers, SolverOptions,
+            },
+            SOLVER_OBJECTIVE_NAME_VEC,
+        },
+        export_inventory::HsmGroupSnapshot,
+        get_nodes_artifacts::{get_node_status_hashmap, NodeSummary},
+    },
+    common::{
+        event_ops::{self, ChangeEvent},
+        hw_inventory_ops, lock_ops,
+        node_migration_ops::{self, get_node_move_vec, NodeMove, NodeMoveError},
+        tenant_ops, xname,
+    },
+    shasta::{hsm, ShastaClient},
+};
+
+/// Parent HSM group of plans if the request does not set one
+const DEFAULT_PARENT_HSM_GROUP_NAME: &str = "nodes_free";
+
+/// Maximum number of candidate nodes to use the exact solver if the request does not set one
+const DEFAULT_EXACT_SOLVER_MAX_NODES: usize = 30;
+
+/// Body of `POST /api/v1/plans`, same options as `apply hsm-group`
+#[derive(Debug, Deserialize)]
+pub struct PlanRequest {
+    /// eg zinal:a100:4:epyc:10
+    pub pattern: String,
+    #[serde(default)]
+    pub parent_hsm_group: Option<String>,
+    /// One of SCORING_STRATEGY_NAME_VEC, density by default
+    #[serde(default)]
+    pub strategy: Option<String>,
+    /// One of SOLVER_OBJECTIVE_NAME_VEC, density by default
+    #[serde(default)]
+    pub objective: Option<String>,
+    /// One of exact_solver::SOLVER_NAME_VEC, greedy by default
+    #[serde(default)]
+    pub solver: Option<String>,
+    #[serde(default)]
+    pub exact_max_nodes: Option<usize>,
+    #[serde(default)]
+    pub exclude_xnames: Vec<String>,
+    #[serde(default)]
+    pub pin_xnames: Vec<String>,
+    #[serde(default)]
+    pub whole_blade: bool,
+}
+
+/// Response of `POST /api/v1/plans`. Node moves are applied posting them to
+/// `POST /api/v1/plans/apply`
+#[derive(Debug, Serialize)]
+pub struct PlanResponse {
+    pub node_moves: Vec<NodeMove>,
+    /// Members of the target HSM group once the node moves are applied
+    pub target_hsm_members: Vec<String>,
+    /// Hw component counters of the target HSM group once the node moves are applied
+    pub target_hsm_hw_components: BTreeMap<String, usize>,
+    /// Hw components in the pattern not found in any node
+    pub unknown_hw_components: Vec<String>,
+    pub solver: String,
+}
+
+/// Body of `POST /api/v1/plans/apply`
+#[derive(Debug, Deserialize)]
+pub struct ApplyRequest {
+    pub node_moves: Vec<NodeMove>,
+}
+
+/// Error answered to an API request as `{"error": "<message>"}`
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl ToString) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    fn bad_request(message: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    /// CSM API failed or rejected the token
+    fn bad_gateway(message: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, message)
+    }
+}
+
+/// Settings shared by all API requests
+struct ServeContext {
+    shasta_base_url: String,
+    shasta_root_cert: Vec<u8>,
+    excluded_xname_vec: Vec<String>,
+}
+
+/// Serves the core operations as a REST API on `listen` (eg 127.0.0.1:8080 or :8443) until
+/// interrupted:
+///
+/// - `GET /api/v1/hsm-groups`: HSM groups and their members
+/// - `GET /api/v1/nodes/<xname>/artifacts`: hw inventory and state of a node
+/// - `POST /api/v1/plans`: node moves needed to fulfill a pattern, nothing is applied
+/// - `POST /api/v1/plans/apply`: applies node moves, fails with 409 if the HSM groups changed
+///
+/// Requests are authenticated with the CSM API token of the caller (`Authorization: Bearer
+/// <token>`), which is used for the CSM API calls of the request, so callers can do no more than
+/// with the CLI. `hsm_available` and nodes excluded in configuration file apply to all callers
+///
+/// HTTPS is served with `tls_cert_key_opt` (certificate chain and private key PEM files), plain
+/// HTTP is only served on loopback addresses so tokens are never sent in clear text
+pub async fn exec(
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    listen: &str,
+    tls_cert_key_opt: Option<(&String, &String)>,
+    excluded_xname_vec: &[String],
+) {
+    let socket_addr = parse_listen_address(listen).unwrap_or_else(|error| {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    });
+
+    if tls_cert_key_opt.is_none() && !socket_addr.ip().is_loopback() {
+        eprintln!(
+            "Listening on {} without TLS would expose the CSM API tokens of callers, use --tls-cert and --tls-key or a loopback address (eg 127.0.0.1:8080). Exit",
+            socket_addr
+        );
+        std::process::exit(1);
+    }
+
+    let serve_context = Arc::new(ServeContext {
+        shasta_base_url: shasta_base_url.to_string(),
+        shasta_root_cert: shasta_root_cert.to_vec(),
+        excluded_xname_vec: excluded_xname_vec.to_vec(),
+    });
+
+    let server_rslt = match tls_cert_key_opt {
+        Some((tls_cert_path, tls_key_path)) => {
+            let tls_acceptor = TlsAcceptor::from(Arc::new(
+                get_tls_server_config(tls_cert_path, tls_key_path).unwrap_or_else(|error| {
+                    eprintln!("{}. Exit", error);
+                    std::process::exit(1);
+                }),
+            ));
+
+            let tcp_listener = TcpListener::bind(socket_addr)
+                .await
+                .unwrap_or_else(|error| {
+                    eprintln!("Could not listen on {}: {}. Exit", socket_addr, error);
+                    std::process::exit(1);
+                });
+
+            println!(
+                "Listening on https://{}",
+                tcp_listener.local_addr().unwrap_or(socket_addr)
+            );
+
+            serve(accept_tls(tcp_listener, tls_acceptor), serve_context).await
+        }
+        None => {
+            let addr_incoming = AddrIncoming::bind(&socket_addr).unwrap_or_else(|error| {
+                eprintln!("Could not listen on {}: {}. Exit", socket_addr, error);
+                std::process::exit(1);
+            });
+
+            println!("Listening on http://{}", addr_incoming.local_addr());
+
+            serve(addr_incoming, serve_context).await
+        }
+    };
+
+    if let Err(error) = server_rslt {
+        eprintln!("Server failed: {}. Exit", error);
+        std::process::exit(1);
+    }
+}
+
+/// Answers the connections accepted until interrupted
+async fn serve<I>(incoming: I, serve_context: Arc<ServeContext>) -> hyper::Result<()>
+where
+    I: Accept,
+    I::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    I::Conn: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let make_service = make_service_fn(move |_| {
+        let serve_context = Arc::clone(&serve_context);
+
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(Arc::clone(&serve_context), request)
+            }))
+        }
+    });
+
+    Server::builder(incoming)
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+}
+
+/// TLS settings with the certificate chain and private key (PKCS#8, RSA or EC) PEM files
+fn get_tls_server_config(
+    tls_cert_path: &str,
+    tls_key_path: &str,
+) -> Result<rustls::ServerConfig, String> {
+    let read_pem_file = |path: &str| {
+        std::fs::read(path)
+            .map_err(|error| format!("Could not read TLS file '{}': {}", path, error))
+    };
+
+    let certificate_vec: Vec<rustls::Certificate> =
+        rustls_pemfile::certs(&mut read_pem_file(tls_cert_path)?.as_slice())
+            .map_err(|error| format!("TLS certificate '{}' not valid: {}", tls_cert_path, error))?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+
+    if certificate_vec.is_empty() {
+        return Err(format!("No certificate found in '{}'", tls_cert_path));
+    }
+
+    let private_key = rustls_pemfile::read_all(&mut read_pem_file(tls_key_path)?.as_slice())
+        .map_err(|error| format!("TLS private key '{}' not valid: {}", tls_key_path, error))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or(format!("No private key found in '{}'", tls_key_path))?;
+
+    rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certificate_vec, private_key)
+        .map_err(|error| format!("TLS certificate or private key not valid: {}", error))
+}
+
+/// Connections accepted on `tcp_listener` once their TLS handshake succeeds. Handshakes run on
+/// their own task so a slow client does not hold back the others
+fn accept_tls(
+    tcp_listener: TcpListener,
+    tls_acceptor: TlsAcceptor,
+) -> impl Accept<Conn = TlsStream<TcpStream>, Error = std::io::Error> {
+    let (tls_stream_sender, tls_stream_receiver) = tokio::sync::mpsc::channel(64);
+
+    tokio::spawn(async move {
+        loop {
+            let (tcp_stream, peer_addr) = match tcp_listener.accept().await {
+                Ok(tcp_connection) => tcp_connection,
+                Err(error) => {
+                    log::warn!("Could not accept connection: {}", error);
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let tls_acceptor = tls_acceptor.clone();
+            let tls_stream_sender = tls_stream_sender.clone();
+
+            tokio::spawn(async move {
+                match tls_acceptor.accept(tcp_stream).await {
+                    Ok(tls_stream) => {
+                        let _ = tls_stream_sender.send(Ok(tls_stream)).await;
+                    }
+                    Err(error) => log::warn!("TLS handshake with {} failed: {}", peer_addr, error),
+                }
+            });
+        }
+    });
+
+    accept::from_stream(ReceiverStream::new(tls_stream_receiver))
+}
+
+/// Listen address like :8443 (all interfaces), 127.0.0.1:8080 or localhost:8080
+pub fn parse_listen_address(listen: &str) -> Result<SocketAddr, String> {
+    let listen_address = if listen.starts_with(':') {
+        format!("0.0.0.0{}", listen)
+    } else {
+        listen.to_string()
+    };
+
+    listen_address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut socket_addr_iter| socket_addr_iter.next())
+        .ok_or(format!(
+            "Listen address '{}' not valid, use [<host>]:<port> eg :8080",
+            listen
+        ))
+}
+
+async fn handle_request(
+    serve_context: Arc<ServeContext>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    // Each request publishes its own events
+    match event_ops::scope(route_request(&serve_context, request)).await {
+        Ok(response) => {
+            log::info!("{} {} -> {}", method, path, response.status());
+            Ok(response)
+        }
+        Err(api_error) => {
+            log::warn!(
+                "{} {} -> {}: {}",
+                method,
+                path,
+                api_error.status,
+                api_error.message
+            );
+            Ok(json_response(
+                api_error.status,
+                &json!({ "error": api_error.message }),
+            ))
+        }
+    }
+}
+
+async fn route_request(
+    serve_context: &ServeContext,
+    request: Request<Body>,
+) -> Result<Response<Body>, ApiError> {
+    let shasta_token = get_bearer_token(&request).ok_or(ApiError::new(
+        StatusCode::UNAUTHORIZED,
+        "missing CSM API token, use 'Authorization: Bearer <token>' header",
+    ))?;
+
+    let method = request.method().clone();
+    let path_segment_vec: Vec<String> = request
+        .uri()
+        .path()
+        .trim_matches('/')
+        .split('/')
+        .map(str::to_string)
+        .collect();
+    let path_segment_vec: Vec<&str> = path_segment_vec.iter().map(String::as_str).collect();
+
+    match (&method, path_segment_vec.as_slice()) {
+        (&Method::GET, ["api", "v1", "hsm-groups"]) => {
+            let hsm_group_snapshot_vec = get_hsm_group_snapshot_vec(
+                &shasta_token,
+                &serve_context.shasta_base_url,
+                &serve_context.shasta_root_cert,
+            )
+            .await?;
+
+            Ok(json_response(StatusCode::OK, &hsm_group_snapshot_vec))
+        }
+        (&Method::GET, ["api", "v1", "nodes", xname, "artifacts"]) => {
+            let node_summary = get_node_summary(
+                &shasta_token,
+                &serve_context.shasta_base_url,
+                &serve_context.shasta_root_cert,
+                xname,
+            )
+            .await?;
+
+            Ok(json_response(StatusCode::OK, &node_summary))
+        }
+        (&Method::POST, ["api", "v1", "plans"]) => {
+            let plan_request: PlanRequest = read_json_body(request).await?;
+
+            let plan_response = calculate_plan_response(
+                &shasta_token,
+                &serve_context.shasta_base_url,
+                &serve_context.shasta_root_cert,
+                &plan_request,
+                &serve_context.excluded_xname_vec,
+            )
+            .await?;
+
+            event_ops::emit(ChangeEvent::PlanCreated {
+                node_moves: plan_response.node_moves.clone(),
+            });
+
+            Ok(json_response(StatusCode::OK, &plan_response))
+        }
+        (&Method::POST, ["api", "v1", "plans", "apply"]) => {
+            let apply_request: ApplyRequest = read_json_body(request).await?;
+
+            apply_node_move_vec(
+                &shasta_token,
+                &serve_context.shasta_base_url,
+                &serve_context.shasta_root_cert,
+                &apply_request.node_moves,
+                &serve_context.excluded_xname_vec,
+            )
+            .await?;
+
+            Ok(json_response(
+                StatusCode::OK,
+                &json!({ "applied": apply_request.node_moves.len() }),
+            ))
+        }
+        (_, ["api", "v1", "hsm-groups"])
+        | (_, ["api", "v1", "nodes", _, "artifacts"])
+        | (_, ["api", "v1", "plans"])
+        | (_, ["api", "v1", "plans", "apply"]) => Err(ApiError::new(
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("method {} not allowed", method),
+        )),
+        _ => Err(ApiError::new(StatusCode::NOT_FOUND, "not found")),
+    }
+}
+
+fn get_bearer_token(request: &Request<Body>) -> Option<String> {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+
+    let (scheme, token) = authorization.split_once(' ')?;
+
+    (scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty())
+        .then(|| token.trim().to_string())
+}
+
+async fn read_json_body<T: for<'de> Deserialize<'de>>(
+    request: Request<Body>,
+) -> Result<T, ApiError> {
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .map_err(ApiError::bad_request)?;
+
+    serde_json::from_slice(&body)
+        .map_err(|error| ApiError::bad_request(format!("request body not valid: {}", error)))
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    let mut response = Response::new(Body::from(serde_json::to_string(value).unwrap()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+
+    response
+}
+
+/// HSM groups available (see `hsm_available` in configuration file) and their members
+async fn get_hsm_group_snapshot_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+) -> Result<Vec<HsmGroupSnapshot>, ApiError> {
+    let hsm_group_value_vec = tenant_ops::filter_available_hsm_group_value_vec(
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+        ))
+        .await
+        .map_err(ApiError::bad_gateway)?,
+    );
+
+    Ok(hsm_group_value_vec
+        .iter()
+        .map(|hsm_group_value| {
+            let mut member_vec =
+                mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                    hsm_group_value,
+                );
+            member_vec.sort_by(|a, b| xname::compare_xnames(a, b));
+
+            HsmGroupSnapshot {
+                label: hsm_group_value["label"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                members: member_vec,
+            }
+        })
+        .collect())
+}
+
+/// Hw inventory and state of a node. With `hsm_available` in configuration file, the node must be
+/// a member of an HSM group available
+async fn get_node_summary(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname: &str,
+) -> Result<NodeSummary, ApiError> {
+    if !tenant_ops::get_hsm_available_vec().is_empty()
+        && !get_hsm_group_snapshot_vec(shasta_token, shasta_base_url, shasta_root_cert)
+            .await?
+            .iter()
+            .any(|hsm_group_snapshot| {
+                hsm_group_snapshot
+                    .members
+                    .iter()
+                    .any(|member| member == xname)
+            })
+    {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("node '{}' not in any HSM group available", xname),
+        ));
+    }
+
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)
+        .map_err(|error| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, error))?;
+
+    let node_hw_inventory = hsm::http_client::get_hw_inventory_query(&shasta_client, xname, "Node")
+        .await
+        .map_err(ApiError::bad_gateway)?;
+
+    let mut node_summary = NodeSummary::from_csm_value(
+        node_hw_inventory
+            .pointer("/Nodes/0")
+            .ok_or(ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("node '{}' has no hw inventory", xname),
+            ))?
+            .clone(),
+    );
+
+    match get_node_status_hashmap(shasta_token, shasta_base_url, shasta_root_cert).await {
+        Ok(node_status_hashmap) => {
+            if let Some(node_status_value) = node_status_hashmap.get(xname) {
+                node_summary.set_component_status(node_status_value);
+            }
+        }
+        Err(error) => log::warn!("Could not get node state from HSM: {}", error),
+    }
+
+    Ok(node_summary)
+}
+
+/// Runs the solver like `apply hsm-group --dry-run`
+async fn calculate_plan_response(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    plan_request: &PlanRequest,
+    excluded_xname_vec: &[String],
+) -> Result<PlanResponse, ApiError> {
+    let (target_hsm_group_name, hw_component_qualified_count_hashmap) =
+        parse_pattern_with_qualifiers(&plan_request.pattern).map_err(|pattern_error_vec| {
+            ApiError::bad_request(format!(
+                "pattern '{}' not valid: {}",
+                plan_request.pattern,
+                pattern_error_vec
+                    .iter()
+                    .map(|pattern_error| pattern_error.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ))
+        })?;
+
+    let parent_hsm_group_name = plan_request
+        .parent_hsm_group
+        .as_deref()
+        .unwrap_or(DEFAULT_PARENT_HSM_GROUP_NAME);
+
+    tenant_ops::check_hsm_group_vec(&[target_hsm_group_name.as_str(), parent_hsm_group_name])
+        .map_err(|error| ApiError::new(StatusCode::FORBIDDEN, error))?;
+
+    let scoring_strategy_name = plan_request.strategy.as_deref().unwrap_or("density");
+    let solver_objective_name = plan_request.objective.as_deref().unwrap_or("density");
+    let solver_name = plan_request.solver.as_deref().unwrap_or("greedy");
+
+    for (option, value, value_vec) in [
+        (
+            "strategy",
+            scoring_strategy_name,
+            SCORING_STRATEGY_NAME_VEC.as_slice(),
+        ),
+        (
+            "objective",
+            solver_objective_name,
+            SOLVER_OBJECTIVE_NAME_VEC.as_slice(),
+        ),
+        (
+            "solver",
+            solver_name,
+            crate::cli::commands::apply_hsm_based_on_component_quantity::exact_solver::SOLVER_NAME_VEC
+                .as_slice(),
+        ),
+    ] {
+        if !value_vec.contains(&value) {
+            return Err(ApiError::bad_request(format!(
+                "{} '{}' not valid, use one of {}",
+                option,
+                value,
+                value_vec.join(", ")
+            )));
+        }
+    }
+
+    let hsm_group_snapshot_vec = node_migration_ops::get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &[
+            target_hsm_group_name.clone(),
+            parent_hsm_group_name.to_string(),
+        ],
+    )
+    .await
+    .map_err(ApiError::bad_gateway)?;
+
+    let target_hsm_member_vec = hsm_group_snapshot_vec[0].members.clone();
+
+    let excluded_xname_vec = [excluded_xname_vec, &plan_request.exclude_xnames].concat();
+
+    let parent_hsm_member_vec: Vec<String> = hsm_group_snapshot_vec[1]
+        .members
+        .iter()
+        .filter(|xname| !excluded_xname_vec.contains(xname))
+        .cloned()
+        .collect();
+
+    let node_hw_inventory_hashmap = hw_inventory_ops::get_node_hw_inventory_hashmap(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &[
+            target_hsm_member_vec.as_slice(),
+            &parent_hsm_member_vec
+                .iter()
+                .filter(|xname| !target_hsm_member_vec.contains(xname))
+                .cloned()
+                .collect::<Vec<String>>(),
+        ]
+        .concat(),
+    )
+    .await
+    .map_err(ApiError::bad_gateway)?;
+
+    let user_defined_hw_component_vec: Vec<String> = hw_component_qualified_count_hashmap
+        .keys()
+        .cloned()
+        .collect();
+
+    let scoring_strategy = get_scoring_strategy(scoring_strategy_name);
+
+    let plan = calculate_plan(
+        get_node_hw_component_count_vec(
+            &target_hsm_member_vec,
+            &node_hw_inventory_hashmap,
+            &user_defined_hw_component_vec,
+        ),
+        get_node_hw_component_count_vec(
+            &parent_hsm_member_vec,
+            &node_hw_inventory_hashmap,
+            &user_defined_hw_component_vec,
+        ),
+        &hw_component_qualified_count_hashmap,
+        &excluded_xname_vec,
+        &plan_request.pin_xnames,
+        &SolverOptions {
+            scoring_strategy: scoring_strategy.as_ref(),
+            min_moves: solver_objective_name.eq("min-moves"),
+            exact_solver_max_nodes_opt: solver_name.eq("exact").then_some(
+                plan_request
+                    .exact_max_nodes
+                    .unwrap_or(DEFAULT_EXACT_SOLVER_MAX_NODES),
+            ),
+            whole_blade: plan_request.whole_blade,
+            verbose: false,
+            explain: false,
+            on_progress_opt: None,
+        },
+    )
+    .map_err(|error| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, error))?;
+
+    let mut new_target_hsm_member_vec = plan.get_target_hsm_member_vec();
+    new_target_hsm_member_vec.sort_by(|a, b| xname::compare_xnames(a, b));
+
+    let (node_added_vec, node_removed_vec) =
+        calculate_node_moves(&target_hsm_member_vec, &new_target_hsm_member_vec);
+
+    let target_hsm_hw_component_count_hashmap: HashMap<String, usize> =
+        plan.get_target_hsm_hw_component_count_hashmap();
+
+    Ok(PlanResponse {
+        node_moves: get_node_move_vec(
+            &target_hsm_group_name,
+            parent_hsm_group_name,
+            &node_added_vec,
+            &node_removed_vec,
+        ),
+        target_hsm_members: new_target_hsm_member_vec,
+        target_hsm_hw_components: target_hsm_hw_component_count_hashmap
+            .into_iter()
+            .filter(|(hw_component, _)| user_defined_hw_component_vec.contains(hw_component))
+            .collect(),
+        unknown_hw_components: plan.unknown_hw_component_vec,
+        solver: plan.solver_report,
+    })
+}
+
+/// Applies node moves like `apply hsm-group`, HSM groups involved are locked while the node moves
+/// are applied
+async fn apply_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    excluded_xname_vec: &[String],
+) -> Result<(), ApiError> {
+    if node_move_vec.is_empty() {
+        return Err(ApiError::bad_request("no node moves to apply"));
+    }
+
+    if let Some(node_move) = node_move_vec
+        .iter()
+        .find(|node_move| excluded_xname_vec.contains(&node_move.xname))
+    {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            format!("node '{}' is excluded, it can't be moved", node_move.xname),
+        ));
+    }
+
+    let hsm_group_name_vec = crate::common::history_ops::get_hsm_group_name_vec(node_move_vec);
+
+    tenant_ops::check_hsm_group_vec(&hsm_group_name_vec)
+        .map_err(|error| ApiError::new(StatusCode::FORBIDDEN, error))?;
+
+    let _lock_file_vec = lock_ops::lock_hsm_group_vec(&hsm_group_name_vec)
+        .map_err(|error| ApiError::new(StatusCode::CONFLICT, error))?;
+
+    node_migration_ops::apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+        "serve",
+    )
+    .await
+    .map(|_| ())
+    .map_err(|error| match error {
+        NodeMoveError::Conflict(_) => ApiError::new(StatusCode::CONFLICT, error.to_string()),
+        NodeMoveError::Failed(_) => ApiError::bad_gateway(error),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listen_address() {
+        assert_eq!(
+            parse_listen_address(":8080"),
+            Ok(SocketAddr::from(([0, 0, 0, 0], 8080)))
+        );
+        assert_eq!(
+            parse_listen_address("127.0.0.1:9090"),
+            Ok(SocketAddr::from(([127, 0, 0, 1], 9090)))
+        );
+        assert!(parse_listen_address("8080").is_err());
+        assert!(parse_listen_address(":http").is_err());
+    }
+}
@@ -0,0 +1,724 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Stdout,
+};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{
+    cli::commands::{
+        export_inventory::{self, HsmGroupSnapshot},
+        get_hsm_pattern,
+        get_nodes_artifacts::NodeSummary,
+    },
+    cli::common::{
+        history_ops::{self, HistoryEntry},
+        lock_ops,
+        node_migration_ops::{self, NodeMove},
+        tenant_ops,
+    },
+};
+
+/// HSM group holding the nodes not assigned to any cluster
+const FREE_POOL_HSM_GROUP_NAME: &str = "nodes_free";
+
+/// Number of history entries shown, newest first
+const RECENT_HISTORY_ENTRY_COUNT: usize = 10;
+
+const HELP: &str = "↑/↓ move  tab switch pane  space select node  m move selected to…  u unselect all  a apply  q quit";
+
+/// Pane with keyboard focus
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Focus {
+    HsmGroups,
+    Nodes,
+}
+
+/// Dialog shown over the panes
+enum Dialog {
+    /// Destination HSM group of the nodes selected
+    Destination(ListState),
+    /// Last confirmation before leaving the dashboard and applying the pending node moves
+    ConfirmApply,
+}
+
+struct Dashboard {
+    hsm_group_snapshot_vec: Vec<HsmGroupSnapshot>,
+    node_summary_hashmap: HashMap<String, NodeSummary>,
+    /// Newest first
+    history_entry_vec: Vec<HistoryEntry>,
+    excluded_xname_vec: Vec<String>,
+    hsm_group_state: ListState,
+    node_state: ListState,
+    focus: Focus,
+    dialog_opt: Option<Dialog>,
+    /// Nodes selected in the HSM group highlighted
+    selected_xname_set: BTreeSet<String>,
+    node_move_vec: Vec<NodeMove>,
+    /// Feedback of the last key pressed, eg why a node can't be selected
+    status_opt: Option<String>,
+}
+
+/// Interactive dashboard with the HSM groups available, their hw components, the free pool
+/// capacity and the last membership changes in history. Nodes are selected and moved to another
+/// HSM group with the keyboard instead of writing a pattern, the node moves are only applied
+/// after confirming them, once the dashboard is closed and the HSM groups involved are locked.
+/// Nodes in `excluded_xname_vec` can't be selected
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    excluded_xname_vec: &[String],
+    dry_run: bool,
+) {
+    println!("Loading HSM groups and hw inventory...");
+
+    let inventory_snapshot = export_inventory::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        None,
+    )
+    .await;
+
+    let history_entry_vec = history_ops::read_history_entry_vec().unwrap_or_else(|error| {
+        log::warn!(
+            "Could not read history, recent changes not shown: {}",
+            error
+        );
+        Vec::new()
+    });
+
+    let mut dashboard = Dashboard::new(
+        inventory_snapshot.hsm_groups,
+        inventory_snapshot.nodes,
+        history_entry_vec,
+        excluded_xname_vec,
+    );
+
+    let node_move_vec_opt = run(&mut dashboard).unwrap_or_else(|error| {
+        eprintln!("Terminal UI failed: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    let Some(node_move_vec) = node_move_vec_opt else {
+        println!("Nothing changed");
+        return;
+    };
+
+    let hsm_group_name_vec = history_ops::get_hsm_group_name_vec(&node_move_vec);
+
+    tenant_ops::exit_if_hsm_group_not_available(&hsm_group_name_vec);
+
+    let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(&hsm_group_name_vec);
+
+    // Node moves were confirmed in the dashboard. HSM groups changed since they were loaded make
+    // the apply fail with a conflict without moving any node
+    node_migration_ops::confirm_and_apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        "tui",
+        dry_run,
+        true,
+    )
+    .await;
+}
+
+/// Restores the terminal when dropped, also if the dashboard panics
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    fn new() -> std::io::Result<Self> {
+        enable_raw_mode()?;
+
+        let mut stdout = std::io::stdout();
+
+        if let Err(error) = execute!(stdout, EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(error);
+        }
+
+        Ok(Self {
+            terminal: Terminal::new(CrosstermBackend::new(stdout))?,
+        })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// Runs the dashboard until the user quits (None) or confirms the node moves to apply
+fn run(dashboard: &mut Dashboard) -> std::io::Result<Option<Vec<NodeMove>>> {
+    let mut terminal_guard = TerminalGuard::new()?;
+
+    loop {
+        terminal_guard
+            .terminal
+            .draw(|frame| draw(frame, dashboard))?;
+
+        if let Event::Key(key_event) = event::read()? {
+            if key_event.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(node_move_vec_opt) = dashboard.handle_key(key_event.code) {
+                return Ok(node_move_vec_opt);
+            }
+        }
+    }
+}
+
+impl Dashboard {
+    fn new(
+        mut hsm_group_snapshot_vec: Vec<HsmGroupSnapshot>,
+        node_summary_vec: Vec<NodeSummary>,
+        mut history_entry_vec: Vec<HistoryEntry>,
+        excluded_xname_vec: &[String],
+    ) -> Self {
+        hsm_group_snapshot_vec.sort_by(|a, b| a.label.cmp(&b.label));
+
+        history_entry_vec.reverse();
+        history_entry_vec.truncate(RECENT_HISTORY_ENTRY_COUNT);
+
+        let mut hsm_group_state = ListState::default();
+        if !hsm_group_snapshot_vec.is_empty() {
+            hsm_group_state.select(Some(0));
+        }
+
+        Self {
+            hsm_group_snapshot_vec,
+            node_summary_hashmap: node_summary_vec
+                .into_iter()
+                .map(|node_summary| (node_summary.xname.clone(), node_summary))
+                .collect(),
+            history_entry_vec,
+            excluded_xname_vec: excluded_xname_vec.to_vec(),
+            hsm_group_state,
+            node_state: ListState::default().with_selected(Some(0)),
+            focus: Focus::HsmGroups,
+            dialog_opt: None,
+            selected_xname_set: BTreeSet::new(),
+            node_move_vec: Vec::new(),
+            status_opt: None,
+        }
+    }
+
+    fn get_hsm_group_snapshot(&self) -> Option<&HsmGroupSnapshot> {
+        self.hsm_group_state
+            .selected()
+            .and_then(|index| self.hsm_group_snapshot_vec.get(index))
+    }
+
+    /// `<hsm group name>:<hw component>:<counter>:...` of the nodes with hw inventory
+    fn get_hsm_pattern(&self, hsm_group_snapshot: &HsmGroupSnapshot) -> String {
+        get_hsm_pattern::get_hsm_pattern(
+            &hsm_group_snapshot.label,
+            hsm_group_snapshot
+                .members
+                .iter()
+                .filter_map(|xname| self.node_summary_hashmap.get(xname).cloned())
+                .collect(),
+        )
+    }
+
+    /// Handles a key press. Returns Some once the dashboard must close: None if the user quits or
+    /// the node moves confirmed
+    fn handle_key(&mut self, key_code: KeyCode) -> Option<Option<Vec<NodeMove>>> {
+        self.status_opt = None;
+
+        match self.dialog_opt.take() {
+            Some(Dialog::Destination(destination_state)) => {
+                self.handle_destination_key(key_code, destination_state);
+                None
+            }
+            Some(Dialog::ConfirmApply) => {
+                if matches!(key_code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    Some(Some(self.node_move_vec.clone()))
+                } else {
+                    self.status_opt = Some("Apply cancelled".to_string());
+                    None
+                }
+            }
+            None => self.handle_pane_key(key_code),
+        }
+    }
+
+    fn handle_pane_key(&mut self, key_code: KeyCode) -> Option<Option<Vec<NodeMove>>> {
+        match key_code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(None),
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Focus::HsmGroups => Focus::Nodes,
+                    Focus::Nodes => Focus::HsmGroups,
+                };
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::Char(' ') | KeyCode::Enter if self.focus == Focus::Nodes => {
+                self.toggle_node_selection()
+            }
+            KeyCode::Char('u') => self.selected_xname_set.clear(),
+            KeyCode::Char('m') => {
+                if self.selected_xname_set.is_empty() {
+                    self.status_opt = Some("No nodes selected, select them with space".to_string());
+                } else {
+                    self.dialog_opt = Some(Dialog::Destination(
+                        ListState::default().with_selected(Some(0)),
+                    ));
+                }
+            }
+            KeyCode::Char('a') => {
+                if self.node_move_vec.is_empty() {
+                    self.status_opt = Some("No node moves to apply".to_string());
+                } else {
+                    self.dialog_opt = Some(Dialog::ConfirmApply);
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn handle_destination_key(&mut self, key_code: KeyCode, mut destination_state: ListState) {
+        let destination_hsm_group_name_vec = self.get_destination_hsm_group_name_vec();
+
+        match key_code {
+            KeyCode::Esc | KeyCode::Char('q') => return,
+            KeyCode::Up | KeyCode::Char('k') => {
+                let index = destination_state.selected().unwrap_or(0);
+                destination_state.select(Some(index.saturating_sub(1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let index = destination_state.selected().unwrap_or(0);
+                destination_state.select(Some(
+                    (index + 1).min(destination_hsm_group_name_vec.len().saturating_sub(1)),
+                ));
+            }
+            KeyCode::Enter => {
+                if let Some(to_hsm_group_name) = destination_state
+                    .selected()
+                    .and_then(|index| destination_hsm_group_name_vec.get(index))
+                {
+                    self.add_node_move_vec(to_hsm_group_name);
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        self.dialog_opt = Some(Dialog::Destination(destination_state));
+    }
+
+    /// HSM groups the nodes selected can be moved to
+    fn get_destination_hsm_group_name_vec(&self) -> Vec<String> {
+        let from_hsm_group_name = self
+            .get_hsm_group_snapshot()
+            .map(|hsm_group_snapshot| hsm_group_snapshot.label.clone())
+            .unwrap_or_default();
+
+        self.hsm_group_snapshot_vec
+            .iter()
+            .map(|hsm_group_snapshot| hsm_group_snapshot.label.clone())
+            .filter(|hsm_group_name| hsm_group_name.ne(&from_hsm_group_name))
+            .collect()
+    }
+
+    /// Replaces the pending node moves of the nodes selected with a move to `to_hsm_group_name`
+    fn add_node_move_vec(&mut self, to_hsm_group_name: &str) {
+        let Some(from_hsm_group_name) = self
+            .get_hsm_group_snapshot()
+            .map(|hsm_group_snapshot| hsm_group_snapshot.label.clone())
+        else {
+            return;
+        };
+
+        let selected_xname_set = std::mem::take(&mut self.selected_xname_set);
+
+        self.node_move_vec.retain(|node_move| {
+            !(node_move.from_hsm_group.eq(&from_hsm_group_name)
+                && selected_xname_set.contains(&node_move.xname))
+        });
+
+        self.node_move_vec.extend(
+            selected_xname_set
+                .iter()
+                .map(|xname| NodeMove::new(xname, &from_hsm_group_name, to_hsm_group_name)),
+        );
+
+        self.status_opt = Some(format!(
+            "{} nodes to move from '{}' to '{}', press 'a' to apply",
+            selected_xname_set.len(),
+            from_hsm_group_name,
+            to_hsm_group_name
+        ));
+    }
+
+    fn move_cursor(&mut self, offset: isize) {
+        let (list_state, len) = match self.focus {
+            Focus::HsmGroups => (&mut self.hsm_group_state, self.hsm_group_snapshot_vec.len()),
+            Focus::Nodes => {
+                let len = self
+                    .get_hsm_group_snapshot()
+                    .map(|hsm_group_snapshot| hsm_group_snapshot.members.len())
+                    .unwrap_or(0);
+                (&mut self.node_state, len)
+            }
+        };
+
+        if len == 0 {
+            return;
+        }
+
+        let index = list_state
+            .selected()
+            .unwrap_or(0)
+            .saturating_add_signed(offset)
+            .min(len - 1);
+        list_state.select(Some(index));
+
+        // Selection is per HSM group
+        if self.focus == Focus::HsmGroups {
+            self.selected_xname_set.clear();
+            self.node_state.select(Some(0));
+        }
+    }
+
+    fn toggle_node_selection(&mut self) {
+        let Some(xname) = self
+            .get_hsm_group_snapshot()
+            .and_then(|hsm_group_snapshot| {
+                self.node_state
+                    .selected()
+                    .and_then(|index| hsm_group_snapshot.members.get(index))
+                    .cloned()
+            })
+        else {
+            return;
+        };
+
+        if self.excluded_xname_vec.contains(&xname) {
+            self.status_opt = Some(format!(
+                "Node '{}' is excluded in configuration file, it can't be moved",
+                xname
+            ));
+        } else if !self.selected_xname_set.remove(&xname) {
+            self.selected_xname_set.insert(xname);
+        }
+
+        self.move_cursor(1);
+    }
+}
+
+fn draw(frame: &mut Frame, dashboard: &mut Dashboard) {
+    let [header_area, body_area, bottom_area, footer_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(8),
+        Constraint::Length(RECENT_HISTORY_ENTRY_COUNT as u16 + 2),
+        Constraint::Length(1),
+    ])
+    .areas(frame.size());
+
+    let [hsm_group_area, node_area] =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .areas(body_area);
+
+    let [node_move_area, history_area] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .areas(bottom_area);
+
+    draw_free_pool(frame, header_area, dashboard);
+    draw_hsm_group_list(frame, hsm_group_area, dashboard);
+    draw_node_list(frame, node_area, dashboard);
+    draw_node_move_list(frame, node_move_area, dashboard);
+    draw_history(frame, history_area, dashboard);
+
+    frame.render_widget(
+        Paragraph::new(dashboard.status_opt.as_deref().unwrap_or(HELP))
+            .style(Style::default().add_modifier(Modifier::DIM)),
+        footer_area,
+    );
+
+    match dashboard.dialog_opt.as_mut() {
+        Some(Dialog::Destination(_)) => {
+            let destination_hsm_group_name_vec = dashboard.get_destination_hsm_group_name_vec();
+            let Some(Dialog::Destination(destination_state)) = dashboard.dialog_opt.as_mut() else {
+                return;
+            };
+            draw_destination_dialog(frame, &destination_hsm_group_name_vec, destination_state);
+        }
+        Some(Dialog::ConfirmApply) => draw_confirm_apply_dialog(frame, dashboard),
+        None => {}
+    }
+}
+
+fn new_block(title: String, focused: bool) -> Block<'static> {
+    let border_style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(title)
+}
+
+fn draw_free_pool(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let free_pool = match dashboard
+        .hsm_group_snapshot_vec
+        .iter()
+        .find(|hsm_group_snapshot| hsm_group_snapshot.label.eq(FREE_POOL_HSM_GROUP_NAME))
+    {
+        Some(hsm_group_snapshot) => format!(
+            "{} nodes  {}",
+            hsm_group_snapshot.members.len(),
+            dashboard.get_hsm_pattern(hsm_group_snapshot)
+        ),
+        None => format!("HSM group '{}' not available", FREE_POOL_HSM_GROUP_NAME),
+    };
+
+    frame.render_widget(
+        Paragraph::new(free_pool).block(new_block("Free pool".to_string(), false)),
+        area,
+    );
+}
+
+fn draw_hsm_group_list(frame: &mut Frame, area: Rect, dashboard: &mut Dashboard) {
+    let item_vec: Vec<ListItem> = dashboard
+        .hsm_group_snapshot_vec
+        .iter()
+        .map(|hsm_group_snapshot| {
+            ListItem::new(format!(
+                "{} ({})",
+                hsm_group_snapshot.label,
+                hsm_group_snapshot.members.len()
+            ))
+        })
+        .collect();
+
+    let list = List::new(item_vec)
+        .block(new_block(
+            "HSM groups".to_string(),
+            dashboard.focus == Focus::HsmGroups,
+        ))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(list, area, &mut dashboard.hsm_group_state);
+}
+
+fn draw_node_list(frame: &mut Frame, area: Rect, dashboard: &mut Dashboard) {
+    let Some(hsm_group_snapshot) = dashboard.get_hsm_group_snapshot().cloned() else {
+        frame.render_widget(new_block("Nodes".to_string(), false), area);
+        return;
+    };
+
+    let [summary_area, list_area] =
+        Layout::vertical([Constraint::Length(4), Constraint::Min(0)]).areas(area);
+
+    frame.render_widget(
+        Paragraph::new(dashboard.get_hsm_pattern(&hsm_group_snapshot))
+            .wrap(Wrap { trim: true })
+            .block(new_block(
+                format!("{} hw components", hsm_group_snapshot.label),
+                false,
+            )),
+        summary_area,
+    );
+
+    let item_vec: Vec<ListItem> = hsm_group_snapshot
+        .members
+        .iter()
+        .map(|xname| {
+            let checkbox = if dashboard.excluded_xname_vec.contains(xname) {
+                Span::styled("[-] ", Style::default().add_modifier(Modifier::DIM))
+            } else if dashboard.selected_xname_set.contains(xname) {
+                Span::styled("[x] ", Style::default().fg(Color::Yellow))
+            } else {
+                Span::raw("[ ] ")
+            };
+
+            // Node hw components, without the xname prefix
+            let hw_component_summary = dashboard
+                .node_summary_hashmap
+                .get(xname)
+                .map(|node_summary| {
+                    get_hsm_pattern::get_hsm_pattern("", vec![node_summary.clone()])
+                        .trim_start_matches(':')
+                        .to_string()
+                })
+                .unwrap_or("no hw inventory".to_string());
+
+            let mut span_vec = vec![
+                checkbox,
+                Span::raw(format!("{:<16}", xname)),
+                Span::styled(hw_component_summary, Style::default().fg(Color::Cyan)),
+            ];
+
+            if let Some(node_move) = dashboard.node_move_vec.iter().find(|node_move| {
+                node_move.xname.eq(xname) && node_move.from_hsm_group.eq(&hsm_group_snapshot.label)
+            }) {
+                span_vec.push(Span::styled(
+                    format!("  → {}", node_move.to_hsm_group),
+                    Style::default().fg(Color::Green),
+                ));
+            }
+
+            ListItem::new(Line::from(span_vec))
+        })
+        .collect();
+
+    let list = List::new(item_vec)
+        .block(new_block(
+            format!("Nodes ({} selected)", dashboard.selected_xname_set.len()),
+            dashboard.focus == Focus::Nodes,
+        ))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(list, list_area, &mut dashboard.node_state);
+}
+
+fn draw_node_move_list(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let item_vec: Vec<ListItem> = dashboard
+        .node_move_vec
+        .iter()
+        .map(|node_move| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<16}", node_move.xname)),
+                Span::styled(
+                    node_move.from_hsm_group.clone(),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::raw(" → "),
+                Span::styled(
+                    node_move.to_hsm_group.clone(),
+                    Style::default().fg(Color::Green),
+                ),
+            ]))
+        })
+        .collect();
+
+    frame.render_widget(
+        List::new(item_vec).block(new_block(
+            format!("Pending node moves ({})", dashboard.node_move_vec.len()),
+            false,
+        )),
+        area,
+    );
+}
+
+fn draw_history(frame: &mut Frame, area: Rect, dashboard: &Dashboard) {
+    let item_vec: Vec<ListItem> = dashboard
+        .history_entry_vec
+        .iter()
+        .map(|history_entry| {
+            let status = match &history_entry.error {
+                Some(_) if history_entry.rolled_back => {
+                    Span::styled("rolled back", Style::default().fg(Color::Yellow))
+                }
+                Some(_) => Span::styled("failed", Style::default().fg(Color::Red)),
+                None => Span::styled("applied", Style::default().fg(Color::Green)),
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(
+                    "#{:<4} {} {} {} nodes  ",
+                    history_entry.id,
+                    history_entry.timestamp,
+                    history_entry.command,
+                    history_entry.node_moves.len()
+                )),
+                status,
+            ]))
+        })
+        .collect();
+
+    frame.render_widget(
+        List::new(item_vec).block(new_block("Recent changes".to_string(), false)),
+        area,
+    );
+}
+
+/// Rectangle of `width` x `height` centered in `area`
+fn get_centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn draw_destination_dialog(
+    frame: &mut Frame,
+    destination_hsm_group_name_vec: &[String],
+    destination_state: &mut ListState,
+) {
+    let area = get_centered_rect(
+        frame.size(),
+        50,
+        destination_hsm_group_name_vec.len() as u16 + 2,
+    );
+
+    let list = List::new(destination_hsm_group_name_vec.to_vec())
+        .block(new_block(
+            "Move selected nodes to (enter, esc cancels)".to_string(),
+            true,
+        ))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, destination_state);
+}
+
+fn draw_confirm_apply_dialog(frame: &mut Frame, dashboard: &Dashboard) {
+    let hsm_group_name_vec = history_ops::get_hsm_group_name_vec(&dashboard.node_move_vec);
+
+    let area = get_centered_rect(frame.size(), 60, 7);
+
+    let paragraph = Paragraph::new(vec![
+        Line::from(format!(
+            "Apply {} node moves?",
+            dashboard.node_move_vec.len()
+        )),
+        Line::from(format!(
+            "HSM groups changed: {}",
+            hsm_group_name_vec.join(", ")
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            "y applies, any other key cancels",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ])
+    .wrap(Wrap { trim: true })
+    .block(new_block("Confirm".to_string(), true));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,144 @@
+use crate::{
+    cli::common::{
+        node_migration_ops::{self, NodeMove},
+        resolver_ops,
+        slurm_ops::{self, SlurmSettings},
+    },
+    common::hostlist_ops::expand_hostlist,
+};
+
+/// Moves the nodes in `xnames` (hostlist expression, eg `x1001c1s5b0n[0-1]`) from one HSM group
+/// to another. All nodes must be members of the source HSM group
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    from_hsm_group_name: &String,
+    to_hsm_group_name: &String,
+    xnames: &str,
+    dry_run: bool,
+    assume_yes: bool,
+    slurm_settings: &SlurmSettings,
+    check_slurm: bool,
+    slurm_sync_opt: Option<&String>,
+) {
+    let xname_vec = expand_hostlist(xnames).unwrap_or_else(|error| {
+        eprintln!("Could not expand xnames '{}': {}. Exit", xnames, error);
+        std::process::exit(1);
+    });
+
+    // Nodes may be given by NID or hostname too
+    let xname_vec = resolver_ops::resolve_xname_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &xname_vec,
+        "--xnames",
+    )
+    .await;
+
+    if from_hsm_group_name.eq(to_hsm_group_name) {
+        eprintln!("Source and destination HSM groups must be different. Exit");
+        std::process::exit(1);
+    }
+
+    let from_hsm_group_member_vec = get_hsm_group_member_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        from_hsm_group_name,
+    )
+    .await;
+
+    // Check destination HSM group exists
+    get_hsm_group_member_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        to_hsm_group_name,
+    )
+    .await;
+
+    let xname_not_member_vec: Vec<&String> = xname_vec
+        .iter()
+        .filter(|xname| !from_hsm_group_member_vec.contains(xname))
+        .collect();
+
+    if !xname_not_member_vec.is_empty() {
+        eprintln!(
+            "Nodes {:?} are not members of HSM group '{}'. Exit",
+            xname_not_member_vec, from_hsm_group_name
+        );
+        std::process::exit(1);
+    }
+
+    let node_move_vec: Vec<NodeMove> = xname_vec
+        .iter()
+        .map(|xname| NodeMove::new(xname, from_hsm_group_name, to_hsm_group_name))
+        .collect();
+
+    let busy_xname_vec = slurm_ops::get_busy_xname_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        Some(slurm_settings).filter(|_| check_slurm),
+    )
+    .await;
+
+    slurm_ops::exit_if_busy_node_moved(&node_move_vec, &busy_xname_vec);
+
+    if node_migration_ops::confirm_and_apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move_vec,
+        &format!(
+            "update hsm-group move --from {} --to {} --xnames {}",
+            from_hsm_group_name, to_hsm_group_name, xnames
+        ),
+        dry_run,
+        assume_yes,
+    )
+    .await
+    {
+        slurm_ops::sync_slurm_partition_vec_or_exit(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &node_move_vec,
+            slurm_sync_opt,
+            slurm_settings,
+        )
+        .await;
+    }
+}
+
+async fn get_hsm_group_member_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &String,
+) -> Vec<String> {
+    let hsm_group_value_vec =
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            Some(hsm_group_name),
+        ))
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not get HSM groups: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+    match hsm_group_value_vec.first() {
+        Some(hsm_group_value) => {
+            mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(hsm_group_value)
+        }
+        None => {
+            eprintln!("HSM group '{}' not found. Exit", hsm_group_name);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::commands::{
+        apply_hsm_based_on_component_quantity::utils::get_xname_location_vec,
+        diff_inventory::calculate_memory_capacity_mib, export_inventory,
+        get_nodes_artifacts::NodeSummary,
+    },
+    common::{format_ops::format_count, table_ops, xname},
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NodeAnomaly {
+    pub xname: String,
+    pub metric: String,
+    pub value: u64,
+    pub expected: u64,
+    /// Blade or HSM group the node was compared against
+    pub scope: String,
+}
+
+/// Flags nodes in a HSM group which DIMM count, total memory capacity or GPU count differs from
+/// the majority of nodes in its blade or, if the blade has no majority, in the HSM group. Exits
+/// with code 1 if any anomaly is found
+pub async fn exec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &String,
+    output_opt: Option<&String>,
+) {
+    let inventory_snapshot = export_inventory::get_inventory_snapshot(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        Some(hsm_group_name),
+    )
+    .await;
+
+    let node_anomaly_vec = calculate_node_anomaly_vec(&inventory_snapshot.nodes, hsm_group_name);
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&node_anomaly_vec).unwrap()
+        );
+    } else if node_anomaly_vec.is_empty() {
+        println!(
+            "No anomalies found in {} nodes of HSM group '{}'",
+            format_count(inventory_snapshot.nodes.len()),
+            hsm_group_name
+        );
+    } else {
+        print_table(&node_anomaly_vec);
+    }
+
+    if !node_anomaly_vec.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Metrics compared between nodes, same order as get_node_metric_value_vec
+const NODE_METRIC_VEC: [&str; 3] = ["dimm count", "memory (MiB)", "gpu count"];
+
+fn get_node_metric_value_vec(node_summary: &NodeSummary) -> [u64; 3] {
+    [
+        node_summary.memory.len() as u64,
+        calculate_memory_capacity_mib(node_summary),
+        node_summary.node_accels.len() as u64,
+    ]
+}
+
+/// Returns the value shared by more than half of the values, if any
+fn get_majority_value(value_vec: &[u64]) -> Option<u64> {
+    let mut value_count_hashmap: HashMap<u64, usize> = HashMap::new();
+
+    for value in value_vec {
+        *value_count_hashmap.entry(*value).or_insert(0) += 1;
+    }
+
+    value_count_hashmap
+        .into_iter()
+        .find(|(_, count)| *count * 2 > value_vec.len())
+        .map(|(value, _)| value)
+}
+
+pub fn calculate_node_anomaly_vec(
+    node_summary_vec: &[NodeSummary],
+    hsm_group_name: &str,
+) -> Vec<NodeAnomaly> {
+    // Blade xname (xXcCsSbB) -> nodes
+    let mut blade_node_summary_hashmap: BTreeMap<String, Vec<&NodeSummary>> = BTreeMap::new();
+
+    for node_summary in node_summary_vec {
+        let blade = get_xname_location_vec(&node_summary.xname)
+            .into_iter()
+            .take(4)
+            .collect::<Vec<String>>()
+            .join("");
+
+        blade_node_summary_hashmap
+            .entry(blade)
+            .or_default()
+            .push(node_summary);
+    }
+
+    let mut node_anomaly_vec = Vec::new();
+
+    for (metric_index, metric) in NODE_METRIC_VEC.iter().enumerate() {
+        let hsm_group_value_vec: Vec<u64> = node_summary_vec
+            .iter()
+            .map(|node_summary| get_node_metric_value_vec(node_summary)[metric_index])
+            .collect();
+
+        let hsm_group_majority_value_opt = get_majority_value(&hsm_group_value_vec);
+
+        for (blade, blade_node_summary_vec) in &blade_node_summary_hashmap {
+            let blade_value_vec: Vec<u64> = blade_node_summary_vec
+                .iter()
+                .map(|node_summary| get_node_metric_value_vec(node_summary)[metric_index])
+                .collect();
+
+            // Blades with a single node or without majority are compared against the HSM group
+            let (majority_value_opt, scope) = match get_majority_value(&blade_value_vec) {
+                Some(blade_majority_value) if blade_value_vec.len() > 1 => {
+                    (Some(blade_majority_value), format!("blade {}", blade))
+                }
+                _ => (
+                    hsm_group_majority_value_opt,
+                    format!("HSM group {}", hsm_group_name),
+                ),
+            };
+
+            let Some(majority_value) = majority_value_opt else {
+                continue;
+            };
+
+            for (node_summary, value) in blade_node_summary_vec.iter().zip(blade_value_vec) {
+                if value != majority_value {
+                    node_anomaly_vec.push(NodeAnomaly {
+                        xname: node_summary.xname.clone(),
+                        metric: metric.to_string(),
+                        value,
+                        expected: majority_value,
+                        scope: scope.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    node_anomaly_vec.sort_by(|a, b| xname::compare_xnames(&a.xname, &b.xname));
+
+    node_anomaly_vec
+}
+
+pub fn print_table(node_anomaly_vec: &[NodeAnomaly]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec![
+        "Node",
+        "Metric",
+        "Value",
+        "Expected",
+        "Compared against",
+    ]);
+
+    for node_anomaly in node_anomaly_vec {
+        table.add_row(vec![
+            Cell::new(&node_anomaly.xname),
+            Cell::new(&node_anomaly.metric),
+            Cell::new(format_count(node_anomaly.value)).fg(Color::Red),
+            Cell::new(format_count(node_anomaly.expected)),
+            Cell::new(&node_anomaly.scope),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::get_nodes_artifacts::{ArtifactSummary, ArtifactType};
+
+    fn node_summary(xname: &str, dimm_count: usize) -> NodeSummary {
+        NodeSummary {
+            xname: xname.to_string(),
+            r#type: "Node".to_string(),
+            processors: Vec::new(),
+            memory: (0..dimm_count)
+                .map(|index| ArtifactSummary {
+                    xname: format!("{}d{}", xname, index),
+                    r#type: ArtifactType::Memory,
+                    info: Some("32768 MiB".to_string()),
+                    serial_number: None,
+                    part_number: None,
+                    core_count: None,
+                    max_speed_mhz: None,
+                    gpu_memory: None,
+                })
+                .collect(),
+            node_accels: Vec::new(),
+            node_hsn_nics: Vec::new(),
+            state: None,
+            flag: None,
+            enabled: None,
+        }
+    }
+
+    #[test]
+    fn test_node_with_missing_dimm_is_flagged() {
+        let node_summary_vec = vec![
+            node_summary("x1000c0s0b0n0", 16),
+            node_summary("x1000c0s0b0n1", 15),
+            node_summary("x1000c0s0b1n0", 16),
+            node_summary("x1000c0s1b0n0", 16),
+        ];
+
+        let node_anomaly_vec = calculate_node_anomaly_vec(&node_summary_vec, "zinal");
+
+        assert_eq!(node_anomaly_vec.len(), 2);
+        assert!(node_anomaly_vec
+            .iter()
+            .all(|node_anomaly| node_anomaly.xname == "x1000c0s0b0n1"
+                && node_anomaly.scope == "HSM group zinal"));
+        assert_eq!(node_anomaly_vec[0].expected, 16);
+    }
+}
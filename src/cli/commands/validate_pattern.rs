@@ -0,0 +1,48 @@
+use comfy_table::Cell;
+
+use crate::{
+    cli::commands::apply_hsm_based_on_component_quantity::utils::{
+        parse_pattern_with_errors, print_pattern_error_vec,
+    },
+    common::table_ops,
+};
+
+/// Checks the syntax of a pattern without talking to CSM, this is, HSM group name, hw components
+/// followed by their counter, hw components repeated and hw profiles not defined in configuration
+/// file. Prints the hw component counters requested or, if the pattern is not valid, each error
+/// pointing at its position in the pattern and exits with error code
+pub fn exec(pattern: &str) {
+    let (target_hsm_group_name, hw_component_qualified_count_hashmap, pattern_error_vec) =
+        parse_pattern_with_errors(pattern);
+
+    if !pattern_error_vec.is_empty() {
+        print_pattern_error_vec(pattern, &pattern_error_vec);
+
+        eprintln!(
+            "Pattern not valid, {} errors. Exit",
+            pattern_error_vec.len()
+        );
+        std::process::exit(1);
+    }
+
+    let mut hw_component_qualified_count_vec: Vec<_> =
+        hw_component_qualified_count_hashmap.into_iter().collect();
+    hw_component_qualified_count_vec
+        .sort_by(|(hw_component_1, _), (hw_component_2, _)| hw_component_1.cmp(hw_component_2));
+
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Hw component", "Qualifier", "Counter"]);
+
+    for (hw_component, (qty, qualifier)) in hw_component_qualified_count_vec {
+        table.add_row(vec![
+            Cell::new(hw_component),
+            Cell::new(qualifier.as_str()),
+            Cell::new(qty),
+        ]);
+    }
+
+    println!("HSM group '{}'", target_hsm_group_name);
+    println!("{table}");
+    println!("Pattern valid");
+}
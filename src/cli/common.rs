@@ -0,0 +1,12 @@
+//! CLI side of the `clstr_core::common` modules: helpers printing to the terminal, asking the
+//! user or exiting on error. Each module re-exports its `clstr_core::common` counterpart
+
+pub mod firmware_ops;
+pub mod history_ops;
+pub mod lock_ops;
+pub mod node_migration_ops;
+pub mod partition_ops;
+pub mod power_ops;
+pub mod resolver_ops;
+pub mod slurm_ops;
+pub mod tenant_ops;
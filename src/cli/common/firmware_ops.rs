@@ -0,0 +1,55 @@
+use comfy_table::Cell;
+
+pub use crate::common::firmware_ops::*;
+
+use crate::common::table_ops;
+
+/// Prints the firmware versions of the nodes after their artifacts (`--with-firmware`). Artifacts
+/// are already printed so FAS errors are only logged
+pub async fn print_firmware_version_vec_after_artifacts(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_xname_vec: &[String],
+    output_opt: Option<&String>,
+) {
+    let firmware_version_vec = match get_firmware_version_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_xname_vec,
+    )
+    .await
+    {
+        Ok(firmware_version_vec) => firmware_version_vec,
+        Err(error) => {
+            log::error!("Could not get firmware versions from FAS: {}", error);
+            return;
+        }
+    };
+
+    if output_opt.is_some() && output_opt.unwrap().eq("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&firmware_version_vec).unwrap()
+        );
+    } else {
+        print_firmware_version_table(&firmware_version_vec);
+    }
+}
+
+pub fn print_firmware_version_table(firmware_version_vec: &[FirmwareVersion]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Node", "Firmware target", "Version"]);
+
+    for firmware_version in firmware_version_vec {
+        table.add_row(vec![
+            Cell::new(&firmware_version.xname),
+            Cell::new(&firmware_version.target),
+            Cell::new(&firmware_version.version),
+        ]);
+    }
+
+    println!("{table}");
+}
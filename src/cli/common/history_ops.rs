@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+pub use crate::common::history_ops::*;
+
+/// Like `rewind_hsm_group_value_vec` with the history file. HSM groups are returned as they are
+/// if `as_of_opt` is None. Exits if the history can't be read
+pub fn rewind_hsm_group_value_vec_or_exit(
+    hsm_group_value_vec: Vec<Value>,
+    as_of_opt: Option<&DateTime<Utc>>,
+) -> Vec<Value> {
+    rewind_hsm_group_value_vec_as_of(hsm_group_value_vec, as_of_opt).unwrap_or_else(|error| {
+        eprintln!("Could not rewind HSM groups: {}. Exit", error);
+        std::process::exit(1);
+    })
+}
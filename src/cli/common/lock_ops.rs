@@ -0,0 +1,11 @@
+use std::fs::File;
+
+pub use crate::common::lock_ops::*;
+
+/// Locks the HSM groups, exits if any of them is locked by another run
+pub fn lock_hsm_group_vec_or_exit(hsm_group_name_vec: &[String]) -> Vec<File> {
+    lock_hsm_group_vec(hsm_group_name_vec).unwrap_or_else(|error| {
+        eprintln!("Could not lock HSM groups: {}. Exit", error);
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,136 @@
+use comfy_table::{Cell, Color};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+pub use crate::common::node_migration_ops::*;
+
+use crate::common::{
+    event_ops::{self, ChangeEvent},
+    format_ops::format_count,
+    table_ops,
+};
+
+pub fn print_node_move_table(node_move_vec: &[NodeMove]) {
+    let mut table = table_ops::new_table();
+
+    table.set_header(vec!["Node", "From HSM group", "To HSM group"]);
+
+    for node_move in node_move_vec {
+        table.add_row(vec![
+            Cell::new(&node_move.xname),
+            Cell::new(&node_move.from_hsm_group).fg(Color::Red),
+            Cell::new(&node_move.to_hsm_group).fg(Color::Green),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// Prints the node moves and asks the user to confirm them, unless `assume_yes`. Returns false if
+/// there is nothing to apply, `dry_run` is set or the user cancels
+pub fn confirm_node_move_vec(node_move_vec: &[NodeMove], dry_run: bool, assume_yes: bool) -> bool {
+    if node_move_vec.is_empty() {
+        println!("No changes needed in HSM groups");
+        return false;
+    }
+
+    event_ops::emit(ChangeEvent::PlanCreated {
+        node_moves: node_move_vec.to_vec(),
+    });
+
+    println!("\n----------------");
+    println!("----- PLAN -----");
+    println!("----------------\n");
+
+    print_node_move_table(node_move_vec);
+
+    if dry_run {
+        println!(
+            "Dry run, {} node moves not applied",
+            format_count(node_move_vec.len())
+        );
+        return false;
+    }
+
+    if assume_yes {
+        return true;
+    }
+
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "Apply {} node moves?",
+            format_count(node_move_vec.len())
+        ))
+        .default(false)
+        .interact()
+        .unwrap_or_else(|error| {
+            eprintln!("Could not read confirmation: {}. Exit", error);
+            std::process::exit(1);
+        });
+
+    if !confirmed {
+        println!("Operation cancelled by user");
+    }
+
+    confirmed
+}
+
+pub fn print_history_id(history_id_opt: Option<u64>) {
+    if let Some(history_id) = history_id_opt {
+        println!("Changes recorded in history entry {}", history_id);
+    }
+}
+
+/// Confirms and applies the node moves, exits if any move fails. Returns true if the node moves
+/// were applied
+pub async fn confirm_and_apply_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    command: &str,
+    dry_run: bool,
+    assume_yes: bool,
+) -> bool {
+    if !confirm_node_move_vec(node_move_vec, dry_run, assume_yes) {
+        return false;
+    }
+
+    apply_node_move_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+        command,
+    )
+    .await;
+
+    true
+}
+
+/// Applies the node moves, exits if any move fails
+pub async fn apply_node_move_vec_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    command: &str,
+) {
+    match apply_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+        command,
+    )
+    .await
+    {
+        Ok(history_id_opt) => {
+            print_history_id(history_id_opt);
+            println!("{} nodes moved", format_count(node_move_vec.len()))
+        }
+        Err(error) => {
+            eprintln!("Failed applying node moves: {}. Exit", error);
+            std::process::exit(1);
+        }
+    }
+}
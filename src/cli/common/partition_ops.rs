@@ -0,0 +1,44 @@
+pub use crate::common::partition_ops::*;
+
+/// Members of the HSM partition, exits if the partition can't be fetched
+pub async fn get_partition_member_vec_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    partition_name: &str,
+) -> Vec<String> {
+    get_hsm_partition_member_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        partition_name,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!(
+            "Could not get HSM partition '{}': {}. Exit",
+            partition_name, error
+        );
+        std::process::exit(1);
+    })
+}
+
+/// Like `get_xname_vec_outside_partition`, exits if the partition or the nodes can't be fetched
+pub async fn get_xname_vec_outside_partition_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    partition_name_opt: Option<&String>,
+) -> Vec<String> {
+    get_xname_vec_outside_partition(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        partition_name_opt,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get nodes outside HSM partition: {}. Exit", error);
+        std::process::exit(1);
+    })
+}
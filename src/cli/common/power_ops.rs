@@ -0,0 +1,63 @@
+pub use crate::common::power_ops::*;
+
+use crate::common::{format_ops::format_count, node_migration_ops::NodeMove};
+
+/// Exits if any node moved is not powered off
+pub async fn exit_if_node_moved_not_off(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+) {
+    let xname_not_off_vec = get_xname_moved_not_off_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("Could not get power state from PCS: {}. Exit", error);
+        std::process::exit(1);
+    });
+
+    if !xname_not_off_vec.is_empty() {
+        eprintln!(
+            "Nodes {:?} are not powered off and can't be moved (see --power-off-before-move). Exit",
+            xname_not_off_vec
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Powers off the nodes moved and waits until the transition finishes. Nodes already off are
+/// skipped
+pub async fn power_off_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let xname_on_vec = get_xname_moved_not_off_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+    )
+    .await?;
+
+    if xname_on_vec.is_empty() {
+        log::info!("Nodes moved already powered off");
+        return Ok(());
+    }
+
+    println!("Powering off {} nodes", format_count(xname_on_vec.len()));
+
+    power_off_xname_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &xname_on_vec,
+    )
+    .await
+}
@@ -0,0 +1,18 @@
+pub use crate::common::resolver_ops::*;
+
+/// Like `resolve_xname_vec`, exits if any name is unknown, `source` (eg '--exclude-xnames') is
+/// mentioned in the error
+pub async fn resolve_xname_vec_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    name_vec: &[String],
+    source: &str,
+) -> Vec<String> {
+    resolve_xname_vec(shasta_token, shasta_base_url, shasta_root_cert, name_vec)
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not resolve {} to xnames: {}. Exit", source, error);
+            std::process::exit(1);
+        })
+}
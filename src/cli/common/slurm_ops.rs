@@ -0,0 +1,90 @@
+pub use crate::common::slurm_ops::*;
+
+use crate::common::node_migration_ops::NodeMove;
+
+/// Nodes with jobs allocated if Slurm check is enabled (`--check-slurm` or `slurm.enabled`),
+/// exits if Slurm can't be queried
+pub async fn get_busy_xname_vec_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    slurm_settings_opt: Option<&SlurmSettings>,
+) -> Vec<String> {
+    let Some(slurm_settings) = slurm_settings_opt else {
+        return Vec::new();
+    };
+
+    let busy_xname_vec = get_busy_xname_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        slurm_settings,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!(
+            "Could not get nodes with jobs allocated from Slurm: {}. Exit",
+            error
+        );
+        std::process::exit(1);
+    });
+
+    log::info!("Nodes with jobs allocated in Slurm: {:?}", busy_xname_vec);
+
+    busy_xname_vec
+}
+
+/// Exits if any node move affects a node with jobs allocated
+pub fn exit_if_busy_node_moved(node_move_vec: &[NodeMove], busy_xname_vec: &[String]) {
+    let busy_node_move_vec = get_busy_node_move_vec(node_move_vec, busy_xname_vec);
+
+    if !busy_node_move_vec.is_empty() {
+        eprintln!(
+            "Nodes {:?} have jobs allocated in Slurm and can't be moved. Exit",
+            busy_node_move_vec
+                .iter()
+                .map(|node_move| &node_move.xname)
+                .collect::<Vec<&String>>()
+        );
+        std::process::exit(1);
+    }
+}
+
+/// Same as `sync_slurm_partition_vec`, exits on error. HSM groups are already changed at this
+/// point so the error message tells the user to sync Slurm partitions by hand
+pub async fn sync_slurm_partition_vec_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    slurm_sync_opt: Option<&String>,
+    slurm_settings: &SlurmSettings,
+) {
+    let Some(slurm_sync) = slurm_sync_opt else {
+        return;
+    };
+
+    match sync_slurm_partition_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+        slurm_sync,
+        slurm_settings,
+    )
+    .await
+    {
+        Ok(line_vec) => {
+            for line in line_vec {
+                println!("{}", line);
+            }
+        }
+        Err(error) => {
+            eprintln!(
+                "HSM groups updated but could not sync Slurm partitions: {}. Please update Slurm partitions manually. Exit",
+                error
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub use crate::common::tenant_ops::*;
+
+/// Like `check_hsm_group_vec`, exits if any HSM group is not available
+pub fn exit_if_hsm_group_not_available<S: AsRef<str>>(hsm_group_name_vec: &[S]) {
+    if let Err(error) = check_hsm_group_vec(hsm_group_name_vec) {
+        eprintln!("{}. Exit", error);
+        std::process::exit(1);
+    }
+}
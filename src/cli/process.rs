@@ -1,16 +1,282 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
 use clap::ArgMatches;
+use config::Config;
+
+use crate::{
+    cli::common::{lock_ops, partition_ops, resolver_ops, slurm_ops::SlurmSettings, tenant_ops},
+    common::hostlist_ops,
+};
 
 use super::commands::{
-    apply_hsm_based_on_component_quantity, get_hsm_artifacts, get_hsm_pattern, get_nodes_artifacts,
+    apply_cluster, apply_defrag, apply_hsm_based_on_component_quantity, apply_rebalance,
+    config_init, config_migrate, config_set, config_show, config_unset, config_validate,
+    create_hsm_group, delete_hsm_group, diff_fru, diff_inventory, export_ansible, export_assets,
+    export_inventory, export_prometheus, find_nodes, get_hsm_artifacts, get_hsm_list,
+    get_hsm_overlap, get_hsm_pattern, get_hsm_scores, get_hw_components, get_layout,
+    get_nodes_artifacts, get_nodes_firmware, get_nodes_membership, get_partitions, history,
+    permissions, pool_audit, reconcile, report_capacity, resolve, serve, tui,
+    update_hsm_group_move, validate_nodes, validate_pattern,
 };
 
+/// Processes `config` subcommands. These don't need to talk to CSM, therefore they run before
+/// authentication and without requiring a valid site
+pub async fn process_config_cli(
+    cli_config: &ArgMatches,
+    settings: &Config,
+    site_name_opt: Option<&String>,
+    config_error_opt: Option<&str>,
+) {
+    if cli_config.subcommand_matches("init").is_some() {
+        config_init::exec(settings).await;
+    } else if cli_config.subcommand_matches("migrate").is_some() {
+        config_migrate::exec();
+    } else if cli_config.subcommand_matches("show").is_some() {
+        config_show::exec(settings, site_name_opt, config_error_opt);
+    } else if let Some(cli_config_set) = cli_config.subcommand_matches("set") {
+        config_set::exec(
+            settings,
+            cli_config_set.get_one::<String>("KEY").unwrap(),
+            cli_config_set.get_one::<String>("VALUE").unwrap(),
+        );
+    } else if let Some(cli_config_unset) = cli_config.subcommand_matches("unset") {
+        config_unset::exec(cli_config_unset.get_one::<String>("KEY").unwrap());
+    } else if cli_config.subcommand_matches("validate").is_some() {
+        config_validate::exec(settings, site_name_opt, config_error_opt);
+    }
+}
+
+/// Processes `diff inventory` and `diff fru` between 2 snapshot files, these don't need to talk
+/// to CSM, therefore they run before authentication and without requiring a valid site
+pub fn process_diff_offline_cli(diff_name: &str, cli_diff_snapshot: &ArgMatches) {
+    let old_inventory_snapshot = diff_inventory::read_inventory_snapshot_or_exit(
+        cli_diff_snapshot.get_one::<String>("OLD").unwrap(),
+    );
+    let new_inventory_snapshot = diff_inventory::read_inventory_snapshot_or_exit(
+        cli_diff_snapshot.get_one::<String>("NEW").unwrap(),
+    );
+
+    if diff_name.eq("fru") {
+        diff_fru::exec(
+            &old_inventory_snapshot,
+            &new_inventory_snapshot,
+            cli_diff_snapshot.get_one::<String>("output"),
+        );
+    } else {
+        diff_inventory::exec(
+            &old_inventory_snapshot,
+            &new_inventory_snapshot,
+            cli_diff_snapshot.get_one::<String>("output"),
+        );
+    }
+}
+
+/// Processes `validate pattern`, pattern syntax does not depend on CSM therefore it runs before
+/// authentication and without requiring a valid site
+pub fn process_validate_pattern_cli(cli_validate_pattern: &ArgMatches) {
+    validate_pattern::exec(cli_validate_pattern.get_one::<String>("PATTERN").unwrap());
+}
+
+/// Processes `history` subcommands, history is a local file therefore they run before
+/// authentication and without requiring a valid site
+pub fn process_history_cli(cli_history: &ArgMatches) {
+    if let Some(cli_history_list) = cli_history.subcommand_matches("list") {
+        history::exec_list(cli_history_list.get_one::<String>("output"));
+    } else if let Some(cli_history_show) = cli_history.subcommand_matches("show") {
+        history::exec_show(
+            *cli_history_show.get_one::<u64>("ID").unwrap(),
+            cli_history_show.get_one::<String>("output"),
+        );
+    } else if let Some(cli_history_audit) = cli_history.subcommand_matches("audit") {
+        history::exec_audit(
+            cli_history_audit.get_one::<String>("user"),
+            cli_history_audit.get_flag("api-calls"),
+            cli_history_audit.get_one::<String>("output"),
+        );
+    }
+}
+
+/// Processes `serve`. It runs before authentication since each API request brings the CSM API
+/// token of its caller
+pub async fn process_serve_cli(
+    cli_serve: &ArgMatches,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    excluded_xname_vec: &[String],
+) {
+    serve::exec(
+        shasta_base_url,
+        shasta_root_cert,
+        cli_serve.get_one::<String>("listen").unwrap(),
+        cli_serve
+            .get_one::<String>("tls-cert")
+            .zip(cli_serve.get_one::<String>("tls-key")),
+        excluded_xname_vec,
+    )
+    .await;
+}
+
+/// Processes `reconcile`. It runs before authentication since the reconciler requests a new API
+/// token on each pass
+pub async fn process_reconcile_cli(
+    cli_reconcile: &ArgMatches,
+    settings: &Config,
+    site_name: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    keycloak_base_url: &str,
+    excluded_xname_vec: &[String],
+) {
+    let interval = reconcile::parse_interval(cli_reconcile.get_one::<String>("interval").unwrap())
+        .unwrap_or_else(|error| {
+            eprintln!("{}. Exit", error);
+            std::process::exit(1);
+        });
+
+    let lease_file_path = match cli_reconcile.get_one::<String>("lease-file") {
+        Some(lease_file_path) => PathBuf::from(lease_file_path),
+        None => {
+            let lease_file_path = reconcile::get_default_lease_file_path(site_name);
+
+            log::warn!(
+                "No --lease-file, lease file '{}' only excludes reconcilers on this host. Use a lease file on a file system shared by the reconcilers for redundancy",
+                lease_file_path.display()
+            );
+
+            lease_file_path
+        }
+    };
+
+    // Fail fast if the cluster definition is not valid
+    apply_cluster::read_cluster_definition_or_exit(
+        cli_reconcile.get_one::<String>("file").unwrap(),
+    );
+
+    reconcile::exec(
+        settings,
+        site_name,
+        shasta_base_url,
+        shasta_root_cert,
+        keycloak_base_url,
+        cli_reconcile.get_one::<String>("file").unwrap(),
+        interval,
+        &lease_file_path,
+        *cli_reconcile.get_one::<usize>("exact-max-nodes").unwrap(),
+        cli_reconcile.get_flag("apply"),
+        Some(&SlurmSettings::from_config(settings)).filter(|slurm_settings| slurm_settings.enabled),
+        excluded_xname_vec,
+    )
+    .await;
+}
+
+/// Expands the hostlist expression of an xnames argument and resolves NIDs and hostnames to
+/// xnames, exits if it is not valid. No xnames if the argument is not set
+async fn get_xname_vec_or_exit(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    cli_subcommand: &ArgMatches,
+    arg_name: &str,
+) -> Vec<String> {
+    match cli_subcommand.get_one::<String>(arg_name) {
+        Some(xnames) => {
+            let name_vec = hostlist_ops::expand_hostlist(xnames).unwrap_or_else(|error| {
+                eprintln!(
+                    "Could not expand --{} '{}': {}. Exit",
+                    arg_name, xnames, error
+                );
+                std::process::exit(1);
+            });
+
+            resolver_ops::resolve_xname_vec_or_exit(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                &name_vec,
+                &format!("--{}", arg_name),
+            )
+            .await
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Slurm is checked if `--check-slurm` is set or `slurm.enabled` is configured
+fn is_slurm_check_enabled(cli_subcommand: &ArgMatches, slurm_settings: &SlurmSettings) -> bool {
+    cli_subcommand.get_flag("check-slurm") || slurm_settings.enabled
+}
+
+/// Arguments holding HSM group names
+const HSM_GROUP_ARG_ID_VEC: [&str; 8] = [
+    "HSM_GROUP_NAME",
+    "hsm",
+    "parent",
+    "pool",
+    "from",
+    "to",
+    "from-hsm",
+    "groups",
+];
+
+/// Exits if the HSM group in configuration file, any HSM group argument of the subcommand or
+/// target HSM group of 'apply hsm-group' patterns is not in 'hsm_available'
+fn exit_if_cli_hsm_group_not_available(cli: &ArgMatches, hsm_group: Option<&String>) {
+    let mut hsm_group_name_vec: Vec<String> = hsm_group.into_iter().cloned().collect();
+
+    let mut cli_subcommand_opt = Some(cli);
+
+    while let Some(cli_subcommand) = cli_subcommand_opt {
+        for arg_id in HSM_GROUP_ARG_ID_VEC {
+            // Arguments not defined by the subcommand are errors
+            if let Ok(Some(value_iter)) = cli_subcommand.try_get_many::<String>(arg_id) {
+                hsm_group_name_vec.extend(value_iter.cloned());
+            }
+        }
+
+        cli_subcommand_opt = cli_subcommand
+            .subcommand()
+            .map(|(_, cli_subcommand)| cli_subcommand);
+    }
+
+    if let Some(pattern_iter) = cli
+        .subcommand_matches("apply")
+        .and_then(|cli_apply| cli_apply.subcommand_matches("hsm-group"))
+        .and_then(|cli_apply_hsm| cli_apply_hsm.get_many::<Vec<String>>("pattern"))
+    {
+        hsm_group_name_vec.extend(
+            pattern_iter
+                .flatten()
+                .filter_map(|pattern| pattern.split(':').next().map(str::to_string)),
+        );
+    }
+
+    tenant_ops::exit_if_hsm_group_not_available(&hsm_group_name_vec);
+}
+
 pub async fn process_cli(
     cli_apply: ArgMatches,
     shasta_token: &str,
     shasta_base_url: &str,
     shasta_root_cert: &[u8],
     hsm_group: Option<&String>,
+    decommissioned_rack_vec: &[String],
+    excluded_xname_vec: &[String],
+    slurm_settings: &SlurmSettings,
 ) -> core::result::Result<(), Box<dyn std::error::Error>> {
+    // 'excluded_xnames' in configuration file may list NIDs or hostnames too
+    let excluded_xname_vec: &[String] = &resolver_ops::resolve_xname_vec_or_exit(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        excluded_xname_vec,
+        "'excluded_xnames' in configuration file",
+    )
+    .await;
+
+    // 'hsm_available' in configuration file restricts the HSM groups operated
+    exit_if_cli_hsm_group_not_available(&cli_apply, hsm_group);
+
     if let Some(cli_get) = cli_apply.subcommand_matches("get") {
         if let Some(cli_get_node) = cli_get.subcommand_matches("nodes") {
             if let Some(cli_get_node_artifacts) = cli_get_node.subcommand_matches("artifacts") {
@@ -24,9 +290,69 @@ pub async fn process_cli(
                     shasta_base_url,
                     shasta_root_cert,
                     hsm_group_name,
-                    cli_get_node_artifacts.get_one::<String>("XNAME").unwrap(),
-                    cli_get_node_artifacts.get_one::<String>("type"),
+                    &resolver_ops::resolve_xname_vec_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        &[cli_get_node_artifacts
+                            .get_one::<String>("XNAME")
+                            .unwrap()
+                            .clone()],
+                        "XNAME",
+                    )
+                    .await[0],
+                    cli_get_node_artifacts
+                        .get_many::<String>("type")
+                        .map(|type_artifact| type_artifact.collect()),
                     cli_get_node_artifacts.get_one::<String>("output"),
+                    cli_get_node_artifacts.get_flag("with-firmware"),
+                    cli_get_node_artifacts.get_flag("wide"),
+                    cli_get_node_artifacts
+                        .get_many::<String>("fields")
+                        .map(|field| field.cloned().collect())
+                        .as_ref(),
+                )
+                .await;
+            } else if let Some(cli_get_node_firmware) = cli_get_node.subcommand_matches("firmware")
+            {
+                get_nodes_firmware::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    cli_get_node_firmware.get_one::<String>("XNAME").unwrap(),
+                    cli_get_node_firmware.get_one::<String>("output"),
+                )
+                .await;
+            } else if let Some(cli_get_node_membership) =
+                cli_get_node.subcommand_matches("membership")
+            {
+                let mut name_vec = Vec::new();
+
+                for xnames in cli_get_node_membership
+                    .get_many::<String>("XNAMES")
+                    .unwrap()
+                {
+                    name_vec.extend(hostlist_ops::expand_hostlist(xnames).unwrap_or_else(
+                        |error| {
+                            eprintln!("Could not expand XNAMES '{}': {}. Exit", xnames, error);
+                            std::process::exit(1);
+                        },
+                    ));
+                }
+
+                get_nodes_membership::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    &resolver_ops::resolve_xname_vec_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        &name_vec,
+                        "XNAMES",
+                    )
+                    .await,
+                    cli_get_node_membership.get_one::<String>("output"),
                 )
                 .await;
             }
@@ -34,6 +360,39 @@ pub async fn process_cli(
             if let Some(cli_get_hsm_groups_artifacts) =
                 cli_get_hsm_groups.subcommand_matches("artifacts")
             {
+                let field_vec_opt: Option<Vec<String>> = cli_get_hsm_groups_artifacts
+                    .get_many::<String>("fields")
+                    .map(|field| field.cloned().collect());
+
+                let partition_name_opt = match hsm_group {
+                    None => cli_get_hsm_groups_artifacts.get_one::<String>("partition"),
+                    Some(_) => None,
+                };
+
+                if let Some(partition_name) = partition_name_opt {
+                    get_hsm_artifacts::exec_member_vec(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        &format!("partition '{}'", partition_name),
+                        partition_ops::get_partition_member_vec_or_exit(
+                            shasta_token,
+                            shasta_base_url,
+                            shasta_root_cert,
+                            partition_name,
+                        )
+                        .await,
+                        cli_get_hsm_groups_artifacts.get_one::<String>("output"),
+                        field_vec_opt.as_ref(),
+                        cli_get_hsm_groups_artifacts.get_flag("with-firmware"),
+                        cli_get_hsm_groups_artifacts.get_flag("no-stream"),
+                        cli_get_hsm_groups_artifacts.get_flag("count"),
+                    )
+                    .await;
+
+                    return Ok(());
+                }
+
                 let hsm_group_name = match hsm_group {
                     None => cli_get_hsm_groups_artifacts
                         .get_one::<String>("HSM_GROUP_NAME")
@@ -45,38 +404,632 @@ pub async fn process_cli(
                     shasta_base_url,
                     shasta_root_cert,
                     hsm_group_name,
+                    cli_get_hsm_groups_artifacts.get_one::<DateTime<Utc>>("as-of"),
                     cli_get_hsm_groups_artifacts.get_one::<String>("output"),
+                    field_vec_opt.as_ref(),
+                    cli_get_hsm_groups_artifacts.get_flag("with-firmware"),
+                    cli_get_hsm_groups_artifacts.get_flag("no-stream"),
+                    cli_get_hsm_groups_artifacts.get_flag("count"),
                 )
                 .await;
             } else if let Some(cli_get_hsm_groups_pattern) =
                 cli_get_hsm_groups.subcommand_matches("pattern")
+            {
+                // Without HSM group name (or with --all) the pattern of each HSM group is printed
+                let hsm_group_name_opt = match hsm_group {
+                    None => cli_get_hsm_groups_pattern.get_one::<String>("HSM_GROUP_NAME"),
+                    Some(_) => hsm_group,
+                };
+                get_hsm_pattern::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    hsm_group_name_opt,
+                    cli_get_hsm_groups_pattern.get_one::<DateTime<Utc>>("as-of"),
+                )
+                .await;
+            } else if let Some(cli_get_hsm_groups_scores) =
+                cli_get_hsm_groups.subcommand_matches("scores")
             {
                 let hsm_group_name = match hsm_group {
-                    None => cli_get_hsm_groups_pattern
+                    None => cli_get_hsm_groups_scores
                         .get_one::<String>("HSM_GROUP_NAME")
                         .unwrap(),
                     Some(hsm_group_name_value) => hsm_group_name_value,
                 };
-                get_hsm_pattern::exec(
+                get_hsm_scores::exec(
                     shasta_token,
                     shasta_base_url,
                     shasta_root_cert,
                     hsm_group_name,
+                    cli_get_hsm_groups_scores
+                        .get_one::<String>("pattern")
+                        .unwrap(),
+                    cli_get_hsm_groups_scores.get_one::<String>("parent"),
+                    cli_get_hsm_groups_scores
+                        .get_one::<String>("strategy")
+                        .unwrap(),
+                    cli_get_hsm_groups_scores.get_one::<String>("output"),
+                )
+                .await;
+            } else if let Some(cli_get_hsm_groups_list) =
+                cli_get_hsm_groups.subcommand_matches("list")
+            {
+                get_hsm_list::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    hsm_group,
+                    cli_get_hsm_groups_list
+                        .get_many::<String>("pattern")
+                        .map(|pattern| pattern.collect()),
+                    cli_get_hsm_groups_list.get_one::<DateTime<Utc>>("as-of"),
+                    cli_get_hsm_groups_list.get_one::<String>("output"),
+                )
+                .await;
+            } else if let Some(cli_get_hsm_groups_overlap) =
+                cli_get_hsm_groups.subcommand_matches("overlap")
+            {
+                get_hsm_overlap::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    &cli_get_hsm_groups_overlap
+                        .get_many::<String>("ignore")
+                        .map(|hsm_group| hsm_group.collect::<Vec<&String>>())
+                        .unwrap_or_default(),
+                    cli_get_hsm_groups_overlap.get_one::<String>("output"),
                 )
                 .await;
             }
+        } else if let Some(cli_get_hw_components) = cli_get.subcommand_matches("hw-components") {
+            get_hw_components::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_get_hw_components.get_one::<String>("hsm").or(hsm_group),
+                cli_get_hw_components.get_one::<String>("output"),
+            )
+            .await;
+        } else if let Some(cli_get_layout) = cli_get.subcommand_matches("layout") {
+            get_layout::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_get_layout
+                    .get_one::<String>("hsm")
+                    .or(hsm_group)
+                    .unwrap(),
+            )
+            .await;
+        } else if let Some(cli_get_partitions) = cli_get.subcommand_matches("partitions") {
+            get_partitions::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_get_partitions.get_one::<String>("output"),
+            )
+            .await;
         }
     } else if let Some(cli_apply) = cli_apply.subcommand_matches("apply") {
-        if let Some(cli_apply_hsm) = cli_apply.subcommand_matches("hsm-group") {
+        if let Some(cli_apply_cluster) = cli_apply.subcommand_matches("cluster") {
+            apply_cluster::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_apply_cluster.get_one::<String>("file").unwrap(),
+                *cli_apply_cluster
+                    .get_one::<usize>("exact-max-nodes")
+                    .unwrap(),
+                cli_apply_cluster.get_flag("verbose"),
+                &[
+                    excluded_xname_vec,
+                    &get_xname_vec_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        cli_apply_cluster,
+                        "exclude-xnames",
+                    )
+                    .await,
+                    &partition_ops::get_xname_vec_outside_partition_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        cli_apply_cluster.get_one::<String>("partition"),
+                    )
+                    .await,
+                ]
+                .concat(),
+                cli_apply_cluster.get_flag("dry-run"),
+                cli_apply_cluster.get_flag("assume-yes"),
+                slurm_settings,
+                is_slurm_check_enabled(cli_apply_cluster, slurm_settings),
+                cli_apply_cluster.get_one::<String>("slurm-sync"),
+            )
+            .await;
+        } else if let Some(cli_apply_defrag) = cli_apply.subcommand_matches("defrag") {
+            apply_defrag::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_apply_defrag.get_one::<String>("pool").unwrap(),
+                &cli_apply_defrag
+                    .get_many::<String>("ignore")
+                    .map(|hsm_group| hsm_group.collect::<Vec<&String>>())
+                    .unwrap_or_default(),
+                &[
+                    excluded_xname_vec,
+                    &get_xname_vec_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        cli_apply_defrag,
+                        "exclude-xnames",
+                    )
+                    .await,
+                    &partition_ops::get_xname_vec_outside_partition_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        cli_apply_defrag.get_one::<String>("partition"),
+                    )
+                    .await,
+                ]
+                .concat(),
+                cli_apply_defrag.get_flag("dry-run"),
+                cli_apply_defrag.get_flag("assume-yes"),
+                slurm_settings,
+                is_slurm_check_enabled(cli_apply_defrag, slurm_settings),
+            )
+            .await;
+        } else if let Some(cli_apply_rebalance) = cli_apply.subcommand_matches("rebalance") {
+            let rebalance_target = match cli_apply_rebalance.get_many::<usize>("weights") {
+                Some(weight_vec) => {
+                    apply_rebalance::RebalanceTarget::Weights(weight_vec.copied().collect())
+                }
+                None => apply_rebalance::RebalanceTarget::Counts(
+                    cli_apply_rebalance
+                        .get_many::<usize>("counts")
+                        .unwrap()
+                        .copied()
+                        .collect(),
+                ),
+            };
+
+            let hsm_group_name_vec = cli_apply_rebalance
+                .get_many::<String>("groups")
+                .unwrap()
+                .cloned()
+                .collect::<Vec<String>>();
+
+            let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(&hsm_group_name_vec);
+
+            apply_rebalance::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                &hsm_group_name_vec,
+                rebalance_target,
+                &cli_apply_rebalance
+                    .get_many::<String>("hw-components")
+                    .map(|hw_component| hw_component.cloned().collect::<Vec<String>>())
+                    .unwrap_or_default(),
+                &[
+                    excluded_xname_vec,
+                    &get_xname_vec_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        cli_apply_rebalance,
+                        "exclude-xnames",
+                    )
+                    .await,
+                    &partition_ops::get_xname_vec_outside_partition_or_exit(
+                        shasta_token,
+                        shasta_base_url,
+                        shasta_root_cert,
+                        cli_apply_rebalance.get_one::<String>("partition"),
+                    )
+                    .await,
+                ]
+                .concat(),
+                cli_apply_rebalance.get_flag("dry-run"),
+                cli_apply_rebalance.get_flag("assume-yes"),
+                slurm_settings,
+                is_slurm_check_enabled(cli_apply_rebalance, slurm_settings),
+            )
+            .await;
+        } else if let Some(cli_apply_hsm) = cli_apply.subcommand_matches("hsm-group") {
+            if let Some(cli_apply_hsm_create) = cli_apply_hsm.subcommand_matches("create") {
+                create_hsm_group::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    cli_apply_hsm_create
+                        .get_one::<String>("HSM_GROUP_NAME")
+                        .unwrap(),
+                    cli_apply_hsm_create.get_one::<String>("description"),
+                    cli_apply_hsm_create
+                        .get_many::<String>("members")
+                        .map(|members| members.map(|xname| xname.trim().to_string()).collect())
+                        .unwrap_or_default(),
+                )
+                .await;
+
+                return Ok(());
+            }
+
+            let pattern_vec: Vec<String> = cli_apply_hsm
+                .get_many::<Vec<String>>("pattern")
+                .unwrap()
+                .flatten()
+                .cloned()
+                .collect();
+
+            if pattern_vec.len() > 1
+                && (cli_apply_hsm.contains_id("from-hsm")
+                    || cli_apply_hsm.contains_id("pin-xnames"))
+            {
+                eprintln!("--from-hsm and --pin-xnames only work with a single pattern. Exit");
+                std::process::exit(1);
+            }
+
+            let pattern_vec = match cli_apply_hsm.get_one::<String>("from-hsm") {
+                Some(source_hsm_group_name) => {
+                    vec![
+                        get_hsm_pattern::get_pattern_from_hsm_group_or_exit(
+                            shasta_token,
+                            shasta_base_url,
+                            shasta_root_cert,
+                            &pattern_vec[0],
+                            source_hsm_group_name,
+                        )
+                        .await,
+                    ]
+                }
+                None => pattern_vec,
+            };
+
+            let hsm_group_name_vec: Vec<String> = pattern_vec
+                .iter()
+                .map(|pattern| pattern.split(':').next().unwrap_or_default().to_string())
+                .chain(["nodes_free".to_string()])
+                .collect();
+
+            // Nodes are taken from and returned to the parent HSM group, it must be available
+            // too (same as 'serve')
+            tenant_ops::exit_if_hsm_group_not_available(&hsm_group_name_vec);
+
+            // Target and parent HSM groups are locked while planning and applying
+            let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(&hsm_group_name_vec);
+
+            let excluded_xname_vec = [
+                excluded_xname_vec,
+                &get_xname_vec_or_exit(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    cli_apply_hsm,
+                    "exclude-xnames",
+                )
+                .await,
+                &partition_ops::get_xname_vec_outside_partition_or_exit(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    cli_apply_hsm.get_one::<String>("partition"),
+                )
+                .await,
+            ]
+            .concat();
+
+            let pinned_xname_vec = get_xname_vec_or_exit(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_apply_hsm,
+                "pin-xnames",
+            )
+            .await;
+
             apply_hsm_based_on_component_quantity::exec(
                 shasta_token,
                 shasta_base_url,
                 shasta_root_cert,
-                cli_apply_hsm.get_one::<String>("pattern").unwrap(),
-                "nodes_free",
+                &pattern_vec,
+                &apply_hsm_based_on_component_quantity::PlanOptions {
+                    parent_hsm_group_name: "nodes_free",
+                    scoring_strategy_name: cli_apply_hsm.get_one::<String>("strategy").unwrap(),
+                    solver_objective_name: cli_apply_hsm.get_one::<String>("objective").unwrap(),
+                    solver_name: cli_apply_hsm.get_one::<String>("solver").unwrap(),
+                    exact_solver_max_nodes: *cli_apply_hsm
+                        .get_one::<usize>("exact-max-nodes")
+                        .unwrap(),
+                    verbose: cli_apply_hsm.get_flag("verbose"),
+                    explain: cli_apply_hsm.get_flag("explain"),
+                    excluded_xname_vec: &excluded_xname_vec,
+                    pinned_xname_vec: &pinned_xname_vec,
+                    whole_blade: cli_apply_hsm.get_flag("whole-blade"),
+                    as_of_opt: cli_apply_hsm.get_one::<DateTime<Utc>>("as-of"),
+                },
+                &apply_hsm_based_on_component_quantity::ApplyOptions {
+                    dry_run: cli_apply_hsm.get_flag("dry-run"),
+                    check: cli_apply_hsm.get_flag("check"),
+                    output_opt: cli_apply_hsm.get_one::<String>("output"),
+                    assume_yes: cli_apply_hsm.get_flag("assume-yes"),
+                    slurm_settings,
+                    check_slurm: is_slurm_check_enabled(cli_apply_hsm, slurm_settings),
+                    slurm_sync_opt: cli_apply_hsm.get_one::<String>("slurm-sync"),
+                    require_off: cli_apply_hsm.get_flag("require-off"),
+                    power_off_before_move: cli_apply_hsm.get_flag("power-off-before-move"),
+                    bos_sessiontemplate_name_opt: cli_apply_hsm.get_one::<String>("bos-template"),
+                    boot_image_id_opt: cli_apply_hsm.get_one::<String>("boot-image"),
+                    kernel_parameters_opt: cli_apply_hsm.get_one::<String>("kernel-parameters"),
+                    cfs_configuration_name_opt: cli_apply_hsm
+                        .get_one::<String>("desired-configuration"),
+                    wait: cli_apply_hsm.get_flag("wait"),
+                },
+            )
+            .await;
+        }
+    } else if let Some(cli_update) = cli_apply.subcommand_matches("update") {
+        if let Some(cli_update_hsm_group) = cli_update.subcommand_matches("hsm-group") {
+            if let Some(cli_update_hsm_group_move) = cli_update_hsm_group.subcommand_matches("move")
+            {
+                let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(&[
+                    cli_update_hsm_group_move
+                        .get_one::<String>("from")
+                        .unwrap()
+                        .clone(),
+                    cli_update_hsm_group_move
+                        .get_one::<String>("to")
+                        .unwrap()
+                        .clone(),
+                ]);
+
+                update_hsm_group_move::exec(
+                    shasta_token,
+                    shasta_base_url,
+                    shasta_root_cert,
+                    cli_update_hsm_group_move.get_one::<String>("from").unwrap(),
+                    cli_update_hsm_group_move.get_one::<String>("to").unwrap(),
+                    cli_update_hsm_group_move
+                        .get_one::<String>("xnames")
+                        .unwrap(),
+                    cli_update_hsm_group_move.get_flag("dry-run"),
+                    cli_update_hsm_group_move.get_flag("assume-yes"),
+                    slurm_settings,
+                    is_slurm_check_enabled(cli_update_hsm_group_move, slurm_settings),
+                    cli_update_hsm_group_move.get_one::<String>("slurm-sync"),
+                )
+                .await;
+            }
+        }
+    } else if let Some(cli_tui) = cli_apply.subcommand_matches("tui") {
+        tui::exec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            excluded_xname_vec,
+            cli_tui.get_flag("dry-run"),
+        )
+        .await;
+    } else if let Some(cli_undo) = cli_apply.subcommand_matches("undo") {
+        history::exec_undo(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            *cli_undo.get_one::<u64>("ID").unwrap(),
+            cli_undo.get_flag("dry-run"),
+            cli_undo.get_flag("assume-yes"),
+        )
+        .await;
+    } else if let Some(cli_delete) = cli_apply.subcommand_matches("delete") {
+        if let Some(cli_delete_hsm_group) = cli_delete.subcommand_matches("hsm-group") {
+            let _lock_file_vec = lock_ops::lock_hsm_group_vec_or_exit(&[cli_delete_hsm_group
+                .get_one::<String>("HSM_GROUP_NAME")
+                .unwrap()
+                .clone()]);
+
+            delete_hsm_group::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_delete_hsm_group
+                    .get_one::<String>("HSM_GROUP_NAME")
+                    .unwrap(),
+                cli_delete_hsm_group.get_flag("force"),
+                cli_delete_hsm_group.get_flag("assume-yes"),
+            )
+            .await;
+        }
+    } else if let Some(cli_export) = cli_apply.subcommand_matches("export") {
+        if let Some(cli_export_inventory) = cli_export.subcommand_matches("inventory") {
+            let hsm_group_name = match hsm_group {
+                None => cli_export_inventory.get_one::<String>("hsm"),
+                Some(_) => hsm_group,
+            };
+            export_inventory::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group_name,
+                cli_export_inventory.get_one::<String>("file"),
+                cli_export_inventory.get_one::<String>("format").unwrap(),
+            )
+            .await;
+        }
+        if let Some(cli_export_ansible) = cli_export.subcommand_matches("ansible") {
+            let hsm_group_name = match hsm_group {
+                None => cli_export_ansible.get_one::<String>("hsm"),
+                Some(_) => hsm_group,
+            };
+            export_ansible::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group_name,
+                cli_export_ansible.get_one::<String>("file"),
+                cli_export_ansible.get_one::<String>("format").unwrap(),
+            )
+            .await;
+        }
+        if let Some(cli_export_assets) = cli_export.subcommand_matches("assets") {
+            let hsm_group_name = match hsm_group {
+                None => cli_export_assets.get_one::<String>("hsm"),
+                Some(_) => hsm_group,
+            };
+            export_assets::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group_name,
+                cli_export_assets.get_one::<String>("file"),
+                cli_export_assets.get_one::<String>("format").unwrap(),
+                cli_export_assets
+                    .get_many::<String>("fields")
+                    .map(|field| field.cloned().collect())
+                    .as_ref(),
+            )
+            .await;
+        }
+        if let Some(cli_export_prometheus) = cli_export.subcommand_matches("prometheus") {
+            export_prometheus::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_export_prometheus.get_one::<String>("pool").unwrap(),
+                cli_export_prometheus.get_one::<String>("listen"),
+                cli_export_prometheus.get_one::<String>("textfile"),
+                *cli_export_prometheus.get_one::<u64>("interval").unwrap(),
+            )
+            .await;
+        }
+    } else if let Some(cli_diff) = cli_apply.subcommand_matches("diff") {
+        if let Some(cli_diff_inventory) = cli_diff.subcommand_matches("inventory") {
+            // Only '--live' reaches this point, diff between files is processed before
+            // authentication
+            let old_inventory_snapshot = diff_inventory::read_inventory_snapshot_or_exit(
+                cli_diff_inventory.get_one::<String>("OLD").unwrap(),
+            );
+
+            let new_inventory_snapshot = diff_inventory::get_live_inventory_snapshot(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group,
+                &old_inventory_snapshot,
+            )
+            .await;
+
+            diff_inventory::exec(
+                &old_inventory_snapshot,
+                &new_inventory_snapshot,
+                cli_diff_inventory.get_one::<String>("output"),
+            );
+        } else if let Some(cli_diff_fru) = cli_diff.subcommand_matches("fru") {
+            // Only '--live' reaches this point, diff between files is processed before
+            // authentication
+            let old_inventory_snapshot = diff_inventory::read_inventory_snapshot_or_exit(
+                cli_diff_fru.get_one::<String>("OLD").unwrap(),
+            );
+
+            let new_inventory_snapshot = diff_inventory::get_live_inventory_snapshot(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group,
+                &old_inventory_snapshot,
+            )
+            .await;
+
+            diff_fru::exec(
+                &old_inventory_snapshot,
+                &new_inventory_snapshot,
+                cli_diff_fru.get_one::<String>("output"),
+            );
+        }
+    } else if let Some(cli_validate) = cli_apply.subcommand_matches("validate") {
+        if let Some(cli_validate_nodes) = cli_validate.subcommand_matches("nodes") {
+            let hsm_group_name = match hsm_group {
+                None => cli_validate_nodes.get_one::<String>("hsm").unwrap(),
+                Some(hsm_group_name_value) => hsm_group_name_value,
+            };
+            validate_nodes::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group_name,
+                cli_validate_nodes.get_one::<String>("output"),
+            )
+            .await;
+        }
+    } else if let Some(cli_pool) = cli_apply.subcommand_matches("pool") {
+        if let Some(cli_pool_audit) = cli_pool.subcommand_matches("audit") {
+            pool_audit::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_pool_audit.get_one::<String>("pool").unwrap(),
+                decommissioned_rack_vec,
+                cli_pool_audit.get_one::<String>("output"),
+            )
+            .await;
+        }
+    } else if let Some(cli_report) = cli_apply.subcommand_matches("report") {
+        if let Some(cli_report_capacity) = cli_report.subcommand_matches("capacity") {
+            report_capacity::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                cli_report_capacity.get_one::<String>("pattern").unwrap(),
+                cli_report_capacity.get_one::<String>("pool").unwrap(),
+                cli_report_capacity.get_one::<DateTime<Utc>>("as-of"),
+                cli_report_capacity.get_one::<String>("output"),
+            )
+            .await;
+        }
+    } else if let Some(cli_find) = cli_apply.subcommand_matches("find") {
+        if let Some(cli_find_nodes) = cli_find.subcommand_matches("nodes") {
+            let hsm_group_name_opt = match hsm_group {
+                None => cli_find_nodes.get_one::<String>("hsm"),
+                Some(_) => hsm_group,
+            };
+            find_nodes::exec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                hsm_group_name_opt,
+                cli_find_nodes.get_one::<String>("where").unwrap(),
+                cli_find_nodes.get_one::<String>("output"),
+                cli_find_nodes.get_flag("count"),
             )
             .await;
         }
+    } else if let Some(cli_resolve) = cli_apply.subcommand_matches("resolve") {
+        resolve::exec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            cli_resolve.get_one::<String>("NODES").unwrap(),
+            cli_resolve.get_one::<String>("output"),
+        )
+        .await;
+    } else if let Some(cli_permissions) = cli_apply.subcommand_matches("permissions") {
+        let hsm_group_name = match hsm_group {
+            None => cli_permissions.get_one::<String>("HSM_GROUP_NAME"),
+            Some(_) => hsm_group,
+        };
+        permissions::exec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            hsm_group_name,
+            cli_permissions.get_flag("write-probe"),
+            cli_permissions.get_one::<String>("output"),
+        )
+        .await;
     } /* else if let Some(cli_update) = cli_apply.subcommand_matches("update") {
           if let Some(cli_update_node) = cli_update.subcommand_matches("nodes") {
               let hsm_group_name = if hsm_group.is_none() {
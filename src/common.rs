@@ -1,2 +1,36 @@
+pub mod artifact_ops;
+pub mod audit_ops;
+pub mod auth_ops;
+pub mod boot_ops;
+pub mod cfs_ops;
+pub mod color_ops;
 pub mod config_ops;
+pub mod csm_version_ops;
+pub mod event_ops;
+pub mod filter_ops;
+pub mod firmware_ops;
+pub mod format_ops;
+pub mod history_ops;
+pub mod hostlist_ops;
+pub mod hw_component_ops;
+pub mod hw_inventory_ops;
+pub mod lock_ops;
 pub mod log_ops;
+pub mod membership_ops;
+pub mod memory_ops;
+pub mod node_migration_ops;
+pub mod pager_ops;
+pub mod partition_ops;
+pub mod plan_ops;
+pub mod power_ops;
+pub mod profile_ops;
+pub mod report_ops;
+pub mod resolver_ops;
+pub mod session_ops;
+pub mod slurm_ops;
+pub mod snapshot_ops;
+pub mod stdout_ops;
+pub mod table_ops;
+pub mod tenant_ops;
+pub mod trace_ops;
+pub mod xname;
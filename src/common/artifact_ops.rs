@@ -0,0 +1,395 @@
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::string::ToString;
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, Display, EnumIter, EnumString, IntoStaticStr};
+
+#[derive(
+    Debug,
+    EnumIter,
+    EnumString,
+    IntoStaticStr,
+    AsRefStr,
+    Display,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Eq,
+)]
+pub enum ArtifactType {
+    Memory,
+    Processor,
+    NodeAccel,
+    NodeHsnNic,
+    Drive,
+    CabinetPDU,
+    CabinetPDUPowerConnector,
+    CMMRectifier,
+    NodeAccelRiser,
+    NodeEnclosurePowerSupplie,
+    NodeBMC,
+    RouterBMC,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NodeSummary {
+    pub xname: String,
+    pub r#type: String,
+    pub processors: Vec<ArtifactSummary>,
+    pub memory: Vec<ArtifactSummary>,
+    pub node_accels: Vec<ArtifactSummary>,
+    pub node_hsn_nics: Vec<ArtifactSummary>,
+    /// HSM State Components State (eg Ready, Off, Standby), missing if not fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    /// HSM State Components Flag (eg OK, Warning, Alert)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag: Option<String>,
+    /// HSM State Components Enabled, disabled nodes are ignored by CSM services
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+impl NodeSummary {
+    /// Sets state, flag and enabled from an HSM State Components node value
+    pub fn set_component_status(&mut self, node_status_value: &Value) {
+        self.state = node_status_value["State"].as_str().map(str::to_string);
+        self.flag = node_status_value["Flag"].as_str().map(str::to_string);
+        self.enabled = node_status_value["Enabled"].as_bool();
+    }
+
+    /// A node is usable if it is enabled, its flag is OK and its state is not Empty or Unknown
+    pub fn is_usable(&self) -> bool {
+        self.enabled.unwrap_or(true)
+            && self.flag.as_deref().unwrap_or("OK").eq("OK")
+            && !matches!(self.state.as_deref(), Some("Empty") | Some("Unknown"))
+    }
+
+    /// Table cell like 'Ready/OK', green if the node is usable and red otherwise
+    pub fn get_component_status_cell(&self) -> Cell {
+        let Some(state) = &self.state else {
+            return Cell::new("n/a");
+        };
+
+        let mut component_status = format!("{}/{}", state, self.flag.as_deref().unwrap_or("n/a"));
+
+        if self.enabled == Some(false) {
+            component_status.push_str(" disabled");
+        }
+
+        Cell::new(component_status).fg(if self.is_usable() {
+            Color::Green
+        } else {
+            Color::Red
+        })
+    }
+
+    pub fn from_csm_value(hw_artifact_value: Value) -> Self {
+        Self::from_csm_value_filtered_by_artifact_type(
+            hw_artifact_value,
+            &ArtifactType::iter().collect::<Vec<ArtifactType>>(),
+        )
+    }
+
+    /// Same as `from_csm_value` but only keeps the artifacts which type is in artifact_type_vec
+    pub fn from_csm_value_filtered_by_artifact_type(
+        hw_artifact_value: Value,
+        artifact_type_vec: &[ArtifactType],
+    ) -> Self {
+        let processors = if artifact_type_vec.contains(&ArtifactType::Processor) {
+            hw_artifact_value["Processors"]
+                .as_array()
+                .unwrap_or(&Vec::new())
+                .iter()
+                .map(|processor_value| {
+                    ArtifactSummary::from_processor_value(processor_value.clone())
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let memory = if artifact_type_vec.contains(&ArtifactType::Memory) {
+            hw_artifact_value["Memory"]
+                .as_array()
+                .unwrap_or(&Vec::new())
+                .iter()
+                .map(|memory_value| ArtifactSummary::from_memory_value(memory_value.clone()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let node_accels = if artifact_type_vec.contains(&ArtifactType::NodeAccel) {
+            hw_artifact_value["NodeAccels"]
+                .as_array()
+                .unwrap_or(&Vec::new())
+                .iter()
+                .map(|nodeaccel_value| {
+                    ArtifactSummary::from_nodeaccel_value(nodeaccel_value.clone())
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let node_hsn_nics = if artifact_type_vec.contains(&ArtifactType::NodeHsnNic) {
+            hw_artifact_value["NodeHsnNics"]
+                .as_array()
+                .unwrap_or(&Vec::new())
+                .iter()
+                .map(|nodehsnnic_value| {
+                    ArtifactSummary::from_nodehsnnics_value(nodehsnnic_value.clone())
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            xname: hw_artifact_value["ID"].as_str().unwrap().to_string(),
+            r#type: hw_artifact_value["Type"].as_str().unwrap().to_string(),
+            processors,
+            memory,
+            node_accels,
+            node_hsn_nics,
+            state: None,
+            flag: None,
+            enabled: None,
+        }
+    }
+}
+
+/// xname -> HSM State Components value of all nodes, fetched in bulk
+pub async fn get_node_status_hashmap(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+) -> Result<HashMap<String, Value>, Box<dyn std::error::Error>> {
+    let node_status_value = crate::shasta::hsm::http_client::get_all_components_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "Node",
+    )
+    .await?;
+
+    Ok(node_status_value["Components"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter_map(|node_status| {
+            Some((node_status["ID"].as_str()?.to_string(), node_status.clone()))
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArtifactSummary {
+    pub xname: String,
+    pub r#type: ArtifactType,
+    pub info: Option<String>,
+    /// Serial number of the FRU (PopulatedFRU), missing if CSM does not report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    /// Part number of the FRU (PopulatedFRU), missing if CSM does not report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_number: Option<String>,
+    /// Processors only (ProcessorFRUInfo/TotalCores)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub core_count: Option<u64>,
+    /// Processors only (ProcessorFRUInfo/MaxSpeedMHz)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_speed_mhz: Option<u64>,
+    /// Accelerators only, memory (bytes) taken from the model since CSM does not report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_memory: Option<u64>,
+}
+
+impl ArtifactSummary {
+    /// Info followed by the attributes of the component, if any, eg 'AMD EPYC 7742 64-Core
+    /// Processor (64 cores, 2250 MHz)'
+    pub fn get_info_with_attributes(&self) -> String {
+        let info = self.info.clone().unwrap_or("*** Missing info".to_string());
+
+        let attribute_vec: Vec<String> = [
+            self.core_count
+                .map(|core_count| format!("{} cores", core_count)),
+            self.max_speed_mhz
+                .map(|max_speed_mhz| format!("{} MHz", max_speed_mhz)),
+            self.gpu_memory
+                .map(crate::common::memory_ops::format_memory_size),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        if attribute_vec.is_empty() {
+            info
+        } else {
+            format!("{} ({})", info, attribute_vec.join(", "))
+        }
+    }
+
+    fn from_processor_value(processor_value: Value) -> Self {
+        Self {
+            xname: processor_value["ID"].as_str().unwrap().to_string(),
+            r#type: ArtifactType::from_str(processor_value["Type"].as_str().unwrap()).unwrap(),
+            info: processor_value
+                .pointer("/PopulatedFRU/ProcessorFRUInfo/Model")
+                .map(|model| model.as_str().unwrap().to_string()),
+            serial_number: get_fru_info_field(&processor_value, "ProcessorFRUInfo", "SerialNumber"),
+            part_number: get_fru_info_field(&processor_value, "ProcessorFRUInfo", "PartNumber"),
+            core_count: processor_value
+                .pointer("/PopulatedFRU/ProcessorFRUInfo/TotalCores")
+                .and_then(Value::as_u64),
+            max_speed_mhz: processor_value
+                .pointer("/PopulatedFRU/ProcessorFRUInfo/MaxSpeedMHz")
+                .and_then(Value::as_u64),
+            gpu_memory: None,
+        }
+    }
+
+    fn from_memory_value(memory_value: Value) -> Self {
+        // println!("DEBUG - memory raw data: {:#?}", memory_value);
+        Self {
+            xname: memory_value["ID"].as_str().unwrap().to_string(),
+            r#type: ArtifactType::from_str(memory_value["Type"].as_str().unwrap()).unwrap(),
+            info: memory_value
+                .pointer("/PopulatedFRU/MemoryFRUInfo/CapacityMiB")
+                .map(|capacity_mib| capacity_mib.as_number().unwrap().to_string() + " MiB"),
+            serial_number: get_fru_info_field(&memory_value, "MemoryFRUInfo", "SerialNumber"),
+            part_number: get_fru_info_field(&memory_value, "MemoryFRUInfo", "PartNumber"),
+            core_count: None,
+            max_speed_mhz: None,
+            gpu_memory: None,
+        }
+    }
+
+    fn from_nodehsnnics_value(nodehsnnic_value: Value) -> Self {
+        Self {
+            xname: nodehsnnic_value["ID"].as_str().unwrap().to_string(),
+            r#type: ArtifactType::from_str(nodehsnnic_value["Type"].as_str().unwrap()).unwrap(),
+            info: nodehsnnic_value
+                .pointer("/NodeHsnNicLocationInfo/Description")
+                .map(|description| description.as_str().unwrap().to_string()),
+            serial_number: get_fru_info_field(
+                &nodehsnnic_value,
+                "NodeHsnNicFRUInfo",
+                "SerialNumber",
+            ),
+            part_number: get_fru_info_field(&nodehsnnic_value, "NodeHsnNicFRUInfo", "PartNumber"),
+            core_count: None,
+            max_speed_mhz: None,
+            gpu_memory: None,
+        }
+    }
+
+    fn from_nodeaccel_value(nodeaccel_value: Value) -> Self {
+        let info = nodeaccel_value
+            .pointer("/PopulatedFRU/NodeAccelFRUInfo/Model")
+            .map(|model| model.as_str().unwrap().to_string());
+
+        Self {
+            xname: nodeaccel_value["ID"].as_str().unwrap().to_string(),
+            r#type: ArtifactType::from_str(nodeaccel_value["Type"].as_str().unwrap()).unwrap(),
+            gpu_memory: info
+                .as_deref()
+                .and_then(crate::common::hw_component_ops::get_accelerator_memory_from_model),
+            info,
+            serial_number: get_fru_info_field(&nodeaccel_value, "NodeAccelFRUInfo", "SerialNumber"),
+            part_number: get_fru_info_field(&nodeaccel_value, "NodeAccelFRUInfo", "PartNumber"),
+            core_count: None,
+            max_speed_mhz: None,
+        }
+    }
+}
+
+/// Field of the FRU info of an artifact, eg SerialNumber in /PopulatedFRU/MemoryFRUInfo. None if
+/// missing or empty
+fn get_fru_info_field(artifact_value: &Value, fru_info_key: &str, field: &str) -> Option<String> {
+    artifact_value
+        .pointer(&format!("/PopulatedFRU/{}/{}", fru_info_key, field))
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_artifact_summary_serial_and_part_number() {
+        let memory_summary = ArtifactSummary::from_memory_value(json!({
+            "ID": "x1000c0s0b0n0d0",
+            "Type": "Memory",
+            "PopulatedFRU": {
+                "MemoryFRUInfo": {
+                    "CapacityMiB": 16384,
+                    "SerialNumber": "80CE01224712A4B3D",
+                    "PartNumber": " M393A2K43DB3-CWE "
+                }
+            }
+        }));
+
+        assert_eq!(
+            memory_summary.serial_number,
+            Some("80CE01224712A4B3D".to_string())
+        );
+        assert_eq!(
+            memory_summary.part_number,
+            Some("M393A2K43DB3-CWE".to_string())
+        );
+
+        let node_accel_summary = ArtifactSummary::from_nodeaccel_value(json!({
+            "ID": "x1000c0s0b0n0a0",
+            "Type": "NodeAccel",
+            "PopulatedFRU": {
+                "NodeAccelFRUInfo": { "Model": "NVIDIA A100", "SerialNumber": "" }
+            }
+        }));
+
+        assert_eq!(node_accel_summary.serial_number, None);
+        assert_eq!(node_accel_summary.part_number, None);
+    }
+
+    #[test]
+    fn test_artifact_summary_attributes() {
+        let processor_summary = ArtifactSummary::from_processor_value(json!({
+            "ID": "x1000c0s0b0n0p0",
+            "Type": "Processor",
+            "PopulatedFRU": {
+                "ProcessorFRUInfo": {
+                    "Model": "AMD EPYC 7742 64-Core Processor",
+                    "TotalCores": 64,
+                    "MaxSpeedMHz": 2250
+                }
+            }
+        }));
+
+        assert_eq!(
+            processor_summary.get_info_with_attributes(),
+            "AMD EPYC 7742 64-Core Processor (64 cores, 2250 MHz)"
+        );
+
+        let node_accel_summary = ArtifactSummary::from_nodeaccel_value(json!({
+            "ID": "x1000c0s0b0n0a0",
+            "Type": "NodeAccel",
+            "PopulatedFRU": {
+                "NodeAccelFRUInfo": { "Model": "NVIDIA A100-SXM4-40GB" }
+            }
+        }));
+
+        assert_eq!(
+            node_accel_summary.get_info_with_attributes(),
+            "NVIDIA A100-SXM4-40GB (40gb)"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    cell::RefCell, error::Error, fs::OpenOptions, future::Future, io::Write, path::PathBuf,
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::{auth_ops, config_ops, log_ops};
+
+pub const AUDIT_FILE_NAME: &str = "audit.ndjson";
+
+/// CSM API request sent while applying node moves
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditedApiCall {
+    pub method: String,
+    /// Secrets redacted
+    pub url: String,
+    /// Secrets redacted, eg the node added to an HSM group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    /// None if no response was received
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Node moves applied (or attempted) by an operator
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    /// RFC 3339
+    pub timestamp: String,
+    pub site: String,
+    /// Keycloak user or service account of the CSM API token
+    pub user: String,
+    /// Command which applied the node moves eg `apply hsm-group -p zinal:a100:4` or `serve`
+    pub command: String,
+    /// History entry of the node moves applied, None if none was recorded (eg conflict)
+    pub history_id: Option<u64>,
+    /// CSM API requests in the order they were sent
+    pub api_calls: Vec<AuditedApiCall>,
+    pub error: Option<String>,
+}
+
+/// Site and `audit_url` in configuration file
+static AUDIT_SETTINGS: OnceLock<(String, Option<String>)> = OnceLock::new();
+
+tokio::task_local! {
+    /// CSM API requests sent by the task being audited
+    static AUDITED_API_CALL_VEC: RefCell<Vec<AuditedApiCall>>;
+}
+
+/// Audit entries are also POSTed as JSON to `audit_url_opt` (eg the CSM audit endpoint) with the
+/// CSM API token of the operator. The token is only sent to the CSM host, `audit_url_opt` on
+/// another host is ignored
+pub fn configure(site_name: &str, audit_url_opt: Option<String>) {
+    let _ = AUDIT_SETTINGS.set((site_name.to_string(), audit_url_opt));
+}
+
+/// Audit trail is kept as NDJSON (one entry per line) and only appended to.
+/// ~/.local/share/clstr/audit.ndjson on Linux
+pub fn get_audit_file_path() -> PathBuf {
+    config_ops::get_data_dir_path().join(AUDIT_FILE_NAME)
+}
+
+/// Runs `future` recording the CSM API requests it sends (see `shasta::SendTraced`). Concurrent
+/// audited futures (eg `serve` requests) record their own requests only
+pub async fn audit<F: Future>(future: F) -> (F::Output, Vec<AuditedApiCall>) {
+    AUDITED_API_CALL_VEC
+        .scope(RefCell::new(Vec::new()), async {
+            let output = future.await;
+
+            (
+                output,
+                AUDITED_API_CALL_VEC.with(|api_call_vec| api_call_vec.take()),
+            )
+        })
+        .await
+}
+
+pub fn is_auditing() -> bool {
+    AUDITED_API_CALL_VEC.try_with(|_| ()).is_ok()
+}
+
+/// Records a CSM API request if the current task is audited
+pub fn record_api_call(
+    method: &reqwest::Method,
+    url: &reqwest::Url,
+    request_body_opt: Option<&[u8]>,
+    status_rslt: Result<u16, String>,
+) {
+    let (status, error) = match status_rslt {
+        Ok(status) => (Some(status), None),
+        Err(error) => (None, Some(error)),
+    };
+
+    let _ = AUDITED_API_CALL_VEC.try_with(|api_call_vec| {
+        api_call_vec.borrow_mut().push(AuditedApiCall {
+            method: method.to_string(),
+            url: log_ops::redact_secrets(url.as_str()),
+            request_body: request_body_opt
+                .map(|body| log_ops::redact_secrets(&String::from_utf8_lossy(body))),
+            status,
+            error,
+        })
+    });
+}
+
+pub fn read_audit_entry_vec() -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+    let audit_file_path = config_ops::get_data_file_path_to_read(AUDIT_FILE_NAME);
+
+    if !audit_file_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut audit_entry_vec = Vec::new();
+
+    for line in std::fs::read_to_string(audit_file_path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+    {
+        audit_entry_vec.push(serde_json::from_str(line)?);
+    }
+
+    Ok(audit_entry_vec)
+}
+
+fn append_audit_entry_to_file(audit_entry: &AuditEntry) -> Result<(), Box<dyn Error>> {
+    let audit_file_path = get_audit_file_path();
+
+    if let Some(audit_dir_path) = audit_file_path.parent() {
+        std::fs::create_dir_all(audit_dir_path)?;
+    }
+
+    let mut audit_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_file_path)?;
+
+    writeln!(audit_file, "{}", serde_json::to_string(audit_entry)?)?;
+
+    Ok(())
+}
+
+/// Appends an audit entry with the operator of `shasta_token` to the audit file and sends it to
+/// `audit_url` if configured and on the host of `shasta_base_url`. Failures only log an error,
+/// node moves are already applied
+pub async fn append_audit_entry(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    command: &str,
+    history_id_opt: Option<u64>,
+    api_call_vec: Vec<AuditedApiCall>,
+    error_opt: Option<String>,
+) {
+    let (site_name, audit_url_opt) = AUDIT_SETTINGS.get().cloned().unwrap_or_default();
+
+    let audit_entry = AuditEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        site: site_name,
+        user: auth_ops::get_token_username(shasta_token),
+        command: command.to_string(),
+        history_id: history_id_opt,
+        api_calls: api_call_vec,
+        error: error_opt,
+    };
+
+    if let Err(error) = append_audit_entry_to_file(&audit_entry) {
+        log::error!(
+            "Could not record node moves in audit file '{}': {}",
+            get_audit_file_path().display(),
+            error
+        );
+    }
+
+    let Some(audit_url) = audit_url_opt else {
+        return;
+    };
+
+    if !is_csm_host(&audit_url, shasta_base_url) {
+        log::error!(
+            "Audit entry not sent to '{}', 'audit_url' must be on the CSM host '{}'",
+            audit_url,
+            shasta_base_url
+        );
+        return;
+    }
+
+    let client = match crate::shasta::build_client(shasta_root_cert) {
+        Ok(client) => client,
+        Err(error) => {
+            log::error!("Could not send audit entry to '{}': {}", audit_url, error);
+            return;
+        }
+    };
+
+    let resp_rslt = client
+        .post(&audit_url)
+        .header("Authorization", format!("Bearer {}", shasta_token))
+        .json(&audit_entry)
+        .send()
+        .await;
+
+    match resp_rslt {
+        Ok(resp) if resp.status().is_success() => {
+            log::info!("Audit entry sent to '{}'", audit_url)
+        }
+        Ok(resp) => log::error!(
+            "Could not send audit entry to '{}': {}",
+            audit_url,
+            resp.status()
+        ),
+        Err(error) => log::error!("Could not send audit entry to '{}': {}", audit_url, error),
+    }
+}
+
+/// Whether `url` is https on the same host and port as `shasta_base_url`, so the CSM API token
+/// sent with the audit entry does not leave CSM
+fn is_csm_host(url: &str, shasta_base_url: &str) -> bool {
+    match (
+        reqwest::Url::parse(url),
+        reqwest::Url::parse(shasta_base_url),
+    ) {
+        (Ok(url), Ok(shasta_base_url)) => {
+            url.scheme().eq("https")
+                && url.host_str().is_some()
+                && url.host_str() == shasta_base_url.host_str()
+                && url.port_or_known_default() == shasta_base_url.port_or_known_default()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_audit() {
+        let method = reqwest::Method::POST;
+        let url =
+            reqwest::Url::parse("https://api.example.com/smd/hsm/v2/groups/zinal/members").unwrap();
+
+        // Requests outside an audited future are not recorded
+        record_api_call(&method, &url, None, Ok(200));
+        assert!(!is_auditing());
+
+        let (output, api_call_vec) = audit(async {
+            assert!(is_auditing());
+            record_api_call(&method, &url, Some(br#"{"id":"x1000c0s0b0n0"}"#), Ok(201));
+            record_api_call(&method, &url, None, Err("timed out".to_string()));
+            "applied"
+        })
+        .await;
+
+        assert_eq!(output, "applied");
+        assert_eq!(
+            api_call_vec,
+            vec![
+                AuditedApiCall {
+                    method: "POST".to_string(),
+                    url: url.to_string(),
+                    request_body: Some(r#"{"id":"x1000c0s0b0n0"}"#.to_string()),
+                    status: Some(201),
+                    error: None,
+                },
+                AuditedApiCall {
+                    method: "POST".to_string(),
+                    url: url.to_string(),
+                    request_body: None,
+                    status: None,
+                    error: Some("timed out".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_csm_host() {
+        let shasta_base_url = "https://api.alps.cscs.ch/apis";
+
+        assert!(is_csm_host(
+            "https://api.alps.cscs.ch/apis/audit",
+            shasta_base_url
+        ));
+        assert!(!is_csm_host(
+            "http://api.alps.cscs.ch/apis/audit",
+            shasta_base_url
+        ));
+        assert!(!is_csm_host(
+            "https://api.alps.cscs.ch:8443/audit",
+            shasta_base_url
+        ));
+        assert!(!is_csm_host(
+            "https://audit.example.com/audit",
+            shasta_base_url
+        ));
+    }
+}
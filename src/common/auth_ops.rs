@@ -0,0 +1,307 @@
+use std::{error::Error, time::SystemTime};
+
+use serde_json::Value;
+
+use crate::shasta::SendTraced;
+
+/// Name used to group clstr entries in the OS keyring
+const KEYRING_SERVICE: &str = "clstr";
+
+/// Keycloak realm and public client used by CSM
+const KEYCLOAK_REALM: &str = "shasta";
+const KEYCLOAK_CLIENT_ID: &str = "shasta";
+
+/// Returns a CSM API token. If `auth.backend = "keyring"` is configured then Keycloak tokens and
+/// client secrets are kept in the OS keyring, otherwise (or if the keyring is not available) the
+/// file based flow is used
+pub async fn get_api_token(
+    settings: &config::Config,
+    site_name: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    keycloak_base_url: &str,
+) -> Result<String, Box<dyn Error>> {
+    let auth_backend = settings
+        .get_string("auth.backend")
+        .unwrap_or("file".to_string());
+
+    if auth_backend.eq("keyring") {
+        match get_api_token_from_keyring(
+            settings,
+            site_name,
+            shasta_root_cert,
+            keycloak_base_url,
+        )
+        .await
+        {
+            Ok(shasta_token) => return Ok(shasta_token),
+            Err(error) => log::warn!(
+                "Could not get token using OS keyring, falling back to file based authentication: {}",
+                error
+            ),
+        }
+    }
+
+    // mesa builds its own client which always verifies certificates, with `--insecure` and no
+    // root certificate every request would fail with a confusing TLS error
+    if shasta_root_cert.is_empty() && crate::shasta::is_insecure_tls() {
+        return Err(
+            "Keycloak user authentication needs the CSM root certificate even with \
+            --insecure, use --cacert, --token or 'auth.backend = \"keyring\"'"
+                .into(),
+        );
+    }
+
+    crate::shasta::with_timeout(mesa::common::authentication::get_api_token(
+        shasta_base_url,
+        shasta_root_cert,
+        keycloak_base_url,
+    ))
+    .await
+}
+
+/// Gets an access token reusing the one stored in the keyring while it is not expired, otherwise
+/// it is renewed using (in this order) the client secret (if `auth.client_id` is configured), the
+/// refresh token or user credentials. A client secret from the keyring rejected by Keycloak is
+/// removed and asked for again
+async fn get_api_token_from_keyring(
+    settings: &config::Config,
+    site_name: &str,
+    shasta_root_cert: &[u8],
+    keycloak_base_url: &str,
+) -> Result<String, Box<dyn Error>> {
+    let access_token_entry =
+        keyring::Entry::new(KEYRING_SERVICE, &format!("{site_name}/access_token"))?;
+    let refresh_token_entry =
+        keyring::Entry::new(KEYRING_SERVICE, &format!("{site_name}/refresh_token"))?;
+
+    if let Ok(access_token) = access_token_entry.get_password() {
+        if !is_token_expired(&access_token) {
+            log::info!("Using access token from OS keyring");
+            return Ok(access_token);
+        }
+    }
+
+    let token_response_value = if let Ok(client_id) = settings.get_string("auth.client_id") {
+        // Service account
+        let client_secret_entry = keyring::Entry::new(
+            KEYRING_SERVICE,
+            &format!("{site_name}/{client_id}/client_secret"),
+        )?;
+
+        let (client_secret, is_client_secret_from_keyring) =
+            match client_secret_entry.get_password() {
+                Ok(client_secret) => (client_secret, true),
+                Err(error) => {
+                    // No keyring available (eg headless host) is not fatal, the secret is asked
+                    // for on each run
+                    if !matches!(error, keyring::Error::NoEntry) {
+                        log::warn!("Could not read client secret from OS keyring: {}", error);
+                    }
+
+                    (
+                        prompt_client_secret(&client_id, &client_secret_entry)?,
+                        false,
+                    )
+                }
+            };
+
+        let mut token_response_rslt = request_client_credentials_token(
+            shasta_root_cert,
+            keycloak_base_url,
+            &client_id,
+            &client_secret,
+        )
+        .await;
+
+        // Secret stored was rotated or revoked, otherwise it would be rejected on every run
+        if is_client_secret_from_keyring
+            && token_response_rslt
+                .as_ref()
+                .is_err_and(|error| is_client_secret_rejected(error.as_ref()))
+        {
+            log::warn!("Client secret stored in OS keyring was rejected by Keycloak, removing it");
+
+            if let Err(error) = client_secret_entry.delete_password() {
+                log::warn!("Could not remove client secret from OS keyring: {}", error);
+            }
+
+            let client_secret = prompt_client_secret(&client_id, &client_secret_entry)?;
+
+            token_response_rslt = request_client_credentials_token(
+                shasta_root_cert,
+                keycloak_base_url,
+                &client_id,
+                &client_secret,
+            )
+            .await;
+        }
+
+        token_response_rslt?
+    } else {
+        let refresh_token_response_rslt = match refresh_token_entry.get_password() {
+            Ok(refresh_token) => {
+                request_token(
+                    shasta_root_cert,
+                    keycloak_base_url,
+                    &[
+                        ("grant_type", "refresh_token"),
+                        ("client_id", KEYCLOAK_CLIENT_ID),
+                        ("refresh_token", &refresh_token),
+                    ],
+                )
+                .await
+            }
+            Err(error) => Err(error.into()),
+        };
+
+        match refresh_token_response_rslt {
+            Ok(token_response_value) => token_response_value,
+            Err(_) => {
+                // Refresh token missing or expired, ask for user credentials
+                let username: String = dialoguer::Input::new()
+                    .with_prompt("username")
+                    .interact_text()?;
+                let password = dialoguer::Password::new()
+                    .with_prompt("password")
+                    .interact()?;
+
+                request_token(
+                    shasta_root_cert,
+                    keycloak_base_url,
+                    &[
+                        ("grant_type", "password"),
+                        ("client_id", KEYCLOAK_CLIENT_ID),
+                        ("username", &username),
+                        ("password", &password),
+                    ],
+                )
+                .await?
+            }
+        }
+    };
+
+    let access_token = token_response_value["access_token"]
+        .as_str()
+        .ok_or("Keycloak response does not contain an access token")?
+        .to_string();
+
+    // Tokens stored are only reused by the next runs, the access token is valid anyway
+    if let Err(error) = access_token_entry.set_password(&access_token) {
+        log::warn!("Could not store access token in OS keyring: {}", error);
+    }
+
+    if let Some(refresh_token) = token_response_value["refresh_token"].as_str() {
+        if let Err(error) = refresh_token_entry.set_password(refresh_token) {
+            log::warn!("Could not store refresh token in OS keyring: {}", error);
+        }
+    }
+
+    Ok(access_token)
+}
+
+/// Asks the user for the client secret of a service account and stores it in the OS keyring
+fn prompt_client_secret(
+    client_id: &str,
+    client_secret_entry: &keyring::Entry,
+) -> Result<String, Box<dyn Error>> {
+    let client_secret = dialoguer::Password::new()
+        .with_prompt(format!("Client secret for '{}'", client_id))
+        .interact()?;
+
+    if let Err(error) = client_secret_entry.set_password(&client_secret) {
+        log::warn!("Could not store client secret in OS keyring: {}", error);
+    }
+
+    Ok(client_secret)
+}
+
+/// Requests a token for a service account
+async fn request_client_credentials_token(
+    shasta_root_cert: &[u8],
+    keycloak_base_url: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<Value, Box<dyn Error>> {
+    request_token(
+        shasta_root_cert,
+        keycloak_base_url,
+        &[
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+        ],
+    )
+    .await
+}
+
+/// Keycloak answers 401 or `invalid_client` if the client secret is wrong, see `request_token`
+/// for the error format
+fn is_client_secret_rejected(error: &dyn Error) -> bool {
+    let error = error.to_string();
+
+    error.starts_with("401 ") || error.contains("invalid_client")
+}
+
+/// Requests a token to Keycloak using --> https://www.keycloak.org/docs/latest/securing_apps/#token-endpoint
+async fn request_token(
+    shasta_root_cert: &[u8],
+    keycloak_base_url: &str,
+    form_param_vec: &[(&str, &str)],
+) -> Result<Value, Box<dyn Error>> {
+    let client = crate::shasta::build_client(shasta_root_cert)?;
+
+    let api_url = format!(
+        "{}/realms/{}/protocol/openid-connect/token",
+        keycloak_base_url, KEYCLOAK_REALM
+    );
+
+    let resp = client
+        .post(api_url)
+        .form(form_param_vec)
+        .send_traced()
+        .await?;
+
+    if resp.status().is_success() {
+        Ok(resp.json().await?)
+    } else {
+        Err(format!("{} {}", resp.status(), resp.text().await?).into())
+    }
+}
+
+/// Decodes the JWT payload (claims) without validating the signature
+fn get_token_claims(token: &str) -> Option<Value> {
+    token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok())
+        .and_then(|payload| serde_json::from_slice::<Value>(&payload).ok())
+}
+
+/// Keycloak user or service account the token was issued to
+pub fn get_token_username(token: &str) -> String {
+    get_token_claims(token)
+        .and_then(|payload_value| {
+            payload_value["preferred_username"]
+                .as_str()
+                .or(payload_value["sub"].as_str())
+                .map(|username| username.to_string())
+        })
+        .unwrap_or("unknown".to_string())
+}
+
+/// Checks JWT `exp` claim, a token is considered expired 30 seconds before its expiration time.
+/// Tokens which can't be decoded are considered expired
+fn is_token_expired(token: &str) -> bool {
+    let exp_opt = get_token_claims(token).and_then(|payload_value| payload_value["exp"].as_u64());
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(u64::MAX);
+
+    match exp_opt {
+        Some(exp) => exp <= now.saturating_add(30),
+        None => true,
+    }
+}
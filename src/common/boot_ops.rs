@@ -0,0 +1,95 @@
+use std::error::Error;
+
+use serde_json::Value;
+
+use crate::shasta::bos;
+
+/// BOS session template booting the nodes of an HSM group with an IMS image. The template boots
+/// the whole HSM group (node_groups) so it can be reused, sessions limit it to the nodes moved
+pub fn get_bos_sessiontemplate_value(
+    hsm_group_name: &str,
+    boot_image_id: &str,
+    kernel_parameters: &str,
+) -> Value {
+    serde_json::json!({
+        "boot_sets": {
+            "compute": {
+                "path": format!("s3://boot-images/{}/manifest.json", boot_image_id),
+                "type": "s3",
+                "kernel_parameters": kernel_parameters,
+                "node_groups": [hsm_group_name],
+            }
+        }
+    })
+}
+
+/// Name of the BOS session template created by `--boot-image`
+pub fn get_bos_sessiontemplate_name(hsm_group_name: &str, boot_image_id: &str) -> String {
+    format!("clstr-{}-{}", hsm_group_name, boot_image_id)
+}
+
+/// Reboots the nodes moved into an HSM group with a BOS session. The session uses the
+/// `bos_sessiontemplate_name_opt` session template or, if `boot_image_id_opt` is set instead, a
+/// session template created for the HSM group and image (see `get_bos_sessiontemplate_name`).
+/// Returns the BOS session name
+pub async fn reboot_xname_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name: &str,
+    xname_vec: &[String],
+    bos_sessiontemplate_name_opt: Option<&String>,
+    boot_image_id_opt: Option<&String>,
+    kernel_parameters_opt: Option<&String>,
+) -> Result<String, Box<dyn Error>> {
+    let bos_sessiontemplate_name = match (bos_sessiontemplate_name_opt, boot_image_id_opt) {
+        (Some(bos_sessiontemplate_name), _) => bos_sessiontemplate_name.clone(),
+        (None, Some(boot_image_id)) => {
+            let bos_sessiontemplate_name =
+                get_bos_sessiontemplate_name(hsm_group_name, boot_image_id);
+
+            bos::template::http_client::put(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                &bos_sessiontemplate_name,
+                &get_bos_sessiontemplate_value(
+                    hsm_group_name,
+                    boot_image_id,
+                    kernel_parameters_opt.map_or("", |kernel_parameters| kernel_parameters),
+                ),
+            )
+            .await
+            .map_err(|error| {
+                format!(
+                    "could not create BOS session template '{}': {}",
+                    bos_sessiontemplate_name, error
+                )
+            })?;
+
+            log::info!(
+                "BOS session template '{}' created",
+                bos_sessiontemplate_name
+            );
+
+            bos_sessiontemplate_name
+        }
+        (None, None) => return Err("no BOS session template nor boot image".into()),
+    };
+
+    let bos_session_value = bos::session::http_client::post(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &bos_sessiontemplate_name,
+        "reboot",
+        &xname_vec.join(","),
+    )
+    .await
+    .map_err(|error| format!("could not create BOS session: {}", error))?;
+
+    Ok(bos_session_value["name"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
@@ -0,0 +1,84 @@
+use std::{collections::BTreeMap, error::Error, time::Duration};
+
+use crate::{common::format_ops::format_count, shasta::cfs};
+
+/// Seconds between polls of the CFS components configuration status
+const CFS_STATUS_POLL_SECS: u64 = 30;
+
+/// Sets the CFS desired configuration of the nodes and enables them so CFS configures them
+pub async fn set_desired_configuration(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+    cfs_configuration_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    cfs::component::http_client::patch_multiple_components(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        xname_vec,
+        &serde_json::json!({
+            "desiredConfig": cfs_configuration_name,
+            "enabled": true,
+        }),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Polls CFS until no node is pending configuration. `on_status` receives the configuration
+/// status of the nodes after each poll (eg '2 pending, 6 configured'). Fails if any node
+/// configuration failed
+pub async fn wait_configuration(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+    on_status: &dyn Fn(&str),
+) -> Result<(), Box<dyn Error>> {
+    loop {
+        let cfs_component_vec = cfs::component::http_client::get_multiple_components(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            xname_vec,
+        )
+        .await?;
+
+        // configuration status -> xnames
+        let mut status_xname_hashmap: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for cfs_component in &cfs_component_vec {
+            status_xname_hashmap
+                .entry(
+                    cfs_component["configurationStatus"]
+                        .as_str()
+                        .unwrap_or("unknown")
+                        .to_string(),
+                )
+                .or_default()
+                .push(cfs_component["id"].as_str().unwrap_or_default().to_string());
+        }
+
+        on_status(
+            &status_xname_hashmap
+                .iter()
+                .map(|(status, xname_vec)| format!("{} {}", format_count(xname_vec.len()), status))
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+
+        if !status_xname_hashmap.contains_key("pending") {
+            return match status_xname_hashmap.get("failed") {
+                Some(xname_failed_vec) => {
+                    Err(format!("CFS configuration failed for nodes {:?}", xname_failed_vec).into())
+                }
+                None => Ok(()),
+            };
+        }
+
+        tokio::time::sleep(Duration::from_secs(CFS_STATUS_POLL_SECS)).await;
+    }
+}
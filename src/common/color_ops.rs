@@ -0,0 +1,46 @@
+use std::sync::OnceLock;
+
+use crate::common::pager_ops;
+
+/// Values of `--color`
+pub const COLOR_MODE_NAME_VEC: [&str; 3] = ["auto", "always", "never"];
+
+static COLOR_MODE: OnceLock<String> = OnceLock::new();
+
+/// Sets when tables and layouts are colored for the rest of the execution (`--color`)
+pub fn configure(color_mode_opt: Option<&String>) {
+    if let Some(color_mode) = color_mode_opt {
+        let _ = COLOR_MODE.set(color_mode.clone());
+    }
+}
+
+/// Whether output is colored: always with `--color always`, never with `--color never`.
+/// Otherwise (auto) if NO_COLOR (https://no-color.org) is not set and stdout is a terminal or is
+/// piped through the pager, so files and CI logs do not get escape codes
+pub fn is_color_enabled() -> bool {
+    match COLOR_MODE.get().map(String::as_str) {
+        Some("always") => true,
+        Some("never") => false,
+        _ => is_color_enabled_auto(
+            std::env::var("NO_COLOR").ok().as_deref(),
+            unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1 || pager_ops::is_running(),
+        ),
+    }
+}
+
+fn is_color_enabled_auto(no_color_opt: Option<&str>, is_terminal: bool) -> bool {
+    no_color_opt.map_or(true, str::is_empty) && is_terminal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_color_enabled_auto() {
+        assert!(is_color_enabled_auto(None, true));
+        assert!(is_color_enabled_auto(Some(""), true));
+        assert!(!is_color_enabled_auto(Some("1"), true));
+        assert!(!is_color_enabled_auto(None, false));
+    }
+}
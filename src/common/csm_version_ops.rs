@@ -0,0 +1,201 @@
+use std::{fmt, sync::OnceLock};
+
+use crate::shasta::SendTraced;
+
+/// CSM release, only major and minor matter to pick APIs and limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CsmVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl CsmVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parses versions like '1.5' or '1.5.2', patch is ignored
+    pub fn parse(version: &str) -> Result<Self, String> {
+        let mut number_iter = version.trim().trim_start_matches('v').split('.');
+
+        let mut next_number = || {
+            number_iter
+                .next()
+                .and_then(|number| number.parse::<u32>().ok())
+                .ok_or(format!(
+                    "CSM version '{}' not valid, use <major>.<minor> eg 1.5",
+                    version
+                ))
+        };
+
+        Ok(Self::new(next_number()?, next_number()?))
+    }
+}
+
+impl fmt::Display for CsmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// CSM release which introduced PCS (replacing CAPMC) and copes with more concurrent requests
+const CSM_VERSION_PCS: CsmVersion = CsmVersion::new(1, 4);
+
+/// CSM release which supports HSM hw inventory queries per chassis
+const CSM_VERSION_CHASSIS_HW_INVENTORY_QUERY: CsmVersion = CsmVersion::new(1, 3);
+
+/// APIs probed to detect the CSM release: (API, endpoint, CSM release introducing it)
+pub const CSM_API_PROBE_VEC: [(&str, &str, CsmVersion); 3] = [
+    ("bos v2", "/bos/v2/version", CsmVersion::new(1, 3)),
+    ("pcs v1", "/power-control/v1/liveness", CSM_VERSION_PCS),
+    ("cfs v3", "/cfs/v3/options", CsmVersion::new(1, 5)),
+];
+
+/// CSM release detected or set in configuration file, None if unknown
+static CSM_VERSION: OnceLock<Option<CsmVersion>> = OnceLock::new();
+
+/// Sets the CSM release of the site for the rest of the execution
+pub fn configure(csm_version_opt: Option<CsmVersion>) {
+    let _ = CSM_VERSION.set(csm_version_opt);
+}
+
+pub fn get_csm_version() -> Option<CsmVersion> {
+    CSM_VERSION.get().copied().flatten()
+}
+
+/// Whether the CSM release is known and older than `csm_version`
+fn is_older_than(csm_version: CsmVersion) -> bool {
+    get_csm_version().is_some_and(|version| version < csm_version)
+}
+
+/// Maximum number of concurrent CSM API requests (eg hw inventory per node). Up to CSM 1.3 a
+/// higher number of concurrent requests won't make it faster
+pub fn get_max_concurrent_requests() -> usize {
+    if get_csm_version().is_some_and(|version| version >= CSM_VERSION_PCS) {
+        10
+    } else {
+        5
+    }
+}
+
+/// PCS is assumed available if the CSM release is unknown
+pub fn is_pcs_available() -> bool {
+    !is_older_than(CSM_VERSION_PCS)
+}
+
+/// Chassis queries are assumed supported if the CSM release is unknown, failed chassis queries
+/// fall back to per node queries anyway
+pub fn is_chassis_hw_inventory_query_supported() -> bool {
+    !is_older_than(CSM_VERSION_CHASSIS_HW_INVENTORY_QUERY)
+}
+
+/// Result of probing an API in CSM_API_PROBE_VEC
+#[derive(Debug, Clone)]
+pub struct CsmApiProbe {
+    pub api: String,
+    pub endpoint: String,
+    pub csm_version: CsmVersion,
+    pub available: bool,
+}
+
+/// Probes all APIs in CSM_API_PROBE_VEC concurrently, an API is available if its endpoint
+/// answers with a success status code
+pub async fn probe_api_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+) -> Vec<CsmApiProbe> {
+    let client_rslt = crate::shasta::build_client(shasta_root_cert);
+
+    futures::future::join_all(
+        CSM_API_PROBE_VEC
+            .iter()
+            .map(|(api, endpoint, csm_version)| {
+                let client_rslt = client_rslt.as_ref();
+
+                async move {
+                    let available = match client_rslt {
+                        Ok(client) => client
+                            .get(shasta_base_url.to_owned() + endpoint)
+                            .header("Authorization", format!("Bearer {}", shasta_token))
+                            .send_traced()
+                            .await
+                            .is_ok_and(|response| response.status().is_success()),
+                        Err(_) => false,
+                    };
+
+                    CsmApiProbe {
+                        api: api.to_string(),
+                        endpoint: endpoint.to_string(),
+                        csm_version: *csm_version,
+                        available,
+                    }
+                }
+            }),
+    )
+    .await
+}
+
+/// Newest CSM release introducing an available API, None if no API is available
+pub fn get_csm_version_from_api_probe_vec(csm_api_probe_vec: &[CsmApiProbe]) -> Option<CsmVersion> {
+    csm_api_probe_vec
+        .iter()
+        .filter(|csm_api_probe| csm_api_probe.available)
+        .map(|csm_api_probe| csm_api_probe.csm_version)
+        .max()
+}
+
+/// CSM release set in configuration file (`sites.<site>.csm_version`) or detected probing its
+/// APIs
+pub async fn get_csm_version_or_detect(
+    csm_version_config_opt: Option<&str>,
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+) -> Result<Option<CsmVersion>, String> {
+    if let Some(csm_version_config) = csm_version_config_opt {
+        return CsmVersion::parse(csm_version_config).map(Some);
+    }
+
+    let csm_version_opt = get_csm_version_from_api_probe_vec(
+        &probe_api_vec(shasta_token, shasta_base_url, shasta_root_cert).await,
+    );
+
+    match csm_version_opt {
+        Some(csm_version) => log::info!("CSM {} or newer detected", csm_version),
+        None => log::warn!(
+            "Could not detect CSM version, set 'csm_version' of the site in configuration file"
+        ),
+    }
+
+    Ok(csm_version_opt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csm_version() {
+        assert_eq!(CsmVersion::parse("1.5.2"), Ok(CsmVersion::new(1, 5)));
+        assert_eq!(CsmVersion::parse("v1.3"), Ok(CsmVersion::new(1, 3)));
+        assert!(CsmVersion::parse("1").is_err());
+        assert!(CsmVersion::parse("latest").is_err());
+        assert!(CsmVersion::new(1, 10) > CsmVersion::new(1, 4));
+
+        let csm_api_probe_vec: Vec<CsmApiProbe> = CSM_API_PROBE_VEC
+            .iter()
+            .map(|(api, endpoint, csm_version)| CsmApiProbe {
+                api: api.to_string(),
+                endpoint: endpoint.to_string(),
+                csm_version: *csm_version,
+                available: !api.starts_with("cfs"),
+            })
+            .collect();
+
+        assert_eq!(
+            get_csm_version_from_api_probe_vec(&csm_api_probe_vec),
+            Some(CsmVersion::new(1, 4))
+        );
+    }
+}
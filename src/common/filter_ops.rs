@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::memory_ops;
+
+/// Value of a field or literal in a filter expression. Numbers accept memory units eg 512GB
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum FilterValue {
+    Text(String),
+    Number(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComparisonOperator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl ComparisonOperator {
+    fn is_satisfied(&self, field_value: &FilterValue, value: &FilterValue) -> bool {
+        match (field_value, value) {
+            (FilterValue::Number(field_number), FilterValue::Number(number)) => match self {
+                ComparisonOperator::Equal => field_number == number,
+                ComparisonOperator::NotEqual => field_number != number,
+                ComparisonOperator::Greater => field_number > number,
+                ComparisonOperator::GreaterOrEqual => field_number >= number,
+                ComparisonOperator::Less => field_number < number,
+                ComparisonOperator::LessOrEqual => field_number <= number,
+            },
+            (FilterValue::Text(field_text), FilterValue::Text(text)) => match self {
+                ComparisonOperator::Equal => field_text.eq_ignore_ascii_case(text),
+                ComparisonOperator::NotEqual => !field_text.eq_ignore_ascii_case(text),
+                _ => false,
+            },
+            // Numbers compared with text eg xname == 123
+            _ => matches!(self, ComparisonOperator::NotEqual),
+        }
+    }
+}
+
+/// Filter expression like `gpu == 'a100' && memory >= 512GB && hsm != 'zinal'`. Fields with
+/// multiple values (eg hsm or gpu) match `==` if any value is equal and `!=` if none is
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpression {
+    Comparison {
+        field: String,
+        operator: ComparisonOperator,
+        value: FilterValue,
+    },
+    Not(Box<FilterExpression>),
+    And(Box<FilterExpression>, Box<FilterExpression>),
+    Or(Box<FilterExpression>, Box<FilterExpression>),
+}
+
+impl FilterExpression {
+    /// Whether a node with these field values (field -> values) matches the expression. Missing
+    /// fields have no values
+    pub fn matches(&self, field_value_hashmap: &HashMap<&str, Vec<FilterValue>>) -> bool {
+        match self {
+            FilterExpression::Comparison {
+                field,
+                operator,
+                value,
+            } => {
+                let field_value_vec = field_value_hashmap
+                    .get(field.as_str())
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+
+                if *operator == ComparisonOperator::NotEqual {
+                    !field_value_vec.iter().any(|field_value| {
+                        ComparisonOperator::Equal.is_satisfied(field_value, value)
+                    })
+                } else {
+                    field_value_vec
+                        .iter()
+                        .any(|field_value| operator.is_satisfied(field_value, value))
+                }
+            }
+            FilterExpression::Not(expression) => !expression.matches(field_value_hashmap),
+            FilterExpression::And(left, right) => {
+                left.matches(field_value_hashmap) && right.matches(field_value_hashmap)
+            }
+            FilterExpression::Or(left, right) => {
+                left.matches(field_value_hashmap) || right.matches(field_value_hashmap)
+            }
+        }
+    }
+
+    /// Fields used in the expression, to validate them or to fetch only what is needed
+    pub fn get_field_vec(&self) -> Vec<&str> {
+        match self {
+            FilterExpression::Comparison { field, .. } => vec![field.as_str()],
+            FilterExpression::Not(expression) => expression.get_field_vec(),
+            FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+                [left.get_field_vec(), right.get_field_vec()].concat()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Operator(ComparisonOperator),
+    And,
+    Or,
+    Not,
+    LeftParenthesis,
+    RightParenthesis,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut token_vec = Vec::new();
+    let mut char_iter = expression.char_indices().peekable();
+
+    while let Some((position, c)) = char_iter.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::LeftParenthesis,
+            ')' => Token::RightParenthesis,
+            '&' | '|' => {
+                if char_iter.next_if(|(_, next_c)| *next_c == c).is_none() {
+                    return Err(format!(
+                        "'{}' at position {} not valid, use '{}{}'",
+                        c, position, c, c
+                    ));
+                }
+
+                if c == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '=' | '!' | '>' | '<' => {
+                let has_equal = char_iter.next_if(|(_, next_c)| *next_c == '=').is_some();
+
+                match (c, has_equal) {
+                    ('=', true) => Token::Operator(ComparisonOperator::Equal),
+                    ('!', true) => Token::Operator(ComparisonOperator::NotEqual),
+                    ('>', true) => Token::Operator(ComparisonOperator::GreaterOrEqual),
+                    ('<', true) => Token::Operator(ComparisonOperator::LessOrEqual),
+                    ('>', false) => Token::Operator(ComparisonOperator::Greater),
+                    ('<', false) => Token::Operator(ComparisonOperator::Less),
+                    ('!', false) => Token::Not,
+                    _ => return Err(format!("'=' at position {} not valid, use '=='", position)),
+                }
+            }
+            '\'' | '"' => {
+                let mut text = String::new();
+                loop {
+                    match char_iter.next() {
+                        Some((_, next_c)) if next_c == c => break,
+                        Some((_, next_c)) => text.push(next_c),
+                        None => {
+                            return Err(format!(
+                                "text starting at position {} is not closed",
+                                position
+                            ))
+                        }
+                    }
+                }
+                Token::Text(text)
+            }
+            _ if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let mut word = c.to_string();
+                while let Some((_, next_c)) = char_iter.next_if(|(_, next_c)| {
+                    next_c.is_ascii_alphanumeric() || ['_', '-', '.'].contains(next_c)
+                }) {
+                    word.push(next_c);
+                }
+                Token::Word(word)
+            }
+            _ => {
+                return Err(format!(
+                    "character '{}' at position {} not valid",
+                    c, position
+                ))
+            }
+        };
+
+        token_vec.push((position, token));
+    }
+
+    Ok(token_vec)
+}
+
+/// Recursive descent parser, '!' binds tighter than '&&' which binds tighter than '||'
+struct Parser {
+    token_vec: Vec<(usize, Token)>,
+    index: usize,
+    expression_len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.token_vec.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.token_vec
+            .get(self.index)
+            .map(|(position, _)| *position)
+            .unwrap_or(self.expression_len)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.index += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpression, String> {
+        let mut expression = self.parse_and()?;
+
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expression = FilterExpression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpression, String> {
+        let mut expression = self.parse_unary()?;
+
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expression = FilterExpression::And(Box::new(expression), Box::new(self.parse_unary()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpression, String> {
+        let position = self.position();
+
+        match self.next() {
+            Some(Token::Not) => Ok(FilterExpression::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LeftParenthesis) => {
+                let expression = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RightParenthesis) => Ok(expression),
+                    _ => Err(format!("missing ')' for '(' at position {}", position)),
+                }
+            }
+            Some(Token::Word(field)) => {
+                let operator_position = self.position();
+                let Some(Token::Operator(operator)) = self.next() else {
+                    return Err(format!(
+                        "missing operator (==, !=, >=, <=, > or <) after '{}' at position {}",
+                        field, operator_position
+                    ));
+                };
+
+                let value_position = self.position();
+                let value = match self.next() {
+                    Some(Token::Text(text)) => FilterValue::Text(text),
+                    Some(Token::Word(word)) if word.starts_with(|c: char| c.is_ascii_digit()) => {
+                        memory_ops::parse_memory_size(&word)
+                            .map(FilterValue::Number)
+                            .unwrap_or(FilterValue::Text(word))
+                    }
+                    Some(Token::Word(word)) => FilterValue::Text(word),
+                    _ => {
+                        return Err(format!(
+                            "missing value after '{}' at position {}",
+                            field, value_position
+                        ))
+                    }
+                };
+
+                Ok(FilterExpression::Comparison {
+                    field: field.to_lowercase(),
+                    operator,
+                    value,
+                })
+            }
+            _ => Err(format!(
+                "expected a field, '!' or '(' at position {}",
+                position
+            )),
+        }
+    }
+}
+
+/// Parses a filter expression like `gpu == 'a100' && memory >= 512GB && hsm != 'zinal'`. Values
+/// are quoted text, words or numbers (memory sizes like 512GB are converted to bytes)
+pub fn parse_filter_expression(expression: &str) -> Result<FilterExpression, String> {
+    let mut parser = Parser {
+        token_vec: tokenize(expression)?,
+        index: 0,
+        expression_len: expression.len(),
+    };
+
+    let filter_expression = parser.parse_or()?;
+
+    if parser.peek().is_some() {
+        return Err(format!(
+            "unexpected text at position {}, missing '&&' or '||'?",
+            parser.position()
+        ));
+    }
+
+    Ok(filter_expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_expression() {
+        let filter_expression =
+            parse_filter_expression("gpu == 'a100' && memory >= 512GB && hsm != \"zinal\"")
+                .unwrap();
+
+        assert_eq!(
+            filter_expression.get_field_vec(),
+            vec!["gpu", "memory", "hsm"]
+        );
+
+        let mut field_value_hashmap = HashMap::from([
+            ("gpu", vec![FilterValue::Text("a100".to_string())]),
+            (
+                "memory",
+                vec![FilterValue::Number(512 * 1024 * 1024 * 1024)],
+            ),
+            (
+                "hsm",
+                vec![
+                    FilterValue::Text("tasna".to_string()),
+                    FilterValue::Text("nodes_free".to_string()),
+                ],
+            ),
+        ]);
+
+        assert!(filter_expression.matches(&field_value_hashmap));
+
+        field_value_hashmap
+            .get_mut("hsm")
+            .unwrap()
+            .push(FilterValue::Text("ZINAL".to_string()));
+
+        assert!(!filter_expression.matches(&field_value_hashmap));
+
+        assert!(parse_filter_expression("!(hsm == zinal) || gpu_count > 4")
+            .unwrap()
+            .matches(&HashMap::new()));
+
+        for expression in [
+            "",
+            "gpu",
+            "gpu == ",
+            "gpu = a100",
+            "gpu == a100 & memory > 1",
+            "(gpu == a100",
+            "gpu == 'a100",
+            "gpu == a100 memory > 1",
+        ] {
+            assert!(
+                parse_filter_expression(expression).is_err(),
+                "expression '{}' should not be valid",
+                expression
+            );
+        }
+    }
+}
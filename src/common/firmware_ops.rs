@@ -0,0 +1,169 @@
+use std::{
+    error::Error,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::shasta::fas;
+
+/// Seconds between polls of a FAS snapshot until it is ready
+const FAS_SNAPSHOT_POLL_SECS: u64 = 5;
+
+/// Firmware version of a node target (eg BIOS, BMC or a GPU) reported by FAS
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FirmwareVersion {
+    pub xname: String,
+    pub target: String,
+    pub version: String,
+}
+
+/// BMC xname of a node, FAS reports firmware by BMC, eg x1001c1s5b0n1 -> x1001c1s5b0
+pub fn get_bmc_xname(node_xname: &str) -> String {
+    match node_xname.rfind('n') {
+        Some(node_index) if node_index > 0 => node_xname[..node_index].to_string(),
+        _ => node_xname.to_string(),
+    }
+}
+
+/// Firmware versions of the nodes from a FAS snapshot. BMC devices list targets of all their
+/// nodes prefixed with `Node<N>.` (eg Node1.BIOS) plus targets shared by the nodes (eg BMC)
+pub fn get_firmware_version_vec_from_snapshot_value(
+    snapshot_value: &Value,
+    node_xname_vec: &[String],
+) -> Vec<FirmwareVersion> {
+    let mut firmware_version_vec = Vec::new();
+
+    for node_xname in node_xname_vec {
+        let bmc_xname = get_bmc_xname(node_xname);
+        let node_target_prefix = format!("Node{}.", &node_xname[bmc_xname.len() + 1..]);
+
+        let Some(device_value) = snapshot_value["devices"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .find(|device_value| device_value["xname"].as_str() == Some(bmc_xname.as_str()))
+            .cloned()
+        else {
+            continue;
+        };
+
+        for target_value in device_value["targets"].as_array().unwrap_or(&Vec::new()) {
+            let target = target_value["name"].as_str().unwrap_or_default();
+
+            let target = if let Some(node_target) = target.strip_prefix(&node_target_prefix) {
+                node_target
+            } else if target.starts_with("Node") {
+                // Target of another node in the same BMC
+                continue;
+            } else {
+                target
+            };
+
+            firmware_version_vec.push(FirmwareVersion {
+                xname: node_xname.clone(),
+                target: target.to_string(),
+                version: target_value["firmwareVersion"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+    }
+
+    firmware_version_vec
+}
+
+/// Firmware versions of the nodes. A FAS snapshot of the nodes BMCs is created and read once ready
+pub async fn get_firmware_version_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_xname_vec: &[String],
+) -> Result<Vec<FirmwareVersion>, Box<dyn Error>> {
+    let mut bmc_xname_vec: Vec<String> = node_xname_vec
+        .iter()
+        .map(|node_xname| get_bmc_xname(node_xname))
+        .collect();
+    bmc_xname_vec.sort();
+    bmc_xname_vec.dedup();
+
+    let snapshot_name = format!(
+        "clstr-{}",
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    );
+
+    fas::snapshot::http_client::post(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &snapshot_name,
+        &bmc_xname_vec,
+    )
+    .await?;
+
+    log::info!("FAS snapshot '{}' created", snapshot_name);
+
+    loop {
+        let snapshot_value = fas::snapshot::http_client::get(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &snapshot_name,
+        )
+        .await?;
+
+        if snapshot_value["ready"].as_bool().unwrap_or(false) {
+            return Ok(get_firmware_version_vec_from_snapshot_value(
+                &snapshot_value,
+                node_xname_vec,
+            ));
+        }
+
+        tokio::time::sleep(Duration::from_secs(FAS_SNAPSHOT_POLL_SECS)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_firmware_version_vec_from_snapshot_value() {
+        let snapshot_value = serde_json::json!({
+            "ready": true,
+            "devices": [{
+                "xname": "x1001c1s5b0",
+                "targets": [
+                    { "name": "BMC", "firmwareVersion": "1.4.2" },
+                    { "name": "Node0.BIOS", "firmwareVersion": "2.1" },
+                    { "name": "Node1.BIOS", "firmwareVersion": "2.0" },
+                ]
+            }]
+        });
+
+        assert_eq!(get_bmc_xname("x1001c1s5b0n1"), "x1001c1s5b0");
+        assert_eq!(
+            get_firmware_version_vec_from_snapshot_value(
+                &snapshot_value,
+                &["x1001c1s5b0n1".to_string()]
+            ),
+            vec![
+                FirmwareVersion {
+                    xname: "x1001c1s5b0n1".to_string(),
+                    target: "BMC".to_string(),
+                    version: "1.4.2".to_string(),
+                },
+                FirmwareVersion {
+                    xname: "x1001c1s5b0n1".to_string(),
+                    target: "BIOS".to_string(),
+                    version: "2.0".to_string(),
+                },
+            ]
+        );
+    }
+}
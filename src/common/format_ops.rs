@@ -0,0 +1,130 @@
+use std::sync::OnceLock;
+
+/// Number formatting used when rendering tables. JSON output is not affected and keeps raw values
+#[derive(Debug, Clone)]
+pub struct FormatSettings {
+    pub precision: usize,
+    pub thousands_separator: Option<char>,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        Self {
+            precision: 2,
+            thousands_separator: None,
+        }
+    }
+}
+
+static FORMAT_SETTINGS: OnceLock<FormatSettings> = OnceLock::new();
+
+/// Sets number formatting for the rest of the execution. Thousands separator accepts a single
+/// character (eg `,`, `.`, `'` or `_`), `space` or `none`
+pub fn configure(precision: usize, thousands_separator_opt: Option<&str>) {
+    let thousands_separator = match thousands_separator_opt {
+        None | Some("none") | Some("") => None,
+        Some("space") => Some(' '),
+        Some(separator) => separator.chars().next(),
+    };
+
+    let _ = FORMAT_SETTINGS.set(FormatSettings {
+        precision,
+        thousands_separator,
+    });
+}
+
+pub fn get_format_settings() -> FormatSettings {
+    FORMAT_SETTINGS.get().cloned().unwrap_or_default()
+}
+
+/// Formats counters (eg number of nodes or hw components) using the thousands separator
+pub fn format_count<T: ToString>(count: T) -> String {
+    format_count_with(&count.to_string(), &get_format_settings())
+}
+
+/// Formats scores with a fixed precision and the thousands separator. If thousands separator is
+/// `.` then `,` is used as decimal separator
+pub fn format_score(score: f32) -> String {
+    format_score_with(score, &get_format_settings())
+}
+
+pub fn format_count_with(count: &str, format_settings: &FormatSettings) -> String {
+    let (sign, digits) = match count.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", count),
+    };
+
+    match format_settings.thousands_separator {
+        Some(separator) => format!("{}{}", sign, group_digits(digits, separator)),
+        None => count.to_string(),
+    }
+}
+
+pub fn format_score_with(score: f32, format_settings: &FormatSettings) -> String {
+    let score_string = format!("{:.*}", format_settings.precision, score);
+
+    let (integer_part, decimal_part_opt) = match score_string.split_once('.') {
+        Some((integer_part, decimal_part)) => (integer_part, Some(decimal_part)),
+        None => (score_string.as_str(), None),
+    };
+
+    let decimal_separator = if format_settings.thousands_separator == Some('.') {
+        ','
+    } else {
+        '.'
+    };
+
+    match decimal_part_opt {
+        Some(decimal_part) => format!(
+            "{}{}{}",
+            format_count_with(integer_part, format_settings),
+            decimal_separator,
+            decimal_part
+        ),
+        None => format_count_with(integer_part, format_settings),
+    }
+}
+
+fn group_digits(digits: &str, separator: char) -> String {
+    let mut grouped_digits = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            grouped_digits.push(separator);
+        }
+        grouped_digits.push(digit);
+    }
+
+    grouped_digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_count_with_thousands_separator() {
+        let format_settings = FormatSettings {
+            precision: 2,
+            thousands_separator: Some(','),
+        };
+
+        assert_eq!(format_count_with("524288", &format_settings), "524,288");
+        assert_eq!(format_count_with("-1024", &format_settings), "-1,024");
+        assert_eq!(format_count_with("512", &format_settings), "512");
+    }
+
+    #[test]
+    fn test_format_score_fixed_precision() {
+        let format_settings = FormatSettings {
+            precision: 2,
+            thousands_separator: Some('.'),
+        };
+
+        assert_eq!(format_score_with(1234.5678, &format_settings), "1.234,57");
+        assert_eq!(
+            format_score_with(-0.123456, &FormatSettings::default()),
+            "-0.12"
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::BTreeSet, error::Error, fs::OpenOptions, io::Write, os::unix::io::AsRawFd,
+    path::PathBuf,
+};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::common::{config_ops, node_migration_ops::NodeMove, snapshot_ops::HsmGroupSnapshot};
+
+pub const HISTORY_FILE_NAME: &str = "history.ndjson";
+
+/// Membership changes applied by a clstr command
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// RFC 3339
+    pub timestamp: String,
+    /// Keycloak user or service account which applied the changes
+    pub user: String,
+    /// Command which applied the changes eg `apply hsm-group -p zinal:a100:4`
+    pub command: String,
+    /// Node moves applied, in order
+    pub node_moves: Vec<NodeMove>,
+    /// Members of the HSM groups involved before and after applying the node moves
+    pub before: Vec<HsmGroupSnapshot>,
+    pub after: Vec<HsmGroupSnapshot>,
+    /// Error which stopped applying the node moves, `node_moves` only lists the moves applied
+    pub error: Option<String>,
+    /// Node moves applied were reverted after `error`, HSM groups are back to `before`. Otherwise
+    /// `after` is the partial state left to recover manually
+    #[serde(default)]
+    pub rolled_back: bool,
+}
+
+/// History is kept as NDJSON (one entry per line) so entries are appended without rewriting the
+/// file. ~/.local/share/clstr/history.ndjson on Linux
+pub fn get_history_file_path() -> PathBuf {
+    config_ops::get_data_dir_path().join(HISTORY_FILE_NAME)
+}
+
+pub fn read_history_entry_vec() -> Result<Vec<HistoryEntry>, Box<dyn Error>> {
+    let history_file_path = config_ops::get_data_file_path_to_read(HISTORY_FILE_NAME);
+
+    if !history_file_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut history_entry_vec = Vec::new();
+
+    for line in std::fs::read_to_string(history_file_path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+    {
+        history_entry_vec.push(serde_json::from_str(line)?);
+    }
+
+    Ok(history_entry_vec)
+}
+
+pub fn get_history_entry(id: u64) -> Result<HistoryEntry, Box<dyn Error>> {
+    read_history_entry_vec()?
+        .into_iter()
+        .find(|history_entry| history_entry.id == id)
+        .ok_or(format!("history entry '{}' not found", id).into())
+}
+
+/// Appends a new entry to the history, `id` is assigned here
+pub fn append_history_entry(mut history_entry: HistoryEntry) -> Result<u64, Box<dyn Error>> {
+    let history_file_path = get_history_file_path();
+
+    if let Some(history_dir_path) = history_file_path.parent() {
+        std::fs::create_dir_all(history_dir_path)?;
+    }
+
+    let mut history_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_file_path)?;
+
+    // Concurrent runs (eg serve and the CLI) wait for each other so they never get the same id
+    // nor interleave their entries. Lock is released when the file is dropped
+    // SAFETY: flock only reads the file descriptor, which is valid while history_file lives
+    if unsafe { libc::flock(history_file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    history_entry.id = read_history_entry_vec()?
+        .iter()
+        .map(|history_entry| history_entry.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    writeln!(history_file, "{}", serde_json::to_string(&history_entry)?)?;
+
+    Ok(history_entry.id)
+}
+
+/// HSM groups involved in a list of node moves, sorted
+pub fn get_hsm_group_name_vec(node_move_vec: &[NodeMove]) -> Vec<String> {
+    node_move_vec
+        .iter()
+        .flat_map(|node_move| {
+            [
+                node_move.from_hsm_group.clone(),
+                node_move.to_hsm_group.clone(),
+            ]
+        })
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// Node moves which revert `node_move_vec`, in reverse order
+pub fn get_reverse_node_move_vec(node_move_vec: &[NodeMove]) -> Vec<NodeMove> {
+    node_move_vec
+        .iter()
+        .rev()
+        .map(|node_move| {
+            NodeMove::new(
+                &node_move.xname,
+                &node_move.to_hsm_group,
+                &node_move.from_hsm_group,
+            )
+        })
+        .collect()
+}
+
+/// Parses `--as-of`, either RFC 3339 (eg 2024-05-14T08:00:00Z) or a date (eg 2024-05-14,
+/// midnight UTC)
+pub fn parse_as_of(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()))
+        .map_err(|_| {
+            format!(
+                "'{}' is not a timestamp, use RFC 3339 (eg 2024-05-14T08:00:00Z) or a date (eg 2024-05-14)",
+                value
+            )
+        })
+}
+
+/// Sets the members HSM groups had at `as_of`. HSM groups changed by history entries recorded
+/// after `as_of` get the members they had before the oldest of those entries, other HSM groups
+/// are left as they are. Changes not applied by clstr are not in the history and are not undone
+pub fn rewind_hsm_group_value_vec(
+    hsm_group_value_vec: &mut [Value],
+    history_entry_vec: &[HistoryEntry],
+    as_of: &DateTime<Utc>,
+) {
+    let mut history_entry_after_vec: Vec<&HistoryEntry> = history_entry_vec
+        .iter()
+        .filter(|history_entry| {
+            DateTime::parse_from_rfc3339(&history_entry.timestamp)
+                .map(|timestamp| timestamp > *as_of)
+                .unwrap_or(false)
+        })
+        .collect();
+
+    // Newest first, so HSM groups end up as before the oldest entry
+    history_entry_after_vec.sort_by_key(|history_entry| std::cmp::Reverse(history_entry.id));
+
+    for hsm_group_snapshot in history_entry_after_vec
+        .iter()
+        .flat_map(|history_entry| &history_entry.before)
+    {
+        if let Some(hsm_group_value) = hsm_group_value_vec
+            .iter_mut()
+            .find(|hsm_group_value| hsm_group_value["label"].eq(&json!(hsm_group_snapshot.label)))
+        {
+            hsm_group_value["members"] = json!({ "ids": hsm_group_snapshot.members });
+        }
+    }
+}
+
+/// HSM groups as they were at `as_of_opt` according to the history, unchanged if None. Fails if
+/// the history file can't be read
+pub fn rewind_hsm_group_value_vec_as_of(
+    mut hsm_group_value_vec: Vec<Value>,
+    as_of_opt: Option<&DateTime<Utc>>,
+) -> Result<Vec<Value>, String> {
+    let Some(as_of) = as_of_opt else {
+        return Ok(hsm_group_value_vec);
+    };
+
+    let history_entry_vec = read_history_entry_vec().map_err(|error| {
+        format!(
+            "could not read history file '{}': {}",
+            get_history_file_path().display(),
+            error
+        )
+    })?;
+
+    rewind_hsm_group_value_vec(&mut hsm_group_value_vec, &history_entry_vec, as_of);
+
+    Ok(hsm_group_value_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_reverse_node_move_vec() {
+        let node_move_vec = vec![
+            NodeMove::new("x1000c0s0b0n0", "nodes_free", "zinal"),
+            NodeMove::new("x1000c0s0b0n1", "zinal", "nodes_free"),
+        ];
+
+        assert_eq!(
+            get_reverse_node_move_vec(&node_move_vec),
+            vec![
+                NodeMove::new("x1000c0s0b0n1", "nodes_free", "zinal"),
+                NodeMove::new("x1000c0s0b0n0", "zinal", "nodes_free"),
+            ]
+        );
+        assert_eq!(
+            get_hsm_group_name_vec(&node_move_vec),
+            vec!["nodes_free", "zinal"]
+        );
+    }
+
+    #[test]
+    fn test_rewind_hsm_group_value_vec() {
+        let history_entry =
+            |id: u64, timestamp: &str, zinal: &[&str], nodes_free: &[&str]| HistoryEntry {
+                id,
+                timestamp: timestamp.to_string(),
+                user: "operator".to_string(),
+                command: "apply hsm-group".to_string(),
+                node_moves: Vec::new(),
+                before: vec![
+                    HsmGroupSnapshot {
+                        label: "zinal".to_string(),
+                        members: zinal.iter().map(|xname| xname.to_string()).collect(),
+                    },
+                    HsmGroupSnapshot {
+                        label: "nodes_free".to_string(),
+                        members: nodes_free.iter().map(|xname| xname.to_string()).collect(),
+                    },
+                ],
+                after: Vec::new(),
+                error: None,
+                rolled_back: false,
+            };
+
+        let history_entry_vec = vec![
+            history_entry(1, "2024-05-01T10:00:00+00:00", &[], &["n0", "n1"]),
+            history_entry(2, "2024-05-10T10:00:00+00:00", &["n0"], &["n1"]),
+            history_entry(3, "2024-05-20T10:00:00+00:00", &["n0", "n1"], &[]),
+        ];
+
+        let mut hsm_group_value_vec = vec![
+            json!({ "label": "zinal", "members": { "ids": ["n1"] } }),
+            json!({ "label": "nodes_free", "members": { "ids": ["n0"] } }),
+            json!({ "label": "eiger", "members": { "ids": ["n2"] } }),
+        ];
+
+        rewind_hsm_group_value_vec(
+            &mut hsm_group_value_vec,
+            &history_entry_vec,
+            &parse_as_of("2024-05-05").unwrap(),
+        );
+
+        // As before entry 2, HSM groups not in the history are left as they are
+        assert_eq!(
+            hsm_group_value_vec,
+            vec![
+                json!({ "label": "zinal", "members": { "ids": ["n0"] } }),
+                json!({ "label": "nodes_free", "members": { "ids": ["n1"] } }),
+                json!({ "label": "eiger", "members": { "ids": ["n2"] } }),
+            ]
+        );
+
+        rewind_hsm_group_value_vec(
+            &mut hsm_group_value_vec,
+            &history_entry_vec,
+            &parse_as_of("2024-04-30T00:00:00Z").unwrap(),
+        );
+
+        assert_eq!(
+            hsm_group_value_vec[..2],
+            vec![
+                json!({ "label": "zinal", "members": { "ids": [] } }),
+                json!({ "label": "nodes_free", "members": { "ids": ["n0", "n1"] } }),
+            ]
+        );
+    }
+}
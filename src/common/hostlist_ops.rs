@@ -0,0 +1,232 @@
+/// Expands a hostlist expression into the list of hosts (xnames) it represents. Hosts are
+/// separated by commas and brackets hold comma separated numbers or ranges, eg
+/// `x1001c1s5b0n[0-1],x1001c1s[6,7]b0n0` expands to x1001c1s5b0n0, x1001c1s5b0n1, x1001c1s6b0n0
+/// and x1001c1s7b0n0. Ranges keep leading zeros (`nid[001-003]`). Duplicated hosts are removed
+pub fn expand_hostlist(hostlist: &str) -> Result<Vec<String>, String> {
+    let mut host_vec: Vec<String> = Vec::new();
+
+    for host_expression in split_hostlist(hostlist)? {
+        for host in expand_host_expression(&host_expression)? {
+            if !host_vec.contains(&host) {
+                host_vec.push(host);
+            }
+        }
+    }
+
+    Ok(host_vec)
+}
+
+/// Compresses a list of hosts into a hostlist expression, the reverse of `expand_hostlist`. Hosts
+/// sharing the prefix before their trailing digits (and the same number of digits) are grouped in
+/// brackets, eg nid001000, nid001001, nid001002 and nid001005 compress to `nid[001000-001002,001005]`
+pub fn compress_hostlist(host_vec: &[String]) -> String {
+    // (prefix, number of digits) -> numbers, in order of first appearance
+    let mut host_group_vec: Vec<((String, usize), Vec<usize>)> = Vec::new();
+    let mut hostlist_item_vec: Vec<String> = Vec::new();
+
+    for host in host_vec {
+        let prefix = host.trim_end_matches(|character: char| character.is_ascii_digit());
+        let digits = &host[prefix.len()..];
+
+        let Ok(number) = digits.parse::<usize>() else {
+            // No trailing digits
+            if !hostlist_item_vec.contains(host) {
+                hostlist_item_vec.push(host.clone());
+            }
+            continue;
+        };
+
+        let key = (prefix.to_string(), digits.len());
+
+        match host_group_vec
+            .iter_mut()
+            .find(|(group_key, _)| *group_key == key)
+        {
+            Some((_, number_vec)) => number_vec.push(number),
+            None => host_group_vec.push((key, vec![number])),
+        }
+    }
+
+    for ((prefix, width), mut number_vec) in host_group_vec {
+        number_vec.sort();
+        number_vec.dedup();
+
+        if number_vec.len() == 1 {
+            hostlist_item_vec.push(format!("{}{:0width$}", prefix, number_vec[0]));
+            continue;
+        }
+
+        let mut range_vec: Vec<String> = Vec::new();
+        let mut range_start = number_vec[0];
+        let mut range_end = number_vec[0];
+
+        for &number in number_vec.iter().skip(1).chain([usize::MAX].iter()) {
+            if number == range_end + 1 {
+                range_end = number;
+                continue;
+            }
+
+            if range_start == range_end {
+                range_vec.push(format!("{:0width$}", range_start));
+            } else {
+                range_vec.push(format!("{:0width$}-{:0width$}", range_start, range_end));
+            }
+
+            range_start = number;
+            range_end = number;
+        }
+
+        hostlist_item_vec.push(format!("{}[{}]", prefix, range_vec.join(",")));
+    }
+
+    hostlist_item_vec.join(",")
+}
+
+/// Splits a hostlist by commas outside brackets
+fn split_hostlist(hostlist: &str) -> Result<Vec<String>, String> {
+    let mut host_expression_vec = Vec::new();
+    let mut host_expression = String::new();
+    let mut bracket_depth = 0;
+
+    for character in hostlist.chars() {
+        match character {
+            '[' => {
+                if bracket_depth > 0 {
+                    return Err(format!("nested brackets not supported in '{}'", hostlist));
+                }
+                bracket_depth += 1;
+                host_expression.push(character);
+            }
+            ']' => {
+                if bracket_depth == 0 {
+                    return Err(format!("unbalanced brackets in '{}'", hostlist));
+                }
+                bracket_depth -= 1;
+                host_expression.push(character);
+            }
+            ',' if bracket_depth == 0 => {
+                host_expression_vec.push(std::mem::take(&mut host_expression));
+            }
+            _ => host_expression.push(character),
+        }
+    }
+
+    if bracket_depth != 0 {
+        return Err(format!("unbalanced brackets in '{}'", hostlist));
+    }
+
+    host_expression_vec.push(host_expression);
+
+    Ok(host_expression_vec
+        .into_iter()
+        .map(|host_expression| host_expression.trim().to_string())
+        .filter(|host_expression| !host_expression.is_empty())
+        .collect())
+}
+
+fn expand_host_expression(host_expression: &str) -> Result<Vec<String>, String> {
+    let Some(bracket_start) = host_expression.find('[') else {
+        return Ok(vec![host_expression.to_string()]);
+    };
+
+    // split_hostlist already checked brackets are balanced
+    let bracket_end = bracket_start + host_expression[bracket_start..].find(']').unwrap();
+
+    let prefix = &host_expression[..bracket_start];
+    let suffix = &host_expression[bracket_end + 1..];
+
+    let mut host_vec = Vec::new();
+
+    for value in expand_bracket(&host_expression[bracket_start + 1..bracket_end])? {
+        host_vec.extend(expand_host_expression(&format!(
+            "{}{}{}",
+            prefix, value, suffix
+        ))?);
+    }
+
+    Ok(host_vec)
+}
+
+/// Expands the content of a bracket like `0-3,7` into 0, 1, 2, 3 and 7
+fn expand_bracket(bracket: &str) -> Result<Vec<String>, String> {
+    let mut value_vec = Vec::new();
+
+    for item in bracket.split(',').map(|item| item.trim()) {
+        match item.split_once('-') {
+            Some((start, end)) => {
+                let start_value = start
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid range '{}'", item))?;
+                let end_value = end
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid range '{}'", item))?;
+
+                if start_value > end_value {
+                    return Err(format!("invalid range '{}'", item));
+                }
+
+                let width = if start.starts_with('0') {
+                    start.len()
+                } else {
+                    0
+                };
+
+                value_vec
+                    .extend((start_value..=end_value).map(|value| format!("{:0width$}", value)));
+            }
+            None => {
+                if item.is_empty() || !item.chars().all(|character| character.is_ascii_digit()) {
+                    return Err(format!("invalid value '{}'", item));
+                }
+
+                value_vec.push(item.to_string());
+            }
+        }
+    }
+
+    Ok(value_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_hostlist() {
+        assert_eq!(
+            expand_hostlist("x1001c1s5b0n[0-1],x1001c1s[6,7]b0n0").unwrap(),
+            vec![
+                "x1001c1s5b0n0",
+                "x1001c1s5b0n1",
+                "x1001c1s6b0n0",
+                "x1001c1s7b0n0"
+            ]
+        );
+        assert_eq!(
+            expand_hostlist("nid[008-010], nid009").unwrap(),
+            vec!["nid008", "nid009", "nid010"]
+        );
+        assert!(expand_hostlist("x1001c1s5b0n[1-0]").is_err());
+        assert!(expand_hostlist("x1001c1s5b0n[0-1").is_err());
+    }
+
+    #[test]
+    fn test_compress_hostlist() {
+        let host_vec: Vec<String> = ["nid001005", "nid001000", "nid001001", "nid001002", "login"]
+            .iter()
+            .map(|host| host.to_string())
+            .collect();
+
+        assert_eq!(
+            compress_hostlist(&host_vec),
+            "login,nid[001000-001002,001005]"
+        );
+        assert_eq!(
+            expand_hostlist(&compress_hostlist(&host_vec))
+                .unwrap()
+                .len(),
+            5
+        );
+        assert_eq!(compress_hostlist(&["nid001000".to_string()]), "nid001000");
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// Words in processor/accelerator models which don't tell hw components apart
+const MODEL_STOPWORD_VEC: [&str; 8] = [
+    "processor",
+    "core",
+    "cores",
+    "cpu",
+    "gpu",
+    "with",
+    "and",
+    "tm",
+];
+
+/// Vendors, skipped when picking the canonical hw component of a model since they don't tell apart
+/// processors from accelerators (eg AMD EPYC and AMD Instinct)
+const VENDOR_VEC: [&str; 4] = ["amd", "nvidia", "intel", "arm"];
+
+/// Hw component counting the HSN NICs of a node
+pub const HSN_NIC_HW_COMPONENT: &str = "hsn";
+
+/// Hw component counting the drives of a node regardless of their kind (nvme, ssd or hdd)
+pub const DRIVE_HW_COMPONENT: &str = "drive";
+
+/// Keyword which can be used as hw component in patterns, found in the model of processors or
+/// accelerators
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HwComponentKeyword {
+    pub keyword: String,
+    /// Processors and accelerators whose model contains the keyword
+    pub components: usize,
+    /// Nodes with at least one processor or accelerator whose model contains the keyword
+    pub nodes: usize,
+    pub models: Vec<String>,
+}
+
+/// Keywords in a processor/accelerator model eg 'AMD EPYC 7742 64-Core Processor' --> [amd, epyc].
+/// Words without letters (eg model numbers) are left out
+pub fn get_model_keyword_vec(model: &str) -> Vec<String> {
+    let mut keyword_vec: Vec<String> = model
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| {
+            word.len() > 1
+                && word.chars().any(|c| c.is_ascii_alphabetic())
+                && !MODEL_STOPWORD_VEC.contains(word)
+        })
+        .map(|word| word.to_string())
+        .collect();
+
+    keyword_vec.dedup();
+
+    keyword_vec
+}
+
+/// Hw component used in patterns for a processor/accelerator model, this is, its first keyword
+/// which is not a vendor eg 'AMD EPYC 7742 64-Core Processor' --> epyc, 'NVIDIA A100-SXM4-40GB'
+/// --> a100. The keyword is part of the model in lowercase, so it matches the model when the
+/// pattern is applied
+pub fn get_canonical_hw_component(model: &str) -> String {
+    let keyword_vec = get_model_keyword_vec(model);
+
+    keyword_vec
+        .iter()
+        .find(|keyword| !VENDOR_VEC.contains(&keyword.as_str()))
+        .or(keyword_vec.first())
+        .cloned()
+        .unwrap_or_else(|| {
+            model
+                .to_lowercase()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect()
+        })
+}
+
+/// Hw component of a drive from its protocol and media type (Drives[].PopulatedFRU.DriveFRUInfo)
+/// eg NVMe --> nvme, SATA SSD --> ssd. Drives of unknown kind are just 'drive'
+pub fn get_drive_hw_component(protocol: Option<&str>, media_type: Option<&str>) -> &'static str {
+    let protocol = protocol.unwrap_or_default().to_lowercase();
+    let media_type = media_type.unwrap_or_default().to_lowercase();
+
+    if protocol == "nvme" {
+        "nvme"
+    } else if media_type == "ssd" {
+        "ssd"
+    } else if media_type == "hdd" {
+        "hdd"
+    } else {
+        DRIVE_HW_COMPONENT
+    }
+}
+
+/// Memory (bytes) of an accelerator from its model eg 'NVIDIA A100-SXM4-40GB' --> 40gb. CSM does
+/// not report GPU memory in NodeAccelFRUInfo. None if the model has no memory size
+pub fn get_accelerator_memory_from_model(model: &str) -> Option<u64> {
+    model
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| word.ends_with("gb") && word.starts_with(|c: char| c.is_ascii_digit()))
+        .find_map(|word| crate::common::memory_ops::parse_memory_size(word).ok())
+}
+
+/// Keywords found in the processor/accelerator models of each node, sorted by keyword.
+/// `node_model_vec` is a list of (xname, models of its processors and accelerators)
+pub fn get_hw_component_keyword_vec(
+    node_model_vec: &[(String, Vec<String>)],
+) -> Vec<HwComponentKeyword> {
+    // keyword -> (components, nodes, models)
+    let mut keyword_hashmap: BTreeMap<String, (usize, BTreeSet<&String>, BTreeSet<&String>)> =
+        BTreeMap::new();
+
+    for (xname, model_vec) in node_model_vec {
+        for model in model_vec {
+            for keyword in get_model_keyword_vec(model) {
+                let (components, node_set, model_set) = keyword_hashmap.entry(keyword).or_default();
+
+                *components += 1;
+                node_set.insert(xname);
+                model_set.insert(model);
+            }
+        }
+    }
+
+    keyword_hashmap
+        .into_iter()
+        .map(
+            |(keyword, (components, node_set, model_set))| HwComponentKeyword {
+                keyword,
+                components,
+                nodes: node_set.len(),
+                models: model_set.into_iter().cloned().collect(),
+            },
+        )
+        .collect()
+}
+
+/// Closest keyword to a hw component which matches no node, used for "did you mean" suggestions.
+/// None if no keyword is close enough
+pub fn get_closest_keyword(hw_component: &str, keyword_vec: &[String]) -> Option<String> {
+    let max_distance = (hw_component.len() / 3).max(1);
+
+    keyword_vec
+        .iter()
+        .map(|keyword| (calculate_edit_distance(hw_component, keyword), keyword))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, keyword)| keyword.clone())
+}
+
+/// Levenshtein distance
+fn calculate_edit_distance(word_1: &str, word_2: &str) -> usize {
+    let char_vec_2: Vec<char> = word_2.chars().collect();
+
+    let mut distance_vec: Vec<usize> = (0..=char_vec_2.len()).collect();
+
+    for (i, c1) in word_1.chars().enumerate() {
+        let mut previous_diagonal = distance_vec[0];
+        distance_vec[0] = i + 1;
+
+        for (j, c2) in char_vec_2.iter().enumerate() {
+            let previous = distance_vec[j + 1];
+
+            distance_vec[j + 1] = if c1 == *c2 {
+                previous_diagonal
+            } else {
+                1 + previous_diagonal.min(previous).min(distance_vec[j])
+            };
+
+            previous_diagonal = previous;
+        }
+    }
+
+    distance_vec[char_vec_2.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_hw_component_keyword_vec() {
+        assert_eq!(
+            get_model_keyword_vec("AMD EPYC 7742 64-Core Processor"),
+            vec!["amd", "epyc"]
+        );
+
+        let hw_component_keyword_vec = get_hw_component_keyword_vec(&[
+            (
+                "x1000c0s0b0n0".to_string(),
+                vec![
+                    "AMD EPYC 7742 64-Core Processor".to_string(),
+                    "AMD EPYC 7742 64-Core Processor".to_string(),
+                    "NVIDIA A100-SXM4-40GB".to_string(),
+                ],
+            ),
+            (
+                "x1000c0s0b0n1".to_string(),
+                vec!["AMD EPYC 7742 64-Core Processor".to_string()],
+            ),
+        ]);
+
+        let epyc = hw_component_keyword_vec
+            .iter()
+            .find(|hw_component_keyword| hw_component_keyword.keyword == "epyc")
+            .unwrap();
+
+        assert_eq!((epyc.components, epyc.nodes), (3, 2));
+
+        let keyword_vec: Vec<String> = hw_component_keyword_vec
+            .into_iter()
+            .map(|hw_component_keyword| hw_component_keyword.keyword)
+            .collect();
+
+        assert_eq!(
+            get_closest_keyword("a10", &keyword_vec),
+            Some("a100".to_string())
+        );
+        assert_eq!(
+            get_closest_keyword("epic", &keyword_vec),
+            Some("epyc".to_string())
+        );
+        assert_eq!(get_closest_keyword("instinct", &keyword_vec), None);
+
+        assert_eq!(
+            get_canonical_hw_component("AMD EPYC 7742 64-Core Processor"),
+            "epyc"
+        );
+        assert_eq!(get_canonical_hw_component("NVIDIA A100-SXM4-40GB"), "a100");
+        assert_eq!(
+            get_canonical_hw_component("AMD Instinct MI250X"),
+            "instinct"
+        );
+    }
+
+    #[test]
+    fn test_get_drive_hw_component() {
+        assert_eq!(get_drive_hw_component(Some("NVMe"), Some("SSD")), "nvme");
+        assert_eq!(get_drive_hw_component(Some("SATA"), Some("SSD")), "ssd");
+        assert_eq!(get_drive_hw_component(Some("SAS"), Some("HDD")), "hdd");
+        assert_eq!(get_drive_hw_component(None, None), DRIVE_HW_COMPONENT);
+    }
+
+    #[test]
+    fn test_get_accelerator_memory_from_model() {
+        assert_eq!(
+            get_accelerator_memory_from_model("NVIDIA A100-SXM4-40GB"),
+            Some(40 * 1024 * 1024 * 1024)
+        );
+        assert_eq!(
+            get_accelerator_memory_from_model("NVIDIA H100 80GB HBM3"),
+            Some(80 * 1024 * 1024 * 1024)
+        );
+        assert_eq!(
+            get_accelerator_memory_from_model("AMD Instinct MI250X"),
+            None
+        );
+    }
+}
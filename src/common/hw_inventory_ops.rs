@@ -0,0 +1,216 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use serde_json::Value;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+
+use crate::{
+    common::csm_version_ops,
+    shasta::{hsm, ShastaClient},
+};
+
+/// Chassis of a node, eg x1000c0s1b0n0 -> x1000c0. None if the xname has no chassis
+pub fn get_chassis_xname(xname: &str) -> Option<String> {
+    let cabinet_end = xname.find('c')?;
+
+    let chassis_digit_count = xname[cabinet_end + 1..]
+        .chars()
+        .take_while(|character| character.is_ascii_digit())
+        .count();
+
+    if !xname.starts_with('x') || chassis_digit_count == 0 {
+        return None;
+    }
+
+    Some(xname[..cabinet_end + 1 + chassis_digit_count].to_string())
+}
+
+/// Cabinet (rack) of a node, eg x1000c0s1b0n0 -> x1000. None if the xname has no cabinet
+pub fn get_cabinet_xname(xname: &str) -> Option<String> {
+    let cabinet_digit_count = xname
+        .strip_prefix('x')?
+        .chars()
+        .take_while(|character| character.is_ascii_digit())
+        .count();
+
+    if cabinet_digit_count == 0 {
+        return None;
+    }
+
+    Some(xname[..1 + cabinet_digit_count].to_string())
+}
+
+/// xname -> hw inventory of each node, in the same format as a per node query
+/// (`{"Nodes": [<node>]}`) so callers can use either. Nodes are fetched with one HSM query per
+/// chassis and, if the chassis query is not supported by the CSM version or misses nodes, with
+/// one query per node. Nodes which can't be fetched are left out, fails if the CSM API client
+/// can't be built
+#[tracing::instrument(skip_all, fields(nodes = xname_vec.len()))]
+pub async fn get_node_hw_inventory_hashmap(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+) -> Result<HashMap<String, Value>, Box<dyn std::error::Error>> {
+    // Nodes without chassis in their xname are left for per node queries, all nodes if the CSM
+    // version does not support chassis queries
+    let mut chassis_xname_hashmap: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for xname in xname_vec
+        .iter()
+        .filter(|_| csm_version_ops::is_chassis_hw_inventory_query_supported())
+    {
+        if let Some(chassis_xname) = get_chassis_xname(xname) {
+            chassis_xname_hashmap
+                .entry(chassis_xname)
+                .or_default()
+                .push(xname.clone());
+        }
+    }
+
+    let mut node_hw_inventory_hashmap: HashMap<String, Value> = HashMap::new();
+
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)?;
+
+    let mut tasks = tokio::task::JoinSet::new();
+
+    let sem = Arc::new(Semaphore::new(
+        csm_version_ops::get_max_concurrent_requests(),
+    ));
+
+    for chassis_xname in chassis_xname_hashmap.keys().cloned() {
+        let shasta_client = Arc::clone(&shasta_client);
+
+        let permit = Arc::clone(&sem).acquire_owned().await;
+
+        // Spawned tasks don't inherit the current span
+        let span = tracing::Span::current();
+
+        tasks.spawn(
+            async move {
+                let _permit = permit;
+                let hw_inventory_rslt = hsm::http_client::get_hw_inventory_query(
+                    &shasta_client,
+                    &chassis_xname,
+                    "Node",
+                )
+                .await
+                .map_err(|error| error.to_string());
+
+                (chassis_xname, hw_inventory_rslt)
+            }
+            .instrument(span),
+        );
+    }
+
+    while let Some(message) = tasks.join_next().await {
+        match message {
+            Ok((chassis_xname, Ok(chassis_hw_inventory_value))) => {
+                let chassis_member_vec = &chassis_xname_hashmap[&chassis_xname];
+
+                for node_hw_inventory_value in chassis_hw_inventory_value["Nodes"]
+                    .as_array()
+                    .unwrap_or(&Vec::new())
+                {
+                    if let Some(xname) = node_hw_inventory_value["ID"].as_str() {
+                        if chassis_member_vec.iter().any(|member| member.eq(xname)) {
+                            node_hw_inventory_hashmap.insert(
+                                xname.to_string(),
+                                serde_json::json!({ "Nodes": [node_hw_inventory_value] }),
+                            );
+                        }
+                    }
+                }
+            }
+            Ok((chassis_xname, Err(error))) => log::warn!(
+                "Could not query hw inventory of chassis '{}', falling back to per node queries: {}",
+                chassis_xname,
+                error
+            ),
+            Err(error) => log::error!("Failed fetching chassis hw inventory: {}", error),
+        }
+    }
+
+    // Nodes missing in chassis queries
+    let xname_missing_vec: Vec<String> = xname_vec
+        .iter()
+        .filter(|xname| !node_hw_inventory_hashmap.contains_key(*xname))
+        .cloned()
+        .collect();
+
+    log::info!(
+        "Hw inventory of {} nodes fetched with {} chassis queries, {} nodes left for per node queries",
+        node_hw_inventory_hashmap.len(),
+        chassis_xname_hashmap.len(),
+        xname_missing_vec.len()
+    );
+
+    for xname in xname_missing_vec {
+        let shasta_client = Arc::clone(&shasta_client);
+
+        let permit = Arc::clone(&sem).acquire_owned().await;
+
+        let span = tracing::info_span!("get_hw_inventory", xname = xname);
+
+        tasks.spawn(
+            async move {
+                let _permit = permit;
+                let hw_inventory_rslt =
+                    hsm::http_client::get_hw_inventory_query(&shasta_client, &xname, "Node")
+                        .await
+                        .map_err(|error| error.to_string());
+
+                (xname, hw_inventory_rslt)
+            }
+            .instrument(span),
+        );
+    }
+
+    while let Some(message) = tasks.join_next().await {
+        match message {
+            Ok((xname, Ok(node_hw_inventory_value))) => {
+                node_hw_inventory_hashmap.insert(xname, node_hw_inventory_value);
+            }
+            Ok((xname, Err(error))) => {
+                log::error!(
+                    "Could not fetch hw inventory of node '{}': {}",
+                    xname,
+                    error
+                )
+            }
+            Err(error) => log::error!("Failed fetching node hw inventory: {}", error),
+        }
+    }
+
+    Ok(node_hw_inventory_hashmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_chassis_xname() {
+        assert_eq!(
+            get_chassis_xname("x1000c0s1b0n0"),
+            Some("x1000c0".to_string())
+        );
+        assert_eq!(
+            get_chassis_xname("x3000c12s3b0n1"),
+            Some("x3000c12".to_string())
+        );
+        assert_eq!(get_chassis_xname("nid001000"), None);
+    }
+
+    #[test]
+    fn test_get_cabinet_xname() {
+        assert_eq!(
+            get_cabinet_xname("x1000c0s1b0n0"),
+            Some("x1000".to_string())
+        );
+        assert_eq!(get_cabinet_xname("nid001000"), None);
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    ffi::CString,
+    fs::{File, OpenOptions, Permissions},
+    io::{ErrorKind, Read, Write},
+    os::unix::{
+        fs::{MetadataExt, OpenOptionsExt, PermissionsExt},
+        io::AsRawFd,
+    },
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::common::config_ops;
+
+static SITE_NAME: OnceLock<String> = OnceLock::new();
+
+static LOCK_DIR_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+static LOCK_GROUP_NAME: OnceLock<String> = OnceLock::new();
+
+/// Sets the site the lock files are keyed by, `lock_dir` and `lock_group` in configuration file
+/// for the rest of the execution
+pub fn configure(site_name: &str, lock_dir_opt: Option<String>, lock_group_opt: Option<String>) {
+    let _ = SITE_NAME.set(site_name.to_string());
+
+    if let Some(lock_dir) = lock_dir_opt {
+        let _ = LOCK_DIR_PATH.set(PathBuf::from(lock_dir));
+    }
+
+    if let Some(lock_group) = lock_group_opt {
+        let _ = LOCK_GROUP_NAME.set(lock_group);
+    }
+}
+
+/// Lock files live in `lock_dir` of configuration file, shared by all operators so their runs
+/// exclude each other. Runs on different hosts only exclude each other if `lock_dir` is on a
+/// file system shared by the hosts (with working flock, eg NFSv4). Without `lock_dir` they live
+/// in the runtime directory of the user ($XDG_RUNTIME_DIR/clstr on Linux), falls back to the
+/// temp directory if there is no runtime directory
+pub fn get_lock_dir_path() -> PathBuf {
+    LOCK_DIR_PATH.get().cloned().unwrap_or_else(|| {
+        config_ops::get_project_dirs()
+            .runtime_dir()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                std::env::temp_dir().join(format!("clstr-locks-{}", unsafe { libc::geteuid() }))
+            })
+    })
+}
+
+/// Lock directory must be owned by the user or by `lock_group` and not writable by others,
+/// otherwise any local user could plant lock files (eg symlinks to files of the operator)
+fn check_lock_dir(lock_dir_path: &Path) -> Result<(), String> {
+    let metadata = std::fs::symlink_metadata(lock_dir_path).map_err(|error| {
+        format!(
+            "could not read lock directory '{}': {}",
+            lock_dir_path.display(),
+            error
+        )
+    })?;
+
+    if !metadata.is_dir() {
+        return Err(format!(
+            "lock directory '{}' is not a directory",
+            lock_dir_path.display()
+        ));
+    }
+
+    // SAFETY: geteuid has no preconditions
+    let is_owned_by_user = metadata.uid() == unsafe { libc::geteuid() };
+
+    let is_owned_by_lock_group = LOCK_GROUP_NAME
+        .get()
+        .and_then(|lock_group_name| get_group_id(lock_group_name))
+        .is_some_and(|lock_group_id| metadata.gid() == lock_group_id);
+
+    if !is_owned_by_user && !is_owned_by_lock_group {
+        return Err(format!(
+            "lock directory '{}' is not owned by the user nor by 'lock_group' in configuration file",
+            lock_dir_path.display()
+        ));
+    }
+
+    if metadata.mode() & 0o002 != 0 {
+        return Err(format!(
+            "lock directory '{}' is writable by any user",
+            lock_dir_path.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Gid of a group name
+fn get_group_id(group_name: &str) -> Option<u32> {
+    let group_name = CString::new(group_name).ok()?;
+
+    // SAFETY: getgrnam reads a nul terminated string, the group returned is only read here
+    let group = unsafe { libc::getgrnam(group_name.as_ptr()) };
+
+    if group.is_null() {
+        None
+    } else {
+        Some(unsafe { (*group).gr_gid })
+    }
+}
+
+/// Opens the lock file without following symlinks, creates it if missing. Returns whether the
+/// file was created by this run
+fn open_lock_file(lock_file_path: &Path) -> Result<(File, bool), std::io::Error> {
+    match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .mode(0o664)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(lock_file_path)
+    {
+        Ok(lock_file) => Ok((lock_file, true)),
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(lock_file_path)
+            .map(|lock_file| (lock_file, false)),
+        Err(error) => Err(error),
+    }
+}
+
+/// One lock file per site and HSM group, so runs sharing any HSM group (eg the parent HSM group)
+/// exclude each other
+pub fn get_lock_file_path(site_name: &str, hsm_group_name: &str) -> PathBuf {
+    get_lock_dir_path().join(format!("{}.{}.lock", site_name, hsm_group_name))
+}
+
+/// Takes an advisory lock (flock) on each HSM group of the site configured so concurrent runs
+/// planning or applying changes to the same HSM groups fail instead of racing each other. Locks
+/// are released when the files returned are dropped or the process exits
+pub fn lock_hsm_group_vec(hsm_group_name_vec: &[String]) -> Result<Vec<File>, String> {
+    let site_name = SITE_NAME.get().map(String::as_str).unwrap_or("default");
+
+    let lock_dir_path = get_lock_dir_path();
+
+    if !lock_dir_path.exists() {
+        std::fs::create_dir_all(&lock_dir_path).map_err(|error| {
+            format!(
+                "could not create lock directory '{}': {}",
+                lock_dir_path.display(),
+                error
+            )
+        })?;
+
+        // Group writable regardless of umask if shared with 'lock_group', lock files inherit
+        // the group of the directory (setgid) so operators sharing a group can lock the HSM
+        // groups
+        let _ = std::fs::set_permissions(
+            &lock_dir_path,
+            Permissions::from_mode(if LOCK_GROUP_NAME.get().is_some() {
+                0o2775
+            } else {
+                0o700
+            }),
+        );
+    }
+
+    check_lock_dir(&lock_dir_path)?;
+
+    let mut hsm_group_name_sorted_vec = hsm_group_name_vec.to_vec();
+    hsm_group_name_sorted_vec.sort();
+    hsm_group_name_sorted_vec.dedup();
+
+    let mut lock_file_vec = Vec::new();
+
+    for hsm_group_name in hsm_group_name_sorted_vec {
+        let lock_file_path = get_lock_file_path(site_name, &hsm_group_name);
+
+        let (mut lock_file, is_created) = open_lock_file(&lock_file_path).map_err(|error| {
+            format!(
+                "could not open lock file '{}': {}, it must be a file writable by all operators",
+                lock_file_path.display(),
+                error
+            )
+        })?;
+
+        let is_owned_by_user = lock_file
+            .metadata()
+            .map(|metadata| metadata.is_file() && metadata.uid() == unsafe { libc::geteuid() })
+            .unwrap_or(false);
+
+        // Group writable regardless of umask, only on lock files created by this run
+        if is_created {
+            let _ = lock_file.set_permissions(Permissions::from_mode(0o664));
+        }
+
+        // SAFETY: flock only reads the file descriptor, which is valid while lock_file lives
+        if unsafe { libc::flock(lock_file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let mut pid = String::new();
+            let _ = lock_file.read_to_string(&mut pid);
+
+            return Err(format!(
+                "plan in progress, HSM group '{}' of site '{}' is being changed by another run (pid {}). Lock file '{}'",
+                hsm_group_name,
+                site_name,
+                if pid.trim().is_empty() { "unknown" } else { pid.trim() },
+                lock_file_path.display()
+            ));
+        }
+
+        // Pid of the lock holder, only informative. Lock files of other operators are left as
+        // they are
+        if is_owned_by_user {
+            let _ = lock_file.set_len(0);
+            let _ = write!(lock_file, "{}", std::process::id());
+        }
+
+        log::debug!("Lock file '{}' locked", lock_file_path.display());
+
+        lock_file_vec.push(lock_file);
+    }
+
+    Ok(lock_file_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_lock_file_refuses_symlink() {
+        let lock_dir = tempfile::tempdir().unwrap();
+
+        let target_file_path = lock_dir.path().join("authorized_keys");
+        std::fs::write(&target_file_path, "ssh-ed25519 AAAA").unwrap();
+
+        let lock_file_path = lock_dir.path().join("alps.zinal.lock");
+        std::os::unix::fs::symlink(&target_file_path, &lock_file_path).unwrap();
+
+        assert!(open_lock_file(&lock_file_path).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&target_file_path).unwrap(),
+            "ssh-ed25519 AAAA"
+        );
+
+        let (_, is_created) = open_lock_file(&lock_dir.path().join("alps.eiger.lock")).unwrap();
+        assert!(is_created);
+
+        let (_, is_created) = open_lock_file(&lock_dir.path().join("alps.eiger.lock")).unwrap();
+        assert!(!is_created);
+    }
+}
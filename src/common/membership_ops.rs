@@ -0,0 +1,90 @@
+use std::{collections::BTreeMap, error::Error};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::shasta::hsm;
+
+/// HSM groups and partition a node belongs to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct NodeMembership {
+    pub xname: String,
+    pub hsm_groups: Vec<String>,
+    pub partition: Option<String>,
+}
+
+/// Parses the values returned by the HSM memberships API, eg
+/// `{"id": "x1000c0s0b0n0", "groupLabels": ["zinal"], "partitionName": "p1"}`. HSM groups are
+/// sorted
+pub fn get_node_membership_vec(membership_value_vec: &[Value]) -> Vec<NodeMembership> {
+    membership_value_vec
+        .iter()
+        .filter_map(|membership_value| {
+            let xname = membership_value["id"].as_str()?;
+
+            let mut hsm_group_vec: Vec<String> = membership_value["groupLabels"]
+                .as_array()
+                .unwrap_or(&Vec::new())
+                .iter()
+                .filter_map(|hsm_group| hsm_group.as_str().map(str::to_string))
+                .collect();
+            hsm_group_vec.sort();
+
+            Some(NodeMembership {
+                xname: xname.to_string(),
+                hsm_groups: hsm_group_vec,
+                partition: membership_value["partitionName"]
+                    .as_str()
+                    .filter(|partition_name| !partition_name.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Reverse lookup of the HSM groups and partition of each node (all nodes if `xname_vec` is
+/// empty) in a single call, instead of fetching every HSM group and scanning its members
+pub async fn get_node_membership_hashmap(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+) -> Result<BTreeMap<String, NodeMembership>, Box<dyn Error>> {
+    let membership_value_vec = hsm::http_client::get_memberships(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        xname_vec,
+    )
+    .await?;
+
+    Ok(get_node_membership_vec(&membership_value_vec)
+        .into_iter()
+        .map(|node_membership| (node_membership.xname.clone(), node_membership))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_node_membership_vec() {
+        let node_membership_vec = get_node_membership_vec(&[
+            json!({ "id": "x1000c0s0b0n0", "groupLabels": ["zinal", "alps"], "partitionName": "p1" }),
+            json!({ "id": "x1000c0s0b0n1", "groupLabels": [], "partitionName": "" }),
+        ]);
+
+        assert_eq!(
+            node_membership_vec[0],
+            NodeMembership {
+                xname: "x1000c0s0b0n0".to_string(),
+                hsm_groups: vec!["alps".to_string(), "zinal".to_string()],
+                partition: Some("p1".to_string()),
+            }
+        );
+        assert!(node_membership_vec[1].hsm_groups.is_empty());
+        assert_eq!(node_membership_vec[1].partition, None);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::common::format_ops::format_count;
+
+/// Hw component with the total memory capacity of a node. Unlike other hw components, which are
+/// counters, its quantity is in bytes so nodes with different DIMM sizes are compared by capacity
+pub const MEMORY_HW_COMPONENT: &str = "memory";
+
+/// Hw component with the total memory capacity (bytes) of the accelerators of a node
+pub const GPU_MEMORY_HW_COMPONENT: &str = "gpumem";
+
+pub const MIB: u64 = 1024 * 1024;
+
+const GIB: u64 = MIB * 1024;
+
+const TIB: u64 = GIB * 1024;
+
+/// Memory capacity weighing as much as 1 counter of any other hw component in scores, memory used
+/// to be counted in DIMMs of 16 GiB
+const MEMORY_SCORE_UNIT: u64 = 16 * GIB;
+
+/// Parses memory sizes like '512gb', '1tib' or '64g' into bytes. Units are powers of 1024 like
+/// DIMM capacities, therefore 'gb' and 'gib' are the same. Numbers without unit are bytes
+pub fn parse_memory_size(memory_size: &str) -> Result<u64, String> {
+    let memory_size = memory_size.trim().to_lowercase();
+
+    let unit_start = memory_size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(memory_size.len());
+
+    let (value, unit) = memory_size.split_at(unit_start);
+
+    let unit_bytes: u64 = match unit.trim() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => MIB,
+        "g" | "gb" | "gib" => GIB,
+        "t" | "tb" | "tib" => TIB,
+        _ => {
+            return Err(format!(
+                "memory size '{}' not valid, use a number followed by kb, mb, gb or tb (eg 512gb)",
+                memory_size
+            ))
+        }
+    };
+
+    value
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(unit_bytes))
+        .ok_or(format!("memory size '{}' not valid", memory_size))
+}
+
+/// Memory capacity (bytes) requested in a pattern eg memory:512gb or memory:1tb. Numbers without
+/// unit are rejected since patterns used to count memory in DIMMs of 16 GiB, eg memory:32 meant
+/// 512gb
+pub fn parse_memory_request(memory_request: &str) -> Result<u64, String> {
+    if let Ok(dimm_count) = memory_request.trim().parse::<u64>() {
+        return Err(format!(
+            "memory quantity '{}' has no unit, memory is a capacity eg {} (numbers without unit used to count DIMMs of 16 GiB)",
+            memory_request,
+            format_memory_size(dimm_count.saturating_mul(MEMORY_SCORE_UNIT))
+        ));
+    }
+
+    parse_memory_size(memory_request)
+}
+
+/// Total memory capacity (bytes) of a node from the capacities (MiB) of its DIMMs
+pub fn get_node_memory_capacity(dimm_capacity_mib_vec: &[u64]) -> usize {
+    (dimm_capacity_mib_vec.iter().sum::<u64>() * MIB) as usize
+}
+
+/// Greatest common divisor (Euclid's algorithm), 0 only if both numbers are 0
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Largest memory capacity dividing every capacity in `memory_capacity_vec` (eg DIMMs or nodes),
+/// this is, the granularity memory can be allocated with. 0 if there are no capacities
+pub fn calculate_memory_gcd(memory_capacity_vec: &[u64]) -> u64 {
+    memory_capacity_vec
+        .iter()
+        .fold(0, |memory_gcd, memory_capacity| {
+            gcd(memory_gcd, *memory_capacity)
+        })
+}
+
+/// Formats a memory capacity in bytes with the largest unit which keeps it a whole number eg
+/// 549755813888 --> 512gb, so it can be used in patterns
+pub fn format_memory_size(memory_size: u64) -> String {
+    match memory_size {
+        0 => "0gb".to_string(),
+        _ if memory_size % TIB == 0 => format!("{}tb", memory_size / TIB),
+        _ if memory_size % GIB == 0 => format!("{}gb", memory_size / GIB),
+        _ if memory_size % MIB == 0 => format!("{}mb", memory_size / MIB),
+        _ => format!("{}b", memory_size),
+    }
+}
+
+/// Hw components whose quantity is a capacity in bytes rather than a counter
+pub fn is_capacity_hw_component(hw_component: &str) -> bool {
+    hw_component == MEMORY_HW_COMPONENT || hw_component == GPU_MEMORY_HW_COMPONENT
+}
+
+/// Formats the quantity of a hw component in tables, capacities (memory and gpumem) with units
+/// and the rest as counters
+pub fn format_hw_component_qty(hw_component: &str, qty: usize) -> String {
+    if is_capacity_hw_component(hw_component) {
+        format_memory_size(qty as u64)
+    } else {
+        format_count(qty)
+    }
+}
+
+/// Weight of a hw component quantity in scores, capacities count 16 GiB units (a DIMM, like memory
+/// was counted before capacities) so they weigh like the other counters instead of dwarfing them
+/// with bytes
+pub fn get_hw_component_score_qty(hw_component: &str, qty: usize) -> usize {
+    if is_capacity_hw_component(hw_component) {
+        qty / MEMORY_SCORE_UNIT as usize
+    } else {
+        qty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_memory_size() {
+        assert_eq!(parse_memory_size("512gb"), Ok(512 * GIB));
+        assert_eq!(parse_memory_size("1TiB"), Ok(TIB));
+        assert_eq!(parse_memory_size("64g"), Ok(64 * GIB));
+        assert!(parse_memory_size("512xb").is_err());
+        assert!(parse_memory_size("gb").is_err());
+
+        assert_eq!(parse_memory_request("2tb"), Ok(2 * TIB));
+        assert!(parse_memory_request("32").unwrap_err().contains("eg 512gb"));
+        assert_eq!(get_hw_component_score_qty("memory", 512 * GIB as usize), 32);
+
+        assert_eq!(get_node_memory_capacity(&[16384, 16384]), 32 * GIB as usize);
+        assert_eq!(format_memory_size(512 * GIB), "512gb");
+        assert_eq!(format_memory_size(1536 * GIB), "1536gb");
+        assert_eq!(format_hw_component_qty("memory", 2 * TIB as usize), "2tb");
+    }
+
+    #[test]
+    fn test_calculate_memory_gcd() {
+        assert_eq!(gcd(16384, 24576), 8192);
+        assert_eq!(gcd(0, 16384), 16384);
+        assert_eq!(calculate_memory_gcd(&[32768, 65536, 16384]), 16384);
+        assert_eq!(calculate_memory_gcd(&[16384, 24576]), 8192);
+        assert_eq!(calculate_memory_gcd(&[]), 0);
+    }
+}
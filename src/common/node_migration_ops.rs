@@ -0,0 +1,792 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common::{
+    audit_ops,
+    event_ops::{self, ChangeEvent},
+    history_ops::{self, HistoryEntry},
+    snapshot_ops::HsmGroupSnapshot,
+};
+
+/// Minutes the members of the HSM groups stay reserved while node moves are applied, maximum
+/// allowed by HSM. Reservations are released once the node moves are applied
+const RESERVATION_DURATION_MINUTES: u64 = 15;
+
+/// (HSM group, node) pairs
+type MemberChangeVec = Vec<(String, String)>;
+
+/// Why node moves were not applied, or only partially applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeMoveError {
+    /// No node was moved because the HSM groups changed since the plan was calculated or the
+    /// nodes moved are reserved by another operation, calculating the plan again may solve it
+    Conflict(String),
+    /// CSM API request failed, the message tells whether node moves were rolled back
+    Failed(String),
+}
+
+impl std::fmt::Display for NodeMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeMoveError::Conflict(message) => write!(f, "conflict, {}", message),
+            NodeMoveError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for NodeMoveError {}
+
+/// Node membership change between 2 HSM groups
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NodeMove {
+    pub xname: String,
+    pub from_hsm_group: String,
+    pub to_hsm_group: String,
+}
+
+impl NodeMove {
+    pub fn new(xname: &str, from_hsm_group: &str, to_hsm_group: &str) -> Self {
+        Self {
+            xname: xname.to_string(),
+            from_hsm_group: from_hsm_group.to_string(),
+            to_hsm_group: to_hsm_group.to_string(),
+        }
+    }
+}
+
+/// Builds the node moves between a target HSM group and its parent HSM group from the nodes added
+/// to and removed from the target HSM group
+pub fn get_node_move_vec(
+    target_hsm_group_name: &str,
+    parent_hsm_group_name: &str,
+    node_added_vec: &[String],
+    node_removed_vec: &[String],
+) -> Vec<NodeMove> {
+    node_added_vec
+        .iter()
+        .map(|xname| NodeMove::new(xname, parent_hsm_group_name, target_hsm_group_name))
+        .chain(
+            node_removed_vec
+                .iter()
+                .map(|xname| NodeMove::new(xname, target_hsm_group_name, parent_hsm_group_name)),
+        )
+        .collect()
+}
+
+/// Moves each node by adding it to the destination HSM group first and then removing it from the
+/// source HSM group, so a node is never left without HSM group if a call fails. Stops on the
+/// first error. Node moves applied are recorded in the history (see common::history_ops) with
+/// `command` and the members of the HSM groups involved before and after
+///
+/// Nodes moved are reserved (HSM locks API) while the node moves are applied. Fails with
+/// `NodeMoveError::Conflict`, without moving any node, if the HSM groups changed since the plan
+/// was calculated or the nodes moved are reserved by another operation
+///
+/// The operator and the CSM API requests sent are recorded in the audit trail (see
+/// common::audit_ops), also if the node moves fail. Nothing is printed, returns the history
+/// entry recorded, if any
+pub async fn apply_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    command: &str,
+) -> Result<Option<u64>, NodeMoveError> {
+    let ((history_id_opt, result), api_call_vec) = audit_ops::audit(apply_node_move_vec_audited(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+        command,
+    ))
+    .await;
+
+    audit_ops::append_audit_entry(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        command,
+        history_id_opt,
+        api_call_vec,
+        result.as_ref().err().map(ToString::to_string),
+    )
+    .await;
+
+    result.map(|_| history_id_opt)
+}
+
+/// `apply_node_move_vec` without the audit entry. Returns the history entry recorded, if any
+async fn apply_node_move_vec_audited(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    command: &str,
+) -> (Option<u64>, Result<(), NodeMoveError>) {
+    let hsm_group_name_vec = history_ops::get_hsm_group_name_vec(node_move_vec);
+
+    let before = match get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &hsm_group_name_vec,
+    )
+    .await
+    {
+        Ok(before) => before,
+        Err(error) => return (None, Err(NodeMoveError::Failed(error.to_string()))),
+    };
+
+    let conflict_vec = get_node_move_conflict_vec(node_move_vec, &before);
+
+    if !conflict_vec.is_empty() {
+        return (
+            None,
+            Err(NodeMoveError::Conflict(format!(
+                "HSM groups changed since the plan was calculated ({}), calculate the plan again",
+                conflict_vec.join(", ")
+            ))),
+        );
+    }
+
+    let reservation_key_vec = match reserve_node_move_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        node_move_vec,
+    )
+    .await
+    {
+        Ok(reservation_key_vec) => reservation_key_vec,
+        Err(error) => return (None, Err(error)),
+    };
+
+    // Members may have changed while the nodes moved were reserved
+    let unchanged = get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &hsm_group_name_vec,
+    )
+    .await
+    .is_ok_and(|locked| locked.eq(&before));
+
+    if !unchanged {
+        release_reservation_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &reservation_key_vec,
+        )
+        .await;
+
+        return (
+            None,
+            Err(NodeMoveError::Conflict(
+                "HSM groups changed while the nodes moved were being reserved, calculate the plan again"
+                    .to_string(),
+            )),
+        );
+    }
+
+    let mut node_move_applied_vec = Vec::new();
+    let mut error_opt = None;
+
+    for node_move in node_move_vec {
+        match apply_node_move(shasta_token, shasta_base_url, shasta_root_cert, node_move).await {
+            Ok(_) => node_move_applied_vec.push(node_move.clone()),
+            Err(error) => {
+                error_opt = Some(error);
+                break;
+            }
+        }
+    }
+
+    // Partial state if a node move failed. HSM groups are reserved, if they can't be read their
+    // members are the ones before plus the node moves applied
+    let after = match get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &hsm_group_name_vec,
+    )
+    .await
+    {
+        Ok(after) => after,
+        Err(error) => {
+            log::warn!(
+                "Could not get HSM groups after applying node moves, history records the node moves applied over the HSM groups before: {}",
+                error
+            );
+            get_hsm_group_snapshot_vec_after(&before, &node_move_applied_vec)
+        }
+    };
+
+    let mut rolled_back = false;
+
+    if let Some(error) = error_opt.as_mut() {
+        // Nodes moved plus the node which failed, it may be half moved
+        let xname_vec: Vec<String> = node_move_vec[..=node_move_applied_vec.len()]
+            .iter()
+            .map(|node_move| node_move.xname.clone())
+            .collect();
+
+        log::warn!(
+            "Failed applying node moves, rolling back {} nodes moved",
+            node_move_applied_vec.len()
+        );
+
+        match rollback_member_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &before,
+            &xname_vec,
+        )
+        .await
+        {
+            Ok(_) => {
+                rolled_back = true;
+                log::info!("Node moves rolled back, HSM groups restored");
+            }
+            Err(rollback_error) => {
+                *error = format!("{}; rollback failed: {}", error, rollback_error);
+            }
+        }
+    }
+
+    release_reservation_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &reservation_key_vec,
+    )
+    .await;
+
+    // Node moves rolled back left HSM groups as they were, nothing to notify
+    if !rolled_back {
+        for node_move in &node_move_applied_vec {
+            event_ops::emit(ChangeEvent::NodeMoved {
+                command: command.to_string(),
+                node_move: node_move.clone(),
+            });
+        }
+
+        for hsm_group_name in event_ops::get_hsm_group_emptied_vec(&before, &after) {
+            event_ops::emit(ChangeEvent::HsmGroupEmptied {
+                command: command.to_string(),
+                hsm_group: hsm_group_name,
+            });
+        }
+    }
+
+    event_ops::publish().await;
+
+    let history_entry = HistoryEntry {
+        id: 0,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        user: crate::common::auth_ops::get_token_username(shasta_token),
+        command: command.to_string(),
+        node_moves: node_move_applied_vec,
+        before,
+        after,
+        error: error_opt.clone(),
+        rolled_back,
+    };
+
+    let history_id_opt = match history_ops::append_history_entry(history_entry) {
+        Ok(id) => Some(id),
+        Err(error) => {
+            log::warn!("Could not record changes in history: {}", error);
+            None
+        }
+    };
+
+    let result = match error_opt {
+        Some(error) if rolled_back => Err(NodeMoveError::Failed(format!(
+            "{}, node moves rolled back",
+            error
+        ))),
+        Some(error) => Err(NodeMoveError::Failed(format!(
+            "{}, HSM groups left partially changed, see 'history show{}' to recover them",
+            error,
+            history_id_opt
+                .map(|id| format!(" {}", id))
+                .unwrap_or_default()
+        ))),
+        None => Ok(()),
+    };
+
+    (history_id_opt, result)
+}
+
+/// Restores the members of the HSM groups in `before` for the nodes in `xname_vec`. Nodes are
+/// added back first and then removed, so a node is never left without HSM group. Other nodes are
+/// left as they are
+async fn rollback_member_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    before: &[HsmGroupSnapshot],
+    xname_vec: &[String],
+) -> Result<(), String> {
+    let current = get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &before
+            .iter()
+            .map(|hsm_group_snapshot| hsm_group_snapshot.label.clone())
+            .collect::<Vec<String>>(),
+    )
+    .await
+    .map_err(|error| error.to_string())?;
+
+    let (member_added_vec, member_removed_vec) =
+        get_rollback_member_change_vec(before, &current, xname_vec);
+
+    for (hsm_group_name, xname) in &member_added_vec {
+        log::info!(
+            "Rollback: adding node '{}' to HSM group '{}'",
+            xname,
+            hsm_group_name
+        );
+
+        crate::shasta::hsm::http_client::post_hsm_group_member(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            hsm_group_name,
+            xname,
+        )
+        .await
+        .map_err(|error| {
+            format!(
+                "could not add node '{}' back to HSM group '{}': {}",
+                xname, hsm_group_name, error
+            )
+        })?;
+    }
+
+    for (hsm_group_name, xname) in &member_removed_vec {
+        log::info!(
+            "Rollback: removing node '{}' from HSM group '{}'",
+            xname,
+            hsm_group_name
+        );
+
+        crate::shasta::hsm::http_client::delete_hsm_group_member(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            hsm_group_name,
+            xname,
+        )
+        .await
+        .map_err(|error| {
+            format!(
+                "could not remove node '{}' from HSM group '{}': {}",
+                xname, hsm_group_name, error
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// (HSM group, node) pairs to add and to remove so the nodes in `xname_vec` are members of the
+/// same HSM groups as in `before`
+pub fn get_rollback_member_change_vec(
+    before: &[HsmGroupSnapshot],
+    current: &[HsmGroupSnapshot],
+    xname_vec: &[String],
+) -> (MemberChangeVec, MemberChangeVec) {
+    let mut member_added_vec = Vec::new();
+    let mut member_removed_vec = Vec::new();
+
+    for hsm_group_snapshot_before in before {
+        let current_member_vec = current
+            .iter()
+            .find(|hsm_group_snapshot| hsm_group_snapshot.label == hsm_group_snapshot_before.label)
+            .map(|hsm_group_snapshot| hsm_group_snapshot.members.clone())
+            .unwrap_or_default();
+
+        for xname in xname_vec {
+            let is_member_before = hsm_group_snapshot_before.members.contains(xname);
+            let is_member_current = current_member_vec.contains(xname);
+
+            if is_member_before && !is_member_current {
+                member_added_vec.push((hsm_group_snapshot_before.label.clone(), xname.clone()));
+            } else if !is_member_before && is_member_current {
+                member_removed_vec.push((hsm_group_snapshot_before.label.clone(), xname.clone()));
+            }
+        }
+    }
+
+    (member_added_vec, member_removed_vec)
+}
+
+/// Node moves which don't match the current members of the HSM groups: node no longer in the
+/// source HSM group or already in the destination HSM group
+pub fn get_node_move_conflict_vec(
+    node_move_vec: &[NodeMove],
+    hsm_group_snapshot_vec: &[HsmGroupSnapshot],
+) -> Vec<String> {
+    let is_member = |hsm_group_name: &str, xname: &String| {
+        hsm_group_snapshot_vec
+            .iter()
+            .find(|hsm_group_snapshot| hsm_group_snapshot.label.eq(hsm_group_name))
+            .is_some_and(|hsm_group_snapshot| hsm_group_snapshot.members.contains(xname))
+    };
+
+    node_move_vec
+        .iter()
+        .filter_map(|node_move| {
+            if !is_member(&node_move.from_hsm_group, &node_move.xname) {
+                Some(format!(
+                    "node '{}' no longer in HSM group '{}'",
+                    node_move.xname, node_move.from_hsm_group
+                ))
+            } else if is_member(&node_move.to_hsm_group, &node_move.xname) {
+                Some(format!(
+                    "node '{}' already in HSM group '{}'",
+                    node_move.xname, node_move.to_hsm_group
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Members of the HSM groups once the node moves are applied over `hsm_group_snapshot_vec`
+pub fn get_hsm_group_snapshot_vec_after(
+    hsm_group_snapshot_vec: &[HsmGroupSnapshot],
+    node_move_vec: &[NodeMove],
+) -> Vec<HsmGroupSnapshot> {
+    let mut hsm_group_snapshot_after_vec = hsm_group_snapshot_vec.to_vec();
+
+    for node_move in node_move_vec {
+        for hsm_group_snapshot in hsm_group_snapshot_after_vec.iter_mut() {
+            if hsm_group_snapshot.label.eq(&node_move.from_hsm_group) {
+                hsm_group_snapshot
+                    .members
+                    .retain(|xname| xname.ne(&node_move.xname));
+            } else if hsm_group_snapshot.label.eq(&node_move.to_hsm_group)
+                && !hsm_group_snapshot.members.contains(&node_move.xname)
+            {
+                hsm_group_snapshot.members.push(node_move.xname.clone());
+                hsm_group_snapshot.members.sort();
+            }
+        }
+    }
+
+    hsm_group_snapshot_after_vec
+}
+
+/// Reserves the nodes moved so concurrent runs moving any of them fail to reserve them, the rest
+/// of the members of the HSM groups are left alone. Reservations expire after
+/// `RESERVATION_DURATION_MINUTES` so nodes are not left reserved if clstr is killed. Returns the
+/// (xname, reservation key) pairs, none if HSM locks API is not available
+async fn reserve_node_move_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+) -> Result<Vec<(String, String)>, NodeMoveError> {
+    let mut xname_vec: Vec<String> = node_move_vec
+        .iter()
+        .map(|node_move| node_move.xname.clone())
+        .collect();
+    xname_vec.sort();
+    xname_vec.dedup();
+
+    if xname_vec.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let reservation_value = match crate::shasta::hsm::http_client::post_locks_reservations(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &xname_vec,
+        RESERVATION_DURATION_MINUTES,
+    )
+    .await
+    {
+        Ok(Some(reservation_value)) => reservation_value,
+        Ok(None) => {
+            log::warn!("HSM locks API not available, nodes are not reserved");
+            return Ok(Vec::new());
+        }
+        Err(error) => {
+            return Err(NodeMoveError::Conflict(format!(
+                "could not reserve nodes moved: {}",
+                error
+            )))
+        }
+    };
+
+    let failure_vec: Vec<String> = reservation_value["Failure"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .map(|failure_value| {
+            format!(
+                "{} ({})",
+                failure_value["ID"].as_str().unwrap_or_default(),
+                failure_value["Reason"].as_str().unwrap_or_default()
+            )
+        })
+        .collect();
+
+    if !failure_vec.is_empty() {
+        return Err(NodeMoveError::Conflict(format!(
+            "nodes moved reserved by another operation: {}",
+            failure_vec.join(", ")
+        )));
+    }
+
+    let reservation_key_vec: Vec<(String, String)> = reservation_value["Success"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter_map(|success_value| {
+            Some((
+                success_value["ID"].as_str()?.to_string(),
+                success_value["ReservationKey"].as_str()?.to_string(),
+            ))
+        })
+        .collect();
+
+    log::info!(
+        "{} nodes reserved for {} minutes",
+        reservation_key_vec.len(),
+        RESERVATION_DURATION_MINUTES
+    );
+
+    Ok(reservation_key_vec)
+}
+
+async fn release_reservation_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    reservation_key_vec: &[(String, String)],
+) {
+    if reservation_key_vec.is_empty() {
+        return;
+    }
+
+    if let Err(error) = crate::shasta::hsm::http_client::post_locks_reservations_release(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        reservation_key_vec,
+    )
+    .await
+    {
+        log::warn!(
+            "Could not release the reservation of {} nodes, they expire in {} minutes: {}",
+            reservation_key_vec.len(),
+            RESERVATION_DURATION_MINUTES,
+            error
+        );
+    }
+}
+
+#[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+async fn apply_node_move(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move: &NodeMove,
+) -> Result<(), String> {
+    log::info!(
+        "Moving node '{}' from HSM group '{}' to '{}'",
+        node_move.xname,
+        node_move.from_hsm_group,
+        node_move.to_hsm_group
+    );
+
+    crate::shasta::hsm::http_client::post_hsm_group_member(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move.to_hsm_group,
+        &node_move.xname,
+    )
+    .await
+    .map_err(|error| {
+        format!(
+            "could not add node '{}' to HSM group '{}': {}",
+            node_move.xname, node_move.to_hsm_group, error
+        )
+    })?;
+
+    crate::shasta::hsm::http_client::delete_hsm_group_member(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &node_move.from_hsm_group,
+        &node_move.xname,
+    )
+    .await
+    .map_err(|error| {
+        format!(
+            "could not remove node '{}' from HSM group '{}': {}",
+            node_move.xname, node_move.from_hsm_group, error
+        )
+    })
+}
+
+/// Members of each HSM group, HSM groups which don't exist have no members
+#[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+pub async fn get_hsm_group_snapshot_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_vec: &[String],
+) -> Result<Vec<HsmGroupSnapshot>, Box<dyn std::error::Error>> {
+    let mut hsm_group_snapshot_vec = Vec::new();
+
+    for hsm_group_name in hsm_group_name_vec {
+        let hsm_group_value_vec =
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+                Some(hsm_group_name),
+            ))
+            .await?;
+
+        let mut member_vec = hsm_group_value_vec
+            .first()
+            .map(mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value)
+            .unwrap_or_default();
+        member_vec.sort();
+
+        hsm_group_snapshot_vec.push(HsmGroupSnapshot {
+            label: hsm_group_name.clone(),
+            members: member_vec,
+        });
+    }
+
+    Ok(hsm_group_snapshot_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_rollback_member_change_vec() {
+        let before = vec![
+            HsmGroupSnapshot {
+                label: "zinal".to_string(),
+                members: vec!["x1000c0s0b0n0".to_string()],
+            },
+            HsmGroupSnapshot {
+                label: "nodes_free".to_string(),
+                members: vec!["x1000c0s0b0n1".to_string(), "x1000c0s0b0n2".to_string()],
+            },
+        ];
+
+        // x1000c0s0b0n1 moved, x1000c0s0b0n2 half moved (added to zinal but not removed from
+        // nodes_free)
+        let current = vec![
+            HsmGroupSnapshot {
+                label: "zinal".to_string(),
+                members: vec![
+                    "x1000c0s0b0n0".to_string(),
+                    "x1000c0s0b0n1".to_string(),
+                    "x1000c0s0b0n2".to_string(),
+                ],
+            },
+            HsmGroupSnapshot {
+                label: "nodes_free".to_string(),
+                members: vec!["x1000c0s0b0n2".to_string()],
+            },
+        ];
+
+        assert_eq!(
+            get_rollback_member_change_vec(
+                &before,
+                &current,
+                &["x1000c0s0b0n1".to_string(), "x1000c0s0b0n2".to_string()]
+            ),
+            (
+                vec![("nodes_free".to_string(), "x1000c0s0b0n1".to_string())],
+                vec![
+                    ("zinal".to_string(), "x1000c0s0b0n1".to_string()),
+                    ("zinal".to_string(), "x1000c0s0b0n2".to_string()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_node_move_conflict_vec() {
+        let hsm_group_snapshot_vec = vec![
+            HsmGroupSnapshot {
+                label: "zinal".to_string(),
+                members: vec!["x1000c0s0b0n0".to_string()],
+            },
+            HsmGroupSnapshot {
+                label: "nodes_free".to_string(),
+                members: vec!["x1000c0s0b0n1".to_string(), "x1000c0s0b0n0".to_string()],
+            },
+        ];
+
+        assert!(get_node_move_conflict_vec(
+            &[NodeMove::new("x1000c0s0b0n1", "nodes_free", "zinal")],
+            &hsm_group_snapshot_vec
+        )
+        .is_empty());
+
+        // Node already moved by someone else and node removed from the source HSM group
+        assert_eq!(
+            get_node_move_conflict_vec(
+                &[
+                    NodeMove::new("x1000c0s0b0n0", "nodes_free", "zinal"),
+                    NodeMove::new("x1000c0s1b0n0", "nodes_free", "zinal"),
+                ],
+                &hsm_group_snapshot_vec
+            ),
+            vec![
+                "node 'x1000c0s0b0n0' already in HSM group 'zinal'".to_string(),
+                "node 'x1000c0s1b0n0' no longer in HSM group 'nodes_free'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_hsm_group_snapshot_vec_after() {
+        let before = vec![
+            HsmGroupSnapshot {
+                label: "zinal".to_string(),
+                members: vec!["x1000c0s0b0n0".to_string()],
+            },
+            HsmGroupSnapshot {
+                label: "nodes_free".to_string(),
+                members: vec!["x1000c0s0b0n1".to_string(), "x1000c0s0b0n2".to_string()],
+            },
+        ];
+
+        assert_eq!(
+            get_hsm_group_snapshot_vec_after(
+                &before,
+                &[NodeMove::new("x1000c0s0b0n2", "nodes_free", "zinal")]
+            ),
+            vec![
+                HsmGroupSnapshot {
+                    label: "zinal".to_string(),
+                    members: vec!["x1000c0s0b0n0".to_string(), "x1000c0s0b0n2".to_string()],
+                },
+                HsmGroupSnapshot {
+                    label: "nodes_free".to_string(),
+                    members: vec!["x1000c0s0b0n1".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    io::Write,
+    os::fd::AsRawFd,
+    process::{Child, Command, Stdio},
+    sync::{Mutex, OnceLock},
+};
+
+/// Subcommands printing tables which may be longer than the terminal. Commands asking for
+/// confirmation or running until interrupted (eg reconcile) are never paged
+pub const PAGED_SUBCOMMAND_NAME_VEC: [&str; 7] = [
+    "get", "history", "diff", "validate", "resolve", "report", "pool",
+];
+
+static PAGER_ENABLED: OnceLock<bool> = OnceLock::new();
+
+static PAGER_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
+
+/// Enables the pager for the rest of the execution (`--no-pager` disables it)
+pub fn configure(enabled: bool) {
+    let _ = PAGER_ENABLED.set(enabled);
+}
+
+/// Pipes stdout through $CLSTR_PAGER or $PAGER (`less` by default) like git does, if the pager is
+/// enabled and stdout is a terminal. LESS defaults to FRX so less quits if the output fits in the
+/// terminal, keeps colors and does not clear the screen. The pager is waited for on exit
+pub fn start() {
+    if !PAGER_ENABLED.get().copied().unwrap_or(false)
+        || unsafe { libc::isatty(libc::STDOUT_FILENO) } == 0
+    {
+        return;
+    }
+
+    let pager = std::env::var("CLSTR_PAGER")
+        .or(std::env::var("PAGER"))
+        .unwrap_or("less".to_string());
+
+    if pager.trim().is_empty() || pager.trim() == "cat" {
+        return;
+    }
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(&pager).stdin(Stdio::piped());
+
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(error) => {
+            log::warn!("Could not start pager '{}': {}", pager, error);
+            return;
+        }
+    };
+
+    let _ = std::io::stdout().flush();
+
+    if let Some(pager_stdin) = child.stdin.take() {
+        unsafe {
+            libc::dup2(pager_stdin.as_raw_fd(), libc::STDOUT_FILENO);
+            // Quit silently instead of panicking on println! if the user quits the pager early
+            libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+            libc::atexit(wait_at_exit);
+        }
+    }
+
+    *PAGER_PROCESS.lock().unwrap() = Some(child);
+}
+
+/// Whether stdout is piped through the pager
+pub fn is_running() -> bool {
+    PAGER_PROCESS
+        .lock()
+        .is_ok_and(|pager_process| pager_process.is_some())
+}
+
+extern "C" fn wait_at_exit() {
+    wait();
+}
+
+/// Closes stdout so the pager gets EOF and waits until the user quits it, otherwise the shell
+/// prompt would be printed over the pager
+pub fn wait() {
+    let Some(mut child) = PAGER_PROCESS.lock().ok().and_then(|mut child| child.take()) else {
+        return;
+    };
+
+    let _ = std::io::stdout().flush();
+
+    unsafe {
+        libc::close(libc::STDOUT_FILENO);
+    }
+
+    let _ = child.wait();
+}
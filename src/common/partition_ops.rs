@@ -0,0 +1,97 @@
+use std::error::Error;
+
+use serde_json::Value;
+
+use crate::shasta::hsm;
+
+/// Members (xnames) of an HSM partition value as returned by HSM v2 partitions API
+pub fn get_partition_member_vec(partition_value: &Value) -> Vec<String> {
+    partition_value
+        .pointer("/members/ids")
+        .and_then(Value::as_array)
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter_map(|member| member.as_str().map(str::to_string))
+        .collect()
+}
+
+/// Members of the HSM partition, fetched from HSM
+pub async fn get_hsm_partition_member_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    partition_name: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let partition_value = hsm::http_client::get_partition(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        partition_name,
+    )
+    .await?;
+
+    Ok(get_partition_member_vec(&partition_value))
+}
+
+/// Nodes outside the HSM partition, used as excluded xnames to scope node moves to a partition.
+/// No nodes if there is no partition
+pub async fn get_xname_vec_outside_partition(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    partition_name_opt: Option<&String>,
+) -> Result<Vec<String>, String> {
+    let Some(partition_name) = partition_name_opt else {
+        return Ok(Vec::new());
+    };
+
+    let partition_member_vec = get_hsm_partition_member_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        partition_name,
+    )
+    .await
+    .map_err(|error| {
+        format!(
+            "could not get HSM partition '{}': {}",
+            partition_name, error
+        )
+    })?;
+
+    let node_status_value = hsm::http_client::get_all_components_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "Node",
+    )
+    .await
+    .map_err(|error| format!("could not get nodes from HSM: {}", error))?;
+
+    Ok(node_status_value["Components"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter_map(|node_status| node_status["ID"].as_str())
+        .filter(|xname| !partition_member_vec.iter().any(|member| member == xname))
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_partition_member_vec() {
+        assert_eq!(
+            get_partition_member_vec(&json!({
+                "name": "p1",
+                "members": { "ids": ["x1000c0s0b0n0", "x1000c0s0b0n1"] }
+            })),
+            vec!["x1000c0s0b0n0".to_string(), "x1000c0s0b0n1".to_string()]
+        );
+        assert!(get_partition_member_vec(&json!({ "name": "p2" })).is_empty());
+    }
+}
@@ -0,0 +1,3094 @@
+pub mod exact_solver;
+pub mod explain;
+pub mod scoring_strategy;
+
+use std::collections::HashMap;
+
+use comfy_table::Color;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::shasta::{hsm, ShastaClient};
+
+use crate::common::{
+    format_ops::{format_count, format_score},
+    hw_component_ops, memory_ops, profile_ops, table_ops,
+    xname::{self, Xname},
+};
+
+use explain::NodeSelectionExplanation;
+use scoring_strategy::{ScoringContext, ScoringStrategy};
+
+/// How the quantity of a hw component in a pattern is honored. `a100>=4` (or `a100:4`) is a
+/// floor, `epyc=30` an exact target and `instinct<=2` a ceiling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuantityQualifier {
+    AtLeast,
+    Exact,
+    AtMost,
+}
+
+impl QuantityQualifier {
+    pub fn is_satisfied(&self, qty_requested: usize, qty: usize) -> bool {
+        match self {
+            QuantityQualifier::AtLeast => qty >= qty_requested,
+            QuantityQualifier::Exact => qty == qty_requested,
+            QuantityQualifier::AtMost => qty <= qty_requested,
+        }
+    }
+
+    /// Quantity the solver has to reach
+    pub fn get_min_qty(&self, qty_requested: usize) -> usize {
+        match self {
+            QuantityQualifier::AtMost => 0,
+            _ => qty_requested,
+        }
+    }
+
+    /// Quantity the solver can't go above, None if there is no ceiling
+    pub fn get_max_qty(&self, qty_requested: usize) -> Option<usize> {
+        match self {
+            QuantityQualifier::AtLeast => None,
+            _ => Some(qty_requested),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            QuantityQualifier::AtLeast => ">=",
+            QuantityQualifier::Exact => "=",
+            QuantityQualifier::AtMost => "<=",
+        }
+    }
+}
+
+/// Hw components of a pattern with their quantity and how it is honored
+pub type HwComponentQualifiedCountHashMap = HashMap<String, (usize, QuantityQualifier)>;
+
+/// Error found in a pattern. Position is the column (starting at 1) of the pattern element
+/// with the error
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatternError {
+    pub position: usize,
+    pub element: String,
+    pub message: String,
+}
+
+impl PatternError {
+    fn new(position: usize, element: &str, message: String) -> Self {
+        Self {
+            position,
+            element: element.to_string(),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "position {}: '{}' {}",
+            self.position, self.element, self.message
+        )
+    }
+}
+
+/// Parses a pattern like <hsm group name>:<hw component>:<counter>:... and returns the target
+/// HSM group name and the hw component counters requested by the user. Pattern is normalized
+/// to lowercase. Hw components also accept qualifiers eg zinal:a100>=4:epyc=30:instinct<=2. Hw components without qualifier (eg zinal:a100:4) are
+/// a floor. Hw components excluded (eg zinal:epyc:20:!a100) are a ceiling of 0, so nodes with
+/// them are never candidates. Hw profiles from configuration file (eg zinal:@gpu_node:4) are
+/// expanded to their hw components, each with the same counter and qualifier. Returns the
+/// errors found if any pattern element is malformed, see `parse_pattern_with_errors`
+pub fn parse_pattern_with_qualifiers(
+    pattern: &str,
+) -> Result<(String, HwComponentQualifiedCountHashMap), Vec<PatternError>> {
+    let (target_hsm_group_name, hw_component_qualified_count_hashmap, pattern_error_vec) =
+        parse_pattern_with_errors(pattern);
+
+    if !pattern_error_vec.is_empty() {
+        return Err(pattern_error_vec);
+    }
+
+    Ok((target_hsm_group_name, hw_component_qualified_count_hashmap))
+}
+
+/// Parses a pattern like `parse_pattern_with_qualifiers` and returns the errors found (missing
+/// HSM group name, hw components without counter, counters which are not a number, hw
+/// components repeated and hw profiles not defined) with the position of the element in the
+/// pattern. Malformed pattern elements are left out of the hw component counters
+pub fn parse_pattern_with_errors(
+    pattern: &str,
+) -> (String, HwComponentQualifiedCountHashMap, Vec<PatternError>) {
+    let mut pattern_error_vec = Vec::new();
+
+    // Pattern elements with their position in the pattern
+    let mut position = 1;
+    let mut pattern_element_vec: Vec<(usize, String)> = Vec::new();
+
+    for pattern_element in pattern.split(':') {
+        pattern_element_vec.push((position, pattern_element.trim().to_lowercase()));
+        position += pattern_element.chars().count() + 1;
+    }
+
+    let (_, target_hsm_group_name) = pattern_element_vec.remove(0);
+
+    if target_hsm_group_name.is_empty() {
+        pattern_error_vec.push(PatternError::new(
+            1,
+            "",
+            "HSM group name missing, pattern must start with <hsm group name>:".to_string(),
+        ));
+    } else if target_hsm_group_name.contains(['!', '@', '=', '<', '>']) {
+        pattern_error_vec.push(PatternError::new(
+            1,
+            &target_hsm_group_name,
+            "is not a HSM group name, pattern must start with <hsm group name>:".to_string(),
+        ));
+    }
+
+    let mut hw_component_qualified_count_hashmap: HwComponentQualifiedCountHashMap = HashMap::new();
+
+    // hw component -> position where it was first requested
+    let mut hw_component_position_hashmap: HashMap<String, usize> = HashMap::new();
+
+    let mut pattern_element_iter = pattern_element_vec.iter().peekable();
+
+    while let Some((position, pattern_element)) = pattern_element_iter.next() {
+        let (hw_component, qty_opt, qualifier) =
+            if let Some(hw_component) = pattern_element.strip_prefix('!') {
+                (
+                    hw_component,
+                    Some((*position, "0")),
+                    QuantityQualifier::AtMost,
+                )
+            } else if let Some((hw_component, qty)) = pattern_element.split_once(">=") {
+                (
+                    hw_component,
+                    Some((position + hw_component.chars().count() + 2, qty)),
+                    QuantityQualifier::AtLeast,
+                )
+            } else if let Some((hw_component, qty)) = pattern_element.split_once("<=") {
+                (
+                    hw_component,
+                    Some((position + hw_component.chars().count() + 2, qty)),
+                    QuantityQualifier::AtMost,
+                )
+            } else if let Some((hw_component, qty)) = pattern_element.split_once('=') {
+                (
+                    hw_component,
+                    Some((position + hw_component.chars().count() + 1, qty)),
+                    QuantityQualifier::Exact,
+                )
+            } else {
+                // Next element is the counter, unless it is another hw component (counter
+                // missing)
+                let qty_opt = pattern_element_iter
+                    .next_if(|(_, qty)| {
+                        qty.starts_with(|c: char| c.is_ascii_digit())
+                            || parse_pattern_qty(pattern_element, qty).is_ok()
+                    })
+                    .map(|(position, qty)| (*position, qty.as_str()));
+
+                (
+                    pattern_element.as_str(),
+                    qty_opt,
+                    QuantityQualifier::AtLeast,
+                )
+            };
+
+        if hw_component.is_empty() {
+            pattern_error_vec.push(PatternError::new(
+                *position,
+                pattern_element,
+                "hw component missing".to_string(),
+            ));
+            continue;
+        }
+
+        let qty = match qty_opt {
+            Some((qty_position, qty)) => match parse_pattern_qty(hw_component, qty) {
+                Ok(qty) => qty,
+                Err(error) => {
+                    pattern_error_vec.push(PatternError::new(qty_position, qty, error));
+                    continue;
+                }
+            },
+            None => {
+                pattern_error_vec.push(PatternError::new(
+                    *position,
+                    hw_component,
+                    "counter missing, expected <hw component>:<counter> or <hw component><qualifier><counter> eg a100:4 or a100>=4".to_string(),
+                ));
+                continue;
+            }
+        };
+
+        let hw_component_vec = match hw_component.strip_prefix('@') {
+            Some(profile_name) => match profile_ops::get_profile_hw_component_vec(profile_name) {
+                Some(hw_component_vec) => hw_component_vec,
+                None => {
+                    pattern_error_vec.push(PatternError::new(
+                        *position,
+                        hw_component,
+                        "hw profile not found in 'profiles' in configuration file".to_string(),
+                    ));
+                    continue;
+                }
+            },
+            None => vec![hw_component.to_string()],
+        };
+
+        for hw_component in hw_component_vec {
+            if let Some(first_position) = hw_component_position_hashmap.get(&hw_component) {
+                pattern_error_vec.push(PatternError::new(
+                    *position,
+                    &hw_component,
+                    format!(
+                        "hw component already requested at position {}",
+                        first_position
+                    ),
+                ));
+                continue;
+            }
+
+            hw_component_position_hashmap.insert(hw_component.clone(), *position);
+            hw_component_qualified_count_hashmap.insert(hw_component, (qty, qualifier));
+        }
+    }
+
+    (
+        target_hsm_group_name,
+        hw_component_qualified_count_hashmap,
+        pattern_error_vec,
+    )
+}
+
+/// Counter of a hw component in a pattern. Memory and gpumem are capacities in bytes,
+/// requested with a unit eg memory:512gb or gpumem>=80gb
+fn parse_pattern_qty(hw_component: &str, qty: &str) -> Result<usize, String> {
+    let counter_error = format!("is not a valid counter for hw component '{}'", hw_component);
+
+    if memory_ops::is_capacity_hw_component(hw_component) {
+        memory_ops::parse_memory_request(qty)?
+            .try_into()
+            .map_err(|_| counter_error)
+    } else {
+        qty.parse::<usize>().map_err(|_| counter_error)
+    }
+}
+
+/// Hw component ceilings (exact and at-most qualifiers) of a pattern
+pub fn get_hw_component_max_count_hashmap(
+    hw_component_qualified_count_hashmap: &HwComponentQualifiedCountHashMap,
+) -> HashMap<String, usize> {
+    hw_component_qualified_count_hashmap
+        .iter()
+        .filter_map(|(hw_component, (qty, qualifier))| {
+            qualifier
+                .get_max_qty(*qty)
+                .map(|max_qty| (hw_component.clone(), max_qty))
+        })
+        .collect()
+}
+
+/// Whether adding a node to the nodes already selected keeps every hw component with a ceiling
+/// within it
+pub fn is_within_hw_component_max_count(
+    selected_hw_component_count_hashmap: &HashMap<String, usize>,
+    node_hw_component_count_hashmap: &HashMap<String, usize>,
+    hw_component_max_count_hashmap: &HashMap<String, usize>,
+) -> bool {
+    hw_component_max_count_hashmap
+        .iter()
+        .all(|(hw_component, max_qty)| {
+            selected_hw_component_count_hashmap
+                .get(hw_component)
+                .unwrap_or(&0)
+                + node_hw_component_count_hashmap
+                    .get(hw_component)
+                    .unwrap_or(&0)
+                <= *max_qty
+        })
+}
+
+/// Splits a xname in its location parts eg x1001c1s5b0n0 --> [x1001, c1, s5, b0, n0]
+pub fn get_xname_location_vec(xname: &str) -> Vec<String> {
+    let mut xname_location_vec: Vec<String> = Vec::new();
+
+    for c in xname.chars() {
+        if c.is_ascii_alphabetic() {
+            xname_location_vec.push(c.to_string());
+        } else if let Some(xname_location) = xname_location_vec.last_mut() {
+            xname_location.push(c);
+        }
+    }
+
+    xname_location_vec
+}
+
+/// Blade of a node eg x1001c1s5b0n0 --> x1001c1s5b0
+pub fn get_blade_xname(xname: &str) -> String {
+    get_xname_location_vec(xname)
+        .into_iter()
+        .take(4)
+        .collect::<Vec<String>>()
+        .concat()
+}
+
+/// Groups node hw component counters by blade, blade hw component counters are the sum of its
+/// nodes. Blades keep the order of their first node
+pub fn group_node_hw_component_count_vec_by_blade(
+    node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+) -> Vec<(String, HashMap<String, usize>)> {
+    let mut blade_hw_component_count_vec: Vec<(String, HashMap<String, usize>)> = Vec::new();
+
+    for (xname, node_hw_component_count_hashmap) in node_hw_component_count_vec {
+        let blade_xname = get_blade_xname(xname);
+
+        let blade_hw_component_count_hashmap = match blade_hw_component_count_vec
+            .iter_mut()
+            .find(|(xname, _)| xname.eq(&blade_xname))
+        {
+            Some((_, blade_hw_component_count_hashmap)) => blade_hw_component_count_hashmap,
+            None => {
+                blade_hw_component_count_vec.push((blade_xname, HashMap::new()));
+                &mut blade_hw_component_count_vec.last_mut().unwrap().1
+            }
+        };
+
+        for (hw_component, qty) in node_hw_component_count_hashmap {
+            *blade_hw_component_count_hashmap
+                .entry(hw_component.to_string())
+                .or_insert(0) += qty;
+        }
+    }
+
+    blade_hw_component_count_vec
+}
+
+/// Reverse of `group_node_hw_component_count_vec_by_blade`, replaces each blade with its nodes
+/// in `node_hw_component_count_vec`
+pub fn ungroup_blade_hw_component_count_vec(
+    blade_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+    node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+) -> Vec<(String, HashMap<String, usize>)> {
+    blade_hw_component_count_vec
+        .iter()
+        .flat_map(|(blade_xname, _)| {
+            node_hw_component_count_vec
+                .iter()
+                .filter(move |(xname, _)| get_blade_xname(xname).eq(blade_xname))
+                .cloned()
+        })
+        .collect()
+}
+
+/// Calculates the "distance" between 2 nodes as described in the exec doc comments:
+/// 0 same blade, 1 same slot, 2 same chassis, 3 same rack and 4 different rack
+pub fn calculate_xname_distance(xname_1: &str, xname_2: &str) -> usize {
+    if let (Ok(xname_1), Ok(xname_2)) = (xname_1.parse::<Xname>(), xname_2.parse::<Xname>()) {
+        return xname_1.distance(&xname_2);
+    }
+
+    // Xnames of components below nodes (eg processors) are compared by their location parts
+    let common_location_count = get_xname_location_vec(xname_1)
+        .iter()
+        .zip(get_xname_location_vec(xname_2).iter())
+        .take(4)
+        .take_while(|(location_1, location_2)| location_1 == location_2)
+        .count();
+
+    4 - common_location_count
+}
+
+/// Hw component counters of the nodes in `xname_vec` (see `calculate_node_hw_component_count`)
+/// sorted by xname. Nodes missing in `node_hw_inventory_hashmap` are left out
+pub fn get_node_hw_component_count_vec(
+    xname_vec: &[String],
+    node_hw_inventory_hashmap: &HashMap<String, Value>,
+    user_defined_hw_component_vec: &[String],
+) -> Vec<(String, HashMap<String, usize>)> {
+    let mut node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)> = xname_vec
+        .iter()
+        .filter_map(|xname| {
+            let node_hw_inventory_value = node_hw_inventory_hashmap.get(xname)?;
+
+            let (mut node_hw_component_vec, node_memory_capacity_vec, node_accelerator_memory_vec) =
+                get_node_hw_properties_from_value(
+                    node_hw_inventory_value,
+                    user_defined_hw_component_vec.to_vec(),
+                );
+
+            node_hw_component_vec.sort();
+
+            Some((
+                xname.clone(),
+                calculate_node_hw_component_count(
+                    node_hw_component_vec,
+                    &node_memory_capacity_vec,
+                    &node_accelerator_memory_vec,
+                ),
+            ))
+        })
+        .collect();
+
+    node_hw_component_count_vec.sort_by_key(|(xname, _)| xname.clone());
+
+    node_hw_component_count_vec
+}
+
+/// Counts the hw components of a node and adds the total memory capacity (bytes) of its DIMMs
+/// (MiB) under the "memory" hw component and, if any, the memory (bytes) of its accelerators
+/// under "gpumem"
+pub fn calculate_node_hw_component_count(
+    node_hw_component_vec: Vec<String>,
+    node_memory_capacity_vec: &[u64],
+    node_accelerator_memory_vec: &[u64],
+) -> HashMap<String, usize> {
+    let mut node_hw_component_count_hashmap: HashMap<String, usize> = HashMap::new();
+
+    for node_hw_property_vec in node_hw_component_vec {
+        let count = node_hw_component_count_hashmap
+            .entry(node_hw_property_vec)
+            .or_insert(0);
+        *count += 1;
+    }
+
+    node_hw_component_count_hashmap.insert(
+        memory_ops::MEMORY_HW_COMPONENT.to_string(),
+        memory_ops::get_node_memory_capacity(node_memory_capacity_vec),
+    );
+
+    if !node_accelerator_memory_vec.is_empty() {
+        node_hw_component_count_hashmap.insert(
+            memory_ops::GPU_MEMORY_HW_COMPONENT.to_string(),
+            node_accelerator_memory_vec.iter().sum::<u64>() as usize,
+        );
+    }
+
+    node_hw_component_count_hashmap
+}
+
+/// Removes as much nodes as it can from the parent HSM group
+/// Returns a tuple with 2 vecs, the left one is the new parent HSM group while the left one is
+/// the one containing the nodes removed from the parent HSM
+pub fn upscale_node_migration(
+    user_defined_hw_component_count_hashmap: &HashMap<String, usize>,
+    user_defined_hw_component_vec: &Vec<String>,
+    parent_hsm_node_hw_component_count_vec: &mut Vec<(String, HashMap<String, usize>)>,
+    parent_hsm_density_score_hashmap: &HashMap<String, usize>,
+    mut parent_hsm_score_tuple_vec: Vec<(String, f32)>,
+    mut hw_components_to_migrate_from_parent_hsm_to_target_hsm: HashMap<String, isize>,
+    hw_component_max_count_hashmap: &HashMap<String, usize>,
+    scoring_strategy: &dyn ScoringStrategy,
+    scoring_context: &ScoringContext,
+    progress_reporter: SolverProgressReporter,
+) -> Vec<(String, HashMap<String, usize>)> {
+    // Nodes which would take a hw component above its ceiling are not candidates
+    parent_hsm_score_tuple_vec.retain(|(xname, _)| {
+        parent_hsm_node_hw_component_count_vec
+            .iter()
+            .find(|(node, _)| node.eq(xname))
+            .is_some_and(|(_, node_hw_component_count_hashmap)| {
+                is_within_hw_component_max_count(
+                    &HashMap::new(),
+                    node_hw_component_count_hashmap,
+                    hw_component_max_count_hashmap,
+                )
+            })
+    });
+
+    if parent_hsm_score_tuple_vec.is_empty() {
+        log::info!("No candidates to choose from");
+        return Vec::new();
+    }
+
+    ////////////////////////////////
+    // Initialize
+
+    let mut nodes_migrated_from_parent_hsm: Vec<(String, HashMap<String, usize>)> = Vec::new();
+
+    let progress_len =
+        count_hw_components_to_migrate(&hw_components_to_migrate_from_parent_hsm_to_target_hsm);
+
+    progress_reporter.report(SolverProgress::Start {
+        message: "Selecting nodes to move to target HSM group",
+        len: progress_len,
+    });
+
+    // Get best candidate
+    let (mut best_candidate, mut best_candidate_counters) =
+        get_best_candidate_to_upscale_migrate_f32_score(
+            &mut parent_hsm_score_tuple_vec,
+            parent_hsm_node_hw_component_count_vec,
+        );
+
+    // Check if we need to keep iterating
+    let mut work_to_do =
+        keep_iterating_upscale(&hw_components_to_migrate_from_parent_hsm_to_target_hsm);
+
+    ////////////////////////////////
+    // Itarate
+
+    let mut iter = 0;
+
+    while work_to_do {
+        if progress_reporter.is_verbose() {
+            // Calculate HSM group hw component counters
+            let parent_hsm_hw_component_count_hashmap =
+                get_hsm_hw_component_count_filtered_by_user_request(
+                    user_defined_hw_component_vec,
+                    parent_hsm_node_hw_component_count_vec,
+                );
+
+            progress_reporter.report(SolverProgress::Details(format!(
+                "-----------------------\n----- ITERATION {} -----\n-----------------------\n\n\
+                HW component counters requested by user: {:?}\n\
+                HSM group hw component counters: {:?}\n\
+                HW component counters yet to remove: {:?}\n\
+                Best candidate is '{}' with score {} and hw component counters {:?}\n\n\
+                {}\n\n",
+                iter,
+                user_defined_hw_component_count_hashmap,
+                parent_hsm_hw_component_count_hashmap,
+                hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+                best_candidate.0,
+                parent_hsm_score_tuple_vec
+                    .iter()
+                    .find(|(node, _score)| node.eq(&best_candidate.0))
+                    .unwrap()
+                    .1,
+                best_candidate_counters,
+                // Target hsm group hw configuration in table
+                build_table_f32_score(
+                    user_defined_hw_component_vec,
+                    parent_hsm_node_hw_component_count_vec,
+                    parent_hsm_density_score_hashmap,
+                    &parent_hsm_score_tuple_vec,
+                )
+            )));
+        }
+
+        ////////////////////////////////
+        // Apply changes - Migrate from target to parent HSM
+
+        // Add best candidate to parent HSM group
+        nodes_migrated_from_parent_hsm
+            .push((best_candidate.0.clone(), best_candidate_counters.clone()));
+
+        // Remove best candidate from target HSM group
+        parent_hsm_node_hw_component_count_vec.retain(|(node, _)| !node.eq(&best_candidate.0));
+
+        if parent_hsm_node_hw_component_count_vec.is_empty() {
+            break;
+        }
+
+        // Update hw component counters to migrate
+        hw_components_to_migrate_from_parent_hsm_to_target_hsm =
+            update_user_defined_hw_component_counters(
+                &hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+                &best_candidate_counters,
+            );
+
+        progress_reporter.report(SolverProgress::Position(progress_len.saturating_sub(
+            count_hw_components_to_migrate(&hw_components_to_migrate_from_parent_hsm_to_target_hsm),
+        )));
+
+        // Calculate hw component counters in HSM
+        /* let parent_hsm_hw_component_count_hashmap =
+        calculate_hsm_hw_component_count(parent_hsm_node_hw_component_count_vec); */
+        /* println!(
+            "DEBUG - New HSM hw component counters: {:?}",
+            parent_hsm_hw_component_count_hashmap
+        ); */
+
+        // Calculate total hw component counters in HSM
+        /* let parent_hsm_total_number_hw_components: usize =
+        calculate_hsm_total_number_hw_components(parent_hsm_node_hw_component_count_vec); */
+        /* println!(
+            "DEBUG - Total number hw components: {}",
+            parent_hsm_total_number_hw_components
+        ); */
+
+        // Update scores
+        let selected_node_vec: Vec<String> = nodes_migrated_from_parent_hsm
+            .iter()
+            .map(|(xname, _)| xname.clone())
+            .collect();
+
+        parent_hsm_score_tuple_vec = scoring_strategy.calculate_node_score_vec(
+            parent_hsm_node_hw_component_count_vec,
+            &hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+            &selected_node_vec,
+            scoring_context,
+        );
+
+        // Remove best candidate and nodes which would go above a ceiling from scores
+        let selected_hw_component_count_hashmap =
+            calculate_hsm_hw_component_count(&nodes_migrated_from_parent_hsm);
+
+        parent_hsm_score_tuple_vec.retain(|(node, _)| {
+            !node.eq(&best_candidate.0)
+                && parent_hsm_node_hw_component_count_vec
+                    .iter()
+                    .find(|(xname, _)| xname.eq(node))
+                    .is_some_and(|(_, node_hw_component_count_hashmap)| {
+                        is_within_hw_component_max_count(
+                            &selected_hw_component_count_hashmap,
+                            node_hw_component_count_hashmap,
+                            hw_component_max_count_hashmap,
+                        )
+                    })
+        });
+
+        if parent_hsm_score_tuple_vec.is_empty() {
+            break;
+        }
+
+        // Get best candidate
+        (best_candidate, best_candidate_counters) = get_best_candidate_to_upscale_migrate_f32_score(
+            &mut parent_hsm_score_tuple_vec,
+            parent_hsm_node_hw_component_count_vec,
+        );
+
+        // Check if we need to keep iterating
+        work_to_do =
+            keep_iterating_upscale(&hw_components_to_migrate_from_parent_hsm_to_target_hsm);
+
+        iter += 1;
+    }
+
+    if progress_reporter.is_verbose() {
+        progress_reporter.report(SolverProgress::Details(format!(
+            "\n------------------------\n----- FINAL RESULT -----\n------------------------\n\n\
+            No candidates found\n\n{}\n\n",
+            build_table_f32_score(
+                user_defined_hw_component_vec,
+                parent_hsm_node_hw_component_count_vec,
+                parent_hsm_density_score_hashmap,
+                &parent_hsm_score_tuple_vec,
+            )
+        )));
+    }
+
+    progress_reporter.report(SolverProgress::Finish);
+
+    nodes_migrated_from_parent_hsm
+}
+
+/// Same as upscale_node_migration but the candidates are first restricted to the current target
+/// HSM group members providing any hw component requested, the rest of the nodes are only
+/// considered if those are not enough to fulfill the user request. This minimizes the number
+/// of nodes moved between HSM groups
+pub fn min_moves_node_migration(
+    user_defined_hw_component_count_hashmap: &HashMap<String, usize>,
+    user_defined_hw_component_vec: &Vec<String>,
+    target_parent_hsm_node_hw_component_count_vec: &mut Vec<(String, HashMap<String, usize>)>,
+    target_parent_hsm_density_score_hashmap: &HashMap<String, usize>,
+    hw_components_to_migrate_from_parent_hsm_to_target_hsm: HashMap<String, isize>,
+    hw_component_max_count_hashmap: &HashMap<String, usize>,
+    scoring_strategy: &dyn ScoringStrategy,
+    scoring_context: &ScoringContext,
+    progress_reporter: SolverProgressReporter,
+) -> Vec<(String, HashMap<String, usize>)> {
+    let (mut target_hsm_node_hw_component_count_vec, mut parent_hsm_node_hw_component_count_vec): (
+        Vec<_>,
+        Vec<_>,
+    ) = target_parent_hsm_node_hw_component_count_vec
+        .drain(..)
+        .partition(|(xname, node_hw_component_count_hashmap)| {
+            scoring_context.target_hsm_member_vec.contains(xname)
+                && node_hw_component_count_hashmap
+                    .keys()
+                    .any(|hw_component| user_defined_hw_component_vec.contains(hw_component))
+        });
+
+    log::info!(
+        "Keeping target HSM group members first, {} candidates",
+        format_count(target_hsm_node_hw_component_count_vec.len())
+    );
+
+    // Select nodes from target HSM group members
+    let target_hsm_score_tuple_vec = scoring_strategy.calculate_node_score_vec(
+        &target_hsm_node_hw_component_count_vec,
+        &hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+        &[],
+        scoring_context,
+    );
+
+    let mut nodes_migrated = upscale_node_migration(
+        user_defined_hw_component_count_hashmap,
+        user_defined_hw_component_vec,
+        &mut target_hsm_node_hw_component_count_vec,
+        target_parent_hsm_density_score_hashmap,
+        target_hsm_score_tuple_vec,
+        hw_components_to_migrate_from_parent_hsm_to_target_hsm.clone(),
+        hw_component_max_count_hashmap,
+        scoring_strategy,
+        scoring_context,
+        progress_reporter,
+    );
+
+    // Target HSM group members not selected go back to the pool of candidates
+    parent_hsm_node_hw_component_count_vec.append(&mut target_hsm_node_hw_component_count_vec);
+
+    let hw_components_still_missing = nodes_migrated.iter().fold(
+        hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+        |hw_components_to_migrate, (_, node_hw_component_count_hashmap)| {
+            update_user_defined_hw_component_counters(
+                &hw_components_to_migrate,
+                node_hw_component_count_hashmap,
+            )
+        },
+    );
+
+    if keep_iterating_upscale(&hw_components_still_missing) {
+        log::info!(
+            "Target HSM group members not enough, hw components still missing {:?}",
+            hw_components_still_missing
+        );
+
+        // Select nodes from the rest of the candidates
+        let selected_node_vec: Vec<String> = nodes_migrated
+            .iter()
+            .map(|(xname, _)| xname.clone())
+            .collect();
+
+        let parent_hsm_score_tuple_vec = scoring_strategy.calculate_node_score_vec(
+            &parent_hsm_node_hw_component_count_vec,
+            &hw_components_still_missing,
+            &selected_node_vec,
+            scoring_context,
+        );
+
+        // Ceilings left once the target HSM group members selected are in
+        let mut hw_component_max_count_left_hashmap = hw_component_max_count_hashmap.clone();
+        subtract_hw_component_count(&mut hw_component_max_count_left_hashmap, &nodes_migrated);
+
+        nodes_migrated.append(&mut upscale_node_migration(
+            user_defined_hw_component_count_hashmap,
+            user_defined_hw_component_vec,
+            &mut parent_hsm_node_hw_component_count_vec,
+            target_parent_hsm_density_score_hashmap,
+            parent_hsm_score_tuple_vec,
+            hw_components_still_missing,
+            &hw_component_max_count_left_hashmap,
+            scoring_strategy,
+            scoring_context,
+            progress_reporter,
+        ));
+    }
+
+    *target_parent_hsm_node_hw_component_count_vec = parent_hsm_node_hw_component_count_vec;
+
+    nodes_migrated
+}
+
+/// Progress of the node selection reported to `SolverOptions::on_progress_opt`
+pub enum SolverProgress<'a> {
+    /// Node selection starts with `len` hw components to migrate
+    Start { message: &'a str, len: u64 },
+    /// Hw components migrated so far
+    Position(u64),
+    /// Details of an iteration, only reported with `SolverOptions::verbose`
+    Details(String),
+    /// Node selection finished
+    Finish,
+}
+
+/// Receives the progress of the solver (eg to draw a progress bar), the solver prints nothing
+pub type SolverProgressCallback<'a> = &'a (dyn Fn(SolverProgress) + Sync);
+
+/// Reports the progress of the node selection to the callback if any. Iteration details are only
+/// built if `verbose` and there is a callback
+#[derive(Clone, Copy, Default)]
+pub struct SolverProgressReporter<'a> {
+    pub on_progress_opt: Option<SolverProgressCallback<'a>>,
+    pub verbose: bool,
+}
+
+impl SolverProgressReporter<'_> {
+    pub fn report(&self, solver_progress: SolverProgress) {
+        if let Some(on_progress) = self.on_progress_opt {
+            on_progress(solver_progress);
+        }
+    }
+
+    pub fn is_verbose(&self) -> bool {
+        self.verbose && self.on_progress_opt.is_some()
+    }
+}
+
+pub fn count_hw_components_to_migrate(hw_components_to_migrate: &HashMap<String, isize>) -> u64 {
+    hw_components_to_migrate
+        .iter()
+        .map(|(hw_component, qty)| {
+            memory_ops::get_hw_component_score_qty(hw_component, qty.unsigned_abs()) as u64
+        })
+        .sum()
+}
+
+/// Returns the nodes added to and removed from a HSM group when its members change from
+/// `current_member_vec` to `new_member_vec`
+pub fn calculate_node_moves(
+    current_member_vec: &[String],
+    new_member_vec: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let node_added_vec = new_member_vec
+        .iter()
+        .filter(|xname| !current_member_vec.contains(xname))
+        .cloned()
+        .collect();
+
+    let node_removed_vec = current_member_vec
+        .iter()
+        .filter(|xname| !new_member_vec.contains(xname))
+        .cloned()
+        .collect();
+
+    (node_added_vec, node_removed_vec)
+}
+
+pub fn keep_iterating_downscale(
+    hw_components_to_migrate_from_target_hsm_to_parent_hsm: &HashMap<String, isize>,
+    best_candidate_counters: &HashMap<String, usize>,
+) -> bool {
+    let mut work_to_do = true;
+
+    // Check best candidate has hw components to remove. Otherwise, this node should not be
+    // considered a candidate
+    // TODO: move this to the function that selects best candidate?
+    if best_candidate_counters
+        .keys()
+        .all(|best_candidate_hw_component| {
+            !hw_components_to_migrate_from_target_hsm_to_parent_hsm
+                .contains_key(best_candidate_hw_component)
+        })
+    {
+        log::info!("Stop processing because none of the hw components in best candidate should be removed. Best candidate {:?}, hw components to remove {:?}", best_candidate_counters, hw_components_to_migrate_from_target_hsm_to_parent_hsm);
+        work_to_do = false;
+    }
+
+    for (hw_component, quantity) in hw_components_to_migrate_from_target_hsm_to_parent_hsm {
+        /* println!("\nDEBUG - hw_component: {}", hw_component);
+        println!("DEBUG - quantity: {}", quantity);
+        println!(
+            "DEBUG - best_candidate_counters: {:?}\n",
+            best_candidate_counters
+        ); */
+        if best_candidate_counters.get(hw_component).is_some()
+            && quantity.unsigned_abs() < *best_candidate_counters.get(hw_component).unwrap()
+        {
+            log::info!("Stop processing because otherwise user will get less hw components ({}) than requested because best candidate has {} and we have {} left", hw_component, best_candidate_counters.get(hw_component).unwrap(), quantity.abs());
+            work_to_do = false;
+            break;
+        }
+
+        /* if quantity.abs() > 0
+            && best_candidate_counters.get(hw_component).is_some()
+            && best_candidate_counters.get(hw_component).unwrap() <= &(quantity.unsigned_abs())
+        {
+            work_to_do = true;
+            break;
+        } */
+    }
+
+    work_to_do
+}
+
+pub fn keep_iterating_upscale(
+    hw_components_to_migrate_from_target_hsm_to_parent_hsm: &HashMap<String, isize>,
+) -> bool {
+    /* println!("DEBUG - inspecting if need to continue iterating ...");
+    println!(
+        "DEBUG - hw_components_to_migrate_from_target_hsm_to_parent_hsm: {:?}",
+        hw_components_to_migrate_from_target_hsm_to_parent_hsm
+    );
+    println!("DEBUG - best_candidate_counters: {:?}", best_candidate_counters); */
+
+    let mut work_to_do = false;
+
+    for quantity in hw_components_to_migrate_from_target_hsm_to_parent_hsm.values() {
+        if quantity.abs() > 0
+        /* && best_candidate_counters.get(hw_component).is_some()
+        && best_candidate_counters.get(hw_component).unwrap() >= &(quantity.unsigned_abs()) */
+        {
+            work_to_do = true;
+            break;
+        }
+    }
+
+    work_to_do
+}
+
+/// Removes as much nodes as it can from the target HSM group
+/// Returns a tuple with 2 vecs, the left one is the new target HSM group while the left one is
+/// the one containing the nodes removed from the target HSM
+pub fn downscale_node_migration(
+    user_defined_hw_component_count_hashmap: &HashMap<String, usize>,
+    user_defined_hw_component_vec: &Vec<String>,
+    target_hsm_node_hw_component_count_vec: &mut Vec<(String, HashMap<String, usize>)>,
+    target_hsm_node_density_score_hashmap: &HashMap<String, usize>,
+    mut target_hsm_score_tuple_vec: Vec<(String, f32)>,
+    mut hw_components_to_migrate_from_target_hsm_to_parent_hsm: HashMap<String, isize>,
+    target_hsm_hw_component_normalized_scores_hashmap: &HashMap<String, f32>,
+    // target_hsm_hw_component_count_hashmap: &HashMap<String, usize>,
+    progress_reporter: SolverProgressReporter,
+) -> Vec<(String, HashMap<String, usize>)> {
+    if target_hsm_score_tuple_vec.is_empty() {
+        log::info!("No candidates to choose from");
+        return Vec::new();
+    }
+
+    ////////////////////////////////
+    // Initialize
+
+    let mut nodes_migrated_from_target_hsm: Vec<(String, HashMap<String, usize>)> = Vec::new();
+
+    let progress_len =
+        count_hw_components_to_migrate(&hw_components_to_migrate_from_target_hsm_to_parent_hsm);
+
+    progress_reporter.report(SolverProgress::Start {
+        message: "Selecting nodes to move out of target HSM group",
+        len: progress_len,
+    });
+
+    // Get best candidate
+    let (mut best_candidate, mut best_candidate_counters) =
+        get_best_candidate_to_downscale_migrate_f32_score(
+            &mut target_hsm_score_tuple_vec,
+            target_hsm_node_hw_component_count_vec,
+        );
+
+    /* println!("DEBUG - best_candidate: {:?}", best_candidate);
+    println!(
+        "DEBUG - best_candidate_counters: {:?}",
+        best_candidate_counters
+    ); */
+
+    // Check if we need to keep iterating
+    let mut work_to_do = keep_iterating_downscale(
+        &hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+        &best_candidate_counters,
+    );
+
+    ////////////////////////////////
+    // Itarate
+
+    let mut iter = 0;
+
+    while work_to_do {
+        if progress_reporter.is_verbose() {
+            // Calculate HSM group hw component counters
+            let target_hsm_hw_component_filtered_by_user_request_count_hashmap =
+                get_hsm_hw_component_count_filtered_by_user_request(
+                    user_defined_hw_component_vec,
+                    target_hsm_node_hw_component_count_vec,
+                );
+
+            progress_reporter.report(SolverProgress::Details(format!(
+                "-----------------------\n----- ITERATION {} -----\n-----------------------\n\n\
+                HW component counters requested by user: {:?}\n\
+                HSM group hw component counters: {:?}\n\
+                HW component counters yet to remove: {:?}\n\
+                Best candidate is '{}' with score {} and hw component counters {:?}\n\n\
+                {}\n\n",
+                iter,
+                user_defined_hw_component_count_hashmap,
+                target_hsm_hw_component_filtered_by_user_request_count_hashmap,
+                hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+                best_candidate.0,
+                target_hsm_score_tuple_vec
+                    .iter()
+                    .find(|(node, _score)| node.eq(&best_candidate.0))
+                    .unwrap()
+                    .1,
+                best_candidate_counters,
+                // Target hsm group hw configuration in table
+                build_table_f32_score(
+                    user_defined_hw_component_vec,
+                    target_hsm_node_hw_component_count_vec,
+                    target_hsm_node_density_score_hashmap,
+                    &target_hsm_score_tuple_vec,
+                )
+            )));
+        }
+
+        ////////////////////////////////
+        // Apply changes - Migrate from target to parent HSM
+
+        // Add best candidate to parent HSM group
+        nodes_migrated_from_target_hsm
+            .push((best_candidate.0.clone(), best_candidate_counters.clone()));
+
+        // Remove best candidate from target HSM grour
+        target_hsm_node_hw_component_count_vec.retain(|(node, _)| !node.eq(&best_candidate.0));
+
+        if target_hsm_node_hw_component_count_vec.is_empty() {
+            break;
+        }
+
+        // Update hw component counters to migrate
+        hw_components_to_migrate_from_target_hsm_to_parent_hsm =
+            update_user_defined_hw_component_counters(
+                &hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+                &best_candidate_counters,
+            );
+
+        progress_reporter.report(SolverProgress::Position(progress_len.saturating_sub(
+            count_hw_components_to_migrate(&hw_components_to_migrate_from_target_hsm_to_parent_hsm),
+        )));
+
+        // Calculate total number of hw components in hsm group
+        /* println!(
+            "DEBUG - ########### hsm hw components: {:?}",
+            target_hsm_hw_component_count_vec
+        ); */
+
+        let target_hsm_hw_component_count_hashmap =
+            calculate_hsm_hw_component_count(target_hsm_node_hw_component_count_vec);
+        /* println!(
+            "DEBUG - New HSM hw component counters: {:?}",
+            target_hsm_hw_component_count_hashmap
+        ); */
+
+        /* let target_hsm_total_number_hw_components: usize =
+        calculate_hsm_total_number_hw_components(target_hsm_node_hw_component_count_vec); */
+        /* println!(
+            "DEBUG - Total number hw components: {}",
+            target_hsm_total_number_hw_components
+        ); */
+
+        // Update scores
+        target_hsm_score_tuple_vec =
+            calculate_hsm_hw_component_normalized_node_density_score_downscale(
+                target_hsm_node_hw_component_count_vec,
+                &hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+                user_defined_hw_component_count_hashmap,
+                target_hsm_hw_component_normalized_scores_hashmap,
+                &target_hsm_hw_component_count_hashmap,
+            );
+
+        // Remove best candidate from scores
+        target_hsm_score_tuple_vec.retain(|(node, _)| !node.eq(&best_candidate.0));
+
+        // Get best candidate
+        (best_candidate, best_candidate_counters) =
+            get_best_candidate_to_downscale_migrate_f32_score(
+                &mut target_hsm_score_tuple_vec,
+                target_hsm_node_hw_component_count_vec,
+            );
+
+        // Check if we need to keep iterating
+        work_to_do = keep_iterating_downscale(
+            &hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+            &best_candidate_counters,
+        );
+
+        iter += 1;
+    }
+
+    if progress_reporter.is_verbose() {
+        progress_reporter.report(SolverProgress::Details(format!(
+            "\n------------------------\n----- FINAL RESULT -----\n------------------------\n\n\
+            No candidates found\n\n{}\n\n",
+            build_table_f32_score(
+                user_defined_hw_component_vec,
+                target_hsm_node_hw_component_count_vec,
+                target_hsm_node_density_score_hashmap,
+                &target_hsm_score_tuple_vec,
+            )
+        )));
+    }
+
+    progress_reporter.report(SolverProgress::Finish);
+
+    nodes_migrated_from_target_hsm
+}
+
+pub fn update_user_defined_hw_component_counters(
+    user_defined_hw_component_counter_hashmap: &HashMap<String, isize>,
+    best_node_candidate_hashmap: &HashMap<String, usize>,
+) -> HashMap<String, isize> {
+    let mut new_user_defined_hw_component_counter_hashmap = HashMap::new();
+
+    for (hw_component, quantity) in user_defined_hw_component_counter_hashmap {
+        if best_node_candidate_hashmap.contains_key(hw_component) {
+            let new_quantity =
+                (*quantity) + (*best_node_candidate_hashmap.get(hw_component).unwrap() as isize);
+
+            if new_quantity <= 0 {
+                new_user_defined_hw_component_counter_hashmap
+                    .insert(hw_component.to_string(), new_quantity);
+            }
+        } else {
+            new_user_defined_hw_component_counter_hashmap.insert(hw_component.clone(), *quantity);
+        }
+    }
+
+    new_user_defined_hw_component_counter_hashmap
+}
+
+/* pub fn calculate_scores_scores(
+    target_hsm_group_hw_component_counter_vec: &Vec<(String, HashMap<String, usize>)>,
+    hw_components_to_migrate_from_target_hsm_to_parent_hsm: &HashMap<String, isize>,
+) -> Vec<(String, isize)> {
+    // Calculate HSM scores
+    let target_hsm_score_vec: Vec<(String, isize)> = calculate_scores(
+        target_hsm_group_hw_component_counter_vec,
+        hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+    );
+
+    target_hsm_score_vec
+} */
+
+pub fn get_best_candidate_to_downscale_migrate(
+    target_hsm_score_vec: &mut [(String, isize)],
+    target_hsm_hw_component_vec: &[(String, HashMap<String, usize>)],
+) -> ((String, isize), HashMap<String, usize>) {
+    target_hsm_score_vec.sort_by(|b, a| a.1.partial_cmp(&b.1).unwrap());
+
+    // Get node with highest normalized score (best candidate)
+    let highest_normalized_score: isize = *target_hsm_score_vec
+        .iter()
+        .map(|(_, normalized_score)| normalized_score)
+        .max()
+        .unwrap();
+
+    let best_candidate: (String, isize) = target_hsm_score_vec
+        .iter()
+        .find(|(_, normalized_score)| *normalized_score == highest_normalized_score)
+        .unwrap()
+        .clone();
+
+    let best_candidate_counters = &target_hsm_hw_component_vec
+        .iter()
+        .find(|(node, _)| node.eq(&best_candidate.0))
+        .unwrap()
+        .1;
+
+    /* println!(
+        "Best candidate is '{}' with a normalized score of {} with alphas {:?}",
+        best_candidate.0, highest_normalized_score, best_candidate_counters
+    ); */
+
+    (best_candidate, best_candidate_counters.clone())
+}
+
+pub fn get_best_candidate_to_downscale_migrate_f32_score(
+    target_hsm_score_vec: &mut [(String, f32)],
+    target_hsm_hw_component_vec: &[(String, HashMap<String, usize>)],
+) -> ((String, f32), HashMap<String, usize>) {
+    target_hsm_score_vec.sort_by(|b, a| a.1.partial_cmp(&b.1).unwrap());
+
+    // Get node with highest normalized score (best candidate)
+    let best_candidate: (String, f32) = target_hsm_score_vec
+        .iter_mut()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap()
+        .clone();
+
+    let best_candidate_counters = &target_hsm_hw_component_vec
+        .iter()
+        .find(|(node, _)| node.eq(&best_candidate.0))
+        .unwrap()
+        .1;
+
+    /* println!(
+        "Best candidate is '{}' with a normalized score of {} with alphas {:?}",
+        best_candidate.0, highest_normalized_score, best_candidate_counters
+    ); */
+
+    (best_candidate, best_candidate_counters.clone())
+}
+
+pub fn get_best_candidate_to_upscale_migrate(
+    parent_hsm_score_vec: &mut [(String, isize)],
+    parent_hsm_hw_component_vec: &[(String, HashMap<String, usize>)],
+) -> ((String, isize), HashMap<String, usize>) {
+    parent_hsm_score_vec.sort_by(|b, a| a.1.partial_cmp(&b.1).unwrap());
+
+    // Get node with highest normalized score (best candidate)
+    let highest_normalized_score: isize = *parent_hsm_score_vec
+        .iter()
+        .map(|(_, normalized_score)| normalized_score)
+        .min()
+        .unwrap();
+
+    let best_candidate: (String, isize) = parent_hsm_score_vec
+        .iter()
+        .find(|(_, normalized_score)| *normalized_score == highest_normalized_score)
+        .unwrap()
+        .clone();
+
+    let best_candidate_counters = &parent_hsm_hw_component_vec
+        .iter()
+        .find(|(node, _)| node.eq(&best_candidate.0))
+        .unwrap()
+        .1;
+
+    /* println!(
+        "Best candidate is '{}' with a normalized score of {} with alphas {:?}",
+        best_candidate.0, highest_normalized_score, best_candidate_counters
+    ); */
+
+    (best_candidate, best_candidate_counters.clone())
+}
+
+pub fn get_best_candidate_to_upscale_migrate_f32_score(
+    parent_hsm_score_vec: &mut [(String, f32)],
+    parent_hsm_hw_component_vec: &[(String, HashMap<String, usize>)],
+) -> ((String, f32), HashMap<String, usize>) {
+    parent_hsm_score_vec.sort_by(|b, a| a.1.partial_cmp(&b.1).unwrap());
+
+    // Get node with highest normalized score (best candidate)
+    let best_candidate: (String, f32) = parent_hsm_score_vec
+        .iter_mut()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap()
+        .clone();
+
+    let best_candidate_counters = &parent_hsm_hw_component_vec
+        .iter()
+        .find(|(node, _)| node.eq(&best_candidate.0))
+        .unwrap()
+        .1;
+
+    /* println!(
+        "Best candidate is '{}' with a normalized score of {} with alphas {:?}",
+        best_candidate.0, highest_normalized_score, best_candidate_counters
+    ); */
+
+    (best_candidate, best_candidate_counters.clone())
+}
+
+// Calculates node score based on hw component density
+pub fn calculate_node_density_score(
+    node_hw_component_count_hashmap_target_hsm_vec: &Vec<(String, HashMap<String, usize>)>,
+) -> HashMap<String, usize> {
+    let mut target_hsm_density_score_hashmap: HashMap<String, usize> = HashMap::new();
+    for target_hsm_group_hw_component in node_hw_component_count_hashmap_target_hsm_vec {
+        let node_density_score = target_hsm_group_hw_component
+            .1
+            .iter()
+            .map(|(hw_component, qty)| memory_ops::get_hw_component_score_qty(hw_component, *qty))
+            .sum();
+        target_hsm_density_score_hashmap
+            .insert(target_hsm_group_hw_component.clone().0, node_density_score);
+    }
+
+    target_hsm_density_score_hashmap
+}
+
+// Given a node hw conter, user request hw conter and HSM hw counters, calculate if it is save
+// to remove this node form the HSM and still be able to fullful the user request using the
+// rest of nodes in the HSM group. This method is used to validate if HSM group still can
+// fulfill user request after removing a node.
+pub fn can_node_be_removed_without_violating_user_request(
+    node_hw_component_count: &HashMap<String, usize>,
+    user_request_hw_components_count_hashmap: &HashMap<String, usize>,
+    target_hsm_node_hw_components_count_hashmap: &HashMap<String, usize>,
+) -> bool {
+    /* println!(
+        "DEBUG - user_request_hw_components_count_hashmap: {:?}",
+        user_request_hw_components_count_hashmap
+    ); */
+    for (hw_component, requested_qty) in user_request_hw_components_count_hashmap {
+        if let Some(node_hw_component_count) = node_hw_component_count.get(hw_component) {
+            let target_hsm_hw_component_count: usize = *target_hsm_node_hw_components_count_hashmap
+                .get(hw_component)
+                .unwrap();
+            /* println!(
+                "DEBUG - User requested hw component {} Node qty {} HSM qty {}",
+                hw_component, node_hw_component_count, target_hsm_hw_component_count
+            ); */
+
+            return target_hsm_hw_component_count - node_hw_component_count >= *requested_qty;
+        }
+    }
+
+    true
+}
+
+/// Calculates a normalized score for each hw component in HSM group based on component
+/// scarcity.
+pub fn calculate_hsm_hw_component_normalized_node_density_score_downscale(
+    target_hsm_node_hw_component_count_hashmap_vec: &[(String, HashMap<String, usize>)],
+    hw_components_to_migrate_from_one_hsm_to_another_hsm: &HashMap<String, isize>,
+    hw_component_count_requested_by_user: &HashMap<String, usize>,
+    target_hsm_hw_component_normalized_scores: &HashMap<String, f32>,
+    target_hsm_hw_component_count_hashmap: &HashMap<String, usize>,
+) -> Vec<(String, f32)> {
+    /* println!(
+        "DEBUG - node_hw_component_count_hashmap_target_hsm_vec: {:?}",
+        target_hsm_node_hw_component_count_hashmap_vec
+    );
+
+    println!(
+        "DEBUG - hw_components_to_migrate_from_one_hsm_to_another_hsm: {:?}",
+        hw_components_to_migrate_from_one_hsm_to_another_hsm
+    );
+
+    println!(
+        "DEBUG - hsm_hw_component_count: {:?}",
+        hsm_hw_component_count
+    ); */
+
+    let mut target_hsm_density_score_hashmap: HashMap<String, f32> = HashMap::new();
+
+    for (xname, node_hw_component_count) in target_hsm_node_hw_component_count_hashmap_vec {
+        let mut node_hw_normalize_score = 0f32;
+
+        /* println!(
+            "DEBUG - #### NODE '{}' counters: {:?} ####",
+            xname, node_hw_component_count
+        ); */
+        if !can_node_be_removed_without_violating_user_request(
+            node_hw_component_count,
+            hw_component_count_requested_by_user,
+            target_hsm_hw_component_count_hashmap,
+        ) {
+            // cannot remove this hw component, otherwise we won't have enough
+            // resources to fullfil user request
+            // println!("DEBUG - cannot remove node '{}', otherwise we will violate user request, updating score to negative one", xname);
+            node_hw_normalize_score = f32::MIN;
+        } else {
+            for hw_component in node_hw_component_count.keys() {
+                let hw_component_normalize_score: f32 =
+                    if hw_components_to_migrate_from_one_hsm_to_another_hsm
+                        .get(hw_component)
+                        .is_some_and(|qty| qty.abs() > 0)
+                    {
+                        /* let hsm_hw_component_count_hashmap: HashMap<String, usize> =
+                        calculate_hsm_hw_component_count(
+                            target_hsm_node_hw_component_count_hashmap_vec,
+                        ); */
+                        // println!("DEBUG - hw component '{}' needs to go", hw_component);
+                        // found hw component that needs to move out
+                        /* let quantity_yet_to_go =
+                        hw_components_to_migrate_from_one_hsm_to_another_hsm
+                            .get(hw_component)
+                            .unwrap()
+                            .abs() as usize; */
+                        // println!(
+                        //     "DEBUG - hw component: {}, quantity node has: {}, quantity to go: {}",
+                        //     hw_component, *qty as f32, quantity_yet_to_go
+                        // );
+                        if can_node_be_removed_without_violating_user_request(
+                            node_hw_component_count,
+                            hw_component_count_requested_by_user,
+                            target_hsm_hw_component_count_hashmap,
+                        ) {
+                            // found hw component in hsm goup with enough quantity to remove
+                            /* println!(
+                                "DEBUG - qty: {}, hsm_hw_component_count: {}",
+                                qty, hsm_hw_component_count
+                            ); */
+                            100_f32
+                                - (*target_hsm_hw_component_normalized_scores
+                                    .get(hw_component)
+                                    .unwrap())
+                        } else {
+                            // cannot remove this hw component, otherwise we won't have enough
+                            // resources to fullfil user request
+                            // println!("DEBUG - cannot remove hw component '{}', otherwise we will violate user request, updating score to negative one", hw_component);
+                            -(100_f32
+                                - (*target_hsm_hw_component_normalized_scores
+                                    .get(hw_component)
+                                    .unwrap()))
+                        }
+                    } else {
+                        // found a hw component the user is not asking for
+                        // println!(
+                        //     "DEBUG - hw component: {} to look for in {:?}",
+                        //     hw_component, target_hsm_hw_component_normalized_scores
+                        // );
+                        -(100_f32
+                            - (*target_hsm_hw_component_normalized_scores
+                                .get(hw_component)
+                                .unwrap()))
+                    };
+
+                /* println!(
+                    "DEBUG - node: {}, hw component: {}, normalized score: {}",
+                    xname, hw_component, hw_component_normalize_score
+                ); */
+                node_hw_normalize_score += hw_component_normalize_score;
+            }
+        }
+
+        target_hsm_density_score_hashmap.insert(xname.to_string(), node_hw_normalize_score);
+    }
+
+    let target_hsm_normalized_density_score_tuple_vec: Vec<(String, f32)> =
+        target_hsm_density_score_hashmap.into_iter().collect();
+
+    target_hsm_normalized_density_score_tuple_vec
+}
+
+pub fn calculate_hsm_hw_component_normalized_node_density_score_upscale(
+    hsm_node_hw_component_count_hashmap_vec: &[(String, HashMap<String, usize>)],
+    hw_components_to_migrate_from_one_hsm_to_another_hsm: &HashMap<String, isize>,
+    // hw_component_count_requested_by_user: &HashMap<String, usize>,
+    hsm_hw_component_normalized_scores: &HashMap<String, f32>,
+    // hsm_hw_component_count_hashmap: &HashMap<String, usize>,
+    // hsm_hw_component_count: usize,
+) -> Vec<(String, f32)> {
+    /* println!(
+        "DEBUG - node_hw_component_count_hashmap_target_hsm_vec: {:?}",
+        hsm_node_hw_component_count_hashmap_vec
+    ); */
+
+    /* println!(
+        "DEBUG - hw_components_to_migrate_from_one_hsm_to_another_hsm: {:?}",
+        hw_components_to_migrate_from_one_hsm_to_another_hsm
+    ); */
+
+    /* println!(
+        "DEBUG - hsm_hw_component_count: {:?}",
+        hsm_hw_component_count
+    ); */
+
+    let mut hsm_density_score_hashmap: HashMap<String, f32> = HashMap::new();
+
+    for (xname, node_hw_component_count) in hsm_node_hw_component_count_hashmap_vec {
+        let mut node_hw_normalize_score = 0f32;
+
+        /* println!(
+            "DEBUG - #### NODE '{}' counters: {:?} ####",
+            xname, node_hw_component_count
+        ); */
+        /* if !can_node_be_removed_without_violating_user_request(
+            node_hw_component_count,
+            hw_component_count_requested_by_user,
+            hsm_hw_component_count_hashmap,
+        ) {
+            // cannot remove this hw component, otherwise we won't have enough
+            // resources to fullfil user request
+            // println!("DEBUG - cannot remove node '{}', otherwise we will violate user request, updating score to negative one", xname);
+            // node_hw_normalize_score = f32::MIN;
+        } else { */
+        for hw_component in node_hw_component_count.keys() {
+            let hw_component_normalize_score: f32 =
+                if hw_components_to_migrate_from_one_hsm_to_another_hsm
+                    .get(hw_component)
+                    .is_some_and(|qty| qty.abs() > 0)
+                {
+                    // found hw component that needs to move out
+                    100_f32
+                        - (*hsm_hw_component_normalized_scores
+                            .get(hw_component)
+                            .unwrap())
+                } else {
+                    // found a hw component the user is not asking for
+                    /* println!(
+                        "DEBUG - hw component: {} to look for in {:?}",
+                        hw_component, hsm_hw_component_normalized_scores
+                    ); */
+                    -(100_f32
+                        - (*hsm_hw_component_normalized_scores
+                            .get(hw_component)
+                            .unwrap()))
+                };
+
+            /* println!(
+                "DEBUG - node: {}, hw component: {}, normalized score: {}",
+                xname, hw_component, hw_component_normalize_score
+            ); */
+            node_hw_normalize_score += hw_component_normalize_score;
+        }
+        // }
+
+        hsm_density_score_hashmap.insert(xname.to_string(), node_hw_normalize_score);
+    }
+
+    let target_hsm_normalized_density_score_tuple_vec: Vec<(String, f32)> =
+        hsm_density_score_hashmap.into_iter().collect();
+
+    target_hsm_normalized_density_score_tuple_vec
+}
+
+pub fn calculate_hsm_hw_component_node_scores(
+    hsm_group_hw_component_counter_vec: &Vec<(String, HashMap<String, usize>)>,
+    hw_components_to_migrate_from_one_hsm_to_another_hsm: &HashMap<String, isize>,
+) -> Vec<(String, isize)> {
+    let mut target_hsm_score_vec: Vec<(String, isize)> = Vec::new();
+
+    for (node, hw_components_count_hashmap) in hsm_group_hw_component_counter_vec {
+        // println!("# Processing node: ({},{:?}", node, hw_components);
+
+        // Calculate node's score
+        let mut score = 0;
+        for (hw_component, quantity) in hw_components_count_hashmap {
+            // IMPORTANT TO LEAVE THIS AS IT IS, WE
+            // WANT TO ITERATE THROUGH ALL HW
+            // COMPONENTS IN THE NODE TO CALCULATE A
+            // SCORE THAT REFLECTS PENALIZATION WHEN
+            // THE COMPONENT IN THE NODE IS NOT
+            // RELATED TO THE COMPONENTS REQUESTED BY
+            // THE USER OR WHEN SELECING THIS NODE AS A CANDIDATE WOULD ACTUALLY IMPACT THE
+            // NUMBER OF COMPONENTS THE USERS REQUESTS NEGATIVELY
+            // NOTE: We are only seeing the node's
+            // components related to what the user is
+            // requesting... maybe we should get a
+            // list of all relevant components
+            // (processors and accelerators????) so we
+            // can get a better idea of the node and
+            // increase the penalization in the
+            // score????
+            // 0 if hw_component is not in user request
+            let component_delta = hw_components_to_migrate_from_one_hsm_to_another_hsm
+                .get(hw_component)
+                .unwrap_or(&0);
+
+            // let component_delta = hw_components_to_migrate_from_one_hsm_to_another_hsm.get(hw_component).unwrap_or(&(quantity.to_owned() as isize));
+            // .get(hw_component)
+            // .unwrap_or(&0)
+            // .clone();
+
+            let quantity_score =
+                memory_ops::get_hw_component_score_qty(hw_component, *quantity) as isize;
+
+            let component_score = if component_delta.unsigned_abs() >= *quantity {
+                // This hw component is in user request and is good to be a candidate
+                quantity_score
+                /* println!(
+                    "hw component {} --> current score {} + node component score {} ==> new score {}",
+                    hw_component, score, node_component_score, (score + component_score)
+                ); */
+            } else {
+                // hw component either is not requested by user or can't be candidate
+                // (othwerwise user will receive less hw components than requested)
+
+                /* println!(
+                    "delta ({}) < node component quantity ({}) --> node component score = 0",
+                    component_delta, quantity
+                ); */
+                if hw_components_to_migrate_from_one_hsm_to_another_hsm
+                    .iter()
+                    .any(|(hw_component_requested_by_user, _quantity)| {
+                        hw_component.contains(hw_component_requested_by_user)
+                    })
+                {
+                    // This hw component type is in user
+                    // pattern but selecting this node as a candidate means the user would receive
+                    // less number of components that it initially requested
+
+                    -quantity_score
+                } else {
+                    // This hw component type is not in user pattern, therefore, its quantity
+                    // is going to count as a penalization to get the node evicted/migrated
+                    // from the HSM group
+
+                    quantity_score // We may want to add a penalization here...
+                }
+            };
+
+            /* println!(
+                "component {}, delta {}, component component_score {}",
+                hw_component, component_delta, component_score
+            ); */
+
+            score += component_score;
+        }
+
+        target_hsm_score_vec.push((node.to_string(), score));
+    }
+
+    target_hsm_score_vec
+}
+
+/// Hw components to move between target and parent HSM groups to honor the pattern. Floors
+/// (at-least) only add hw components, ceilings (at-most) only remove them and exact targets
+/// do both
+pub fn calculate_all_deltas(
+    user_defined_hw_component_counter_hashmap: &HwComponentQualifiedCountHashMap,
+    hsm_hw_component_summary_hashmap: &HashMap<String, usize>,
+) -> (HashMap<String, isize>, HashMap<String, isize>) {
+    /* println!(
+        "DEBUG -- user_defined_hw_component_counter_hashmap: {:?}",
+        user_defined_hw_component_counter_hashmap
+    );
+    println!(
+        "DEBUG - hsm_hw_component_count_hashmap: {:?}",
+        hsm_hw_component_count_hashmap
+    ); */
+
+    let mut hw_components_to_migrate_from_target_hsm_to_parent_hsm: HashMap<String, isize> =
+        HashMap::new();
+
+    let mut hw_components_to_migrate_from_parent_hsm_to_target_hsm: HashMap<String, isize> =
+        HashMap::new();
+
+    for (user_defined_hw_component, (new_quantity, qualifier)) in
+        user_defined_hw_component_counter_hashmap
+    {
+        let quantity_from = hsm_hw_component_summary_hashmap
+            .get(user_defined_hw_component)
+            .unwrap_or(&0);
+
+        let delta = match qualifier {
+            QuantityQualifier::AtLeast => (*new_quantity as isize - *quantity_from as isize).max(0),
+            QuantityQualifier::AtMost => (*new_quantity as isize - *quantity_from as isize).min(0),
+            QuantityQualifier::Exact => *new_quantity as isize - *quantity_from as isize,
+        };
+
+        /* println!(
+            "DEBUG - hw component {} : user request qty {} target hsm qty {} --> delta is {}",
+            user_defined_hw_component, new_quantity, quantity_from, delta
+        ); */
+
+        /* if delta > 0 {
+            // Migrate nodes from parent to target HSM group
+            hw_components_to_migrate_from_parent_hsm_to_target_hsm
+                .insert(user_defined_hw_component.to_string(), -delta);
+        } else if delta < 0 {
+            // Migrate nodes from target to parent HSM group
+            hw_components_to_migrate_from_target_hsm_to_parent_hsm
+                .insert(user_defined_hw_component.to_string(), delta);
+        } else {
+            // No change
+        } */
+
+        match delta as i32 {
+            1.. =>
+            // delta > 0 -> Migrate nodes from parent to target HSM group
+            {
+                hw_components_to_migrate_from_parent_hsm_to_target_hsm
+                    .insert(user_defined_hw_component.to_string(), -delta);
+            }
+            ..=-1 =>
+            // delta < 0 -> Migrate nodes from target to parent HSM group
+            {
+                hw_components_to_migrate_from_target_hsm_to_parent_hsm
+                    .insert(user_defined_hw_component.to_string(), delta);
+            }
+            0 =>
+                // delta == 0 -> Do nothing
+                {}
+        }
+    }
+
+    (
+        hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+        hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+    )
+}
+
+/// Returns a tuple like (<xname>, <list of hw components>, <list of memory capacity>, <list of
+/// accelerator memory>)
+/// Note: list of hw components can be either the hw componentn pattern provided by user or the
+/// description from the HSM API
+pub async fn get_node_hw_component_count(
+    shasta_client: &ShastaClient,
+    hsm_member: &str,
+    user_defined_hw_profile_vec: Vec<String>,
+) -> (String, Vec<String>, Vec<u64>, Vec<u64>) {
+    let node_hw_inventory_value =
+        hsm::http_client::get_hw_inventory_query(shasta_client, hsm_member, "Node")
+            .await
+            .unwrap();
+
+    let node_hw_profile = get_node_hw_properties_from_value(
+        &node_hw_inventory_value,
+        user_defined_hw_profile_vec.clone(),
+    );
+
+    (
+        hsm_member.to_string(),
+        node_hw_profile.0,
+        node_hw_profile.1,
+        node_hw_profile.2,
+    )
+}
+
+// Calculate/groups hw component counters filtered by user request
+pub fn get_hsm_hw_component_count_filtered_by_user_request(
+    user_defined_hw_component_vec: &Vec<String>,
+    target_hsm_group_hw_component_vec: &Vec<(String, HashMap<String, usize>)>,
+) -> HashMap<String, usize> {
+    let mut hsm_hw_component_count_hashmap = HashMap::new();
+
+    for x in user_defined_hw_component_vec {
+        let mut hsm_hw_component_count = 0;
+        for y in target_hsm_group_hw_component_vec {
+            hsm_hw_component_count += y.1.get(x).unwrap_or(&0);
+        }
+        hsm_hw_component_count_hashmap.insert(x.to_string(), hsm_hw_component_count);
+    }
+
+    hsm_hw_component_count_hashmap
+}
+
+// Calculate/groups hw component counters
+pub fn calculate_hsm_hw_component_count(
+    target_hsm_group_node_hw_component_vec: &Vec<(String, HashMap<String, usize>)>,
+) -> HashMap<String, usize> {
+    let mut hsm_hw_component_count_hashmap = HashMap::new();
+
+    for (_xname, node_hw_component_count_hashmap) in target_hsm_group_node_hw_component_vec {
+        for (hw_component, &qty) in node_hw_component_count_hashmap {
+            hsm_hw_component_count_hashmap
+                .entry(hw_component.to_string())
+                .and_modify(|qty_aux| *qty_aux += qty)
+                .or_insert(qty);
+        }
+    }
+
+    hsm_hw_component_count_hashmap
+}
+
+/// Takes the hw components of the nodes out of the hw component counters requested by the
+/// user, counters don't go below 0
+pub fn subtract_hw_component_count(
+    user_defined_hw_component_count_hashmap: &mut HashMap<String, usize>,
+    node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+) {
+    for (_xname, node_hw_component_count_hashmap) in node_hw_component_count_vec {
+        for (hw_component, qty) in node_hw_component_count_hashmap {
+            if let Some(qty_requested) =
+                user_defined_hw_component_count_hashmap.get_mut(hw_component)
+            {
+                *qty_requested = qty_requested.saturating_sub(*qty);
+            }
+        }
+    }
+}
+
+// Given a list of tuples (xname, list of hw components qty hasmap), this function will return
+// the list of hw components wih their quantity normalized in within the hsm group
+pub fn calculate_hsm_hw_component_normalized_density_score_from_hsm_node_hw_component_count_vec(
+    hsm_node_hw_component_count_vec: &Vec<(String, HashMap<String, usize>)>,
+    total_number_hw_components: usize,
+) -> HashMap<String, f32> {
+    let mut hsm_hw_component_count_hashmap: HashMap<String, usize> = HashMap::new();
+
+    for (_xname, node_hw_component_count_hashmap) in hsm_node_hw_component_count_vec {
+        for (hw_component, qty) in node_hw_component_count_hashmap {
+            hsm_hw_component_count_hashmap
+                .entry(hw_component.to_string())
+                .and_modify(|qty_aux| *qty_aux += qty)
+                .or_insert(*qty);
+        }
+    }
+
+    calculate_hsm_hw_component_normalized_density_score_from_hsm_hw_component_count_hashmap(
+        hsm_hw_component_count_hashmap,
+        total_number_hw_components,
+    )
+}
+
+// Given the list of hw components qty in a node, this function will return the list of hw
+// components with their quantity normalized within the node
+pub fn calculate_hsm_hw_component_normalized_density_score_from_hsm_hw_component_count_hashmap(
+    hsm_hw_component_count_hashmap: HashMap<String, usize>,
+    total_number_hw_components: usize,
+) -> HashMap<String, f32> {
+    /* let total_number_hw_components = hsm_hw_component_count_hashmap
+    .iter()
+    .fold(0, |acc, (_hw_component, qty)| acc + qty); */
+
+    /* let mut hsm_hw_component_normalized_score_hashmap: HashMap<String, f32> = HashMap::new();
+
+    for (hw_component, qty) in hsm_hw_component_count_hashmap {
+        hsm_hw_component_normalized_score_hashmap
+            .entry(hw_component.to_string())
+            .and_modify(|qty_aux| {
+                *qty_aux = *qty_aux as f32 / total_number_hw_components as f32
+            })
+            .or_insert(qty as f32 / total_number_hw_components as f32);
+    }
+
+    hsm_hw_component_normalized_score_hashmap */
+
+    // Capacities (memory and gpumem) are not a number of hw components, they do not weigh in
+    // the density
+    hsm_hw_component_count_hashmap
+        .iter()
+        .map(|(hw_component, qty)| {
+            let normalized_density_score = if memory_ops::is_capacity_hw_component(hw_component) {
+                100_f32
+            } else {
+                (*qty * 100) as f32 / total_number_hw_components as f32
+            };
+
+            (hw_component.to_string(), normalized_density_score)
+        })
+        .collect()
+}
+
+/// Returns the properties in hw_property_list found in the node_hw_inventory_value which is
+/// HSM hardware inventory API json response, the capacity (MiB) of its DIMMs and the memory
+/// (bytes) of its accelerators, the later only if the pattern asks for gpumem
+pub fn get_node_hw_properties_from_value(
+    node_hw_inventory_value: &Value,
+    hw_component_pattern_list: Vec<String>,
+) -> (Vec<String>, Vec<u64>, Vec<u64>) {
+    let processor_vec =
+        mesa::hsm::hw_inventory::shasta::utils::get_list_processor_model_from_hw_inventory_value(
+            node_hw_inventory_value,
+        )
+        .unwrap_or_default();
+
+    let accelerator_vec =
+        mesa::hsm::hw_inventory::shasta::utils::get_list_accelerator_model_from_hw_inventory_value(
+            node_hw_inventory_value,
+        )
+        .unwrap_or_default();
+
+    let accelerator_memory_vec = if hw_component_pattern_list
+        .iter()
+        .any(|hw_component| hw_component == memory_ops::GPU_MEMORY_HW_COMPONENT)
+    {
+        accelerator_vec
+            .iter()
+            .filter_map(|model| hw_component_ops::get_accelerator_memory_from_model(model))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let processor_and_accelerator = [processor_vec, accelerator_vec].concat();
+
+    let processor_and_accelerator_lowercase = processor_and_accelerator
+        .iter()
+        .map(|hw_component| hw_component.to_lowercase());
+
+    /* let mut node_hw_component_pattern_vec = Vec::new();
+
+    for actual_hw_component_pattern in processor_and_accelerator_lowercase {
+        for hw_component_pattern in &hw_component_pattern_list {
+            if actual_hw_component_pattern.contains(hw_component_pattern) {
+                node_hw_component_pattern_vec.push(hw_component_pattern.to_string());
+            } else {
+                node_hw_component_pattern_vec.push(actual_hw_component_pattern.clone());
+            }
+        }
+    }
+
+    node_hw_component_pattern_vec */
+
+    let mut node_hw_component_pattern_vec = Vec::new();
+
+    for actual_hw_component_pattern in processor_and_accelerator_lowercase {
+        if let Some(hw_component_pattern) = hw_component_pattern_list
+            .iter()
+            .find(|&hw_component| actual_hw_component_pattern.contains(hw_component))
+        {
+            node_hw_component_pattern_vec.push(hw_component_pattern.to_string());
+        } else {
+            node_hw_component_pattern_vec.push(actual_hw_component_pattern);
+        }
+    }
+
+    // HSN NICs and drives are only counted if the pattern asks for them, otherwise they would
+    // weigh in the scores of every pattern
+    let node_value = &node_hw_inventory_value["Nodes"][0];
+
+    if hw_component_pattern_list
+        .iter()
+        .any(|hw_component| hw_component == hw_component_ops::HSN_NIC_HW_COMPONENT)
+    {
+        let hsn_nic_count = node_value["NodeHsnNics"]
+            .as_array()
+            .map_or(0, |hsn_nic_vec| hsn_nic_vec.len());
+
+        node_hw_component_pattern_vec.extend(
+            std::iter::repeat(hw_component_ops::HSN_NIC_HW_COMPONENT.to_string())
+                .take(hsn_nic_count),
+        );
+    }
+
+    for drive_value in node_value["Drives"].as_array().unwrap_or(&Vec::new()) {
+        let drive_hw_component = hw_component_ops::get_drive_hw_component(
+            drive_value
+                .pointer("/PopulatedFRU/DriveFRUInfo/Protocol")
+                .and_then(Value::as_str),
+            drive_value
+                .pointer("/PopulatedFRU/DriveFRUInfo/MediaType")
+                .and_then(Value::as_str),
+        );
+
+        if let Some(hw_component) = [drive_hw_component, hw_component_ops::DRIVE_HW_COMPONENT]
+            .into_iter()
+            .find(|hw_component| {
+                hw_component_pattern_list
+                    .iter()
+                    .any(|hw| hw == hw_component)
+            })
+        {
+            node_hw_component_pattern_vec.push(hw_component.to_string());
+        }
+    }
+
+    let memory_vec =
+        mesa::hsm::hw_inventory::shasta::utils::get_list_memory_capacity_from_hw_inventory_value(
+            node_hw_inventory_value,
+        )
+        .unwrap_or_default();
+
+    (
+        node_hw_component_pattern_vec,
+        memory_vec,
+        accelerator_memory_vec,
+    )
+}
+
+pub fn build_table(
+    user_defined_hw_componet_vec: &[String],
+    hsm_hw_pattern_vec: &[(String, HashMap<String, usize>)],
+    hsm_density_score_hashmap: &HashMap<String, usize>,
+    hsm_score_vec: &[(String, isize)],
+) -> comfy_table::Table {
+    /* println!("DEBUG - hsm_hw_pattern_vec:\n{:?}", hsm_hw_pattern_vec);
+    println!(
+        "DEBUG - hsm_density_score_hashmap:\n{:?}",
+        hsm_density_score_hashmap
+    ); */
+
+    let hsm_hw_component_vec: Vec<String> = hsm_hw_pattern_vec
+        .iter()
+        .flat_map(|(_xname, node_pattern_hashmap)| node_pattern_hashmap.keys().cloned())
+        .collect();
+
+    let mut all_hw_component_vec =
+        [hsm_hw_component_vec, user_defined_hw_componet_vec.to_vec()].concat();
+
+    all_hw_component_vec.sort();
+    all_hw_component_vec.dedup();
+
+    // println!("DEBUG - all_hw_component_vec : {:?}", all_hw_component_vec);
+
+    let mut table = table_ops::new_table();
+
+    let header_vec = [
+        vec!["Node".to_string()],
+        all_hw_component_vec.clone(),
+        vec!["Density Score".to_string()],
+        vec!["Score".to_string()],
+    ]
+    .concat();
+
+    table.set_header(header_vec.clone());
+
+    let mut row_vec: Vec<Vec<comfy_table::Cell>> = Vec::new();
+
+    for (xname, node_pattern_hashmap) in hsm_hw_pattern_vec {
+        // println!("node_pattern_hashmap: {:?}", node_pattern_hashmap);
+
+        let mut row: Vec<comfy_table::Cell> = Vec::new();
+        // Node xname table cell
+        row.push(
+            comfy_table::Cell::new(xname.clone()).set_alignment(comfy_table::CellAlignment::Center),
+        );
+        // User hw components table cell
+        for hw_component in &all_hw_component_vec {
+            if user_defined_hw_componet_vec.contains(hw_component)
+                && node_pattern_hashmap.contains_key(hw_component)
+            {
+                let counter = node_pattern_hashmap.get(hw_component).unwrap();
+                row.push(
+                    comfy_table::Cell::new(format!(
+                        "✅ ({})",
+                        memory_ops::format_hw_component_qty(hw_component, *counter)
+                    ))
+                    .fg(Color::Green)
+                    .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            } else if node_pattern_hashmap.contains_key(hw_component) {
+                let counter = node_pattern_hashmap.get(hw_component).unwrap();
+                row.push(
+                    comfy_table::Cell::new(format!(
+                        "\u{26A0} ({})",
+                        memory_ops::format_hw_component_qty(hw_component, *counter)
+                    ))
+                    .fg(Color::Yellow)
+                    .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            } else {
+                // node does not contain hardware but it was requested by the user
+                row.push(
+                    comfy_table::Cell::new("❌".to_string())
+                        .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            }
+        }
+        /* for user_defined_hw_component in user_defined_hw_componet_vec {
+            if node_pattern_hashmap.contains_key(user_defined_hw_component) {
+                let counter = node_pattern_hashmap.get(user_defined_hw_component).unwrap();
+                row.push(
+                    comfy_table::Cell::new(format!("✅ ({})", format_count(counter)))
+                        .fg(Color::Green)
+                        .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            } else {
+                row.push(
+                    comfy_table::Cell::new("❌".to_string())
+                        .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            }
+        } */
+        // Node density score table cell
+        row.push(
+            comfy_table::Cell::new(format_count(hsm_density_score_hashmap.get(xname).unwrap()))
+                .set_alignment(comfy_table::CellAlignment::Center),
+        );
+        // Node score table cell
+        let node_score = hsm_score_vec
+            .iter()
+            .find(|(node_name, _)| node_name.eq(xname))
+            .unwrap()
+            .1;
+        let node_score_table_cell = if node_score <= 0 {
+            comfy_table::Cell::new(format_count(node_score))
+                .set_alignment(comfy_table::CellAlignment::Center)
+                .fg(Color::Red)
+        } else {
+            comfy_table::Cell::new(format_count(node_score))
+                .set_alignment(comfy_table::CellAlignment::Center)
+                .fg(Color::Green)
+        };
+        row.push(node_score_table_cell);
+        row_vec.push(row);
+    }
+
+    table_ops::sort_row_vec(&header_vec, &mut row_vec);
+    table.add_rows(row_vec);
+
+    table
+}
+
+pub fn build_table_f32_score(
+    user_defined_hw_componet_vec: &[String],
+    hsm_hw_pattern_vec: &[(String, HashMap<String, usize>)],
+    hsm_density_score_hashmap: &HashMap<String, usize>,
+    hsm_score_vec: &[(String, f32)],
+) -> comfy_table::Table {
+    /* println!("DEBUG - hsm_hw_pattern_vec:\n{:?}", hsm_hw_pattern_vec);
+    println!(
+        "DEBUG - hsm_density_score_hashmap:\n{:?}",
+        hsm_density_score_hashmap
+    ); */
+
+    let hsm_hw_component_vec: Vec<String> = hsm_hw_pattern_vec
+        .iter()
+        .flat_map(|(_xname, node_pattern_hashmap)| node_pattern_hashmap.keys().cloned())
+        .collect();
+
+    let mut all_hw_component_vec =
+        [hsm_hw_component_vec, user_defined_hw_componet_vec.to_vec()].concat();
+
+    all_hw_component_vec.sort();
+    all_hw_component_vec.dedup();
+
+    // println!("DEBUG - all_hw_component_vec : {:?}", all_hw_component_vec);
+
+    let mut table = table_ops::new_table();
+
+    let header_vec = [
+        vec!["Node".to_string()],
+        all_hw_component_vec.clone(),
+        vec!["Density Score".to_string()],
+        vec!["Score".to_string()],
+    ]
+    .concat();
+
+    table.set_header(header_vec.clone());
+
+    let mut row_vec: Vec<Vec<comfy_table::Cell>> = Vec::new();
+
+    for (xname, node_pattern_hashmap) in hsm_hw_pattern_vec {
+        // println!("node_pattern_hashmap: {:?}", node_pattern_hashmap);
+
+        let mut row: Vec<comfy_table::Cell> = Vec::new();
+        // Node xname table cell
+        row.push(
+            comfy_table::Cell::new(xname.clone()).set_alignment(comfy_table::CellAlignment::Center),
+        );
+        // User hw components table cell
+        for hw_component in &all_hw_component_vec {
+            if user_defined_hw_componet_vec.contains(hw_component)
+                && node_pattern_hashmap.contains_key(hw_component)
+            {
+                let counter = node_pattern_hashmap.get(hw_component).unwrap();
+                row.push(
+                    comfy_table::Cell::new(format!(
+                        "✅ ({})",
+                        memory_ops::format_hw_component_qty(hw_component, *counter)
+                    ))
+                    .fg(Color::Green)
+                    .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            } else if node_pattern_hashmap.contains_key(hw_component) {
+                let counter = node_pattern_hashmap.get(hw_component).unwrap();
+                row.push(
+                    comfy_table::Cell::new(format!(
+                        "⚠️ ({})",
+                        memory_ops::format_hw_component_qty(hw_component, *counter)
+                    )) // NOTE: emojis
+                    // can also be printed using unicode like \u{26A0}
+                    .fg(Color::Yellow)
+                    .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            } else {
+                // node does not contain hardware but it was requested by the user
+                row.push(
+                    comfy_table::Cell::new("❌".to_string())
+                        .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            }
+        }
+        /* for user_defined_hw_component in user_defined_hw_componet_vec {
+            if node_pattern_hashmap.contains_key(user_defined_hw_component) {
+                let counter = node_pattern_hashmap.get(user_defined_hw_component).unwrap();
+                row.push(
+                    comfy_table::Cell::new(format!("✅ ({})", format_count(counter)))
+                        .fg(Color::Green)
+                        .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            } else {
+                row.push(
+                    comfy_table::Cell::new("❌".to_string())
+                        .set_alignment(comfy_table::CellAlignment::Center),
+                );
+            }
+        } */
+        // Node density score table cell
+        row.push(
+            comfy_table::Cell::new(format_count(
+                hsm_density_score_hashmap.get(xname).unwrap_or(&0),
+            ))
+            .set_alignment(comfy_table::CellAlignment::Center),
+        );
+        // Node score table cell
+        let node_score = hsm_score_vec
+            .iter()
+            .find(|(node_name, _)| node_name.eq(xname))
+            .unwrap_or(&(xname.to_string(), 0f32))
+            .1;
+        let node_score_table_cell = if node_score <= 0f32 {
+            comfy_table::Cell::new(format_score(node_score))
+                .set_alignment(comfy_table::CellAlignment::Center)
+                .fg(Color::Red)
+        } else {
+            comfy_table::Cell::new(format_score(node_score))
+                .set_alignment(comfy_table::CellAlignment::Center)
+                .fg(Color::Green)
+        };
+        row.push(node_score_table_cell);
+        row_vec.push(row);
+    }
+
+    table_ops::sort_row_vec(&header_vec, &mut row_vec);
+    table.add_rows(row_vec);
+
+    table
+}
+
+pub fn calculate_hsm_total_number_hw_components(
+    target_hsm_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+) -> usize {
+    target_hsm_hw_component_count_vec
+        .iter()
+        .flat_map(|(_node, hw_component_hashmap)| hw_component_hashmap.iter())
+        .filter(|(hw_component, _)| !memory_ops::is_capacity_hw_component(hw_component))
+        .map(|(_, qty)| qty)
+        .sum()
+}
+
+/// Solver settings to calculate a plan
+pub struct SolverOptions<'a> {
+    pub scoring_strategy: &'a dyn ScoringStrategy,
+    /// 'min-moves' objective, otherwise 'density'
+    pub min_moves: bool,
+    /// Maximum number of candidate nodes for the exact solver, None for the greedy solver
+    pub exact_solver_max_nodes_opt: Option<usize>,
+    pub whole_blade: bool,
+    /// Reports the details of each iteration of the greedy solver to `on_progress_opt`
+    pub verbose: bool,
+    pub explain: bool,
+    /// Progress of the node selection, nothing is reported if None
+    pub on_progress_opt: Option<SolverProgressCallback<'a>>,
+}
+
+/// Nodes ending up in the target and parent HSM groups to honor a pattern
+pub struct Plan {
+    /// Excluded and pinned nodes kept in (or moved to) the target HSM group regardless of
+    /// their score
+    pub kept_node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)>,
+    /// Nodes selected by the solver for the target HSM group, sorted by xname
+    pub selected_node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)>,
+    /// Candidate nodes not selected, they end up in the parent HSM group, sorted by xname
+    pub parent_hsm_node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)>,
+    /// Hw components requested (floors) once the hw components of the kept nodes and the hw
+    /// components not found in any candidate are taken out
+    pub hw_component_count_requested_hashmap: HashMap<String, usize>,
+    /// Hw component counters of all candidates (target and parent HSM groups)
+    pub collective_hw_component_count_hashmap: HashMap<String, usize>,
+    /// Hw components requested but not found in any candidate
+    pub unknown_hw_component_vec: Vec<String>,
+    pub solver_report: String,
+    pub node_selection_explanation_vec_opt: Option<Vec<NodeSelectionExplanation>>,
+}
+
+impl Plan {
+    /// Members of the target HSM group once the plan is applied
+    pub fn get_target_hsm_member_vec(&self) -> Vec<String> {
+        self.selected_node_hw_component_count_vec
+            .iter()
+            .chain(self.kept_node_hw_component_count_vec.iter())
+            .map(|(xname, _)| xname.clone())
+            .collect()
+    }
+
+    /// Hw component counters of the target HSM group once the plan is applied
+    pub fn get_target_hsm_hw_component_count_hashmap(&self) -> HashMap<String, usize> {
+        calculate_hsm_hw_component_count(
+            &[
+                self.selected_node_hw_component_count_vec.clone(),
+                self.kept_node_hw_component_count_vec.clone(),
+            ]
+            .concat(),
+        )
+    }
+}
+
+/// Runs the solver over the hw component counters of the nodes in the target and parent HSM
+/// groups, no CSM API is called. Nodes in `excluded_xname_vec` stay in the target HSM group
+/// and nodes in `pinned_xname_vec` end up in the target HSM group, both count towards the
+/// pattern. Fails if the target and parent HSM groups together don't have enough hw
+/// components
+pub fn calculate_plan(
+    mut target_hsm_node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)>,
+    mut parent_hsm_node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)>,
+    hw_component_qualified_count_hashmap: &HwComponentQualifiedCountHashMap,
+    excluded_xname_vec: &[String],
+    pinned_xname_vec: &[String],
+    solver_options: &SolverOptions,
+) -> Result<Plan, String> {
+    let scoring_strategy = solver_options.scoring_strategy;
+
+    let progress_reporter = SolverProgressReporter {
+        on_progress_opt: solver_options.on_progress_opt,
+        verbose: solver_options.verbose,
+    };
+
+    // Solver reaches the floors (at-least and exact qualifiers) without going above the
+    // ceilings (exact and at-most qualifiers)
+    let mut user_defined_hw_component_count_hashmap: HashMap<String, usize> =
+        hw_component_qualified_count_hashmap
+            .iter()
+            .map(|(hw_component, (qty, qualifier))| {
+                (hw_component.clone(), qualifier.get_min_qty(*qty))
+            })
+            .collect();
+
+    let mut hw_component_max_count_hashmap =
+        get_hw_component_max_count_hashmap(hw_component_qualified_count_hashmap);
+
+    let mut user_defined_hw_component_vec: Vec<String> = user_defined_hw_component_count_hashmap
+        .keys()
+        .cloned()
+        .collect();
+
+    user_defined_hw_component_vec.sort();
+
+    // Excluded and pinned nodes in target HSM group stay there and pinned nodes in parent HSM
+    // group are moved to target HSM group. They are taken out of the candidates and their hw
+    // components are taken out of the user request
+    let mut kept_node_hw_component_count_vec = Vec::new();
+
+    target_hsm_node_hw_component_count_vec.retain(|node_hw_component_count| {
+        let is_kept = excluded_xname_vec.contains(&node_hw_component_count.0)
+            || pinned_xname_vec.contains(&node_hw_component_count.0);
+
+        if is_kept {
+            kept_node_hw_component_count_vec.push(node_hw_component_count.clone());
+        }
+
+        !is_kept
+    });
+
+    parent_hsm_node_hw_component_count_vec.retain(|node_hw_component_count| {
+        let is_kept = pinned_xname_vec.contains(&node_hw_component_count.0);
+
+        if is_kept {
+            kept_node_hw_component_count_vec.push(node_hw_component_count.clone());
+        }
+
+        !is_kept
+    });
+
+    subtract_hw_component_count(
+        &mut user_defined_hw_component_count_hashmap,
+        &kept_node_hw_component_count_vec,
+    );
+    subtract_hw_component_count(
+        &mut hw_component_max_count_hashmap,
+        &kept_node_hw_component_count_vec,
+    );
+
+    // Candidates in target HSM group before the migration
+    let target_hsm_member_vec: Vec<String> = target_hsm_node_hw_component_count_vec
+        .iter()
+        .map(|(xname, _)| xname.clone())
+        .collect();
+
+    let mut target_parent_hsm_node_hw_component_count_vec = [
+        target_hsm_node_hw_component_count_vec,
+        parent_hsm_node_hw_component_count_vec,
+    ]
+    .concat();
+
+    // Candidates are blades instead of nodes, blade hw component counters are the sum of its
+    // nodes
+    let candidate_node_hw_component_count_vec =
+        target_parent_hsm_node_hw_component_count_vec.clone();
+
+    let candidate_target_hsm_member_vec: Vec<String> = if solver_options.whole_blade {
+        target_parent_hsm_node_hw_component_count_vec = group_node_hw_component_count_vec_by_blade(
+            &target_parent_hsm_node_hw_component_count_vec,
+        );
+
+        let mut target_hsm_blade_vec: Vec<String> = target_hsm_member_vec
+            .iter()
+            .map(|xname| get_blade_xname(xname))
+            .collect();
+        target_hsm_blade_vec.sort();
+        target_hsm_blade_vec.dedup();
+
+        log::info!(
+            "{} candidate blades",
+            format_count(target_parent_hsm_node_hw_component_count_vec.len())
+        );
+
+        target_hsm_blade_vec
+    } else {
+        target_hsm_member_vec
+    };
+
+    // Collective (target and parent HSM groups) counters and scores
+    let target_parent_hsm_hw_component_count_hashmap: HashMap<String, usize> =
+        calculate_hsm_hw_component_count(&target_parent_hsm_node_hw_component_count_vec);
+
+    let target_parent_hsm_density_score_hashmap: HashMap<String, usize> =
+        calculate_node_density_score(&target_parent_hsm_node_hw_component_count_vec);
+
+    let target_parent_hsm_total_number_hw_components: usize =
+        calculate_hsm_total_number_hw_components(&target_parent_hsm_node_hw_component_count_vec);
+
+    let target_parent_hsm_hw_component_normalized_scores_hashmap =
+        calculate_hsm_hw_component_normalized_density_score_from_hsm_node_hw_component_count_vec(
+            &target_parent_hsm_node_hw_component_count_vec,
+            target_parent_hsm_total_number_hw_components,
+        );
+
+    // Hw components requested not found in any candidate are ignored
+    let mut unknown_hw_component_vec: Vec<String> = user_defined_hw_component_vec
+        .iter()
+        .filter(|hw_component| {
+            !target_parent_hsm_hw_component_count_hashmap.contains_key(*hw_component)
+        })
+        .cloned()
+        .collect();
+
+    unknown_hw_component_vec.sort();
+
+    user_defined_hw_component_count_hashmap.retain(|hw_component, _qty| {
+        target_parent_hsm_hw_component_count_hashmap.contains_key(hw_component)
+    });
+
+    // Check collective HSM has enough capacity to process user request
+    for (hw_component, qty_requested) in &user_defined_hw_component_count_hashmap {
+        let qty_available = target_parent_hsm_hw_component_count_hashmap
+            .get(hw_component)
+            .unwrap();
+        if qty_available < qty_requested {
+            return Err(format!("HSM 'collective' does not have enough resources to fulfill user request. User is requesting {} ({}) but only avaiable {}", hw_component, qty_requested, qty_available));
+        }
+    }
+
+    let hw_components_to_migrate_from_parent_hsm_to_target_hsm: HashMap<String, isize> =
+        user_defined_hw_component_count_hashmap
+            .iter()
+            .map(|(hw_inventory, count)| (hw_inventory.to_string(), -(*count as isize)))
+            .collect();
+
+    // Hw components the collective HSM group has above the user request, used to score the
+    // nodes before any is selected
+    let (hw_components_to_migrate_from_target_hsm_to_parent_hsm, _) = calculate_all_deltas(
+        &user_defined_hw_component_count_hashmap
+            .iter()
+            .map(|(hw_component, qty)| (hw_component.clone(), (*qty, QuantityQualifier::Exact)))
+            .collect(),
+        &target_parent_hsm_hw_component_count_hashmap,
+    );
+
+    let scoring_context = ScoringContext {
+        hw_component_normalized_scores_hashmap:
+            &target_parent_hsm_hw_component_normalized_scores_hashmap,
+        hw_component_count_hashmap: &target_parent_hsm_hw_component_count_hashmap,
+        hw_component_count_requested_hashmap: &user_defined_hw_component_count_hashmap,
+        hw_components_to_migrate_from_target_hsm_to_parent_hsm:
+            &hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+        target_hsm_member_vec: &candidate_target_hsm_member_vec,
+    };
+
+    // Calculate initial scores
+    let target_parent_hsm_score_tuple_vec = scoring_strategy.calculate_node_score_vec(
+        &target_parent_hsm_node_hw_component_count_vec,
+        &hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+        &[],
+        &scoring_context,
+    );
+
+    // Keep candidates to replay the node selection afterwards
+    let explain_candidate_node_vec_opt = solver_options
+        .explain
+        .then(|| target_parent_hsm_node_hw_component_count_vec.clone());
+
+    // Find optimal solution if problem is small enough
+    let mut solver_report = "greedy (not guaranteed optimal)".to_string();
+
+    let exact_solution_opt = match solver_options.exact_solver_max_nodes_opt {
+        Some(exact_solver_max_nodes) => {
+            let candidate_node_count = exact_solver::count_candidate_nodes(
+                &user_defined_hw_component_count_hashmap,
+                &target_parent_hsm_node_hw_component_count_vec,
+            );
+
+            if candidate_node_count > exact_solver_max_nodes {
+                solver_report = format!(
+                    "greedy, exact solver skipped because {} candidate nodes is above the limit of {}",
+                    format_count(candidate_node_count),
+                    format_count(exact_solver_max_nodes)
+                );
+                None
+            } else {
+                let exact_solution_opt = exact_solver::solve(
+                    &user_defined_hw_component_count_hashmap,
+                    &target_parent_hsm_node_hw_component_count_vec,
+                    &target_parent_hsm_score_tuple_vec,
+                    &candidate_target_hsm_member_vec,
+                    solver_options.min_moves,
+                )
+                .filter(|exact_solution| {
+                    // Exact solver only knows about floors
+                    is_within_hw_component_max_count(
+                        &calculate_hsm_hw_component_count(
+                            &exact_solution.node_hw_component_count_vec,
+                        ),
+                        &HashMap::new(),
+                        &hw_component_max_count_hashmap,
+                    )
+                });
+
+                if exact_solution_opt.is_none() {
+                    solver_report =
+                        "greedy, exact solver could not fulfill the request".to_string();
+                }
+
+                exact_solution_opt
+            }
+        }
+        None => None,
+    };
+
+    // Migrate nodes
+    let selected_node_hw_component_count_vec = if let Some(exact_solution) = exact_solution_opt {
+        solver_report = if exact_solution.optimal {
+            "exact (optimal)".to_string()
+        } else {
+            "exact (search limit reached, best solution found may not be optimal)".to_string()
+        };
+
+        target_parent_hsm_node_hw_component_count_vec.retain(|(xname, _)| {
+            !exact_solution
+                .node_hw_component_count_vec
+                .iter()
+                .any(|(xname_selected, _)| xname_selected.eq(xname))
+        });
+
+        exact_solution.node_hw_component_count_vec
+    } else if solver_options.min_moves {
+        min_moves_node_migration(
+            &user_defined_hw_component_count_hashmap,
+            &user_defined_hw_component_vec,
+            &mut target_parent_hsm_node_hw_component_count_vec,
+            &target_parent_hsm_density_score_hashmap,
+            hw_components_to_migrate_from_parent_hsm_to_target_hsm.clone(),
+            &hw_component_max_count_hashmap,
+            scoring_strategy,
+            &scoring_context,
+            progress_reporter,
+        )
+    } else {
+        upscale_node_migration(
+            &user_defined_hw_component_count_hashmap,
+            &user_defined_hw_component_vec,
+            &mut target_parent_hsm_node_hw_component_count_vec,
+            &target_parent_hsm_density_score_hashmap,
+            target_parent_hsm_score_tuple_vec,
+            hw_components_to_migrate_from_parent_hsm_to_target_hsm.clone(),
+            &hw_component_max_count_hashmap,
+            scoring_strategy,
+            &scoring_context,
+            progress_reporter,
+        )
+    };
+
+    // Back from blades to nodes
+    let mut selected_node_hw_component_count_vec = if solver_options.whole_blade {
+        target_parent_hsm_node_hw_component_count_vec = ungroup_blade_hw_component_count_vec(
+            &target_parent_hsm_node_hw_component_count_vec,
+            &candidate_node_hw_component_count_vec,
+        );
+
+        ungroup_blade_hw_component_count_vec(
+            &selected_node_hw_component_count_vec,
+            &candidate_node_hw_component_count_vec,
+        )
+    } else {
+        selected_node_hw_component_count_vec
+    };
+
+    let node_selection_explanation_vec_opt =
+        explain_candidate_node_vec_opt.map(|explain_candidate_node_vec| {
+            explain::explain_node_selection(
+                &selected_node_hw_component_count_vec,
+                &explain_candidate_node_vec,
+                &hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+                &target_parent_hsm_density_score_hashmap,
+                scoring_strategy,
+                &scoring_context,
+            )
+        });
+
+    selected_node_hw_component_count_vec.sort_by(|a, b| xname::compare_xnames(&a.0, &b.0));
+    target_parent_hsm_node_hw_component_count_vec.sort_by(|a, b| xname::compare_xnames(&a.0, &b.0));
+
+    Ok(Plan {
+        kept_node_hw_component_count_vec,
+        selected_node_hw_component_count_vec,
+        parent_hsm_node_hw_component_count_vec: target_parent_hsm_node_hw_component_count_vec,
+        hw_component_count_requested_hashmap: user_defined_hw_component_count_hashmap,
+        collective_hw_component_count_hashmap: target_parent_hsm_hw_component_count_hashmap,
+        unknown_hw_component_vec,
+        solver_report,
+        node_selection_explanation_vec_opt,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    pub async fn test_memory_capacity() {
+        // XDG Base Directory Specification
+        let project_dirs = directories::ProjectDirs::from(
+            "local", /*qualifier*/
+            "cscs",  /*organization*/
+            "manta", /*application*/
+        );
+
+        let mut path_to_manta_configuration_file =
+            std::path::PathBuf::from(project_dirs.unwrap().config_dir());
+
+        path_to_manta_configuration_file.push("config.toml"); // ~/.config/manta/config is the file
+
+        log::info!(
+            "Reading manta configuration from {}",
+            &path_to_manta_configuration_file.to_string_lossy()
+        );
+
+        let settings = crate::common::config_ops::get_configuration().unwrap();
+
+        let site_name = settings.get_string("site").unwrap();
+        let site_detail_hashmap = settings.get_table("sites").unwrap();
+        let site_detail_value = site_detail_hashmap
+            .get(&site_name)
+            .unwrap()
+            .clone()
+            .into_table()
+            .unwrap();
+
+        let shasta_base_url = site_detail_value
+            .get("shasta_base_url")
+            .unwrap()
+            .to_string();
+
+        let keycloak_base_url = site_detail_value
+            .get("keycloak_base_url")
+            .unwrap()
+            .to_string();
+
+        if let Some(socks_proxy) = site_detail_value.get("socks5_proxy") {
+            std::env::set_var("SOCKS5", socks_proxy.to_string());
+        }
+
+        let shasta_root_cert =
+            crate::common::config_ops::get_csm_root_cert_content(&site_name).unwrap();
+
+        let shasta_token =
+            crate::shasta::with_timeout(mesa::common::authentication::get_api_token(
+                &shasta_base_url,
+                &shasta_root_cert,
+                &keycloak_base_url,
+            ))
+            .await
+            .unwrap();
+
+        // Groups of each node in one call instead of scanning the members of every HSM group
+        let node_membership_hashmap = crate::common::membership_ops::get_node_membership_hashmap(
+            &shasta_token,
+            &shasta_base_url,
+            &shasta_root_cert,
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let mut node_hsm_groups_hw_inventory_map = HashMap::new();
+
+        let shasta_client =
+            crate::shasta::ShastaClient::new(&shasta_token, &shasta_base_url, &shasta_root_cert)
+                .unwrap();
+
+        for (member, node_membership) in &node_membership_hashmap {
+            if node_membership.hsm_groups.is_empty() {
+                continue;
+            }
+
+            println!(
+                "DEBUG - fetching hw components for node {} in hsm groups {:?}",
+                member, node_membership.hsm_groups
+            );
+            let hw_inventory =
+                get_node_hw_component_count(&shasta_client, member, Vec::new()).await;
+
+            node_hsm_groups_hw_inventory_map.insert(
+                member,
+                (
+                    node_membership
+                        .hsm_groups
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>(),
+                    hw_inventory.1,
+                    hw_inventory.2,
+                ),
+            );
+        }
+
+        println!("\n************************************\nDEBUG - HW COMPONENT SUMMARY:\n",);
+
+        let mut hsm_memory_capacity_gcd = 0;
+
+        for (node, hsm_groups_hw_inventory) in node_hsm_groups_hw_inventory_map {
+            let node_memory_capacity_gcd =
+                memory_ops::calculate_memory_gcd(&hsm_groups_hw_inventory.2);
+            hsm_memory_capacity_gcd =
+                memory_ops::gcd(hsm_memory_capacity_gcd, node_memory_capacity_gcd);
+            println!(
+                "DEBUG - node {} hwm groups {:?} hw inventory {:?} memory dimms capacity {:?} gcd {}",
+                node,
+                hsm_groups_hw_inventory.0,
+                hsm_groups_hw_inventory.1,
+                hsm_groups_hw_inventory.2,
+                node_memory_capacity_gcd
+            );
+        }
+
+        println!(
+            "Query GCD: {} ({})",
+            hsm_memory_capacity_gcd,
+            memory_ops::format_memory_size(hsm_memory_capacity_gcd * memory_ops::MIB)
+        );
+    }
+
+    #[test]
+    fn test_hsm_hw_management() {
+        // Hw component counters of the nodes in HSM groups 'zinal' and 'nodes_free', memory in MiB
+        let node = |xname: &str, hw_component_count_vec: &[(&str, usize)]| {
+            (
+                xname.to_string(),
+                hw_component_count_vec
+                    .iter()
+                    .map(|(hw_component, qty)| (hw_component.to_string(), *qty))
+                    .collect::<HashMap<String, usize>>(),
+            )
+        };
+
+        let hsm_zinal_hw_counters = vec![
+            node("x1001c1s5b0n0", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s5b0n1", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s5b1n0", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s5b1n1", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s6b0n0", &[("epyc", 2), ("memory", 245760)]),
+            node("x1001c1s6b0n1", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s6b1n0", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s6b1n1", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s7b0n0", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s7b0n1", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s7b1n0", &[("epyc", 2), ("memory", 262144)]),
+            node("x1001c1s7b1n1", &[("epyc", 2), ("memory", 262144)]),
+            node(
+                "x1005c0s4b0n0",
+                &[("a100", 4), ("epyc", 1), ("memory", 524288)],
+            ),
+            node(
+                "x1005c0s4b0n1",
+                &[("a100", 4), ("epyc", 1), ("memory", 524288)],
+            ),
+            node(
+                "x1006c1s4b0n0",
+                &[("instinct", 8), ("epyc", 1), ("memory", 524288)],
+            ),
+            node(
+                "x1006c1s4b1n0",
+                &[("instinct", 8), ("epyc", 1), ("memory", 524288)],
+            ),
+        ];
+
+        let hsm_nodes_free_hw_conters: Vec<(String, HashMap<String, usize>)> = [
+            "x1000c1s7b0n0",
+            "x1000c1s7b0n1",
+            "x1000c1s7b1n0",
+            "x1000c1s7b1n1",
+            "x1001c1s1b0n0",
+            "x1001c1s1b0n1",
+            "x1001c1s1b1n0",
+            "x1001c1s1b1n1",
+            "x1001c1s2b0n0",
+            "x1001c1s2b0n1",
+            "x1001c1s4b0n0",
+            "x1001c1s4b0n1",
+            "x1001c1s4b1n0",
+            "x1001c1s4b1n1",
+        ]
+        .iter()
+        .map(|xname| node(xname, &[("epyc", 2), ("memory", 262144)]))
+        .collect();
+
+        let scoring_strategy = scoring_strategy::get_scoring_strategy("density");
+
+        let mut solver_options = SolverOptions {
+            scoring_strategy: scoring_strategy.as_ref(),
+            min_moves: false,
+            exact_solver_max_nodes_opt: None,
+            whole_blade: false,
+            verbose: false,
+            explain: false,
+            on_progress_opt: None,
+        };
+
+        let calculate_plan = |pattern: &str,
+                              excluded_xname_vec: &[String],
+                              pinned_xname_vec: &[String],
+                              solver_options: &SolverOptions| {
+            calculate_plan(
+                hsm_zinal_hw_counters.clone(),
+                hsm_nodes_free_hw_conters.clone(),
+                &parse_pattern_with_qualifiers(pattern).unwrap().1,
+                excluded_xname_vec,
+                pinned_xname_vec,
+                solver_options,
+            )
+        };
+
+        // Greedy solver reaches the floors and every node ends up in one HSM group only
+        let plan = calculate_plan("zinal:a100:4:epyc:10", &[], &[], &solver_options).unwrap();
+
+        let target_hsm_hw_component_count_hashmap =
+            plan.get_target_hsm_hw_component_count_hashmap();
+
+        assert!(target_hsm_hw_component_count_hashmap["a100"] >= 4);
+        assert!(target_hsm_hw_component_count_hashmap["epyc"] >= 10);
+        assert_eq!(
+            plan.selected_node_hw_component_count_vec.len()
+                + plan.parent_hsm_node_hw_component_count_vec.len(),
+            30
+        );
+        assert!(plan
+            .selected_node_hw_component_count_vec
+            .iter()
+            .all(|(xname, _)| !plan
+                .parent_hsm_node_hw_component_count_vec
+                .iter()
+                .any(|(parent_xname, _)| parent_xname.eq(xname))));
+        assert_eq!(
+            plan.collective_hw_component_count_hashmap,
+            HashMap::from([
+                ("a100".to_string(), 8),
+                ("instinct".to_string(), 16),
+                ("epyc".to_string(), 56),
+                ("memory".to_string(), 8896512),
+            ])
+        );
+
+        // Density scores nodes like the original implementation before any node is selected: the
+        // instinct nodes can't be removed without violating the request so they score lowest and a
+        // node with 2 epyc is picked first, the plan is the same as before the scoring strategies
+        let plan = calculate_plan("zinal:instinct:16:epyc:2", &[], &[], &solver_options).unwrap();
+
+        let target_hsm_member_vec = plan.get_target_hsm_member_vec();
+
+        assert_eq!(target_hsm_member_vec.len(), 3);
+        assert!(target_hsm_member_vec.contains(&"x1006c1s4b0n0".to_string()));
+        assert!(target_hsm_member_vec.contains(&"x1006c1s4b1n0".to_string()));
+
+        let plan = calculate_plan("zinal:a100:4:epyc:10", &[], &[], &solver_options).unwrap();
+
+        assert_eq!(plan.selected_node_hw_component_count_vec.len(), 6);
+        assert_eq!(
+            plan.selected_node_hw_component_count_vec
+                .iter()
+                .filter(|(_, node_hw_component_count)| node_hw_component_count.contains_key("a100"))
+                .count(),
+            1
+        );
+
+        // Exact solver picks the minimum number of nodes, eg 1 node with a100 and 5 nodes with 2 epyc
+        solver_options.exact_solver_max_nodes_opt = Some(30);
+
+        let plan = calculate_plan("zinal:a100:4:epyc:10", &[], &[], &solver_options).unwrap();
+
+        assert_eq!(plan.solver_report, "exact (optimal)");
+        assert_eq!(plan.selected_node_hw_component_count_vec.len(), 6);
+        assert!(plan.get_target_hsm_hw_component_count_hashmap()["epyc"] >= 10);
+
+        // Excluded and pinned nodes end up in target HSM group and count towards the pattern
+        let plan = calculate_plan(
+            "zinal:instinct:8:epyc:4",
+            &["x1006c1s4b0n0".to_string()],
+            &["x1000c1s7b0n0".to_string()],
+            &solver_options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan.kept_node_hw_component_count_vec
+                .iter()
+                .map(|(xname, _)| xname.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["x1006c1s4b0n0", "x1000c1s7b0n0"]
+        );
+        assert_eq!(
+            plan.hw_component_count_requested_hashmap,
+            HashMap::from([("instinct".to_string(), 0), ("epyc".to_string(), 1)])
+        );
+        assert_eq!(plan.get_target_hsm_member_vec().len(), 3);
+
+        // Min-moves objective keeps current target HSM group members, no node is taken from the parent
+        // HSM group while target HSM group members provide the hw components requested
+        solver_options.min_moves = true;
+
+        let plan = calculate_plan("zinal:a100:4:epyc:10", &[], &[], &solver_options).unwrap();
+
+        assert!(plan
+            .selected_node_hw_component_count_vec
+            .iter()
+            .all(|(xname, _)| hsm_zinal_hw_counters
+                .iter()
+                .any(|(zinal_xname, _)| zinal_xname.eq(xname))));
+        assert!(plan.get_target_hsm_hw_component_count_hashmap()["epyc"] >= 10);
+
+        // Exact solver with min-moves objective, no node moved at all since 'zinal' already fulfills
+        // the pattern
+        solver_options.exact_solver_max_nodes_opt = Some(30);
+
+        let plan = calculate_plan("zinal:a100:4:epyc:10", &[], &[], &solver_options).unwrap();
+
+        assert!(plan
+            .selected_node_hw_component_count_vec
+            .iter()
+            .all(|(xname, _)| hsm_zinal_hw_counters
+                .iter()
+                .any(|(zinal_xname, _)| zinal_xname.eq(xname))));
+
+        solver_options.min_moves = false;
+
+        // Hw components not found in any node are ignored
+        let plan = calculate_plan("zinal:h100:4:epyc:2", &[], &[], &solver_options).unwrap();
+
+        assert_eq!(plan.unknown_hw_component_vec, vec!["h100"]);
+        assert!(!plan
+            .hw_component_count_requested_hashmap
+            .contains_key("h100"));
+
+        // Not enough hw components in target and parent HSM groups
+        assert!(calculate_plan("zinal:a100:12", &[], &[], &solver_options).is_err());
+    }
+
+    #[test]
+    fn test_subtract_hw_component_count() {
+        let mut user_defined_hw_component_count_hashmap =
+            HashMap::from([("a100".to_string(), 8), ("epyc".to_string(), 2)]);
+
+        subtract_hw_component_count(
+            &mut user_defined_hw_component_count_hashmap,
+            &[(
+                "x1001c1s5b0n0".to_string(),
+                HashMap::from([("a100".to_string(), 4), ("epyc".to_string(), 4)]),
+            )],
+        );
+
+        assert_eq!(
+            user_defined_hw_component_count_hashmap,
+            HashMap::from([("a100".to_string(), 4), ("epyc".to_string(), 0)])
+        );
+    }
+
+    #[test]
+    fn test_group_node_hw_component_count_vec_by_blade() {
+        let node_hw_component_count_vec = vec![
+            (
+                "x1001c1s5b0n0".to_string(),
+                HashMap::from([("a100".to_string(), 4)]),
+            ),
+            (
+                "x1001c1s5b1n0".to_string(),
+                HashMap::from([("epyc".to_string(), 2)]),
+            ),
+            (
+                "x1001c1s5b0n1".to_string(),
+                HashMap::from([("a100".to_string(), 4), ("epyc".to_string(), 1)]),
+            ),
+        ];
+
+        let blade_hw_component_count_vec =
+            group_node_hw_component_count_vec_by_blade(&node_hw_component_count_vec);
+
+        assert_eq!(
+            blade_hw_component_count_vec,
+            vec![
+                (
+                    "x1001c1s5b0".to_string(),
+                    HashMap::from([("a100".to_string(), 8), ("epyc".to_string(), 1)]),
+                ),
+                (
+                    "x1001c1s5b1".to_string(),
+                    HashMap::from([("epyc".to_string(), 2)]),
+                ),
+            ]
+        );
+
+        assert_eq!(
+            ungroup_blade_hw_component_count_vec(
+                &blade_hw_component_count_vec[..1],
+                &node_hw_component_count_vec
+            )
+            .into_iter()
+            .map(|(xname, _)| xname)
+            .collect::<Vec<String>>(),
+            vec!["x1001c1s5b0n0", "x1001c1s5b0n1"]
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_qualifiers() {
+        use QuantityQualifier;
+
+        let (target_hsm_group_name, hw_component_qualified_count_hashmap) =
+            parse_pattern_with_qualifiers("Zinal:a100>=4:epyc=30:instinct<=2:nvidia:8:!mi250")
+                .unwrap();
+
+        assert_eq!(target_hsm_group_name, "zinal");
+        assert_eq!(
+            hw_component_qualified_count_hashmap,
+            HashMap::from([
+                ("a100".to_string(), (4, QuantityQualifier::AtLeast)),
+                ("epyc".to_string(), (30, QuantityQualifier::Exact)),
+                ("instinct".to_string(), (2, QuantityQualifier::AtMost)),
+                ("nvidia".to_string(), (8, QuantityQualifier::AtLeast)),
+                ("mi250".to_string(), (0, QuantityQualifier::AtMost)),
+            ])
+        );
+
+        let (
+            hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+            hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+        ) = calculate_all_deltas(
+            &hw_component_qualified_count_hashmap,
+            &HashMap::from([
+                ("a100".to_string(), 8),
+                ("epyc".to_string(), 20),
+                ("instinct".to_string(), 4),
+                ("nvidia".to_string(), 8),
+            ]),
+        );
+
+        assert_eq!(
+            hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+            HashMap::from([("instinct".to_string(), -2)])
+        );
+        assert_eq!(
+            hw_components_to_migrate_from_parent_hsm_to_target_hsm,
+            HashMap::from([("epyc".to_string(), -10)])
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_with_errors() {
+        let (target_hsm_group_name, hw_component_qualified_count_hashmap, pattern_error_vec) =
+            parse_pattern_with_errors("zinal:a100:epyc:10:epyc>=2:instinct=x");
+
+        assert_eq!(target_hsm_group_name, "zinal");
+        assert_eq!(
+            hw_component_qualified_count_hashmap,
+            HashMap::from([("epyc".to_string(), (10, QuantityQualifier::AtLeast))])
+        );
+        assert_eq!(
+            pattern_error_vec
+                .iter()
+                .map(|pattern_error| (pattern_error.position, pattern_error.element.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(7, "a100"), (20, "epyc"), (37, "x")]
+        );
+
+        let (_, _, pattern_error_vec) = parse_pattern_with_errors(":a100:4");
+
+        assert_eq!(pattern_error_vec[0].position, 1);
+    }
+
+    #[test]
+    fn test_parse_pattern_with_qualifiers_edge_cases() {
+        // Examples in doc comments
+        assert!(parse_pattern_with_qualifiers("zinal:a100:4").is_ok());
+        assert!(parse_pattern_with_qualifiers("zinal:epyc:20:!a100").is_ok());
+        assert!(parse_pattern_with_qualifiers("tasna:a100:4:epyc:10:instinct:8").is_ok());
+        assert_eq!(
+            parse_pattern_with_qualifiers("zinal:memory:512gb")
+                .unwrap()
+                .1["memory"]
+                .0,
+            512 * 1024 * 1024 * 1024
+        );
+
+        // Memory used to be counted in DIMMs of 16 GiB, numbers without unit are rejected
+        assert!(parse_pattern_with_qualifiers("zinal:memory:512").is_err());
+
+        // Malformed patterns
+        for pattern in [
+            "",
+            ":a100:4",
+            "zinal:a100",
+            "zinal:a100:4:",
+            "zinal::4",
+            "zinal:a100:four",
+            "zinal:a100>=",
+            "zinal:a100:4:a100<=8",
+            "zinal:memory:512xb",
+            "zinal:@undefined_profile:4",
+        ] {
+            assert!(
+                parse_pattern_with_qualifiers(pattern).is_err(),
+                "pattern '{}' should not be valid",
+                pattern
+            );
+        }
+    }
+}
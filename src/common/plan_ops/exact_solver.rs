@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+/// Names accepted by `apply hsm-group --solver`
+pub const SOLVER_NAME_VEC: [&str; 2] = ["greedy", "exact"];
+
+/// Maximum number of search tree nodes visited, once reached the best solution found so far is
+/// returned and it is not guaranteed to be optimal
+const SEARCH_NODE_LIMIT: usize = 1_000_000;
+
+pub struct ExactSolution {
+    /// Nodes selected for the target HSM group
+    pub node_hw_component_count_vec: Vec<(String, HashMap<String, usize>)>,
+    /// Number of nodes (density objective) or number of node moves (min-moves objective)
+    pub cost: usize,
+    /// False if the search was stopped before exploring all node subsets
+    pub optimal: bool,
+}
+
+struct Candidate {
+    xname: String,
+    /// Quantity of each hw component requested by the user, same order as Search::requested_vec
+    hw_component_count_vec: Vec<usize>,
+    score: f32,
+    is_target_member: bool,
+}
+
+struct Search {
+    requested_vec: Vec<usize>,
+    candidate_vec: Vec<Candidate>,
+    /// Quantity of each hw component requested available in candidate_vec[index..]
+    remaining_supply_vec: Vec<Vec<usize>>,
+    /// Number of target HSM group members in candidate_vec[index..]
+    remaining_target_member_vec: Vec<usize>,
+    minimize_moves: bool,
+    best_opt: Option<(usize, f32, Vec<usize>)>,
+    visited: usize,
+}
+
+/// Returns the number of nodes the exact solver would need to explore, this is, the nodes with at
+/// least one of the hw components requested by the user
+pub fn count_candidate_nodes(
+    user_defined_hw_component_count_hashmap: &HashMap<String, usize>,
+    hsm_node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+) -> usize {
+    hsm_node_hw_component_count_vec
+        .iter()
+        .filter(|(_, node_hw_component_count_hashmap)| {
+            is_candidate(
+                user_defined_hw_component_count_hashmap,
+                node_hw_component_count_hashmap,
+            )
+        })
+        .count()
+}
+
+/// Branch and bound over the subsets of candidate nodes. Finds the subset fulfilling the user
+/// request with the lowest cost (number of nodes or number of moves if `minimize_moves`), ties
+/// are broken with the highest score. Subsets with nodes which can be removed without violating
+/// the user request are ignored. Returns None if the user request can't be fulfilled
+pub fn solve(
+    user_defined_hw_component_count_hashmap: &HashMap<String, usize>,
+    hsm_node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+    node_score_vec: &[(String, f32)],
+    target_hsm_member_vec: &[String],
+    minimize_moves: bool,
+) -> Option<ExactSolution> {
+    let mut hw_component_vec: Vec<&String> =
+        user_defined_hw_component_count_hashmap.keys().collect();
+    hw_component_vec.sort();
+
+    let requested_vec: Vec<usize> = hw_component_vec
+        .iter()
+        .map(|hw_component| user_defined_hw_component_count_hashmap[*hw_component])
+        .collect();
+
+    let mut candidate_vec: Vec<Candidate> = hsm_node_hw_component_count_vec
+        .iter()
+        .filter(|(_, node_hw_component_count_hashmap)| {
+            is_candidate(
+                user_defined_hw_component_count_hashmap,
+                node_hw_component_count_hashmap,
+            )
+        })
+        .map(|(xname, node_hw_component_count_hashmap)| Candidate {
+            xname: xname.clone(),
+            hw_component_count_vec: hw_component_vec
+                .iter()
+                .map(|hw_component| {
+                    *node_hw_component_count_hashmap
+                        .get(*hw_component)
+                        .unwrap_or(&0)
+                })
+                .collect(),
+            score: node_score_vec
+                .iter()
+                .find(|(node, _)| node.eq(xname))
+                .map(|(_, score)| *score)
+                .unwrap_or(0f32),
+            is_target_member: target_hsm_member_vec.contains(xname),
+        })
+        .collect();
+
+    // Best scored nodes first so a good solution is found early and prunes the search tree
+    candidate_vec.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut remaining_supply_vec = vec![vec![0; requested_vec.len()]; candidate_vec.len() + 1];
+    let mut remaining_target_member_vec = vec![0; candidate_vec.len() + 1];
+
+    for index in (0..candidate_vec.len()).rev() {
+        remaining_supply_vec[index] = remaining_supply_vec[index + 1]
+            .iter()
+            .zip(&candidate_vec[index].hw_component_count_vec)
+            .map(|(remaining_supply, hw_component_count)| remaining_supply + hw_component_count)
+            .collect();
+
+        remaining_target_member_vec[index] =
+            remaining_target_member_vec[index + 1] + candidate_vec[index].is_target_member as usize;
+    }
+
+    let mut search = Search {
+        requested_vec: requested_vec.clone(),
+        candidate_vec,
+        remaining_supply_vec,
+        remaining_target_member_vec,
+        minimize_moves,
+        best_opt: None,
+        visited: 0,
+    };
+
+    search.search(0, &requested_vec, 0, 0f32, &mut Vec::new());
+
+    let optimal = search.visited <= SEARCH_NODE_LIMIT;
+
+    search.best_opt.map(|(cost, _, selected_vec)| {
+        let node_hw_component_count_vec = selected_vec
+            .iter()
+            .map(|index| {
+                hsm_node_hw_component_count_vec
+                    .iter()
+                    .find(|(xname, _)| xname.eq(&search.candidate_vec[*index].xname))
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+
+        ExactSolution {
+            node_hw_component_count_vec,
+            cost,
+            optimal,
+        }
+    })
+}
+
+fn is_candidate(
+    user_defined_hw_component_count_hashmap: &HashMap<String, usize>,
+    node_hw_component_count_hashmap: &HashMap<String, usize>,
+) -> bool {
+    node_hw_component_count_hashmap
+        .keys()
+        .any(|hw_component| user_defined_hw_component_count_hashmap.contains_key(hw_component))
+}
+
+impl Search {
+    fn search(
+        &mut self,
+        index: usize,
+        missing_vec: &[usize],
+        cost: usize,
+        score: f32,
+        selected_vec: &mut Vec<usize>,
+    ) {
+        self.visited += 1;
+
+        if self.visited > SEARCH_NODE_LIMIT {
+            return;
+        }
+
+        if missing_vec.iter().all(|qty| *qty == 0) {
+            // User request fulfilled, the rest of candidates stay out of the target HSM group
+            let cost = if self.minimize_moves {
+                cost + self.remaining_target_member_vec[index]
+            } else {
+                cost
+            };
+
+            let is_better = match &self.best_opt {
+                None => true,
+                Some((best_cost, best_score, _)) => {
+                    cost < *best_cost || (cost == *best_cost && score > *best_score)
+                }
+            };
+
+            if is_better && self.is_minimal(selected_vec) {
+                self.best_opt = Some((cost, score, selected_vec.clone()));
+            }
+
+            return;
+        }
+
+        if index == self.candidate_vec.len() {
+            return;
+        }
+
+        // Bound: cost never decreases while going down the search tree
+        if let Some((best_cost, _, _)) = &self.best_opt {
+            if cost > *best_cost {
+                return;
+            }
+        }
+
+        // Bound: remaining candidates can't fulfill the user request
+        if missing_vec
+            .iter()
+            .zip(&self.remaining_supply_vec[index])
+            .any(|(qty_missing, qty_supply)| qty_supply < qty_missing)
+        {
+            return;
+        }
+
+        let is_target_member = self.candidate_vec[index].is_target_member;
+        let candidate_score = self.candidate_vec[index].score;
+        let new_missing_vec: Vec<usize> = missing_vec
+            .iter()
+            .zip(&self.candidate_vec[index].hw_component_count_vec)
+            .map(|(qty_missing, qty)| qty_missing.saturating_sub(*qty))
+            .collect();
+
+        // Branch: node goes to the target HSM group
+        let include_cost = if self.minimize_moves {
+            !is_target_member as usize
+        } else {
+            1
+        };
+
+        selected_vec.push(index);
+        self.search(
+            index + 1,
+            &new_missing_vec,
+            cost + include_cost,
+            score + candidate_score,
+            selected_vec,
+        );
+        selected_vec.pop();
+
+        // Branch: node stays out of the target HSM group
+        let exclude_cost = if self.minimize_moves {
+            is_target_member as usize
+        } else {
+            0
+        };
+
+        self.search(
+            index + 1,
+            missing_vec,
+            cost + exclude_cost,
+            score,
+            selected_vec,
+        );
+    }
+
+    /// Checks no selected node can be removed without violating the user request
+    fn is_minimal(&self, selected_vec: &[usize]) -> bool {
+        let supply_vec: Vec<usize> = (0..self.requested_vec.len())
+            .map(|hw_component_index| {
+                selected_vec
+                    .iter()
+                    .map(|index| {
+                        self.candidate_vec[*index].hw_component_count_vec[hw_component_index]
+                    })
+                    .sum()
+            })
+            .collect();
+
+        selected_vec.iter().all(|index| {
+            supply_vec
+                .iter()
+                .zip(&self.candidate_vec[*index].hw_component_count_vec)
+                .zip(&self.requested_vec)
+                .any(|((qty_supply, qty), qty_requested)| qty_supply - qty < *qty_requested)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_solver_finds_minimum_number_of_nodes() {
+        // Greedy picks the best scored node (x1000c0s0b0n0) first and then needs 2 more nodes to
+        // get the 3 a100 left, while 2 nodes with 2 a100 each are enough
+        let hsm_node_hw_component_count_vec = vec![
+            (
+                "x1000c0s0b0n0".to_string(),
+                HashMap::from([("a100".to_string(), 1)]),
+            ),
+            (
+                "x1000c0s0b0n1".to_string(),
+                HashMap::from([("a100".to_string(), 2)]),
+            ),
+            (
+                "x1000c0s0b1n0".to_string(),
+                HashMap::from([("a100".to_string(), 2)]),
+            ),
+            (
+                "x1000c0s0b1n1".to_string(),
+                HashMap::from([("epyc".to_string(), 2)]),
+            ),
+        ];
+        let node_score_vec = vec![
+            ("x1000c0s0b0n0".to_string(), 300f32),
+            ("x1000c0s0b0n1".to_string(), 200f32),
+            ("x1000c0s0b1n0".to_string(), 200f32),
+        ];
+
+        let exact_solution = solve(
+            &HashMap::from([("a100".to_string(), 4)]),
+            &hsm_node_hw_component_count_vec,
+            &node_score_vec,
+            &[],
+            false,
+        )
+        .unwrap();
+
+        let mut xname_vec: Vec<String> = exact_solution
+            .node_hw_component_count_vec
+            .into_iter()
+            .map(|(xname, _)| xname)
+            .collect();
+        xname_vec.sort();
+
+        assert!(exact_solution.optimal);
+        assert_eq!(exact_solution.cost, 2);
+        assert_eq!(xname_vec, vec!["x1000c0s0b0n1", "x1000c0s0b1n0"]);
+    }
+
+    #[test]
+    fn test_exact_solver_min_moves_and_infeasible_request() {
+        let hsm_node_hw_component_count_vec = vec![
+            (
+                "x1000c0s0b0n0".to_string(),
+                HashMap::from([("a100".to_string(), 4)]),
+            ),
+            (
+                "x1000c0s0b0n1".to_string(),
+                HashMap::from([("a100".to_string(), 4)]),
+            ),
+        ];
+        let target_hsm_member_vec = vec!["x1000c0s0b0n1".to_string()];
+
+        let exact_solution = solve(
+            &HashMap::from([("a100".to_string(), 4)]),
+            &hsm_node_hw_component_count_vec,
+            &[("x1000c0s0b0n0".to_string(), 100f32)],
+            &target_hsm_member_vec,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(exact_solution.cost, 0);
+        assert_eq!(
+            exact_solution.node_hw_component_count_vec[0].0,
+            "x1000c0s0b0n1"
+        );
+
+        assert!(solve(
+            &HashMap::from([("a100".to_string(), 16)]),
+            &hsm_node_hw_component_count_vec,
+            &[],
+            &target_hsm_member_vec,
+            true,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_exact_solver_nan_score() {
+        // Scores are f32 ratios, a NaN score must not panic while sorting candidates
+        let hsm_node_hw_component_count_vec = vec![
+            (
+                "x1000c0s0b0n0".to_string(),
+                HashMap::from([("a100".to_string(), 4)]),
+            ),
+            (
+                "x1000c0s0b0n1".to_string(),
+                HashMap::from([("a100".to_string(), 4)]),
+            ),
+        ];
+        let node_score_vec = vec![
+            ("x1000c0s0b0n0".to_string(), f32::NAN),
+            ("x1000c0s0b0n1".to_string(), 100f32),
+        ];
+
+        let exact_solution = solve(
+            &HashMap::from([("a100".to_string(), 4)]),
+            &hsm_node_hw_component_count_vec,
+            &node_score_vec,
+            &[],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(exact_solution.cost, 1);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::common::format_ops::format_score;
+
+use super::{
+    scoring_strategy::{ScoringContext, ScoringStrategy},
+    update_user_defined_hw_component_counters,
+};
+
+/// Number of rejected alternatives listed for each node selected
+const NUM_ALTERNATIVES: usize = 3;
+
+/// Why a node was selected to be moved to the target HSM group
+pub struct NodeSelectionExplanation {
+    pub xname: String,
+    /// Score given by the scoring strategy when the node was selected
+    pub score: f32,
+    /// Position of the node among the candidates ordered by score (1 is the best)
+    pub rank: usize,
+    pub density_score_opt: Option<usize>,
+    /// Percentage of each hw component of the node within the collective HSM groups
+    pub hw_component_normalized_score_vec: Vec<(String, f32)>,
+    /// Quantity of each hw component still missing provided by the node
+    pub contributed_hw_component_vec: Vec<(String, usize)>,
+    /// Best candidates not selected in this step with their score and the reason
+    pub rejected_alternative_vec: Vec<(String, f32, String)>,
+}
+
+/// Replays the node selection, in the same order nodes were selected, scoring the candidates left
+/// in each step with the scoring strategy to explain why each node was chosen over the rest
+pub fn explain_node_selection(
+    selected_node_vec: &[(String, HashMap<String, usize>)],
+    candidate_node_vec: &[(String, HashMap<String, usize>)],
+    hw_components_to_migrate: &HashMap<String, isize>,
+    density_score_hashmap: &HashMap<String, usize>,
+    scoring_strategy: &dyn ScoringStrategy,
+    scoring_context: &ScoringContext,
+) -> Vec<NodeSelectionExplanation> {
+    let mut candidate_node_vec = candidate_node_vec.to_vec();
+    let mut hw_components_to_migrate = hw_components_to_migrate.clone();
+    let mut selected_xname_vec: Vec<String> = Vec::new();
+    let mut node_selection_explanation_vec = Vec::new();
+
+    for (xname, node_hw_component_count_hashmap) in selected_node_vec {
+        let mut node_score_vec = scoring_strategy.calculate_node_score_vec(
+            &candidate_node_vec,
+            &hw_components_to_migrate,
+            &selected_xname_vec,
+            scoring_context,
+        );
+
+        node_score_vec.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let rank = node_score_vec
+            .iter()
+            .position(|(node, _)| node.eq(xname))
+            .map(|position| position + 1)
+            .unwrap_or(0);
+
+        let score = node_score_vec
+            .iter()
+            .find(|(node, _)| node.eq(xname))
+            .map(|(_, score)| *score)
+            .unwrap_or(0f32);
+
+        let mut hw_component_normalized_score_vec: Vec<(String, f32)> =
+            node_hw_component_count_hashmap
+                .keys()
+                .map(|hw_component| {
+                    (
+                        hw_component.clone(),
+                        *scoring_context
+                            .hw_component_normalized_scores_hashmap
+                            .get(hw_component)
+                            .unwrap_or(&0f32),
+                    )
+                })
+                .collect();
+        hw_component_normalized_score_vec.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut contributed_hw_component_vec: Vec<(String, usize)> =
+            node_hw_component_count_hashmap
+                .iter()
+                .filter_map(|(hw_component, qty)| {
+                    hw_components_to_migrate
+                        .get(hw_component)
+                        .map(|qty_missing| {
+                            (hw_component.clone(), (*qty).min(qty_missing.unsigned_abs()))
+                        })
+                })
+                .filter(|(_, qty)| *qty > 0)
+                .collect();
+        contributed_hw_component_vec.sort();
+
+        let rejected_alternative_vec = node_score_vec
+            .iter()
+            .filter(|(node, _)| !node.eq(xname))
+            .take(NUM_ALTERNATIVES)
+            .map(|(node, node_score)| {
+                let provides_missing_hw_component = candidate_node_vec
+                    .iter()
+                    .find(|(candidate, _)| candidate.eq(node))
+                    .is_some_and(|(_, candidate_hw_component_count_hashmap)| {
+                        candidate_hw_component_count_hashmap
+                            .keys()
+                            .any(|hw_component| hw_components_to_migrate.contains_key(hw_component))
+                    });
+
+                let reason = if !provides_missing_hw_component {
+                    "does not provide any hw component still missing".to_string()
+                } else if *node_score < score {
+                    format!(
+                        "lower score ({} < {})",
+                        format_score(*node_score),
+                        format_score(score)
+                    )
+                } else if *node_score > score {
+                    "higher score but not part of the solution found by the solver".to_string()
+                } else {
+                    "same score, first candidate found was selected".to_string()
+                };
+
+                (node.clone(), *node_score, reason)
+            })
+            .collect();
+
+        node_selection_explanation_vec.push(NodeSelectionExplanation {
+            xname: xname.clone(),
+            score,
+            rank,
+            density_score_opt: density_score_hashmap.get(xname).copied(),
+            hw_component_normalized_score_vec,
+            contributed_hw_component_vec,
+            rejected_alternative_vec,
+        });
+
+        // Apply selection
+        hw_components_to_migrate = update_user_defined_hw_component_counters(
+            &hw_components_to_migrate,
+            node_hw_component_count_hashmap,
+        );
+        candidate_node_vec.retain(|(node, _)| !node.eq(xname));
+        selected_xname_vec.push(xname.clone());
+    }
+
+    node_selection_explanation_vec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_node_selection() {
+        let candidate_node_vec = vec![
+            (
+                "x1000c0s0b0n0".to_string(),
+                HashMap::from([("a100".to_string(), 4)]),
+            ),
+            (
+                "x1000c0s0b0n1".to_string(),
+                HashMap::from([("epyc".to_string(), 2)]),
+            ),
+        ];
+        let hw_component_normalized_scores_hashmap =
+            HashMap::from([("a100".to_string(), 50f32), ("epyc".to_string(), 50f32)]);
+        let scoring_context = ScoringContext {
+            hw_component_normalized_scores_hashmap: &hw_component_normalized_scores_hashmap,
+            hw_component_count_hashmap: &HashMap::new(),
+            hw_component_count_requested_hashmap: &HashMap::new(),
+            hw_components_to_migrate_from_target_hsm_to_parent_hsm: &HashMap::new(),
+            target_hsm_member_vec: &[],
+        };
+
+        let node_selection_explanation_vec = explain_node_selection(
+            &candidate_node_vec[..1],
+            &candidate_node_vec,
+            &HashMap::from([("a100".to_string(), -4)]),
+            &HashMap::new(),
+            super::super::scoring_strategy::get_scoring_strategy("scarcity").as_ref(),
+            &scoring_context,
+        );
+
+        let node_selection_explanation = &node_selection_explanation_vec[0];
+
+        assert_eq!(node_selection_explanation.rank, 1);
+        assert_eq!(
+            node_selection_explanation.contributed_hw_component_vec,
+            vec![("a100".to_string(), 4)]
+        );
+        assert_eq!(
+            node_selection_explanation.rejected_alternative_vec[0].2,
+            "does not provide any hw component still missing"
+        );
+    }
+}
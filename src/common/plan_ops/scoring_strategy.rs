@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use super::{
+    calculate_hsm_hw_component_normalized_node_density_score_downscale,
+    calculate_hsm_hw_component_normalized_node_density_score_upscale, calculate_xname_distance,
+};
+
+/// Names accepted by `apply hsm-group --strategy`
+/// Keeping current target HSM group members is a solver objective ('min-moves'), not a strategy
+pub const SCORING_STRATEGY_NAME_VEC: [&str; 3] = ["density", "scarcity", "locality"];
+
+/// Data shared by all scoring strategies which does not change while nodes are migrated
+pub struct ScoringContext<'a> {
+    /// Percentage of each hw component within the collective (target + parent) HSM groups
+    pub hw_component_normalized_scores_hashmap: &'a HashMap<String, f32>,
+    /// Number of each hw component within the collective (target + parent) HSM groups
+    pub hw_component_count_hashmap: &'a HashMap<String, usize>,
+    /// Quantity of each hw component requested by the user
+    pub hw_component_count_requested_hashmap: &'a HashMap<String, usize>,
+    /// Hw components the collective (target + parent) HSM groups have above the user request
+    /// (negative counters)
+    pub hw_components_to_migrate_from_target_hsm_to_parent_hsm: &'a HashMap<String, isize>,
+    /// Members of the target HSM group before the migration
+    pub target_hsm_member_vec: &'a [String],
+}
+
+/// Heuristic used to select the next node to migrate to the target HSM group. The node with the
+/// highest score is the best candidate
+pub trait ScoringStrategy {
+    fn name(&self) -> &str;
+
+    /// Returns the score of each node in `hsm_node_hw_component_count_vec`.
+    /// `hw_components_to_migrate` contains the hw components (negative counters) still missing in
+    /// the target HSM group and `selected_node_vec` the nodes already migrated
+    fn calculate_node_score_vec(
+        &self,
+        hsm_node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+        hw_components_to_migrate: &HashMap<String, isize>,
+        selected_node_vec: &[String],
+        scoring_context: &ScoringContext,
+    ) -> Vec<(String, f32)>;
+}
+
+/// Returns the scoring strategy for a name in SCORING_STRATEGY_NAME_VEC, defaults to density
+pub fn get_scoring_strategy(scoring_strategy_name: &str) -> Box<dyn ScoringStrategy> {
+    match scoring_strategy_name {
+        "scarcity" => Box::new(ScarcityScoringStrategy),
+        "locality" => Box::new(LocalityScoringStrategy),
+        _ => Box::new(DensityScoringStrategy),
+    }
+}
+
+/// Original heuristic. Each hw component in the node adds (if requested) or subtracts (if not
+/// requested) 100 minus its percentage in the collective HSM group, quantities are ignored.
+/// Before any node is selected, hw components are scored against the ones the collective HSM group
+/// has above the user request and nodes which can't be removed without violating the user request
+/// get the lowest score
+pub struct DensityScoringStrategy;
+
+impl ScoringStrategy for DensityScoringStrategy {
+    fn name(&self) -> &str {
+        "density"
+    }
+
+    fn calculate_node_score_vec(
+        &self,
+        hsm_node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+        hw_components_to_migrate: &HashMap<String, isize>,
+        selected_node_vec: &[String],
+        scoring_context: &ScoringContext,
+    ) -> Vec<(String, f32)> {
+        if selected_node_vec.is_empty() {
+            return calculate_hsm_hw_component_normalized_node_density_score_downscale(
+                hsm_node_hw_component_count_vec,
+                scoring_context.hw_components_to_migrate_from_target_hsm_to_parent_hsm,
+                scoring_context.hw_component_count_requested_hashmap,
+                scoring_context.hw_component_normalized_scores_hashmap,
+                scoring_context.hw_component_count_hashmap,
+            );
+        }
+
+        calculate_hsm_hw_component_normalized_node_density_score_upscale(
+            hsm_node_hw_component_count_vec,
+            hw_components_to_migrate,
+            scoring_context.hw_component_normalized_scores_hashmap,
+        )
+    }
+}
+
+/// Quantity aware heuristic. Rewards the percentage of the remaining request a node covers and
+/// penalizes the percentage of the collective supply of each hw component the node would waste,
+/// so nodes with scarce hw components nobody asked for are kept in the pool
+pub struct ScarcityScoringStrategy;
+
+impl ScoringStrategy for ScarcityScoringStrategy {
+    fn name(&self) -> &str {
+        "scarcity"
+    }
+
+    fn calculate_node_score_vec(
+        &self,
+        hsm_node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+        hw_components_to_migrate: &HashMap<String, isize>,
+        _selected_node_vec: &[String],
+        scoring_context: &ScoringContext,
+    ) -> Vec<(String, f32)> {
+        hsm_node_hw_component_count_vec
+            .iter()
+            .map(|(xname, node_hw_component_count_hashmap)| {
+                let mut node_score = 0f32;
+
+                for (hw_component, qty) in node_hw_component_count_hashmap {
+                    let qty_missing = hw_components_to_migrate
+                        .get(hw_component)
+                        .map(|qty_missing| qty_missing.unsigned_abs())
+                        .unwrap_or(0);
+
+                    let qty_useful = (*qty).min(qty_missing);
+                    let qty_wasted = qty - qty_useful;
+
+                    if qty_useful > 0 {
+                        node_score += 100f32 * qty_useful as f32 / qty_missing as f32;
+                    }
+
+                    if qty_wasted > 0 {
+                        let qty_supply = scoring_context
+                            .hw_component_count_hashmap
+                            .get(hw_component)
+                            .copied()
+                            .unwrap_or(*qty)
+                            .max(1);
+
+                        node_score -= 100f32 * qty_wasted as f32 / qty_supply as f32;
+                    }
+                }
+
+                (xname.to_string(), node_score)
+            })
+            .collect()
+    }
+}
+
+/// Density heuristic penalized by the average xname distance to the nodes already selected (or to
+/// the current target HSM group members before any node is selected), so the target HSM group
+/// ends up as compact as possible
+pub struct LocalityScoringStrategy;
+
+impl ScoringStrategy for LocalityScoringStrategy {
+    fn name(&self) -> &str {
+        "locality"
+    }
+
+    fn calculate_node_score_vec(
+        &self,
+        hsm_node_hw_component_count_vec: &[(String, HashMap<String, usize>)],
+        hw_components_to_migrate: &HashMap<String, isize>,
+        selected_node_vec: &[String],
+        scoring_context: &ScoringContext,
+    ) -> Vec<(String, f32)> {
+        let anchor_node_vec = if selected_node_vec.is_empty() {
+            scoring_context.target_hsm_member_vec
+        } else {
+            selected_node_vec
+        };
+
+        DensityScoringStrategy
+            .calculate_node_score_vec(
+                hsm_node_hw_component_count_vec,
+                hw_components_to_migrate,
+                selected_node_vec,
+                scoring_context,
+            )
+            .into_iter()
+            .map(|(xname, node_score)| {
+                if anchor_node_vec.is_empty() {
+                    return (xname, node_score);
+                }
+
+                let distance_avg = anchor_node_vec
+                    .iter()
+                    .map(|anchor_node| calculate_xname_distance(&xname, anchor_node))
+                    .sum::<usize>() as f32
+                    / anchor_node_vec.len() as f32;
+
+                // distance goes from 0 (same blade) to 4 (different rack)
+                (xname, node_score - 25f32 * distance_avg)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_best_node(node_score_vec: Vec<(String, f32)>) -> String {
+        node_score_vec
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_scarcity_keeps_scarce_hw_components_in_pool() {
+        // Both nodes provide the epyc requested but x1000c0s0b0n1 also has the only a100 in the pool
+        let hsm_node_hw_component_count_vec = vec![
+            (
+                "x1000c0s0b0n0".to_string(),
+                HashMap::from([("epyc".to_string(), 2)]),
+            ),
+            (
+                "x1000c0s0b0n1".to_string(),
+                HashMap::from([("epyc".to_string(), 2), ("a100".to_string(), 4)]),
+            ),
+        ];
+        let hw_component_count_hashmap =
+            HashMap::from([("epyc".to_string(), 4), ("a100".to_string(), 4)]);
+        let scoring_context = ScoringContext {
+            hw_component_normalized_scores_hashmap: &HashMap::new(),
+            hw_component_count_hashmap: &hw_component_count_hashmap,
+            hw_component_count_requested_hashmap: &HashMap::new(),
+            hw_components_to_migrate_from_target_hsm_to_parent_hsm: &HashMap::new(),
+            target_hsm_member_vec: &[],
+        };
+
+        let node_score_vec = ScarcityScoringStrategy.calculate_node_score_vec(
+            &hsm_node_hw_component_count_vec,
+            &HashMap::from([("epyc".to_string(), -2)]),
+            &[],
+            &scoring_context,
+        );
+
+        assert_eq!(get_best_node(node_score_vec), "x1000c0s0b0n0");
+    }
+}
@@ -0,0 +1,183 @@
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use crate::{
+    common::{csm_version_ops, node_migration_ops::NodeMove},
+    shasta::pcs,
+};
+
+/// Seconds between polls of a PCS power transition
+const POWER_TRANSITION_POLL_SECS: u64 = 10;
+
+/// PCS transition status once the transition finished
+const POWER_TRANSITION_FINISHED_STATUS_VEC: [&str; 2] = ["completed", "aborted"];
+
+/// xname -> power state ('on', 'off' or 'undefined') using PCS
+pub async fn get_power_state_hashmap(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    if xname_vec.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    if !csm_version_ops::is_pcs_available() {
+        return Err(format!(
+            "PCS not available in CSM {}",
+            csm_version_ops::get_csm_version().unwrap()
+        )
+        .into());
+    }
+
+    let power_status_value = pcs::http_client::post_power_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        xname_vec,
+    )
+    .await?;
+
+    Ok(pcs::utils::get_power_state_hashmap_from_power_status_value(
+        &power_status_value,
+    ))
+}
+
+/// Same as `get_power_state_hashmap` but artifact tables are still printed if PCS fails, nodes
+/// have no power state then
+pub async fn get_power_state_hashmap_or_empty(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_vec: &[String],
+) -> HashMap<String, String> {
+    get_power_state_hashmap(shasta_token, shasta_base_url, shasta_root_cert, xname_vec)
+        .await
+        .unwrap_or_else(|error| {
+            log::warn!("Could not get power state from PCS: {}", error);
+            HashMap::new()
+        })
+}
+
+/// Nodes moved which are not powered off (power state 'on' or unknown)
+pub async fn get_xname_moved_not_off_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let xname_vec = get_xname_vec(node_move_vec);
+
+    let power_state_hashmap =
+        get_power_state_hashmap(shasta_token, shasta_base_url, shasta_root_cert, &xname_vec)
+            .await?;
+
+    Ok(xname_vec
+        .into_iter()
+        .filter(|xname| {
+            power_state_hashmap
+                .get(xname)
+                .map_or(true, |power_state| power_state.ne("off"))
+        })
+        .collect())
+}
+
+/// Powers off the nodes ('soft-off' PCS transition) and waits until the transition finishes
+pub async fn power_off_xname_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    xname_on_vec: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let transition_value = pcs::http_client::post_transition(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "soft-off",
+        xname_on_vec,
+    )
+    .await?;
+
+    let transition_id = transition_value["transitionID"]
+        .as_str()
+        .ok_or("PCS transition without transitionID")?
+        .to_string();
+
+    log::info!("PCS transition '{}' created", transition_id);
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(POWER_TRANSITION_POLL_SECS)).await;
+
+        let transition_value = pcs::http_client::get_transition(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            &transition_id,
+        )
+        .await?;
+
+        let transition_status = transition_value["transitionStatus"]
+            .as_str()
+            .unwrap_or("unknown");
+
+        log::info!(
+            "PCS transition '{}' status: {}",
+            transition_id,
+            transition_status
+        );
+
+        if !POWER_TRANSITION_FINISHED_STATUS_VEC.contains(&transition_status) {
+            continue;
+        }
+
+        let failed_task_vec: Vec<String> = transition_value["tasks"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .filter(|task| {
+                task["taskStatus"]
+                    .as_str()
+                    .is_some_and(|status| status.eq("failed"))
+            })
+            .map(|task| {
+                format!(
+                    "{} ({})",
+                    task["xname"].as_str().unwrap_or_default(),
+                    task["error"].as_str().unwrap_or_default()
+                )
+            })
+            .collect();
+
+        if transition_status.eq("aborted") || !failed_task_vec.is_empty() {
+            return Err(format!(
+                "PCS transition '{}' {}, nodes failed: {:?}",
+                transition_id, transition_status, failed_task_vec
+            )
+            .into());
+        }
+
+        return Ok(());
+    }
+}
+
+/// Table cell with the power state, green if on and red if off
+pub fn get_power_state_cell(power_state_opt: Option<&String>) -> comfy_table::Cell {
+    match power_state_opt.map(|power_state| power_state.as_str()) {
+        Some("on") => comfy_table::Cell::new("on").fg(comfy_table::Color::Green),
+        Some("off") => comfy_table::Cell::new("off").fg(comfy_table::Color::Red),
+        Some(power_state) => comfy_table::Cell::new(power_state).fg(comfy_table::Color::Yellow),
+        None => comfy_table::Cell::new("n/a"),
+    }
+    .set_alignment(comfy_table::CellAlignment::Center)
+}
+
+fn get_xname_vec(node_move_vec: &[NodeMove]) -> Vec<String> {
+    let mut xname_vec: Vec<String> = node_move_vec
+        .iter()
+        .map(|node_move| node_move.xname.clone())
+        .collect();
+    xname_vec.sort();
+    xname_vec.dedup();
+
+    xname_vec
+}
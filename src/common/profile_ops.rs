@@ -0,0 +1,63 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+/// Hw profiles defined in the `[profiles]` table of the configuration file, profile name -> hw
+/// components eg `gpu_node = "a100:epyc"`
+static PROFILE_HASHMAP: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Sets the hw profiles for the rest of the execution
+pub fn configure(profile_hashmap: HashMap<String, String>) {
+    let _ = PROFILE_HASHMAP.set(profile_hashmap);
+}
+
+pub fn get_profile_hashmap_from_config(settings: &config::Config) -> HashMap<String, String> {
+    settings
+        .get_table("profiles")
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(profile_name, hw_components)| {
+            Some((profile_name, hw_components.into_string().ok()?))
+        })
+        .collect()
+}
+
+/// Hw components of a profile referenced in a pattern as `@<profile name>`, None if the profile
+/// is not defined
+pub fn get_profile_hw_component_vec(profile_name: &str) -> Option<Vec<String>> {
+    get_profile_hw_component_vec_from(PROFILE_HASHMAP.get()?, profile_name)
+}
+
+pub fn get_profile_hw_component_vec_from(
+    profile_hashmap: &HashMap<String, String>,
+    profile_name: &str,
+) -> Option<Vec<String>> {
+    profile_hashmap
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(profile_name))
+        .map(|(_, hw_components)| {
+            hw_components
+                .to_lowercase()
+                .split(':')
+                .map(|hw_component| hw_component.trim().to_string())
+                .filter(|hw_component| !hw_component.is_empty())
+                .collect()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_profile_hw_component_vec_from() {
+        let profile_hashmap = HashMap::from([("gpu_node".to_string(), "A100:epyc".to_string())]);
+
+        assert_eq!(
+            get_profile_hw_component_vec_from(&profile_hashmap, "gpu_node"),
+            Some(vec!["a100".to_string(), "epyc".to_string()])
+        );
+        assert_eq!(
+            get_profile_hw_component_vec_from(&profile_hashmap, "cpu_node"),
+            None
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::common::{
+    artifact_ops::{ArtifactSummary, NodeSummary},
+    firmware_ops::FirmwareVersion,
+    format_ops::format_count,
+    xname,
+};
+
+/// Report output formats, rendered from the same tables so both carry the same data
+pub const REPORT_FORMAT_NAME_VEC: [&str; 2] = ["markdown", "html"];
+
+/// Table of a report, cells are plain text and escaped when rendered
+pub struct ReportTable {
+    pub title: String,
+    pub header_vec: Vec<String>,
+    pub row_vec: Vec<Vec<String>>,
+}
+
+/// Document meant to be pasted in change tickets and wikis
+pub struct Report {
+    pub title: String,
+    pub description: String,
+    pub table_vec: Vec<ReportTable>,
+}
+
+impl Report {
+    /// Renders the report in one of REPORT_FORMAT_NAME_VEC, markdown if the format is not known
+    pub fn render(&self, format: &str) -> String {
+        match format {
+            "html" => self.to_html(),
+            _ => self.to_markdown(),
+        }
+    }
+
+    /// GitHub flavoured markdown
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n{}\n", self.title, self.description);
+
+        for table in &self.table_vec {
+            markdown.push_str(&format!("\n## {}\n\n", table.title));
+            markdown.push_str(&format!(
+                "| {} |\n",
+                table
+                    .header_vec
+                    .iter()
+                    .map(|header| escape_markdown(header))
+                    .collect::<Vec<String>>()
+                    .join(" | ")
+            ));
+            markdown.push_str(&format!("|{}\n", " --- |".repeat(table.header_vec.len())));
+
+            for row in &table.row_vec {
+                markdown.push_str(&format!(
+                    "| {} |\n",
+                    row.iter()
+                        .map(|cell| escape_markdown(cell))
+                        .collect::<Vec<String>>()
+                        .join(" | ")
+                ));
+            }
+        }
+
+        markdown
+    }
+
+    /// Standalone HTML document. Each table has the id of its title (eg `summary`) so its data
+    /// can be picked by charting tools
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}</p>\n",
+            escape_html(&self.title),
+            escape_html(&self.title),
+            escape_html(&self.description)
+        );
+
+        for table in &self.table_vec {
+            html.push_str(&format!("<h2>{}</h2>\n", escape_html(&table.title)));
+            html.push_str(&format!(
+                "<table id=\"{}\">\n<thead>\n<tr>{}</tr>\n</thead>\n<tbody>\n",
+                table.title.to_lowercase().replace(' ', "-"),
+                table
+                    .header_vec
+                    .iter()
+                    .map(|header| format!("<th>{}</th>", escape_html(header)))
+                    .collect::<String>()
+            ));
+
+            for row in &table.row_vec {
+                html.push_str(&format!(
+                    "<tr>{}</tr>\n",
+                    row.iter()
+                        .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                        .collect::<String>()
+                ));
+            }
+
+            html.push_str("</tbody>\n</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+
+        html
+    }
+}
+
+/// Report of the hw artifacts of a HSM group (or partition), `scope` eg "HSM group 'zinal'":
+/// quantity of each hw component in the HSM group (aggregate data ready for charts), hw
+/// components per node and firmware versions if any
+pub fn get_hsm_artifacts_report(
+    scope: &str,
+    node_summary_vec: &[NodeSummary],
+    power_state_hashmap: &HashMap<String, String>,
+    firmware_version_vec: &[FirmwareVersion],
+) -> Report {
+    let mut node_summary_sorted_vec: Vec<&NodeSummary> = node_summary_vec.iter().collect();
+    node_summary_sorted_vec.sort_by(|a, b| xname::compare_xnames(&a.xname, &b.xname));
+
+    // (type, hw component) -> (quantity, nodes)
+    let mut hw_component_summary_hashmap: BTreeMap<(&str, String), (usize, usize)> =
+        BTreeMap::new();
+
+    let mut node_row_vec = Vec::new();
+
+    for node_summary in &node_summary_sorted_vec {
+        let mut node_row = vec![
+            node_summary.xname.clone(),
+            power_state_hashmap
+                .get(&node_summary.xname)
+                .cloned()
+                .unwrap_or("n/a".to_string()),
+            node_summary.get_component_status_cell().content(),
+        ];
+
+        for (hw_component_type, artifact_summary_vec) in [
+            ("Processor", &node_summary.processors),
+            ("Accelerator", &node_summary.node_accels),
+            ("Memory", &node_summary.memory),
+            ("HSN NIC", &node_summary.node_hsn_nics),
+        ] {
+            let hw_component_count_hashmap = get_hw_component_count_hashmap(artifact_summary_vec);
+
+            for (hw_component, qty) in &hw_component_count_hashmap {
+                let hw_component_summary = hw_component_summary_hashmap
+                    .entry((hw_component_type, hw_component.clone()))
+                    .or_default();
+                hw_component_summary.0 += qty;
+                hw_component_summary.1 += 1;
+            }
+
+            node_row.push(
+                hw_component_count_hashmap
+                    .iter()
+                    .map(|(hw_component, qty)| format!("{} x{}", hw_component, format_count(qty)))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            );
+        }
+
+        node_row_vec.push(node_row);
+    }
+
+    let mut table_vec = vec![
+        ReportTable {
+            title: "Summary".to_string(),
+            header_vec: vec![
+                "Type".to_string(),
+                "Hw component".to_string(),
+                "Quantity".to_string(),
+                "Nodes".to_string(),
+            ],
+            row_vec: hw_component_summary_hashmap
+                .into_iter()
+                .map(|((hw_component_type, hw_component), (qty, node_count))| {
+                    vec![
+                        hw_component_type.to_string(),
+                        hw_component,
+                        format_count(qty),
+                        format_count(node_count),
+                    ]
+                })
+                .collect(),
+        },
+        ReportTable {
+            title: "Nodes".to_string(),
+            header_vec: vec![
+                "Node".to_string(),
+                "Power".to_string(),
+                "State".to_string(),
+                "Processors".to_string(),
+                "Accelerators".to_string(),
+                "Memory".to_string(),
+                "HSN NICs".to_string(),
+            ],
+            row_vec: node_row_vec,
+        },
+    ];
+
+    if !firmware_version_vec.is_empty() {
+        table_vec.push(ReportTable {
+            title: "Firmware".to_string(),
+            header_vec: vec![
+                "Node".to_string(),
+                "Firmware target".to_string(),
+                "Version".to_string(),
+            ],
+            row_vec: firmware_version_vec
+                .iter()
+                .map(|firmware_version| {
+                    vec![
+                        firmware_version.xname.clone(),
+                        firmware_version.target.clone(),
+                        firmware_version.version.clone(),
+                    ]
+                })
+                .collect(),
+        });
+    }
+
+    Report {
+        title: format!("Hw artifacts of {}", scope),
+        description: format!("{} nodes", format_count(node_summary_vec.len())),
+        table_vec,
+    }
+}
+
+fn get_hw_component_count_hashmap(
+    artifact_summary_vec: &[ArtifactSummary],
+) -> BTreeMap<String, usize> {
+    let mut hw_component_count_hashmap = BTreeMap::new();
+
+    for artifact_summary in artifact_summary_vec {
+        *hw_component_count_hashmap
+            .entry(artifact_summary.info.clone().unwrap_or("ERROR".to_string()))
+            .or_insert(0) += 1;
+    }
+
+    hw_component_count_hashmap
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_report() -> Report {
+        Report {
+            title: "Hw artifacts of HSM group 'zinal'".to_string(),
+            description: "1 nodes".to_string(),
+            table_vec: vec![ReportTable {
+                title: "Summary".to_string(),
+                header_vec: vec!["Type".to_string(), "Hw component".to_string()],
+                row_vec: vec![vec!["Accelerator".to_string(), "A100 | <SXM4>".to_string()]],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_report_to_markdown() {
+        assert_eq!(
+            get_report().to_markdown(),
+            "# Hw artifacts of HSM group 'zinal'\n\n1 nodes\n\n## Summary\n\n| Type | Hw component |\n| --- | --- |\n| Accelerator | A100 \\| <SXM4> |\n"
+        );
+    }
+
+    #[test]
+    fn test_report_to_html() {
+        let html = get_report().to_html();
+
+        assert!(html.contains("<table id=\"summary\">"));
+        assert!(html.contains("<tr><td>Accelerator</td><td>A100 | &lt;SXM4&gt;</td></tr>"));
+    }
+}
@@ -0,0 +1,281 @@
+use std::{collections::BTreeMap, error::Error};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    common::xname::Xname,
+    shasta::{hsm, sls},
+};
+
+/// Names a node is known by: xname, NID, hostnames (SLS aliases, eg nid001000) and the MAC and IP
+/// addresses of its ethernet interfaces
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct NodeIdentity {
+    pub xname: String,
+    pub nid: Option<u64>,
+    pub hostnames: Vec<String>,
+    pub ip_addresses: Vec<String>,
+    pub mac_addresses: Vec<String>,
+}
+
+/// Maps xnames, NIDs, hostnames and IP/MAC addresses to nodes
+#[derive(Debug, Default)]
+pub struct NodeResolver {
+    node_identity_hashmap: BTreeMap<String, NodeIdentity>,
+}
+
+/// Node xname, eg x1000c0s0b0n0
+pub fn is_node_xname(name: &str) -> bool {
+    name.parse::<Xname>()
+        .is_ok_and(|xname| xname.is_node() && xname.to_string() == name)
+}
+
+impl NodeResolver {
+    /// Builds the resolver from SLS nodes (xname, NID and aliases), HSM node components (xname
+    /// and NID, used for nodes missing in SLS) and HSM ethernet interfaces. Nodes without aliases
+    /// get the CSM default hostname nid<NID with 6 digits>
+    pub fn from_value_vec(
+        sls_node_value_vec: &[Value],
+        node_status_value_vec: &[Value],
+        ethernet_interface_value_vec: &[Value],
+    ) -> Self {
+        let mut node_identity_hashmap: BTreeMap<String, NodeIdentity> = BTreeMap::new();
+
+        for sls_node_value in sls_node_value_vec {
+            let Some(xname) = sls_node_value["Xname"].as_str() else {
+                continue;
+            };
+
+            let node_identity = node_identity_hashmap
+                .entry(xname.to_string())
+                .or_insert_with(|| NodeIdentity {
+                    xname: xname.to_string(),
+                    ..Default::default()
+                });
+
+            node_identity.nid = sls_node_value
+                .pointer("/ExtraProperties/NID")
+                .and_then(Value::as_u64);
+            node_identity.hostnames = sls_node_value
+                .pointer("/ExtraProperties/Aliases")
+                .and_then(Value::as_array)
+                .unwrap_or(&Vec::new())
+                .iter()
+                .filter_map(|alias| alias.as_str().map(str::to_string))
+                .collect();
+        }
+
+        for node_status_value in node_status_value_vec {
+            let Some(xname) = node_status_value["ID"].as_str() else {
+                continue;
+            };
+
+            let node_identity = node_identity_hashmap
+                .entry(xname.to_string())
+                .or_insert_with(|| NodeIdentity {
+                    xname: xname.to_string(),
+                    ..Default::default()
+                });
+
+            if node_identity.nid.is_none() {
+                node_identity.nid = node_status_value["NID"].as_u64();
+            }
+        }
+
+        for node_identity in node_identity_hashmap.values_mut() {
+            if let (true, Some(nid)) = (node_identity.hostnames.is_empty(), node_identity.nid) {
+                node_identity.hostnames.push(format!("nid{:06}", nid));
+            }
+        }
+
+        for ethernet_interface_value in ethernet_interface_value_vec {
+            let Some(node_identity) = ethernet_interface_value["ComponentID"]
+                .as_str()
+                .and_then(|xname| node_identity_hashmap.get_mut(xname))
+            else {
+                continue;
+            };
+
+            if let Some(mac_address) = ethernet_interface_value["MACAddress"].as_str() {
+                node_identity.mac_addresses.push(mac_address.to_lowercase());
+            }
+
+            node_identity.ip_addresses.extend(
+                ethernet_interface_value["IPAddresses"]
+                    .as_array()
+                    .unwrap_or(&Vec::new())
+                    .iter()
+                    .filter_map(|ip_address| ip_address["IPAddress"].as_str())
+                    .filter(|ip_address| !ip_address.is_empty())
+                    .map(str::to_string),
+            );
+        }
+
+        Self {
+            node_identity_hashmap,
+        }
+    }
+
+    /// Fetches nodes from SLS and HSM. SLS and ethernet interfaces are optional, a warning is
+    /// logged if they can't be fetched
+    pub async fn fetch(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+    ) -> Result<Self, Box<dyn Error>> {
+        let sls_node_value_vec = sls::http_client::get_search_hardware(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            "comptype_node",
+        )
+        .await
+        .unwrap_or_else(|error| {
+            log::warn!("Could not get nodes from SLS: {}", error);
+            Vec::new()
+        });
+
+        let node_status_value = hsm::http_client::get_all_components_status(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            "Node",
+        )
+        .await?;
+
+        let ethernet_interface_value_vec = hsm::http_client::get_ethernet_interfaces(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            "Node",
+        )
+        .await
+        .unwrap_or_else(|error| {
+            log::warn!("Could not get ethernet interfaces from HSM: {}", error);
+            Vec::new()
+        });
+
+        Ok(Self::from_value_vec(
+            &sls_node_value_vec,
+            node_status_value["Components"]
+                .as_array()
+                .unwrap_or(&Vec::new()),
+            &ethernet_interface_value_vec,
+        ))
+    }
+
+    /// Node known by `name`: xname, NID (eg 1000), hostname (eg nid001000), IP or MAC address.
+    /// Names are case insensitive
+    pub fn resolve(&self, name: &str) -> Option<&NodeIdentity> {
+        let name = name.trim().to_lowercase();
+
+        if let Some(node_identity) = self.node_identity_hashmap.get(&name) {
+            return Some(node_identity);
+        }
+
+        let nid_opt = name.parse::<u64>().ok();
+
+        self.node_identity_hashmap.values().find(|node_identity| {
+            (nid_opt.is_some() && node_identity.nid == nid_opt)
+                || node_identity
+                    .hostnames
+                    .iter()
+                    .chain(&node_identity.ip_addresses)
+                    .chain(&node_identity.mac_addresses)
+                    .any(|node_name| node_name.to_lowercase() == name)
+        })
+    }
+
+    /// Xnames of the nodes known by `name_vec`, fails listing the names which are unknown
+    pub fn resolve_xname_vec(&self, name_vec: &[String]) -> Result<Vec<String>, String> {
+        let mut xname_vec = Vec::new();
+        let mut unknown_name_vec = Vec::new();
+
+        for name in name_vec {
+            match self.resolve(name) {
+                Some(node_identity) => {
+                    if !xname_vec.contains(&node_identity.xname) {
+                        xname_vec.push(node_identity.xname.clone())
+                    }
+                }
+                None => unknown_name_vec.push(name.clone()),
+            }
+        }
+
+        if unknown_name_vec.is_empty() {
+            Ok(xname_vec)
+        } else {
+            Err(format!("nodes not found {:?}", unknown_name_vec))
+        }
+    }
+}
+
+/// Translates node names (xnames, NIDs, hostnames or IP/MAC addresses) into xnames so they can be
+/// used anywhere an xname is accepted. Nothing is fetched if all names are xnames already. Fails
+/// if any name is unknown
+pub async fn resolve_xname_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    name_vec: &[String],
+) -> Result<Vec<String>, String> {
+    if name_vec.iter().all(|name| is_node_xname(name)) {
+        return Ok(name_vec.to_vec());
+    }
+
+    NodeResolver::fetch(shasta_token, shasta_base_url, shasta_root_cert)
+        .await
+        .map_err(|error| error.to_string())
+        .and_then(|node_resolver| node_resolver.resolve_xname_vec(name_vec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_node_resolver_resolve() {
+        let node_resolver = NodeResolver::from_value_vec(
+            &[json!({
+                "Xname": "x1000c0s0b0n0",
+                "ExtraProperties": { "NID": 1000, "Aliases": ["nid001000", "daint-cn1000"] }
+            })],
+            &[
+                json!({ "ID": "x1000c0s0b0n0", "NID": 1000 }),
+                json!({ "ID": "x1000c0s0b0n1", "NID": 1001 }),
+            ],
+            &[json!({
+                "ComponentID": "x1000c0s0b0n1",
+                "MACAddress": "A4:BF:01:3E:C8:A2",
+                "IPAddresses": [{ "IPAddress": "10.252.1.11" }]
+            })],
+        );
+
+        let resolve_xname = |name: &str| {
+            node_resolver
+                .resolve(name)
+                .map(|node_identity| node_identity.xname.as_str())
+        };
+
+        assert_eq!(resolve_xname("daint-cn1000"), Some("x1000c0s0b0n0"));
+        assert_eq!(resolve_xname("1000"), Some("x1000c0s0b0n0"));
+        assert_eq!(resolve_xname("nid001001"), Some("x1000c0s0b0n1"));
+        assert_eq!(resolve_xname("10.252.1.11"), Some("x1000c0s0b0n1"));
+        assert_eq!(resolve_xname("a4:bf:01:3e:c8:a2"), Some("x1000c0s0b0n1"));
+        assert_eq!(resolve_xname("X1000C0S0B0N1"), Some("x1000c0s0b0n1"));
+        assert_eq!(resolve_xname("nid001002"), None);
+
+        assert!(node_resolver
+            .resolve_xname_vec(&["nid001000".to_string(), "nid001002".to_string()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_is_node_xname() {
+        assert!(is_node_xname("x1000c0s0b0n0"));
+        assert!(!is_node_xname("x1000c0s0b0"));
+        assert!(!is_node_xname("nid001000"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::log_ops;
+
+/// CSM API response captured with `--record`. Sessions are NDJSON (one exchange per line) so
+/// exchanges are appended as they arrive and commands exiting on error still leave a usable
+/// session
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordedExchange {
+    pub method: String,
+    /// Path and query, without the CSM API host so sessions don't expose it and can be replayed
+    /// against any site
+    pub url: String,
+    pub status: u16,
+    /// Response body with secrets redacted
+    pub body: String,
+}
+
+/// method and url -> responses in the order they were recorded, and how many were replayed
+type RecordedExchangeHashMap = HashMap<(String, String), (Vec<RecordedExchange>, usize)>;
+
+enum Session {
+    Record(Mutex<File>),
+    Replay(Mutex<RecordedExchangeHashMap>),
+}
+
+/// `--record` or `--replay`
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+/// Appends the CSM API responses received for the rest of the execution to `session_file_path`
+pub fn configure_record(session_file_path: &Path) -> Result<(), Box<dyn Error>> {
+    let session_file = File::create(session_file_path)?;
+
+    let _ = SESSION.set(Session::Record(Mutex::new(session_file)));
+
+    Ok(())
+}
+
+/// Answers CSM API requests for the rest of the execution with the responses recorded in
+/// `session_file_path`, no request reaches CSM
+pub fn configure_replay(session_file_path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut recorded_exchange_hashmap = RecordedExchangeHashMap::new();
+
+    for line in std::fs::read_to_string(session_file_path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+    {
+        let recorded_exchange: RecordedExchange = serde_json::from_str(line)?;
+
+        recorded_exchange_hashmap
+            .entry((
+                recorded_exchange.method.clone(),
+                recorded_exchange.url.clone(),
+            ))
+            .or_default()
+            .0
+            .push(recorded_exchange);
+    }
+
+    let _ = SESSION.set(Session::Replay(Mutex::new(recorded_exchange_hashmap)));
+
+    Ok(())
+}
+
+pub fn is_recording() -> bool {
+    matches!(SESSION.get(), Some(Session::Record(_)))
+}
+
+pub fn is_replaying() -> bool {
+    matches!(SESSION.get(), Some(Session::Replay(_)))
+}
+
+/// Path and query of a CSM API url
+fn get_url_path_and_query(url: &reqwest::Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Appends a response to the session if recording. Failing to write the session only logs an
+/// error, the command goes on
+pub fn record(method: &reqwest::Method, url: &reqwest::Url, status: u16, body: &[u8]) {
+    let Some(Session::Record(session_file)) = SESSION.get() else {
+        return;
+    };
+
+    let recorded_exchange = RecordedExchange {
+        method: method.to_string(),
+        url: log_ops::redact_secrets(&get_url_path_and_query(url)),
+        status,
+        body: log_ops::redact_secrets(&String::from_utf8_lossy(body)),
+    };
+
+    let mut session_file = session_file.lock().unwrap();
+
+    if let Err(error) = writeln!(
+        session_file,
+        "{}",
+        serde_json::to_string(&recorded_exchange).unwrap()
+    ) {
+        log::error!("Could not record CSM API response in session: {}", error);
+    }
+}
+
+/// Next recorded response to a request if replaying. Requests sent more often than recorded get
+/// the last recorded response again, requests never recorded get a 404
+pub fn replay(method: &reqwest::Method, url: &reqwest::Url) -> Option<reqwest::Response> {
+    let Some(Session::Replay(recorded_exchange_hashmap)) = SESSION.get() else {
+        return None;
+    };
+
+    let url = log_ops::redact_secrets(&get_url_path_and_query(url));
+
+    let mut recorded_exchange_hashmap = recorded_exchange_hashmap.lock().unwrap();
+
+    let (status, body) = match recorded_exchange_hashmap.get_mut(&(method.to_string(), url.clone()))
+    {
+        Some((recorded_exchange_vec, replayed)) => {
+            let recorded_exchange =
+                &recorded_exchange_vec[(*replayed).min(recorded_exchange_vec.len() - 1)];
+            *replayed += 1;
+
+            (recorded_exchange.status, recorded_exchange.body.clone())
+        }
+        None => {
+            log::warn!("{} {} not recorded in session, replying 404", method, url);
+
+            (
+                404,
+                format!(
+                    r#"{{"detail":"{} {} not recorded in session"}}"#,
+                    method, url
+                ),
+            )
+        }
+    };
+
+    let mut http_resp = http::Response::new(body);
+    *http_resp.status_mut() =
+        reqwest::StatusCode::from_u16(status).unwrap_or(reqwest::StatusCode::NOT_FOUND);
+
+    Some(reqwest::Response::from(http_resp))
+}
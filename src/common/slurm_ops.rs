@@ -0,0 +1,343 @@
+use std::{collections::HashMap, error::Error};
+
+use serde_json::Value;
+
+use crate::common::{
+    history_ops, hostlist_ops,
+    node_migration_ops::{self, NodeMove},
+};
+
+/// Slurm node states meaning the node is running (or finishing) jobs
+const SLURM_BUSY_STATE_VEC: [&str; 3] = ["allocated", "mixed", "completing"];
+
+/// Values accepted by `--slurm-sync`. 'scontrol' prints scontrol commands, 'conf' prints
+/// slurm.conf PartitionName lines and 'apply' runs the scontrol commands
+pub const SLURM_SYNC_NAME_VEC: [&str; 3] = ["scontrol", "conf", "apply"];
+
+/// Slurm access configured in the `[slurm]` table of the configuration file. If `rest_url` is set
+/// then slurmrestd is queried (`user` and `token` are sent as X-SLURM-USER-NAME and
+/// X-SLURM-USER-TOKEN), otherwise `sinfo` is run locally
+#[derive(Debug, Clone, Default)]
+pub struct SlurmSettings {
+    /// `slurm.enabled`, check Slurm before moving nodes even without `--check-slurm`
+    pub enabled: bool,
+    pub rest_url_opt: Option<String>,
+    pub user_opt: Option<String>,
+    pub token_opt: Option<String>,
+    /// `slurm.partitions`, HSM group -> Slurm partition. If empty, each HSM group is synced to the
+    /// Slurm partition with the same name
+    pub partition_hashmap: HashMap<String, String>,
+}
+
+impl SlurmSettings {
+    pub fn from_config(settings: &config::Config) -> Self {
+        Self {
+            enabled: settings.get_bool("slurm.enabled").unwrap_or(false),
+            rest_url_opt: settings.get_string("slurm.rest_url").ok(),
+            user_opt: settings.get_string("slurm.user").ok(),
+            token_opt: settings.get_string("slurm.token").ok(),
+            partition_hashmap: settings
+                .get_table("slurm.partitions")
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(hsm_group_name, partition_name)| {
+                    Some((hsm_group_name, partition_name.into_string().ok()?))
+                })
+                .collect(),
+        }
+    }
+
+    /// Slurm partition an HSM group is synced to, None if the HSM group is not mapped
+    pub fn get_partition_name(&self, hsm_group_name: &str) -> Option<String> {
+        if self.partition_hashmap.is_empty() {
+            Some(hsm_group_name.to_string())
+        } else {
+            self.partition_hashmap.get(hsm_group_name).cloned()
+        }
+    }
+}
+
+/// Returns the xnames of the nodes with jobs allocated in Slurm. Slurm node names are expected to
+/// be either xnames or CSM hostnames (nid<NID with 6 digits>), the latter are translated to xnames
+/// using HSM state components
+pub async fn get_busy_xname_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    slurm_settings: &SlurmSettings,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let busy_node_name_vec = match &slurm_settings.rest_url_opt {
+        Some(rest_url) => get_busy_node_name_vec_from_rest(rest_url, slurm_settings).await?,
+        None => get_busy_node_name_vec_from_sinfo()?,
+    };
+
+    if busy_node_name_vec.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let hostname_xname_hashmap: HashMap<String, String> =
+        get_xname_hostname_vec(shasta_token, shasta_base_url, shasta_root_cert)
+            .await?
+            .into_iter()
+            .map(|(xname, hostname)| (hostname, xname))
+            .collect();
+
+    Ok(busy_node_name_vec
+        .into_iter()
+        .map(|node_name| {
+            hostname_xname_hashmap
+                .get(&node_name)
+                .cloned()
+                .unwrap_or(node_name)
+        })
+        .collect())
+}
+
+/// (xname, CSM hostname) of each node, CSM hostnames are nid<NID with 6 digits>
+async fn get_xname_hostname_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let node_status_value = crate::shasta::hsm::http_client::get_all_components_status(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        "Node",
+    )
+    .await?;
+
+    Ok(node_status_value["Components"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter_map(|node_status| {
+            Some((
+                node_status["ID"].as_str()?.to_string(),
+                format!("nid{:06}", node_status["NID"].as_u64()?),
+            ))
+        })
+        .collect())
+}
+
+/// Node moves of nodes with jobs allocated
+pub fn get_busy_node_move_vec<'a>(
+    node_move_vec: &'a [NodeMove],
+    busy_xname_vec: &[String],
+) -> Vec<&'a NodeMove> {
+    node_move_vec
+        .iter()
+        .filter(|node_move| busy_xname_vec.contains(&node_move.xname))
+        .collect()
+}
+
+/// Updates the Slurm partitions of the HSM groups changed by `node_move_vec` so they list the
+/// current members of the HSM groups. `slurm_sync` is one of SLURM_SYNC_NAME_VEC. Returns the
+/// lines for the user: slurm.conf lines ('conf'), scontrol commands ('scontrol') or the
+/// partitions updated ('apply')
+pub async fn sync_slurm_partition_vec(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    node_move_vec: &[NodeMove],
+    slurm_sync: &str,
+    slurm_settings: &SlurmSettings,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let hsm_group_name_vec: Vec<String> = history_ops::get_hsm_group_name_vec(node_move_vec)
+        .into_iter()
+        .filter(|hsm_group_name| slurm_settings.get_partition_name(hsm_group_name).is_some())
+        .collect();
+
+    if hsm_group_name_vec.is_empty() {
+        log::info!("No HSM group mapped to a Slurm partition changed");
+        return Ok(Vec::new());
+    }
+
+    let hsm_group_snapshot_vec = node_migration_ops::get_hsm_group_snapshot_vec(
+        shasta_token,
+        shasta_base_url,
+        shasta_root_cert,
+        &hsm_group_name_vec,
+    )
+    .await?;
+
+    let xname_hostname_hashmap: HashMap<String, String> =
+        get_xname_hostname_vec(shasta_token, shasta_base_url, shasta_root_cert)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut line_vec = Vec::new();
+
+    for hsm_group_snapshot in hsm_group_snapshot_vec {
+        let partition_name = slurm_settings
+            .get_partition_name(&hsm_group_snapshot.label)
+            .unwrap();
+
+        let hostname_vec: Vec<String> = hsm_group_snapshot
+            .members
+            .iter()
+            .map(|xname| {
+                xname_hostname_hashmap
+                    .get(xname)
+                    .cloned()
+                    .unwrap_or(xname.clone())
+            })
+            .collect();
+
+        let nodes = hostlist_ops::compress_hostlist(&hostname_vec);
+
+        match slurm_sync {
+            "conf" => line_vec.push(format!("PartitionName={} Nodes={}", partition_name, nodes)),
+            "scontrol" => line_vec.push(format!(
+                "scontrol update PartitionName={} Nodes={}",
+                partition_name, nodes
+            )),
+            "apply" => {
+                run_scontrol_update_partition(&partition_name, &nodes)?;
+                line_vec.push(format!(
+                    "Slurm partition '{}' updated with {} nodes from HSM group '{}'",
+                    partition_name,
+                    hostname_vec.len(),
+                    hsm_group_snapshot.label
+                ));
+            }
+            _ => return Err(format!("Slurm sync '{}' not valid", slurm_sync).into()),
+        }
+    }
+
+    Ok(line_vec)
+}
+
+fn run_scontrol_update_partition(partition_name: &str, nodes: &str) -> Result<(), Box<dyn Error>> {
+    let output = std::process::Command::new("scontrol")
+        .args([
+            "update",
+            &format!("PartitionName={}", partition_name),
+            &format!("Nodes={}", nodes),
+        ])
+        .output()
+        .map_err(|error| format!("could not run scontrol: {}", error))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "scontrol update of partition '{}' failed: {}",
+            partition_name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn get_busy_node_name_vec_from_sinfo() -> Result<Vec<String>, Box<dyn Error>> {
+    let output = std::process::Command::new("sinfo")
+        .args(["--noheader", "--Node", "--format=%N %T"])
+        .output()
+        .map_err(|error| format!("could not run sinfo: {}", error))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "sinfo failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    Ok(parse_sinfo_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses `sinfo --noheader --Node --format='%N %T'` output. Nodes in several partitions are listed
+/// once per partition, states may have a suffix (eg 'mixed*' or 'allocated+')
+pub fn parse_sinfo_output(sinfo_output: &str) -> Vec<String> {
+    let mut busy_node_name_vec: Vec<String> = Vec::new();
+
+    for line in sinfo_output.lines() {
+        let mut field_iter = line.split_whitespace();
+
+        let (Some(node_name), Some(state)) = (field_iter.next(), field_iter.next()) else {
+            continue;
+        };
+
+        if is_busy_state(state) && !busy_node_name_vec.iter().any(|node| node.eq(node_name)) {
+            busy_node_name_vec.push(node_name.to_string());
+        }
+    }
+
+    busy_node_name_vec
+}
+
+async fn get_busy_node_name_vec_from_rest(
+    rest_url: &str,
+    slurm_settings: &SlurmSettings,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    // Proxy and timeout like CSM API requests
+    let client = crate::shasta::build_client(&[])?;
+
+    let mut request = client.get(rest_url.trim_end_matches('/').to_owned() + "/nodes");
+
+    if let Some(user) = &slurm_settings.user_opt {
+        request = request.header("X-SLURM-USER-NAME", user);
+    }
+
+    if let Some(token) = &slurm_settings.token_opt {
+        request = request.header("X-SLURM-USER-TOKEN", token);
+    }
+
+    let resp = request.send().await?;
+
+    if !resp.status().is_success() {
+        return Err(format!("slurmrestd {} {}", resp.status(), resp.text().await?).into());
+    }
+
+    let nodes_value: Value = resp.json().await?;
+
+    Ok(nodes_value["nodes"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter(|node_value| {
+            // 'state' is a string in older slurmrestd versions and a list of flags in newer ones
+            match &node_value["state"] {
+                Value::String(state) => is_busy_state(state),
+                Value::Array(state_vec) => state_vec
+                    .iter()
+                    .filter_map(|state| state.as_str())
+                    .any(is_busy_state),
+                _ => false,
+            }
+        })
+        .filter_map(|node_value| node_value["name"].as_str())
+        .map(|node_name| node_name.to_string())
+        .collect())
+}
+
+fn is_busy_state(state: &str) -> bool {
+    let state = state.to_lowercase();
+
+    SLURM_BUSY_STATE_VEC
+        .iter()
+        .any(|busy_state| state.starts_with(busy_state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sinfo_output() {
+        let sinfo_output = "nid001000 idle
+nid001001 allocated
+nid001001 allocated
+nid001002 mixed*
+nid001003 drained
+nid001004 completing
+";
+
+        assert_eq!(
+            parse_sinfo_output(sinfo_output),
+            vec!["nid001001", "nid001002", "nid001004"]
+        );
+    }
+}
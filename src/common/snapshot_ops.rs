@@ -0,0 +1,269 @@
+use std::{
+    collections::HashSet,
+    io::Write,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+use crate::{
+    common::{artifact_ops::NodeSummary, xname},
+    shasta::{hsm, ShastaClient},
+};
+
+/// Version of the inventory snapshot format. Increase it when the format changes in a non
+/// backwards compatible way
+pub const INVENTORY_SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HsmGroupSnapshot {
+    pub label: String,
+    pub members: Vec<String>,
+}
+
+/// Hw inventory of a set of HSM groups at a point in time (JSON format)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InventorySnapshot {
+    pub version: u32,
+    /// Seconds since UNIX epoch
+    pub timestamp: u64,
+    pub hsm_groups: Vec<HsmGroupSnapshot>,
+    pub nodes: Vec<NodeSummary>,
+    /// Members which hw inventory could not be fetched, they are not in `nodes` but were not
+    /// removed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_nodes: Vec<String>,
+}
+
+/// Line of an inventory snapshot in NDJSON format. First line is the header, then one line per
+/// node
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InventorySnapshotRecord {
+    Header {
+        version: u32,
+        timestamp: u64,
+        hsm_groups: Vec<HsmGroupSnapshot>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        failed_nodes: Vec<String>,
+    },
+    Node(NodeSummary),
+}
+
+/// Fetches the hw inventory of all members of a HSM group (or all HSM groups if None). Fails if
+/// the HSM group does not exist. Members which hw inventory can't be fetched are listed in
+/// `failed_nodes`
+pub async fn get_inventory_snapshot(
+    shasta_token: &str,
+    shasta_base_url: &str,
+    shasta_root_cert: &[u8],
+    hsm_group_name_opt: Option<&String>,
+) -> Result<InventorySnapshot, Box<dyn std::error::Error>> {
+    let hsm_group_value_vec = if hsm_group_name_opt.is_some() {
+        crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_hsm_group_vec(
+            shasta_token,
+            shasta_base_url,
+            shasta_root_cert,
+            hsm_group_name_opt,
+        ))
+        .await?
+    } else {
+        crate::common::tenant_ops::filter_available_hsm_group_value_vec(
+            crate::shasta::with_timeout(crate::shasta::hsm::http_client::get_all_hsm_groups(
+                shasta_token,
+                shasta_base_url,
+                shasta_root_cert,
+            ))
+            .await?,
+        )
+    };
+
+    if hsm_group_value_vec.is_empty() {
+        return Err(format!(
+            "HSM group '{}' not found",
+            hsm_group_name_opt.map(|name| name.as_str()).unwrap_or("")
+        )
+        .into());
+    }
+
+    let hsm_group_snapshot_vec: Vec<HsmGroupSnapshot> = hsm_group_value_vec
+        .iter()
+        .map(|hsm_group_value| {
+            let mut member_vec =
+                mesa::hsm::group::shasta::utils::get_member_vec_from_hsm_group_value(
+                    hsm_group_value,
+                );
+            member_vec.sort();
+
+            HsmGroupSnapshot {
+                label: hsm_group_value["label"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                members: member_vec,
+            }
+        })
+        .collect();
+
+    let mut member_vec: Vec<String> = hsm_group_snapshot_vec
+        .iter()
+        .flat_map(|hsm_group_snapshot| hsm_group_snapshot.members.clone())
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+    member_vec.sort();
+
+    let start_total = Instant::now();
+
+    let mut tasks = tokio::task::JoinSet::new();
+
+    let sem = Arc::new(Semaphore::new(
+        crate::common::csm_version_ops::get_max_concurrent_requests(),
+    ));
+
+    // Get HW inventory details for all members
+    let shasta_client = ShastaClient::new(shasta_token, shasta_base_url, shasta_root_cert)?;
+
+    for hsm_member in member_vec.clone() {
+        let shasta_client = Arc::clone(&shasta_client);
+
+        let permit = Arc::clone(&sem).acquire_owned().await;
+
+        log::info!("Getting HW inventory details for node '{}'", hsm_member);
+        tasks.spawn(async move {
+            let _permit = permit; // Wait semaphore to allow new tasks https://github.com/tokio-rs/tokio/discussions/2648#discussioncomment-34885
+            (
+                hsm_member.clone(),
+                hsm::http_client::get_hw_inventory_query(&shasta_client, &hsm_member, "Node")
+                    .await
+                    .map_err(|error| error.to_string()),
+            )
+        });
+    }
+
+    let mut node_summary_vec: Vec<NodeSummary> = Vec::new();
+
+    while let Some(message) = tasks.join_next().await {
+        match message {
+            Ok((xname, Ok(node_hw_inventory))) => match node_hw_inventory.pointer("/Nodes/0") {
+                Some(node_hw_inventory) => {
+                    node_summary_vec.push(NodeSummary::from_csm_value(node_hw_inventory.clone()))
+                }
+                None => log::warn!("Node '{}' has no hw inventory, skipping", xname),
+            },
+            Ok((xname, Err(error))) => {
+                log::error!(
+                    "Failed fetching hw inventory for node '{}': {}",
+                    xname,
+                    error
+                )
+            }
+            Err(error) => log::error!("Failed procesing/fetching node hw information: {}", error),
+        }
+    }
+
+    node_summary_vec.sort_by(|a, b| xname::compare_xnames(&a.xname, &b.xname));
+
+    // Members without hw inventory, because the request failed or CSM has none
+    let failed_xname_vec: Vec<String> = member_vec
+        .iter()
+        .filter(|member| {
+            !node_summary_vec
+                .iter()
+                .any(|node_summary| node_summary.xname.eq(*member))
+        })
+        .cloned()
+        .collect();
+
+    log::info!(
+        "Time elapsed in http calls to get hw inventory for {} nodes is: {:?}",
+        member_vec.len(),
+        start_total.elapsed()
+    );
+
+    Ok(InventorySnapshot {
+        version: INVENTORY_SNAPSHOT_VERSION,
+        timestamp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+        hsm_groups: hsm_group_snapshot_vec,
+        nodes: node_summary_vec,
+        failed_nodes: failed_xname_vec,
+    })
+}
+
+pub fn write_ndjson(
+    writer: &mut impl Write,
+    inventory_snapshot: &InventorySnapshot,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let header = InventorySnapshotRecord::Header {
+        version: inventory_snapshot.version,
+        timestamp: inventory_snapshot.timestamp,
+        hsm_groups: inventory_snapshot.hsm_groups.clone(),
+        failed_nodes: inventory_snapshot.failed_nodes.clone(),
+    };
+
+    writeln!(writer, "{}", serde_json::to_string(&header)?)?;
+
+    for node_summary in &inventory_snapshot.nodes {
+        let record = InventorySnapshotRecord::Node(node_summary.clone());
+        writeln!(writer, "{}", serde_json::to_string(&record)?)?;
+    }
+
+    Ok(())
+}
+
+/// Reads an inventory snapshot file either in JSON or NDJSON format
+pub fn read_inventory_snapshot(
+    file_path: &str,
+) -> Result<InventorySnapshot, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(file_path)?;
+
+    let inventory_snapshot = match serde_json::from_str::<InventorySnapshot>(&content) {
+        Ok(inventory_snapshot) => inventory_snapshot,
+        Err(_) => read_ndjson(&content)?,
+    };
+
+    if inventory_snapshot.version > INVENTORY_SNAPSHOT_VERSION {
+        return Err(format!(
+            "inventory snapshot version {} not supported, latest version supported is {}",
+            inventory_snapshot.version, INVENTORY_SNAPSHOT_VERSION
+        )
+        .into());
+    }
+
+    Ok(inventory_snapshot)
+}
+
+pub fn read_ndjson(content: &str) -> Result<InventorySnapshot, Box<dyn std::error::Error>> {
+    let mut inventory_snapshot_opt: Option<InventorySnapshot> = None;
+
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<InventorySnapshotRecord>(line)? {
+            InventorySnapshotRecord::Header {
+                version,
+                timestamp,
+                hsm_groups,
+                failed_nodes,
+            } => {
+                inventory_snapshot_opt = Some(InventorySnapshot {
+                    version,
+                    timestamp,
+                    hsm_groups,
+                    nodes: Vec::new(),
+                    failed_nodes,
+                })
+            }
+            InventorySnapshotRecord::Node(node_summary) => inventory_snapshot_opt
+                .as_mut()
+                .ok_or("NDJSON inventory snapshot must start with a header line")?
+                .nodes
+                .push(node_summary),
+        }
+    }
+
+    inventory_snapshot_opt.ok_or("inventory snapshot is empty".into())
+}
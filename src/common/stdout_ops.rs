@@ -0,0 +1,44 @@
+use std::{fs::File, io::Write, os::fd::FromRawFd};
+
+/// Sends what is printed to stdout to stderr while alive, so commands with `--output json` keep
+/// stdout for the JSON document while plans, tables and progress are still shown. Stdout is
+/// restored when dropped
+pub struct StdoutRedirect {
+    /// Duplicate of the original stdout
+    stdout_fd: libc::c_int,
+}
+
+impl StdoutRedirect {
+    pub fn to_stderr() -> Self {
+        let _ = std::io::stdout().flush();
+
+        let stdout_fd = unsafe {
+            let stdout_fd = libc::dup(libc::STDOUT_FILENO);
+            libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO);
+            stdout_fd
+        };
+
+        Self { stdout_fd }
+    }
+
+    /// Prints a line to the original stdout
+    pub fn println(&self, line: &str) {
+        let _ = std::io::stdout().flush();
+
+        // Duplicate is closed when the file is dropped, the original stdout stays open
+        let mut stdout_file = unsafe { File::from_raw_fd(libc::dup(self.stdout_fd)) };
+
+        let _ = writeln!(stdout_file, "{}", line);
+    }
+}
+
+impl Drop for StdoutRedirect {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().flush();
+
+        unsafe {
+            libc::dup2(self.stdout_fd, libc::STDOUT_FILENO);
+            libc::close(self.stdout_fd);
+        }
+    }
+}
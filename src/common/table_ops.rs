@@ -0,0 +1,141 @@
+use std::{cmp::Ordering, sync::OnceLock};
+
+use comfy_table::{Cell, Table};
+
+use crate::common::color_ops;
+
+static SORT_BY: OnceLock<String> = OnceLock::new();
+
+/// Sets the column used to sort table rows for the rest of the execution (`--sort-by`)
+pub fn configure(sort_by_opt: Option<&String>) {
+    if let Some(sort_by) = sort_by_opt {
+        let _ = SORT_BY.set(sort_by.clone());
+    }
+}
+
+/// Empty table, cells are colored depending on `--color` and NO_COLOR (see
+/// `color_ops::is_color_enabled`)
+pub fn new_table() -> Table {
+    let mut table = Table::new();
+
+    if color_ops::is_color_enabled() {
+        table.enforce_styling();
+    } else {
+        table.force_no_tty();
+    }
+
+    table
+}
+
+/// Sorts table rows by the column set with `--sort-by` (header name, case insensitive) in
+/// natural order. Rows keep their order if no column is set or the table has no such column
+pub fn sort_row_vec(header_vec: &[String], row_vec: &mut [Vec<Cell>]) {
+    let Some(sort_by) = SORT_BY.get() else {
+        return;
+    };
+
+    let Some(column_index) = header_vec
+        .iter()
+        .position(|header| header.eq_ignore_ascii_case(sort_by))
+    else {
+        log::warn!(
+            "Table not sorted, no column '{}' in {:?}",
+            sort_by,
+            header_vec
+        );
+        return;
+    };
+
+    row_vec.sort_by(|row_1, row_2| {
+        compare_natural(
+            &row_1
+                .get(column_index)
+                .map(Cell::content)
+                .unwrap_or_default(),
+            &row_2
+                .get(column_index)
+                .map(Cell::content)
+                .unwrap_or_default(),
+        )
+    });
+}
+
+/// Compares strings by chunks, numbers by their value and the rest as strings, eg
+/// x1001c1s2b0n0 < x1001c1s10b0n0 and '✅ (4)' < '✅ (16)'. Strings which are numbers (eg
+/// negative scores) are compared by their value
+pub fn compare_natural(text_1: &str, text_2: &str) -> Ordering {
+    if let (Ok(number_1), Ok(number_2)) = (text_1.parse::<f64>(), text_2.parse::<f64>()) {
+        return number_1.total_cmp(&number_2);
+    }
+
+    let chunk_vec_1 = get_chunk_vec(text_1);
+    let chunk_vec_2 = get_chunk_vec(text_2);
+
+    for (chunk_1, chunk_2) in chunk_vec_1.iter().zip(chunk_vec_2.iter()) {
+        let ordering = match (chunk_1.parse::<f64>(), chunk_2.parse::<f64>()) {
+            (Ok(number_1), Ok(number_2)) => number_1.total_cmp(&number_2),
+            _ => chunk_1.cmp(chunk_2),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    chunk_vec_1.len().cmp(&chunk_vec_2.len())
+}
+
+/// Splits a string in numbers (digits with an optional decimal point) and the text between them
+fn get_chunk_vec(text: &str) -> Vec<String> {
+    let mut chunk_vec: Vec<String> = Vec::new();
+    let mut is_previous_numeric = None;
+
+    for character in text.chars() {
+        let is_numeric =
+            character.is_ascii_digit() || (character == '.' && is_previous_numeric == Some(true));
+
+        match chunk_vec.last_mut() {
+            Some(chunk) if is_previous_numeric == Some(is_numeric) => chunk.push(character),
+            _ => chunk_vec.push(character.to_string()),
+        }
+
+        is_previous_numeric = Some(is_numeric);
+    }
+
+    chunk_vec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_natural() {
+        let mut text_vec = vec![
+            "x1001c1s10b0n0",
+            "x1001c1s2b0n1",
+            "x1001c1s2b0n0",
+            "✅ (16)",
+            "✅ (4)",
+            "0.75",
+            "-1.5",
+            "0.5",
+        ];
+
+        text_vec.sort_by(|text_1, text_2| compare_natural(text_1, text_2));
+
+        assert_eq!(
+            text_vec,
+            vec![
+                "-1.5",
+                "0.5",
+                "0.75",
+                "x1001c1s2b0n0",
+                "x1001c1s2b0n1",
+                "x1001c1s10b0n0",
+                "✅ (4)",
+                "✅ (16)",
+            ]
+        );
+    }
+}
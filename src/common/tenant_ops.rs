@@ -0,0 +1,51 @@
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+/// `hsm_available` in configuration file
+static HSM_AVAILABLE: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Restricts operations to these HSM groups for the rest of the execution (eg delegated operators
+/// of a tenant), no restriction if empty
+pub fn configure(hsm_available_vec: Vec<String>) {
+    let _ = HSM_AVAILABLE.set(hsm_available_vec);
+}
+
+pub fn get_hsm_available_vec() -> &'static [String] {
+    HSM_AVAILABLE.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+pub fn is_hsm_group_available(hsm_group_name: &str) -> bool {
+    let hsm_available_vec = get_hsm_available_vec();
+
+    hsm_available_vec.is_empty()
+        || hsm_available_vec
+            .iter()
+            .any(|hsm_available| hsm_available.eq(hsm_group_name))
+}
+
+/// Fails with the first HSM group not available
+pub fn check_hsm_group_vec<S: AsRef<str>>(hsm_group_name_vec: &[S]) -> Result<(), String> {
+    match hsm_group_name_vec
+        .iter()
+        .find(|hsm_group_name| !is_hsm_group_available(hsm_group_name.as_ref()))
+    {
+        Some(hsm_group_name) => Err(format!(
+            "HSM group '{}' not available, HSM groups available: {}",
+            hsm_group_name.as_ref(),
+            get_hsm_available_vec().join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Keeps the HSM groups (as returned by HSM API) available, so commands over all HSM groups (eg
+/// 'apply defrag' or 'get hsm-groups list') don't see other tenants
+pub fn filter_available_hsm_group_value_vec(hsm_group_value_vec: Vec<Value>) -> Vec<Value> {
+    hsm_group_value_vec
+        .into_iter()
+        .filter(|hsm_group_value| {
+            is_hsm_group_available(hsm_group_value["label"].as_str().unwrap_or_default())
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+/// Spans finished, exported in one OTLP request once the command finishes
+static SPAN_VEC: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// OTLP/HTTP endpoint eg http://localhost:4318, `otlp_endpoint` in configuration file
+static OTLP_ENDPOINT: OnceLock<String> = OnceLock::new();
+
+/// Enables tracing of the command and the CSM API calls if `otlp_endpoint_opt` is set. Spans are
+/// kept in memory and exported to the OTLP collector (eg Jaeger or Tempo) by `export`
+pub fn configure(otlp_endpoint_opt: Option<String>) {
+    let Some(otlp_endpoint) = otlp_endpoint_opt else {
+        return;
+    };
+
+    let _ = OTLP_ENDPOINT.set(otlp_endpoint.trim_end_matches('/').to_string());
+
+    if let Err(error) = tracing_subscriber::registry().with(OtlpLayer).try_init() {
+        log::warn!("Could not enable tracing: {}", error);
+    }
+}
+
+/// Sends the spans finished to the OTLP collector with the OTLP/HTTP JSON protocol. Nothing is
+/// sent if tracing is not configured. Spans of commands which exit early are not exported
+pub async fn export() {
+    let Some(otlp_endpoint) = OTLP_ENDPOINT.get() else {
+        return;
+    };
+
+    let span_vec: Vec<Value> = std::mem::take(&mut *SPAN_VEC.lock().unwrap());
+
+    if span_vec.is_empty() {
+        return;
+    }
+
+    let export_request = json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": "clstr" } },
+                    { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } }
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "clstr" },
+                "spans": span_vec
+            }]
+        }]
+    });
+
+    let span_count = span_vec.len();
+
+    // Proxy and timeout like CSM API requests
+    let client = match crate::shasta::build_client(&[]) {
+        Ok(client) => client,
+        Err(error) => {
+            log::warn!("Could not export spans to '{}': {}", otlp_endpoint, error);
+            return;
+        }
+    };
+
+    let resp_rslt = client
+        .post(format!("{}/v1/traces", otlp_endpoint))
+        .json(&export_request)
+        .send()
+        .await;
+
+    match resp_rslt {
+        Ok(resp) if resp.status().is_success() => {
+            log::info!("{} spans exported to '{}'", span_count, otlp_endpoint)
+        }
+        Ok(resp) => log::warn!(
+            "Could not export spans to '{}': {}",
+            otlp_endpoint,
+            resp.status()
+        ),
+        Err(error) => log::warn!("Could not export spans to '{}': {}", otlp_endpoint, error),
+    }
+}
+
+/// Span in progress
+struct SpanData {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: String,
+    start_time_unix_nano: u128,
+    attribute_vec: Vec<Value>,
+}
+
+/// Records each span with its fields as attributes (OTLP string values)
+struct OtlpLayer;
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        // Root spans start a new trace, children inherit the trace of their parent
+        let (trace_id, parent_span_id) =
+            span.parent()
+                .and_then(|parent| {
+                    parent.extensions().get::<SpanData>().map(|parent_data| {
+                        (parent_data.trace_id.clone(), parent_data.span_id.clone())
+                    })
+                })
+                .unwrap_or_else(|| {
+                    (
+                        format!("{:016x}{:016x}", get_random_u64(), get_random_u64()),
+                        String::new(),
+                    )
+                });
+
+        let mut attribute_visitor = AttributeVisitor(Vec::new());
+        attrs.record(&mut attribute_visitor);
+
+        span.extensions_mut().insert(SpanData {
+            trace_id,
+            span_id: format!("{:016x}", get_random_u64()),
+            parent_span_id,
+            start_time_unix_nano: get_time_unix_nano(),
+            attribute_vec: attribute_visitor.0,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(span_data) = span.extensions_mut().get_mut::<SpanData>() {
+                let mut attribute_visitor = AttributeVisitor(Vec::new());
+                values.record(&mut attribute_visitor);
+                span_data.attribute_vec.extend(attribute_visitor.0);
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let Some(span_data) = span.extensions_mut().remove::<SpanData>() else {
+            return;
+        };
+
+        let mut span_value = json!({
+            "traceId": span_data.trace_id,
+            "spanId": span_data.span_id,
+            "name": span.name(),
+            "kind": 1,
+            "startTimeUnixNano": span_data.start_time_unix_nano.to_string(),
+            "endTimeUnixNano": get_time_unix_nano().to_string(),
+            "attributes": span_data.attribute_vec,
+        });
+
+        if !span_data.parent_span_id.is_empty() {
+            span_value["parentSpanId"] = Value::String(span_data.parent_span_id);
+        }
+
+        SPAN_VEC.lock().unwrap().push(span_value);
+    }
+}
+
+struct AttributeVisitor(Vec<Value>);
+
+impl Visit for AttributeVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0
+            .push(json!({ "key": field.name(), "value": { "stringValue": value } }));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+fn get_time_unix_nano() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default()
+}
+
+/// Random ids without an extra dependency, RandomState is seeded randomly per instance
+fn get_random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(get_time_unix_nano());
+    hasher.finish()
+}
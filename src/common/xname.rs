@@ -0,0 +1,215 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+/// Location of a node, or of its BMC, blade (slot), chassis or cabinet, parsed from a xname eg
+/// x1000c0s5b0n1. Fields missing in the xname are None, eg x1000c0 is a chassis. Xnames are
+/// ordered by their numbers so x1001c1s10 comes after x1001c1s2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Xname {
+    pub cabinet: u32,
+    pub chassis: Option<u32>,
+    pub slot: Option<u32>,
+    pub bmc: Option<u32>,
+    pub node: Option<u32>,
+}
+
+impl Xname {
+    /// Xname of a node (has cabinet, chassis, slot, BMC and node)
+    pub fn is_node(&self) -> bool {
+        self.node.is_some()
+    }
+
+    /// Blade of the xname eg x1001c1s5b0n0 --> x1001c1s5b0
+    pub fn blade(&self) -> Self {
+        Self {
+            node: None,
+            ..*self
+        }
+    }
+
+    /// Chassis of the xname eg x1001c1s5b0n0 --> x1001c1
+    pub fn chassis(&self) -> Self {
+        Self {
+            slot: None,
+            bmc: None,
+            node: None,
+            ..*self
+        }
+    }
+
+    /// "Distance" between 2 xnames: 0 same blade, 1 same slot, 2 same chassis, 3 same cabinet
+    /// and 4 different cabinet
+    pub fn distance(&self, other: &Self) -> usize {
+        if self.cabinet != other.cabinet {
+            4
+        } else if self.chassis != other.chassis {
+            3
+        } else if self.slot != other.slot {
+            2
+        } else if self.bmc != other.bmc {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl FromStr for Xname {
+    type Err = String;
+
+    /// Parses `x<cabinet>[c<chassis>[s<slot>[b<bmc>[n<node>]]]]`, case insensitive
+    fn from_str(xname: &str) -> Result<Self, Self::Err> {
+        let invalid_xname_error = || {
+            format!(
+                "'{}' is not a valid xname, expected x<cabinet>c<chassis>s<slot>b<bmc>n<node> eg x1000c0s5b0n1",
+                xname
+            )
+        };
+
+        let location_vec =
+            get_location_vec(&xname.to_lowercase()).ok_or_else(invalid_xname_error)?;
+
+        let location_letter_vec: String = location_vec.iter().map(|(letter, _)| *letter).collect();
+
+        if location_vec.len() > 5
+            || !"xcsbn".starts_with(&location_letter_vec)
+            || location_vec.is_empty()
+        {
+            return Err(invalid_xname_error());
+        }
+
+        let number = |index: usize| location_vec.get(index).map(|(_, number)| *number);
+
+        Ok(Self {
+            cabinet: number(0).unwrap(),
+            chassis: number(1),
+            slot: number(2),
+            bmc: number(3),
+            node: number(4),
+        })
+    }
+}
+
+impl fmt::Display for Xname {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "x{}", self.cabinet)?;
+
+        for (letter, number_opt) in [
+            ('c', self.chassis),
+            ('s', self.slot),
+            ('b', self.bmc),
+            ('n', self.node),
+        ] {
+            match number_opt {
+                Some(number) => write!(f, "{}{}", letter, number)?,
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a xname in (letter, number) pairs eg x1000c0s5b0n1p0 --> [(x, 1000), (c, 0), ...].
+/// None if it is not a sequence of letters followed by numbers
+fn get_location_vec(xname: &str) -> Option<Vec<(char, u32)>> {
+    let mut location_vec = Vec::new();
+    let mut chars = xname.chars().peekable();
+
+    while let Some(letter) = chars.next() {
+        if !letter.is_ascii_alphabetic() {
+            return None;
+        }
+
+        let mut digits = String::new();
+
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+
+        location_vec.push((letter, digits.parse().ok()?));
+    }
+
+    Some(location_vec)
+}
+
+/// Validates a node xname CLI argument, to be used as clap value parser
+pub fn parse_node_xname(xname: &str) -> Result<String, String> {
+    match xname.parse::<Xname>() {
+        Ok(node_xname) if node_xname.is_node() => Ok(xname.to_string()),
+        Ok(_) => Err(format!(
+            "'{}' is not the xname of a node eg x1000c0s5b0n1",
+            xname
+        )),
+        Err(error) => Err(error),
+    }
+}
+
+/// Natural order of xnames of any component (eg x1001c1s10b0n0p0 after x1001c1s2b0n0p0), used to
+/// sort tables. Strings which are not xnames are compared as strings after the xnames
+pub fn compare_xnames(xname_1: &str, xname_2: &str) -> Ordering {
+    match (get_location_vec(xname_1), get_location_vec(xname_2)) {
+        (Some(location_vec_1), Some(location_vec_2)) => location_vec_1.cmp(&location_vec_2),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => xname_1.cmp(xname_2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xname_from_str() {
+        let xname: Xname = "x1000c0s5b0n1".parse().unwrap();
+
+        assert_eq!(
+            xname,
+            Xname {
+                cabinet: 1000,
+                chassis: Some(0),
+                slot: Some(5),
+                bmc: Some(0),
+                node: Some(1),
+            }
+        );
+        assert_eq!(xname.chassis().to_string(), "x1000c0");
+        assert!("x1000c0".parse::<Xname>().is_ok());
+        assert!("x1000s0".parse::<Xname>().is_err());
+        assert!("x1000c0s5b0n1p0".parse::<Xname>().is_err());
+        assert!("nid001000".parse::<Xname>().is_err());
+        assert!(parse_node_xname("x1000c0s5b0").is_err());
+    }
+
+    #[test]
+    fn test_xname_distance() {
+        let xname = |xname: &str| xname.parse::<Xname>().unwrap();
+
+        assert_eq!(xname("x1000c0s5b0n0").distance(&xname("x1000c0s5b0n1")), 0);
+        assert_eq!(xname("x1000c0s5b0n0").distance(&xname("x1000c0s5b1n0")), 1);
+        assert_eq!(xname("x1000c0s5b0n0").distance(&xname("x1000c1s5b0n0")), 3);
+        assert_eq!(xname("x1000c0s5b0n0").distance(&xname("x1001c0s5b0n0")), 4);
+    }
+
+    #[test]
+    fn test_compare_xnames() {
+        let mut xname_vec = vec![
+            "x1001c1s10b0n0",
+            "nid001000",
+            "x1001c1s2b0n0",
+            "x1001c1s2b0n0p0",
+        ];
+
+        xname_vec.sort_by(|xname_1, xname_2| compare_xnames(xname_1, xname_2));
+
+        assert_eq!(
+            xname_vec,
+            vec![
+                "x1001c1s2b0n0",
+                "x1001c1s2b0n0p0",
+                "x1001c1s10b0n0",
+                "nid001000"
+            ]
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! clstr core: inventory collection, scoring, plan computation and apply of HSM group
+//! re-partitioning on CSM, used by the `clstr` command line front-end and embeddable by other
+//! tools. Entry points return a `Result` and print nothing:
+//!
+//! - Inventory: `common::snapshot_ops::get_inventory_snapshot` and
+//!   `common::hw_inventory_ops::get_node_hw_inventory_hashmap`
+//! - Scoring and plan computation: `common::plan_ops::calculate_plan` (no CSM API calls)
+//! - Apply: `common::node_migration_ops::apply_node_move_vec`
+//! - CSM API clients: `shasta`
+//!
+//! Nothing is printed nor the process exited, errors are returned to the caller. The exceptions
+//! are opt-in: HTTP traces once enabled with `shasta::configure_http_trace` and the credentials
+//! prompt of `common::auth_ops::get_api_token` when there is no valid token. Long running calls
+//! report their progress through callbacks (eg `common::plan_ops::SolverOptions::on_progress_opt`)
+//!
+//! Global settings (eg `shasta::configure_http_timeout` or `common::tenant_ops::configure`) are
+//! set once per process, before calling any entry point
+
+pub mod common;
+pub mod shasta;
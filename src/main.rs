@@ -1,11 +1,9 @@
-mod cli;
-mod common;
-
 use std::path::PathBuf;
 
-use directories::ProjectDirs;
+use clstr_core::{common, common::log_ops, shasta};
 
-use crate::common::log_ops;
+mod cli;
+use tracing::Instrument;
 
 // DHAT (profiling)
 // #[cfg(feature = "dhat-heap")]
@@ -18,89 +16,521 @@ async fn main() -> core::result::Result<(), Box<dyn std::error::Error>> {
     #[cfg(feature = "dhat-heap")]
     let _profiler = dhat::Profiler::new_heap();
 
-    // XDG Base Directory Specification
-    let project_dirs = ProjectDirs::from(
-        "local", /*qualifier*/
-        "cscs",  /*organization*/
-        "manta", /*application*/
+    // --config/CLSTR_CONFIG is needed before parsing the cli since defaults (eg HSM group) are
+    // read from configuration file
+    common::config_ops::configure(common::config_ops::get_configuration_file_path_from_args());
+
+    // Configuration not valid is reported by 'config' subcommands (eg 'config validate'), other
+    // commands exit once the cli is parsed
+    let (settings, config_error_opt) = match common::config_ops::get_configuration() {
+        Ok(settings) => (settings, None),
+        Err(error) => (config::Config::default(), Some(error.to_string())),
+    };
+
+    // println!("settings:\n{:#?}", settings);
+
+    // 'log' is either the log level or a table with 'level' and 'target'
+    let log_level = settings
+        .get_string("log")
+        .or(settings.get_string("log.level"))
+        .unwrap_or("error".to_string());
+    let log_target_opt = settings.get_string("log.target").ok();
+
+    let settings_hsm_group_opt = settings.get_string("hsm_group").ok();
+
+    // Process input params
+    let matches = cli::build::build_cli(settings_hsm_group_opt.as_ref()).get_matches();
+
+    if let Some(config_error) = config_error_opt.as_ref() {
+        if matches.subcommand_name().ne(&Some("config")) {
+            eprintln!(
+                "Configuration file {} not valid: {}. Run 'clstr config validate' for details. Exit",
+                common::config_ops::get_configuration_toml_file_path().to_string_lossy(),
+                config_error
+            );
+            std::process::exit(1);
+        }
+    }
+
+    // manta files are only read until they are copied into clstr own directories
+    let is_config_migrate = matches
+        .subcommand_matches("config")
+        .and_then(|cli_config| cli_config.subcommand_name())
+        .eq(&Some("migrate"));
+
+    if !is_config_migrate
+        && (common::config_ops::is_legacy_configuration()
+            || common::config_ops::is_legacy_data_file(common::history_ops::HISTORY_FILE_NAME))
+    {
+        eprintln!("WARNING - Reading manta configuration and history, clstr writes to its own directories. Run 'clstr config migrate' to copy them");
+    }
+
+    let legacy_env_var_name_vec = common::config_ops::get_legacy_env_var_name_vec();
+
+    if !legacy_env_var_name_vec.is_empty() {
+        eprintln!(
+            "WARNING - Env vars {} are read by manta, use CLSTR_* instead. They are only used for keys without CLSTR_* env var",
+            legacy_env_var_name_vec.join(", ")
+        );
+    }
+
+    // Log file, cli params have precedence over configuration file
+    let log_file_settings_opt = matches
+        .get_one::<String>("log-file")
+        .cloned()
+        .or(settings.get_string("log_file").ok())
+        .map(|log_file_path| log_ops::LogFileSettings {
+            path: std::path::PathBuf::from(log_file_path),
+            format: matches
+                .get_one::<String>("log-format")
+                .cloned()
+                .or(settings.get_string("log_format").ok())
+                .unwrap_or("pattern".to_string()),
+            max_size: settings
+                .get_string("log_file_max_size")
+                .ok()
+                .and_then(|max_size| common::memory_ops::parse_memory_size(&max_size).ok())
+                .unwrap_or(log_ops::LogFileSettings::DEFAULT_MAX_SIZE),
+            max_files: settings
+                .get_int("log_file_max_files")
+                .ok()
+                .map(|max_files| max_files as u32)
+                .unwrap_or(log_ops::LogFileSettings::DEFAULT_MAX_FILES),
+        });
+
+    // --quiet keeps stderr clean for shell pipelines, the log file still gets info records
+    let log_level = if matches.get_flag("quiet") {
+        "error".to_string()
+    } else {
+        log_level
+    };
+
+    // Init logger
+    // env_logger::init();
+    // log4rs::init_file("log4rs.yml", Default::default()).unwrap(); // log4rs file configuration
+    // log4rs programatically configuration
+    for warning in log_ops::configure(log_level, log_target_opt.as_deref(), log_file_settings_opt) {
+        eprintln!("WARNING - {}", warning);
+    }
+
+    shasta::configure_http_trace(
+        matches.get_flag("trace-http"),
+        matches.get_flag("trace-http-body"),
     );
 
-    let mut path_to_manta_configuration_file = PathBuf::from(project_dirs.unwrap().config_dir());
+    // Sessions to reproduce bugs without access to the live system
+    if let Some(session_file_path) = matches.get_one::<String>("record") {
+        if let Err(error) = common::session_ops::configure_record(&PathBuf::from(session_file_path))
+        {
+            eprintln!(
+                "Could not create session file '{}': {}. Exit",
+                session_file_path, error
+            );
+            std::process::exit(1);
+        }
+    }
+    if let Some(session_file_path) = matches.get_one::<String>("replay") {
+        if let Err(error) = common::session_ops::configure_replay(&PathBuf::from(session_file_path))
+        {
+            eprintln!(
+                "Could not read session file '{}': {}. Exit",
+                session_file_path, error
+            );
+            std::process::exit(1);
+        }
+    }
 
-    path_to_manta_configuration_file.push("config.toml"); // ~/.config/manta/config is the file
+    // Hung CSM endpoints fail fast, cli params have precedence over configuration file
+    shasta::configure_http_timeout(
+        Some(
+            matches
+                .get_one::<u64>("timeout")
+                .copied()
+                .or(settings
+                    .get_int("timeout")
+                    .ok()
+                    .map(|timeout| timeout as u64))
+                .unwrap_or(shasta::DEFAULT_HTTP_TIMEOUT_SECS),
+        )
+        .filter(|timeout| *timeout > 0)
+        .map(std::time::Duration::from_secs),
+    );
 
-    log::info!(
-        "Reading manta configuration from {}",
-        &path_to_manta_configuration_file.to_string_lossy()
+    // Development CSM instances with self-signed or rotated certificates
+    shasta::configure_insecure_tls(matches.get_flag("insecure"));
+    if matches.get_flag("insecure") {
+        log::warn!("TLS certificates of CSM are not verified (--insecure)");
+    }
+    common::config_ops::configure_ca_cert(matches.get_one::<String>("cacert").map(PathBuf::from));
+
+    // Spans exported to an OTLP collector (eg Jaeger or Tempo) to analyze slow runs
+    common::trace_ops::configure(settings.get_string("otlp_endpoint").ok());
+
+    // Number formatting in tables, cli params have precedence over configuration file
+    common::format_ops::configure(
+        matches
+            .get_one::<usize>("precision")
+            .copied()
+            .or(settings
+                .get_int("precision")
+                .ok()
+                .map(|precision| precision as usize))
+            .unwrap_or(2),
+        matches
+            .get_one::<String>("thousands-separator")
+            .cloned()
+            .or(settings.get_string("thousands_separator").ok())
+            .as_deref(),
     );
 
-    let settings = common::config_ops::get_configuration();
+    // Column used to sort table rows
+    common::table_ops::configure(matches.get_one::<String>("sort-by"));
 
-    // println!("settings:\n{:#?}", settings);
+    // Colors in tables and layouts, cli params have precedence over configuration file
+    common::color_ops::configure(
+        matches
+            .get_one::<String>("color")
+            .cloned()
+            .or(settings.get_string("color").ok())
+            .as_ref(),
+    );
+
+    // Long tables are paged like git does, only for commands which do not ask for confirmation
+    common::pager_ops::configure(
+        !matches.get_flag("no-pager")
+            && matches.subcommand_name().is_some_and(|subcommand_name| {
+                common::pager_ops::PAGED_SUBCOMMAND_NAME_VEC.contains(&subcommand_name)
+            }),
+    );
+
+    // Memory used to be normalized in counters of 'mem_lcm', now it is accounted by capacity
+    if settings.get_string("mem_lcm").is_ok() {
+        log::warn!("'mem_lcm' in configuration file is ignored, memory is requested by capacity eg memory:512gb");
+    }
 
-    let site_name = settings.get_string("site").unwrap();
-    let site_detail_hashmap = settings.get_table("sites").unwrap();
-    let site_detail_value = site_detail_hashmap
-        .get(&site_name)
-        .unwrap()
-        .clone()
-        .into_table()
-        .unwrap();
+    // Hw profiles referenced in patterns as @<profile name>
+    common::profile_ops::configure(common::profile_ops::get_profile_hashmap_from_config(
+        &settings,
+    ));
+
+    // Site from --site/CLSTR_SITE has precedence over the one in configuration file
+    let site_name_opt = matches
+        .get_one::<String>("site")
+        .cloned()
+        .or(settings.get_string("site").ok());
+
+    let proxy_settings_opt =
+        shasta::ProxySettings::from_config(&settings, site_name_opt.as_deref());
+
+    // mesa builds its own http clients and only reads the proxy from env var SOCKS5
+    if let Some(proxy_settings) = &proxy_settings_opt {
+        let shasta_base_url = matches
+            .get_one::<String>("base-url")
+            .cloned()
+            .or(site_name_opt.as_ref().and_then(|site_name| {
+                settings
+                    .get_string(&format!("sites.{}.shasta_base_url", site_name))
+                    .ok()
+            }));
+
+        if !shasta_base_url
+            .is_some_and(|shasta_base_url| proxy_settings.is_no_proxy_url(&shasta_base_url))
+        {
+            std::env::set_var("SOCKS5", &proxy_settings.socks5_proxy);
+        }
+    }
+
+    shasta::configure_proxy(proxy_settings_opt);
+
+    // Health check reports an invalid site or failed authentication instead of exiting
+    if let Some(cli_health) = matches.subcommand_matches("health") {
+        cli::commands::health::exec(
+            &settings,
+            site_name_opt.as_ref(),
+            cli_health.get_flag("api-versions"),
+        )
+        .await;
+        return Ok(());
+    }
+
+    // Configuration management does not need a valid site nor authentication
+    if let Some(cli_config) = matches.subcommand_matches("config") {
+        cli::process::process_config_cli(
+            cli_config,
+            &settings,
+            site_name_opt.as_ref(),
+            config_error_opt.as_deref(),
+        )
+        .await;
+        return Ok(());
+    }
+
+    // History is a local file, it does not need a valid site nor authentication
+    if let Some(cli_history) = matches.subcommand_matches("history") {
+        common::pager_ops::start();
+        cli::process::process_history_cli(cli_history);
+        return Ok(());
+    }
+
+    // Diff between inventory snapshot files does not need a valid site nor authentication
+    if let Some((diff_name, cli_diff_snapshot)) = matches
+        .subcommand_matches("diff")
+        .and_then(|cli_diff| cli_diff.subcommand())
+        .filter(|(_, cli_diff_snapshot)| !cli_diff_snapshot.get_flag("live"))
+    {
+        common::pager_ops::start();
+        cli::process::process_diff_offline_cli(diff_name, cli_diff_snapshot);
+        return Ok(());
+    }
+
+    // Pattern syntax does not need a valid site nor authentication
+    if let Some(cli_validate_pattern) = matches
+        .subcommand_matches("validate")
+        .and_then(|cli_validate| cli_validate.subcommand_matches("pattern"))
+    {
+        common::pager_ops::start();
+        cli::process::process_validate_pattern_cli(cli_validate_pattern);
+        return Ok(());
+    }
+
+    // With --token and --base-url the site is optional (eg automation without configuration
+    // file), it is then named after the CSM API host
+    let token_base_url_opt = matches
+        .get_one::<String>("token")
+        .zip(matches.get_one::<String>("base-url"));
+
+    let site_name = site_name_opt
+        .or(token_base_url_opt.map(|(_, shasta_base_url)| {
+            reqwest::Url::parse(shasta_base_url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or("default".to_string())
+        }))
+        .unwrap_or_else(|| {
+            eprintln!("Site not defined, please use --site, CLSTR_SITE env var or 'site' in configuration file. Exit");
+            std::process::exit(1);
+        });
+    let site_detail_hashmap = settings.get_table("sites").unwrap_or_default();
+
+    // Lock files preventing concurrent runs on the same HSM groups are keyed by site, shared by
+    // all operators in 'lock_dir' owned by 'lock_group'
+    common::lock_ops::configure(
+        &site_name,
+        settings.get_string("lock_dir").ok(),
+        settings.get_string("lock_group").ok(),
+    );
+
+    // Operator and CSM API requests of each apply, optionally also sent to 'audit_url'
+    common::audit_ops::configure(&site_name, settings.get_string("audit_url").ok());
+
+    // Change events published to Kafka or NATS for downstream automation (eg DNS or monitoring)
+    common::event_ops::configure(
+        common::event_ops::EventSettings::from_config(&settings),
+        &site_name,
+    );
 
     let site_available_vec = site_detail_hashmap.keys().cloned().collect::<Vec<String>>();
 
+    let site_detail_value = match site_detail_hashmap.get(&site_name) {
+        Some(site_detail_value) => site_detail_value.clone().into_table().unwrap(),
+        None if token_base_url_opt.is_some() => {
+            log::info!(
+                "Site '{}' not found in configuration, using --token and --base-url",
+                site_name
+            );
+            Default::default()
+        }
+        None => {
+            eprintln!(
+                "Site '{}' not found in configuration. Sites available: {}. Exit",
+                site_name,
+                site_available_vec.join(", ")
+            );
+            std::process::exit(1);
+        }
+    };
+
     // println!("site_detail_value:\n{:#?}", site_detail_value);
 
-    let shasta_base_url = site_detail_value
-        .get("shasta_base_url")
-        .unwrap()
-        .to_string();
+    // --base-url/CLSTR_BASE_URL has precedence over 'shasta_base_url' of the site
+    let shasta_base_url = matches
+        .get_one::<String>("base-url")
+        .cloned()
+        .or(site_detail_value
+            .get("shasta_base_url")
+            .map(|shasta_base_url| shasta_base_url.to_string()))
+        .unwrap_or_else(|| {
+            eprintln!(
+                "Key 'sites.{}.shasta_base_url' missing in configuration file, use --base-url or add it. Exit",
+                site_name
+            );
+            std::process::exit(1);
+        });
 
-    let keycloak_base_url = site_detail_value
+    // Keycloak is only needed to request API tokens, not with a token injected with --token
+    let keycloak_base_url_opt = site_detail_value
         .get("keycloak_base_url")
-        .unwrap()
-        .to_string();
+        .map(|keycloak_base_url| keycloak_base_url.to_string());
+    let get_keycloak_base_url_or_exit = || {
+        keycloak_base_url_opt.clone().unwrap_or_else(|| {
+            eprintln!(
+                "Key 'sites.{}.keycloak_base_url' missing in configuration file, it is needed to request API tokens (or use --token). Exit",
+                site_name
+            );
+            std::process::exit(1);
+        })
+    };
 
-    let log_level = settings.get_string("log").unwrap_or("error".to_string());
+    // Racks marked for decommission, nodes in these racks should not be allocated to tenants
+    let decommissioned_rack_vec = site_detail_value
+        .get("decommissioned_racks")
+        .and_then(|rack_vec| rack_vec.clone().into_array().ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|rack| rack.to_string())
+        .collect::<Vec<String>>();
 
-    // Init logger
-    // env_logger::init();
-    // log4rs::init_file("log4rs.yml", Default::default()).unwrap(); // log4rs file configuration
-    log_ops::configure(log_level); // log4rs programatically configuration
+    // Nodes never moved between HSM groups (eg login nodes), accepts hostlist expressions
+    let excluded_xname_vec = site_detail_value
+        .get("excluded_xnames")
+        .and_then(|xname_vec| xname_vec.clone().into_array().ok())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|xnames| {
+            let xnames = xnames.to_string();
+            common::hostlist_ops::expand_hostlist(&xnames).unwrap_or_else(|error| {
+                eprintln!(
+                    "Could not expand 'excluded_xnames' entry '{}' in configuration file: {}. Exit",
+                    xnames, error
+                );
+                std::process::exit(1);
+            })
+        })
+        .collect::<Vec<String>>();
+
+    // HSM groups delegated operators are restricted to, all HSM groups if missing
+    let settings_hsm_available_vec = settings
+        .get_array("hsm_available")
+        .unwrap_or(Vec::new())
+        .into_iter()
+        .map(|hsm_group| hsm_group.to_string())
+        .collect::<Vec<String>>();
+
+    common::tenant_ops::configure(settings_hsm_available_vec);
+
+    // Without the site in configuration file there is no root certificate of the site, CSM API
+    // certificate is verified against --cacert or the public CAs
+    let shasta_root_cert = if site_detail_hashmap.contains_key(&site_name)
+        || matches.get_one::<String>("cacert").is_some()
+    {
+        common::config_ops::get_csm_root_cert_content(&site_name).unwrap_or_else(|error| {
+            eprintln!("Could not read CSM root certificate: {}. Exit", error);
+            std::process::exit(1);
+        })
+    } else {
+        Vec::new()
+    };
 
-    if let Ok(socks_proxy) = settings.get_string("socks5_proxy") {
-        std::env::set_var("SOCKS5", socks_proxy);
+    if let Some(cli_reconcile) = matches.subcommand_matches("reconcile") {
+        cli::process::process_reconcile_cli(
+            cli_reconcile,
+            &settings,
+            &site_name,
+            &shasta_base_url,
+            &shasta_root_cert,
+            &get_keycloak_base_url_or_exit(),
+            &excluded_xname_vec,
+        )
+        .await;
+        return Ok(());
     }
 
-    let settings_hsm_group_opt = settings.get_string("hsm_group").ok();
+    if let Some(cli_serve) = matches.subcommand_matches("serve") {
+        // No token to detect the CSM version, only 'csm_version' of the site is used
+        common::csm_version_ops::configure(site_detail_value.get("csm_version").map(
+            |csm_version| {
+                common::csm_version_ops::CsmVersion::parse(&csm_version.to_string()).unwrap_or_else(
+                    |error| {
+                        eprintln!("{} in configuration file. Exit", error);
+                        std::process::exit(1);
+                    },
+                )
+            },
+        ));
 
-    /* let settings_hsm_available_vec = settings
-    .get_array("hsm_available")
-    .unwrap_or(Vec::new())
-    .into_iter()
-    .map(|hsm_group| hsm_group.into_string().unwrap())
-    .collect::<Vec<String>>(); */
+        cli::process::process_serve_cli(
+            cli_serve,
+            &shasta_base_url,
+            &shasta_root_cert,
+            &excluded_xname_vec,
+        )
+        .await;
+        return Ok(());
+    }
 
-    let shasta_root_cert = common::config_ops::get_csm_root_cert_content(&site_name);
+    // A token injected with --token/CLSTR_TOKEN (eg service token in automation) skips Keycloak,
+    // replayed sessions need no token
+    let shasta_token = match matches.get_one::<String>("token") {
+        Some(shasta_token) => shasta_token.clone(),
+        None if common::session_ops::is_replaying() => "replay".to_string(),
+        None => {
+            common::auth_ops::get_api_token(
+                &settings,
+                &site_name,
+                &shasta_base_url,
+                &shasta_root_cert,
+                &get_keycloak_base_url_or_exit(),
+            )
+            .await?
+        }
+    };
 
-    let shasta_token = mesa::common::authentication::get_api_token(
+    // CSM version picks APIs and concurrency limits, 'csm_version' of the site skips detection
+    let csm_version_opt = common::csm_version_ops::get_csm_version_or_detect(
+        site_detail_value
+            .get("csm_version")
+            .map(|csm_version| csm_version.to_string())
+            .as_deref(),
+        &shasta_token,
         &shasta_base_url,
         &shasta_root_cert,
-        &keycloak_base_url,
     )
-    .await?;
+    .await
+    .unwrap_or_else(|error| {
+        eprintln!("{} in configuration file. Exit", error);
+        std::process::exit(1);
+    });
+    common::csm_version_ops::configure(csm_version_opt);
 
-    // Process input params
-    let matches = crate::cli::build::build_cli(settings_hsm_group_opt.as_ref()).get_matches();
-    let cli_result = crate::cli::process::process_cli(
+    // Subcommands eg 'apply hsm-group', used to name the command span
+    let mut command_name_vec = Vec::new();
+    let mut subcommand_matches = &matches;
+    while let Some((command_name, matches_aux)) = subcommand_matches.subcommand() {
+        command_name_vec.push(command_name.to_string());
+        subcommand_matches = matches_aux;
+    }
+
+    common::pager_ops::start();
+
+    let cli_result = common::event_ops::scope(cli::process::process_cli(
         matches,
         &shasta_token,
         &shasta_base_url,
         &shasta_root_cert,
         settings_hsm_group_opt.as_ref(),
-    )
+        &decommissioned_rack_vec,
+        &excluded_xname_vec,
+        &common::slurm_ops::SlurmSettings::from_config(&settings),
+    ))
+    .instrument(tracing::info_span!(
+        "command",
+        command = command_name_vec.join(" "),
+        site = site_name
+    ))
     .await;
 
+    common::trace_ops::export().await;
+
     match cli_result {
         Ok(_) => Ok(()),
         Err(e) => panic!("{}", e),
@@ -0,0 +1,345 @@
+pub mod bos;
+pub mod cfs;
+pub mod fas;
+pub mod hsm;
+pub mod pcs;
+pub mod sls;
+
+use std::{
+    error::Error,
+    future::Future,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::common::{audit_ops, log_ops, session_ops};
+
+/// `--trace-http` and `--trace-http-body`
+static HTTP_TRACE: OnceLock<(bool, bool)> = OnceLock::new();
+
+/// Prints each CSM API request sent by clstr (method, url, status and latency) to stderr for the
+/// rest of the execution, with `trace_http_body` also request and response bodies with secrets
+/// redacted. Requests sent by mesa are not traced
+pub fn configure_http_trace(trace_http: bool, trace_http_body: bool) {
+    let _ = HTTP_TRACE.set((trace_http || trace_http_body, trace_http_body));
+}
+
+/// `--timeout`
+static HTTP_TIMEOUT: OnceLock<Option<Duration>> = OnceLock::new();
+
+/// Default `--timeout` in seconds
+pub const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 120;
+
+/// Sets the maximum time a CSM API request may take for the rest of the execution, requests are
+/// never cancelled if None
+pub fn configure_http_timeout(timeout_opt: Option<Duration>) {
+    let _ = HTTP_TIMEOUT.set(timeout_opt);
+}
+
+fn get_http_timeout() -> Option<Duration> {
+    HTTP_TIMEOUT
+        .get()
+        .copied()
+        .unwrap_or(Some(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECS)))
+}
+
+/// Awaits a request sent by mesa failing if it takes longer than `--timeout`. mesa builds its own
+/// http clients so the timeout can't be set in them
+pub async fn with_timeout<T, E: Into<Box<dyn Error>>>(
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, Box<dyn Error>> {
+    let result = match get_http_timeout() {
+        Some(timeout) => tokio::time::timeout(timeout, request).await.map_err(|_| {
+            format!(
+                "CSM API request timed out after {}s, see --timeout",
+                timeout.as_secs()
+            )
+        })?,
+        None => request.await,
+    };
+
+    result.map_err(Into::into)
+}
+
+/// `socks5_proxy` and `no_proxy` of the site
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxySettings {
+    /// eg socks5h://localhost:1080
+    pub socks5_proxy: String,
+    /// Hosts not routed through the proxy: host names (also matching their subdomains), domains
+    /// starting with '.' or '*.', IP addresses or '*' for all hosts
+    pub no_proxy_vec: Vec<String>,
+}
+
+impl ProxySettings {
+    /// `sites.<site>.socks5_proxy` and `sites.<site>.no_proxy` (list or comma separated string).
+    /// Top level `socks5_proxy` is still read if the site has none. None if no proxy is configured
+    pub fn from_config(settings: &config::Config, site_name_opt: Option<&str>) -> Option<Self> {
+        let site_key = |key: &str| format!("sites.{}.{}", site_name_opt.unwrap_or_default(), key);
+
+        let socks5_proxy = site_name_opt
+            .and_then(|_| settings.get_string(&site_key("socks5_proxy")).ok())
+            .or_else(|| {
+                let socks5_proxy_opt = settings.get_string("socks5_proxy").ok();
+                if socks5_proxy_opt.is_some() {
+                    log::warn!("Top level 'socks5_proxy' in configuration file is deprecated, please move it to 'sites.<site>.socks5_proxy'");
+                }
+                socks5_proxy_opt
+            })?;
+
+        let no_proxy_vec = site_name_opt
+            .and_then(|_| settings.get(&site_key("no_proxy")).ok())
+            .map(
+                |no_proxy_value: config::Value| match no_proxy_value.clone().into_array() {
+                    Ok(no_proxy_value_vec) => no_proxy_value_vec
+                        .into_iter()
+                        .map(|no_proxy| no_proxy.to_string())
+                        .collect(),
+                    Err(_) => no_proxy_value
+                        .to_string()
+                        .split(',')
+                        .map(|no_proxy| no_proxy.trim().to_string())
+                        .filter(|no_proxy| !no_proxy.is_empty())
+                        .collect(),
+                },
+            )
+            .unwrap_or_default();
+
+        Some(Self {
+            socks5_proxy,
+            no_proxy_vec,
+        })
+    }
+
+    /// Whether requests to `url` skip the proxy
+    pub fn is_no_proxy_url(&self, url: &str) -> bool {
+        let Some(host) = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        else {
+            return false;
+        };
+
+        self.no_proxy_vec.iter().any(|no_proxy| {
+            let domain = no_proxy.trim_start_matches('*').trim_start_matches('.');
+
+            no_proxy.eq("*")
+                || host.eq_ignore_ascii_case(domain)
+                || host
+                    .to_lowercase()
+                    .ends_with(&format!(".{}", domain.to_lowercase()))
+        })
+    }
+}
+
+/// `--insecure`
+static INSECURE_TLS: OnceLock<bool> = OnceLock::new();
+
+/// Accepts any TLS certificate for the rest of the execution (eg development CSM instances with
+/// self-signed certificates). Requests sent by mesa still verify the root certificate
+pub fn configure_insecure_tls(insecure: bool) {
+    let _ = INSECURE_TLS.set(insecure);
+}
+
+pub fn is_insecure_tls() -> bool {
+    INSECURE_TLS.get().copied().unwrap_or_default()
+}
+
+/// Proxy of the site
+static PROXY_SETTINGS: OnceLock<Option<ProxySettings>> = OnceLock::new();
+
+/// Routes requests sent by clstr through the proxy for the rest of the execution
+pub fn configure_proxy(proxy_settings_opt: Option<ProxySettings>) {
+    let _ = PROXY_SETTINGS.set(proxy_settings_opt);
+}
+
+/// Builds an http client trusting the CSM root certificate. If the site has a SOCKS5 proxy, then
+/// requests (except to `no_proxy` hosts) are routed through it. Requests fail if they take longer
+/// than `--timeout`. With `--insecure` certificates are not verified
+pub fn build_client(shasta_root_cert: &[u8]) -> Result<reqwest::Client, Box<dyn Error>> {
+    let mut client_builder = if is_insecure_tls() {
+        reqwest::Client::builder().danger_accept_invalid_certs(true)
+    } else if shasta_root_cert.is_empty() {
+        // No root certificate of the site, eg --token and --base-url without configuration file
+        reqwest::Client::builder()
+    } else {
+        reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(shasta_root_cert)?)
+    };
+
+    if let Some(timeout) = get_http_timeout() {
+        client_builder = client_builder.timeout(timeout);
+    }
+
+    if let Some(proxy_settings) = PROXY_SETTINGS.get().and_then(Option::as_ref) {
+        log::debug!("SOCKS5 enabled");
+        let socks5_proxy = reqwest::Proxy::all(&proxy_settings.socks5_proxy)?.no_proxy(
+            reqwest::NoProxy::from_string(&proxy_settings.no_proxy_vec.join(",")),
+        );
+
+        client_builder = client_builder.proxy(socks5_proxy);
+    }
+
+    Ok(client_builder.build()?)
+}
+
+/// CSM API connection shared by concurrent tasks (eg hw inventory scans). The http client is built
+/// once so tasks reuse its TLS connections instead of cloning the token and root certificate and
+/// building a client per request
+#[derive(Debug, Clone)]
+pub struct ShastaClient {
+    pub token: String,
+    pub base_url: String,
+    pub client: reqwest::Client,
+}
+
+impl ShastaClient {
+    pub fn new(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+    ) -> Result<Arc<Self>, Box<dyn Error>> {
+        Ok(Arc::new(Self {
+            token: shasta_token.to_string(),
+            base_url: shasta_base_url.to_string(),
+            client: build_client(shasta_root_cert)?,
+        }))
+    }
+}
+
+/// `send` which traces the request if enabled (see `configure_http_trace`), records or replays
+/// the response with `--record` and `--replay` (see `session_ops`) and records the request in the
+/// audit trail of node moves (see `audit_ops`)
+#[allow(async_fn_in_trait)]
+pub trait SendTraced {
+    async fn send_traced(self) -> reqwest::Result<reqwest::Response>;
+}
+
+impl SendTraced for reqwest::RequestBuilder {
+    async fn send_traced(self) -> reqwest::Result<reqwest::Response> {
+        let (trace_http, trace_http_body) = HTTP_TRACE.get().copied().unwrap_or_default();
+
+        let recording = session_ops::is_recording();
+        let auditing = audit_ops::is_auditing();
+
+        if !trace_http && !recording && !session_ops::is_replaying() && !auditing {
+            return self.send().await;
+        }
+
+        let (client, request_rslt) = self.build_split();
+        let request = request_rslt?;
+
+        let method = request.method().clone();
+        let url = log_ops::redact_secrets(request.url().as_str());
+
+        if trace_http_body {
+            if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+                eprintln!(
+                    "HTTP - {} {} request body: {}",
+                    method,
+                    url,
+                    log_ops::redact_secrets(&String::from_utf8_lossy(body))
+                );
+            }
+        }
+
+        let start = Instant::now();
+
+        let request_url = request.url().clone();
+
+        // Request is consumed when sent, its body is kept for the audit trail
+        let request_body_opt = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .filter(|_| auditing)
+            .map(<[u8]>::to_vec);
+
+        let resp = match session_ops::replay(&method, &request_url) {
+            Some(resp) => resp,
+            None => match client.execute(request).await {
+                Ok(resp) => resp,
+                Err(error) => {
+                    if trace_http {
+                        eprintln!(
+                            "HTTP - {} {} -> error after {} ms: {}",
+                            method,
+                            url,
+                            start.elapsed().as_millis(),
+                            error
+                        );
+                    }
+                    audit_ops::record_api_call(
+                        &method,
+                        &request_url,
+                        request_body_opt.as_deref(),
+                        Err(error.to_string()),
+                    );
+                    return Err(error);
+                }
+            },
+        };
+
+        audit_ops::record_api_call(
+            &method,
+            &request_url,
+            request_body_opt.as_deref(),
+            Ok(resp.status().as_u16()),
+        );
+
+        if trace_http {
+            eprintln!(
+                "HTTP - {} {} -> {} ({} ms)",
+                method,
+                url,
+                resp.status(),
+                start.elapsed().as_millis()
+            );
+        }
+
+        if !trace_http_body && !recording {
+            return Ok(resp);
+        }
+
+        // Body is consumed to print or record it, response is rebuilt with the same status and
+        // headers
+        let status = resp.status();
+        let header_map = resp.headers().clone();
+        let body = resp.bytes().await?;
+
+        if trace_http_body {
+            eprintln!(
+                "HTTP - {} {} response body: {}",
+                method,
+                url,
+                log_ops::redact_secrets(&String::from_utf8_lossy(&body))
+            );
+        }
+
+        session_ops::record(&method, &request_url, status.as_u16(), &body);
+
+        let mut http_resp = http::Response::new(body);
+        *http_resp.status_mut() = status;
+        *http_resp.headers_mut() = header_map;
+
+        Ok(reqwest::Response::from(http_resp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_no_proxy_url() {
+        let proxy_settings = ProxySettings {
+            socks5_proxy: "socks5h://localhost:1080".to_string(),
+            no_proxy_vec: vec!["cscs.ch".to_string(), "*.alps.local".to_string()],
+        };
+
+        assert!(proxy_settings.is_no_proxy_url("https://api.cmn.cscs.ch/apis"));
+        assert!(proxy_settings.is_no_proxy_url("https://CSCS.ch"));
+        assert!(proxy_settings.is_no_proxy_url("https://auth.alps.local/keycloak"));
+        assert!(!proxy_settings.is_no_proxy_url("https://notcscs.ch/apis"));
+        assert!(!proxy_settings.is_no_proxy_url("https://api.example.com"));
+    }
+}
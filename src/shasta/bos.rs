@@ -0,0 +1,132 @@
+pub mod template {
+    pub mod http_client {
+
+        use std::error::Error;
+
+        use serde_json::Value;
+
+        use crate::shasta::SendTraced;
+
+        /// Get all BOS session templates using --> https://apidocs.svc.cscs.ch/paas/bos/operation/get_v2_sessiontemplates/
+        pub async fn get_all(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+        ) -> Result<Vec<Value>, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = format!("{}/bos/v2/sessiontemplates", shasta_base_url);
+
+            let resp = client
+                .get(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Vec<Value>>().await?)
+            } else {
+                Err(resp.text().await?.into()) // Black magic conversion from Err(Box::new("my error msg")) which does not
+            }
+        }
+
+        /// Create or replace a BOS session template using --> https://apidocs.svc.cscs.ch/paas/bos/operation/put_v2_sessiontemplate/
+        pub async fn put(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+            bos_sessiontemplate_name: &str,
+            bos_sessiontemplate_value: &Value,
+        ) -> Result<Value, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = format!(
+                "{}/bos/v2/sessiontemplates/{}",
+                shasta_base_url, bos_sessiontemplate_name
+            );
+
+            let resp = client
+                .put(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .json(bos_sessiontemplate_value)
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Value>().await?)
+            } else {
+                Err(format!("{} {}", resp.status(), resp.text().await?).into())
+            }
+        }
+    }
+
+    pub mod utils {
+
+        use serde_json::Value;
+
+        /// Returns the list of xnames explicitly referenced in the boot sets of a BOS session
+        /// template (boot_sets.<name>.node_list)
+        pub fn get_node_list_from_bos_sessiontemplate_value(
+            bos_sessiontemplate_value: &Value,
+        ) -> Vec<String> {
+            bos_sessiontemplate_value["boot_sets"]
+                .as_object()
+                .map(|boot_set_map| {
+                    boot_set_map
+                        .values()
+                        .flat_map(|boot_set| {
+                            boot_set["node_list"]
+                                .as_array()
+                                .cloned()
+                                .unwrap_or_default()
+                        })
+                        .filter_map(|xname| xname.as_str().map(|xname| xname.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+}
+
+pub mod session {
+    pub mod http_client {
+
+        use std::error::Error;
+
+        use serde_json::Value;
+
+        use crate::shasta::SendTraced;
+
+        /// Create a BOS session using --> https://apidocs.svc.cscs.ch/paas/bos/operation/post_v2_session/
+        /// `limit` is a comma separated list of xnames the operation is restricted to
+        pub async fn post(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+            bos_sessiontemplate_name: &str,
+            operation: &str,
+            limit: &str,
+        ) -> Result<Value, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = format!("{}/bos/v2/sessions", shasta_base_url);
+
+            let resp = client
+                .post(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .json(&serde_json::json!({
+                    "template_name": bos_sessiontemplate_name,
+                    "operation": operation,
+                    "limit": limit,
+                }))
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Value>().await?)
+            } else {
+                Err(format!("{} {}", resp.status(), resp.text().await?).into())
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+pub mod component {
+    pub mod http_client {
+
+        use std::error::Error;
+
+        use reqwest::Url;
+        use serde_json::Value;
+
+        use crate::shasta::SendTraced;
+
+        /// Get CFS components using --> https://apidocs.svc.cscs.ch/paas/cfs/operation/get_components_v2/
+        /// ids is a list of xnames
+        pub async fn get_multiple_components(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+            ids: &[String],
+        ) -> Result<Vec<Value>, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = Url::parse_with_params(
+                &format!("{}/cfs/v2/components", shasta_base_url),
+                &[("ids", ids.join(","))],
+            )?;
+
+            let resp = client
+                .get(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Vec<Value>>().await?)
+            } else {
+                Err(resp.text().await?.into()) // Black magic conversion from Err(Box::new("my error msg")) which does not
+            }
+        }
+
+        /// Patch CFS components using --> https://apidocs.svc.cscs.ch/paas/cfs/operation/patch_components_v2/
+        /// ids is a list of xnames, `component_patch_value` is applied to all of them
+        pub async fn patch_multiple_components(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+            ids: &[String],
+            component_patch_value: &Value,
+        ) -> Result<Value, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = format!("{}/cfs/v2/components", shasta_base_url);
+
+            let resp = client
+                .patch(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .json(&serde_json::json!({
+                    "patch": component_patch_value,
+                    "filters": { "ids": ids.join(",") },
+                }))
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Value>().await?)
+            } else {
+                Err(format!("{} {}", resp.status(), resp.text().await?).into())
+            }
+        }
+    }
+}
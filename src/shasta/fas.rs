@@ -0,0 +1,64 @@
+pub mod snapshot {
+    pub mod http_client {
+
+        use std::error::Error;
+
+        use serde_json::Value;
+
+        use crate::shasta::SendTraced;
+
+        /// Create a FAS snapshot of the firmware versions of some devices using --> https://apidocs.svc.cscs.ch/iaas/firmware-action/operation/post_snapshots/
+        /// xname_vec is a list of BMC xnames
+        pub async fn post(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+            snapshot_name: &str,
+            xname_vec: &[String],
+        ) -> Result<Value, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = format!("{}/fas/v1/snapshots", shasta_base_url);
+
+            let resp = client
+                .post(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .json(&serde_json::json!({
+                    "name": snapshot_name,
+                    "stateComponentFilter": { "xnames": xname_vec },
+                }))
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Value>().await?)
+            } else {
+                Err(format!("{} {}", resp.status(), resp.text().await?).into())
+            }
+        }
+
+        /// Get a FAS snapshot using --> https://apidocs.svc.cscs.ch/iaas/firmware-action/operation/get_snapshot/
+        pub async fn get(
+            shasta_token: &str,
+            shasta_base_url: &str,
+            shasta_root_cert: &[u8],
+            snapshot_name: &str,
+        ) -> Result<Value, Box<dyn Error>> {
+            let client = crate::shasta::build_client(shasta_root_cert)?;
+
+            let api_url = format!("{}/fas/v1/snapshots/{}", shasta_base_url, snapshot_name);
+
+            let resp = client
+                .get(api_url)
+                .header("Authorization", format!("Bearer {}", shasta_token))
+                .send_traced()
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(resp.json::<Value>().await?)
+            } else {
+                Err(format!("{} {}", resp.status(), resp.text().await?).into())
+            }
+        }
+    }
+}
@@ -3,28 +3,28 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Default)]
-struct HsmGroup {
-    label: String,
+pub struct HsmGroup {
+    pub label: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    description: Option<String>,
+    pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    tags: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    members: Option<Vec<Member>>,
+    pub members: Option<Member>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
-struct Member {
+pub struct Member {
     #[serde(skip_serializing_if = "Option::is_none")]
-    ids: Option<Vec<String>>,
+    pub ids: Option<Vec<String>>,
 }
 
-/* impl HsmGroup {
+impl HsmGroup {
     pub fn new(
         label: String,
         description: Option<String>,
         tags: Option<Vec<String>>,
-        members: Option<Vec<Member>>,
+        members: Option<Member>,
     ) -> Self {
         Self {
             label,
@@ -33,13 +33,13 @@ struct Member {
             members,
         }
     }
-} */
+}
 
-/* impl Member {
+impl Member {
     pub fn new(ids: Option<Vec<String>>) -> Self {
         Self { ids }
     }
-} */
+}
 
 pub mod http_client {
 
@@ -48,38 +48,26 @@ pub mod http_client {
     use reqwest::Url;
     use serde_json::Value;
 
+    use crate::shasta::{SendTraced, ShastaClient};
+
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
     pub async fn get_all_hsm_groups(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
     ) -> Result<Vec<Value>, Box<dyn Error>> {
-        let client;
-
-        let client_builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
-
-        // Build client
-        if std::env::var("SOCKS5").is_ok() {
-            // socks5 proxy
-            log::debug!("SOCKS5 enabled");
-            let socks5proxy = reqwest::Proxy::all(std::env::var("SOCKS5").unwrap())?;
-
-            // rest client to authenticate
-            client = client_builder.proxy(socks5proxy).build()?;
-        } else {
-            client = client_builder.build()?;
-        }
-
-        let json_response: Value;
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
         let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups";
 
         let resp = client
             .get(url_api)
             .header("Authorization", format!("Bearer {}", shasta_token))
-            .send()
+            .send_traced()
             .await?;
 
-        if resp.status().is_success() {
-            json_response = serde_json::from_str(&resp.text().await?)?;
+        let json_response: Value = if resp.status().is_success() {
+            serde_json::from_str(&resp.text().await?)?
         } else {
             return Err(resp.text().await?.into()); // Black magic conversion from Err(Box::new("my error msg")) which does not
         };
@@ -87,23 +75,56 @@ pub mod http_client {
         Ok(json_response.as_array().unwrap().to_owned())
     }
 
+    /// Get HSM groups using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupsGet/
+    /// NOTE: returns the HSM group which label is hsm_group_name_opt, empty if it does not exist, or
+    /// all HSM groups if None
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_hsm_group_vec(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hsm_group_name_opt: Option<&String>,
+    ) -> Result<Vec<Value>, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups";
+
+        let mut request = client
+            .get(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token));
+
+        if let Some(hsm_group_name) = hsm_group_name_opt {
+            request = request.query(&[("group", hsm_group_name)]);
+        }
+
+        let resp = request.send_traced().await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Vec<Value>>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
     /// Get list of HSM groups using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupsGet/
     /// NOTE: this returns all HSM groups which name contains hsm_groupu_name param value
     pub async fn get_hsm_groups(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
         hsm_group_name: Option<&String>,
     ) -> Result<Vec<Value>, Box<dyn Error>> {
-        let json_response = get_all_hsm_groups(shasta_token, shasta_base_url).await?;
+        let json_response =
+            get_all_hsm_groups(shasta_token, shasta_base_url, shasta_root_cert).await?;
 
         let mut hsm_groups: Vec<Value> = Vec::new();
 
-        if hsm_group_name.is_some() {
+        if let Some(hsm_group_name) = hsm_group_name {
             for hsm_group in json_response {
                 if hsm_group["label"]
                     .as_str()
                     .unwrap()
-                    .contains(hsm_group_name.unwrap())
+                    .contains(hsm_group_name)
                 {
                     hsm_groups.push(hsm_group.clone());
                 }
@@ -114,64 +135,248 @@ pub mod http_client {
     }
 
     /// Get list of HSM group using --> shttps://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupsGet/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
     pub async fn get_hsm_group(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
         hsm_group_name: &str,
     ) -> Result<Value, Box<dyn Error>> {
-        let client;
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
-        let client_builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+        let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups/" + hsm_group_name;
 
-        // Build client
-        if std::env::var("SOCKS5").is_ok() {
-            // socks5 proxy
-            log::debug!("SOCKS5 enabled");
-            let socks5proxy = reqwest::Proxy::all(std::env::var("SOCKS5").unwrap())?;
+        let resp = client
+            .get(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
 
-            // rest client to authenticate
-            client = client_builder.proxy(socks5proxy).build()?;
+        if resp.status().is_success() {
+            Ok(resp.json().await?)
+            //json_response = serde_json::from_str(&resp.text().await?)?;
         } else {
-            client = client_builder.build()?;
+            Err(resp.text().await?.into()) // Black magic conversion from Err(Box::new("my error msg")) which does not
         }
+    }
 
-        let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups/" + hsm_group_name;
+    /// Create HSM group using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupsPost/
+    pub async fn post_hsm_group(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hsm_group: &super::HsmGroup,
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups";
 
         let resp = client
-            .get(url_api)
+            .post(url_api)
             .header("Authorization", format!("Bearer {}", shasta_token))
-            .send()
+            .json(hsm_group)
+            .send_traced()
             .await?;
 
         if resp.status().is_success() {
             Ok(resp.json().await?)
-            //json_response = serde_json::from_str(&resp.text().await?)?;
         } else {
-            Err(resp.text().await?.into()) // Black magic conversion from Err(Box::new("my error msg")) which does not
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
         }
     }
 
-    /// Fetches node/compnent details using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doComponentsGet/
-    pub async fn get_component_status(
+    /// Delete HSM group using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupDelete/
+    pub async fn delete_hsm_group(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hsm_group_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups/" + hsm_group_name;
+
+        let resp = client
+            .delete(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Add a member to a HSM group using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupMembersPost/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn post_hsm_group_member(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hsm_group_name: &str,
+        xname: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api =
+            shasta_base_url.to_owned() + "/smd/hsm/v2/groups/" + hsm_group_name + "/members";
+
+        let resp = client
+            .post(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .json(&serde_json::json!({ "id": xname }))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Remove a member from a HSM group using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupMemberDelete/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn delete_hsm_group_member(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hsm_group_name: &str,
         xname: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api = shasta_base_url.to_owned()
+            + "/smd/hsm/v2/groups/"
+            + hsm_group_name
+            + "/members/"
+            + xname;
+
+        let resp = client
+            .delete(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Update HSM group description and/or tags using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doGroupPatch/
+    /// NOTE: members can't be changed through this endpoint
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn patch_hsm_group(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hsm_group_name: &str,
+        hsm_group_patch: &Value,
+    ) -> Result<(), Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api = shasta_base_url.to_owned() + "/smd/hsm/v2/groups/" + hsm_group_name;
+
+        let resp = client
+            .patch(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .json(hsm_group_patch)
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Reserves components using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doLocksReservationsCreate/
+    /// Reservations expire after `reservation_duration` minutes (1 to 15) if they are not
+    /// released. Returns None if the HSM locks API is not available
+    /// NOTE: with 'rigid' processing model no component is reserved if any of them is already
+    /// reserved or locked
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn post_locks_reservations(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        xname_vec: &[String],
+        reservation_duration: u64,
+    ) -> Result<Option<Value>, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let url_api = format!("{}/smd/hsm/v2/locks/reservations", shasta_base_url);
+
+        let resp = client
+            .post(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .json(&serde_json::json!({
+                "ComponentIDs": xname_vec,
+                "ProcessingModel": "rigid",
+                "ReservationDuration": reservation_duration
+            }))
+            .send_traced()
+            .await?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            Ok(None)
+        } else if resp.status().is_success() {
+            Ok(Some(resp.json().await?))
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Releases reservations using --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doLocksReservationsRelease/
+    /// `reservation_key_vec` are (xname, reservation key) pairs returned when the components were
+    /// reserved
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert, reservation_key_vec))]
+    pub async fn post_locks_reservations_release(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        reservation_key_vec: &[(String, String)],
     ) -> Result<Value, Box<dyn Error>> {
-        let client;
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
-        let client_builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+        let url_api = format!("{}/smd/hsm/v2/locks/reservations/release", shasta_base_url);
 
-        // Build client
-        if std::env::var("SOCKS5").is_ok() {
-            // socks5 proxy
-            log::debug!("SOCKS5 enabled");
-            let socks5proxy = reqwest::Proxy::all(std::env::var("SOCKS5").unwrap())?;
+        let resp = client
+            .post(url_api)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .json(&serde_json::json!({
+                "ReservationKeys": reservation_key_vec
+                    .iter()
+                    .map(|(xname, reservation_key)| serde_json::json!({
+                        "ID": xname,
+                        "Key": reservation_key
+                    }))
+                    .collect::<Vec<Value>>(),
+                "ProcessingModel": "flexible"
+            }))
+            .send_traced()
+            .await?;
 
-            // rest client to authenticate
-            client = client_builder.proxy(socks5proxy).build()?;
+        if resp.status().is_success() {
+            Ok(resp.json().await?)
         } else {
-            client = client_builder.build()?;
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
         }
+    }
+
+    /// Fetches node/compnent details using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doComponentsGet/
+    pub async fn get_component_status(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        xname: &str,
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
         let resp = client
             .get(format!(
@@ -179,7 +384,7 @@ pub mod http_client {
                 shasta_base_url, xname
             ))
             .header("Authorization", format!("Bearer {}", shasta_token))
-            .send()
+            .send_traced()
             .await?;
 
         if resp.status().is_success() {
@@ -193,37 +398,56 @@ pub mod http_client {
     }
 
     /// Fetches nodes/compnents details using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doComponentsGet/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
     pub async fn get_components_status(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
         xnames: Vec<String>,
     ) -> Result<Value, Box<dyn Error>> {
-        let client;
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
-        let client_builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+        let url_params: Vec<_> = xnames.iter().map(|xname| ("id", xname)).collect();
+        let api_url = Url::parse_with_params(
+            &format!("{}/smd/hsm/v2/State/Components", shasta_base_url),
+            &url_params,
+        )?;
 
-        // Build client
-        if std::env::var("SOCKS5").is_ok() {
-            // socks5 proxy
-            log::debug!("SOCKS5 enabled");
-            let socks5proxy = reqwest::Proxy::all(std::env::var("SOCKS5").unwrap())?;
+        let resp = client
+            .get(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
 
-            // rest client to authenticate
-            client = client_builder.proxy(socks5proxy).build()?;
+        if resp.status().is_success() {
+            Ok(serde_json::from_str(&resp.text().await?)?)
         } else {
-            client = client_builder.build()?;
+            Err(resp.json::<Value>().await?["detail"]
+                .as_str()
+                .unwrap()
+                .into()) // Black magic conversion from Err(Box::new("my error msg")) which does not
         }
+    }
+
+    /// Fetches all compnents of a type (eg Node) in a single call using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doComponentsGet/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_all_components_status(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        component_type: &str,
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
-        let url_params: Vec<_> = xnames.iter().map(|xname| ("id", xname)).collect();
         let api_url = Url::parse_with_params(
             &format!("{}/smd/hsm/v2/State/Components", shasta_base_url),
-            &url_params,
+            &[("type", component_type)],
         )?;
 
         let resp = client
             .get(api_url)
             .header("Authorization", format!("Bearer {}", shasta_token))
-            .send()
+            .send_traced()
             .await?;
 
         if resp.status().is_success() {
@@ -236,26 +460,159 @@ pub mod http_client {
         }
     }
 
-    pub async fn get_hw_inventory(
+    /// Fetches the hw inventory of all components of a type (eg Node) under a parent component (eg
+    /// a chassis, or the node itself) in a single call using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doHWInvByLocationQueryGet/
+    /// Uses the shared client of concurrent hw inventory scans
+    #[tracing::instrument(skip(shasta_client))]
+    pub async fn get_hw_inventory_query(
+        shasta_client: &ShastaClient,
+        xname: &str,
+        component_type: &str,
+    ) -> Result<Value, Box<dyn Error>> {
+        let api_url = Url::parse_with_params(
+            &format!(
+                "{}/smd/hsm/v2/Inventory/Hardware/Query/{}",
+                shasta_client.base_url, xname
+            ),
+            &[("type", component_type), ("format", "NestNodesOnly")],
+        )?;
+
+        let resp = shasta_client
+            .client
+            .get(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_client.token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Value>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Fetches the HSM groups and partition of each node in one call using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doMembershipsGet/
+    /// All nodes if `xname_vec` is empty
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_memberships(
         shasta_token: &str,
         shasta_base_url: &str,
-        xname: &str,
+        shasta_root_cert: &[u8],
+        xname_vec: &[String],
+    ) -> Result<Vec<Value>, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let mut url_params: Vec<(&str, &str)> = xname_vec
+            .iter()
+            .map(|xname| ("id", xname.as_str()))
+            .collect();
+        url_params.push(("type", "Node"));
+
+        let api_url = Url::parse_with_params(
+            &format!("{}/smd/hsm/v2/memberships", shasta_base_url),
+            &url_params,
+        )?;
+
+        let resp = client
+            .get(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Vec<Value>>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Fetches all HSM partitions with their members using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doPartitionsGet/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_partitions(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+    ) -> Result<Vec<Value>, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let resp = client
+            .get(format!("{}/smd/hsm/v2/partitions", shasta_base_url))
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Vec<Value>>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Fetches an HSM partition with its members using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doPartitionGet/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_partition(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        partition_name: &str,
     ) -> Result<Value, Box<dyn Error>> {
-        let client;
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
-        let client_builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+        let resp = client
+            .get(format!(
+                "{}/smd/hsm/v2/partitions/{}",
+                shasta_base_url, partition_name
+            ))
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
 
-        // Build client
-        if std::env::var("SOCKS5").is_ok() {
-            // socks5 proxy
-            log::debug!("SOCKS5 enabled");
-            let socks5proxy = reqwest::Proxy::all(std::env::var("SOCKS5").unwrap())?;
+        if resp.status().is_success() {
+            Ok(resp.json::<Value>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Fetches the ethernet interfaces (MAC and IP addresses) of all components of a type (eg Node)
+    /// using HSM v2 ref --> https://apidocs.svc.cscs.ch/iaas/hardware-state-manager/operation/doCompEthInterfacesGetV2/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_ethernet_interfaces(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        component_type: &str,
+    ) -> Result<Vec<Value>, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let api_url = Url::parse_with_params(
+            &format!(
+                "{}/smd/hsm/v2/Inventory/EthernetInterfaces",
+                shasta_base_url
+            ),
+            &[("Type", component_type)],
+        )?;
 
-            // rest client to authenticate
-            client = client_builder.proxy(socks5proxy).build()?;
+        let resp = client
+            .get(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Vec<Value>>().await?)
         } else {
-            client = client_builder.build()?;
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
         }
+    }
+
+    pub async fn get_hw_inventory(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        xname: &str,
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
 
         let api_url = format!(
             "{}/smd/hsm/v2/Inventory/Hardware/Query/{}",
@@ -265,7 +622,7 @@ pub mod http_client {
         let resp = client
             .get(api_url)
             .header("Authorization", format!("Bearer {}", shasta_token))
-            .send()
+            .send_traced()
             .await?;
 
         if resp.status().is_success() {
@@ -328,10 +685,11 @@ pub mod utils {
     pub async fn get_members_ids(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
         hsm_group: &str,
     ) -> Vec<String> {
         // Take all nodes for all hsm_groups found and put them in a Vec
-        http_client::get_hsm_group(shasta_token, shasta_base_url, hsm_group)
+        http_client::get_hsm_group(shasta_token, shasta_base_url, shasta_root_cert, hsm_group)
             .await
             .unwrap()["members"]["ids"]
             .as_array()
@@ -344,11 +702,13 @@ pub mod utils {
     pub async fn get_hsm_group_from_xname(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
         xname: &String,
     ) -> Option<String> {
-        let hsm_groups_details = get_all_hsm_groups(shasta_token, shasta_base_url)
-            .await
-            .unwrap();
+        let hsm_groups_details =
+            get_all_hsm_groups(shasta_token, shasta_base_url, shasta_root_cert)
+                .await
+                .unwrap();
 
         for hsm_group_details in hsm_groups_details.iter() {
             if hsm_group_details["members"]["ids"]
@@ -376,22 +736,21 @@ pub mod utils {
     pub async fn validate_config_hsm_group_and_hsm_group_accessed(
         shasta_token: &str,
         shasta_base_url: &str,
+        shasta_root_cert: &[u8],
         hsm_group: Option<&String>,
         session_name: Option<&String>,
         cfs_sessions: &[Value],
-    ) {
+    ) -> Result<(), String> {
         if let Some(hsm_group_name) = hsm_group {
-            let hsm_group_details = crate::shasta::hsm::http_client::get_hsm_groups(
+            let hsm_group_details = http_client::get_hsm_groups(
                 shasta_token,
                 shasta_base_url,
+                shasta_root_cert,
                 hsm_group,
             )
             .await
             .unwrap();
-            let hsm_group_members =
-                crate::shasta::hsm::utils::get_members_from_hsm_groups_serde_value(
-                    &hsm_group_details,
-                );
+            let hsm_group_members = get_members_from_hsm_groups_serde_value(&hsm_group_details);
             let cfs_session_hsm_groups: Vec<String> = cfs_sessions.last().unwrap()["target"]
                 ["groups"]
                 .as_array()
@@ -402,7 +761,7 @@ pub mod utils {
             let cfs_session_members: Vec<String> = cfs_sessions.last().unwrap()["ansible"]["limit"]
                 .as_str()
                 .unwrap_or_default()
-                .split(",")
+                .split(',')
                 .map(|xname| xname.to_string())
                 .collect();
             if !cfs_session_hsm_groups.contains(hsm_group_name)
@@ -410,25 +769,15 @@ pub mod utils {
                     .iter()
                     .all(|cfs_session_member| hsm_group_members.contains(cfs_session_member))
             {
-                println!(
+                return Err(format!(
                     "CFS session {} does not apply to HSM group {}",
                     session_name.unwrap(),
                     hsm_group_name
-                );
-                std::process::exit(1);
+                ));
             }
-            /* if !cfs_session_members
-                .iter()
-                .all(|cfs_session_member| hsm_group_members.contains(cfs_session_member))
-            {
-                println!(
-                    "CFS session {} does not apply to HSM group {}",
-                    session_name.unwrap(),
-                    hsm_group_name
-                );
-                std::process::exit(1);
-            } */
         }
+
+        Ok(())
     }
 
     pub fn get_list_memory_capacity_from_hw_inventory_value(
@@ -454,7 +803,7 @@ pub mod utils {
         pattern_hw_inv: Vec<String>,
     ) -> Vec<(String, u8)> {
         let sol = vec![];
-        let processor = get_list_processor_model_from_hw_inventory_value(&node_hw_inventory_value)
+        let processor = mesa::hsm::hw_inventory::shasta::utils::get_list_processor_model_from_hw_inventory_value(node_hw_inventory_value)
             .unwrap_or_default();
 
         let mut processor_map = HashMap::new();
@@ -468,10 +817,10 @@ pub mod utils {
             }
         }
 
-        println!("Count for processors: {:?}", processor_map);
+        log::debug!("Count for processors: {:?}", processor_map);
 
         let accelerator =
-            get_list_accelerator_model_from_hw_inventory_value(&node_hw_inventory_value)
+            mesa::hsm::hw_inventory::shasta::utils::get_list_accelerator_model_from_hw_inventory_value(node_hw_inventory_value)
                 .unwrap_or_default();
         let mut accelerator_map = HashMap::new();
         for mut value in accelerator {
@@ -484,7 +833,7 @@ pub mod utils {
             }
         }
 
-        println!("Count for accelerators: {:?}", accelerator_map);
+        log::debug!("Count for accelerators: {:?}", accelerator_map);
 
         sol
     }
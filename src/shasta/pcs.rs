@@ -0,0 +1,116 @@
+pub mod http_client {
+
+    use std::error::Error;
+
+    use serde_json::Value;
+
+    use crate::shasta::SendTraced;
+
+    /// Get power state of nodes using --> https://apidocs.svc.cscs.ch/iaas/power-control/operation/post_power_status/
+    /// POST is used instead of GET since the list of xnames may be too long for a query string
+    pub async fn post_power_status(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        xname_vec: &[String],
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let api_url = format!("{}/power-control/v1/power-status", shasta_base_url);
+
+        let resp = client
+            .post(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .json(&serde_json::json!({ "xname": xname_vec }))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Value>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Create a power transition (eg 'soft-off') using --> https://apidocs.svc.cscs.ch/iaas/power-control/operation/post_transitions/
+    pub async fn post_transition(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        operation: &str,
+        xname_vec: &[String],
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let api_url = format!("{}/power-control/v1/transitions", shasta_base_url);
+
+        let location_vec: Vec<Value> = xname_vec
+            .iter()
+            .map(|xname| serde_json::json!({ "xname": xname }))
+            .collect();
+
+        let resp = client
+            .post(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .json(&serde_json::json!({ "operation": operation, "location": location_vec }))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Value>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+
+    /// Get a power transition using --> https://apidocs.svc.cscs.ch/iaas/power-control/operation/get_transitions_transitionID/
+    pub async fn get_transition(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        transition_id: &str,
+    ) -> Result<Value, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let api_url = format!(
+            "{}/power-control/v1/transitions/{}",
+            shasta_base_url, transition_id
+        );
+
+        let resp = client
+            .get(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Value>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+}
+
+pub mod utils {
+
+    use std::collections::HashMap;
+
+    use serde_json::Value;
+
+    /// xname -> power state ('on', 'off' or 'undefined') from a PCS power-status response
+    pub fn get_power_state_hashmap_from_power_status_value(
+        power_status_value: &Value,
+    ) -> HashMap<String, String> {
+        power_status_value["status"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .filter_map(|node_power_status| {
+                Some((
+                    node_power_status["xname"].as_str()?.to_string(),
+                    node_power_status["powerState"].as_str()?.to_string(),
+                ))
+            })
+            .collect()
+    }
+}
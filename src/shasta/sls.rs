@@ -0,0 +1,38 @@
+pub mod http_client {
+
+    use std::error::Error;
+
+    use reqwest::Url;
+    use serde_json::Value;
+
+    use crate::shasta::SendTraced;
+
+    /// Fetches the hardware of a type (eg comptype_node) declared in SLS, with their extra
+    /// properties (eg NID and aliases of nodes) using --> https://apidocs.svc.cscs.ch/iaas/sls/operation/get_search_hardware/
+    #[tracing::instrument(skip(shasta_token, shasta_root_cert))]
+    pub async fn get_search_hardware(
+        shasta_token: &str,
+        shasta_base_url: &str,
+        shasta_root_cert: &[u8],
+        hardware_type: &str,
+    ) -> Result<Vec<Value>, Box<dyn Error>> {
+        let client = crate::shasta::build_client(shasta_root_cert)?;
+
+        let api_url = Url::parse_with_params(
+            &format!("{}/sls/v1/search/hardware", shasta_base_url),
+            &[("type", hardware_type)],
+        )?;
+
+        let resp = client
+            .get(api_url)
+            .header("Authorization", format!("Bearer {}", shasta_token))
+            .send_traced()
+            .await?;
+
+        if resp.status().is_success() {
+            Ok(resp.json::<Vec<Value>>().await?)
+        } else {
+            Err(format!("{} {}", resp.status(), resp.text().await?).into())
+        }
+    }
+}
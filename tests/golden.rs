@@ -0,0 +1,626 @@
+//! End-to-end tests running the `clstr` binary against a mock CSM API which serves the recorded
+//! responses in tests/fixtures/csm, no credentials nor CSM system needed. Stdout of each command
+//! is compared with tests/golden/<test name>.txt, run with `CLSTR_UPDATE_GOLDEN=1 cargo test
+//! --test golden` to rewrite the golden files after an intended output change.
+//!
+//! The recorded inventory is heterogeneous on purpose:
+//! - x1000c1s0b0n[0-1] (zinal) have 4 A100 SXM4 and x1002c0s0b0n0 (nodes_free) 4 A100 PCIe, the
+//!   same hw component with different model names
+//! - x1000c1s1b0n0 (zinal) has 4 Instinct MI250X
+//! - x1001c0s1b1n1 (nodes_free) has DIMMs of 16 GiB and 32 GiB
+//! - x1001c0s2b0n0 (nodes_free) has 2 empty DIMM slots and is Off
+//! - x1003c0s0b0n0 (nodes_free) has no hw inventory in HSM
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use wiremock::{
+    matchers::{method, path, path_regex},
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+};
+
+const SITE_NAME: &str = "alps";
+
+fn get_fixture_path(file_name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(file_name)
+}
+
+fn read_fixture(file_name: &str) -> Value {
+    let fixture_path = get_fixture_path(file_name);
+
+    serde_json::from_str(
+        &std::fs::read_to_string(&fixture_path)
+            .unwrap_or_else(|error| panic!("Could not read {}: {}", fixture_path.display(), error)),
+    )
+    .unwrap()
+}
+
+/// HSM groups, filtered by label if the request has a 'group' query parameter like HSM does
+struct HsmGroupResponder {
+    hsm_group_vec: Vec<Value>,
+}
+
+impl Respond for HsmGroupResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let group_vec: Vec<String> = request
+            .url
+            .query_pairs()
+            .filter(|(key, _)| key == "group")
+            .map(|(_, value)| value.to_string())
+            .collect();
+
+        let hsm_group_vec: Vec<&Value> = self
+            .hsm_group_vec
+            .iter()
+            .filter(|hsm_group| {
+                group_vec.is_empty()
+                    || group_vec
+                        .iter()
+                        .any(|group| hsm_group["label"].as_str() == Some(group))
+            })
+            .collect();
+
+        ResponseTemplate::new(200).set_body_json(hsm_group_vec)
+    }
+}
+
+/// Hw inventory query of a node or of all nodes in a chassis (NestNodesOnly format), 404 if no
+/// node is found like HSM does
+struct HwInventoryResponder {
+    node_vec: Vec<Value>,
+}
+
+impl Respond for HwInventoryResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let xname = request.url.path().rsplit('/').next().unwrap_or_default();
+
+        let node_vec: Vec<&Value> = self
+            .node_vec
+            .iter()
+            .filter(|node| {
+                let node_xname = node["ID"].as_str().unwrap_or_default();
+
+                node_xname == xname
+                    || node_xname
+                        .strip_prefix(xname)
+                        .is_some_and(|suffix| suffix.starts_with('s'))
+            })
+            .collect();
+
+        if node_vec.is_empty() {
+            ResponseTemplate::new(404).set_body_json(json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "detail": format!("no such xname: {}", xname),
+                "status": 404
+            }))
+        } else {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "XName": xname,
+                "Format": "NestNodesOnly",
+                "Nodes": node_vec
+            }))
+        }
+    }
+}
+
+/// CSM API serving the recorded responses, any other endpoint answers 404
+async fn start_mock_csm() -> MockServer {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/smd/hsm/v2/groups"))
+        .respond_with(HsmGroupResponder {
+            hsm_group_vec: read_fixture("csm/hsm_groups.json")
+                .as_array()
+                .unwrap()
+                .clone(),
+        })
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(
+            "^/smd/hsm/v2/Inventory/Hardware/Query/[a-z0-9]+$",
+        ))
+        .respond_with(HwInventoryResponder {
+            node_vec: read_fixture("csm/hw_inventory.json")["Nodes"]
+                .as_array()
+                .unwrap()
+                .clone(),
+        })
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/smd/hsm/v2/State/Components"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(read_fixture("csm/components.json")))
+        .mount(&mock_server)
+        .await;
+
+    mock_server
+}
+
+/// Runs clstr against the mock CSM API with its own configuration, data and lock directories.
+/// Returns stdout, the mock CSM API url is replaced by '<base_url>'
+async fn run_clstr(arg_vec: &[&str]) -> String {
+    let mock_server = start_mock_csm().await;
+
+    run_clstr_against(&mock_server.uri(), arg_vec).await
+}
+
+/// clstr command against the CSM API at `base_url` with its own configuration (`settings` are
+/// added on top of the site), data and lock directories under `home_dir`
+fn get_clstr_command(home_dir: &Path, base_url: &str, settings: &str) -> tokio::process::Command {
+    let config_file_path = home_dir.join("clstr.toml");
+
+    std::fs::write(
+        &config_file_path,
+        format!(
+            "site = \"{site}\"\nlog = \"off\"\nlock_dir = \"{lock_dir}\"\n{settings}\n[sites.{site}]\nshasta_base_url = \"{base_url}\"\nkeycloak_base_url = \"{base_url}/keycloak\"\ncsm_version = \"1.5\"\n",
+            site = SITE_NAME,
+            lock_dir = home_dir.join("locks").display(),
+            settings = settings,
+            base_url = base_url
+        ),
+    )
+    .unwrap();
+
+    let mut command = tokio::process::Command::new(env!("CARGO_BIN_EXE_clstr"));
+
+    command
+        .arg("--config")
+        .arg(&config_file_path)
+        .arg("--cacert")
+        .arg(get_fixture_path("root_cert.pem"))
+        .args(["--token", "test-token", "--color", "never", "--no-pager"])
+        .env_clear()
+        .env("PATH", std::env::var("PATH").unwrap_or_default())
+        .env("HOME", home_dir)
+        .env("XDG_CONFIG_HOME", home_dir.join("config"))
+        .env("XDG_DATA_HOME", home_dir.join("data"));
+
+    command
+}
+
+/// Runs clstr against the CSM API at `base_url`, see `run_clstr`
+async fn run_clstr_against(base_url: &str, arg_vec: &[&str]) -> String {
+    let home_dir = tempfile::tempdir().unwrap();
+
+    let output = get_clstr_command(home_dir.path(), base_url, "")
+        .args(arg_vec)
+        .output()
+        .await
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout).replace(base_url, "<base_url>");
+
+    assert!(
+        output.status.success(),
+        "clstr {} failed with {}\nstdout:\n{}\nstderr:\n{}",
+        arg_vec.join(" "),
+        output.status,
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    stdout
+}
+
+/// Compares the output with the golden file, or rewrites the golden file if CLSTR_UPDATE_GOLDEN
+/// is set
+fn assert_golden(golden_name: &str, output: &str) {
+    let golden_file_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.txt", golden_name));
+
+    if std::env::var_os("CLSTR_UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden_file_path, output).unwrap();
+        return;
+    }
+
+    let golden = std::fs::read_to_string(&golden_file_path).unwrap_or_else(|error| {
+        panic!(
+            "Could not read {} ({}), run with CLSTR_UPDATE_GOLDEN=1 to create it",
+            golden_file_path.display(),
+            error
+        )
+    });
+
+    assert_eq!(
+        output,
+        golden,
+        "output differs from {}, run with CLSTR_UPDATE_GOLDEN=1 if the change is intended",
+        golden_file_path.display()
+    );
+}
+
+#[tokio::test]
+async fn test_get_nodes_artifacts_gpu_node() {
+    assert_golden(
+        "get_nodes_artifacts_gpu_node",
+        &run_clstr(&["get", "nodes", "artifacts", "x1000c1s0b0n0", "zinal"]).await,
+    );
+}
+
+#[tokio::test]
+async fn test_get_nodes_artifacts_empty_dimm_slots() {
+    assert_golden(
+        "get_nodes_artifacts_empty_dimm_slots",
+        &run_clstr(&[
+            "get",
+            "nodes",
+            "artifacts",
+            "x1001c0s2b0n0",
+            "nodes_free",
+            "--type",
+            "Memory",
+        ])
+        .await,
+    );
+}
+
+#[tokio::test]
+async fn test_get_nodes_artifacts_json() {
+    assert_golden(
+        "get_nodes_artifacts_json",
+        &run_clstr(&[
+            "get",
+            "nodes",
+            "artifacts",
+            "x1001c0s1b1n1",
+            "nodes_free",
+            "--output",
+            "json",
+        ])
+        .await,
+    );
+}
+
+#[tokio::test]
+async fn test_get_hsm_pattern() {
+    assert_golden(
+        "get_hsm_pattern",
+        &run_clstr(&["get", "hsm-groups", "pattern", "zinal"]).await,
+    );
+}
+
+#[tokio::test]
+async fn test_get_hsm_pattern_all_hsm_groups() {
+    assert_golden(
+        "get_hsm_pattern_all_hsm_groups",
+        &run_clstr(&["get", "hsm-groups", "pattern"]).await,
+    );
+}
+
+#[tokio::test]
+async fn test_apply_hsm_group_dry_run() {
+    assert_golden(
+        "apply_hsm_group_dry_run",
+        &run_clstr(&[
+            "apply",
+            "hsm-group",
+            "--pattern",
+            "zinal:a100:12:epyc:4",
+            "--solver",
+            "exact",
+            "--dry-run",
+        ])
+        .await,
+    );
+}
+
+#[tokio::test]
+async fn test_apply_hsm_group_memory_dry_run() {
+    assert_golden(
+        "apply_hsm_group_memory_dry_run",
+        &run_clstr(&[
+            "apply",
+            "hsm-group",
+            "--pattern",
+            "zinal:instinct:4:memory=1tb",
+            "--objective",
+            "min-moves",
+            "--dry-run",
+        ])
+        .await,
+    );
+}
+
+#[tokio::test]
+async fn test_apply_hsm_group_check_no_changes_json() {
+    assert_golden(
+        "apply_hsm_group_check_no_changes_json",
+        &run_clstr(&[
+            "apply",
+            "hsm-group",
+            "--pattern",
+            "zinal:instinct:4:memory>=1tb",
+            "--check",
+            "--output",
+            "json",
+        ])
+        .await,
+    );
+}
+
+/// zinal has 8 a100 instead of the 12 declared, it drifted from the pattern. `--check` exits with 2 instead of
+/// failing, so `run_clstr` can't be used
+#[tokio::test]
+async fn test_apply_hsm_group_check_changes_json() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+
+    let output = get_clstr_command(home_dir.path(), &mock_server.uri(), "")
+        .args([
+            "apply",
+            "hsm-group",
+            "--pattern",
+            "zinal:a100:12:epyc:4",
+            "--solver",
+            "exact",
+            "--check",
+            "--output",
+            "json",
+        ])
+        .output()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        serde_json::from_str::<Value>(&stdout).unwrap()["changed"],
+        json!(true)
+    );
+    assert_golden("apply_hsm_group_check_changes_json", &stdout);
+}
+
+#[tokio::test]
+async fn test_record_replay_round_trip() {
+    let session_dir = tempfile::tempdir().unwrap();
+    let session_file_path = session_dir.path().join("session.ndjson");
+    let session_file_path = session_file_path.to_str().unwrap();
+
+    let arg_vec = ["get", "nodes", "artifacts", "x1000c1s0b0n0", "zinal"];
+
+    let recorded_output =
+        run_clstr(&[&arg_vec[..], &["--record", session_file_path]].concat()).await;
+
+    assert!(!std::fs::read_to_string(session_file_path)
+        .unwrap()
+        .trim()
+        .is_empty());
+
+    // Nothing listens on the replay base url, every request must be answered by the session
+    let replayed_output = run_clstr_against(
+        "http://127.0.0.1:9",
+        &[&arg_vec[..], &["--replay", session_file_path]].concat(),
+    )
+    .await;
+
+    assert_eq!(recorded_output, replayed_output);
+}
+
+/// `clstr serve` on a free loopback port against the mock CSM API with zinal and nodes_free as
+/// the only HSM groups available. Returns the url of the REST API once it accepts connections
+/// and the process, killed when dropped
+async fn start_clstr_serve(
+    mock_server: &MockServer,
+    home_dir: &Path,
+) -> (String, tokio::process::Child) {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let child = get_clstr_command(
+        home_dir,
+        &mock_server.uri(),
+        "hsm_available = [\"zinal\", \"nodes_free\"]\n",
+    )
+    .args(["serve", "--listen", &format!("127.0.0.1:{}", port)])
+    .kill_on_drop(true)
+    .spawn()
+    .unwrap();
+
+    for _ in 0..100 {
+        if tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            return (format!("http://127.0.0.1:{}", port), child);
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    panic!("clstr serve not listening on port {}", port);
+}
+
+#[tokio::test]
+async fn test_serve_missing_token() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+    let (serve_url, _child) = start_clstr_serve(&mock_server, home_dir.path()).await;
+
+    let resp = reqwest::Client::new()
+        .get(format!("{}/api/v1/hsm-groups", serve_url))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), reqwest::StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_serve_plan_hsm_group_not_available() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+    let (serve_url, _child) = start_clstr_serve(&mock_server, home_dir.path()).await;
+
+    let resp = reqwest::Client::new()
+        .post(format!("{}/api/v1/plans", serve_url))
+        .bearer_auth("test-token")
+        .json(&json!({ "pattern": "eiger:a100:1" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_serve_apply_conflict() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+    let (serve_url, _child) = start_clstr_serve(&mock_server, home_dir.path()).await;
+
+    // x1000c1s0b0n0 is a member of zinal, not of nodes_free
+    let resp = reqwest::Client::new()
+        .post(format!("{}/api/v1/plans/apply", serve_url))
+        .bearer_auth("test-token")
+        .json(&json!({
+            "node_moves": [{
+                "xname": "x1000c1s0b0n0",
+                "from_hsm_group": "nodes_free",
+                "to_hsm_group": "zinal"
+            }]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), reqwest::StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_serve_refuses_plain_http_on_all_interfaces() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+
+    let output = get_clstr_command(home_dir.path(), &mock_server.uri(), "")
+        .args(["serve", "--listen", ":0"])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(!output.status.success());
+}
+
+#[tokio::test]
+async fn test_token_and_base_url_without_site() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+
+    let arg_vec = ["get", "nodes", "artifacts", "x1000c1s0b0n0", "zinal"];
+
+    // Site not in configuration file, --token (see get_clstr_command) and --base-url are enough
+    let output = get_clstr_command(home_dir.path(), &mock_server.uri(), "")
+        .args(["--site", "missing", "--base-url", &mock_server.uri()])
+        .args(arg_vec)
+        .output()
+        .await
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).replace(&mock_server.uri(), "<base_url>"),
+        run_clstr(&arg_vec).await
+    );
+}
+
+#[tokio::test]
+async fn test_report_capacity_as_of() {
+    let mock_server = start_mock_csm().await;
+    let home_dir = tempfile::tempdir().unwrap();
+
+    // 'nodes_free' was emptied before being refilled on 2024-05-10
+    let history_dir_path = home_dir.path().join("data").join("clstr");
+    std::fs::create_dir_all(&history_dir_path).unwrap();
+    std::fs::write(
+        history_dir_path.join("history.ndjson"),
+        format!(
+            "{}\n",
+            json!({
+                "id": 1,
+                "timestamp": "2024-05-10T10:00:00+00:00",
+                "user": "operator",
+                "command": "apply hsm-group -p nodes_free:a100:4",
+                "node_moves": [],
+                "before": [{ "label": "nodes_free", "members": [] }],
+                "after": [],
+                "error": null,
+            })
+        ),
+    )
+    .unwrap();
+
+    let arg_vec = ["report", "capacity", "-p", "a100:4", "--pool", "nodes_free"];
+
+    let output = get_clstr_command(home_dir.path(), &mock_server.uri(), "")
+        .args(arg_vec)
+        .args(["--as-of", "2024-05-01"])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Pool 'nodes_free' could satisfy 0 instances of 'a100:4'"));
+    assert_ne!(stdout, run_clstr(&arg_vec).await);
+}
+
+#[tokio::test]
+async fn test_config_set_refuses_manta_configuration() {
+    let home_dir = tempfile::tempdir().unwrap();
+
+    // Only manta has a configuration file
+    let manta_config_dir_path = home_dir.path().join("config").join("manta");
+    std::fs::create_dir_all(&manta_config_dir_path).unwrap();
+    std::fs::write(manta_config_dir_path.join("config.toml"), "log = \"off\"\n").unwrap();
+
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_clstr"))
+        .args(["config", "set", "log", "debug"])
+        .env_clear()
+        .env("PATH", std::env::var("PATH").unwrap_or_default())
+        .env("HOME", home_dir.path())
+        .env("XDG_CONFIG_HOME", home_dir.path().join("config"))
+        .env("XDG_DATA_HOME", home_dir.path().join("data"))
+        .output()
+        .await
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("clstr config migrate"));
+    assert_eq!(
+        std::fs::read_to_string(manta_config_dir_path.join("config.toml")).unwrap(),
+        "log = \"off\"\n"
+    );
+    assert!(!home_dir
+        .path()
+        .join("config")
+        .join("clstr")
+        .join("config.toml")
+        .exists());
+}